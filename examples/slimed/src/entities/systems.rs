@@ -4,7 +4,7 @@ use libretrogd::math::*;
 
 use crate::{Core, TILE_HEIGHT, TILE_WIDTH};
 use crate::entities::*;
-use crate::tilemap::*;
+use crate::tilemap::TileMap;
 
 pub fn remove_entity(entities: &mut Entities, entity: EntityId) {
     remove_entity_attachment(entities, entity);
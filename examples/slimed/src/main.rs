@@ -16,7 +16,7 @@ use libretrogd::system::*;
 use crate::entities::*;
 use crate::states::*;
 use crate::support::*;
-use crate::tilemap::*;
+use crate::tilemap::TileMap;
 
 mod states;
 mod entities;
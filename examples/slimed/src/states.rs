@@ -8,6 +8,7 @@ use libretrogd::math::Vector2;
 use libretrogd::states::*;
 
 use crate::entities::*;
+use crate::entities::Camera;
 use crate::Game;
 use crate::support::*;
 
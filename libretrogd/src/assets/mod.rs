@@ -0,0 +1,528 @@
+use std::any::TypeId;
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+use thiserror::Error;
+
+use crate::audio::*;
+use crate::graphics::*;
+use crate::utils::AsAny;
+
+pub use self::pak::*;
+pub use self::source::*;
+pub use self::vfs::*;
+
+pub mod pak;
+pub mod source;
+pub mod vfs;
+
+#[derive(Error, Debug)]
+pub enum AssetError {
+    #[error("Failed to load asset \"{path}\"")]
+    LoadFailed {
+        path: PathBuf,
+        #[source]
+        source: Box<dyn std::error::Error>,
+    },
+}
+
+/// A type that can be loaded from a file and managed by [`Assets`].
+pub trait Asset: Sized + 'static {
+    type Error: std::error::Error + 'static;
+
+    /// Loads and returns the asset from the contents of the given file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: the contents of the file
+    /// * `name`: the name of the file, which can be used to determine its format
+    fn load_asset(data: &[u8], name: &Path) -> Result<Self, Self::Error>;
+}
+
+/// Bitmaps are loaded with [`Bitmap::load_bytes`], with the palette loaded along with the bitmap
+/// set as the bitmap's own palette.
+impl Asset for Bitmap {
+    type Error = BitmapError;
+
+    fn load_asset(data: &[u8], name: &Path) -> Result<Self, Self::Error> {
+        let (mut bitmap, palette) = Bitmap::load_bytes(data, name)?;
+        bitmap.set_palette(Some(palette));
+        Ok(bitmap)
+    }
+}
+
+/// Palettes are loaded with [`Palette::load_bytes`], detecting the palette format automatically.
+impl Asset for Palette {
+    type Error = PaletteError;
+
+    fn load_asset(data: &[u8], name: &Path) -> Result<Self, Self::Error> {
+        Palette::load_bytes(data, name)
+    }
+}
+
+/// Fonts are loaded as BDF fonts if they have a ".bdf" file extension, or as this crate's own
+/// font format otherwise.
+impl Asset for BitmaskFont {
+    type Error = FontError;
+
+    fn load_asset(data: &[u8], name: &Path) -> Result<Self, Self::Error> {
+        let extension = name
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let mut reader = Cursor::new(data);
+        match extension.as_deref() {
+            Some("bdf") => BitmaskFont::load_bdf_from_bytes(&mut reader),
+            _ => BitmaskFont::load_from_bytes(&mut reader),
+        }
+    }
+}
+
+/// Sounds are loaded from WAV files.
+impl Asset for AudioBuffer {
+    type Error = WavError;
+
+    fn load_asset(data: &[u8], _name: &Path) -> Result<Self, Self::Error> {
+        AudioBuffer::load_wav_bytes(&mut Cursor::new(data))
+    }
+}
+
+#[derive(Debug)]
+struct AssetSlot<T> {
+    path: PathBuf,
+    data: RefCell<T>,
+    version: Cell<u32>,
+}
+
+/// A reference counted handle to an asset loaded by [`Assets`]. Cloning a handle is cheap, and
+/// all clones refer to the same asset. If the asset is reloaded (see [`Assets::reload_changed`]),
+/// the reloaded data is swapped in behind all of the existing handles to it.
+pub struct Handle<T> {
+    slot: Rc<AssetSlot<T>>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handle")
+            .field("path", &self.slot.path)
+            .field("version", &self.slot.version.get())
+            .field("ref_count", &self.ref_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Handle<T> {
+    /// Returns a reference to the asset. The asset cannot be reloaded while this reference is
+    /// held.
+    #[inline]
+    pub fn get(&self) -> Ref<'_, T> {
+        self.slot.data.borrow()
+    }
+
+    /// Returns the path that the asset was loaded from.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.slot.path
+    }
+
+    /// Returns the number of times the asset has been reloaded since it was first loaded. This
+    /// can be checked to find out if anything derived from the asset needs to be updated.
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.slot.version.get()
+    }
+
+    /// Returns the number of handles to the asset, including the one held by [`Assets`] if the
+    /// asset has not been released from it.
+    #[inline]
+    pub fn ref_count(&self) -> usize {
+        Rc::strong_count(&self.slot)
+    }
+
+    /// Returns true if both handles refer to the same asset.
+    #[inline]
+    pub fn ptr_eq(&self, other: &Handle<T>) -> bool {
+        Rc::ptr_eq(&self.slot, &other.slot)
+    }
+}
+
+fn load<T: Asset>(source: &dyn AssetSource, path: &Path) -> Result<T, AssetError> {
+    let load_failed = |error: Box<dyn std::error::Error>| AssetError::LoadFailed {
+        path: path.to_path_buf(),
+        source: error,
+    };
+    let data = source
+        .read(path)
+        .map_err(|error| load_failed(Box::new(error)))?;
+    T::load_asset(&data, path).map_err(|error| load_failed(Box::new(error)))
+}
+
+struct AssetEntry<T> {
+    slot: Rc<AssetSlot<T>>,
+    modified: Option<SystemTime>,
+}
+
+trait GenericAssetStore: AsAny {
+    fn len(&self) -> usize;
+
+    fn release_unused(&mut self) -> usize;
+
+    fn reload_changed(&mut self, source: &dyn AssetSource) -> Vec<Result<PathBuf, AssetError>>;
+}
+
+type AssetStore<T> = HashMap<PathBuf, AssetEntry<T>>;
+
+impl<T: Asset> GenericAssetStore for AssetStore<T> {
+    #[inline]
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn release_unused(&mut self) -> usize {
+        let before_len = HashMap::len(self);
+        self.retain(|_, entry| Rc::strong_count(&entry.slot) > 1);
+        before_len - HashMap::len(self)
+    }
+
+    fn reload_changed(&mut self, source: &dyn AssetSource) -> Vec<Result<PathBuf, AssetError>> {
+        let mut results = Vec::new();
+        for (path, entry) in self.iter_mut() {
+            let modified = source.modified(path);
+            if modified.is_none() || modified == entry.modified {
+                continue;
+            }
+            // remember the new modified time even if reloading fails, so that a bad file is
+            // only retried once it has been changed again
+            entry.modified = modified;
+            match load::<T>(source, path) {
+                Ok(asset) => {
+                    *entry.slot.data.borrow_mut() = asset;
+                    entry
+                        .slot
+                        .version
+                        .set(entry.slot.version.get().wrapping_add(1));
+                    results.push(Ok(path.clone()));
+                }
+                Err(error) => results.push(Err(error)),
+            }
+        }
+        results
+    }
+}
+
+/// A registry of assets (bitmaps, palettes, fonts, sounds, or any other type implementing
+/// [`Asset`]) loaded from files and handed out as [`Handle`]s. Each file is only loaded once per
+/// asset type, with any further loads of the same path returning another handle to the already
+/// loaded asset.
+///
+/// During development, changed files can be automatically reloaded by enabling hot reloading and
+/// calling [`Assets::update`] once per frame, which lets you see changes to your game's assets
+/// without restarting it.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use std::time::Duration;
+///
+/// use libretrogd::assets::*;
+/// use libretrogd::graphics::*;
+///
+/// let mut assets = Assets::new();
+/// assets.enable_hot_reload(Duration::from_millis(500));
+///
+/// let player = assets.load::<Bitmap>(Path::new("assets/player.pcx")).unwrap();
+/// let same_player = assets.load::<Bitmap>(Path::new("assets/player.pcx")).unwrap();
+/// assert!(player.ptr_eq(&same_player));
+///
+/// loop {
+///     assets.update();
+///     let width = player.get().width();
+///     // ...
+/// }
+/// ```
+pub struct Assets {
+    source: Box<dyn AssetSource>,
+    stores: HashMap<TypeId, Box<dyn GenericAssetStore>>,
+    hot_reload_interval: Option<Duration>,
+    last_checked: Instant,
+}
+
+impl std::fmt::Debug for Assets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Assets")
+            .field("len()", &self.len())
+            .field("hot_reload_interval", &self.hot_reload_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Assets::new()
+    }
+}
+
+impl Assets {
+    /// Creates and returns a new, empty, asset registry that loads assets from files on disk,
+    /// with hot reloading disabled.
+    pub fn new() -> Self {
+        Self::with_source(DirectorySource::new(Path::new("")))
+    }
+
+    /// Creates and returns a new, empty, asset registry that loads assets from the given source
+    /// (such as a [`PakArchive`] or [`Vfs`]), with hot reloading disabled.
+    pub fn with_source(source: impl AssetSource + 'static) -> Self {
+        Assets {
+            source: Box::new(source),
+            stores: HashMap::new(),
+            hot_reload_interval: None,
+            last_checked: Instant::now(),
+        }
+    }
+
+    /// Returns the source that assets are loaded from.
+    #[inline]
+    pub fn source(&self) -> &dyn AssetSource {
+        self.source.as_ref()
+    }
+
+    fn get_store<T: Asset>(&self) -> Option<&AssetStore<T>> {
+        self.stores
+            .get(&TypeId::of::<T>())
+            .map(|store| store.as_ref().as_any().downcast_ref().unwrap())
+    }
+
+    fn get_store_mut<T: Asset>(&mut self) -> &mut AssetStore<T> {
+        self.stores
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(AssetStore::<T>::new()))
+            .as_mut()
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Loads an asset from the given file, returning a handle to it. If an asset of the same
+    /// type was already loaded from the same path, a handle to that asset is returned instead
+    /// of loading it again. Paths are compared exactly as given, so the same file loaded via
+    /// two different paths (e.g. relative and absolute) will be loaded twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to load the asset from
+    pub fn load<T: Asset>(&mut self, path: &Path) -> Result<Handle<T>, AssetError> {
+        if let Some(handle) = self.get(path) {
+            return Ok(handle);
+        }
+        let modified = self.source.modified(path);
+        let asset = load::<T>(self.source.as_ref(), path)?;
+        let slot = Rc::new(AssetSlot {
+            path: path.to_path_buf(),
+            data: RefCell::new(asset),
+            version: Cell::new(0),
+        });
+        self.get_store_mut::<T>().insert(
+            path.to_path_buf(),
+            AssetEntry {
+                slot: slot.clone(),
+                modified,
+            },
+        );
+        Ok(Handle { slot })
+    }
+
+    /// Returns a handle to the asset of the given type that was loaded from the given path, or
+    /// `None` if no such asset is loaded.
+    pub fn get<T: Asset>(&self, path: &Path) -> Option<Handle<T>> {
+        self.get_store::<T>()
+            .and_then(|store| store.get(path))
+            .map(|entry| Handle {
+                slot: entry.slot.clone(),
+            })
+    }
+
+    /// Returns true if an asset of the given type has been loaded from the given path.
+    pub fn is_loaded<T: Asset>(&self, path: &Path) -> bool {
+        self.get_store::<T>()
+            .is_some_and(|store| store.contains_key(path))
+    }
+
+    /// Returns the total number of assets of all types currently loaded.
+    pub fn len(&self) -> usize {
+        self.stores.values().map(|store| store.len()).sum()
+    }
+
+    /// Returns true if no assets are currently loaded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the asset of the given type that was loaded from the given path. Any handles to
+    /// it remain valid, but it will no longer be reloaded and loading the same path again will
+    /// load a new copy of it. Returns true if the asset was removed.
+    pub fn remove<T: Asset>(&mut self, path: &Path) -> bool {
+        self.get_store_mut::<T>().remove(path).is_some()
+    }
+
+    /// Removes all assets that no longer have any handles to them outside of this registry,
+    /// freeing them. Returns the number of assets that were removed.
+    pub fn release_unused(&mut self) -> usize {
+        self.stores
+            .values_mut()
+            .map(|store| store.release_unused())
+            .sum()
+    }
+
+    /// Checks the files of all of the loaded assets, reloading any that have been modified since
+    /// they were (re)loaded. Reloaded data is swapped in behind the existing handles to those
+    /// assets, which must not be borrowed when this is called. If an asset fails to reload, the
+    /// previously loaded data is kept.
+    ///
+    /// returns: the path of each asset that was reloaded, or the error if it failed to reload
+    pub fn reload_changed(&mut self) -> Vec<Result<PathBuf, AssetError>> {
+        self.last_checked = Instant::now();
+        self.stores
+            .values_mut()
+            .flat_map(|store| store.reload_changed(self.source.as_ref()))
+            .collect()
+    }
+
+    /// Enables hot reloading, checking for modified asset files each time [`Assets::update`] is
+    /// called if at least the given amount of time has passed since they were last checked.
+    pub fn enable_hot_reload(&mut self, interval: Duration) {
+        self.hot_reload_interval = Some(interval);
+    }
+
+    /// Disables hot reloading.
+    pub fn disable_hot_reload(&mut self) {
+        self.hot_reload_interval = None;
+    }
+
+    /// Returns true if hot reloading is enabled.
+    #[inline]
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.hot_reload_interval.is_some()
+    }
+
+    /// Reloads any modified assets if hot reloading is enabled and it is time to check for
+    /// modified asset files again. Meant to be called once per frame. See
+    /// [`Assets::reload_changed`].
+    pub fn update(&mut self) -> Vec<Result<PathBuf, AssetError>> {
+        match self.hot_reload_interval {
+            Some(interval) if self.last_checked.elapsed() >= interval => self.reload_changed(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::fs::File;
+
+    use claim::*;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn touch(path: &Path, modified: SystemTime) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    pub fn loading_assets() -> Result<(), AssetError> {
+        let mut assets = Assets::new();
+        let path = Path::new("./test-assets/dp2.pal");
+
+        let palette = assets.load::<Palette>(path)?;
+        let same_palette = assets.load::<Palette>(path)?;
+        assert!(palette.ptr_eq(&same_palette));
+        assert_eq!(path, palette.path());
+        assert_eq!(3, palette.ref_count());
+        assert_eq!(1, assets.len());
+        assert!(assets.is_loaded::<Palette>(path));
+        assert!(!assets.is_loaded::<Bitmap>(path));
+
+        let font = assets.load::<BitmaskFont>(Path::new("./test-assets/test.bdf"))?;
+        assert_eq!(2, assets.len());
+
+        assert_matches!(
+            assets.load::<Bitmap>(Path::new("./test-assets/missing.pcx")),
+            Err(AssetError::LoadFailed { .. })
+        );
+        assert_eq!(2, assets.len());
+
+        drop(same_palette);
+        drop(font);
+        assert_eq!(1, assets.release_unused());
+        assert!(assets.is_loaded::<Palette>(path));
+        drop(palette);
+        assert_eq!(1, assets.release_unused());
+        assert!(assets.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn reloading_changed_assets() -> Result<(), AssetError> {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("test.pal");
+        let start_time = SystemTime::now() - Duration::from_secs(60);
+
+        let mut palette = Palette::new_with_default(200, 100, 50);
+        palette.to_file(&path, PaletteFormat::Normal).unwrap();
+        touch(&path, start_time);
+
+        let mut assets = Assets::new();
+        let handle = assets.load::<Palette>(&path)?;
+        assert_eq!(0, handle.version());
+        assert!(assets.reload_changed().is_empty());
+
+        palette[0] = 0xff102030;
+        palette.to_file(&path, PaletteFormat::Normal).unwrap();
+        touch(&path, start_time + Duration::from_secs(1));
+        let reloaded = assets.reload_changed();
+        assert_eq!(1, reloaded.len());
+        assert_eq!(&path, assert_ok!(&reloaded[0]));
+        assert_eq!(1, handle.version());
+        assert_eq!(0xff102030, handle.get()[0]);
+        assert!(assets.reload_changed().is_empty());
+
+        // a bad file keeps the previously loaded data around
+        std::fs::write(&path, b"bad").unwrap();
+        touch(&path, start_time + Duration::from_secs(2));
+        let reloaded = assets.reload_changed();
+        assert_eq!(1, reloaded.len());
+        assert_err!(&reloaded[0]);
+        assert_eq!(1, handle.version());
+        assert_eq!(palette, *handle.get());
+        assert!(assets.reload_changed().is_empty());
+
+        // hot reloading only checks for changes once enabled
+        palette[0] = 0xff405060;
+        palette.to_file(&path, PaletteFormat::Normal).unwrap();
+        touch(&path, start_time + Duration::from_secs(3));
+        assert!(assets.update().is_empty());
+        assets.enable_hot_reload(Duration::ZERO);
+        assert_eq!(1, assets.update().len());
+        assert_eq!(0xff405060, handle.get()[0]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,551 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use thiserror::Error;
+
+use crate::assets::*;
+use crate::utils::io::StreamSize;
+
+const PAK_MAGIC: [u8; 4] = *b"RGPK";
+const PAK_VERSION: u8 = 1;
+const PAK_HEADER_SIZE: usize = 9;
+const PAK_ENTRY_HEADER_SIZE: usize = 19;
+const PAK_FLAG_COMPRESSED: u8 = 0x01;
+// zlib cannot compress data to much less than 1/1032 of its size, so no more than this much
+// memory is reserved up-front for decompressing an entry, whatever size its header claims
+const PAK_MAX_COMPRESSION_RATIO: usize = 1032;
+
+#[derive(Error, Debug)]
+pub enum PakError {
+    #[error("Bad or unsupported pak file: {0}")]
+    BadFile(String),
+
+    #[error("Invalid pak file entry name: {0}")]
+    InvalidName(String),
+
+    #[error("Pak file has no entry named \"{0}\"")]
+    NotFound(String),
+
+    #[error("Pak file entry \"{0}\" is corrupt")]
+    CorruptEntry(String),
+
+    #[error("Pak file I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+fn entry_name(path: &Path) -> Result<String, PakError> {
+    match normalize_path(path) {
+        Some(name) if !name.is_empty() && name.len() <= u16::MAX as usize => Ok(name),
+        _ => Err(PakError::InvalidName(path.to_string_lossy().into_owned())),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PakEntry {
+    offset: u32,
+    size: u32,
+    stored_size: u32,
+    checksum: u32,
+    compressed: bool,
+}
+
+trait PakReader: Read + Seek {}
+impl<T: Read + Seek> PakReader for T {}
+
+/// A resource archive, holding any number of files (for example, all of a game's assets) in a
+/// single pak file, optionally compressed. Only the archive's index of file names is read when it
+/// is opened, with each file's contents being read from the archive as needed.
+///
+/// Pak files are created using [`PakBuilder`]. Mainly intended to be used as the
+/// [`AssetSource`] for [`Assets`], so that a game can be shipped as an executable plus one data
+/// file, but files can also be read directly via [`PakArchive::read`] and then loaded using
+/// functions like [`crate::graphics::Bitmap::load_bytes`].
+pub struct PakArchive {
+    reader: RefCell<Box<dyn PakReader>>,
+    entries: HashMap<String, PakEntry>,
+}
+
+impl std::fmt::Debug for PakArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PakArchive")
+            .field("entries.len()", &self.entries.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PakArchive {
+    /// Opens the pak file on disk with the given path, reading its index. The file is kept open
+    /// to read files from it as needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the pak file to open
+    pub fn open(path: &Path) -> Result<PakArchive, PakError> {
+        let f = File::open(path)?;
+        Self::load_from_bytes(BufReader::new(f))
+    }
+
+    /// Opens a pak file from a reader, reading its index. The reader is kept to read files from
+    /// it as needed. The data being read is expected to be the same as if the pak file was being
+    /// opened from a file on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to read the pak file from
+    pub fn load_from_bytes<T: Read + Seek + 'static>(
+        mut reader: T,
+    ) -> Result<PakArchive, PakError> {
+        let file_size = reader.stream_size()?;
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != PAK_MAGIC {
+            return Err(PakError::BadFile(String::from(
+                "Unexpected magic bytes, probably not a pak file",
+            )));
+        }
+        let version = reader.read_u8()?;
+        if version != PAK_VERSION {
+            return Err(PakError::BadFile(format!(
+                "Unsupported version: {}",
+                version
+            )));
+        }
+
+        let num_entries = reader.read_u32::<LittleEndian>()?;
+        let mut entries = HashMap::new();
+        for _ in 0..num_entries {
+            let name_length = reader.read_u16::<LittleEndian>()?;
+            let mut name = vec![0u8; name_length as usize];
+            reader.read_exact(&mut name)?;
+            let name = match String::from_utf8(name) {
+                Ok(name) => name,
+                Err(..) => return Err(PakError::BadFile(String::from("Invalid entry name"))),
+            };
+            let flags = reader.read_u8()?;
+            let entry = PakEntry {
+                offset: reader.read_u32::<LittleEndian>()?,
+                size: reader.read_u32::<LittleEndian>()?,
+                stored_size: reader.read_u32::<LittleEndian>()?,
+                checksum: reader.read_u32::<LittleEndian>()?,
+                compressed: flags & PAK_FLAG_COMPRESSED != 0,
+            };
+            if entry.offset as u64 + entry.stored_size as u64 > file_size {
+                return Err(PakError::BadFile(format!(
+                    "Entry \"{}\" extends past the end of the file",
+                    name
+                )));
+            }
+            entries.insert(name, entry);
+        }
+
+        Ok(PakArchive {
+            reader: RefCell::new(Box::new(reader)),
+            entries,
+        })
+    }
+
+    /// Returns the number of files in the archive.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the archive has no files in it.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns true if the archive has a file with the given path in it.
+    pub fn contains(&self, path: &Path) -> bool {
+        normalize_path(path).is_some_and(|name| self.entries.contains_key(&name))
+    }
+
+    /// Returns an iterator over the paths of all of the files in the archive, in no particular
+    /// order. Paths always use "/" as the separator.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|name| name.as_str())
+    }
+
+    /// Returns the (uncompressed) size of the file with the given path in the archive.
+    pub fn size(&self, path: &Path) -> Option<u32> {
+        normalize_path(path)
+            .and_then(|name| self.entries.get(&name))
+            .map(|entry| entry.size)
+    }
+
+    /// Reads and returns the entire contents of the file with the given path in the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file within the archive
+    pub fn read(&self, path: &Path) -> Result<Vec<u8>, PakError> {
+        let name = entry_name(path)?;
+        let entry = match self.entries.get(&name) {
+            Some(entry) => entry,
+            None => return Err(PakError::NotFound(name)),
+        };
+
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(entry.offset as u64))?;
+        let mut stored = vec![0u8; entry.stored_size as usize];
+        reader.read_exact(&mut stored)?;
+        let data = if entry.compressed {
+            let capacity =
+                (entry.size as usize).min(stored.len().saturating_mul(PAK_MAX_COMPRESSION_RATIO));
+            let mut data = Vec::with_capacity(capacity);
+            // decompressing one byte more than expected is enough to tell that the entry is bad,
+            // without decompressing any further
+            if ZlibDecoder::new(stored.as_slice())
+                .take(entry.size as u64 + 1)
+                .read_to_end(&mut data)
+                .is_err()
+            {
+                return Err(PakError::CorruptEntry(name));
+            }
+            data
+        } else {
+            stored
+        };
+        if data.len() != entry.size as usize || crc32fast::hash(&data) != entry.checksum {
+            return Err(PakError::CorruptEntry(name));
+        }
+        Ok(data)
+    }
+}
+
+impl AssetSource for PakArchive {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, std::io::Error> {
+        match PakArchive::read(self, path) {
+            Ok(data) => Ok(data),
+            Err(PakError::IOError(error)) => Err(error),
+            Err(error @ PakError::NotFound(..)) => {
+                Err(std::io::Error::new(std::io::ErrorKind::NotFound, error))
+            }
+            Err(error) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+        }
+    }
+
+    #[inline]
+    fn exists(&self, path: &Path) -> bool {
+        self.contains(path)
+    }
+
+    #[inline]
+    fn modified(&self, _path: &Path) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// Builds and writes out pak files, to later be read using [`PakArchive`].
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use libretrogd::assets::*;
+///
+/// let mut builder = PakBuilder::new().with_compression(true);
+/// builder.add_directory(Path::new("assets")).unwrap();
+/// builder.add(Path::new("version.txt"), b"1.0".to_vec()).unwrap();
+/// builder.to_file(Path::new("game.pak")).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PakBuilder {
+    entries: Vec<(String, Vec<u8>)>,
+    compression: bool,
+}
+
+impl PakBuilder {
+    /// Creates and returns a new builder with no files added to it yet, and with compression
+    /// disabled.
+    pub fn new() -> Self {
+        PakBuilder {
+            entries: Vec::new(),
+            compression: false,
+        }
+    }
+
+    /// Returns this builder with compression enabled or disabled. When enabled, each file is
+    /// compressed when written, unless compressing it does not make it any smaller.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns the number of files added so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no files have been added yet.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a file with the given path and contents, replacing any file already added with the
+    /// same path. The path must be relative and cannot refer to a parent directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path the file will have within the archive
+    /// * `data`: the contents of the file
+    pub fn add(&mut self, path: &Path, data: Vec<u8>) -> Result<(), PakError> {
+        let name = entry_name(path)?;
+        match self
+            .entries
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            Some(entry) => entry.1 = data,
+            None => self.entries.push((name, data)),
+        }
+        Ok(())
+    }
+
+    /// Reads a file from disk and adds it with the given path. See [`PakBuilder::add`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path the file will have within the archive
+    /// * `file`: the path of the file on disk to be added
+    pub fn add_file(&mut self, path: &Path, file: &Path) -> Result<(), PakError> {
+        let data = std::fs::read(file)?;
+        self.add(path, data)
+    }
+
+    /// Adds all of the files in the given directory on disk, including any in its
+    /// subdirectories, with paths relative to the directory. See [`PakBuilder::add`].
+    ///
+    /// # Arguments
+    ///
+    /// * `dir`: the path of the directory on disk to add the files of
+    ///
+    /// returns: the number of files added
+    pub fn add_directory(&mut self, dir: &Path) -> Result<usize, PakError> {
+        let mut files = Vec::new();
+        let mut dirs = vec![PathBuf::new()];
+        while let Some(subdir) = dirs.pop() {
+            for dir_entry in std::fs::read_dir(dir.join(&subdir))? {
+                let dir_entry = dir_entry?;
+                let path = subdir.join(dir_entry.file_name());
+                if dir_entry.file_type()?.is_dir() {
+                    dirs.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+        for path in files.iter() {
+            self.add_file(path, &dir.join(path))?;
+        }
+        Ok(files.len())
+    }
+
+    /// Writes the pak file to a file on disk. If the file already exists, it will be
+    /// overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to write the pak file to
+    pub fn to_file(&self, path: &Path) -> Result<(), PakError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_bytes(&mut writer)
+    }
+
+    /// Writes the pak file to a writer, in the same format as if it was writing to a file on
+    /// disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the pak file to
+    pub fn to_bytes<T: Write>(&self, writer: &mut T) -> Result<(), PakError> {
+        let mut stored_entries = Vec::with_capacity(self.entries.len());
+        for (name, data) in self.entries.iter() {
+            let compressed = if self.compression {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(data)?;
+                Some(encoder.finish()?).filter(|compressed| compressed.len() < data.len())
+            } else {
+                None
+            };
+            stored_entries.push((name, data, compressed));
+        }
+
+        let index_size: usize = self
+            .entries
+            .iter()
+            .map(|(name, _)| PAK_ENTRY_HEADER_SIZE + name.len())
+            .sum();
+        let mut offset = PAK_HEADER_SIZE + index_size;
+
+        writer.write_all(&PAK_MAGIC)?;
+        writer.write_u8(PAK_VERSION)?;
+        writer.write_u32::<LittleEndian>(stored_entries.len() as u32)?;
+        for (name, data, compressed) in stored_entries.iter() {
+            let stored_size = compressed.as_ref().map_or(data.len(), |data| data.len());
+            if offset + stored_size > u32::MAX as usize {
+                return Err(PakError::BadFile(String::from(
+                    "Pak file would be larger than 4GB",
+                )));
+            }
+            writer.write_u16::<LittleEndian>(name.len() as u16)?;
+            writer.write_all(name.as_bytes())?;
+            writer.write_u8(if compressed.is_some() {
+                PAK_FLAG_COMPRESSED
+            } else {
+                0
+            })?;
+            writer.write_u32::<LittleEndian>(offset as u32)?;
+            writer.write_u32::<LittleEndian>(data.len() as u32)?;
+            writer.write_u32::<LittleEndian>(stored_size as u32)?;
+            writer.write_u32::<LittleEndian>(crc32fast::hash(data))?;
+            offset += stored_size;
+        }
+        for (_, data, compressed) in stored_entries.iter() {
+            writer.write_all(compressed.as_deref().unwrap_or(data))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+
+    use claim::*;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn build(builder: &PakBuilder) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        builder.to_bytes(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    pub fn creating_and_reading_pak_files() -> Result<(), PakError> {
+        let zeroes = vec![0u8; 1000];
+        let mut builder = PakBuilder::new().with_compression(true);
+        builder.add(Path::new("zeroes.bin"), zeroes.clone())?;
+        builder.add(Path::new("./data/small.txt"), b"hi".to_vec())?;
+        builder.add(Path::new("data/replaced.txt"), b"old".to_vec())?;
+        builder.add(Path::new("data/replaced.txt"), b"new".to_vec())?;
+        assert_matches!(
+            builder.add(Path::new("../outside.txt"), Vec::new()),
+            Err(PakError::InvalidName(..))
+        );
+        assert_eq!(3, builder.len());
+
+        let bytes = build(&builder);
+        assert!(bytes.len() < zeroes.len());
+
+        let pak = PakArchive::load_from_bytes(Cursor::new(bytes))?;
+        assert_eq!(3, pak.len());
+        let mut names: Vec<&str> = pak.names().collect();
+        names.sort();
+        assert_eq!(
+            vec!["data/replaced.txt", "data/small.txt", "zeroes.bin"],
+            names
+        );
+        assert!(pak.contains(Path::new("data/small.txt")));
+        assert!(!pak.contains(Path::new("small.txt")));
+        assert_eq!(Some(1000), pak.size(Path::new("zeroes.bin")));
+
+        assert_eq!(zeroes, pak.read(Path::new("zeroes.bin"))?);
+        assert_eq!(b"hi".to_vec(), pak.read(Path::new("data/small.txt"))?);
+        assert_eq!(b"new".to_vec(), pak.read(Path::new("data/replaced.txt"))?);
+        assert_matches!(
+            pak.read(Path::new("missing.txt")),
+            Err(PakError::NotFound(..))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn bad_pak_files() -> Result<(), PakError> {
+        let mut builder = PakBuilder::new();
+        builder.add(Path::new("a.txt"), b"hello".to_vec())?;
+        let bytes = build(&builder);
+
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert_matches!(
+            PakArchive::load_from_bytes(Cursor::new(corrupt)),
+            Err(PakError::BadFile(..))
+        );
+
+        let truncated = bytes[0..bytes.len() - 1].to_vec();
+        assert_matches!(
+            PakArchive::load_from_bytes(Cursor::new(truncated)),
+            Err(PakError::BadFile(..))
+        );
+
+        let mut corrupt = bytes.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] = b'?';
+        let pak = PakArchive::load_from_bytes(Cursor::new(corrupt))?;
+        assert_matches!(
+            pak.read(Path::new("a.txt")),
+            Err(PakError::CorruptEntry(..))
+        );
+
+        // compressed entries which decompress to more or less than their header says
+        let mut builder = PakBuilder::new().with_compression(true);
+        builder.add(Path::new("zeroes.bin"), vec![0u8; 1000])?;
+        let bytes = build(&builder);
+        let size_offset = PAK_HEADER_SIZE + 2 + "zeroes.bin".len() + 1 + 4;
+        for size in [10u32, 2000, u32::MAX] {
+            let mut corrupt = bytes.clone();
+            corrupt[size_offset..size_offset + 4].copy_from_slice(&size.to_le_bytes());
+            let pak = PakArchive::load_from_bytes(Cursor::new(corrupt))?;
+            assert_eq!(Some(size), pak.size(Path::new("zeroes.bin")));
+            assert_matches!(
+                pak.read(Path::new("zeroes.bin")),
+                Err(PakError::CorruptEntry(..))
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn loading_assets_from_pak_files() -> Result<(), PakError> {
+        let tmp_dir = TempDir::new()?;
+        let assets_dir = tmp_dir.path().join("assets");
+        std::fs::create_dir_all(assets_dir.join("gfx"))?;
+        std::fs::copy("./test-assets/dp2.pal", assets_dir.join("gfx/dp2.pal"))?;
+        std::fs::copy("./test-assets/test.bdf", assets_dir.join("font.bdf"))?;
+
+        let pak_path = tmp_dir.path().join("game.pak");
+        let mut builder = PakBuilder::new().with_compression(true);
+        assert_eq!(2, builder.add_directory(&assets_dir)?);
+        builder.to_file(&pak_path)?;
+
+        let mut assets = Assets::with_source(PakArchive::open(&pak_path)?);
+        let palette = assets.load::<Palette>(Path::new("gfx/dp2.pal")).unwrap();
+        assert_eq!(
+            Palette::load_file(Path::new("./test-assets/dp2.pal")).unwrap(),
+            *palette.get()
+        );
+        assert_ok!(assets.load::<BitmaskFont>(Path::new("font.bdf")));
+        assert_err!(assets.load::<Palette>(Path::new("missing.pal")));
+        assert!(assets.reload_changed().is_empty());
+
+        Ok(())
+    }
+}
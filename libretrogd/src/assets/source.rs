@@ -0,0 +1,105 @@
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// Somewhere that asset files can be read from, such as a directory on disk or a
+/// [`crate::assets::PakArchive`]. Paths given to an asset source are always relative to it.
+pub trait AssetSource {
+    /// Reads and returns the entire contents of the file with the given path.
+    fn read(&self, path: &Path) -> Result<Vec<u8>, std::io::Error>;
+
+    /// Returns true if a file with the given path exists.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Returns the last time the file with the given path was modified, or `None` if that is not
+    /// known, in which case the file will never be hot reloaded.
+    fn modified(&self, path: &Path) -> Option<SystemTime>;
+}
+
+/// Returns the given relative path in the form used for file names within archives: each
+/// component separated by a forward slash, with any "." components removed. Returns `None` if
+/// the path is absolute or refers to a parent directory.
+pub(crate) fn normalize_path(path: &Path) -> Option<String> {
+    let mut normalized = String::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => {
+                if !normalized.is_empty() {
+                    normalized.push('/');
+                }
+                normalized.push_str(name.to_str()?);
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(normalized)
+}
+
+/// An [`AssetSource`] that reads files from a directory on disk.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    /// Creates and returns a new source reading files from the given directory. An empty path
+    /// reads files relative to the current working directory.
+    pub fn new(root: &Path) -> Self {
+        DirectorySource {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Returns the directory files are read from.
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl AssetSource for DirectorySource {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, std::io::Error> {
+        std::fs::read(self.root.join(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).is_file()
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(self.root.join(path))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    #[test]
+    pub fn normalizing_paths() {
+        assert_eq!(
+            Some(String::from("gfx/player.pcx")),
+            normalize_path(Path::new("./gfx/./player.pcx"))
+        );
+        assert_eq!(
+            Some(String::from("a.pal")),
+            normalize_path(Path::new("a.pal"))
+        );
+        assert_none!(normalize_path(Path::new("../a.pal")));
+        assert_none!(normalize_path(Path::new("/a.pal")));
+    }
+
+    #[test]
+    pub fn reading_from_directories() {
+        let source = DirectorySource::new(Path::new("./test-assets"));
+        assert!(source.exists(Path::new("dp2.pal")));
+        assert!(!source.exists(Path::new("missing.pal")));
+        assert_some!(source.modified(Path::new("dp2.pal")));
+        assert_eq!(768, assert_ok!(source.read(Path::new("dp2.pal"))).len());
+        assert_err!(source.read(Path::new("missing.pal")));
+    }
+}
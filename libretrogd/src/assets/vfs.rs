@@ -0,0 +1,261 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use thiserror::Error;
+
+use crate::assets::*;
+
+#[derive(Error, Debug)]
+pub enum VfsError {
+    #[error("Invalid mount point: {0}")]
+    InvalidMountPoint(String),
+
+    #[error("Failed to open pak file")]
+    PakError(#[from] PakError),
+}
+
+struct Mount {
+    mount_point: String,
+    source: Box<dyn AssetSource>,
+}
+
+impl Mount {
+    /// Returns the path within this mount's source for the given (normalized) path, if the path
+    /// is under this mount's mount point.
+    fn source_path(&self, path: &str) -> Option<PathBuf> {
+        if self.mount_point.is_empty() {
+            Some(PathBuf::from(path))
+        } else {
+            path.strip_prefix(self.mount_point.as_str())
+                .and_then(|path| path.strip_prefix('/'))
+                .map(PathBuf::from)
+        }
+    }
+}
+
+/// A virtual filesystem, layering any number of [`AssetSource`]s (directories on disk, pak files,
+/// or anything else) on top of each other, each mounted at some path within the virtual
+/// filesystem. Files in sources mounted later take priority over files with the same path in
+/// sources mounted earlier, which makes it simple to support patches and mods that replace some
+/// of a game's files. For example, a game might mount its own data first, then any official
+/// patches, and then any mods the player has installed.
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use libretrogd::assets::*;
+/// use libretrogd::graphics::*;
+///
+/// let mut vfs = Vfs::new();
+/// vfs.mount_pak_file(Path::new(""), Path::new("game.pak")).unwrap();
+/// vfs.mount_pak_file(Path::new(""), Path::new("patch1.pak")).unwrap();
+/// vfs.mount_directory(Path::new("gfx"), Path::new("mods/new-graphics")).unwrap();
+///
+/// // "gfx/player.pcx" is loaded from "mods/new-graphics/player.pcx" if it exists there, or
+/// // otherwise from "patch1.pak" or "game.pak", in that order
+/// let mut assets = Assets::with_source(vfs);
+/// let player = assets.load::<Bitmap>(Path::new("gfx/player.pcx")).unwrap();
+/// ```
+#[derive(Default)]
+pub struct Vfs {
+    mounts: Vec<Mount>,
+}
+
+impl std::fmt::Debug for Vfs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vfs")
+            .field("mount_points()", &self.mount_points().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Vfs {
+    /// Creates and returns a new virtual filesystem with nothing mounted in it yet.
+    pub fn new() -> Self {
+        Vfs { mounts: Vec::new() }
+    }
+
+    /// Mounts the given source at the given path within the virtual filesystem, on top of all
+    /// of the sources mounted so far. An empty mount point mounts the source at the root of the
+    /// virtual filesystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `mount_point`: the path within the virtual filesystem to mount the source at. Must be
+    ///   relative and cannot refer to a parent directory.
+    /// * `source`: the source to be mounted
+    pub fn mount(
+        &mut self,
+        mount_point: &Path,
+        source: impl AssetSource + 'static,
+    ) -> Result<(), VfsError> {
+        let mount_point = match normalize_path(mount_point) {
+            Some(mount_point) => mount_point,
+            None => {
+                return Err(VfsError::InvalidMountPoint(
+                    mount_point.to_string_lossy().into_owned(),
+                ))
+            }
+        };
+        self.mounts.push(Mount {
+            mount_point,
+            source: Box::new(source),
+        });
+        Ok(())
+    }
+
+    /// Mounts a directory on disk at the given path. See [`Vfs::mount`].
+    pub fn mount_directory(&mut self, mount_point: &Path, dir: &Path) -> Result<(), VfsError> {
+        self.mount(mount_point, DirectorySource::new(dir))
+    }
+
+    /// Opens and mounts a pak file at the given path. See [`Vfs::mount`].
+    pub fn mount_pak_file(&mut self, mount_point: &Path, path: &Path) -> Result<(), VfsError> {
+        self.mount(mount_point, PakArchive::open(path)?)
+    }
+
+    /// Unmounts the most recently mounted source that is mounted at the given path, returning
+    /// true if there was one.
+    pub fn unmount(&mut self, mount_point: &Path) -> bool {
+        let mount_point = match normalize_path(mount_point) {
+            Some(mount_point) => mount_point,
+            None => return false,
+        };
+        match self
+            .mounts
+            .iter()
+            .rposition(|mount| mount.mount_point == mount_point)
+        {
+            Some(index) => {
+                self.mounts.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the number of sources mounted.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.mounts.len()
+    }
+
+    /// Returns true if nothing is mounted.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.mounts.is_empty()
+    }
+
+    /// Returns an iterator over the mount points of all of the mounted sources, from the lowest
+    /// to the highest priority. Mount points always use "/" as the separator.
+    pub fn mount_points(&self) -> impl Iterator<Item = &str> {
+        self.mounts.iter().map(|mount| mount.mount_point.as_str())
+    }
+
+    /// Returns the index of the highest priority source that has a file with the given path,
+    /// along with the path of the file within that source.
+    fn find(&self, path: &Path) -> Option<(usize, PathBuf)> {
+        let path = normalize_path(path)?;
+        self.mounts
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, mount)| {
+                mount
+                    .source_path(&path)
+                    .filter(|source_path| mount.source.exists(source_path))
+                    .map(|source_path| (index, source_path))
+            })
+    }
+
+    /// Returns the index of the mounted source (in the same order as [`Vfs::mount_points`]) that
+    /// the file with the given path would currently be read from, or `None` if the file does not
+    /// exist in any of them.
+    pub fn resolve(&self, path: &Path) -> Option<usize> {
+        self.find(path).map(|(index, _)| index)
+    }
+}
+
+impl AssetSource for Vfs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, std::io::Error> {
+        match self.find(path) {
+            Some((index, source_path)) => self.mounts[index].source.read(&source_path),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("\"{}\" not found in any mounted source", path.display()),
+            )),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.find(path).is_some()
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.find(path)
+            .and_then(|(index, source_path)| self.mounts[index].source.modified(&source_path))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+
+    use claim::*;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    pub fn layering_mounted_sources() -> Result<(), VfsError> {
+        let tmp_dir = TempDir::new().unwrap();
+        let game_dir = tmp_dir.path().join("game");
+        let mod_dir = tmp_dir.path().join("mod");
+        std::fs::create_dir_all(game_dir.join("gfx")).unwrap();
+        std::fs::create_dir_all(&mod_dir).unwrap();
+        std::fs::write(game_dir.join("gfx/player.txt"), "game player").unwrap();
+        std::fs::write(game_dir.join("gfx/enemy.txt"), "game enemy").unwrap();
+        std::fs::write(game_dir.join("level1.txt"), "game level1").unwrap();
+        std::fs::write(mod_dir.join("player.txt"), "mod player").unwrap();
+
+        let mut patch = PakBuilder::new();
+        patch.add(Path::new("gfx/player.txt"), b"patch player".to_vec())?;
+        patch.add(Path::new("level1.txt"), b"patch level1".to_vec())?;
+        let mut patch_bytes = Vec::new();
+        patch.to_bytes(&mut patch_bytes)?;
+
+        let mut vfs = Vfs::new();
+        vfs.mount_directory(Path::new(""), &game_dir)?;
+        vfs.mount(
+            Path::new("./"),
+            PakArchive::load_from_bytes(Cursor::new(patch_bytes))?,
+        )?;
+        vfs.mount_directory(Path::new("gfx"), &mod_dir)?;
+        assert_matches!(
+            vfs.mount_directory(Path::new("../outside"), &mod_dir),
+            Err(VfsError::InvalidMountPoint(..))
+        );
+        assert_eq!(3, vfs.len());
+        assert_eq!(vec!["", "", "gfx"], vfs.mount_points().collect::<Vec<_>>());
+
+        let read = |vfs: &Vfs, path: &str| String::from_utf8(vfs.read(Path::new(path)).unwrap());
+        assert_eq!("mod player", read(&vfs, "gfx/player.txt").unwrap());
+        assert_eq!("game enemy", read(&vfs, "gfx/enemy.txt").unwrap());
+        assert_eq!("patch level1", read(&vfs, "./level1.txt").unwrap());
+        assert_eq!(Some(2), vfs.resolve(Path::new("gfx/player.txt")));
+        assert_eq!(Some(0), vfs.resolve(Path::new("gfx/enemy.txt")));
+        assert!(!vfs.exists(Path::new("player.txt")));
+        assert_none!(vfs.resolve(Path::new("missing.txt")));
+        assert_err!(vfs.read(Path::new("missing.txt")));
+        assert_some!(vfs.modified(Path::new("gfx/player.txt")));
+        assert_none!(vfs.modified(Path::new("level1.txt")));
+
+        assert!(vfs.unmount(Path::new("gfx")));
+        assert!(!vfs.unmount(Path::new("gfx")));
+        assert_eq!("patch player", read(&vfs, "gfx/player.txt").unwrap());
+        assert!(vfs.unmount(Path::new("")));
+        assert_eq!("game player", read(&vfs, "gfx/player.txt").unwrap());
+
+        Ok(())
+    }
+}
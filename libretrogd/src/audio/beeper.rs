@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The amplitude of the beeper's square wave, in the same range as [`crate::audio::AudioChannel`]
+/// samples (-128 to 127). Kept well below the maximum so that the harsh square wave does not
+/// drown out everything else being mixed with it.
+pub const BEEPER_AMPLITUDE: f32 = 48.0;
+
+/// A single tone to be played by the beeper, as queued via [`crate::audio::AudioDevice::beep`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Beep {
+    /// The frequencies (in Hz) of the tone. If more than one is given, the beeper rapidly cycles
+    /// between them as an arpeggio, the classic trick for faking chords on a single voice. If
+    /// empty, the beep is silent.
+    pub frequencies: Vec<u32>,
+    /// How long the beep lasts.
+    pub duration: Duration,
+    /// How long each frequency of an arpeggio is played for before moving on to the next one.
+    pub arpeggio_rate: Duration,
+}
+
+impl Beep {
+    /// Returns a new [`Beep`] that plays a single frequency (in Hz) for the given duration.
+    pub fn new(frequency: u32, duration: Duration) -> Self {
+        Beep {
+            frequencies: vec![frequency],
+            duration,
+            arpeggio_rate: Duration::ZERO,
+        }
+    }
+
+    /// Returns a new silent [`Beep`] that lasts for the given duration, useful for putting gaps
+    /// between queued beeps.
+    pub fn rest(duration: Duration) -> Self {
+        Beep {
+            frequencies: Vec::new(),
+            duration,
+            arpeggio_rate: Duration::ZERO,
+        }
+    }
+
+    /// Returns a new [`Beep`] that cycles between the given frequencies (in Hz) for the given
+    /// duration.
+    ///
+    /// # Arguments
+    ///
+    /// * `frequencies`: the frequencies to cycle between, in order
+    /// * `duration`: how long the beep lasts in total
+    /// * `arpeggio_rate`: how long each frequency is played for before moving on to the next
+    pub fn arpeggio(frequencies: &[u32], duration: Duration, arpeggio_rate: Duration) -> Self {
+        Beep {
+            frequencies: frequencies.to_vec(),
+            duration,
+            arpeggio_rate,
+        }
+    }
+
+    /// Returns the frequency to be played at the given time since the start of the beep, or
+    /// `None` if it is silent at that time.
+    fn frequency_at(&self, elapsed: f64) -> Option<u32> {
+        let index = match self.frequencies.len() {
+            0 => return None,
+            1 => 0,
+            len => {
+                let rate = self.arpeggio_rate.as_secs_f64();
+                if rate > 0.0 {
+                    (elapsed / rate) as usize % len
+                } else {
+                    0
+                }
+            }
+        };
+        match self.frequencies[index] {
+            0 => None,
+            frequency => Some(frequency),
+        }
+    }
+}
+
+/// A single square wave voice, emulating the sound of a PC speaker. Plays a queue of [`Beep`]s
+/// one after the other, separately from (and mixed together with) the sounds playing on
+/// [`crate::audio::AudioChannel`]s. Owned and mixed by [`crate::audio::AudioDevice`].
+#[derive(Debug, Clone)]
+pub(crate) struct Beeper {
+    queue: VecDeque<Beep>,
+    elapsed: f64,
+    phase: f32,
+}
+
+impl Beeper {
+    pub fn new() -> Self {
+        Beeper {
+            queue: VecDeque::new(),
+            elapsed: 0.0,
+            phase: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn is_beeping(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    pub fn beep(&mut self, beep: Beep) {
+        self.queue.push_back(beep);
+    }
+
+    pub fn stop(&mut self) {
+        self.queue.clear();
+        self.elapsed = 0.0;
+    }
+
+    /// Returns the next sample of the square wave to be mixed in, or `None` if there are no more
+    /// beeps left to play.
+    pub fn sample(&mut self, output_frequency: u32) -> Option<f32> {
+        let output_frequency = output_frequency.max(1) as f64;
+        loop {
+            let beep = self.queue.front()?;
+            if self.elapsed < beep.duration.as_secs_f64() {
+                break;
+            }
+            self.queue.pop_front();
+            self.elapsed = 0.0;
+        }
+
+        let beep = self.queue.front()?;
+        let sample = match beep.frequency_at(self.elapsed) {
+            Some(frequency) => {
+                let sample = if self.phase < 0.5 {
+                    BEEPER_AMPLITUDE
+                } else {
+                    -BEEPER_AMPLITUDE
+                };
+                self.phase = (self.phase + (frequency as f64 / output_frequency) as f32).fract();
+                sample
+            }
+            None => 0.0,
+        };
+        self.elapsed += 1.0 / output_frequency;
+        Some(sample)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn play(beeper: &mut Beeper, output_frequency: u32, count: usize) -> Vec<f32> {
+        (0..count)
+            .map_while(|_| beeper.sample(output_frequency))
+            .map(|sample| sample / BEEPER_AMPLITUDE)
+            .collect()
+    }
+
+    #[test]
+    pub fn queued_beeps() {
+        let mut beeper = Beeper::new();
+        assert!(!beeper.is_beeping());
+        beeper.beep(Beep::new(2, Duration::from_millis(500)));
+        beeper.beep(Beep::rest(Duration::from_millis(250)));
+        beeper.beep(Beep::new(4, Duration::from_millis(250)));
+        assert!(beeper.is_beeping());
+        assert_eq!(
+            vec![1.0, 1.0, -1.0, -1.0, 0.0, 0.0, 1.0, -1.0],
+            play(&mut beeper, 8, 10)
+        );
+        assert!(!beeper.is_beeping());
+
+        beeper.beep(Beep::new(2, Duration::from_secs(1)));
+        assert_eq!(vec![1.0, 1.0], play(&mut beeper, 8, 2));
+        beeper.stop();
+        assert_eq!(Vec::<f32>::new(), play(&mut beeper, 8, 2));
+    }
+
+    #[test]
+    pub fn arpeggio_beeps() {
+        let mut beeper = Beeper::new();
+        beeper.beep(Beep::arpeggio(
+            &[2, 4],
+            Duration::from_secs(1),
+            Duration::from_millis(250),
+        ));
+        assert_eq!(
+            vec![1.0, 1.0, -1.0, 1.0, -1.0, -1.0, 1.0, -1.0],
+            play(&mut beeper, 8, 10)
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io;
-use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
@@ -31,7 +31,6 @@ impl ChunkId {
         Ok(ChunkId { id })
     }
 
-    #[allow(dead_code)]
     pub fn write<T: Write>(&self, writer: &mut T) -> Result<(), WavError> {
         writer.write_all(&self.id)?;
         Ok(())
@@ -51,7 +50,6 @@ impl SubChunkHeader {
         Ok(SubChunkHeader { chunk_id, size })
     }
 
-    #[allow(dead_code)]
     pub fn write<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), WavError> {
         self.chunk_id.write(writer)?;
         writer.write_u32::<LittleEndian>(self.size)?;
@@ -75,7 +73,6 @@ impl WavHeader {
         })
     }
 
-    #[allow(dead_code)]
     pub fn write<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), WavError> {
         self.file_chunk.write(writer)?;
         self.file_container_id.write(writer)?;
@@ -131,7 +128,6 @@ impl FormatChunk {
         })
     }
 
-    #[allow(dead_code)]
     pub fn write<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), WavError> {
         writer.write_u16::<LittleEndian>(self.compression_code)?;
         writer.write_u16::<LittleEndian>(self.channels)?;
@@ -191,6 +187,118 @@ impl DataChunk {
     }
 }
 
+/// Writes audio data out in WAV format as it is provided, for when all of the audio is not known
+/// up-front (e.g. when capturing the audio being played). Only unsigned 8-bit and signed 16-bit
+/// little-endian audio can be written. The WAV file is not complete until [`WavWriter::finish`]
+/// is called.
+#[derive(Debug)]
+pub struct WavWriter<T: Write + Seek> {
+    writer: T,
+    spec: AudioSpec,
+    data_size: u32,
+}
+
+impl<T: Write + Seek> WavWriter<T> {
+    /// Creates a new [`WavWriter`], writing out the WAV headers for audio in the given spec
+    /// straight away.
+    pub fn new(mut writer: T, spec: AudioSpec) -> Result<Self, WavError> {
+        let bits_per_sample = match spec.format() {
+            AudioFormat::U8 => 8,
+            AudioFormat::S16LSB => 16,
+            format => {
+                return Err(WavError::BadFile(format!(
+                    "Audio in the {:?} format cannot be written to a WAV file",
+                    format
+                )))
+            }
+        };
+        let block_alignment = spec.channels() as u16 * (bits_per_sample / 8);
+
+        // the RIFF and data chunk sizes are left as zero until we know how much data there is
+        let header = WavHeader {
+            file_chunk: SubChunkHeader {
+                chunk_id: ChunkId { id: *b"RIFF" },
+                size: 0,
+            },
+            file_container_id: ChunkId { id: *b"WAVE" },
+        };
+        header.write(&mut writer)?;
+        SubChunkHeader {
+            chunk_id: ChunkId { id: *b"fmt " },
+            size: 16,
+        }
+        .write(&mut writer)?;
+        FormatChunk {
+            compression_code: 1,
+            channels: spec.channels() as u16,
+            frequency: spec.frequency(),
+            bytes_per_second: spec.frequency() * block_alignment as u32,
+            block_alignment,
+            bits_per_sample,
+            additional_data_length: 0,
+            additional_data: None,
+        }
+        .write(&mut writer)?;
+        SubChunkHeader {
+            chunk_id: ChunkId { id: *b"data" },
+            size: 0,
+        }
+        .write(&mut writer)?;
+
+        Ok(WavWriter {
+            writer,
+            spec,
+            data_size: 0,
+        })
+    }
+
+    /// Returns the spec of the audio being written.
+    #[inline]
+    pub fn spec(&self) -> &AudioSpec {
+        &self.spec
+    }
+
+    /// Returns the number of bytes of audio data written so far.
+    #[inline]
+    pub fn data_size(&self) -> u32 {
+        self.data_size
+    }
+
+    /// Appends the given audio data, which must be in the spec this writer was created with.
+    pub fn write_samples(&mut self, data: &[u8]) -> Result<(), WavError> {
+        self.writer.write_all(data)?;
+        self.data_size = self.data_size.saturating_add(data.len() as u32);
+        Ok(())
+    }
+
+    /// Completes the WAV file by filling in the chunk sizes in its headers now that the amount of
+    /// audio data is known, returning the underlying writer.
+    pub fn finish(mut self) -> Result<T, WavError> {
+        // chunks are padded to an even size, though the padding is not counted in the chunk size
+        let padding = self.data_size & 1;
+        if padding != 0 {
+            self.writer.write_u8(0)?;
+        }
+        let riff_size = 4 + (8 + 16) + 8 + self.data_size + padding;
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_u32::<LittleEndian>(riff_size)?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_u32::<LittleEndian>(self.data_size)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl WavWriter<BufWriter<File>> {
+    /// Creates a new WAV file at the given path (replacing it if it already exists) to write
+    /// audio in the given spec to.
+    pub fn create_file(path: &Path, spec: AudioSpec) -> Result<Self, WavError> {
+        let f = File::create(path)?;
+        Self::new(BufWriter::new(f), spec)
+    }
+}
+
 impl AudioBuffer {
     /// Loads the bytes of a WAV file into an [`AudioBuffer`]. The returned buffer will be in its
     /// original format and may need to be converted before it can be played.
@@ -298,6 +406,8 @@ impl AudioBuffer {
 
 #[cfg(test)]
 mod tests {
+    use claim::*;
+
     use crate::audio::*;
 
     use super::*;
@@ -330,4 +440,29 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn write_wav_file() -> Result<(), WavError> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = tmp_dir.path().join("written.wav");
+
+        let spec = AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 2, AudioFormat::U8);
+        let mut writer = WavWriter::create_file(&path, spec)?;
+        writer.write_samples(&[128, 130, 140])?;
+        writer.write_samples(&[150, 160])?;
+        assert_eq!(5, writer.data_size());
+        writer.finish()?;
+
+        let wav_buffer = AudioBuffer::load_wav_file(&path)?;
+        assert_eq!(spec, *wav_buffer.spec());
+        assert_eq!(vec![128, 130, 140, 150, 160], wav_buffer.data);
+
+        let float_spec = AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 1, AudioFormat::F32LSB);
+        assert_matches!(
+            WavWriter::new(std::io::Cursor::new(Vec::new()), float_spec),
+            Err(WavError::BadFile(..))
+        );
+
+        Ok(())
+    }
 }
@@ -1,6 +1,11 @@
+use std::collections::VecDeque;
 use std::ops::{Index, IndexMut};
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::JoinHandle;
+use std::time::Duration;
 
-use sdl2::audio::AudioCallback;
+use sdl2::audio::{AudioCallback, AudioFormat};
 use thiserror::Error;
 
 use crate::audio::*;
@@ -23,10 +28,39 @@ pub struct AudioChannel {
     pub generator: Option<Box<dyn AudioGenerator>>,
     /// The volume level to play this channel at. 1.0 is "normal", 0.0 is completely silent.
     pub volume: f32,
+    /// The playback rate (pitch) of this channel. 1.0 is "normal", 2.0 plays twice as fast (an
+    /// octave higher), 0.5 plays at half speed (an octave lower), etc.
+    pub pitch: f32,
+    /// The stereo panning of this channel, from -1.0 (fully left) to 1.0 (fully right). 0.0 is
+    /// centered. Has no effect if the audio device is only outputting mono audio.
+    pub pan: f32,
+    /// The priority of the sound playing on this channel. When all channels are busy, sounds
+    /// played with [`AudioDevice::play_buffer_with_priority`] will interrupt the lowest priority
+    /// sound playing, as long as its priority is not higher than that of the new sound.
+    pub priority: u8,
     /// The current playback position (index). 0 is the start of playback. The end position is
     /// either the (current) size of the [`data`] buffer or dependant on the implementation of this
     /// channel's current [`generator`] if not `None`.
     pub position: usize,
+    /// The position that playback jumps back to when looping. 0 is the start of playback.
+    pub loop_start: usize,
+    /// The position that playback loops from, or `None` to loop from the end of the [`data`]
+    /// buffer (or when the [`generator`] returns `None`).
+    pub loop_end: Option<usize>,
+    /// How far the position is advanced for each output sample, used to resample audio data
+    /// recorded at a different frequency than the audio device is playing at.
+    sample_step: f32,
+    position_fraction: f32,
+    fade: Option<VolumeFade>,
+}
+
+/// A gradual change of an [`AudioChannel`]'s volume, applied as the channel plays.
+#[derive(Debug, Copy, Clone)]
+struct VolumeFade {
+    target_volume: f32,
+    volume_step: f32,
+    remaining_ticks: usize,
+    stop_when_done: bool,
 }
 
 impl std::fmt::Debug for AudioChannel {
@@ -40,7 +74,14 @@ impl std::fmt::Debug for AudioChannel {
                 None => &"None",
             })
             .field("volume", &self.volume)
+            .field("pitch", &self.pitch)
+            .field("pan", &self.pan)
+            .field("priority", &self.priority)
             .field("position", &self.position)
+            .field("loop_start", &self.loop_start)
+            .field("loop_end", &self.loop_end)
+            .field("sample_step", &self.sample_step)
+            .field("fade", &self.fade)
             .finish_non_exhaustive()
     }
 }
@@ -51,12 +92,41 @@ impl AudioChannel {
             playing: false,
             loops: false,
             volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            priority: 0,
             position: 0,
+            loop_start: 0,
+            loop_end: None,
+            sample_step: 1.0,
+            position_fraction: 0.0,
+            fade: None,
             generator: None,
             data: Vec::new(),
         }
     }
 
+    /// Returns the volume multipliers for the left and right output channels for this channel's
+    /// current pan setting.
+    #[inline]
+    fn pan_gains(&self) -> (f32, f32) {
+        let pan = self.pan.clamp(-1.0, 1.0);
+        ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+    }
+
+    #[inline]
+    fn restart(&mut self, sample_step: f32, loops: bool) {
+        self.position = 0;
+        self.position_fraction = 0.0;
+        self.loop_start = 0;
+        self.loop_end = None;
+        self.fade = None;
+        self.pitch = 1.0;
+        self.sample_step = sample_step;
+        self.playing = true;
+        self.loops = loops;
+    }
+
     /// Returns the audio sample for the given position, or `None` if that position is invalid.
     #[inline]
     fn data_at(&mut self, position: usize) -> Option<u8> {
@@ -69,7 +139,8 @@ impl AudioChannel {
 
     /// Returns the next sample from this channel's buffer. If this channel's buffer is done
     /// playing or there is no buffer data at all, `None` is returned. If the next sample was
-    /// successfully loaded from the buffer, the channel's current position is advanced by 1.
+    /// successfully loaded from the buffer, the channel's current position is advanced (by 1,
+    /// unless the buffer is being resampled or played at a different pitch).
     ///
     /// The returned sample will be a byte value, but in an `i16` with the buffer's original `u8`
     /// value centered around 0 (meaning the returned sample will be within the range -128 to 127
@@ -77,14 +148,17 @@ impl AudioChannel {
     #[inline]
     fn next_sample(&mut self) -> Option<i16> {
         if let Some(sample) = self.data_at(self.position) {
-            self.position += 1;
+            self.position_fraction += self.sample_step * self.pitch.max(0.0);
+            let whole = self.position_fraction as usize;
+            self.position += whole;
+            self.position_fraction -= whole as f32;
             Some(sample as i16 - 128)
         } else {
             None
         }
     }
 
-    /// Samples the channel's current audio buffer, advancing the position within that buffer by 1.
+    /// Samples the channel's current audio buffer, advancing the position within that buffer.
     /// The channel will automatically stop playing when the end of the buffer is reached and if
     /// the channel is not set to loop. `None` is returned if no data can be read from the buffer
     /// for any reason, or if the channel is not currently playing.
@@ -98,19 +172,99 @@ impl AudioChannel {
             return None;
         }
 
-        if let Some(sample) = self.next_sample() {
-            Some((sample as f32 * self.volume) as i16)
-        } else {
-            if self.loops {
-                self.position = 0;
-                None
+        let past_loop_end = self
+            .loop_end
+            .is_some_and(|loop_end| self.position >= loop_end);
+        if self.loops && past_loop_end {
+            self.position = self.loop_start;
+            self.position_fraction = 0.0;
+        }
+
+        let sample = match self.next_sample() {
+            Some(sample) => sample,
+            None if self.loops && self.position != self.loop_start => {
+                self.position = self.loop_start;
+                self.position_fraction = 0.0;
+                self.next_sample()?
+            }
+            None => {
+                if !self.loops {
+                    self.stop();
+                }
+                return None;
+            }
+        };
+        let sample = (sample as f32 * self.volume) as i16;
+        self.update_fade();
+        Some(sample)
+    }
+
+    /// Advances the current volume fade (if any) by one tick.
+    #[inline]
+    fn update_fade(&mut self) {
+        if let Some(fade) = &mut self.fade {
+            fade.remaining_ticks = fade.remaining_ticks.saturating_sub(1);
+            if fade.remaining_ticks == 0 {
+                self.volume = fade.target_volume;
+                if fade.stop_when_done {
+                    self.stop();
+                }
+                self.fade = None;
             } else {
+                self.volume += fade.volume_step;
+            }
+        }
+    }
+
+    /// Gradually changes this channel's volume to the given volume as it plays. Any fade that was
+    /// already in progress is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `volume`: the volume to end up at
+    /// * `ticks`: how long the fade takes, in ticks of the audio device (one tick per sample
+    ///   played, e.g. 22050 ticks is one second with the audio device playing at 22khz). if zero,
+    ///   the volume is changed immediately
+    pub fn fade_to(&mut self, volume: f32, ticks: usize) {
+        self.start_fade(volume, ticks, false);
+    }
+
+    /// Starts playing this channel silently and gradually raises the volume up to the given
+    /// volume. See [`AudioChannel::fade_to`] for more details about the `ticks` argument.
+    pub fn fade_in(&mut self, volume: f32, ticks: usize) {
+        self.volume = 0.0;
+        self.start_fade(volume, ticks, false);
+    }
+
+    /// Gradually lowers this channel's volume to silence, and then stops playback. See
+    /// [`AudioChannel::fade_to`] for more details about the `ticks` argument.
+    pub fn fade_out(&mut self, ticks: usize) {
+        self.start_fade(0.0, ticks, true);
+    }
+
+    fn start_fade(&mut self, volume: f32, ticks: usize, stop_when_done: bool) {
+        self.fade = None;
+        if ticks == 0 {
+            self.volume = volume;
+            if stop_when_done {
                 self.stop();
-                None
             }
+        } else {
+            self.fade = Some(VolumeFade {
+                target_volume: volume,
+                volume_step: (volume - self.volume) / ticks as f32,
+                remaining_ticks: ticks,
+                stop_when_done,
+            });
         }
     }
 
+    /// Returns true if this channel's volume is currently being faded.
+    #[inline]
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
+
     /// Resets the audio channel to a "blank slate", clearing the audio buffer, setting no current
     /// audio generator, and turning playback off.
     #[inline]
@@ -118,31 +272,47 @@ impl AudioChannel {
         self.data.clear();
         self.generator = None;
         self.position = 0;
+        self.loop_start = 0;
+        self.loop_end = None;
+        self.fade = None;
         self.playing = false;
     }
 
     /// Copies the data from the given audio buffer into this channel's buffer (clearing it first,
     /// and extending the size of the buffer if necessary) and then begins playback from position 0.
-    /// This also sets the associated [`generator`] to `None`.
+    /// This also sets the associated [`generator`] to `None`. The pitch and loop region are reset
+    /// and any volume fade in progress is cancelled.
     #[inline]
     pub fn play_buffer(&mut self, buffer: &AudioBuffer, loops: bool) {
         self.data.clear();
         self.data.extend(&buffer.data);
         self.generator = None;
-        self.position = 0;
-        self.playing = true;
-        self.loops = loops;
+        self.restart(1.0, loops);
+    }
+
+    /// Converts the given audio buffer's data into this channel's buffer and begins playback from
+    /// position 0, resampling from the buffer's frequency to the given output frequency.
+    fn play_converted_buffer(
+        &mut self,
+        buffer: &AudioBuffer,
+        output_frequency: u32,
+        loops: bool,
+    ) -> Result<(), AudioDeviceError> {
+        copy_as_mixer_samples(buffer, &mut self.data)?;
+        self.generator = None;
+        let sample_step = buffer.spec().frequency() as f32 / output_frequency.max(1) as f32;
+        self.restart(sample_step, loops);
+        Ok(())
     }
 
     /// Begins playback on this channel from the given [`AudioGenerator`] instance from position 0.
-    /// This also clears the existing audio buffer contents.
+    /// This also clears the existing audio buffer contents. The pitch and loop region are reset
+    /// and any volume fade in progress is cancelled.
     #[inline]
     pub fn play_generator(&mut self, generator: Box<dyn AudioGenerator>, loops: bool) {
         self.data.clear();
         self.generator = Some(generator);
-        self.position = 0;
-        self.playing = true;
-        self.loops = loops;
+        self.restart(1.0, loops);
     }
 
     /// Returns true if this channel has something that can be played back currently.
@@ -158,6 +328,7 @@ impl AudioChannel {
     pub fn play(&mut self, loops: bool) -> bool {
         if self.is_playable() {
             self.position = 0;
+            self.position_fraction = 0.0;
             self.playing = true;
             self.loops = loops;
             true
@@ -177,8 +348,8 @@ impl AudioChannel {
 
 #[derive(Debug, Error)]
 pub enum AudioDeviceError {
-    #[error("That buffer's AudioSpec does not match the device's AudioSpec")]
-    AudioSpecMismatch,
+    #[error("Audio buffers in the {0:?} format cannot be played")]
+    UnsupportedAudioFormat(AudioFormat),
 
     #[error("The channel index {0} is out of range")]
     ChannelIndexOutOfRange(usize),
@@ -190,8 +361,135 @@ pub enum AudioDeviceError {
 #[derive(Debug)]
 pub struct AudioDevice {
     spec: AudioSpec,
+    output_channels: u8,
     channels: Vec<AudioChannel>,
+    music: MusicPlayer,
     pub volume: f32,
+    /// The volume level to play music at, relative to the overall `volume`. 1.0 is "normal", 0.0
+    /// is completely silent.
+    pub music_volume: f32,
+    beeper: Beeper,
+    /// The volume level to play the beeper at, relative to the overall `volume`. 1.0 is "normal",
+    /// 0.0 is completely silent.
+    pub beeper_volume: f32,
+    capture: Option<AudioCapture>,
+    sample_clock: u64,
+    scheduled: VecDeque<ScheduledSound>,
+}
+
+/// The number of sample buffers shared between the audio callback and an [`AudioCapture`]'s writer
+/// thread. This is how many callbacks' worth of audio can be waiting to be written at once.
+const CAPTURE_BUFFERS: usize = 8;
+
+/// Audio sent from the audio callback to an [`AudioCapture`]'s writer thread.
+#[derive(Debug)]
+enum CapturedAudio {
+    /// Mixed samples, in one of the capture's buffers which is sent back once written.
+    Samples(Vec<u8>),
+    /// The given number of samples that could not be captured because the writer thread had
+    /// fallen behind and no buffers were free. These are written out as silence instead.
+    Dropped(usize),
+}
+
+/// A capture of the final mixed audio output to a WAV file that is in progress. The mixed samples
+/// are sent off to a separate thread which does the actual writing, so that the audio callback is
+/// never held up waiting on file I/O. The samples are copied into a fixed set of buffers which are
+/// passed back and forth with that thread, so the audio callback does not allocate either.
+#[derive(Debug)]
+pub(crate) struct AudioCapture {
+    spec: AudioSpec,
+    sender: SyncSender<CapturedAudio>,
+    free_buffers: Receiver<Vec<u8>>,
+    writer: JoinHandle<Result<(), WavError>>,
+}
+
+impl AudioCapture {
+    /// Creates the WAV file at the given path and starts the thread which writes captured audio
+    /// to it. `buffer_size` is the number of samples expected from each audio callback.
+    pub(crate) fn start(
+        path: &Path,
+        spec: AudioSpec,
+        buffer_size: usize,
+    ) -> Result<Self, WavError> {
+        let mut writer = WavWriter::create_file(path, spec)?;
+        // each channel has room for every buffer, so neither side ever blocks on a full channel
+        // (except for dropped audio, which is no great loss if it does not fit either)
+        let (sender, receiver) = sync_channel::<CapturedAudio>(CAPTURE_BUFFERS * 2);
+        let (free_sender, free_buffers) = sync_channel::<Vec<u8>>(CAPTURE_BUFFERS);
+        for _ in 0..CAPTURE_BUFFERS {
+            let _ = free_sender.send(Vec::with_capacity(buffer_size));
+        }
+        let writer = std::thread::Builder::new()
+            .name(String::from("audio capture"))
+            .spawn(move || {
+                // stops early on the first error, which is then returned once the capture is
+                // finished. the audio callback's samples are simply dropped after that
+                for audio in receiver {
+                    match audio {
+                        CapturedAudio::Samples(samples) => {
+                            writer.write_samples(&samples)?;
+                            let _ = free_sender.send(samples);
+                        }
+                        CapturedAudio::Dropped(length) => {
+                            // captures are always written as unsigned 8-bit samples
+                            writer.write_samples(&vec![SILENCE; length])?;
+                        }
+                    }
+                }
+                writer.finish()?;
+                Ok(())
+            })?;
+        Ok(AudioCapture {
+            spec,
+            sender,
+            free_buffers,
+            writer,
+        })
+    }
+
+    /// Returns the spec of the audio being written out by this capture.
+    #[inline]
+    pub fn spec(&self) -> &AudioSpec {
+        &self.spec
+    }
+
+    /// Queues the given samples to be written out by the capture's writer thread.
+    fn capture(&self, samples: &[u8]) {
+        // these only fail if the writer thread has stopped after an error, which there is no way
+        // to report from here. it is returned by finish instead
+        match self.free_buffers.try_recv() {
+            Ok(mut buffer) => {
+                buffer.clear();
+                buffer.extend_from_slice(samples);
+                let _ = self.sender.try_send(CapturedAudio::Samples(buffer));
+            }
+            Err(..) => {
+                let _ = self.sender.try_send(CapturedAudio::Dropped(samples.len()));
+            }
+        }
+    }
+
+    /// Waits for all of the samples captured so far to be written, and then completes the WAV
+    /// file, returning any error that occurred while writing it. As this can take a while, it
+    /// should not be called while the audio device is locked.
+    pub fn finish(self) -> Result<(), WavError> {
+        // the writer thread stops once it has received everything sent before this
+        drop(self.sender);
+        match self.writer.join() {
+            Ok(result) => result,
+            Err(..) => Err(WavError::IOError(std::io::Error::other(
+                "Audio capture thread panicked",
+            ))),
+        }
+    }
+}
+
+/// A sound waiting to be started by the mixer at a specific sample frame.
+#[derive(Debug)]
+struct ScheduledSound {
+    at: u64,
+    buffer: AudioBuffer,
+    loops: bool,
 }
 
 /// SDL audio callback implementation which performs audio mixing, generating the final sample data
@@ -200,40 +498,178 @@ impl AudioCallback for AudioDevice {
     type Channel = u8;
 
     fn callback(&mut self, out: &mut [u8]) {
-        for dest in out.iter_mut() {
-            let mut sample: i16 = 0;
+        let output_channels = self.output_channels.max(1) as usize;
+        for frame in out.chunks_mut(output_channels) {
+            self.start_scheduled();
+            let mut mono = 0.0;
+            let mut left = 0.0;
+            let mut right = 0.0;
             for channel in self.channels.iter_mut() {
-                if let Some(this_sample) = channel.sample() {
-                    sample += this_sample;
+                if let Some(sample) = channel.sample() {
+                    let sample = sample as f32;
+                    let (left_gain, right_gain) = channel.pan_gains();
+                    mono += sample;
+                    left += sample * left_gain;
+                    right += sample * right_gain;
                 }
             }
-            sample = ((sample as f32) * self.volume) as i16;
-            *dest = (sample.clamp(-128, 127) + 128) as u8;
+            if let Some(sample) = self.music.sample() {
+                let sample = sample * self.music_volume;
+                mono += sample;
+                left += sample;
+                right += sample;
+            }
+            if let Some(sample) = self.beeper.sample(self.spec.frequency()) {
+                let sample = sample * self.beeper_volume;
+                mono += sample;
+                left += sample;
+                right += sample;
+            }
+            if frame.len() == 1 {
+                frame[0] = to_output_sample(mono * self.volume);
+            } else {
+                // any output channels beyond the first two (left and right) get the unpanned mix
+                frame.fill(to_output_sample(mono * self.volume));
+                frame[0] = to_output_sample(left * self.volume);
+                frame[1] = to_output_sample(right * self.volume);
+            }
+            self.sample_clock += 1;
+        }
+        if let Some(capture) = &self.capture {
+            capture.capture(out);
+        }
+    }
+}
+
+#[inline]
+fn to_output_sample(sample: f32) -> u8 {
+    (sample.clamp(-128.0, 127.0) as i16 + 128) as u8
+}
+
+/// Copies the audio data from the given buffer to `dest` as unsigned 8-bit mono samples, the
+/// format that [`AudioChannel`]s play, mixing down all of the buffer's channels.
+pub(crate) fn copy_as_mixer_samples(
+    buffer: &AudioBuffer,
+    dest: &mut Vec<u8>,
+) -> Result<(), AudioDeviceError> {
+    let channels = buffer.spec().channels().max(1) as usize;
+    let (sample_size, decode): (usize, fn(&[u8]) -> i32) = match buffer.spec().format() {
+        AudioFormat::U8 => (1, |b| b[0] as i32 - 128),
+        AudioFormat::S8 => (1, |b| b[0] as i8 as i32),
+        AudioFormat::U16LSB => (2, |b| {
+            (u16::from_le_bytes([b[0], b[1]]) as i32 - 32768) >> 8
+        }),
+        AudioFormat::U16MSB => (2, |b| {
+            (u16::from_be_bytes([b[0], b[1]]) as i32 - 32768) >> 8
+        }),
+        AudioFormat::S16LSB => (2, |b| i16::from_le_bytes([b[0], b[1]]) as i32 >> 8),
+        AudioFormat::S16MSB => (2, |b| i16::from_be_bytes([b[0], b[1]]) as i32 >> 8),
+        format => return Err(AudioDeviceError::UnsupportedAudioFormat(format)),
+    };
+    dest.clear();
+    if sample_size == 1 && channels == 1 && buffer.spec().format() == AudioFormat::U8 {
+        dest.extend_from_slice(&buffer.data);
+    } else {
+        for frame in buffer.data.chunks_exact(sample_size * channels) {
+            let sum: i32 = frame.chunks_exact(sample_size).map(decode).sum();
+            dest.push(((sum / channels as i32).clamp(-128, 127) + 128) as u8);
         }
     }
+    Ok(())
+}
+
+/// Returns an error if audio buffers with the given spec cannot be played by an [`AudioDevice`].
+pub(crate) fn check_playable_spec(spec: &AudioSpec) -> Result<(), AudioDeviceError> {
+    match spec.format() {
+        AudioFormat::U8
+        | AudioFormat::S8
+        | AudioFormat::U16LSB
+        | AudioFormat::U16MSB
+        | AudioFormat::S16LSB
+        | AudioFormat::S16MSB => Ok(()),
+        format => Err(AudioDeviceError::UnsupportedAudioFormat(format)),
+    }
 }
 
 impl AudioDevice {
-    /// Creates a new [`AudioDevice`] instance, using the given spec as its playback format.
+    /// Creates a new [`AudioDevice`] instance with [`NUM_CHANNELS`] channels, using the given spec
+    /// as its playback format and outputting mono audio.
     pub fn new(spec: AudioSpec) -> Self {
+        Self::with_channels(spec, NUM_CHANNELS, 1)
+    }
+
+    /// Creates a new [`AudioDevice`] instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec`: the playback format. the number of channels in this spec is ignored, as all
+    ///   channels are mixed as mono audio before being panned to the output channels
+    /// * `num_channels`: the number of [`AudioChannel`]s that can be playing simultaneously
+    /// * `output_channels`: the number of channels the audio device is outputting (e.g. 1 = mono,
+    ///   2 = stereo, etc)
+    pub fn with_channels(spec: AudioSpec, num_channels: usize, output_channels: u8) -> Self {
         let mut channels = Vec::new();
-        for _ in 0..NUM_CHANNELS {
+        for _ in 0..num_channels {
             channels.push(AudioChannel::new());
         }
         AudioDevice {
-            spec,
+            spec: AudioSpec::new(spec.frequency(), 1, spec.format()),
+            output_channels,
             channels,
+            music: MusicPlayer::new(),
             volume: 1.0,
+            music_volume: 1.0,
+            beeper: Beeper::new(),
+            beeper_volume: 1.0,
+            capture: None,
+            sample_clock: 0,
+            scheduled: VecDeque::new(),
         }
     }
 
-    /// Returns the spec that this device is currently set to play. All audio to be played via
-    /// this device must be pre-converted to match this spec!
+    /// Returns the spec that this device is currently set to play. Audio buffers in this spec can
+    /// be played the fastest, though buffers with other frequencies, channel counts and 8-bit or
+    /// 16-bit sample formats are converted automatically when played.
     #[inline]
     pub fn spec(&self) -> &AudioSpec {
         &self.spec
     }
 
+    /// Returns the number of channels the audio device is outputting (e.g. 1 = mono, 2 = stereo).
+    #[inline]
+    pub fn output_channels(&self) -> u8 {
+        self.output_channels
+    }
+
+    /// Changes the spec and number of output channels that this device mixes audio for, adjusting
+    /// everything already playing so that it carries on playing at the same speed. Used when the
+    /// system's audio device is reopened with a different format. A WAV file cannot change format
+    /// part way through, so if the capture in progress no longer matches the output, it is
+    /// stopped and returned to be finished by the caller.
+    pub(crate) fn set_output_spec(
+        &mut self,
+        spec: AudioSpec,
+        output_channels: u8,
+    ) -> Option<AudioCapture> {
+        let ratio = self.spec.frequency() as f32 / spec.frequency().max(1) as f32;
+        for channel in self.channels.iter_mut() {
+            channel.sample_step *= ratio;
+        }
+        self.music.rescale(ratio);
+        self.spec = AudioSpec::new(spec.frequency(), 1, spec.format());
+        self.output_channels = output_channels;
+        let capture_spec = self.capture_spec();
+        self.capture
+            .take_if(|capture| *capture.spec() != capture_spec)
+    }
+
+    /// Returns the number of [`AudioChannel`]s, which is the maximum number of sounds that can be
+    /// playing simultaneously.
+    #[inline]
+    pub fn num_channels(&self) -> usize {
+        self.channels.len()
+    }
+
     /// Returns true if any of the audio channels are currently playing, false otherwise.
     #[inline]
     pub fn is_playing(&self) -> bool {
@@ -243,7 +679,7 @@ impl AudioDevice {
     /// Stops the specified channel's playback, or does nothing if that channel was not currently
     /// playing. This does not affect the channel's other state (data buffer, etc).
     pub fn stop_channel(&mut self, channel_index: usize) -> Result<(), AudioDeviceError> {
-        if channel_index >= NUM_CHANNELS {
+        if channel_index >= self.channels.len() {
             Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
         } else {
             self.channels[channel_index].stop();
@@ -258,27 +694,94 @@ impl AudioDevice {
         }
     }
 
+    /// Returns the index of the first channel that is not currently playing. If all channels are
+    /// playing, returns the index of the channel playing the lowest priority sound, as long as its
+    /// priority is not higher than the given priority.
+    fn find_channel(&self, priority: Option<u8>) -> Option<usize> {
+        if let Some(index) = self.channels.iter().position(|channel| !channel.playing) {
+            return Some(index);
+        }
+        let priority = priority?;
+        self.channels
+            .iter()
+            .enumerate()
+            .filter(|(_, channel)| channel.priority <= priority)
+            .min_by_key(|(_, channel)| channel.priority)
+            .map(|(index, _)| index)
+    }
+
+    fn play_buffer_at(
+        &mut self,
+        channel_index: usize,
+        buffer: &AudioBuffer,
+        loops: bool,
+        priority: u8,
+    ) -> Result<(), AudioDeviceError> {
+        let frequency = self.spec.frequency();
+        let channel = &mut self.channels[channel_index];
+        if *buffer.spec() == self.spec {
+            channel.play_buffer(buffer, loops);
+        } else {
+            channel.play_converted_buffer(buffer, frequency, loops)?;
+        }
+        channel.priority = priority;
+        Ok(())
+    }
+
     /// Tries to play the given [`AudioBuffer`] on the first channel found that is not already
     /// playing. If a free channel is found, playback will be started by copying the buffer's
-    /// contents to the channel. The index of the channel is returned. If playback was not started
-    /// because no channel is free currently, then `None` is returned.
+    /// contents to the channel (converting and resampling it if needed). The index of the channel
+    /// is returned. If playback was not started because no channel is free currently, then `None`
+    /// is returned.
     pub fn play_buffer(
         &mut self,
         buffer: &AudioBuffer,
         loops: bool,
     ) -> Result<Option<usize>, AudioDeviceError> {
-        if *buffer.spec() != self.spec {
-            Err(AudioDeviceError::AudioSpecMismatch)
+        check_playable_spec(buffer.spec())?;
+        if let Some(index) = self.find_channel(None) {
+            self.play_buffer_at(index, buffer, loops, 0)?;
+            Ok(Some(index))
         } else {
-            if let Some((index, channel)) = self.stopped_channels_iter_mut().enumerate().next() {
-                channel.play_buffer(buffer, loops);
-                Ok(Some(index))
-            } else {
-                Ok(None)
-            }
+            Ok(None)
+        }
+    }
+
+    /// Same as [`AudioDevice::play_buffer`], except that if all channels are busy, the sound
+    /// playing with the lowest priority will be interrupted to play this buffer instead, as long
+    /// as that sound's priority is not higher than the given priority. If playback was not started
+    /// because all channels are playing higher priority sounds, then `None` is returned.
+    pub fn play_buffer_with_priority(
+        &mut self,
+        buffer: &AudioBuffer,
+        loops: bool,
+        priority: u8,
+    ) -> Result<Option<usize>, AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        if let Some(index) = self.find_channel(Some(priority)) {
+            self.play_buffer_at(index, buffer, loops, priority)?;
+            Ok(Some(index))
+        } else {
+            Ok(None)
         }
     }
 
+    /// Same as [`AudioDevice::play_buffer`], except that the buffer is played at the given pitch
+    /// (playback rate), where 1.0 is "normal". Useful for adding some variation to frequently
+    /// played sounds.
+    pub fn play_buffer_with_pitch(
+        &mut self,
+        buffer: &AudioBuffer,
+        loops: bool,
+        pitch: f32,
+    ) -> Result<Option<usize>, AudioDeviceError> {
+        let index = self.play_buffer(buffer, loops)?;
+        if let Some(index) = index {
+            self.channels[index].pitch = pitch;
+        }
+        Ok(index)
+    }
+
     /// Plays the given [`AudioBuffer`] on the specified channel. Whatever that channel was playing
     /// will be interrupted and replaced with a copy of the given buffer's data.
     pub fn play_buffer_on_channel(
@@ -287,13 +790,11 @@ impl AudioDevice {
         buffer: &AudioBuffer,
         loops: bool,
     ) -> Result<(), AudioDeviceError> {
-        if *buffer.spec() != self.spec {
-            Err(AudioDeviceError::AudioSpecMismatch)
-        } else if channel_index >= NUM_CHANNELS {
+        check_playable_spec(buffer.spec())?;
+        if channel_index >= self.channels.len() {
             Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
         } else {
-            self.channels[channel_index].play_buffer(buffer, loops);
-            Ok(())
+            self.play_buffer_at(channel_index, buffer, loops, 0)
         }
     }
 
@@ -306,8 +807,10 @@ impl AudioDevice {
         generator: Box<dyn AudioGenerator>,
         loops: bool,
     ) -> Result<Option<usize>, AudioDeviceError> {
-        if let Some((index, channel)) = self.stopped_channels_iter_mut().enumerate().next() {
+        if let Some(index) = self.find_channel(None) {
+            let channel = &mut self.channels[index];
             channel.play_generator(generator, loops);
+            channel.priority = 0;
             Ok(Some(index))
         } else {
             Ok(None)
@@ -322,14 +825,192 @@ impl AudioDevice {
         generator: Box<dyn AudioGenerator>,
         loops: bool,
     ) -> Result<(), AudioDeviceError> {
-        if channel_index >= NUM_CHANNELS {
+        if channel_index >= self.channels.len() {
             Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
         } else {
-            self.channels[channel_index].play_generator(generator, loops);
+            let channel = &mut self.channels[channel_index];
+            channel.play_generator(generator, loops);
+            channel.priority = 0;
             Ok(())
         }
     }
 
+    /// Gradually changes the specified channel's volume to the given volume as it plays. See
+    /// [`AudioChannel::fade_to`].
+    pub fn fade_channel(
+        &mut self,
+        channel_index: usize,
+        volume: f32,
+        ticks: usize,
+    ) -> Result<(), AudioDeviceError> {
+        if channel_index >= self.channels.len() {
+            Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
+        } else {
+            self.channels[channel_index].fade_to(volume, ticks);
+            Ok(())
+        }
+    }
+
+    /// Gradually lowers the specified channel's volume to silence, and then stops its playback.
+    /// See [`AudioChannel::fade_out`].
+    pub fn fade_out_channel(
+        &mut self,
+        channel_index: usize,
+        ticks: usize,
+    ) -> Result<(), AudioDeviceError> {
+        if channel_index >= self.channels.len() {
+            Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
+        } else {
+            self.channels[channel_index].fade_out(ticks);
+            Ok(())
+        }
+    }
+
+    /// Begins playing the given [`MusicTrack`], replacing any music that was already playing.
+    ///
+    /// # Arguments
+    ///
+    /// * `track`: the music to play
+    /// * `crossfade`: how long to take to fade from the music that was already playing to the new
+    ///   track. if zero, the new track replaces it immediately
+    pub fn play_music(&mut self, track: MusicTrack, crossfade: Duration) {
+        let frequency = self.spec.frequency();
+        self.music
+            .play(track, frequency, duration_to_samples(crossfade, frequency));
+    }
+
+    /// Stops playing music, fading it out over the given duration first if it is not zero.
+    pub fn stop_music(&mut self, fade_out: Duration) {
+        let frequency = self.spec.frequency();
+        self.music.stop(duration_to_samples(fade_out, frequency));
+    }
+
+    /// Returns true if a [`MusicTrack`] is currently playing (not counting any music that is
+    /// still fading out after being stopped).
+    #[inline]
+    pub fn is_music_playing(&self) -> bool {
+        self.music.is_playing()
+    }
+
+    /// Queues the given [`Beep`] to be played by the PC speaker-style square wave beeper once any
+    /// beeps queued before it have finished playing.
+    pub fn beep(&mut self, beep: Beep) {
+        self.beeper.beep(beep);
+    }
+
+    /// Stops the beeper, discarding any beeps that were queued.
+    pub fn stop_beeper(&mut self) {
+        self.beeper.stop();
+    }
+
+    /// Returns true if the beeper is currently playing a [`Beep`].
+    #[inline]
+    pub fn is_beeping(&self) -> bool {
+        self.beeper.is_beeping()
+    }
+
+    /// Returns the number of sample frames that have been mixed since this device was created.
+    /// This is the clock that sounds are scheduled against with [`AudioDevice::schedule_buffer`].
+    /// Note that the frames most recently mixed are not actually heard until the audio device's
+    /// buffer has finished playing, see [`Audio::output_latency`].
+    #[inline]
+    pub fn sample_clock(&self) -> u64 {
+        self.sample_clock
+    }
+
+    /// Schedules a copy of the given [`AudioBuffer`] to start playing exactly when the sample
+    /// clock (see [`AudioDevice::sample_clock`]) reaches the given sample frame, rather than
+    /// whenever the audio device next happens to mix a new buffer. Sounds scheduled for a sample
+    /// frame that has already been mixed start playing straight away. As with
+    /// [`AudioDevice::play_buffer`], the sound is played on the first channel that is not already
+    /// playing, and nothing is played if all channels are busy at that time.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer`: the audio to play
+    /// * `loops`: whether the sound should loop
+    /// * `at`: the sample frame to start playing at
+    pub fn schedule_buffer(
+        &mut self,
+        buffer: &AudioBuffer,
+        loops: bool,
+        at: u64,
+    ) -> Result<(), AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        // kept sorted by start time, with sounds scheduled for the same time kept in the order
+        // they were scheduled in
+        let index = self.scheduled.partition_point(|scheduled| scheduled.at <= at);
+        self.scheduled.insert(
+            index,
+            ScheduledSound {
+                at,
+                buffer: buffer.clone(),
+                loops,
+            },
+        );
+        Ok(())
+    }
+
+    /// Same as [`AudioDevice::schedule_buffer`], except that the sound is scheduled to start
+    /// playing after the given delay, measured from the current sample clock.
+    pub fn schedule_buffer_after(
+        &mut self,
+        buffer: &AudioBuffer,
+        loops: bool,
+        delay: Duration,
+    ) -> Result<(), AudioDeviceError> {
+        let at = self.sample_clock + duration_to_samples(delay, self.spec.frequency()) as u64;
+        self.schedule_buffer(buffer, loops, at)
+    }
+
+    /// Returns the number of scheduled sounds that have not started playing yet.
+    #[inline]
+    pub fn num_scheduled(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    /// Discards all scheduled sounds that have not started playing yet.
+    pub fn cancel_scheduled(&mut self) {
+        self.scheduled.clear();
+    }
+
+    /// Starts playing any scheduled sounds that are due to start at the current sample frame.
+    fn start_scheduled(&mut self) {
+        while self
+            .scheduled
+            .front()
+            .is_some_and(|scheduled| scheduled.at <= self.sample_clock)
+        {
+            if let Some(scheduled) = self.scheduled.pop_front() {
+                // the buffer's spec was already checked when it was scheduled
+                let _ = self.play_buffer(&scheduled.buffer, scheduled.loops);
+            }
+        }
+    }
+
+    /// Returns the spec of the final mixed audio output, as written out by captures.
+    pub fn capture_spec(&self) -> AudioSpec {
+        AudioSpec::new(self.spec.frequency(), self.output_channels, AudioFormat::U8)
+    }
+
+    /// Begins capturing the final mixed audio output with the given capture, returning any
+    /// capture that was previously in progress so that it can be finished by the caller.
+    pub(crate) fn replace_capture(&mut self, capture: AudioCapture) -> Option<AudioCapture> {
+        self.capture.replace(capture)
+    }
+
+    /// Stops capturing the final mixed audio output, returning the capture that was in progress
+    /// (if any) so that it can be finished by the caller once the audio device is unlocked.
+    pub(crate) fn take_capture(&mut self) -> Option<AudioCapture> {
+        self.capture.take()
+    }
+
+    /// Returns true if the final mixed audio output is currently being written to a WAV file.
+    #[inline]
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
     /// Returns an iterator of any [`AudioChannel`]s that are currently playing.
     #[inline]
     pub fn playing_channels_iter(&mut self) -> impl Iterator<Item = &AudioChannel> {
@@ -400,3 +1081,263 @@ impl IndexMut<usize> for AudioDevice {
         self.get_mut(index).unwrap()
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    fn mono_spec() -> AudioSpec {
+        AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 1, AudioFormat::U8)
+    }
+
+    fn buffer_with(spec: AudioSpec, data: &[u8]) -> AudioBuffer {
+        let mut buffer = AudioBuffer::new(spec);
+        buffer.data.extend_from_slice(data);
+        buffer
+    }
+
+    #[test]
+    pub fn mixing_channels() {
+        let mut device = AudioDevice::with_channels(mono_spec(), 4, 1);
+        assert_eq!(4, device.num_channels());
+        assert_eq!(
+            Some(0),
+            assert_ok!(device.play_buffer(&buffer_with(mono_spec(), &[138, 138]), false))
+        );
+        assert_eq!(
+            Some(1),
+            assert_ok!(device.play_buffer(&buffer_with(mono_spec(), &[148]), false))
+        );
+
+        let mut out = [0u8; 3];
+        device.callback(&mut out);
+        assert_eq!([158, 138, 128], out);
+        assert!(!device.is_playing());
+    }
+
+    #[test]
+    pub fn stereo_panning() {
+        let mut device = AudioDevice::with_channels(mono_spec(), 2, 2);
+        assert_ok!(device.play_buffer_on_channel(1, &buffer_with(mono_spec(), &[188]), false));
+        device[1].pan = -1.0;
+
+        let mut out = [0u8; 4];
+        device.callback(&mut out);
+        assert_eq!([188, 128], out[0..2]);
+
+        device[1].pan = 0.5;
+        device[1].play(false);
+        device.callback(&mut out);
+        assert_eq!([158, 188], out[0..2]);
+        assert_eq!([128, 128], out[2..4]);
+    }
+
+    #[test]
+    pub fn channel_priorities() {
+        let mut device = AudioDevice::with_channels(mono_spec(), 2, 1);
+        let buffer = buffer_with(mono_spec(), &[128; 16]);
+        assert_eq!(
+            Some(0),
+            assert_ok!(device.play_buffer_with_priority(&buffer, false, 5))
+        );
+        assert_eq!(
+            Some(1),
+            assert_ok!(device.play_buffer_with_priority(&buffer, false, 2))
+        );
+        assert_none!(assert_ok!(device.play_buffer(&buffer, false)));
+        assert_none!(assert_ok!(
+            device.play_buffer_with_priority(&buffer, false, 1)
+        ));
+        assert_eq!(
+            Some(1),
+            assert_ok!(device.play_buffer_with_priority(&buffer, false, 3))
+        );
+        assert_eq!(3, device[1].priority);
+
+        device.stop_channel(0).unwrap();
+        assert_eq!(Some(0), assert_ok!(device.play_buffer(&buffer, false)));
+        assert_matches!(
+            device.stop_channel(2),
+            Err(AudioDeviceError::ChannelIndexOutOfRange(2))
+        );
+    }
+
+    #[test]
+    pub fn resampling_buffers() {
+        let mut device = AudioDevice::with_channels(mono_spec(), 1, 1);
+        let spec = AudioSpec::new(AUDIO_FREQUENCY_11KHZ, 1, AudioFormat::U8);
+        assert_ok!(device.play_buffer(&buffer_with(spec, &[130, 140]), false));
+
+        let mut out = [0u8; 5];
+        device.callback(&mut out);
+        assert_eq!([130, 130, 140, 140, 128], out);
+    }
+
+    #[test]
+    pub fn converting_buffers() {
+        let mut device = AudioDevice::with_channels(mono_spec(), 1, 1);
+        let spec = AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 2, AudioFormat::S16LSB);
+        let mut data = Vec::new();
+        for sample in [0x1000i16, 0x3000, -0x4000, -0x4000] {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        assert_ok!(device.play_buffer(&buffer_with(spec, &data), false));
+        assert_eq!([160, 64], device[0].data[..]);
+
+        let spec = AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 1, AudioFormat::F32LSB);
+        assert_matches!(
+            device.play_buffer(&buffer_with(spec, &[0; 4]), false),
+            Err(AudioDeviceError::UnsupportedAudioFormat(
+                AudioFormat::F32LSB
+            ))
+        );
+    }
+
+    fn play_channel(channel: &mut AudioChannel, count: usize) -> Vec<Option<i16>> {
+        (0..count).map(|_| channel.sample()).collect()
+    }
+
+    #[test]
+    pub fn channel_pitch_and_loop_region() {
+        let mut channel = AudioChannel::new();
+        channel.play_buffer(&buffer_with(mono_spec(), &[129, 130, 131, 132, 133]), false);
+        channel.pitch = 2.0;
+        assert_eq!(
+            vec![Some(1), Some(3), Some(5), None],
+            play_channel(&mut channel, 4)
+        );
+        assert!(!channel.playing);
+
+        channel.play_buffer(&buffer_with(mono_spec(), &[129, 130, 131, 132, 133]), true);
+        assert_eq!(1.0, channel.pitch);
+        channel.loop_start = 1;
+        channel.loop_end = Some(3);
+        assert_eq!(
+            vec![Some(1), Some(2), Some(3), Some(2), Some(3), Some(2)],
+            play_channel(&mut channel, 6)
+        );
+
+        channel.loop_end = None;
+        channel.pitch = 0.5;
+        assert_eq!(
+            vec![
+                Some(3),
+                Some(3),
+                Some(4),
+                Some(4),
+                Some(5),
+                Some(5),
+                Some(2)
+            ],
+            play_channel(&mut channel, 7)
+        );
+        assert!(channel.playing);
+    }
+
+    #[test]
+    pub fn channel_fades() {
+        let mut channel = AudioChannel::new();
+        channel.play_buffer(&buffer_with(mono_spec(), &[228; 8]), true);
+        channel.fade_out(4);
+        assert!(channel.is_fading());
+        assert_eq!(
+            vec![Some(100), Some(75), Some(50), Some(25), None],
+            play_channel(&mut channel, 5)
+        );
+        assert!(!channel.playing);
+        assert!(!channel.is_fading());
+
+        channel.play(true);
+        channel.fade_in(0.5, 2);
+        assert_eq!(
+            vec![Some(0), Some(25), Some(50), Some(50)],
+            play_channel(&mut channel, 4)
+        );
+        channel.fade_to(1.0, 0);
+        assert_eq!(vec![Some(100)], play_channel(&mut channel, 1));
+
+        let mut device = AudioDevice::with_channels(mono_spec(), 2, 1);
+        assert_matches!(
+            device.fade_out_channel(2, 10),
+            Err(AudioDeviceError::ChannelIndexOutOfRange(2))
+        );
+    }
+
+    #[test]
+    pub fn changing_output_spec() {
+        let mut device = AudioDevice::with_channels(mono_spec(), 2, 1);
+        assert_ok!(device.play_buffer(&buffer_with(mono_spec(), &[138, 148]), false));
+
+        let mut out = [0u8; 1];
+        device.callback(&mut out);
+        assert_eq!([138], out);
+
+        // the rest of the buffer should now be played at the new frequency, and in stereo
+        assert!(device
+            .set_output_spec(AudioSpec::new(AUDIO_FREQUENCY_44KHZ, 2, AudioFormat::U8), 2)
+            .is_none());
+        assert_eq!(AUDIO_FREQUENCY_44KHZ, device.spec().frequency());
+        assert_eq!(1, device.spec().channels());
+        assert_eq!(2, device.output_channels());
+        let mut out = [0u8; 6];
+        device.callback(&mut out);
+        assert_eq!([148, 148, 148, 148, 128, 128], out);
+    }
+
+    #[test]
+    pub fn capturing_output() -> Result<(), WavError> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = tmp_dir.path().join("capture.wav");
+
+        let mut device = AudioDevice::with_channels(mono_spec(), 2, 2);
+        assert_ok!(device.play_buffer(&buffer_with(mono_spec(), &[138, 148]), false));
+        let capture = AudioCapture::start(&path, device.capture_spec(), 6)?;
+        assert!(device.replace_capture(capture).is_none());
+        assert!(device.is_capturing());
+        let mut out = [0u8; 6];
+        device.callback(&mut out);
+        if let Some(capture) = device.take_capture() {
+            capture.finish()?;
+        }
+        assert!(!device.is_capturing());
+        device.callback(&mut out);
+
+        let captured = AudioBuffer::load_wav_file(&path)?;
+        assert_eq!(
+            AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 2, AudioFormat::U8),
+            *captured.spec()
+        );
+        assert_eq!(vec![138, 138, 148, 148, 128, 128], captured.data);
+        Ok(())
+    }
+
+    #[test]
+    pub fn scheduled_sounds() {
+        let mut device = AudioDevice::with_channels(mono_spec(), 2, 1);
+        assert_ok!(device.schedule_buffer(&buffer_with(mono_spec(), &[148]), false, 5));
+        assert_ok!(device.schedule_buffer(&buffer_with(mono_spec(), &[138]), false, 2));
+        assert_eq!(2, device.num_scheduled());
+
+        let mut out = [0u8; 4];
+        device.callback(&mut out);
+        assert_eq!([128, 128, 138, 128], out);
+        assert_eq!(4, device.sample_clock());
+        assert_eq!(1, device.num_scheduled());
+
+        // scheduled sounds that are already due start with the next frame mixed
+        assert_ok!(device.schedule_buffer(&buffer_with(mono_spec(), &[138]), false, 0));
+        device.callback(&mut out);
+        assert_eq!([138, 148, 128, 128], out);
+        assert_eq!(0, device.num_scheduled());
+
+        assert_ok!(device.schedule_buffer_after(
+            &buffer_with(mono_spec(), &[138]),
+            false,
+            Duration::from_secs(1)
+        ));
+        device.cancel_scheduled();
+        assert_eq!(0, device.num_scheduled());
+    }
+}
@@ -1,18 +1,34 @@
 use std::fmt::Formatter;
+use std::path::Path;
+use std::time::Duration;
 
 use sdl2::audio::{AudioFormat, AudioFormatNum, AudioSpecDesired};
+use sdl2::event::Event;
 use sdl2::AudioSubsystem;
 use thiserror::Error;
 
+pub use self::beeper::*;
 pub use self::buffer::*;
 pub use self::device::*;
+pub use self::music::*;
+#[cfg(feature = "ogg")]
+pub use self::ogg::*;
+pub use self::positional::*;
 pub use self::queue::*;
+pub use self::sfx::*;
 
+pub mod beeper;
 pub mod buffer;
 pub mod device;
+pub mod music;
+#[cfg(feature = "ogg")]
+pub mod ogg;
+pub mod positional;
 pub mod queue;
+pub mod sfx;
 
-/// The number of simultaneously playing audio channels supported by this library currently.
+/// The default number of simultaneously playing audio channels. A different number can be used
+/// via [`Audio::with_channels`].
 pub const NUM_CHANNELS: usize = 8;
 
 pub const AUDIO_FREQUENCY_44KHZ: u32 = 44100;
@@ -25,6 +41,8 @@ pub const SILENCE: u8 = AudioFormatNum::SILENCE;
 pub const TARGET_AUDIO_FREQUENCY: u32 = AUDIO_FREQUENCY_22KHZ;
 /// The number of channels per audio buffer supported by this library currently.
 pub const TARGET_AUDIO_CHANNELS: u8 = 1;
+/// The number of channels the audio device is opened with, so that playing channels can be panned.
+pub const TARGET_AUDIO_OUTPUT_CHANNELS: u8 = 2;
 
 //////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -90,11 +108,29 @@ pub enum AudioError {
     OpenDeviceFailed(String),
 }
 
+/// Returns the names of all of the audio output devices available on the system. Any of these can
+/// be passed to [`Audio::open`] to play audio on that device instead of the default one.
+pub fn audio_output_devices(sdl_audio_subsystem: &AudioSubsystem) -> Vec<String> {
+    let num_devices = sdl_audio_subsystem
+        .num_audio_playback_devices()
+        .unwrap_or(0);
+    (0..num_devices)
+        .filter_map(|index| sdl_audio_subsystem.audio_playback_device_name(index).ok())
+        .collect()
+}
+
 /// Top-level abstraction over the system's audio output device. To play audio or change other
 /// playback properties, you will need to lock the audio device via [`Audio::lock`] to obtain an
 /// [`AudioDevice`].
 pub struct Audio {
     spec: AudioSpec,
+    num_channels: usize,
+    output_channels: u8,
+    buffer_size: u16,
+    device_name: Option<String>,
+    desired_spec: AudioSpecDesired,
+    known_devices: Vec<String>,
+    sdl_audio_subsystem: AudioSubsystem,
     sdl_audio_device: sdl2::audio::AudioDevice<AudioDevice>,
 }
 
@@ -102,13 +138,52 @@ impl std::fmt::Debug for Audio {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Audio")
             .field("spec", &self.spec)
+            .field("num_channels", &self.num_channels)
+            .field("output_channels", &self.output_channels)
+            .field("buffer_size", &self.buffer_size)
+            .field("device_name", &self.device_name)
             .finish_non_exhaustive()
     }
 }
 
+/// Opens the named audio output device (or the default one), returning it along with the spec
+/// that audio will actually be mixed at and the spec SDL actually opened the device with.
+fn open_sdl_audio_device(
+    device_name: Option<&str>,
+    desired_spec: &AudioSpecDesired,
+    num_channels: usize,
+    sdl_audio_subsystem: &AudioSubsystem,
+) -> Result<(sdl2::audio::AudioDevice<AudioDevice>, AudioSpec), AudioError> {
+    let mut spec = None;
+    let sdl_audio_device =
+        match sdl_audio_subsystem.open_playback(device_name, desired_spec, |opened_spec| {
+            // all channels are mixed together as mono audio, regardless of how many output
+            // channels the device was actually opened with
+            let our_spec = AudioSpec::new(
+                opened_spec.freq as u32,
+                TARGET_AUDIO_CHANNELS,
+                opened_spec.format,
+            );
+            spec = Some(our_spec);
+            AudioDevice::with_channels(our_spec, num_channels, opened_spec.channels)
+        }) {
+            Ok(audio_device) => audio_device,
+            Err(error) => return Err(AudioError::OpenDeviceFailed(error)),
+        };
+
+    if let Some(spec) = spec {
+        Ok((sdl_audio_device, spec))
+    } else {
+        Err(AudioError::OpenDeviceFailed(String::from(
+            "Device initialization failed to set AudioSpec",
+        )))
+    }
+}
+
 impl Audio {
     /// Creates a new [`Audio`] instance, wrapping the given SDL [`sdl2::audio::AudioSubsystem`].
-    /// The `desired_spec` given specifies the target audio playback format.
+    /// The `desired_spec` given specifies the target audio playback format. [`NUM_CHANNELS`]
+    /// audio channels will be available for simultaneous playback.
     ///
     /// Ideally, you should not be creating an instance of this yourself and should just use the
     /// one provided by [`crate::system::System`].
@@ -116,40 +191,180 @@ impl Audio {
         desired_spec: AudioSpecDesired,
         sdl_audio_subsystem: &AudioSubsystem,
     ) -> Result<Self, AudioError> {
-        let mut spec = None;
-        let sdl_audio_device =
-            match sdl_audio_subsystem.open_playback(None, &desired_spec, |opened_spec| {
-                let our_spec = AudioSpec::new(
-                    opened_spec.freq as u32,
-                    opened_spec.channels,
-                    opened_spec.format,
-                );
-                spec = Some(our_spec);
-                AudioDevice::new(our_spec)
-            }) {
-                Ok(audio_device) => audio_device,
-                Err(error) => return Err(AudioError::OpenDeviceFailed(error)),
-            };
-
-        if let Some(spec) = spec {
-            Ok(Audio {
-                spec,
-                sdl_audio_device,
-            })
-        } else {
-            Err(AudioError::OpenDeviceFailed(String::from(
-                "Device initialization failed to set AudioSpec",
-            )))
-        }
+        Self::with_channels(desired_spec, NUM_CHANNELS, sdl_audio_subsystem)
+    }
+
+    /// Creates a new [`Audio`] instance, wrapping the given SDL [`sdl2::audio::AudioSubsystem`]
+    /// and playing on the system's default audio output device.
+    ///
+    /// # Arguments
+    ///
+    /// * `desired_spec`: the target audio playback format. if more than one output channel is
+    ///   requested (and the audio device supports it), playing channels can be panned
+    /// * `num_channels`: the number of audio channels available for simultaneous playback
+    /// * `sdl_audio_subsystem`: the SDL audio subsystem to open the audio device with
+    pub fn with_channels(
+        desired_spec: AudioSpecDesired,
+        num_channels: usize,
+        sdl_audio_subsystem: &AudioSubsystem,
+    ) -> Result<Self, AudioError> {
+        Self::open(None, desired_spec, num_channels, sdl_audio_subsystem)
     }
 
-    /// Returns current audio device's audio specification/format for playback. All [`AudioBuffer`]s
-    /// that are to be used for playback must be converted to match this before they can be played.
+    /// Creates a new [`Audio`] instance, wrapping the given SDL [`sdl2::audio::AudioSubsystem`].
+    ///
+    /// # Arguments
+    ///
+    /// * `device_name`: the name of the audio output device to play on (see
+    ///   [`audio_output_devices`]), or `None` to use the system's default device
+    /// * `desired_spec`: the target audio playback format, including the frequency and buffer
+    ///   size. the values actually obtained may differ and can be checked afterwards
+    /// * `num_channels`: the number of audio channels available for simultaneous playback
+    /// * `sdl_audio_subsystem`: the SDL audio subsystem to open the audio device with
+    pub fn open(
+        device_name: Option<&str>,
+        desired_spec: AudioSpecDesired,
+        num_channels: usize,
+        sdl_audio_subsystem: &AudioSubsystem,
+    ) -> Result<Self, AudioError> {
+        let (sdl_audio_device, spec) = open_sdl_audio_device(
+            device_name,
+            &desired_spec,
+            num_channels,
+            sdl_audio_subsystem,
+        )?;
+        let obtained_spec = *sdl_audio_device.spec();
+        Ok(Audio {
+            spec,
+            num_channels,
+            output_channels: obtained_spec.channels,
+            buffer_size: obtained_spec.samples,
+            device_name: device_name.map(String::from),
+            desired_spec,
+            known_devices: audio_output_devices(sdl_audio_subsystem),
+            sdl_audio_subsystem: sdl_audio_subsystem.clone(),
+            sdl_audio_device,
+        })
+    }
+
+    /// Returns current audio device's audio specification/format for playback. [`AudioBuffer`]s
+    /// that match this can be played the fastest, though other 8-bit and 16-bit buffers are
+    /// converted automatically when played.
     #[inline]
     pub fn spec(&self) -> &AudioSpec {
         &self.spec
     }
 
+    /// Returns the number of audio channels available for simultaneous playback.
+    #[inline]
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    /// Returns the number of channels the audio device is actually outputting (e.g. 1 = mono,
+    /// 2 = stereo).
+    #[inline]
+    pub fn output_channels(&self) -> u8 {
+        self.output_channels
+    }
+
+    /// Returns the size of the audio device's buffer that was actually obtained, in samples.
+    /// Smaller buffers mean lower latency, but need to be mixed more often.
+    #[inline]
+    pub fn buffer_size(&self) -> u16 {
+        self.buffer_size
+    }
+
+    /// Returns how long it takes for audio to be heard after it has been mixed, based on the size
+    /// of the audio device's buffer. The operating system's own audio mixing may add some further
+    /// latency on top of this which cannot be queried. Rhythm games and the like can use this
+    /// together with [`AudioDevice::sample_clock`] to work out which sample frame is actually
+    /// being heard at any given moment.
+    #[inline]
+    pub fn output_latency(&self) -> Duration {
+        Duration::from_secs_f64(self.buffer_size as f64 / self.spec.frequency().max(1) as f64)
+    }
+
+    /// Returns the name of the audio output device that was requested, or `None` if the system's
+    /// default device is being used.
+    #[inline]
+    pub fn device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
+
+    /// Returns the names of all of the audio output devices available on the system.
+    #[inline]
+    pub fn output_devices(&self) -> Vec<String> {
+        audio_output_devices(&self.sdl_audio_subsystem)
+    }
+
+    /// Closes the audio device and opens the given one in its place (or the system's default
+    /// device if `None`), using the same desired playback format as before. Everything that was
+    /// playing carries on playing on the new device. If the new device could not be opened, an
+    /// error is returned and the current device is left as it was.
+    pub fn reopen(&mut self, device_name: Option<&str>) -> Result<(), AudioError> {
+        let (sdl_audio_device, spec) = open_sdl_audio_device(
+            device_name,
+            &self.desired_spec,
+            self.num_channels,
+            &self.sdl_audio_subsystem,
+        )?;
+        let obtained_spec = *sdl_audio_device.spec();
+        let was_playing = self.status() == sdl2::audio::AudioStatus::Playing;
+
+        let old_sdl_audio_device = std::mem::replace(&mut self.sdl_audio_device, sdl_audio_device);
+        let mut device = old_sdl_audio_device.close_and_get_callback();
+        let stopped_capture = device.set_output_spec(spec, obtained_spec.channels);
+        *self.sdl_audio_device.lock() = device;
+        if was_playing {
+            self.resume();
+        }
+        if let Some(capture) = stopped_capture {
+            // there is nowhere to report this from here, but the capture was cut short anyway
+            let _ = capture.finish();
+        }
+
+        self.spec = spec;
+        self.output_channels = obtained_spec.channels;
+        self.buffer_size = obtained_spec.samples;
+        self.device_name = device_name.map(String::from);
+        self.known_devices = audio_output_devices(&self.sdl_audio_subsystem);
+        Ok(())
+    }
+
+    /// Reopens the audio device if needed when audio output devices are added to or removed from
+    /// the system. When playing on the default device, audio moves to whatever the new default
+    /// device is (e.g. headphones that were just plugged in). When playing on a specific device
+    /// that was removed, audio moves to the default device.
+    ///
+    /// This is called automatically by [`crate::system::System`]'s `do_events`.
+    pub fn handle_event(&mut self, event: &Event) -> Result<(), AudioError> {
+        match event {
+            Event::AudioDeviceAdded {
+                iscapture: false, ..
+            }
+            | Event::AudioDeviceRemoved {
+                iscapture: false, ..
+            } => {
+                // SDL also reports all of the devices that were already present when it starts
+                // up, so only reopen if the set of devices actually changed since last time
+                let devices = audio_output_devices(&self.sdl_audio_subsystem);
+                if devices == self.known_devices {
+                    return Ok(());
+                }
+                match self.device_name.clone() {
+                    None => self.reopen(None),
+                    Some(name) if !devices.contains(&name) => self.reopen(None),
+                    Some(_) => {
+                        self.known_devices = devices;
+                        Ok(())
+                    }
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Returns the current status of the audio device (e.g. whether it is paused, stopped, etc).
     #[inline]
     pub fn status(&self) -> sdl2::audio::AudioStatus {
@@ -177,4 +392,39 @@ impl Audio {
     pub fn lock(&mut self) -> sdl2::audio::AudioDeviceLockGuard<AudioDevice> {
         self.sdl_audio_device.lock()
     }
+
+    /// Begins writing all of the final mixed audio output to a WAV file at the given path, until
+    /// [`Audio::stop_capture`] is called. Any capture already in progress is stopped first.
+    /// Combined with a [`crate::graphics::FrameRecorder`], this can be used to capture complete
+    /// gameplay videos (the audio and frames would need to be combined with an external tool).
+    /// Captures are stopped automatically if the audio device is reopened with a different format.
+    pub fn start_capture(&mut self, path: &Path) -> Result<(), WavError> {
+        self.stop_capture()?;
+        let spec = self.lock().capture_spec();
+        let buffer_size = self.buffer_size as usize * self.output_channels.max(1) as usize;
+        let capture = AudioCapture::start(path, spec, buffer_size)?;
+        let previous = self.lock().replace_capture(capture);
+        if let Some(previous) = previous {
+            previous.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Stops the capture started by [`Audio::start_capture`], completing the WAV file. The file
+    /// is written on a separate thread as audio is captured, so this waits for any remaining
+    /// captured audio to be written first (without keeping the audio device locked meanwhile).
+    /// Returns any error that occurred while writing the captured audio.
+    pub fn stop_capture(&mut self) -> Result<(), WavError> {
+        let capture = self.lock().take_capture();
+        match capture {
+            Some(capture) => capture.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if the final mixed audio output is currently being written to a WAV file.
+    #[inline]
+    pub fn is_capturing(&mut self) -> bool {
+        self.lock().is_capturing()
+    }
 }
@@ -0,0 +1,383 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::audio::*;
+
+#[derive(Error, Debug)]
+pub enum AudioStreamError {
+    #[error("Bad or unsupported audio stream: {0}")]
+    BadData(String),
+
+    #[error("Audio stream I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+/// A source of audio that is decoded incrementally while it is being played, instead of all being
+/// loaded into memory up-front like an [`AudioBuffer`]. Streams are decoded on the audio thread
+/// as they are played via [`AudioDevice::play_music`].
+pub trait AudioStream: Send {
+    /// Returns the frequency of the audio decoded by this stream. It is automatically resampled
+    /// to the audio device's frequency as it plays.
+    fn frequency(&self) -> u32;
+
+    /// Decodes and returns the next sample frame, mixed down to a single signed 16-bit mono
+    /// sample. `None` is returned once the end of the stream has been reached.
+    fn next_frame(&mut self) -> Result<Option<i16>, AudioStreamError>;
+
+    /// Moves the stream to the given sample frame, where 0 is the start of the stream.
+    fn seek(&mut self, frame: u64) -> Result<(), AudioStreamError>;
+}
+
+/// An [`AudioStream`] that plays audio already loaded into an [`AudioBuffer`]. Useful for
+/// shorter music tracks, or to use the looping and cross-fading of [`MusicTrack`]s with audio
+/// loaded from formats that cannot be streamed.
+#[derive(Debug, Clone)]
+pub struct BufferStream {
+    frequency: u32,
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl BufferStream {
+    /// Creates a new [`BufferStream`] that plays a copy of the given buffer's audio data. Returns
+    /// an error if the buffer is not in an audio format that can be played.
+    pub fn new(buffer: &AudioBuffer) -> Result<Self, AudioDeviceError> {
+        let mut data = Vec::new();
+        copy_as_mixer_samples(buffer, &mut data)?;
+        Ok(BufferStream {
+            frequency: buffer.spec().frequency(),
+            data,
+            position: 0,
+        })
+    }
+}
+
+impl AudioStream for BufferStream {
+    fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    fn next_frame(&mut self) -> Result<Option<i16>, AudioStreamError> {
+        if let Some(&sample) = self.data.get(self.position) {
+            self.position += 1;
+            Ok(Some((sample as i16 - 128) << 8))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<(), AudioStreamError> {
+        self.position = (frame as usize).min(self.data.len());
+        Ok(())
+    }
+}
+
+/// A piece of music to be played via [`AudioDevice::play_music`], streamed from an
+/// [`AudioStream`] as it plays. Only one music track plays at a time, separately from (and mixed
+/// together with) the sounds playing on [`AudioChannel`]s.
+pub struct MusicTrack {
+    stream: Box<dyn AudioStream>,
+    /// Whether this track loops back to `loop_start` once it reaches `loop_end`. If not, playback
+    /// stops at the end of the track.
+    pub loops: bool,
+    /// The sample frame that playback jumps back to when looping. 0 is the start of the track.
+    pub loop_start: u64,
+    /// The sample frame that playback loops from, or `None` to loop from the end of the track.
+    pub loop_end: Option<u64>,
+}
+
+impl std::fmt::Debug for MusicTrack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MusicTrack")
+            .field("frequency", &self.stream.frequency())
+            .field("loops", &self.loops)
+            .field("loop_start", &self.loop_start)
+            .field("loop_end", &self.loop_end)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MusicTrack {
+    /// Creates a new [`MusicTrack`] which plays the given stream, looping over the whole track
+    /// if `loops` is true.
+    pub fn new(stream: Box<dyn AudioStream>, loops: bool) -> Self {
+        MusicTrack {
+            stream,
+            loops,
+            loop_start: 0,
+            loop_end: None,
+        }
+    }
+
+    /// Creates a new [`MusicTrack`] which plays a copy of the given buffer's audio data, looping
+    /// over the whole track if `loops` is true.
+    pub fn from_buffer(buffer: &AudioBuffer, loops: bool) -> Result<Self, AudioDeviceError> {
+        Ok(Self::new(Box::new(BufferStream::new(buffer)?), loops))
+    }
+
+    /// Returns the frequency of the music's audio data.
+    #[inline]
+    pub fn frequency(&self) -> u32 {
+        self.stream.frequency()
+    }
+}
+
+/// A [`MusicTrack`] that is currently being played, tracking the playback position and
+/// resampling it to the audio device's frequency.
+struct PlayingTrack {
+    track: MusicTrack,
+    position: u64,
+    sample: Option<i16>,
+    sample_step: f32,
+    position_fraction: f32,
+}
+
+impl PlayingTrack {
+    fn new(track: MusicTrack, output_frequency: u32) -> Self {
+        let sample_step = track.frequency() as f32 / output_frequency.max(1) as f32;
+        let mut playing = PlayingTrack {
+            track,
+            position: 0,
+            sample: None,
+            sample_step,
+            position_fraction: 0.0,
+        };
+        playing.sample = playing.read_frame();
+        playing
+    }
+
+    fn seek_to_loop_start(&mut self) -> bool {
+        let loop_start = self.track.loop_start;
+        if self.track.stream.seek(loop_start).is_err() {
+            return false;
+        }
+        self.position = loop_start;
+        true
+    }
+
+    /// Reads the next sample frame from the stream, looping if needed. Stream errors simply end
+    /// playback, as there is nothing else that could be done about them on the audio thread.
+    fn read_frame(&mut self) -> Option<i16> {
+        if self.track.loops
+            && self
+                .track
+                .loop_end
+                .is_some_and(|loop_end| self.position >= loop_end)
+            && !self.seek_to_loop_start()
+        {
+            return None;
+        }
+        match self.track.stream.next_frame() {
+            Ok(Some(sample)) => {
+                self.position += 1;
+                Some(sample)
+            }
+            // only loop if some audio was played since the last time, so that an empty loop
+            // region does not spin forever
+            Ok(None) if self.track.loops && self.position > self.track.loop_start => {
+                if !self.seek_to_loop_start() {
+                    return None;
+                }
+                match self.track.stream.next_frame() {
+                    Ok(Some(sample)) => {
+                        self.position += 1;
+                        Some(sample)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn is_finished(&self) -> bool {
+        self.sample.is_none()
+    }
+
+    /// Returns the next sample to be played, or `None` if the track has finished playing.
+    fn next_sample(&mut self) -> Option<i16> {
+        let sample = self.sample?;
+        self.position_fraction += self.sample_step;
+        while self.position_fraction >= 1.0 && self.sample.is_some() {
+            self.position_fraction -= 1.0;
+            self.sample = self.read_frame();
+        }
+        Some(sample)
+    }
+}
+
+/// Plays [`MusicTrack`]s, cross-fading between them as they are changed. Owned and mixed by
+/// [`AudioDevice`].
+pub(crate) struct MusicPlayer {
+    current: Option<PlayingTrack>,
+    fading_out: Option<PlayingTrack>,
+    fade_length: usize,
+    fade_position: usize,
+}
+
+impl std::fmt::Debug for MusicPlayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MusicPlayer")
+            .field(
+                "current",
+                &self.current.as_ref().map(|playing| &playing.track),
+            )
+            .field("fade_length", &self.fade_length)
+            .field("fade_position", &self.fade_position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MusicPlayer {
+    pub fn new() -> Self {
+        MusicPlayer {
+            current: None,
+            fading_out: None,
+            fade_length: 0,
+            fade_position: 0,
+        }
+    }
+
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.current
+            .as_ref()
+            .is_some_and(|current| !current.is_finished())
+    }
+
+    fn start_fade(&mut self, fade_length: usize) {
+        self.fading_out = if fade_length > 0 {
+            self.current.take()
+        } else {
+            None
+        };
+        self.fade_length = fade_length;
+        self.fade_position = 0;
+    }
+
+    pub fn play(&mut self, track: MusicTrack, output_frequency: u32, crossfade_length: usize) {
+        self.start_fade(crossfade_length);
+        self.current = Some(PlayingTrack::new(track, output_frequency));
+    }
+
+    pub fn stop(&mut self, fade_length: usize) {
+        self.start_fade(fade_length);
+        self.current = None;
+    }
+
+    /// Multiplies the resampling step of any tracks playing by the given ratio, for when the
+    /// output frequency changes while they are playing.
+    pub fn rescale(&mut self, ratio: f32) {
+        for playing in self.current.iter_mut().chain(self.fading_out.iter_mut()) {
+            playing.sample_step *= ratio;
+        }
+    }
+
+    /// Returns the next sample of music to be mixed in, in the same range as [`AudioChannel`]
+    /// samples (-128 to 127), or `None` if no music is playing.
+    pub fn sample(&mut self) -> Option<f32> {
+        if self.fade_position >= self.fade_length
+            || self
+                .fading_out
+                .as_ref()
+                .is_some_and(PlayingTrack::is_finished)
+        {
+            self.fading_out = None;
+        }
+        if self.current.as_ref().is_some_and(PlayingTrack::is_finished) {
+            self.current = None;
+        }
+        if self.current.is_none() && self.fading_out.is_none() {
+            return None;
+        }
+
+        let fade_in = if self.fade_position < self.fade_length {
+            self.fade_position += 1;
+            self.fade_position as f32 / self.fade_length as f32
+        } else {
+            1.0
+        };
+
+        let mut mixed = 0.0;
+        if let Some(sample) = self.current.as_mut().and_then(PlayingTrack::next_sample) {
+            mixed += sample as f32 * fade_in;
+        }
+        if let Some(sample) = self.fading_out.as_mut().and_then(PlayingTrack::next_sample) {
+            mixed += sample as f32 * (1.0 - fade_in);
+        }
+        Some(mixed / 256.0)
+    }
+}
+
+/// Returns the number of samples played over the given duration at the given frequency.
+#[inline]
+pub(crate) fn duration_to_samples(duration: Duration, frequency: u32) -> usize {
+    (duration.as_secs_f64() * frequency as f64) as usize
+}
+
+#[cfg(test)]
+pub mod tests {
+    use sdl2::audio::AudioFormat;
+
+    use super::*;
+
+    fn buffer_with(frequency: u32, data: &[u8]) -> AudioBuffer {
+        let mut buffer = AudioBuffer::new(AudioSpec::new(frequency, 1, AudioFormat::U8));
+        buffer.data.extend_from_slice(data);
+        buffer
+    }
+
+    fn play(player: &mut MusicPlayer, count: usize) -> Vec<f32> {
+        (0..count).map_while(|_| player.sample()).collect()
+    }
+
+    #[test]
+    pub fn looping_music() {
+        let mut player = MusicPlayer::new();
+        let buffer = buffer_with(AUDIO_FREQUENCY_22KHZ, &[129, 130, 131, 132]);
+        player.play(
+            MusicTrack::from_buffer(&buffer, false).unwrap(),
+            AUDIO_FREQUENCY_22KHZ,
+            0,
+        );
+        assert_eq!(vec![1.0, 2.0, 3.0, 4.0], play(&mut player, 10));
+        assert!(!player.is_playing());
+
+        let mut track = MusicTrack::from_buffer(&buffer, true).unwrap();
+        track.loop_start = 1;
+        track.loop_end = Some(3);
+        player.play(track, AUDIO_FREQUENCY_22KHZ, 0);
+        assert_eq!(vec![1.0, 2.0, 3.0, 2.0, 3.0, 2.0], play(&mut player, 6));
+        assert!(player.is_playing());
+
+        let track = MusicTrack::from_buffer(&buffer, true).unwrap();
+        player.play(track, AUDIO_FREQUENCY_11KHZ, 0);
+        assert_eq!(vec![1.0, 3.0, 1.0, 3.0], play(&mut player, 4));
+    }
+
+    #[test]
+    pub fn crossfading_music() {
+        let mut player = MusicPlayer::new();
+        let first = buffer_with(AUDIO_FREQUENCY_22KHZ, &[228; 8]);
+        let second = buffer_with(AUDIO_FREQUENCY_22KHZ, &[28; 8]);
+        player.play(
+            MusicTrack::from_buffer(&first, true).unwrap(),
+            AUDIO_FREQUENCY_22KHZ,
+            0,
+        );
+        assert_eq!(vec![100.0, 100.0], play(&mut player, 2));
+
+        player.play(
+            MusicTrack::from_buffer(&second, true).unwrap(),
+            AUDIO_FREQUENCY_22KHZ,
+            4,
+        );
+        assert_eq!(vec![50.0, 0.0, -50.0, -100.0, -100.0], play(&mut player, 5));
+
+        player.stop(2);
+        assert!(!player.is_playing());
+        assert_eq!(vec![-50.0, 0.0], play(&mut player, 4));
+    }
+}
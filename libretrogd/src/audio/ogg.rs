@@ -0,0 +1,113 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use lewton::inside_ogg::OggStreamReader;
+use lewton::VorbisError;
+
+use crate::audio::*;
+
+impl From<VorbisError> for AudioStreamError {
+    fn from(error: VorbisError) -> Self {
+        AudioStreamError::BadData(error.to_string())
+    }
+}
+
+/// An [`AudioStream`] which decodes OGG Vorbis audio as it is played. Only available with the
+/// "ogg" feature enabled.
+pub struct OggStream<T: Read + Seek + Send> {
+    reader: OggStreamReader<T>,
+    channels: usize,
+    samples: Vec<i16>,
+    index: usize,
+}
+
+impl<T: Read + Seek + Send> std::fmt::Debug for OggStream<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OggStream")
+            .field("frequency", &self.reader.ident_hdr.audio_sample_rate)
+            .field("channels", &self.channels)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Read + Seek + Send> OggStream<T> {
+    /// Creates a new [`OggStream`] which will decode OGG Vorbis audio from the given reader. Only
+    /// the headers are read up-front, the audio itself is decoded as it is played.
+    pub fn new(reader: T) -> Result<Self, AudioStreamError> {
+        let reader = OggStreamReader::new(reader)?;
+        let channels = reader.ident_hdr.audio_channels.max(1) as usize;
+        Ok(OggStream {
+            reader,
+            channels,
+            samples: Vec::new(),
+            index: 0,
+        })
+    }
+
+    /// Decodes the next packet of audio, returning false if the end of the stream was reached.
+    fn decode_packet(&mut self) -> Result<bool, AudioStreamError> {
+        match self.reader.read_dec_packet_itl()? {
+            Some(samples) => {
+                self.samples.extend(samples);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl OggStream<BufReader<File>> {
+    /// Opens the given OGG Vorbis file for streaming.
+    pub fn load_from_file(path: &Path) -> Result<Self, AudioStreamError> {
+        let f = File::open(path)?;
+        Self::new(BufReader::new(f))
+    }
+}
+
+impl<T: Read + Seek + Send> AudioStream for OggStream<T> {
+    fn frequency(&self) -> u32 {
+        self.reader.ident_hdr.audio_sample_rate
+    }
+
+    fn next_frame(&mut self) -> Result<Option<i16>, AudioStreamError> {
+        // packets can decode to no samples at all, so keep going until there is a full frame
+        while self.index + self.channels > self.samples.len() {
+            self.samples.drain(..self.index);
+            self.index = 0;
+            if !self.decode_packet()? {
+                return Ok(None);
+            }
+        }
+        let frame = &self.samples[self.index..self.index + self.channels];
+        let sum: i32 = frame.iter().map(|&sample| sample as i32).sum();
+        self.index += self.channels;
+        Ok(Some((sum / self.channels as i32) as i16))
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<(), AudioStreamError> {
+        // seeking is only accurate to the page containing the frame, so decode until the end of
+        // a page tells us exactly where in the stream we are, and skip ahead from there
+        self.reader.seek_absgp_pg(frame)?;
+        self.samples.clear();
+        self.index = 0;
+        while self.decode_packet()? {
+            if let Some(page_end) = self.reader.get_last_absgp() {
+                let decoded = (self.samples.len() / self.channels) as u64;
+                let decoded_start = page_end.saturating_sub(decoded);
+                let skip = frame.saturating_sub(decoded_start).min(decoded);
+                self.index = skip as usize * self.channels;
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MusicTrack {
+    /// Creates a new [`MusicTrack`] which streams the given OGG Vorbis file as it plays, looping
+    /// over the whole track if `loops` is true. Only available with the "ogg" feature enabled.
+    pub fn load_ogg_file(path: &Path, loops: bool) -> Result<Self, AudioStreamError> {
+        Ok(Self::new(Box::new(OggStream::load_from_file(path)?), loops))
+    }
+}
@@ -0,0 +1,211 @@
+use crate::audio::*;
+use crate::graphics::Camera;
+use crate::math::*;
+
+/// How the volume of a sound falls off with its distance from an [`AudioListener`], between the
+/// listener's `min_distance` (full volume) and `max_distance` (silent).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Attenuation {
+    /// The volume does not change with distance, though sounds beyond `max_distance` are still
+    /// silent.
+    None,
+    /// The volume falls off evenly with distance.
+    Linear,
+    /// The volume falls off quickly at first and then more slowly, which is closer to how sound
+    /// behaves in the real world. Higher rolloff values fall off more quickly.
+    Inverse { rolloff: f32 },
+    /// The volume falls off slowly at first and then more quickly. Higher exponent values keep
+    /// sounds louder for longer.
+    Exponential { exponent: f32 },
+}
+
+impl Attenuation {
+    /// Returns the volume multiplier for the given fraction of the distance between the minimum
+    /// and maximum distances, where 0.0 is at (or within) the minimum distance and 1.0 is at (or
+    /// beyond) the maximum distance.
+    pub fn volume_at(&self, distance_fraction: f32) -> f32 {
+        let t = distance_fraction.clamp(0.0, 1.0);
+        if t >= 1.0 {
+            return 0.0;
+        }
+        match *self {
+            Attenuation::None => 1.0,
+            Attenuation::Linear => 1.0 - t,
+            // scaled so that the volume still reaches zero at the maximum distance
+            Attenuation::Inverse { rolloff } => {
+                let rolloff = rolloff.max(0.0);
+                (1.0 / (1.0 + rolloff * t) - 1.0 / (1.0 + rolloff)) / (1.0 - 1.0 / (1.0 + rolloff))
+            }
+            Attenuation::Exponential { exponent } => 1.0 - t.powf(exponent.max(0.0)),
+        }
+    }
+}
+
+/// Computes the volume and stereo panning for sounds emitted from positions in the game world,
+/// relative to the position of the listener (usually the player, or the center of the screen).
+/// Sounds to the left of the listener are panned left, sounds to the right are panned right, and
+/// sounds get quieter the further away they are.
+///
+/// ```no_run
+/// use libretrogd::audio::*;
+/// use libretrogd::math::*;
+///
+/// # let mut audio_device = AudioDevice::new(AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 1, sdl2::audio::AudioFormat::U8));
+/// # let channel_index = 0;
+/// let listener = AudioListener::new(Vector2::new(160.0, 100.0));
+/// let emitter_position = Vector2::new(250.0, 90.0);
+/// // once per frame, for each channel playing a positional sound
+/// listener.apply(&mut audio_device[channel_index], emitter_position, 1.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioListener {
+    /// The listener's position in the world.
+    pub position: Vector2,
+    /// Sounds within this distance of the listener are played at full volume.
+    pub min_distance: f32,
+    /// Sounds beyond this distance from the listener are silent.
+    pub max_distance: f32,
+    /// How the volume falls off between `min_distance` and `max_distance`.
+    pub attenuation: Attenuation,
+    /// The horizontal distance from the listener at which sounds are panned fully to the left or
+    /// right.
+    pub pan_distance: f32,
+}
+
+impl AudioListener {
+    /// Creates a new listener at the given world position, with default distances suitable for a
+    /// listener at the center of a 320x200 screen.
+    pub fn new(position: Vector2) -> Self {
+        AudioListener {
+            position,
+            min_distance: 32.0,
+            max_distance: 320.0,
+            attenuation: Attenuation::Linear,
+            pan_distance: 160.0,
+        }
+    }
+
+    /// Creates a new listener at the center of the given [`Camera`]'s view. Sounds are panned
+    /// fully left or right at the edges of the view and become silent once they are as far from
+    /// the center of the view as its width.
+    pub fn from_camera(camera: &Camera) -> Self {
+        let mut listener = Self::new(camera.position());
+        let view_width = camera.viewport().width as f32 / camera.zoom();
+        listener.pan_distance = view_width / 2.0;
+        listener.max_distance = view_width;
+        listener.min_distance = view_width / 10.0;
+        listener
+    }
+
+    /// Moves the listener to the center of the given [`Camera`]'s view. Call this each frame
+    /// after the camera is updated to keep positional sounds in sync with what is on screen.
+    #[inline]
+    pub fn follow_camera(&mut self, camera: &Camera) {
+        self.position = camera.position();
+    }
+
+    /// Returns the volume multiplier (from 0.0 to 1.0) and pan (from -1.0 to 1.0) for a sound
+    /// emitted at the given world position.
+    pub fn volume_and_pan(&self, emitter_position: Vector2) -> (f32, f32) {
+        let distance = self.position.distance(&emitter_position);
+        let range = self.max_distance - self.min_distance;
+        let volume = if distance <= self.min_distance {
+            1.0
+        } else if range <= 0.0 {
+            0.0
+        } else {
+            self.attenuation
+                .volume_at((distance - self.min_distance) / range)
+        };
+        let pan = if self.pan_distance > 0.0 {
+            ((emitter_position.x - self.position.x) / self.pan_distance).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        (volume, pan)
+    }
+
+    /// Sets the volume and pan of the given [`AudioChannel`] for a sound emitted at the given
+    /// world position.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel`: the channel playing the sound
+    /// * `emitter_position`: the world position the sound is coming from
+    /// * `volume`: the volume of the sound before any distance attenuation is applied
+    pub fn apply(&self, channel: &mut AudioChannel, emitter_position: Vector2, volume: f32) {
+        let (attenuation, pan) = self.volume_and_pan(emitter_position);
+        channel.volume = volume * attenuation;
+        channel.pan = pan;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::math::Rect;
+
+    use super::*;
+
+    #[test]
+    pub fn attenuation_curves() {
+        for attenuation in [
+            Attenuation::None,
+            Attenuation::Linear,
+            Attenuation::Inverse { rolloff: 4.0 },
+            Attenuation::Exponential { exponent: 2.0 },
+        ] {
+            assert_eq!(0.0, attenuation.volume_at(1.0));
+            assert_eq!(0.0, attenuation.volume_at(2.0));
+            assert!((attenuation.volume_at(0.0) - 1.0).abs() < 0.0001);
+        }
+        assert_eq!(1.0, Attenuation::None.volume_at(0.5));
+        assert_eq!(0.5, Attenuation::Linear.volume_at(0.5));
+        assert_eq!(
+            0.75,
+            Attenuation::Exponential { exponent: 2.0 }.volume_at(0.5)
+        );
+        assert!(Attenuation::Inverse { rolloff: 4.0 }.volume_at(0.5) < 0.5);
+    }
+
+    #[test]
+    pub fn positional_volume_and_pan() {
+        let listener = AudioListener::new(Vector2::new(100.0, 100.0));
+        assert_eq!(
+            (1.0, 0.0),
+            listener.volume_and_pan(Vector2::new(100.0, 110.0))
+        );
+        assert_eq!(
+            (0.0, 1.0),
+            listener.volume_and_pan(Vector2::new(500.0, 100.0))
+        );
+
+        let (volume, pan) = listener.volume_and_pan(Vector2::new(20.0, 100.0));
+        assert!((volume - 0.8333).abs() < 0.001);
+        assert_eq!(-0.5, pan);
+
+        let mut channel = AudioChannel::new();
+        listener.apply(&mut channel, Vector2::new(20.0, 100.0), 0.5);
+        assert!((channel.volume - 0.4167).abs() < 0.001);
+        assert_eq!(-0.5, channel.pan);
+    }
+
+    #[test]
+    pub fn camera_listener() {
+        let mut camera = Camera::new(Rect::new(0, 0, 320, 200));
+        camera.set_position(Vector2::new(500.0, 300.0));
+        let mut listener = AudioListener::from_camera(&camera);
+        assert_eq!(Vector2::new(500.0, 300.0), listener.position);
+        assert_eq!(160.0, listener.pan_distance);
+        assert_eq!(
+            (0.0, -1.0),
+            listener.volume_and_pan(Vector2::new(100.0, 300.0))
+        );
+
+        camera.move_by(Vector2::new(-400.0, 0.0));
+        listener.follow_camera(&camera);
+        assert_eq!(
+            (1.0, 0.0),
+            listener.volume_and_pan(Vector2::new(100.0, 300.0))
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::VecDeque;
 use std::rc::Rc;
+use std::time::Duration;
 
 use crate::audio::*;
 
@@ -24,6 +25,37 @@ pub enum AudioCommand {
         buffer: Rc<AudioBuffer>,
         loops: bool,
     },
+    PlayBufferWithPriority {
+        buffer: AudioBuffer,
+        loops: bool,
+        priority: u8,
+    },
+    PlayRcBufferWithPriority {
+        buffer: Rc<AudioBuffer>,
+        loops: bool,
+        priority: u8,
+    },
+    PlayBufferWithPitch {
+        buffer: AudioBuffer,
+        loops: bool,
+        pitch: f32,
+    },
+    PlayRcBufferWithPitch {
+        buffer: Rc<AudioBuffer>,
+        loops: bool,
+        pitch: f32,
+    },
+    ScheduleBuffer {
+        buffer: AudioBuffer,
+        loops: bool,
+        at: u64,
+    },
+    ScheduleRcBuffer {
+        buffer: Rc<AudioBuffer>,
+        loops: bool,
+        at: u64,
+    },
+    CancelScheduled,
     PlayGenerator {
         generator: Box<dyn AudioGenerator>,
         loops: bool,
@@ -33,6 +65,24 @@ pub enum AudioCommand {
         generator: Box<dyn AudioGenerator>,
         loops: bool,
     },
+    FadeChannel {
+        channel: usize,
+        volume: f32,
+        ticks: usize,
+    },
+    FadeOutChannel {
+        channel: usize,
+        ticks: usize,
+    },
+    PlayMusic {
+        track: MusicTrack,
+        crossfade: Duration,
+    },
+    StopMusic {
+        fade_out: Duration,
+    },
+    Beep(Beep),
+    StopBeeper,
 }
 
 impl std::fmt::Debug for AudioCommand {
@@ -67,6 +117,49 @@ impl std::fmt::Debug for AudioCommand {
                     .field("loops", loops)
                     .finish()
             },
+            PlayBufferWithPriority { buffer, loops, priority } => {
+                f.debug_struct("PlayBufferWithPriority")
+                    .field("buffer", buffer)
+                    .field("loops", loops)
+                    .field("priority", priority)
+                    .finish()
+            },
+            PlayRcBufferWithPriority { buffer, loops, priority } => {
+                f.debug_struct("PlayRcBufferWithPriority")
+                    .field("buffer", buffer)
+                    .field("loops", loops)
+                    .field("priority", priority)
+                    .finish()
+            },
+            PlayBufferWithPitch { buffer, loops, pitch } => {
+                f.debug_struct("PlayBufferWithPitch")
+                    .field("buffer", buffer)
+                    .field("loops", loops)
+                    .field("pitch", pitch)
+                    .finish()
+            },
+            PlayRcBufferWithPitch { buffer, loops, pitch } => {
+                f.debug_struct("PlayRcBufferWithPitch")
+                    .field("buffer", buffer)
+                    .field("loops", loops)
+                    .field("pitch", pitch)
+                    .finish()
+            },
+            ScheduleBuffer { buffer, loops, at } => {
+                f.debug_struct("ScheduleBuffer")
+                    .field("buffer", buffer)
+                    .field("loops", loops)
+                    .field("at", at)
+                    .finish()
+            },
+            ScheduleRcBuffer { buffer, loops, at } => {
+                f.debug_struct("ScheduleRcBuffer")
+                    .field("buffer", buffer)
+                    .field("loops", loops)
+                    .field("at", at)
+                    .finish()
+            },
+            CancelScheduled => write!(f, "CancelScheduled"),
             PlayGenerator { loops, .. } => {
                 f.debug_struct("PlayGenerator")
                     .field("loops", loops)
@@ -78,6 +171,36 @@ impl std::fmt::Debug for AudioCommand {
                     .field("loops", loops)
                     .finish_non_exhaustive()
             },
+            FadeChannel { channel, volume, ticks } => {
+                f.debug_struct("FadeChannel")
+                    .field("channel", channel)
+                    .field("volume", volume)
+                    .field("ticks", ticks)
+                    .finish()
+            },
+            FadeOutChannel { channel, ticks } => {
+                f.debug_struct("FadeOutChannel")
+                    .field("channel", channel)
+                    .field("ticks", ticks)
+                    .finish()
+            },
+            PlayMusic { track, crossfade } => {
+                f.debug_struct("PlayMusic")
+                    .field("track", track)
+                    .field("crossfade", crossfade)
+                    .finish()
+            },
+            StopMusic { fade_out } => {
+                f.debug_struct("StopMusic")
+                    .field("fade_out", fade_out)
+                    .finish()
+            },
+            Beep(beep) => {
+                f.debug_tuple("Beep")
+                    .field(beep)
+                    .finish()
+            },
+            StopBeeper => write!(f, "StopBeeper"),
         }
     }
 }
@@ -90,6 +213,7 @@ impl std::fmt::Debug for AudioCommand {
 #[derive(Debug)]
 pub struct AudioQueue {
     spec: AudioSpec,
+    num_channels: usize,
     commands: VecDeque<AudioCommand>,
 }
 
@@ -98,13 +222,15 @@ impl AudioQueue {
     pub fn new(audio: &Audio) -> Self {
         AudioQueue {
             spec: audio.spec,
+            num_channels: audio.num_channels,
             commands: VecDeque::new(),
         }
     }
 
-    /// Returns the spec that this queue is currently set to play. All audio to be played via
-    /// this queue must be pre-converted to match this spec! This spec is a copy of the one that
-    /// was obtained from the [`Audio`] instance used to create this [`AudioQueue`].
+    /// Returns the spec that this queue is currently set to play. Audio matching this spec can be
+    /// played the fastest, though other 8-bit and 16-bit audio is converted automatically when
+    /// played. This spec is a copy of the one that was obtained from the [`Audio`] instance used
+    /// to create this [`AudioQueue`].
     #[inline]
     pub fn spec(&self) -> &AudioSpec {
         &self.spec
@@ -112,7 +238,7 @@ impl AudioQueue {
 
     /// Queues a stop command for the given channel.
     pub fn stop_channel(&mut self, channel_index: usize) -> Result<(), AudioDeviceError> {
-        if channel_index >= NUM_CHANNELS {
+        if channel_index >= self.num_channels {
             Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
         } else {
             self.commands.push_back(AudioCommand::StopChannel(channel_index));
@@ -133,15 +259,12 @@ impl AudioQueue {
         buffer: &AudioBuffer,
         loops: bool,
     ) -> Result<(), AudioDeviceError> {
-        if *buffer.spec() != self.spec {
-            Err(AudioDeviceError::AudioSpecMismatch)
-        } else {
-            self.commands.push_back(AudioCommand::PlayBuffer {
-                buffer: buffer.clone(),
-                loops,
-            });
-            Ok(())
-        }
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::PlayBuffer {
+            buffer: buffer.clone(),
+            loops,
+        });
+        Ok(())
     }
 
     /// Queues a command to play the given [`AudioBuffer`]'s data. The buffer will be played on
@@ -153,15 +276,128 @@ impl AudioQueue {
         buffer: Rc<AudioBuffer>,
         loops: bool,
     ) -> Result<(), AudioDeviceError> {
-        if *buffer.spec() != self.spec {
-            Err(AudioDeviceError::AudioSpecMismatch)
-        } else {
-            self.commands.push_back(AudioCommand::PlayRcBuffer {
-                buffer,
-                loops,
-            });
-            Ok(())
-        }
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::PlayRcBuffer {
+            buffer,
+            loops,
+        });
+        Ok(())
+    }
+
+    /// Queues a command to play a copy of the given [`AudioBuffer`]'s data with the given priority.
+    /// The buffer will be played on the first channel found that is not already playing. If all
+    /// channels are already playing, the lowest priority sound will be interrupted to play this
+    /// buffer instead, as long as its priority is not higher than the given priority.
+    pub fn play_buffer_with_priority(
+        &mut self,
+        buffer: &AudioBuffer,
+        loops: bool,
+        priority: u8,
+    ) -> Result<(), AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::PlayBufferWithPriority {
+            buffer: buffer.clone(),
+            loops,
+            priority,
+        });
+        Ok(())
+    }
+
+    /// Queues a command to play the given [`AudioBuffer`]'s data with the given priority. This
+    /// method is more performant than [`AudioQueue::play_buffer_with_priority`], as that method
+    /// will always immediately copy the given buffer to create the queued command.
+    pub fn play_buffer_rc_with_priority(
+        &mut self,
+        buffer: Rc<AudioBuffer>,
+        loops: bool,
+        priority: u8,
+    ) -> Result<(), AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::PlayRcBufferWithPriority {
+            buffer,
+            loops,
+            priority,
+        });
+        Ok(())
+    }
+
+    /// Queues a command to play a copy of the given [`AudioBuffer`]'s data at the given pitch
+    /// (playback rate), where 1.0 is "normal". The buffer will be played on the first channel
+    /// found that is not already playing. If all channels are already playing, then nothing will
+    /// be done.
+    pub fn play_buffer_with_pitch(
+        &mut self,
+        buffer: &AudioBuffer,
+        loops: bool,
+        pitch: f32,
+    ) -> Result<(), AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::PlayBufferWithPitch {
+            buffer: buffer.clone(),
+            loops,
+            pitch,
+        });
+        Ok(())
+    }
+
+    /// Queues a command to play the given [`AudioBuffer`]'s data at the given pitch. This method
+    /// is more performant than [`AudioQueue::play_buffer_with_pitch`], as that method will always
+    /// immediately copy the given buffer to create the queued command.
+    pub fn play_buffer_rc_with_pitch(
+        &mut self,
+        buffer: Rc<AudioBuffer>,
+        loops: bool,
+        pitch: f32,
+    ) -> Result<(), AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::PlayRcBufferWithPitch {
+            buffer,
+            loops,
+            pitch,
+        });
+        Ok(())
+    }
+
+    /// Queues a command to schedule a copy of the given [`AudioBuffer`]'s data to start playing
+    /// once the audio device's sample clock reaches the given sample frame. See
+    /// [`AudioDevice::schedule_buffer`].
+    pub fn schedule_buffer(
+        &mut self,
+        buffer: &AudioBuffer,
+        loops: bool,
+        at: u64,
+    ) -> Result<(), AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::ScheduleBuffer {
+            buffer: buffer.clone(),
+            loops,
+            at,
+        });
+        Ok(())
+    }
+
+    /// Queues a command to schedule the given [`AudioBuffer`]'s data to start playing once the
+    /// audio device's sample clock reaches the given sample frame. This method is more performant
+    /// than [`AudioQueue::schedule_buffer`], as that method will always immediately copy the given
+    /// buffer to create the queued command.
+    pub fn schedule_buffer_rc(
+        &mut self,
+        buffer: Rc<AudioBuffer>,
+        loops: bool,
+        at: u64,
+    ) -> Result<(), AudioDeviceError> {
+        check_playable_spec(buffer.spec())?;
+        self.commands.push_back(AudioCommand::ScheduleRcBuffer {
+            buffer,
+            loops,
+            at,
+        });
+        Ok(())
+    }
+
+    /// Queues a command to discard all scheduled sounds that have not started playing yet.
+    pub fn cancel_scheduled(&mut self) {
+        self.commands.push_back(AudioCommand::CancelScheduled);
     }
 
     /// Queues a command to play a copy of the given [`AudioBuffer`]'s data on the channel
@@ -173,9 +409,8 @@ impl AudioQueue {
         buffer: &AudioBuffer,
         loops: bool,
     ) -> Result<(), AudioDeviceError> {
-        if *buffer.spec() != self.spec {
-            Err(AudioDeviceError::AudioSpecMismatch)
-        } else if channel_index >= NUM_CHANNELS {
+        check_playable_spec(buffer.spec())?;
+        if channel_index >= self.num_channels {
             Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
         } else {
             self.commands.push_back(AudioCommand::PlayBufferOnChannel {
@@ -197,9 +432,8 @@ impl AudioQueue {
         buffer: Rc<AudioBuffer>,
         loops: bool,
     ) -> Result<(), AudioDeviceError> {
-        if *buffer.spec() != self.spec {
-            Err(AudioDeviceError::AudioSpecMismatch)
-        } else if channel_index >= NUM_CHANNELS {
+        check_playable_spec(buffer.spec())?;
+        if channel_index >= self.num_channels {
             Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
         } else {
             self.commands.push_back(AudioCommand::PlayRcBufferOnChannel {
@@ -238,6 +472,65 @@ impl AudioQueue {
         Ok(())
     }
 
+    /// Queues a command to gradually change the volume of the given channel. See
+    /// [`AudioChannel::fade_to`].
+    pub fn fade_channel(
+        &mut self,
+        channel_index: usize,
+        volume: f32,
+        ticks: usize,
+    ) -> Result<(), AudioDeviceError> {
+        if channel_index >= self.num_channels {
+            Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
+        } else {
+            self.commands.push_back(AudioCommand::FadeChannel {
+                channel: channel_index,
+                volume,
+                ticks,
+            });
+            Ok(())
+        }
+    }
+
+    /// Queues a command to gradually lower the volume of the given channel to silence, and then
+    /// stop its playback. See [`AudioChannel::fade_out`].
+    pub fn fade_out_channel(
+        &mut self,
+        channel_index: usize,
+        ticks: usize,
+    ) -> Result<(), AudioDeviceError> {
+        if channel_index >= self.num_channels {
+            Err(AudioDeviceError::ChannelIndexOutOfRange(channel_index))
+        } else {
+            self.commands.push_back(AudioCommand::FadeOutChannel {
+                channel: channel_index,
+                ticks,
+            });
+            Ok(())
+        }
+    }
+
+    /// Queues a command to play the given [`MusicTrack`], cross-fading from any music that was
+    /// already playing over the given duration.
+    pub fn play_music(&mut self, track: MusicTrack, crossfade: Duration) {
+        self.commands.push_back(AudioCommand::PlayMusic { track, crossfade });
+    }
+
+    /// Queues a command to stop playing music, fading it out over the given duration.
+    pub fn stop_music(&mut self, fade_out: Duration) {
+        self.commands.push_back(AudioCommand::StopMusic { fade_out });
+    }
+
+    /// Queues a command to add the given [`Beep`] to the end of the beeper's queue of beeps.
+    pub fn beep(&mut self, beep: Beep) {
+        self.commands.push_back(AudioCommand::Beep(beep));
+    }
+
+    /// Queues a command to stop the beeper, discarding any beeps it had queued.
+    pub fn stop_beeper(&mut self) {
+        self.commands.push_back(AudioCommand::StopBeeper);
+    }
+
     /// Flushes the queued commands, issuing them in the same order they were created, to the
     /// given [`AudioDevice`].
     pub fn apply_to_device(&mut self, device: &mut AudioDevice) -> Result<(), AudioDeviceError> {
@@ -263,12 +556,51 @@ impl AudioQueue {
                     PlayRcBufferOnChannel { channel, buffer, loops } => {
                         device.play_buffer_on_channel(channel, &buffer, loops)?;
                     },
+                    PlayBufferWithPriority { buffer, loops, priority } => {
+                        device.play_buffer_with_priority(&buffer, loops, priority)?;
+                    },
+                    PlayRcBufferWithPriority { buffer, loops, priority } => {
+                        device.play_buffer_with_priority(&buffer, loops, priority)?;
+                    },
+                    PlayBufferWithPitch { buffer, loops, pitch } => {
+                        device.play_buffer_with_pitch(&buffer, loops, pitch)?;
+                    },
+                    PlayRcBufferWithPitch { buffer, loops, pitch } => {
+                        device.play_buffer_with_pitch(&buffer, loops, pitch)?;
+                    },
+                    ScheduleBuffer { buffer, loops, at } => {
+                        device.schedule_buffer(&buffer, loops, at)?;
+                    },
+                    ScheduleRcBuffer { buffer, loops, at } => {
+                        device.schedule_buffer(&buffer, loops, at)?;
+                    },
+                    CancelScheduled => {
+                        device.cancel_scheduled();
+                    },
                     PlayGenerator { generator, loops } => {
                         device.play_generator(generator, loops)?;
                     },
                     PlayGeneratorOnChannel { channel, generator, loops } => {
                         device.play_generator_on_channel(channel, generator, loops)?;
                     },
+                    FadeChannel { channel, volume, ticks } => {
+                        device.fade_channel(channel, volume, ticks)?;
+                    },
+                    FadeOutChannel { channel, ticks } => {
+                        device.fade_out_channel(channel, ticks)?;
+                    },
+                    PlayMusic { track, crossfade } => {
+                        device.play_music(track, crossfade);
+                    },
+                    StopMusic { fade_out } => {
+                        device.stop_music(fade_out);
+                    },
+                    Beep(beep) => {
+                        device.beep(beep);
+                    },
+                    StopBeeper => {
+                        device.stop_beeper();
+                    },
                 }
             } else {
                 return Ok(())
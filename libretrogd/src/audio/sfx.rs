@@ -0,0 +1,390 @@
+use std::f32::consts::PI;
+
+use sdl2::audio::AudioFormat;
+
+use crate::audio::*;
+use crate::utils::prng::Prng;
+
+/// The number of random values making up one period of the [`SfxWaveform::Noise`] waveform.
+const NOISE_SAMPLES_PER_PERIOD: usize = 32;
+
+/// Seed used for the noise waveform, so that the same [`SfxParams`] always produce the exact same
+/// sound.
+const NOISE_SEED: u64 = 0x5f8e;
+
+/// The shape of the wave used as the basis of a synthesized sound effect.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SfxWaveform {
+    Square,
+    Sawtooth,
+    Triangle,
+    Sine,
+    Noise,
+}
+
+/// Categories of classic retro sound effects that [`SfxParams::random`] can randomly generate
+/// parameters for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SfxCategory {
+    Pickup,
+    Laser,
+    Explosion,
+    Powerup,
+    Hit,
+    Jump,
+    Blip,
+}
+
+/// Describes a sound effect to be synthesized by an [`SfxGenerator`]. Times are in seconds and
+/// frequencies are in Hz.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SfxParams {
+    /// The shape of the wave the sound is made of.
+    pub waveform: SfxWaveform,
+    /// The frequency (pitch) the sound starts at.
+    pub base_frequency: f32,
+    /// If non-zero, the sound ends early once a downward frequency slide reaches this frequency.
+    pub frequency_limit: f32,
+    /// How quickly the frequency changes, in octaves per second. Negative values slide down.
+    pub frequency_slide: f32,
+    /// How quickly `frequency_slide` itself changes, in octaves per second per second.
+    pub frequency_delta_slide: f32,
+    /// How much the frequency wavers, as a fraction of the current frequency. 0.0 for none.
+    pub vibrato_depth: f32,
+    /// How many times per second the frequency wavers.
+    pub vibrato_speed: f32,
+    /// The frequency is multiplied by this once `arpeggio_time` has elapsed. 1.0 for no change.
+    pub arpeggio_multiplier: f32,
+    /// How long after the start of the sound the arpeggio frequency change happens.
+    pub arpeggio_time: f32,
+    /// How much of each period a [`SfxWaveform::Square`] wave spends "high", from 0.0 to 1.0.
+    pub duty_cycle: f32,
+    /// How quickly `duty_cycle` changes, per second.
+    pub duty_sweep: f32,
+    /// How long the volume takes to rise from silence at the start of the sound.
+    pub attack_time: f32,
+    /// How long the volume is held after the attack.
+    pub sustain_time: f32,
+    /// How much louder the start of the sustain is, fading down to the normal volume over the
+    /// sustain time. 0.0 for no extra "punch".
+    pub sustain_punch: f32,
+    /// How long the volume takes to fall to silence at the end of the sound.
+    pub decay_time: f32,
+    /// The overall volume of the sound. 1.0 is "normal", 0.0 is completely silent.
+    pub volume: f32,
+}
+
+impl Default for SfxParams {
+    fn default() -> Self {
+        SfxParams {
+            waveform: SfxWaveform::Square,
+            base_frequency: 440.0,
+            frequency_limit: 0.0,
+            frequency_slide: 0.0,
+            frequency_delta_slide: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            arpeggio_multiplier: 1.0,
+            arpeggio_time: 0.0,
+            duty_cycle: 0.5,
+            duty_sweep: 0.0,
+            attack_time: 0.0,
+            sustain_time: 0.1,
+            sustain_punch: 0.0,
+            decay_time: 0.2,
+            volume: 0.5,
+        }
+    }
+}
+
+impl SfxParams {
+    /// Returns randomly generated parameters for a sound effect in the given category. The same
+    /// sequence of values from the given [`Prng`] always results in the same parameters, so a
+    /// sound effect can be re-created later from just its category and seed.
+    ///
+    /// # Arguments
+    ///
+    /// * `category`: the kind of sound effect to generate parameters for
+    /// * `prng`: the random number generator to use
+    pub fn random(category: SfxCategory, prng: &mut Prng) -> SfxParams {
+        use SfxWaveform::*;
+
+        let mut params = SfxParams::default();
+        match category {
+            SfxCategory::Pickup => {
+                params.waveform = *prng.choose(&[Square, Sawtooth]).unwrap();
+                params.base_frequency = prng.rand_range(400.0..=1200.0);
+                params.sustain_time = prng.rand_range(0.02..=0.1);
+                params.sustain_punch = prng.rand_range(0.3..=0.6);
+                params.decay_time = prng.rand_range(0.1..=0.3);
+                if prng.rand_chance(0.5) {
+                    params.arpeggio_multiplier = prng.rand_range(1.25..=1.6);
+                    params.arpeggio_time = prng.rand_range(0.03..=0.1);
+                }
+            }
+            SfxCategory::Laser => {
+                params.waveform = *prng.choose(&[Square, Sawtooth, Sine]).unwrap();
+                params.base_frequency = prng.rand_range(500.0..=2000.0);
+                params.frequency_limit = prng.rand_range(50.0..=params.base_frequency * 0.3);
+                params.frequency_slide = prng.rand_range(-10.0..=-3.0);
+                params.duty_cycle = prng.rand_range(0.2..=0.5);
+                params.duty_sweep = prng.rand_range(-0.5..=0.5);
+                params.sustain_time = prng.rand_range(0.05..=0.15);
+                params.sustain_punch = prng.rand_range(0.0..=0.3);
+                params.decay_time = prng.rand_range(0.05..=0.3);
+            }
+            SfxCategory::Explosion => {
+                params.waveform = Noise;
+                params.base_frequency = prng.rand_range(50.0..=400.0);
+                params.frequency_slide = prng.rand_range(-2.0..=0.5);
+                if prng.rand_chance(0.5) {
+                    params.vibrato_depth = prng.rand_range(0.1..=0.4);
+                    params.vibrato_speed = prng.rand_range(5.0..=20.0);
+                }
+                params.sustain_time = prng.rand_range(0.1..=0.4);
+                params.sustain_punch = prng.rand_range(0.2..=0.8);
+                params.decay_time = prng.rand_range(0.3..=0.8);
+            }
+            SfxCategory::Powerup => {
+                params.waveform = *prng.choose(&[Square, Sawtooth]).unwrap();
+                params.base_frequency = prng.rand_range(200.0..=600.0);
+                params.frequency_slide = prng.rand_range(1.0..=4.0);
+                if prng.rand_chance(0.5) {
+                    params.vibrato_depth = prng.rand_range(0.05..=0.2);
+                    params.vibrato_speed = prng.rand_range(10.0..=30.0);
+                }
+                params.sustain_time = prng.rand_range(0.1..=0.3);
+                params.decay_time = prng.rand_range(0.1..=0.4);
+            }
+            SfxCategory::Hit => {
+                params.waveform = *prng.choose(&[Square, Sawtooth, Noise]).unwrap();
+                params.base_frequency = prng.rand_range(100.0..=700.0);
+                params.frequency_slide = prng.rand_range(-6.0..=-2.0);
+                params.sustain_time = prng.rand_range(0.01..=0.05);
+                params.decay_time = prng.rand_range(0.05..=0.2);
+            }
+            SfxCategory::Jump => {
+                params.waveform = Square;
+                params.base_frequency = prng.rand_range(200.0..=500.0);
+                params.frequency_slide = prng.rand_range(2.0..=5.0);
+                params.duty_cycle = prng.rand_range(0.2..=0.5);
+                params.sustain_time = prng.rand_range(0.05..=0.2);
+                params.decay_time = prng.rand_range(0.05..=0.2);
+            }
+            SfxCategory::Blip => {
+                params.waveform = *prng.choose(&[Square, Sawtooth]).unwrap();
+                params.base_frequency = prng.rand_range(300.0..=1500.0);
+                params.duty_cycle = prng.rand_range(0.2..=0.5);
+                params.sustain_time = prng.rand_range(0.02..=0.08);
+                params.decay_time = prng.rand_range(0.01..=0.05);
+            }
+        }
+        params
+    }
+
+    /// Returns the length of the sound described by these parameters, in seconds. The sound may
+    /// end earlier if it has a `frequency_limit`.
+    #[inline]
+    pub fn duration(&self) -> f32 {
+        self.attack_time.max(0.0) + self.sustain_time.max(0.0) + self.decay_time.max(0.0)
+    }
+
+    /// Returns the volume envelope's level at the given time into the sound, or `None` if the
+    /// sound is over by then.
+    fn envelope_at(&self, time: f32) -> Option<f32> {
+        let attack_time = self.attack_time.max(0.0);
+        let sustain_time = self.sustain_time.max(0.0);
+        let decay_time = self.decay_time.max(0.0);
+        if time < attack_time {
+            Some(time / attack_time)
+        } else if time < attack_time + sustain_time {
+            let progress = (time - attack_time) / sustain_time;
+            Some(1.0 + (1.0 - progress) * 2.0 * self.sustain_punch)
+        } else if time < attack_time + sustain_time + decay_time {
+            Some(1.0 - (time - attack_time - sustain_time) / decay_time)
+        } else {
+            None
+        }
+    }
+}
+
+/// Synthesizes classic retro sound effects (in the style of "sfxr") from [`SfxParams`] into
+/// [`AudioBuffer`]s that can be played like any other sound.
+///
+/// ```no_run
+/// use libretrogd::audio::*;
+/// use libretrogd::utils::prng::Prng;
+///
+/// let spec = AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 1, sdl2::audio::AudioFormat::U8);
+/// let generator = SfxGenerator::new(&spec);
+/// let params = SfxParams::random(SfxCategory::Pickup, &mut Prng::new(42));
+/// let buffer = generator.generate(&params);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SfxGenerator {
+    frequency: u32,
+}
+
+impl SfxGenerator {
+    /// Creates a new [`SfxGenerator`] which will generate sound effects with the frequency of the
+    /// given spec. Generated buffers are always unsigned 8-bit mono audio.
+    pub fn new(spec: &AudioSpec) -> Self {
+        SfxGenerator {
+            frequency: spec.frequency().max(1),
+        }
+    }
+
+    /// Returns the spec of the audio buffers this generator creates.
+    #[inline]
+    pub fn spec(&self) -> AudioSpec {
+        AudioSpec::new(self.frequency, 1, AudioFormat::U8)
+    }
+
+    /// Synthesizes the sound effect described by the given parameters, returning it in a new
+    /// [`AudioBuffer`]. The same parameters always result in the exact same audio.
+    pub fn generate(&self, params: &SfxParams) -> AudioBuffer {
+        let mut buffer = AudioBuffer::new(self.spec());
+        let sample_rate = self.frequency as f32;
+        let time_step = 1.0 / sample_rate;
+        let max_samples = (params.duration() * sample_rate).ceil() as usize;
+        buffer.data.reserve(max_samples);
+
+        let mut prng = Prng::new(NOISE_SEED);
+        let mut noise = [0.0f32; NOISE_SAMPLES_PER_PERIOD];
+        fill_noise(&mut noise, &mut prng);
+
+        let mut frequency = params.base_frequency.max(0.0);
+        let mut slide = params.frequency_slide;
+        let mut duty_cycle = params.duty_cycle;
+        let mut arpeggio_done = params.arpeggio_time <= 0.0 || params.arpeggio_multiplier == 1.0;
+        let mut phase = 0.0f32;
+
+        for index in 0..max_samples {
+            let time = index as f32 * time_step;
+            let envelope = match params.envelope_at(time) {
+                Some(envelope) => envelope,
+                None => break,
+            };
+
+            if !arpeggio_done && time >= params.arpeggio_time {
+                frequency *= params.arpeggio_multiplier;
+                arpeggio_done = true;
+            }
+            frequency *= (slide * time_step).exp2();
+            slide += params.frequency_delta_slide * time_step;
+            if params.frequency_limit > 0.0 && frequency < params.frequency_limit && slide < 0.0 {
+                break;
+            }
+            duty_cycle = (duty_cycle + params.duty_sweep * time_step).clamp(0.0, 1.0);
+
+            let vibrato =
+                1.0 + (2.0 * PI * params.vibrato_speed * time).sin() * params.vibrato_depth;
+            phase += frequency * vibrato / sample_rate;
+            if phase >= 1.0 {
+                phase = phase.fract();
+                if params.waveform == SfxWaveform::Noise {
+                    fill_noise(&mut noise, &mut prng);
+                }
+            }
+
+            let sample = match params.waveform {
+                SfxWaveform::Square => {
+                    if phase < duty_cycle.clamp(0.05, 0.95) {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                SfxWaveform::Sawtooth => 1.0 - phase * 2.0,
+                SfxWaveform::Triangle => (phase - 0.5).abs() * 4.0 - 1.0,
+                SfxWaveform::Sine => (2.0 * PI * phase).sin(),
+                SfxWaveform::Noise => noise[(phase * NOISE_SAMPLES_PER_PERIOD as f32) as usize],
+            };
+            let sample = sample * envelope * params.volume;
+            buffer
+                .data
+                .push(((sample * 127.0).clamp(-128.0, 127.0) as i16 + 128) as u8);
+        }
+
+        buffer
+    }
+}
+
+fn fill_noise(noise: &mut [f32], prng: &mut Prng) {
+    for value in noise.iter_mut() {
+        *value = prng.rand_range(-1.0..=1.0);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn generator() -> SfxGenerator {
+        SfxGenerator::new(&AudioSpec::new(AUDIO_FREQUENCY_22KHZ, 1, AudioFormat::U8))
+    }
+
+    #[test]
+    pub fn generating_sounds() {
+        let generator = generator();
+        let params = SfxParams {
+            attack_time: 0.0,
+            sustain_time: 0.1,
+            decay_time: 0.1,
+            ..SfxParams::default()
+        };
+        let buffer = generator.generate(&params);
+        assert_eq!(AUDIO_FREQUENCY_22KHZ, buffer.spec().frequency());
+        assert_eq!(1, buffer.spec().channels());
+        assert_eq!(AudioFormat::U8, buffer.spec().format());
+        assert_eq!(4410, buffer.data.len());
+        // a square wave at half volume, fading out towards the end
+        assert!(buffer.data[..2205]
+            .iter()
+            .all(|&sample| sample == 191 || sample == 65));
+        assert!(buffer.data[4400..]
+            .iter()
+            .all(|&sample| (120..=136).contains(&sample)));
+
+        let silent = SfxParams {
+            volume: 0.0,
+            ..params.clone()
+        };
+        assert!(generator
+            .generate(&silent)
+            .data
+            .iter()
+            .all(|&sample| sample == 128));
+
+        // sliding down past the frequency limit ends the sound early
+        let limited = SfxParams {
+            frequency_slide: -10.0,
+            frequency_limit: 220.0,
+            ..params
+        };
+        let length = generator.generate(&limited).data.len();
+        assert!(length > 2000 && length < 2300);
+    }
+
+    #[test]
+    pub fn random_sounds_are_repeatable() {
+        let generator = generator();
+        for category in [
+            SfxCategory::Pickup,
+            SfxCategory::Laser,
+            SfxCategory::Explosion,
+            SfxCategory::Powerup,
+            SfxCategory::Hit,
+            SfxCategory::Jump,
+            SfxCategory::Blip,
+        ] {
+            let params = SfxParams::random(category, &mut Prng::new(1234));
+            assert_eq!(params, SfxParams::random(category, &mut Prng::new(1234)));
+            assert_ne!(params, SfxParams::random(category, &mut Prng::new(4321)));
+
+            let buffer = generator.generate(&params);
+            assert!(!buffer.data.is_empty());
+            assert_eq!(buffer, generator.generate(&params));
+        }
+    }
+}
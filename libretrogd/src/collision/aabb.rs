@@ -0,0 +1,110 @@
+use crate::math::*;
+
+/// An axis-aligned bounding box, using floating point coordinates. Unlike [Rect], the box covers
+/// the area from `x` to `x + width` and `y` to `y + height`, with the right and bottom edges
+/// being exclusive, which is more convenient for collision checks against movement that is not
+/// pixel-aligned.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Aabb {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Aabb {
+    #[inline]
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Aabb {
+        Aabb {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a new box covering the same area as the given rect.
+    #[inline]
+    pub fn from_rect(rect: &Rect) -> Aabb {
+        Aabb::new(
+            rect.x as f32,
+            rect.y as f32,
+            rect.width as f32,
+            rect.height as f32,
+        )
+    }
+
+    /// Returns the box's top-left corner.
+    #[inline]
+    pub fn position(&self) -> Vector2 {
+        Vector2::new(self.x, self.y)
+    }
+
+    /// Returns the x coordinate of the box's (exclusive) right edge.
+    #[inline]
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    /// Returns the y coordinate of the box's (exclusive) bottom edge.
+    #[inline]
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    /// Returns the point at the center of the box.
+    #[inline]
+    pub fn center(&self) -> Vector2 {
+        Vector2::new(self.x + self.width * 0.5, self.y + self.height * 0.5)
+    }
+
+    /// Returns true if the given point is inside the box.
+    #[inline]
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        x >= self.x && y >= self.y && x < self.right() && y < self.bottom()
+    }
+
+    /// Returns true if the given box overlaps this one. Boxes which only touch along an edge do
+    /// not overlap.
+    #[inline]
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    /// Returns a copy of this box, moved by the given amount.
+    #[inline]
+    pub fn translate(&self, delta: Vector2) -> Aabb {
+        Aabb::new(self.x + delta.x, self.y + delta.y, self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_edges_and_overlaps() {
+        let a = Aabb::new(2.0, 4.0, 8.0, 6.0);
+        assert_eq!(10.0, a.right());
+        assert_eq!(10.0, a.bottom());
+        assert_eq!(Vector2::new(6.0, 7.0), a.center());
+        assert_eq!(a, Aabb::from_rect(&Rect::new(2, 4, 8, 6)));
+
+        assert!(a.contains_point(2.0, 4.0));
+        assert!(a.contains_point(9.9, 9.9));
+        assert!(!a.contains_point(10.0, 5.0));
+        assert!(!a.contains_point(5.0, 10.0));
+
+        assert!(a.overlaps(&Aabb::new(9.5, 9.5, 4.0, 4.0)));
+        assert!(a.overlaps(&Aabb::new(4.0, 5.0, 1.0, 1.0)));
+        assert!(!a.overlaps(&Aabb::new(10.0, 4.0, 4.0, 4.0)));
+        assert!(!a.overlaps(&Aabb::new(2.0, 0.0, 8.0, 4.0)));
+
+        let b = a.translate(Vector2::new(-2.0, 1.5));
+        assert_eq!(Vector2::new(0.0, 5.5), b.position());
+        assert_eq!(8.0, b.width);
+    }
+}
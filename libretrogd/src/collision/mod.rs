@@ -0,0 +1,5 @@
+pub use self::aabb::*;
+pub use self::tilecollision::*;
+
+pub mod aabb;
+pub mod tilecollision;
@@ -0,0 +1,664 @@
+use crate::collision::*;
+use crate::graphics::*;
+use crate::math::*;
+
+// how close to a tile's edge a box's edge needs to be to be considered touching it. keeps boxes
+// which have been snapped to a tile's edge from being treated as overlapping it due to rounding.
+const EDGE_EPSILON: f32 = 0.001;
+
+/// How a single tile in a [CollisionMap] collides with things moving through it.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum TileCollision {
+    #[default]
+    Empty,
+    /// Blocks movement in all directions.
+    Solid,
+    /// Only blocks movement downwards onto the top of the tile, for platforms which can be
+    /// jumped up through from below.
+    OneWay,
+}
+
+/// Options for [CollisionMap::move_and_collide].
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MoveOptions {
+    /// The highest obstacle (in pixels) which a box moving horizontally will automatically step
+    /// up onto instead of being stopped by, such as stairs or small bumps in the ground. 0.0 to
+    /// never step up.
+    pub step_height: f32,
+    /// If true, one-way platforms are fallen through instead of being landed on.
+    pub drop_through: bool,
+}
+
+/// The result of [CollisionMap::move_and_collide].
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MoveResult {
+    /// The box at its new position.
+    pub aabb: Aabb,
+    /// True if horizontal movement was stopped by a wall.
+    pub hit_wall: bool,
+    /// True if upwards movement was stopped by a ceiling.
+    pub hit_ceiling: bool,
+    /// True if downwards movement was stopped by the ground.
+    pub hit_floor: bool,
+    /// True if the box is standing on the ground after moving. Always false when moving upwards.
+    pub on_ground: bool,
+    /// True if the box stepped up onto an obstacle while moving horizontally.
+    pub stepped_up: bool,
+}
+
+/// Returns the first and last cells covered by the span from `start` to `start + size`, using
+/// cells of the given size.
+#[inline]
+fn cell_span(start: f32, size: f32, cell_size: f32) -> (i32, i32) {
+    (
+        ((start + EDGE_EPSILON) / cell_size).floor() as i32,
+        ((start + size - EDGE_EPSILON) / cell_size).floor() as i32,
+    )
+}
+
+/// A grid describing which tiles of a tile map are solid, for platformer-style movement and
+/// collision checks against a [TileMap]. This is kept separate from the tile map itself so that
+/// collision can be built once from whichever layer (or combination of tile properties) a game
+/// wants, and modified independently from what is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionMap {
+    /// How everything outside of the map collides. Defaults to [TileCollision::Empty], but
+    /// setting this to [TileCollision::Solid] keeps things from leaving the map.
+    pub outside: TileCollision,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    cells: Box<[TileCollision]>,
+}
+
+impl CollisionMap {
+    /// Creates a new collision map with all tiles empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the map, in tiles
+    /// * `height`: the height of the map, in tiles
+    /// * `tile_width`: the width of each tile, in pixels
+    /// * `tile_height`: the height of each tile, in pixels
+    pub fn new(width: u32, height: u32, tile_width: u32, tile_height: u32) -> CollisionMap {
+        CollisionMap {
+            outside: TileCollision::Empty,
+            width,
+            height,
+            tile_width: tile_width.max(1),
+            tile_height: tile_height.max(1),
+            cells: vec![TileCollision::Empty; (width * height) as usize].into_boxed_slice(),
+        }
+    }
+
+    /// Creates a new collision map from one of a tile map's layers, using [TILE_FLAG_SOLID] and
+    /// [TILE_FLAG_ONE_WAY] to decide which tiles are solid. Returns `None` if the layer does not
+    /// exist.
+    pub fn from_layer(map: &TileMap, layer: usize) -> Option<CollisionMap> {
+        Self::from_layer_with(map, layer, |tile| {
+            if tile.has_flags(TILE_FLAG_SOLID) {
+                TileCollision::Solid
+            } else if tile.has_flags(TILE_FLAG_ONE_WAY) {
+                TileCollision::OneWay
+            } else {
+                TileCollision::Empty
+            }
+        })
+    }
+
+    /// Creates a new collision map from one of a tile map's layers, using the given function to
+    /// decide how each tile collides (for example, by tileset index). Returns `None` if the layer
+    /// does not exist.
+    pub fn from_layer_with(
+        map: &TileMap,
+        layer: usize,
+        mut f: impl FnMut(&Tile) -> TileCollision,
+    ) -> Option<CollisionMap> {
+        let layer = map.layer(layer)?;
+        let mut collision = CollisionMap::new(
+            map.width(),
+            map.height(),
+            map.tile_width(),
+            map.tile_height(),
+        );
+        for (cell, tile) in collision.cells.iter_mut().zip(layer.tiles().iter()) {
+            *cell = f(tile);
+        }
+        Some(collision)
+    }
+
+    /// Returns the width of the map, in tiles.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the map, in tiles.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the width of each tile, in pixels.
+    #[inline]
+    pub fn tile_width(&self) -> u32 {
+        self.tile_width
+    }
+
+    /// Returns the height of each tile, in pixels.
+    #[inline]
+    pub fn tile_height(&self) -> u32 {
+        self.tile_height
+    }
+
+    /// Returns how the tile at the given tile coordinates collides, which is
+    /// [CollisionMap::outside] for coordinates outside of the map.
+    #[inline]
+    pub fn get(&self, x: i32, y: i32) -> TileCollision {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            self.cells[(y as u32 * self.width + x as u32) as usize]
+        } else {
+            self.outside
+        }
+    }
+
+    /// Sets how the tile at the given tile coordinates collides. Returns false if the coordinates
+    /// are outside of the map.
+    pub fn set(&mut self, x: i32, y: i32, collision: TileCollision) -> bool {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            self.cells[(y as u32 * self.width + x as u32) as usize] = collision;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns how the tile at the given world (pixel) coordinates collides.
+    #[inline]
+    pub fn get_at_world(&self, world_x: f32, world_y: f32) -> TileCollision {
+        self.get(
+            (world_x / self.tile_width as f32).floor() as i32,
+            (world_y / self.tile_height as f32).floor() as i32,
+        )
+    }
+
+    #[inline]
+    fn columns(&self, aabb: &Aabb) -> (i32, i32) {
+        cell_span(aabb.x, aabb.width, self.tile_width as f32)
+    }
+
+    #[inline]
+    fn rows(&self, aabb: &Aabb) -> (i32, i32) {
+        cell_span(aabb.y, aabb.height, self.tile_height as f32)
+    }
+
+    /// Returns true if the given box overlaps any solid tiles. One-way platforms are ignored.
+    pub fn overlaps_solid(&self, aabb: &Aabb) -> bool {
+        let (first_column, last_column) = self.columns(aabb);
+        let (first_row, last_row) = self.rows(aabb);
+        (first_row..=last_row)
+            .any(|y| (first_column..=last_column).any(|x| self.get(x, y) == TileCollision::Solid))
+    }
+
+    /// Returns true if the given box is standing directly on top of a solid tile or one-way
+    /// platform.
+    pub fn is_on_ground(&self, aabb: &Aabb) -> bool {
+        self.ground_below(aabb, true)
+    }
+
+    fn ground_below(&self, aabb: &Aabb, include_one_way: bool) -> bool {
+        let (_, last_row) = self.rows(aabb);
+        let row = last_row + 1;
+        if (row as f32 * self.tile_height as f32 - aabb.bottom()).abs() > EDGE_EPSILON {
+            return false;
+        }
+        let (first_column, last_column) = self.columns(aabb);
+        (first_column..=last_column).any(|x| match self.get(x, row) {
+            TileCollision::Solid => true,
+            TileCollision::OneWay => include_one_way,
+            TileCollision::Empty => false,
+        })
+    }
+
+    #[inline]
+    fn column_blocked(&self, x: i32, aabb: &Aabb) -> bool {
+        let (first_row, last_row) = self.rows(aabb);
+        (first_row..=last_row).any(|y| self.get(x, y) == TileCollision::Solid)
+    }
+
+    /// Returns how far the box needs to be raised to step up onto the obstacle in the given
+    /// column, or `None` if it is not an obstacle that can be stepped onto.
+    fn step_up_height(
+        &self,
+        x: i32,
+        aabb: &Aabb,
+        step_height: f32,
+        moving_right: bool,
+    ) -> Option<f32> {
+        let tile_height = self.tile_height as f32;
+        let (_, last_row) = self.rows(aabb);
+        // the obstacle has to be at the box's feet, and not a wall that it is walking into
+        if step_height <= 0.0 || self.get(x, last_row) != TileCollision::Solid {
+            return None;
+        }
+        let mut top_row = last_row;
+        while self.get(x, top_row - 1) == TileCollision::Solid {
+            top_row -= 1;
+            if aabb.bottom() - top_row as f32 * tile_height > step_height + EDGE_EPSILON {
+                return None;
+            }
+        }
+        let lift = aabb.bottom() - top_row as f32 * tile_height;
+        if lift <= 0.0 || lift > step_height + EDGE_EPSILON {
+            return None;
+        }
+
+        // make sure there is room for the raised box everywhere between where it is now and the
+        // top of the obstacle
+        let tile_width = self.tile_width as f32;
+        let clearance = if moving_right {
+            Aabb::new(
+                aabb.x,
+                aabb.y - lift,
+                (x + 1) as f32 * tile_width - aabb.x,
+                aabb.height,
+            )
+        } else {
+            let left = x as f32 * tile_width;
+            Aabb::new(left, aabb.y - lift, aabb.right() - left, aabb.height)
+        };
+        if self.overlaps_solid(&clearance) {
+            None
+        } else {
+            Some(lift)
+        }
+    }
+
+    /// Moves the box horizontally, returning whether it hit a wall and whether it stepped up.
+    fn sweep_x(&self, aabb: &mut Aabb, dx: f32, step_height: f32) -> (bool, bool) {
+        if dx == 0.0 {
+            return (false, false);
+        }
+        let tile_width = self.tile_width as f32;
+        let target = Aabb::new(aabb.x + dx, aabb.y, aabb.width, aabb.height);
+        let (first_column, last_column) = self.columns(aabb);
+        let (target_first_column, target_last_column) = self.columns(&target);
+        let moving_right = dx > 0.0;
+        let mut stepped_up = false;
+
+        // check every column of tiles passed through (not just the destination), so that fast
+        // moving boxes cannot pass through thin walls
+        let mut x = if moving_right {
+            last_column + 1
+        } else {
+            first_column - 1
+        };
+        while (moving_right && x <= target_last_column)
+            || (!moving_right && x >= target_first_column)
+        {
+            if self.column_blocked(x, aabb) {
+                if let Some(lift) = self.step_up_height(x, aabb, step_height, moving_right) {
+                    aabb.y -= lift;
+                    stepped_up = true;
+                } else {
+                    aabb.x = if moving_right {
+                        x as f32 * tile_width - aabb.width
+                    } else {
+                        (x + 1) as f32 * tile_width
+                    };
+                    return (true, stepped_up);
+                }
+            }
+            x += if moving_right { 1 } else { -1 };
+        }
+        aabb.x = target.x;
+        (false, stepped_up)
+    }
+
+    /// Moves the box vertically, returning whether it hit something.
+    fn sweep_y(&self, aabb: &mut Aabb, dy: f32, drop_through: bool) -> bool {
+        if dy == 0.0 {
+            return false;
+        }
+        let tile_height = self.tile_height as f32;
+        let target = Aabb::new(aabb.x, aabb.y + dy, aabb.width, aabb.height);
+        let (first_column, last_column) = self.columns(aabb);
+        let (first_row, last_row) = self.rows(aabb);
+        let (target_first_row, target_last_row) = self.rows(&target);
+
+        if dy > 0.0 {
+            // only rows entirely below the box are checked, so one-way platforms which the box
+            // is already partway through (e.g. from jumping up through them) are not landed on
+            for y in (last_row + 1)..=target_last_row {
+                let blocked = (first_column..=last_column).any(|x| match self.get(x, y) {
+                    TileCollision::Solid => true,
+                    TileCollision::OneWay => !drop_through,
+                    TileCollision::Empty => false,
+                });
+                if blocked {
+                    aabb.y = y as f32 * tile_height - aabb.height;
+                    return true;
+                }
+            }
+        } else {
+            for y in (target_first_row..first_row).rev() {
+                if (first_column..=last_column).any(|x| self.get(x, y) == TileCollision::Solid) {
+                    aabb.y = (y + 1) as f32 * tile_height;
+                    return true;
+                }
+            }
+        }
+        aabb.y = target.y;
+        false
+    }
+
+    /// Moves a box through the map, stopping it against any solid tiles in the way. Movement is
+    /// resolved horizontally first and then vertically, and every tile passed through is checked
+    /// so that even very fast movement cannot skip over thin walls or floors. One-way platforms
+    /// only stop downwards movement, and only when the box starts out above them.
+    ///
+    /// The box should not already be overlapping any solid tiles.
+    ///
+    /// # Arguments
+    ///
+    /// * `aabb`: the box to move, in world (pixel) coordinates
+    /// * `delta`: how far to move the box
+    /// * `options`: step height and one-way platform options
+    pub fn move_and_collide(
+        &self,
+        aabb: &Aabb,
+        delta: Vector2,
+        options: &MoveOptions,
+    ) -> MoveResult {
+        let mut aabb = *aabb;
+        let (hit_wall, stepped_up) = self.sweep_x(&mut aabb, delta.x, options.step_height);
+        let hit_y = self.sweep_y(&mut aabb, delta.y, options.drop_through);
+        MoveResult {
+            aabb,
+            hit_wall,
+            hit_ceiling: hit_y && delta.y < 0.0,
+            hit_floor: hit_y && delta.y > 0.0,
+            on_ground: delta.y >= 0.0 && self.ground_below(&aabb, !options.drop_through),
+            stepped_up,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // an 8x6 map of 16x16 tiles:
+    //
+    //   .......#
+    //   .......#
+    //   ..==...#
+    //   .......#
+    //   ....#..#
+    //   ########
+    fn test_map() -> CollisionMap {
+        let mut map = CollisionMap::new(8, 6, 16, 16);
+        for x in 0..8 {
+            map.set(x, 5, TileCollision::Solid);
+        }
+        for y in 0..5 {
+            map.set(7, y, TileCollision::Solid);
+        }
+        map.set(4, 4, TileCollision::Solid);
+        map.set(2, 2, TileCollision::OneWay);
+        map.set(3, 2, TileCollision::OneWay);
+        map
+    }
+
+    fn move_by(
+        map: &CollisionMap,
+        aabb: Aabb,
+        dx: f32,
+        dy: f32,
+        options: MoveOptions,
+    ) -> MoveResult {
+        map.move_and_collide(&aabb, Vector2::new(dx, dy), &options)
+    }
+
+    #[test]
+    pub fn building_from_layers() {
+        let mut tile_map = TileMap::new(3, 1, 8, 8);
+        let layer = tile_map.add_layer("ground");
+        let tiles = tile_map.layer_mut(layer).unwrap();
+        tiles.set(0, 0, Tile::with_flags(1, TILE_FLAG_SOLID));
+        tiles.set(1, 0, Tile::with_flags(2, TILE_FLAG_ONE_WAY));
+        tiles.set(2, 0, Tile::new(3));
+
+        let map = CollisionMap::from_layer(&tile_map, layer).unwrap();
+        assert_eq!(3, map.width());
+        assert_eq!(8, map.tile_height());
+        assert_eq!(TileCollision::Solid, map.get(0, 0));
+        assert_eq!(TileCollision::OneWay, map.get(1, 0));
+        assert_eq!(TileCollision::Empty, map.get(2, 0));
+        assert_eq!(TileCollision::Empty, map.get(3, 0));
+        assert_eq!(TileCollision::OneWay, map.get_at_world(15.9, 7.9));
+
+        let map = CollisionMap::from_layer_with(&tile_map, layer, |tile| {
+            if tile.index == 3 {
+                TileCollision::Solid
+            } else {
+                TileCollision::Empty
+            }
+        })
+        .unwrap();
+        assert_eq!(TileCollision::Empty, map.get(0, 0));
+        assert_eq!(TileCollision::Solid, map.get(2, 0));
+
+        assert!(CollisionMap::from_layer(&tile_map, 1).is_none());
+    }
+
+    #[test]
+    pub fn overlapping_and_ground_checks() {
+        let mut map = test_map();
+        assert!(map.overlaps_solid(&Aabb::new(60.0, 60.0, 8.0, 8.0)));
+        assert!(!map.overlaps_solid(&Aabb::new(48.0, 48.0, 16.0, 16.0)));
+        // one-way platforms are not solid
+        assert!(!map.overlaps_solid(&Aabb::new(32.0, 32.0, 8.0, 8.0)));
+
+        assert!(map.is_on_ground(&Aabb::new(16.0, 64.0, 8.0, 16.0)));
+        assert!(map.is_on_ground(&Aabb::new(40.0, 16.0, 8.0, 16.0)));
+        assert!(!map.is_on_ground(&Aabb::new(16.0, 63.0, 8.0, 16.0)));
+        assert!(!map.is_on_ground(&Aabb::new(8.0, 16.0, 8.0, 16.0)));
+
+        assert!(!map.overlaps_solid(&Aabb::new(-20.0, 0.0, 8.0, 8.0)));
+        map.outside = TileCollision::Solid;
+        assert!(map.overlaps_solid(&Aabb::new(-20.0, 0.0, 8.0, 8.0)));
+    }
+
+    #[test]
+    pub fn moving_into_floors_and_ceilings() {
+        let mut map = test_map();
+
+        // falling onto the floor
+        let result = move_by(
+            &map,
+            Aabb::new(16.0, 40.0, 8.0, 16.0),
+            0.0,
+            100.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(Aabb::new(16.0, 64.0, 8.0, 16.0), result.aabb);
+        assert!(result.hit_floor);
+        assert!(result.on_ground);
+        assert!(!result.hit_wall);
+
+        // standing still on the floor
+        let result = move_by(&map, result.aabb, 0.0, 0.0, MoveOptions::default());
+        assert!(!result.hit_floor);
+        assert!(result.on_ground);
+
+        // falling while moving diagonally
+        let result = move_by(
+            &map,
+            Aabb::new(8.0, 8.0, 8.0, 8.0),
+            10.0,
+            80.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(Aabb::new(18.0, 72.0, 8.0, 8.0), result.aabb);
+        assert!(result.hit_floor);
+
+        // jumping up with nothing above leaves the map, unless outside of the map is solid
+        let result = move_by(
+            &map,
+            Aabb::new(16.0, 20.0, 8.0, 8.0),
+            0.0,
+            -100.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(-80.0, result.aabb.y);
+        assert!(!result.hit_ceiling);
+        assert!(!result.on_ground);
+        map.outside = TileCollision::Solid;
+        let result = move_by(
+            &map,
+            Aabb::new(16.0, 20.0, 8.0, 8.0),
+            0.0,
+            -100.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(0.0, result.aabb.y);
+        assert!(result.hit_ceiling);
+    }
+
+    #[test]
+    pub fn moving_through_one_way_platforms() {
+        let map = test_map();
+
+        // landing on top
+        let result = move_by(
+            &map,
+            Aabb::new(32.0, 8.0, 8.0, 16.0),
+            0.0,
+            20.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(16.0, result.aabb.y);
+        assert!(result.hit_floor);
+        assert!(result.on_ground);
+
+        // jumping up through from below, and then falling while still partway through
+        let result = move_by(
+            &map,
+            Aabb::new(32.0, 40.0, 8.0, 16.0),
+            0.0,
+            -20.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(20.0, result.aabb.y);
+        assert!(!result.hit_ceiling);
+        let result = move_by(&map, result.aabb, 0.0, 2.0, MoveOptions::default());
+        assert_eq!(22.0, result.aabb.y);
+        assert!(!result.on_ground);
+
+        // walking sideways into the platform
+        let result = move_by(
+            &map,
+            Aabb::new(16.0, 24.0, 8.0, 16.0),
+            30.0,
+            0.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(46.0, result.aabb.x);
+        assert!(!result.hit_wall);
+
+        // dropping down through
+        let options = MoveOptions {
+            drop_through: true,
+            ..Default::default()
+        };
+        let result = move_by(&map, Aabb::new(32.0, 16.0, 8.0, 16.0), 0.0, 4.0, options);
+        assert_eq!(20.0, result.aabb.y);
+        assert!(!result.hit_floor);
+        assert!(!map.ground_below(&Aabb::new(32.0, 16.0, 8.0, 16.0), false));
+    }
+
+    #[test]
+    pub fn moving_into_walls() {
+        let map = test_map();
+
+        let result = move_by(
+            &map,
+            Aabb::new(84.0, 64.0, 8.0, 16.0),
+            100.0,
+            0.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(Aabb::new(104.0, 64.0, 8.0, 16.0), result.aabb);
+        assert!(result.hit_wall);
+        assert!(result.on_ground);
+
+        let result = move_by(
+            &map,
+            Aabb::new(90.0, 64.0, 8.0, 16.0),
+            -20.0,
+            0.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(80.0, result.aabb.x);
+        assert!(result.hit_wall);
+
+        // fast movement does not skip over walls
+        let result = move_by(
+            &map,
+            Aabb::new(8.0, 64.0, 4.0, 4.0),
+            500.0,
+            0.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(60.0, result.aabb.x);
+        assert!(result.hit_wall);
+
+        // moving away from a wall that the box is touching
+        let result = move_by(
+            &map,
+            Aabb::new(104.0, 64.0, 8.0, 16.0),
+            -4.0,
+            0.0,
+            MoveOptions::default(),
+        );
+        assert_eq!(100.0, result.aabb.x);
+        assert!(!result.hit_wall);
+    }
+
+    #[test]
+    pub fn stepping_up() {
+        let map = test_map();
+        let options = MoveOptions {
+            step_height: 16.0,
+            ..Default::default()
+        };
+
+        let result = move_by(&map, Aabb::new(90.0, 64.0, 8.0, 16.0), -20.0, 0.0, options);
+        assert_eq!(Aabb::new(70.0, 48.0, 8.0, 16.0), result.aabb);
+        assert!(result.stepped_up);
+        assert!(!result.hit_wall);
+        assert!(result.on_ground);
+
+        // too high to step up onto
+        let options = MoveOptions {
+            step_height: 8.0,
+            ..Default::default()
+        };
+        let result = move_by(&map, Aabb::new(90.0, 64.0, 8.0, 16.0), -20.0, 0.0, options);
+        assert_eq!(Aabb::new(80.0, 64.0, 8.0, 16.0), result.aabb);
+        assert!(!result.stepped_up);
+        assert!(result.hit_wall);
+
+        // walls taller than the step height are not stepped up onto
+        let options = MoveOptions {
+            step_height: 24.0,
+            ..Default::default()
+        };
+        let result = move_by(&map, Aabb::new(84.0, 64.0, 8.0, 16.0), 100.0, 0.0, options);
+        assert_eq!(104.0, result.aabb.x);
+        assert!(result.hit_wall);
+        assert!(!result.stepped_up);
+    }
+}
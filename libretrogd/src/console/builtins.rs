@@ -0,0 +1,241 @@
+use std::path::{Path, PathBuf};
+
+use crate::console::*;
+use crate::entities::*;
+
+/// Returns the path of the first "screenshot_NNNN.png" file in the current directory that does
+/// not exist yet.
+fn next_screenshot_path() -> PathBuf {
+    let mut index = 0;
+    loop {
+        let path = PathBuf::from(format!("screenshot_{:04}.png", index));
+        if !path.exists() {
+            return path;
+        }
+        index += 1;
+    }
+}
+
+/// Built-in commands that can be added to a console, each given a function to get whatever it
+/// needs from the context passed to the console's commands.
+impl<ContextType: 'static> Console<ContextType> {
+    /// Registers the "palette" and "setcolor" commands, for showing and changing palette colors.
+    ///
+    /// * `palette <index> [<count>]`: shows one or more colors, starting from the given index
+    /// * `setcolor <index> <r> <g> <b>`: changes a color
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: a function returning the palette the commands use from the context, usually
+    ///   the [`System`](crate::System)'s palette
+    pub fn register_palette_commands(&mut self, palette: fn(&mut ContextType) -> &mut Palette) {
+        self.register(
+            "palette",
+            "Shows palette colors: palette <index> [<count>]",
+            move |context, args| {
+                args.expect_count(1, 2)?;
+                let index: u8 = args.parse(0)?;
+                let count = args.parse_or(1, 1usize)?.min(256 - index as usize);
+                let palette = palette(context);
+                let lines: Vec<String> = (index as usize..index as usize + count)
+                    .map(|index| {
+                        let (r, g, b) = from_rgb32(palette[index as u8]);
+                        format!(
+                            "{:3}: #{:02x}{:02x}{:02x} ({}, {}, {})",
+                            index, r, g, b, r, g, b
+                        )
+                    })
+                    .collect();
+                Ok(lines.join("\n"))
+            },
+        );
+        self.register(
+            "setcolor",
+            "Changes a palette color: setcolor <index> <r> <g> <b>",
+            move |context, args| {
+                args.expect_count(4, 4)?;
+                let index: u8 = args.parse(0)?;
+                let (r, g, b) = (args.parse(1)?, args.parse(2)?, args.parse(3)?);
+                palette(context)[index] = to_rgb32(r, g, b);
+                Ok(String::new())
+            },
+        );
+    }
+
+    /// Registers the "entities" and "entity" commands, for inspecting entities.
+    ///
+    /// * `entities`: shows the number of entities and the number of components of each type
+    /// * `entity <id>`: shows the types of all of the components an entity has
+    ///
+    /// # Arguments
+    ///
+    /// * `entities`: a function returning the entities the commands use from the context. None of
+    ///   its component stores can be mutably borrowed when these commands are run.
+    pub fn register_entity_commands(&mut self, entities: fn(&mut ContextType) -> &Entities) {
+        self.register(
+            "entities",
+            "Shows the number of entities and components",
+            move |context, args| {
+                args.expect_count(0, 0)?;
+                let entities = entities(context);
+                let mut lines = vec![format!("{} entities", entities.len())];
+                for (type_name, count) in entities.component_counts() {
+                    lines.push(format!("{:6} {}", count, type_name));
+                }
+                Ok(lines.join("\n"))
+            },
+        );
+        self.register(
+            "entity",
+            "Shows an entity's components: entity <id>",
+            move |context, args| {
+                args.expect_count(1, 1)?;
+                let entity: EntityId = args.parse(0)?;
+                let entities = entities(context);
+                if !entities.has_entity(entity) {
+                    return Err(ConsoleError::CommandFailed(format!(
+                        "No entity with id {}",
+                        entity
+                    )));
+                }
+                let mut lines = vec![format!("Entity {}:", entity)];
+                for type_name in entities.component_type_names(entity) {
+                    lines.push(format!("  {}", type_name));
+                }
+                Ok(lines.join("\n"))
+            },
+        );
+    }
+
+    /// Registers the "screenshot" command, which saves a screenshot using
+    /// [`capture_screenshot`].
+    ///
+    /// * `screenshot [<path>]`: saves a screenshot to the given file, or if no path is given, to
+    ///   the first "screenshot_NNNN.png" file in the current directory that does not exist yet
+    ///
+    /// # Arguments
+    ///
+    /// * `screen`: a function returning the bitmap to capture and the palette to capture it with
+    ///   from the context, usually the [`System`](crate::System)'s `video` and `palette`. Note that the
+    ///   console itself will be captured if it has already been rendered onto the bitmap.
+    pub fn register_screenshot_command(
+        &mut self,
+        screen: fn(&mut ContextType) -> (&Bitmap, &Palette),
+    ) {
+        self.register(
+            "screenshot",
+            "Saves a screenshot: screenshot [<path>]",
+            move |context, args| {
+                args.expect_count(0, 1)?;
+                let path = match args.get(0) {
+                    Some(path) => PathBuf::from(path),
+                    None => next_screenshot_path(),
+                };
+                let (bitmap, palette) = screen(context);
+                match capture_screenshot(bitmap, palette, Path::new(&path)) {
+                    Ok(()) => Ok(format!("Saved screenshot to {}", path.display())),
+                    Err(error) => Err(ConsoleError::CommandFailed(format!(
+                        "Failed to save screenshot: {}",
+                        error
+                    ))),
+                }
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    struct Game {
+        palette: Palette,
+        screen: Bitmap,
+        entities: Entities,
+    }
+
+    #[derive(Debug)]
+    struct Position(i32, i32);
+
+    fn new_game() -> (Console<Game>, Game) {
+        let mut console = Console::new();
+        console.register_palette_commands(|game: &mut Game| &mut game.palette);
+        console.register_entity_commands(|game: &mut Game| &game.entities);
+        console.register_screenshot_command(|game: &mut Game| (&game.screen, &game.palette));
+        let game = Game {
+            palette: Palette::new_vga_palette().unwrap(),
+            screen: Bitmap::new(16, 16).unwrap(),
+            entities: Entities::new(),
+        };
+        (console, game)
+    }
+
+    fn last_output(console: &Console<Game>) -> String {
+        console.output().last().unwrap().text.clone()
+    }
+
+    #[test]
+    pub fn palette_commands() {
+        let (mut console, mut game) = new_game();
+        assert_ok!(console.execute(&mut game, "setcolor 1 255 128 0"));
+        assert_eq!(to_rgb32(255, 128, 0), game.palette[1]);
+        assert_ok!(console.execute(&mut game, "palette 0 2"));
+        assert_eq!("  1: #ff8000 (255, 128, 0)", last_output(&console));
+        assert_eq!(2, console.output().count());
+        assert_ok!(console.execute(&mut game, "palette 255 10"));
+        assert_eq!(3, console.output().count());
+        assert_err!(console.execute(&mut game, "palette 256"));
+        assert_err!(console.execute(&mut game, "setcolor 1 255 128"));
+    }
+
+    #[test]
+    pub fn entity_commands() {
+        let (mut console, mut game) = new_game();
+        let a = game.entities.new_entity();
+        game.entities.add_component(a, Position(1, 2));
+        game.entities.new_entity();
+
+        assert_ok!(console.execute(&mut game, "entities"));
+        assert_eq!(
+            format!("{:6} {}", 1, std::any::type_name::<Position>()),
+            last_output(&console)
+        );
+        assert_eq!("2 entities", console.output().next().unwrap().text);
+        assert_ok!(console.execute(&mut game, &format!("entity {}", a)));
+        assert_eq!(
+            format!("  {}", std::any::type_name::<Position>()),
+            last_output(&console)
+        );
+        assert_matches!(
+            console.execute(&mut game, "entity 100"),
+            Err(ConsoleError::CommandFailed(..))
+        );
+        // listing an entity's components should leave them as they were
+        let positions = game.entities.components::<Position>().unwrap();
+        let position = positions.get(&a).unwrap();
+        assert_eq!((1, 2), (position.0, position.1));
+    }
+
+    #[test]
+    pub fn screenshot_command() {
+        let (mut console, mut game) = new_game();
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path().join("shot.pcx");
+        game.screen.set_pixel(3, 4, 5);
+
+        let command = format!(
+            "screenshot \"{}\"",
+            path.to_string_lossy().replace('\\', "\\\\")
+        );
+        assert_ok!(console.execute(&mut game, &command));
+        let (bitmap, _) = Bitmap::load_file(&path).unwrap();
+        assert_eq!(Some(5), bitmap.get_pixel(3, 4));
+        assert_matches!(
+            console.execute(&mut game, "screenshot shot.xyz"),
+            Err(ConsoleError::CommandFailed(..))
+        );
+    }
+}
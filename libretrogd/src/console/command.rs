@@ -0,0 +1,208 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum ConsoleError {
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+
+    #[error("Invalid command line: {0}")]
+    BadCommandLine(String),
+
+    #[error("Bad arguments: {0}")]
+    BadArguments(String),
+
+    #[error("{0}")]
+    CommandFailed(String),
+}
+
+/// Splits a line of text entered into a console into the command name and its arguments.
+/// Arguments are separated by whitespace, unless enclosed in double quotes. Within double
+/// quotes, a backslash can be used to include a literal double quote or backslash.
+///
+/// ```
+/// use libretrogd::console::*;
+///
+/// let (name, args) = parse_command_line(r#"say "hello, world" 3"#).unwrap().unwrap();
+/// assert_eq!("say", name);
+/// assert_eq!(Some("hello, world"), args.get(0));
+/// assert_eq!(3, args.parse::<u32>(1).unwrap());
+/// ```
+///
+/// returns: the command name and its arguments, or `None` if the line is blank
+pub fn parse_command_line(line: &str) -> Result<Option<(String, ConsoleArgs)>, ConsoleError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let c = match chars.next() {
+            Some(c) => c,
+            None => break,
+        };
+        let mut token = String::new();
+        if c == '"' {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c @ ('"' | '\\')) => token.push(c),
+                        Some(c) => {
+                            token.push('\\');
+                            token.push(c);
+                        }
+                        None => break,
+                    },
+                    Some(c) => token.push(c),
+                    None => {
+                        return Err(ConsoleError::BadCommandLine(String::from(
+                            "Missing closing quote",
+                        )))
+                    }
+                }
+            }
+        } else {
+            token.push(c);
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+        }
+        tokens.push(token);
+    }
+
+    let mut tokens = tokens.into_iter();
+    Ok(tokens
+        .next()
+        .map(|name| (name, ConsoleArgs::new(tokens.collect()))))
+}
+
+/// The arguments given to a console command, not including the command name itself.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ConsoleArgs {
+    args: Vec<String>,
+}
+
+impl ConsoleArgs {
+    /// Creates and returns a new set of arguments.
+    pub fn new(args: Vec<String>) -> Self {
+        ConsoleArgs { args }
+    }
+
+    /// Returns the number of arguments.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    /// Returns true if there are no arguments.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    /// Returns the argument at the given index, or `None` if there are not that many arguments.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(|arg| arg.as_str())
+    }
+
+    /// Returns an iterator over all of the arguments.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().map(|arg| arg.as_str())
+    }
+
+    /// Returns the argument at the given index parsed as the given type, or an error suitable for
+    /// returning from a command if the argument is missing or could not be parsed.
+    pub fn parse<T: FromStr>(&self, index: usize) -> Result<T, ConsoleError> {
+        match self.get(index) {
+            Some(arg) => arg.parse().map_err(|_| {
+                ConsoleError::BadArguments(format!("Invalid argument {}: {}", index + 1, arg))
+            }),
+            None => Err(ConsoleError::BadArguments(format!(
+                "Missing argument {}",
+                index + 1
+            ))),
+        }
+    }
+
+    /// Returns the argument at the given index parsed as the given type, or the given default if
+    /// there are not that many arguments. An error is still returned if the argument is present
+    /// but could not be parsed.
+    pub fn parse_or<T: FromStr>(&self, index: usize, default: T) -> Result<T, ConsoleError> {
+        if index < self.len() {
+            self.parse(index)
+        } else {
+            Ok(default)
+        }
+    }
+
+    /// Returns an error suitable for returning from a command if there are not between `min` and
+    /// `max` (inclusive) arguments.
+    pub fn expect_count(&self, min: usize, max: usize) -> Result<(), ConsoleError> {
+        if self.len() < min || self.len() > max {
+            Err(ConsoleError::BadArguments(if min == max {
+                format!("Expected {} argument(s), got {}", min, self.len())
+            } else {
+                format!("Expected {} to {} arguments, got {}", min, max, self.len())
+            }))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    fn parse(line: &str) -> Option<(String, Vec<String>)> {
+        parse_command_line(line)
+            .unwrap()
+            .map(|(name, args)| (name, args.iter().map(String::from).collect()))
+    }
+
+    #[test]
+    pub fn parsing_command_lines() {
+        assert_none!(parse(""));
+        assert_none!(parse("   "));
+        assert_eq!(Some((String::from("help"), vec![])), parse("help"));
+        assert_eq!(
+            Some((
+                String::from("give"),
+                vec![String::from("sword"), String::from("2")]
+            )),
+            parse("  give\tsword   2 ")
+        );
+        assert_eq!(
+            Some((
+                String::from("say"),
+                vec![
+                    String::from("a \"quoted\" \\ string"),
+                    String::from(""),
+                    String::from("c:\\path")
+                ]
+            )),
+            parse(r#"say "a \"quoted\" \\ string" "" c:\path"#)
+        );
+        assert_matches!(
+            parse_command_line("say \"oops"),
+            Err(ConsoleError::BadCommandLine(..))
+        );
+    }
+
+    #[test]
+    pub fn parsing_arguments() {
+        let args = ConsoleArgs::new(vec![String::from("12"), String::from("abc")]);
+        assert_eq!(2, args.len());
+        assert_eq!(Ok(12), args.parse::<u8>(0));
+        assert_matches!(args.parse::<u8>(1), Err(ConsoleError::BadArguments(..)));
+        assert_matches!(args.parse::<u8>(2), Err(ConsoleError::BadArguments(..)));
+        assert_eq!(Ok(7), args.parse_or::<u8>(2, 7));
+        assert_err!(args.parse_or::<u8>(1, 7));
+        assert_ok!(args.expect_count(1, 2));
+        assert_err!(args.expect_count(0, 1));
+        assert_err!(args.expect_count(3, 3));
+    }
+}
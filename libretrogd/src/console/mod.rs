@@ -0,0 +1,793 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Formatter;
+
+use sdl2::keyboard::Scancode;
+
+use crate::graphics::*;
+use crate::system::*;
+use crate::SCREEN_HEIGHT;
+
+pub use self::command::*;
+
+pub mod builtins;
+pub mod command;
+
+const DEFAULT_MAX_OUTPUT_LINES: usize = 200;
+const DEFAULT_MAX_HISTORY: usize = 50;
+const SCROLL_LINES: usize = 4;
+const PROMPT: &str = "> ";
+
+const BUILTIN_COMMANDS: [(&str, &str); 3] = [
+    ("clear", "Clears the console output"),
+    ("help", "Lists all commands, or describes the given command"),
+    ("history", "Lists previously entered commands"),
+];
+
+type CommandFn<ContextType> =
+    Box<dyn FnMut(&mut ContextType, &ConsoleArgs) -> Result<String, ConsoleError>>;
+
+struct ConsoleCommand<ContextType> {
+    description: String,
+    func: CommandFn<ContextType>,
+}
+
+/// The kinds of lines shown in a [`Console`]'s output.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConsoleLineKind {
+    /// Output from a command, or printed with [`Console::print`].
+    Output,
+    /// An error from a command, or printed with [`Console::print_error`].
+    Error,
+    /// A command line that was entered, echoed back.
+    Input,
+}
+
+/// A single line of a [`Console`]'s output.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConsoleLine {
+    pub text: String,
+    pub kind: ConsoleLineKind,
+}
+
+/// The palette colors a [`Console`] is rendered with.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ConsoleColors {
+    pub background: u8,
+    pub border: u8,
+    pub output: u8,
+    pub error: u8,
+    pub input: u8,
+}
+
+impl Default for ConsoleColors {
+    fn default() -> Self {
+        ConsoleColors {
+            background: 0,
+            border: 7,
+            output: 7,
+            error: 12,
+            input: 15,
+        }
+    }
+}
+
+/// An in-game developer console which drops down from the top of the screen when its toggle key
+/// (the backquote/tilde key by default) is pressed, letting commands be typed in and run while
+/// the game is running. Commands are registered along with a function to run them, which is
+/// passed the game's context and the command's arguments. Previously entered commands can be
+/// recalled with the up and down keys, command names are completed with tab, and the output can
+/// be scrolled with page up and page down.
+///
+/// The "help", "clear" and "history" commands are always available. Other built-in commands for
+/// inspecting the palette, dumping entities and taking screenshots can be added with
+/// [`Console::register_palette_commands`], [`Console::register_entity_commands`] and
+/// [`Console::register_screenshot_command`].
+///
+/// While open, the console uses the [`System`]'s text input mode (see [`TextInput`]) for the
+/// command line, so games should generally ignore their usual keyboard controls while
+/// [`Console::is_open`] returns true.
+///
+/// ```no_run
+/// use libretrogd::console::*;
+/// use libretrogd::system::*;
+///
+/// struct Game {
+///     god_mode: bool,
+/// }
+///
+/// let mut system = SystemBuilder::new().window_title("Game").build().unwrap();
+/// let mut game = Game { god_mode: false };
+///
+/// let mut console = Console::new();
+/// console.register("god", "Toggles god mode", |game: &mut Game, _args: &ConsoleArgs| {
+///     game.god_mode = !game.god_mode;
+///     Ok(format!("God mode is now {}", if game.god_mode { "on" } else { "off" }))
+/// });
+///
+/// loop {
+///     system.do_events();
+///     if let Some(line) = console.update(&mut system) {
+///         let _ = console.execute(&mut game, &line);
+///     }
+///     // ...
+///     console.render(&mut system.video, &system.font);
+///     system.display().unwrap();
+/// }
+/// ```
+pub struct Console<ContextType> {
+    commands: BTreeMap<String, ConsoleCommand<ContextType>>,
+    output: VecDeque<ConsoleLine>,
+    max_output_lines: usize,
+    history: VecDeque<String>,
+    history_index: Option<usize>,
+    max_history: usize,
+    input: String,
+    toggle_key: Scancode,
+    open: bool,
+    started_text_input: bool,
+    height: u32,
+    position: u32,
+    scroll: usize,
+    ticks: u32,
+    pub colors: ConsoleColors,
+}
+
+impl<ContextType> std::fmt::Debug for Console<ContextType> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Console")
+            .field("commands.keys()", &self.commands.keys())
+            .field("output.len()", &self.output.len())
+            .field("history.len()", &self.history.len())
+            .field("input", &self.input)
+            .field("toggle_key", &self.toggle_key)
+            .field("open", &self.open)
+            .field("height", &self.height)
+            .field("colors", &self.colors)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<ContextType> Default for Console<ContextType> {
+    fn default() -> Self {
+        Console::new()
+    }
+}
+
+impl<ContextType> Console<ContextType> {
+    /// Creates and returns a new, closed, console with only the "help", "clear" and "history"
+    /// commands, which covers the top half of the screen when open.
+    pub fn new() -> Self {
+        Console {
+            commands: BTreeMap::new(),
+            output: VecDeque::new(),
+            max_output_lines: DEFAULT_MAX_OUTPUT_LINES,
+            history: VecDeque::new(),
+            history_index: None,
+            max_history: DEFAULT_MAX_HISTORY,
+            input: String::new(),
+            toggle_key: Scancode::Grave,
+            open: false,
+            started_text_input: false,
+            height: SCREEN_HEIGHT / 2,
+            position: 0,
+            scroll: 0,
+            ticks: 0,
+            colors: ConsoleColors::default(),
+        }
+    }
+
+    /// Returns this console with the key used to open and close it changed.
+    pub fn with_toggle_key(mut self, toggle_key: Scancode) -> Self {
+        self.toggle_key = toggle_key;
+        self
+    }
+
+    /// Returns this console with the height (in pixels) that it covers when fully open changed.
+    pub fn with_height(mut self, height: u32) -> Self {
+        self.height = height.max(1);
+        self
+    }
+
+    /// Returns this console with the maximum number of output lines and previously entered
+    /// command lines it keeps changed. The oldest are discarded once these are reached.
+    pub fn with_limits(mut self, max_output_lines: usize, max_history: usize) -> Self {
+        self.max_output_lines = max_output_lines.max(1);
+        self.max_history = max_history;
+        self
+    }
+
+    /// Registers a command, replacing any existing command with the same name (including the
+    /// built-in "help", "clear" and "history" commands). Command names cannot contain
+    /// whitespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name the command is run with
+    /// * `description`: a short description of the command, shown by "help"
+    /// * `f`: the function which runs the command, passed the context given to
+    ///   [`Console::execute`] and the command's arguments. Returns the command's output (which
+    ///   can be empty, or have multiple lines), or an error which is shown in the console.
+    pub fn register(
+        &mut self,
+        name: &str,
+        description: &str,
+        f: impl FnMut(&mut ContextType, &ConsoleArgs) -> Result<String, ConsoleError> + 'static,
+    ) {
+        self.commands.insert(
+            String::from(name),
+            ConsoleCommand {
+                description: String::from(description),
+                func: Box::new(f),
+            },
+        );
+    }
+
+    /// Removes a previously registered command, returning true if it was removed.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.commands.remove(name).is_some()
+    }
+
+    /// Returns true if a command with the given name exists, including the built-in "help",
+    /// "clear" and "history" commands.
+    pub fn has_command(&self, name: &str) -> bool {
+        self.commands.contains_key(name) || BUILTIN_COMMANDS.iter().any(|(n, _)| *n == name)
+    }
+
+    /// Returns the names of all of the commands, in sorted order.
+    pub fn command_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .commands
+            .keys()
+            .map(|name| name.as_str())
+            .chain(BUILTIN_COMMANDS.iter().map(|(name, _)| *name))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    fn description(&self, name: &str) -> Option<&str> {
+        match self.commands.get(name) {
+            Some(command) => Some(command.description.as_str()),
+            None => BUILTIN_COMMANDS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, description)| *description),
+        }
+    }
+
+    /// Returns the names of all of the commands starting with the given text, in sorted order.
+    pub fn complete(&self, prefix: &str) -> Vec<&str> {
+        self.command_names()
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .collect()
+    }
+
+    /// Returns true if the console is open (or opening).
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Returns true if any part of the console is currently visible, which includes while it is
+    /// sliding closed.
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.open || self.position > 0
+    }
+
+    /// Opens the console. It slides open over the next few updates.
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Closes the console. It slides closed over the next few updates.
+    pub fn close(&mut self) {
+        self.open = false;
+        self.history_index = None;
+    }
+
+    /// Opens the console if it is closed, or closes it if it is open.
+    pub fn toggle(&mut self) {
+        if self.open {
+            self.close();
+        } else {
+            self.open();
+        }
+    }
+
+    /// Returns the command line currently being entered.
+    #[inline]
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Adds text to the console's output. Multi-line text is split into separate lines.
+    pub fn print(&mut self, text: &str) {
+        self.add_output(text, ConsoleLineKind::Output);
+    }
+
+    /// Adds an error message to the console's output, which is shown in a different color to
+    /// normal output. Multi-line text is split into separate lines.
+    pub fn print_error(&mut self, text: &str) {
+        self.add_output(text, ConsoleLineKind::Error);
+    }
+
+    fn add_output(&mut self, text: &str, kind: ConsoleLineKind) {
+        for line in text.lines() {
+            self.output.push_back(ConsoleLine {
+                text: String::from(line),
+                kind,
+            });
+        }
+        while self.output.len() > self.max_output_lines {
+            self.output.pop_front();
+        }
+        self.scroll = 0;
+    }
+
+    /// Returns an iterator over all of the lines of the console's output, oldest first.
+    pub fn output(&self) -> impl Iterator<Item = &ConsoleLine> {
+        self.output.iter()
+    }
+
+    /// Removes all of the console's output.
+    pub fn clear_output(&mut self) {
+        self.output.clear();
+        self.scroll = 0;
+    }
+
+    /// Returns an iterator over the previously entered command lines, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        self.history.iter().map(|line| line.as_str())
+    }
+
+    fn add_history(&mut self, line: &str) {
+        if self.max_history == 0 || self.history.back().is_some_and(|last| last == line) {
+            return;
+        }
+        self.history.push_back(String::from(line));
+        while self.history.len() > self.max_history {
+            self.history.pop_front();
+        }
+    }
+
+    fn recall_history(&mut self, older: bool, text_input: &mut TextInput) {
+        if self.history.is_empty() {
+            return;
+        }
+        self.history_index = match (self.history_index, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => return,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        match self.history_index {
+            Some(index) => text_input.set_text(&self.history[index]),
+            None => text_input.clear(),
+        }
+    }
+
+    fn autocomplete(&mut self, text_input: &mut TextInput) {
+        let text = text_input.text();
+        if text.contains(char::is_whitespace) {
+            return;
+        }
+        let candidates: Vec<String> = self.complete(text).into_iter().map(String::from).collect();
+        match candidates.as_slice() {
+            [] => {}
+            [name] => text_input.set_text(&format!("{} ", name)),
+            [first, rest @ ..] => {
+                let mut common = first.len();
+                for name in rest.iter() {
+                    common = first
+                        .char_indices()
+                        .zip(name.chars())
+                        .find(|((_, a), b)| a != b)
+                        .map_or(common.min(name.len()), |((index, _), _)| index.min(common));
+                }
+                text_input.set_text(&first[..common]);
+                self.print(&candidates.join("  "));
+            }
+        }
+    }
+
+    /// Updates the console from the state of the given input devices, without needing a
+    /// [`System`]. This is the same as [`Console::update`], except that text input mode must be
+    /// started and stopped by the caller to match [`Console::is_open`].
+    ///
+    /// returns: a command line that was entered, which should be passed to [`Console::execute`]
+    pub fn handle_input(
+        &mut self,
+        keyboard: &Keyboard,
+        text_input: &mut TextInput,
+    ) -> Option<String> {
+        self.ticks = self.ticks.wrapping_add(1);
+        let speed = (self.height / 8).max(1);
+        self.position = if self.open {
+            (self.position + speed).min(self.height)
+        } else {
+            self.position.saturating_sub(speed)
+        };
+
+        if keyboard.is_key_pressed(self.toggle_key) {
+            if self.open {
+                // the toggle key will probably also have been typed into the command line
+                if let Some(text) = text_input.text().strip_suffix(text_input.typed()) {
+                    let text = String::from(text);
+                    text_input.set_text(&text);
+                }
+                self.input = String::from(text_input.text());
+            }
+            self.toggle();
+            return None;
+        }
+        if !self.open || !text_input.is_active() {
+            return None;
+        }
+
+        if keyboard.is_key_pressed(Scancode::Up) {
+            self.recall_history(true, text_input);
+        } else if keyboard.is_key_pressed(Scancode::Down) {
+            self.recall_history(false, text_input);
+        } else if keyboard.is_key_pressed(Scancode::Tab) {
+            self.autocomplete(text_input);
+        } else if keyboard.is_key_pressed(Scancode::PageUp) {
+            self.scroll = (self.scroll + SCROLL_LINES).min(self.output.len().saturating_sub(1));
+        } else if keyboard.is_key_pressed(Scancode::PageDown) {
+            self.scroll = self.scroll.saturating_sub(SCROLL_LINES);
+        }
+
+        let mut entered = None;
+        if text_input.is_submitted() {
+            let line = String::from(text_input.text());
+            text_input.clear();
+            self.history_index = None;
+            self.add_output(&format!("{}{}", PROMPT, line), ConsoleLineKind::Input);
+            if !line.trim().is_empty() {
+                self.add_history(&line);
+                entered = Some(line);
+            }
+        }
+        self.input = String::from(text_input.text());
+        entered
+    }
+
+    /// Updates the console from the [`System`]'s keyboard and text input, opening or closing it
+    /// when the toggle key is pressed, and starting and stopping text input mode to match. Should
+    /// be called once per frame, after [`System::do_events`].
+    ///
+    /// returns: a command line that was entered, which should be passed to [`Console::execute`]
+    pub fn update(&mut self, system: &mut System) -> Option<String> {
+        let was_open = self.open;
+        let line = self.handle_input(&system.keyboard, &mut system.text_input);
+        if self.open && !system.text_input.is_active() {
+            system.start_text_input();
+            system.text_input.set_text(&self.input);
+            self.started_text_input = true;
+        } else if !self.open && was_open && self.started_text_input {
+            system.stop_text_input();
+            self.started_text_input = false;
+        }
+        line
+    }
+
+    fn run_builtin(&mut self, name: &str, args: &ConsoleArgs) -> Result<String, ConsoleError> {
+        match name {
+            "clear" => {
+                args.expect_count(0, 0)?;
+                self.clear_output();
+                Ok(String::new())
+            }
+            "help" => {
+                args.expect_count(0, 1)?;
+                match args.get(0) {
+                    Some(name) => match self.description(name) {
+                        Some(description) => Ok(format!("{} - {}", name, description)),
+                        None => Err(ConsoleError::UnknownCommand(String::from(name))),
+                    },
+                    None => Ok(self
+                        .command_names()
+                        .iter()
+                        .map(|name| format!("{} - {}", name, self.description(name).unwrap_or("")))
+                        .collect::<Vec<String>>()
+                        .join("\n")),
+                }
+            }
+            "history" => {
+                args.expect_count(0, 0)?;
+                Ok(self.history().collect::<Vec<&str>>().join("\n"))
+            }
+            _ => Err(ConsoleError::UnknownCommand(String::from(name))),
+        }
+    }
+
+    /// Runs a command line, printing the command's output (or error) to the console. The command
+    /// line does not need to have been entered into the console, so this can also be used to
+    /// run commands from elsewhere, such as from a startup script.
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: the context passed to the command's function
+    /// * `line`: the command line, which is parsed with [`parse_command_line`]
+    pub fn execute(&mut self, context: &mut ContextType, line: &str) -> Result<(), ConsoleError> {
+        let result = match parse_command_line(line) {
+            Ok(Some((name, args))) => match self.commands.get_mut(&name) {
+                Some(command) => (command.func)(context, &args),
+                None => self.run_builtin(&name, &args),
+            },
+            Ok(None) => return Ok(()),
+            Err(error) => Err(error),
+        };
+        match result {
+            Ok(output) => {
+                self.print(&output);
+                Ok(())
+            }
+            Err(error) => {
+                self.print_error(&error.to_string());
+                Err(error)
+            }
+        }
+    }
+
+    /// Draws the console onto the given bitmap (typically the screen) if any part of it is
+    /// visible, covering the top part of the bitmap. Lines too long to fit are cut off.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest`: the bitmap to draw the console onto
+    /// * `font`: the font to draw the console's text with
+    pub fn render<T: Font>(&self, dest: &mut Bitmap, font: &T) {
+        if self.position == 0 {
+            return;
+        }
+        let width = dest.width() as i32;
+        let bottom = self.position.min(dest.height()) as i32 - 1;
+        dest.filled_rect(0, 0, width - 1, bottom, self.colors.background);
+        dest.horiz_line(0, width - 1, bottom, self.colors.border);
+
+        let line_height = font.line_height() as i32;
+        let max_width = (width - 4).max(0) as u32;
+
+        // show the end of the command line if it is too long to fit
+        let cursor = if (self.ticks / 16) % 2 == 0 { "_" } else { " " };
+        let mut input = format!("{}{}{}", PROMPT, self.input, cursor);
+        while font.measure(&input, FontRenderOpts::Color(0)).0 > max_width {
+            input.remove(0);
+        }
+        let mut y = bottom - 1 - line_height;
+        dest.print_string(&input, 2, y, FontRenderOpts::Color(self.colors.input), font);
+
+        for line in self.output.iter().rev().skip(self.scroll) {
+            y -= line_height;
+            if y + line_height <= 0 {
+                break;
+            }
+            let color = match line.kind {
+                ConsoleLineKind::Output => self.colors.output,
+                ConsoleLineKind::Error => self.colors.error,
+                ConsoleLineKind::Input => self.colors.input,
+            };
+            dest.print_string(&line.text, 2, y, FontRenderOpts::Color(color), font);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use sdl2::event::Event;
+    use sdl2::keyboard::Mod;
+
+    use claim::*;
+
+    use super::*;
+
+    struct Game {
+        score: u32,
+    }
+
+    fn key_event(keyboard: &mut Keyboard, text_input: &mut TextInput, scancode: Scancode) {
+        // releasing first so that the key is reported as just pressed even if it is being held
+        keyboard.handle_event(&Event::KeyUp {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: Mod::empty(),
+            repeat: false,
+        });
+        let event = Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: Mod::empty(),
+            repeat: false,
+        };
+        keyboard.handle_event(&event);
+        text_input.handle_event(&event);
+    }
+
+    fn press(keyboard: &mut Keyboard, text_input: &mut TextInput, scancode: Scancode) {
+        keyboard.update();
+        text_input.update();
+        key_event(keyboard, text_input, scancode);
+    }
+
+    fn type_text(keyboard: &mut Keyboard, text_input: &mut TextInput, text: &str) {
+        keyboard.update();
+        text_input.update();
+        text_input.handle_event(&Event::TextInput {
+            timestamp: 0,
+            window_id: 0,
+            text: String::from(text),
+        });
+    }
+
+    fn new_console() -> Console<Game> {
+        let mut console = Console::new();
+        console.register(
+            "add",
+            "Adds to the score",
+            |game: &mut Game, args: &ConsoleArgs| {
+                args.expect_count(1, 1)?;
+                game.score += args.parse::<u32>(0)?;
+                Ok(format!("Score is now {}", game.score))
+            },
+        );
+        console.register(
+            "addall",
+            "Adds many",
+            |_game: &mut Game, _args: &ConsoleArgs| Ok(String::new()),
+        );
+        console
+    }
+
+    fn output_texts(console: &Console<Game>) -> Vec<&str> {
+        console.output().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    pub fn executing_commands() {
+        let mut console = new_console();
+        let mut game = Game { score: 0 };
+
+        assert_ok!(console.execute(&mut game, "add 5"));
+        assert_eq!(5, game.score);
+        assert_matches!(
+            console.execute(&mut game, "add lots"),
+            Err(ConsoleError::BadArguments(..))
+        );
+        assert_matches!(
+            console.execute(&mut game, "subtract 1"),
+            Err(ConsoleError::UnknownCommand(..))
+        );
+        assert_ok!(console.execute(&mut game, "   "));
+        assert_eq!(5, game.score);
+        assert_eq!(
+            vec![
+                "Score is now 5",
+                "Bad arguments: Invalid argument 1: lots",
+                "Unknown command: subtract"
+            ],
+            output_texts(&console)
+        );
+        assert_eq!(
+            ConsoleLineKind::Error,
+            console.output().last().unwrap().kind
+        );
+
+        assert_ok!(console.execute(&mut game, "help add"));
+        assert_eq!(
+            Some("add - Adds to the score"),
+            output_texts(&console).last().copied()
+        );
+        assert_ok!(console.execute(&mut game, "clear"));
+        assert_eq!(0, console.output().count());
+        assert_ok!(console.execute(&mut game, "help"));
+        assert_eq!(5, console.output().count());
+
+        assert!(console.has_command("history"));
+        assert!(console.unregister("addall"));
+        assert!(!console.has_command("addall"));
+        assert_eq!(
+            vec!["add", "clear", "help", "history"],
+            console.command_names()
+        );
+    }
+
+    #[test]
+    pub fn entering_commands() {
+        let mut console = new_console();
+        let mut game = Game { score: 0 };
+        let mut keyboard = Keyboard::new();
+        let mut text_input = TextInput::new();
+
+        press(&mut keyboard, &mut text_input, Scancode::Grave);
+        assert_none!(console.handle_input(&keyboard, &mut text_input));
+        assert!(console.is_open());
+        text_input.set_active(true);
+
+        type_text(&mut keyboard, &mut text_input, "add 2");
+        assert_none!(console.handle_input(&keyboard, &mut text_input));
+        assert_eq!("add 2", console.input());
+        press(&mut keyboard, &mut text_input, Scancode::Return);
+        let line = assert_some!(console.handle_input(&keyboard, &mut text_input));
+        assert_eq!("add 2", line);
+        assert_ok!(console.execute(&mut game, &line));
+        assert_eq!(2, game.score);
+        assert_eq!("", console.input());
+        assert_eq!(vec!["> add 2", "Score is now 2"], output_texts(&console));
+
+        // history
+        type_text(&mut keyboard, &mut text_input, "help");
+        console.handle_input(&keyboard, &mut text_input);
+        press(&mut keyboard, &mut text_input, Scancode::Return);
+        assert_some!(console.handle_input(&keyboard, &mut text_input));
+        assert_eq!(vec!["add 2", "help"], console.history().collect::<Vec<_>>());
+        press(&mut keyboard, &mut text_input, Scancode::Up);
+        console.handle_input(&keyboard, &mut text_input);
+        assert_eq!("help", console.input());
+        press(&mut keyboard, &mut text_input, Scancode::Up);
+        console.handle_input(&keyboard, &mut text_input);
+        assert_eq!("add 2", console.input());
+        press(&mut keyboard, &mut text_input, Scancode::Down);
+        console.handle_input(&keyboard, &mut text_input);
+        assert_eq!("help", console.input());
+        press(&mut keyboard, &mut text_input, Scancode::Down);
+        console.handle_input(&keyboard, &mut text_input);
+        assert_eq!("", console.input());
+
+        // autocomplete
+        type_text(&mut keyboard, &mut text_input, "hi");
+        console.handle_input(&keyboard, &mut text_input);
+        press(&mut keyboard, &mut text_input, Scancode::Tab);
+        console.handle_input(&keyboard, &mut text_input);
+        assert_eq!("history ", console.input());
+        text_input.set_text("a");
+        press(&mut keyboard, &mut text_input, Scancode::Tab);
+        console.handle_input(&keyboard, &mut text_input);
+        assert_eq!("add", console.input());
+        assert_eq!(Some("add  addall"), output_texts(&console).last().copied());
+
+        // closing, with the toggle key also being typed
+        type_text(&mut keyboard, &mut text_input, "`");
+        key_event(&mut keyboard, &mut text_input, Scancode::Grave);
+        assert_none!(console.handle_input(&keyboard, &mut text_input));
+        assert!(!console.is_open());
+        assert!(console.is_visible());
+        assert_eq!("add", console.input());
+    }
+
+    #[test]
+    pub fn rendering() {
+        let mut console = new_console().with_height(40);
+        let mut game = Game { score: 0 };
+        let font = BitmaskFont::new_vga_font().unwrap();
+        let mut screen = Bitmap::new(64, 64).unwrap();
+        screen.clear(99);
+
+        console.render(&mut screen, &font);
+        assert!(screen.pixels().iter().all(|pixel| *pixel == 99));
+
+        console.open();
+        let keyboard = Keyboard::new();
+        let mut text_input = TextInput::new();
+        for _ in 0..8 {
+            console.handle_input(&keyboard, &mut text_input);
+        }
+        assert_ok!(console.execute(&mut game, "add 1"));
+        console.render(&mut screen, &font);
+        assert_eq!(Some(console.colors.border), screen.get_pixel(0, 39));
+        assert_eq!(Some(99), screen.get_pixel(0, 40));
+        assert!(screen.pixels()[..64 * 39]
+            .iter()
+            .any(|pixel| *pixel == console.colors.output));
+        assert!(screen.pixels()[..64 * 39]
+            .iter()
+            .any(|pixel| *pixel == console.colors.input));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::graphics::*;
+use crate::utils::prng::Prng;
+
+/// The colors of the fire used in the PSX version of Doom, from coldest to hottest.
+#[rustfmt::skip]
+pub const DOOM_FIRE_COLORS: [u32; 37] = [
+    0xff070707, 0xff1f0707, 0xff2f0f07, 0xff470f07, 0xff571707, 0xff671f07, 0xff771f07,
+    0xff8f2707, 0xff9f2f07, 0xffaf3f07, 0xffbf4707, 0xffc74707, 0xffdf4f07, 0xffdf5707,
+    0xffdf5707, 0xffd75f07, 0xffd75f07, 0xffd7670f, 0xffcf6f0f, 0xffcf770f, 0xffcf7f0f,
+    0xffcf8717, 0xffc78717, 0xffc78f17, 0xffc7971f, 0xffbf9f1f, 0xffbf9f1f, 0xffbfa727,
+    0xffbfa727, 0xffbfaf2f, 0xffb7af2f, 0xffb7b72f, 0xffb7b737, 0xffcfcf6f, 0xffdfdf9f,
+    0xffefefc7, 0xffffffff,
+];
+
+/// The bottom-up fire effect from the PSX version of Doom. The bottom row of the fire is kept at
+/// a set heat, and each step the heat of every cell spreads up to the row above it, randomly
+/// drifting sideways and cooling down as it goes. Each level of heat is drawn with its own
+/// palette color, starting from `first_color` for cold (zero heat).
+#[derive(Debug, Clone)]
+pub struct Fire {
+    /// The palette color used for cells with no heat. Each level of heat uses the following
+    /// colors, up to `first_color + max_heat()`.
+    pub first_color: u8,
+    /// The heat the bottom row of the fire is kept at, from zero (which puts the fire out) up to
+    /// [Fire::max_heat].
+    pub source_heat: u8,
+    /// How many times per second the heat spreads upwards. The original effect ran at 27.
+    pub steps_per_second: f32,
+    /// How far heat drifts sideways each step, in addition to the random drift, in cells. Positive
+    /// values blow the fire towards the right.
+    pub wind: i32,
+    width: u32,
+    height: u32,
+    max_heat: u8,
+    heat: Box<[u8]>,
+    prng: Prng,
+    accumulator: f32,
+}
+
+impl Fire {
+    /// Creates a new fire, which starts out with only its bottom row burning.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the fire, in pixels
+    /// * `height`: the height of the fire, in pixels
+    /// * `num_levels`: how many levels of heat there are, including zero, and so how many
+    ///   palette colors the fire uses. The original effect used 37.
+    /// * `seed`: the seed for the random spreading of the fire
+    pub fn new(width: u32, height: u32, num_levels: u8, seed: u64) -> Fire {
+        let max_heat = num_levels.saturating_sub(1);
+        let mut fire = Fire {
+            first_color: 0,
+            source_heat: max_heat,
+            steps_per_second: 27.0,
+            wind: 0,
+            width,
+            height,
+            max_heat,
+            heat: vec![0u8; (width * height) as usize].into_boxed_slice(),
+            prng: Prng::new(seed),
+            accumulator: 0.0,
+        };
+        fire.fill_source_row();
+        fire
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the highest heat any cell can have.
+    #[inline]
+    pub fn max_heat(&self) -> u8 {
+        self.max_heat
+    }
+
+    /// Returns the heat of the given cell, or `None` if it is outside of the fire.
+    pub fn heat(&self, x: i32, y: i32) -> Option<u8> {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            Some(self.heat[(y as u32 * self.width + x as u32) as usize])
+        } else {
+            None
+        }
+    }
+
+    fn fill_source_row(&mut self) {
+        if self.height == 0 {
+            return;
+        }
+        let source_heat = self.source_heat.min(self.max_heat);
+        let start = ((self.height - 1) * self.width) as usize;
+        self.heat[start..].fill(source_heat);
+    }
+
+    /// Spreads the heat of every cell up to the row above it once.
+    pub fn step(&mut self) {
+        let width = self.width as usize;
+        for x in 0..width {
+            for y in 1..self.height as usize {
+                let heat = self.heat[y * width + x];
+                let above = (y - 1) * width;
+                if heat == 0 {
+                    self.heat[above + x] = 0;
+                    continue;
+                }
+                let random = self.prng.next_u32();
+                let cooling = (random & 1) as u8;
+                let drift = ((random >> 1) % 3) as i32 - 1 + self.wind;
+                let dest_x = (x as i32 + drift).rem_euclid(width as i32) as usize;
+                self.heat[above + dest_x] = heat - cooling;
+            }
+        }
+        self.fill_source_row();
+    }
+
+    /// Moves the fire along by the given amount of time, in seconds, running as many steps as
+    /// have passed at the rate set by `steps_per_second`.
+    pub fn update(&mut self, delta: f32) {
+        self.accumulator += delta * self.steps_per_second;
+        while self.accumulator >= 1.0 {
+            self.accumulator -= 1.0;
+            self.step();
+        }
+    }
+
+    /// Draws the fire onto the destination bitmap, with its top-left corner at the given
+    /// position.
+    pub fn draw(&self, dest: &mut Bitmap, x: i32, y: i32) {
+        for (row, cells) in self
+            .heat
+            .chunks_exact(self.width.max(1) as usize)
+            .enumerate()
+        {
+            for (column, &heat) in cells.iter().enumerate() {
+                dest.set_pixel(
+                    x + column as i32,
+                    y + row as i32,
+                    self.first_color.wrapping_add(heat),
+                );
+            }
+        }
+    }
+
+    /// Fills a range of palette colors with the colors of the fire from Doom, stretched or
+    /// squashed to fit the given number of heat levels.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to modify
+    /// * `first_color`: the palette color to use for zero heat
+    /// * `num_levels`: the number of heat levels the fire has
+    pub fn write_palette(palette: &mut Palette, first_color: u8, num_levels: u8) {
+        let num_levels = (num_levels as usize).min(256 - first_color as usize);
+        let last = DOOM_FIRE_COLORS.len() - 1;
+        for i in 0..num_levels {
+            let index = if num_levels > 1 {
+                (i * last + (num_levels - 1) / 2) / (num_levels - 1)
+            } else {
+                last
+            };
+            palette[first_color + i as u8] = DOOM_FIRE_COLORS[index];
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn row(fire: &Fire, y: i32) -> Vec<u8> {
+        (0..fire.width() as i32)
+            .map(|x| fire.heat(x, y).unwrap())
+            .collect()
+    }
+
+    #[test]
+    pub fn spreading_fire() {
+        let mut fire = Fire::new(16, 8, 37, 1);
+        assert_eq!(36, fire.max_heat());
+        assert_eq!(vec![36; 16], row(&fire, 7));
+        assert_eq!(vec![0; 16], row(&fire, 6));
+        assert_eq!(None, fire.heat(16, 0));
+
+        fire.step();
+        assert!(row(&fire, 6).iter().any(|&heat| heat >= 35));
+        assert_eq!(vec![36; 16], row(&fire, 7));
+
+        // reaches all the way to the top, and never gets hotter than its source
+        fire.update(100.0 / 27.0);
+        assert!(row(&fire, 0).iter().any(|&heat| heat > 0));
+        for y in 0..8 {
+            assert!(row(&fire, y).iter().all(|&heat| heat <= 36));
+        }
+
+        // putting the fire out
+        fire.source_heat = 0;
+        for _ in 0..8 {
+            fire.step();
+        }
+        for y in 0..8 {
+            assert_eq!(vec![0; 16], row(&fire, y));
+        }
+    }
+
+    #[test]
+    pub fn heat_rises_one_row_per_step() {
+        // with only one column there is nowhere for the heat to drift to
+        let mut fire = Fire::new(1, 8, 37, 1);
+        fire.step();
+        assert_eq!(
+            vec![0, 0, 0, 0, 0, 0],
+            (0..6).map(|y| fire.heat(0, y).unwrap()).collect::<Vec<_>>()
+        );
+        assert!(fire.heat(0, 6).unwrap() >= 35);
+
+        for _ in 0..6 {
+            fire.step();
+        }
+        assert!(fire.heat(0, 0).unwrap() >= 29);
+    }
+
+    #[test]
+    pub fn drawing_fire() {
+        let mut fire = Fire::new(16, 8, 37, 1);
+        fire.first_color = 100;
+
+        let mut dest = Bitmap::new(16, 8).unwrap();
+        fire.draw(&mut dest, 0, 0);
+        assert_eq!(Some(100), dest.get_pixel(0, 0));
+        assert_eq!(Some(136), dest.get_pixel(0, 7));
+        assert_eq!(Some(136), dest.get_pixel(15, 7));
+
+        let mut dest = Bitmap::new(16, 8).unwrap();
+        fire.draw(&mut dest, -4, -2);
+        assert_eq!(Some(136), dest.get_pixel(11, 5));
+        assert_eq!(Some(0), dest.get_pixel(12, 5));
+        assert_eq!(Some(0), dest.get_pixel(0, 6));
+    }
+
+    #[test]
+    pub fn fire_palette() {
+        let mut palette = Palette::new();
+        Fire::write_palette(&mut palette, 100, 37);
+        assert_eq!(0xff070707, palette[100]);
+        assert_eq!(0xff8f2707, palette[107]);
+        assert_eq!(0xffffffff, palette[136]);
+        assert_eq!(0, palette[137]);
+
+        // squashed down to fewer colors, but still going from coldest to hottest
+        Fire::write_palette(&mut palette, 0, 5);
+        assert_eq!(0xff070707, palette[0]);
+        assert_eq!(0xffffffff, palette[4]);
+    }
+}
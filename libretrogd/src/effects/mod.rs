@@ -0,0 +1,9 @@
+pub use self::fire::*;
+pub use self::plasma::*;
+pub use self::rotozoom::*;
+pub use self::starfield::*;
+
+pub mod fire;
+pub mod plasma;
+pub mod rotozoom;
+pub mod starfield;
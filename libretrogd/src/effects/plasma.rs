@@ -0,0 +1,167 @@
+use crate::graphics::*;
+use crate::math::*;
+
+/// The classic palette plasma: several overlapping sine waves which are combined and mapped onto
+/// a range of palette colors. The waves drift around over time and the colors are cycled through
+/// the range, giving the same look as rotating the palette, without modifying it.
+///
+/// [Plasma::write_palette] fills a range of colors with a smooth, looping gradient which suits the
+/// effect, though any gradient which starts and ends with the same color works well.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Plasma {
+    /// The first palette color used.
+    pub first_color: u8,
+    /// How many palette colors are used, starting from `first_color`.
+    pub num_colors: usize,
+    /// How wide the waves making up the plasma are, in pixels. Larger values give smoother,
+    /// larger blobs.
+    pub scale: f32,
+    /// How quickly the waves move.
+    pub speed: f32,
+    /// How quickly the colors cycle through the range, in colors per second.
+    pub color_speed: f32,
+    time: f32,
+}
+
+impl Plasma {
+    /// Creates a new plasma using the given range of palette colors.
+    pub fn new(first_color: u8, num_colors: usize) -> Plasma {
+        Plasma {
+            first_color,
+            num_colors,
+            scale: 16.0,
+            speed: 1.0,
+            color_speed: 16.0,
+            time: 0.0,
+        }
+    }
+
+    /// Returns how long (in seconds) the plasma has been running for.
+    #[inline]
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Moves the plasma along by the given amount of time, in seconds.
+    #[inline]
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+    }
+
+    /// Returns the value of the plasma at the given pixel, from 0.0 to 1.0.
+    pub fn value_at(&self, x: i32, y: i32) -> f32 {
+        let t = self.time * self.speed;
+        let x = x as f32 / self.scale;
+        let y = y as f32 / self.scale;
+
+        // a moving center for the circular waves
+        let cx = x + (t * 0.3).sin() * 4.0;
+        let cy = y + (t * 0.5).cos() * 4.0;
+
+        let value = (x + t).sin()
+            + (y * 0.8 - t * 0.7).sin()
+            + ((x + y) * 0.5 + t * 1.3).sin()
+            + ((cx * cx + cy * cy).sqrt() - t).sin();
+        ((value + 4.0) / 8.0).clamp(0.0, 1.0)
+    }
+
+    /// Returns the palette color to use at the given pixel.
+    pub fn color_at(&self, x: i32, y: i32) -> u8 {
+        if self.num_colors == 0 {
+            return self.first_color;
+        }
+        let offset = self.value_at(x, y) * self.num_colors as f32 + self.time * self.color_speed;
+        let offset = (offset as i64).rem_euclid(self.num_colors as i64) as usize;
+        self.first_color.wrapping_add(offset as u8)
+    }
+
+    /// Fills the destination bitmap's clip region with the plasma. The plasma is positioned
+    /// relative to the top-left corner of the bitmap, not the clip region.
+    pub fn draw(&self, dest: &mut Bitmap) {
+        let clip_region = *dest.clip_region();
+        for y in clip_region.y..=clip_region.bottom() {
+            for x in clip_region.x..=clip_region.right() {
+                dest.set_pixel(x, y, self.color_at(x, y));
+            }
+        }
+    }
+
+    /// Fills a range of palette colors with a looping rainbow gradient, which starts and ends at
+    /// nearly the same color so that cycling through it looks smooth.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to modify
+    /// * `first_color`: the first palette color to change
+    /// * `num_colors`: how many palette colors to change. The gradient stops at the end of the
+    ///   palette if there are not enough colors after `first_color`.
+    pub fn write_palette(palette: &mut Palette, first_color: u8, num_colors: usize) {
+        let num_colors = num_colors.min(256 - first_color as usize);
+        for i in 0..num_colors {
+            let angle = (i as f32 / num_colors as f32) * TWO_PI;
+            let component = |offset: f32| (128.0 + 127.0 * (angle + offset).sin()).round() as u8;
+            palette[first_color + i as u8] = to_rgb32(
+                component(0.0),
+                component(TWO_PI / 3.0),
+                component(TWO_PI * 2.0 / 3.0),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn drawing_plasma() {
+        let mut plasma = Plasma::new(64, 32);
+        let mut dest = Bitmap::new(32, 32).unwrap();
+        dest.set_clip_region(&Rect::new(4, 4, 24, 24));
+        plasma.draw(&mut dest);
+
+        for y in 0..32 {
+            for x in 0..32 {
+                let pixel = dest.get_pixel(x, y).unwrap();
+                if dest.clip_region().contains_point(x, y) {
+                    assert!((64..96).contains(&pixel));
+                    assert_eq!(plasma.color_at(x, y), pixel);
+                } else {
+                    assert_eq!(0, pixel);
+                }
+            }
+        }
+
+        // the same plasma at the same time always looks the same
+        let mut other = Bitmap::new(32, 32).unwrap();
+        other.set_clip_region(&Rect::new(4, 4, 24, 24));
+        Plasma::new(64, 32).draw(&mut other);
+        assert_eq!(dest.pixels(), other.pixels());
+
+        plasma.update(0.5);
+        assert_eq!(0.5, plasma.time());
+        plasma.draw(&mut other);
+        assert_ne!(dest.pixels(), other.pixels());
+
+        for y in 0..32 {
+            for x in 0..32 {
+                let value = plasma.value_at(x * 7, y * 3);
+                assert!((0.0..=1.0).contains(&value));
+            }
+        }
+    }
+
+    #[test]
+    pub fn plasma_palette() {
+        let mut palette = Palette::new();
+        Plasma::write_palette(&mut palette, 64, 32);
+        assert_eq!(0, palette[63]);
+        assert_eq!(to_rgb32(128, 238, 18), palette[64]);
+        assert_ne!(palette[64], palette[80]);
+        assert_eq!(0, palette[96]);
+
+        // stops at the end of the palette
+        Plasma::write_palette(&mut palette, 250, 32);
+        assert_eq!(to_rgb32(128, 238, 18), palette[250]);
+    }
+}
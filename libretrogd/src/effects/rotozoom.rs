@@ -0,0 +1,177 @@
+use crate::graphics::*;
+use crate::math::*;
+
+/// The classic rotozoomer: a source bitmap tiled infinitely in every direction, which is rotated
+/// and zoomed around a point and drawn so that it fills the destination. Drawing the destination
+/// back into itself each frame with [Rotozoomer::draw_feedback] gives the swirling "feedback"
+/// variant of the effect.
+#[derive(Debug, Clone)]
+pub struct Rotozoomer {
+    /// The rotation of the source, in radians.
+    pub angle: f32,
+    /// How much the source is scaled up by. Values between 0.0 and 1.0 zoom out.
+    pub zoom: f32,
+    /// The point in the source which is drawn at the center of the destination's clip region.
+    pub center: Vector2,
+    /// How quickly `angle` changes, in radians per second.
+    pub rotation_speed: f32,
+    /// How quickly `center` moves, in source pixels per second.
+    pub pan_speed: Vector2,
+    feedback: Option<Bitmap>,
+}
+
+impl Rotozoomer {
+    /// Creates a new rotozoomer centered on the given point of the source, which is not rotated
+    /// or zoomed to begin with.
+    pub fn new(center: Vector2) -> Rotozoomer {
+        Rotozoomer {
+            angle: 0.0,
+            zoom: 1.0,
+            center,
+            rotation_speed: 0.0,
+            pan_speed: Vector2::ZERO,
+            feedback: None,
+        }
+    }
+
+    /// Rotates and pans the rotozoomer by the given amount of time, in seconds.
+    pub fn update(&mut self, delta: f32) {
+        self.angle += self.rotation_speed * delta;
+        self.center += self.pan_speed * delta;
+    }
+
+    /// Fills the destination bitmap's clip region with the rotated and zoomed source bitmap.
+    /// Nothing is drawn if `zoom` is not a positive value.
+    pub fn draw(&self, dest: &mut Bitmap, source: &Bitmap) {
+        if self.zoom <= 0.0 || source.width() == 0 || source.height() == 0 {
+            return;
+        }
+
+        let clip_region = *dest.clip_region();
+        let clip_center = Vector2::new(
+            clip_region.x as f32 + clip_region.width as f32 / 2.0,
+            clip_region.y as f32 + clip_region.height as f32 / 2.0,
+        );
+        let (sin, cos) = self.angle.sin_cos();
+        let (sin, cos) = (sin / self.zoom, cos / self.zoom);
+        let source_width = source.width() as i32;
+        let source_height = source.height() as i32;
+        let source_pitch = source.pitch() as i32;
+        let source_pixels = source.pixels();
+
+        for y in clip_region.y..=clip_region.bottom() {
+            let dy = y as f32 + 0.5 - clip_center.y;
+            for x in clip_region.x..=clip_region.right() {
+                let dx = x as f32 + 0.5 - clip_center.x;
+                let src_x = (self.center.x + dx * cos + dy * sin).floor() as i32;
+                let src_y = (self.center.y - dx * sin + dy * cos).floor() as i32;
+                let src_x = src_x.rem_euclid(source_width);
+                let src_y = src_y.rem_euclid(source_height);
+                let color = source_pixels[(src_y * source_pitch + src_x) as usize];
+                dest.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Same as [Rotozoomer::draw], but uses the current contents of the destination bitmap as
+    /// the source. The destination is copied into a bitmap kept by the rotozoomer first, which is
+    /// reused each time as long as the destination stays the same size.
+    pub fn draw_feedback(&mut self, dest: &mut Bitmap) {
+        let mut feedback = match self.feedback.take() {
+            Some(feedback)
+                if feedback.width() == dest.width() && feedback.height() == dest.height() =>
+            {
+                feedback
+            }
+            _ => match Bitmap::new(dest.width(), dest.height()) {
+                Ok(feedback) => feedback,
+                Err(_) => return,
+            },
+        };
+        feedback.blit(BlitMethod::Solid, dest, 0, 0);
+        self.draw(dest, &feedback);
+        self.feedback = Some(feedback);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn source() -> Bitmap {
+        let mut source = Bitmap::new(8, 8).unwrap();
+        for y in 0..8 {
+            for x in 0..8 {
+                source.set_pixel(x, y, (y * 8 + x) as u8);
+            }
+        }
+        source
+    }
+
+    #[test]
+    pub fn rotating_and_zooming() {
+        let source = source();
+        let mut dest = Bitmap::new(8, 8).unwrap();
+
+        let mut rotozoomer = Rotozoomer::new(Vector2::new(4.0, 4.0));
+        rotozoomer.draw(&mut dest, &source);
+        assert_eq!(source.pixels(), dest.pixels());
+
+        rotozoomer.zoom = 2.0;
+        rotozoomer.draw(&mut dest, &source);
+        assert_eq!(Some(18), dest.get_pixel(0, 0));
+        assert_eq!(Some(45), dest.get_pixel(7, 7));
+
+        rotozoomer.zoom = 1.0;
+        rotozoomer.rotation_speed = RADIANS_90;
+        rotozoomer.update(1.0);
+        assert!(nearly_equal(RADIANS_90, rotozoomer.angle, 0.0001));
+        rotozoomer.draw(&mut dest, &source);
+        assert_eq!(Some(0), dest.get_pixel(7, 0));
+        assert_eq!(Some(56), dest.get_pixel(0, 0));
+        assert_eq!(Some(63), dest.get_pixel(0, 7));
+
+        // the source repeats in every direction
+        let mut rotozoomer = Rotozoomer::new(Vector2::ZERO);
+        rotozoomer.draw(&mut dest, &source);
+        assert_eq!(Some(36), dest.get_pixel(0, 0));
+        assert_eq!(Some(0), dest.get_pixel(4, 4));
+
+        rotozoomer.pan_speed = Vector2::new(2.0, 1.0);
+        rotozoomer.update(2.0);
+        assert_eq!(Vector2::new(4.0, 2.0), rotozoomer.center);
+    }
+
+    #[test]
+    pub fn drawing_within_clip_region() {
+        let source = source();
+        let mut dest = Bitmap::new(16, 16).unwrap();
+        dest.set_clip_region(&Rect::new(4, 4, 8, 8));
+
+        let rotozoomer = Rotozoomer::new(Vector2::new(4.0, 4.0));
+        rotozoomer.draw(&mut dest, &source);
+        assert_eq!(Some(1), dest.get_pixel(5, 4));
+        assert_eq!(Some(63), dest.get_pixel(11, 11));
+        dest.reset_clip_region();
+        assert_eq!(Some(0), dest.get_pixel(3, 3));
+        assert_eq!(Some(0), dest.get_pixel(12, 12));
+    }
+
+    #[test]
+    pub fn drawing_feedback() {
+        let mut dest = source();
+
+        let mut rotozoomer = Rotozoomer::new(Vector2::new(4.0, 4.0));
+        rotozoomer.draw_feedback(&mut dest);
+        assert_eq!(source().pixels(), dest.pixels());
+
+        rotozoomer.zoom = 2.0;
+        rotozoomer.draw_feedback(&mut dest);
+        assert_eq!(Some(18), dest.get_pixel(0, 0));
+        assert_eq!(Some(45), dest.get_pixel(7, 7));
+
+        // zooming in on the zoomed in copy
+        rotozoomer.draw_feedback(&mut dest);
+        assert_eq!(Some(27), dest.get_pixel(0, 0));
+    }
+}
@@ -0,0 +1,258 @@
+use crate::graphics::*;
+use crate::math::*;
+use crate::utils::prng::Prng;
+
+/// Settings for one layer of stars in a [Starfield].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StarfieldLayer {
+    /// How many stars are in the layer.
+    pub num_stars: usize,
+    /// How fast the layer's stars move, in pixels per second. Giving layers further away slower
+    /// speeds creates a parallax effect.
+    pub speed: f32,
+    /// The palette color the layer's stars are drawn with.
+    pub color: u8,
+}
+
+impl StarfieldLayer {
+    #[inline]
+    pub fn new(num_stars: usize, speed: f32, color: u8) -> StarfieldLayer {
+        StarfieldLayer {
+            num_stars,
+            speed,
+            color,
+        }
+    }
+}
+
+/// A single star in a [Starfield].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Star {
+    /// The position of the star, relative to the top-left corner of the starfield.
+    pub position: Vector2,
+    /// The index of the layer the star belongs to.
+    pub layer: usize,
+}
+
+// wraps a coordinate around to the other side of the starfield. rem_euclid() alone can round tiny
+// negative values up to exactly `size`, which would still be outside of the starfield.
+#[inline]
+fn wrap(value: f32, size: f32) -> f32 {
+    let value = value.rem_euclid(size);
+    if value >= size {
+        0.0
+    } else {
+        value
+    }
+}
+
+/// The classic scrolling starfield, with multiple layers of stars moving at different speeds.
+/// Stars which move off one edge of the starfield come back in on the opposite edge at a new
+/// random position along it.
+#[derive(Debug, Clone)]
+pub struct Starfield {
+    /// The direction the stars move in, which should be a unit vector.
+    pub direction: Vector2,
+    width: u32,
+    height: u32,
+    layers: Vec<StarfieldLayer>,
+    stars: Vec<Star>,
+    prng: Prng,
+}
+
+impl Starfield {
+    /// Creates a new starfield, with its stars placed randomly. The stars move towards the left
+    /// to begin with.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the area the stars move around in
+    /// * `height`: the height of the area the stars move around in
+    /// * `layers`: the layers of stars. Layers are drawn in this order, so the furthest away
+    ///   layers should be listed first.
+    /// * `seed`: the seed for the random placement of the stars. The same seed always gives the
+    ///   same starfield.
+    pub fn new(width: u32, height: u32, layers: &[StarfieldLayer], seed: u64) -> Starfield {
+        let mut prng = Prng::new(seed);
+        let mut stars = Vec::new();
+        for (index, layer) in layers.iter().enumerate() {
+            for _ in 0..layer.num_stars {
+                stars.push(Star {
+                    position: Vector2::new(
+                        prng.next_f32() * width as f32,
+                        prng.next_f32() * height as f32,
+                    ),
+                    layer: index,
+                });
+            }
+        }
+        Starfield {
+            direction: Vector2::LEFT,
+            width,
+            height,
+            layers: layers.to_vec(),
+            stars,
+            prng,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn layers(&self) -> &[StarfieldLayer] {
+        &self.layers
+    }
+
+    /// Returns all of the stars, in the order they are drawn.
+    #[inline]
+    pub fn stars(&self) -> &[Star] {
+        &self.stars
+    }
+
+    /// Moves the stars along by the given amount of time, in seconds.
+    pub fn update(&mut self, delta: f32) {
+        let width = self.width as f32;
+        let height = self.height as f32;
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+
+        for star in self.stars.iter_mut() {
+            let speed = self.layers[star.layer].speed;
+            let mut position = star.position + self.direction * (speed * delta);
+            if position.x < 0.0 || position.x >= width {
+                position.x = wrap(position.x, width);
+                position.y = self.prng.next_f32() * height;
+            }
+            if position.y < 0.0 || position.y >= height {
+                position.y = wrap(position.y, height);
+                position.x = self.prng.next_f32() * width;
+            }
+            star.position = position;
+        }
+    }
+
+    /// Draws the stars onto the destination bitmap, with the top-left corner of the starfield at
+    /// the given position. Only the stars are drawn, so the bitmap should usually be cleared
+    /// first.
+    pub fn draw(&self, dest: &mut Bitmap, x: i32, y: i32) {
+        for star in self.stars.iter() {
+            let color = self.layers[star.layer].color;
+            dest.set_pixel(
+                x + star.position.x as i32,
+                y + star.position.y as i32,
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const LAYERS: [StarfieldLayer; 2] = [
+        StarfieldLayer {
+            num_stars: 10,
+            speed: 10.0,
+            color: 1,
+        },
+        StarfieldLayer {
+            num_stars: 5,
+            speed: 40.0,
+            color: 2,
+        },
+    ];
+
+    fn in_bounds(starfield: &Starfield) -> bool {
+        starfield.stars().iter().all(|star| {
+            star.position.x >= 0.0
+                && star.position.y >= 0.0
+                && star.position.x < starfield.width() as f32
+                && star.position.y < starfield.height() as f32
+        })
+    }
+
+    #[test]
+    pub fn creating_and_moving_stars() {
+        let mut starfield = Starfield::new(64, 32, &LAYERS, 42);
+        assert_eq!(15, starfield.stars().len());
+        assert_eq!(
+            10,
+            starfield
+                .stars()
+                .iter()
+                .filter(|star| star.layer == 0)
+                .count()
+        );
+        assert_eq!(
+            5,
+            starfield
+                .stars()
+                .iter()
+                .filter(|star| star.layer == 1)
+                .count()
+        );
+        assert!(in_bounds(&starfield));
+        assert_eq!(
+            starfield.stars(),
+            Starfield::new(64, 32, &LAYERS, 42).stars()
+        );
+
+        let before = starfield.stars().to_vec();
+        starfield.update(0.1);
+        for (old, new) in before.iter().zip(starfield.stars().iter()) {
+            let distance = LAYERS[old.layer].speed * 0.1;
+            if old.position.x >= distance {
+                assert!(nearly_equal(
+                    new.position.x,
+                    old.position.x - distance,
+                    0.001
+                ));
+                assert_eq!(new.position.y, old.position.y);
+            } else {
+                // wrapped around to the right edge
+                assert!(new.position.x >= 64.0 - distance);
+            }
+        }
+
+        starfield.direction = Vector2::new(0.6, 0.8);
+        for _ in 0..100 {
+            starfield.update(0.1);
+            assert!(in_bounds(&starfield));
+        }
+    }
+
+    #[test]
+    pub fn drawing_stars() {
+        let starfield = Starfield::new(64, 32, &LAYERS, 42);
+        let mut dest = Bitmap::new(64, 32).unwrap();
+        starfield.draw(&mut dest, 0, 0);
+
+        let num_drawn = dest.pixels().iter().filter(|&&pixel| pixel != 0).count();
+        assert!(num_drawn > 0 && num_drawn <= 15);
+        for star in starfield.stars().iter() {
+            let pixel = dest
+                .get_pixel(star.position.x as i32, star.position.y as i32)
+                .unwrap();
+            // the nearer layer is drawn last, so its stars are never covered up
+            if star.layer == 1 {
+                assert_eq!(2, pixel);
+            } else {
+                assert_ne!(0, pixel);
+            }
+        }
+
+        // stars outside of the bitmap are clipped
+        let mut dest = Bitmap::new(8, 8).unwrap();
+        starfield.draw(&mut dest, -32, -16);
+    }
+}
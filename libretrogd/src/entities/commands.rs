@@ -0,0 +1,191 @@
+use crate::entities::*;
+
+type CommandFn = Box<dyn FnOnce(&mut Entities)>;
+
+/// A buffer of changes to be made to an [`Entities`] instance at a later time, such as adding and
+/// removing entities and components. Since component stores are borrowed while iterating over
+/// them, entities and components cannot be added or removed at the same time. Instead, the
+/// changes can be recorded here while iterating, and then all applied in the order they were
+/// recorded via [`EntityCommands::apply`] once nothing is borrowed anymore (for example, after all
+/// of the update component systems have been run).
+///
+/// This is meant to be kept alongside [`Entities`] in the context passed to your component system
+/// functions.
+///
+/// ```
+/// use libretrogd::entities::*;
+///
+/// struct Health(u32);
+///
+/// let mut entities = Entities::new();
+/// let mut commands = EntityCommands::new();
+/// let id = entities.new_entity();
+/// entities.add_component(id, Health(0));
+///
+/// for (entity, (health,)) in &mut entities.query::<(&Health,)>() {
+///     if health.0 == 0 {
+///         commands.remove_entity(entity);
+///     }
+/// }
+/// commands.apply(&mut entities);
+/// assert!(!entities.has_entity(id));
+/// ```
+pub struct EntityCommands {
+    commands: Vec<CommandFn>,
+}
+
+impl std::fmt::Debug for EntityCommands {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EntityCommands")
+            .field("commands.len()", &self.commands.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for EntityCommands {
+    fn default() -> Self {
+        EntityCommands::new()
+    }
+}
+
+impl EntityCommands {
+    /// Creates and returns a new, empty, command buffer.
+    pub fn new() -> Self {
+        EntityCommands {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Returns the number of commands waiting to be applied.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns true if there are no commands waiting to be applied.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Discards all of the commands waiting to be applied. Note that any entity IDs reserved by
+    /// [`EntityCommands::spawn`] will not be re-used.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Records an arbitrary change to be made to the entities, for anything not covered by the
+    /// other commands.
+    pub fn add<F: FnOnce(&mut Entities) + 'static>(&mut self, f: F) {
+        self.commands.push(Box::new(f));
+    }
+
+    /// Records the creation of a new entity, returning the ID that it will have. The ID is
+    /// reserved straight away, so it can be used in other commands recorded afterwards (e.g. to
+    /// add components to the new entity), but the entity itself will not exist until the commands
+    /// are applied.
+    pub fn spawn(&mut self, entities: &Entities) -> EntityId {
+        let entity = entities.reserve_entity();
+        self.add(move |entities| entities.add_reserved_entity(entity));
+        entity
+    }
+
+    /// Records the creation of a new entity with copies of all of the given prefab's components,
+    /// returning the ID that it will have. See [`EntityCommands::spawn`].
+    pub fn spawn_prefab(&mut self, entities: &Entities, prefab: &Prefab) -> EntityId {
+        let entity = self.spawn(entities);
+        let prefab = prefab.clone();
+        self.add(move |entities| prefab.add_to(entities, entity));
+        entity
+    }
+
+    /// Records the removal of an entity and all of its components. See
+    /// [`Entities::remove_entity`].
+    pub fn remove_entity(&mut self, entity: EntityId) {
+        self.add(move |entities| {
+            entities.remove_entity(entity);
+        });
+    }
+
+    /// Records the addition of a component to an entity, replacing any existing component of the
+    /// same type. See [`Entities::add_component`].
+    pub fn add_component<T: Component>(&mut self, entity: EntityId, component: T) {
+        self.add(move |entities| {
+            entities.add_component(entity, component);
+        });
+    }
+
+    /// Records the removal of a component from an entity. See [`Entities::remove_component`].
+    pub fn remove_component<T: Component>(&mut self, entity: EntityId) {
+        self.add(move |entities| {
+            entities.remove_component::<T>(entity);
+        });
+    }
+
+    /// Applies all of the recorded commands to the given entities, in the order they were
+    /// recorded, leaving this command buffer empty. None of the entities' component stores can be
+    /// borrowed when this is called.
+    pub fn apply(&mut self, entities: &mut Entities) {
+        for command in self.commands.drain(..) {
+            command(entities);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    struct Position(i32, i32);
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    struct Health(u32);
+
+    #[test]
+    pub fn deferred_commands() {
+        let mut em = Entities::new();
+        let mut commands = EntityCommands::new();
+        let a = em.new_entity();
+        em.add_component(a, Health(0));
+        em.add_component(a, Position(1, 1));
+        let b = em.new_entity();
+        em.add_component(b, Health(10));
+        em.add_component(b, Position(2, 2));
+
+        let mut spawned = Vec::new();
+        for (entity, (health, position)) in &mut em.query::<(&Health, &Position)>() {
+            if health.0 == 0 {
+                commands.remove_entity(entity);
+                let spawned_entity = commands.spawn(&em);
+                commands.add_component(spawned_entity, position.clone());
+                spawned.push(spawned_entity);
+            } else {
+                commands.remove_component::<Position>(entity);
+            }
+        }
+        let prefab = Prefab::new().with(Health(5));
+        let c = commands.spawn_prefab(&em, &prefab);
+        assert_eq!(6, commands.len());
+
+        assert_eq!(1, spawned.len());
+        let spawned = spawned[0];
+        assert!(!em.has_entity(spawned));
+        assert_ne!(spawned, em.new_entity());
+
+        commands.apply(&mut em);
+        assert!(commands.is_empty());
+        assert!(!em.has_entity(a));
+        assert!(em.has_entity(b));
+        assert!(!em.has_component::<Position>(b));
+        assert!(em.has_entity(spawned));
+        assert_eq!(
+            Position(1, 1),
+            *em.components::<Position>().unwrap().get(&spawned).unwrap()
+        );
+        assert_eq!(
+            Health(5),
+            *em.components::<Health>().unwrap().get(&c).unwrap()
+        );
+    }
+}
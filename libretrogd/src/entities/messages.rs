@@ -0,0 +1,207 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt::Formatter;
+
+use crate::utils::AsAny;
+
+// alias `Message` to always be `'static` ...
+pub trait Message: 'static {}
+impl<T: 'static> Message for T {}
+
+pub trait GenericMessageQueue: AsAny {
+    /// Returns the number of messages currently in this queue.
+    fn len(&self) -> usize;
+
+    /// Returns true if this queue currently has no messages in it.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all messages from this queue.
+    fn clear(&mut self);
+}
+
+impl<T: Message> GenericMessageQueue for Vec<T> {
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+}
+
+/// A typed message bus that "component systems" can use to communicate with each other (e.g.
+/// "damage dealt" or "entity spawned") without being directly coupled to each other. Systems
+/// publish messages of any type during an update, and systems that run later read or drain all of
+/// the messages of the types they are interested in. Messages are kept in the order they were
+/// published, and all messages are discarded when [`MessageBus::clear`] is called, which would
+/// normally be done once per frame.
+///
+/// This is meant to be kept alongside [`crate::entities::Entities`] in the context passed to
+/// your component system functions, so that messages can be published while iterating over
+/// component stores.
+///
+/// ```
+/// use libretrogd::entities::*;
+///
+/// struct Damaged {
+///     entity: EntityId,
+///     amount: u32,
+/// }
+///
+/// let mut messages = MessageBus::new();
+/// // in one system ...
+/// messages.publish(Damaged { entity: 1, amount: 10 });
+/// // and then later in another system ...
+/// for damaged in messages.read::<Damaged>() {
+///     println!("entity {} took {} damage", damaged.entity, damaged.amount);
+/// }
+/// // and once the frame is over
+/// messages.clear();
+/// ```
+pub struct MessageBus {
+    queues: HashMap<TypeId, Box<dyn GenericMessageQueue>>,
+}
+
+impl std::fmt::Debug for MessageBus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageBus")
+            .field("queues.keys()", &self.queues.keys())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for MessageBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageBus {
+    /// Creates and returns a new, empty, message bus.
+    pub fn new() -> Self {
+        MessageBus {
+            queues: HashMap::new(),
+        }
+    }
+
+    fn get_queue<T: Message>(&self) -> Option<&Vec<T>> {
+        self.queues
+            .get(&TypeId::of::<T>())
+            .map(|queue| queue.as_ref().as_any().downcast_ref().unwrap())
+    }
+
+    fn get_queue_mut<T: Message>(&mut self) -> Option<&mut Vec<T>> {
+        self.queues
+            .get_mut(&TypeId::of::<T>())
+            .map(|queue| queue.as_mut().as_any_mut().downcast_mut().unwrap())
+    }
+
+    fn get_or_add_queue<T: Message>(&mut self) -> &mut Vec<T> {
+        self.queues
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<T>::new()))
+            .as_mut()
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Publishes the given message, adding it to the end of the queue of messages of its type.
+    #[inline]
+    pub fn publish<T: Message>(&mut self, message: T) {
+        self.get_or_add_queue().push(message);
+    }
+
+    /// Returns all of the messages of the given type that have been published since the last time
+    /// the bus was cleared (or that type of message was drained), in the order they were
+    /// published. The messages are left in place for any other systems to read too.
+    pub fn read<T: Message>(&self) -> &[T] {
+        match self.get_queue::<T>() {
+            Some(queue) => queue.as_slice(),
+            None => &[],
+        }
+    }
+
+    /// Removes and returns all of the messages of the given type that have been published since
+    /// the last time the bus was cleared (or that type of message was drained), in the order they
+    /// were published. Use this instead of [`MessageBus::read`] when no other systems need to see
+    /// these messages, or to take ownership of them.
+    pub fn drain<T: Message>(&mut self) -> std::vec::Drain<'_, T> {
+        self.get_or_add_queue().drain(..)
+    }
+
+    /// Returns the number of messages of the given type that are currently queued.
+    #[inline]
+    pub fn len<T: Message>(&self) -> usize {
+        self.get_queue::<T>().map_or(0, Vec::len)
+    }
+
+    /// Returns true if there are no messages of the given type currently queued.
+    #[inline]
+    pub fn is_empty<T: Message>(&self) -> bool {
+        self.len::<T>() == 0
+    }
+
+    /// Discards all queued messages of every type. This would normally be called once per frame,
+    /// after all of the component systems have been run.
+    pub fn clear(&mut self) {
+        for queue in self.queues.values_mut() {
+            queue.clear();
+        }
+    }
+
+    /// Discards all queued messages of the given type only.
+    pub fn clear_messages<T: Message>(&mut self) {
+        if let Some(queue) = self.get_queue_mut::<T>() {
+            queue.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct Damaged(u32);
+    #[derive(Debug, Eq, PartialEq)]
+    struct Spawned(&'static str);
+
+    #[test]
+    pub fn publishing_and_reading_messages() {
+        let mut messages = MessageBus::new();
+        assert!(messages.is_empty::<Damaged>());
+        assert!(messages.read::<Damaged>().is_empty());
+
+        messages.publish(Damaged(5));
+        messages.publish(Spawned("goblin"));
+        messages.publish(Damaged(10));
+        assert_eq!(2, messages.len::<Damaged>());
+        assert_eq!(1, messages.len::<Spawned>());
+
+        // reading leaves the messages in place
+        assert_eq!(&[Damaged(5), Damaged(10)], messages.read::<Damaged>());
+        assert_eq!(&[Damaged(5), Damaged(10)], messages.read::<Damaged>());
+
+        // draining does not
+        assert_eq!(
+            vec![Spawned("goblin")],
+            messages.drain::<Spawned>().collect::<Vec<_>>()
+        );
+        assert!(messages.is_empty::<Spawned>());
+        assert_eq!(0, messages.drain::<u32>().count());
+
+        messages.clear_messages::<Damaged>();
+        assert!(messages.is_empty::<Damaged>());
+
+        messages.publish(Damaged(1));
+        messages.publish(Spawned("orc"));
+        messages.clear();
+        assert!(messages.is_empty::<Damaged>());
+        assert!(messages.is_empty::<Spawned>());
+    }
+}
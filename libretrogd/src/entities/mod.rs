@@ -1,10 +1,26 @@
 use std::any::TypeId;
-use std::cell::{Ref, RefCell, RefMut};
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Formatter;
 
 use crate::utils::AsAny;
 
+pub use self::commands::*;
+pub use self::messages::*;
+pub use self::prefab::*;
+pub use self::query::*;
+#[cfg(feature = "serde")]
+pub use self::save::*;
+pub use self::schedule::*;
+
+pub mod commands;
+pub mod messages;
+pub mod prefab;
+pub mod query;
+#[cfg(feature = "serde")]
+pub mod save;
+pub mod schedule;
+
 pub type EntityId = usize;
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
@@ -27,6 +43,16 @@ pub trait GenericComponentStore: AsAny {
 
     /// Removes all components from this store.
     fn clear(&mut self);
+
+    /// Returns the number of components in this store.
+    fn len(&self) -> usize;
+
+    /// Returns true if this store has no components in it.
+    fn is_empty(&self) -> bool;
+
+    /// Returns the name of the type of component held in this store. Intended only for
+    /// debugging purposes, such as listing an entity's components.
+    fn type_name(&self) -> &'static str;
 }
 
 impl<T: Component> GenericComponentStore for ComponentStore<T> {
@@ -44,6 +70,21 @@ impl<T: Component> GenericComponentStore for ComponentStore<T> {
     fn clear(&mut self) {
         self.get_mut().clear();
     }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.borrow().len()
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.borrow().is_empty()
+    }
+
+    #[inline]
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
 }
 
 #[inline]
@@ -66,7 +107,7 @@ pub fn as_component_store_mut<T: Component>(
 pub struct Entities {
     entities: HashSet<EntityId>,
     component_stores: HashMap<TypeId, Box<dyn GenericComponentStore>>,
-    next_id: EntityId,
+    next_id: Cell<EntityId>,
 }
 
 impl std::fmt::Debug for Entities {
@@ -74,7 +115,7 @@ impl std::fmt::Debug for Entities {
         f.debug_struct("Entities")
             .field("entities.len()", &self.entities.len())
             .field("component_stores.keys()", &self.component_stores.keys())
-            .field("next_id", &self.next_id)
+            .field("next_id", &self.next_id.get())
             .finish_non_exhaustive()
     }
 }
@@ -85,7 +126,7 @@ impl Entities {
         Entities {
             entities: HashSet::new(),
             component_stores: HashMap::new(),
-            next_id: 0,
+            next_id: Cell::new(0),
         }
     }
 
@@ -126,12 +167,25 @@ impl Entities {
 
     /// Returns a previously unused entity ID. Use this to "create" a new entity.
     pub fn new_entity(&mut self) -> EntityId {
-        let new_entity_id = self.next_id;
-        self.next_id = self.next_id.wrapping_add(1);
+        let new_entity_id = self.reserve_entity();
         self.entities.insert(new_entity_id);
         new_entity_id
     }
 
+    /// Returns a previously unused entity ID without actually creating the entity yet. No other
+    /// entity will be given this ID. This is mainly used by [`EntityCommands::spawn`] so that the
+    /// IDs of entities to be created later can be known straight away.
+    pub fn reserve_entity(&self) -> EntityId {
+        let new_entity_id = self.next_id.get();
+        self.next_id.set(new_entity_id.wrapping_add(1));
+        new_entity_id
+    }
+
+    /// Creates an entity using an ID previously returned by [`Entities::reserve_entity`].
+    pub(crate) fn add_reserved_entity(&mut self, entity: EntityId) {
+        self.entities.insert(entity);
+    }
+
     /// Removes an entity, making the entity ID unusable with this entity manager as well as
     /// removing all of the entity's components. Returns true if the entity was removed, false if
     /// the entity ID given did not exist.
@@ -243,6 +297,51 @@ impl Entities {
             self.add_component_store::<T>();
         }
     }
+
+    /// Returns the number of entities.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns true if there are no entities.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Returns an iterator over the IDs of all of the entities, in no particular order.
+    pub fn entity_ids(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.entities.iter().copied()
+    }
+
+    /// Returns the names of the types of all of the components the given entity has, in sorted
+    /// order. Intended only for debugging purposes, as type names are not guaranteed to be
+    /// stable. None of the component stores can be mutably borrowed when this is called.
+    pub fn component_type_names(&self, entity: EntityId) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self
+            .component_stores
+            .values()
+            .filter(|component_store| component_store.has(entity))
+            .map(|component_store| component_store.type_name())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the name of each type of component along with the number of components of that
+    /// type, in sorted order by name. Intended only for debugging purposes, as type names are
+    /// not guaranteed to be stable. None of the component stores can be mutably borrowed when
+    /// this is called.
+    pub fn component_counts(&self) -> Vec<(&'static str, usize)> {
+        let mut counts: Vec<(&'static str, usize)> = self
+            .component_stores
+            .values()
+            .map(|component_store| (component_store.type_name(), component_store.len()))
+            .collect();
+        counts.sort_unstable();
+        counts
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
@@ -425,12 +524,18 @@ pub type RenderFn<T> = fn(&mut T);
 /// This is a totally optional minor convenience to help you to manage your "component systems"
 /// and ensure they are always called in the same order.
 ///
+/// By default, systems are called in the order they were added. Each system can also be given a
+/// label and ordering constraints relative to other labelled systems via the [`SystemOrdering`]
+/// returned when adding it, in which case the order the systems are called in is worked out from
+/// those constraints instead (keeping the order they were added in wherever the constraints allow
+/// it).
+///
 /// The generic types `U` and `R` refer to application-specific context types that are needed by
 /// all of your "update" and "render" component system functions. Both of these types may be the
 /// same type or different depending on your needs.
 pub struct ComponentSystems<U, R> {
-    update_systems: Vec<UpdateFn<U>>,
-    render_systems: Vec<RenderFn<R>>,
+    update_systems: SystemSchedule<U>,
+    render_systems: SystemSchedule<R>,
 }
 
 impl<U, R> std::fmt::Debug for ComponentSystems<U, R> {
@@ -445,21 +550,35 @@ impl<U, R> std::fmt::Debug for ComponentSystems<U, R> {
 impl<U, R> ComponentSystems<U, R> {
     pub fn new() -> Self {
         ComponentSystems {
-            update_systems: Vec::new(),
-            render_systems: Vec::new(),
+            update_systems: SystemSchedule::new(),
+            render_systems: SystemSchedule::new(),
         }
     }
 
-    /// Adds an update component system function to the list of functions that will be called in
-    /// order whenever [`ComponentSystems::update`] is called.
-    pub fn add_update_system(&mut self, f: UpdateFn<U>) {
-        self.update_systems.push(f);
+    /// Adds an update component system function to the list of functions that will be called
+    /// whenever [`ComponentSystems::update`] is called. The returned [`SystemOrdering`] can be
+    /// used to label the system and control when it is called relative to other systems.
+    pub fn add_update_system(&mut self, f: UpdateFn<U>) -> SystemOrdering<'_> {
+        self.update_systems.add(f)
+    }
+
+    /// Adds a render component system function to the list of functions that will be called
+    /// whenever [`ComponentSystems::render`] is called. The returned [`SystemOrdering`] can be
+    /// used to label the system and control when it is called relative to other systems.
+    pub fn add_render_system(&mut self, f: RenderFn<R>) -> SystemOrdering<'_> {
+        self.render_systems.add(f)
+    }
+
+    /// Removes all update component system functions with the given label, returning the number
+    /// of functions removed.
+    pub fn remove_update_systems(&mut self, label: &str) -> usize {
+        self.update_systems.remove(label)
     }
 
-    /// Adds a render component system function to the list of functions that will be called in
-    /// order whenever [`ComponentSystems::render`] is called.
-    pub fn add_render_system(&mut self, f: RenderFn<R>) {
-        self.render_systems.push(f);
+    /// Removes all render component system functions with the given label, returning the number
+    /// of functions removed.
+    pub fn remove_render_systems(&mut self, label: &str) -> usize {
+        self.render_systems.remove(label)
     }
 
     /// Removes all existing update and render component system functions.
@@ -468,27 +587,32 @@ impl<U, R> ComponentSystems<U, R> {
         self.render_systems.clear();
     }
 
-    /// Calls each of the update component system functions in the same order that they were added,
-    /// passing each of them the context argument provided.
+    /// Checks that the ordering constraints given for all of the update and render component
+    /// system functions can be satisfied. [`ComponentSystems::update`] and
+    /// [`ComponentSystems::render`] will panic if they cannot be, so this can be called once all
+    /// of the systems have been added to find out about any mistakes up front instead.
+    pub fn check_order(&mut self) -> Result<(), SystemOrderError> {
+        self.update_systems.check_order()?;
+        self.render_systems.check_order()
+    }
+
+    /// Calls each of the update component system functions in order, passing each of them the
+    /// context argument provided.
     pub fn update(&mut self, context: &mut U) {
-        for f in self.update_systems.iter_mut() {
-            f(context);
-        }
+        self.update_systems.run(context);
     }
 
-    /// Calls each of the render component system functions in the same order that they were added,
-    /// passing each of them the context argument provided.
+    /// Calls each of the render component system functions in order, passing each of them the
+    /// context argument provided.
     pub fn render(&mut self, context: &mut R) {
-        for f in self.render_systems.iter_mut() {
-            f(context);
-        }
+        self.render_systems.run(context);
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use claim::*;
 
     use super::*;
@@ -539,6 +663,42 @@ mod tests {
         assert_ne!(b, c);
     }
 
+    #[test]
+    fn inspecting_entities() {
+        let mut em = Entities::new();
+        assert!(em.is_empty());
+
+        let a = em.new_entity();
+        em.add_component(a, Position(1, 2));
+        em.add_component(a, Health(10));
+        let b = em.new_entity();
+        em.add_component(b, Position(3, 4));
+        assert_eq!(2, em.len());
+
+        let mut ids: Vec<EntityId> = em.entity_ids().collect();
+        ids.sort_unstable();
+        assert_eq!(vec![a, b], ids);
+
+        assert_eq!(
+            vec![
+                std::any::type_name::<Health>(),
+                std::any::type_name::<Position>()
+            ],
+            em.component_type_names(a)
+        );
+        assert_eq!(
+            vec![std::any::type_name::<Position>()],
+            em.component_type_names(b)
+        );
+        assert_eq!(
+            vec![
+                (std::any::type_name::<Health>(), 1),
+                (std::any::type_name::<Position>(), 2)
+            ],
+            em.component_counts()
+        );
+    }
+
     #[test]
     fn add_and_remove_entity_components() {
         let mut em = Entities::new();
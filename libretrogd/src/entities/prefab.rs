@@ -0,0 +1,504 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::{BufReader, Read};
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::entities::*;
+
+#[derive(Error, Debug)]
+pub enum PrefabError {
+    #[error("There is no prefab named \"{0}\"")]
+    UnknownPrefab(String),
+
+    #[error("There is no component type registered with the name \"{0}\"")]
+    UnknownComponentType(String),
+
+    #[error("Bad or unsupported prefab data: {0}")]
+    BadData(String),
+
+    #[error("Prefab I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+/// A component that can be part of a [`Prefab`]. Implemented for all components that can be
+/// cloned, as each entity spawned from a prefab gets its own copy of the prefab's components.
+pub trait PrefabComponent: AsAny {
+    /// Adds a copy of this component to the given entity.
+    fn add_to(&self, entities: &mut Entities, entity: EntityId);
+
+    /// Returns a boxed copy of this component.
+    fn clone_boxed(&self) -> Box<dyn PrefabComponent>;
+}
+
+impl<T: Component + Clone> PrefabComponent for T {
+    #[inline]
+    fn add_to(&self, entities: &mut Entities, entity: EntityId) {
+        entities.add_component(entity, self.clone());
+    }
+
+    #[inline]
+    fn clone_boxed(&self) -> Box<dyn PrefabComponent> {
+        Box::new(self.clone())
+    }
+}
+
+/// A bundle of components that can be used as a template to spawn any number of entities with
+/// the same set of components. Each entity spawned gets its own copy of the components.
+///
+/// ```
+/// use libretrogd::entities::*;
+///
+/// #[derive(Clone)]
+/// struct Position(i32, i32);
+/// #[derive(Clone)]
+/// struct Health(u32);
+///
+/// let goblin = Prefab::new()
+///     .with(Position(0, 0))
+///     .with(Health(20));
+///
+/// let mut entities = Entities::new();
+/// let a = goblin.spawn(&mut entities);
+/// let b = goblin.spawn_with(&mut entities, &Prefab::new().with(Position(10, 5)));
+/// ```
+pub struct Prefab {
+    components: Vec<(TypeId, Box<dyn PrefabComponent>)>,
+}
+
+impl std::fmt::Debug for Prefab {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Prefab")
+            .field("components.len()", &self.components.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Clone for Prefab {
+    fn clone(&self) -> Self {
+        Prefab {
+            components: self
+                .components
+                .iter()
+                .map(|(type_id, component)| (*type_id, component.as_ref().clone_boxed()))
+                .collect(),
+        }
+    }
+}
+
+impl Default for Prefab {
+    fn default() -> Self {
+        Prefab::new()
+    }
+}
+
+impl Prefab {
+    /// Creates and returns a new prefab with no components.
+    pub fn new() -> Self {
+        Prefab {
+            components: Vec::new(),
+        }
+    }
+
+    /// Returns this prefab with the given component added to it, replacing any existing
+    /// component of the same type.
+    #[inline]
+    pub fn with<T: Component + Clone>(mut self, component: T) -> Self {
+        self.set(component);
+        self
+    }
+
+    /// Adds the given component to this prefab, replacing any existing component of the same
+    /// type.
+    pub fn set<T: Component + Clone>(&mut self, component: T) {
+        self.set_boxed(TypeId::of::<T>(), Box::new(component));
+    }
+
+    fn set_boxed(&mut self, type_id: TypeId, component: Box<dyn PrefabComponent>) {
+        match self.components.iter_mut().find(|(id, _)| *id == type_id) {
+            Some((_, existing)) => *existing = component,
+            None => self.components.push((type_id, component)),
+        }
+    }
+
+    /// Removes any component of the given type from this prefab. Returns true if there was one.
+    pub fn remove<T: Component>(&mut self) -> bool {
+        let type_id = TypeId::of::<T>();
+        let before_len = self.components.len();
+        self.components.retain(|(id, _)| *id != type_id);
+        before_len != self.components.len()
+    }
+
+    /// Returns true if this prefab has a component of the given type.
+    pub fn has<T: Component>(&self) -> bool {
+        let type_id = TypeId::of::<T>();
+        self.components.iter().any(|(id, _)| *id == type_id)
+    }
+
+    /// Returns this prefab's component of the given type, if it has one.
+    pub fn get<T: Component>(&self) -> Option<&T> {
+        let type_id = TypeId::of::<T>();
+        self.components
+            .iter()
+            .find(|(id, _)| *id == type_id)
+            .and_then(|(_, component)| component.as_ref().as_any().downcast_ref())
+    }
+
+    /// Returns the number of components in this prefab.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Returns true if this prefab has no components.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Adds copies of all of this prefab's components to the given (existing) entity, replacing
+    /// any components of the same types that it already had.
+    pub fn add_to(&self, entities: &mut Entities, entity: EntityId) {
+        for (_, component) in self.components.iter() {
+            component.as_ref().add_to(entities, entity);
+        }
+    }
+
+    /// Spawns a new entity with copies of all of this prefab's components, returning its ID.
+    pub fn spawn(&self, entities: &mut Entities) -> EntityId {
+        let entity = entities.new_entity();
+        self.add_to(entities, entity);
+        entity
+    }
+
+    /// Spawns a new entity with copies of all of this prefab's components, except for any that
+    /// have been overridden by components of the same type in `overrides`. Any components in
+    /// `overrides` that this prefab does not have are added to the entity too.
+    pub fn spawn_with(&self, entities: &mut Entities, overrides: &Prefab) -> EntityId {
+        let entity = self.spawn(entities);
+        overrides.add_to(entities, entity);
+        entity
+    }
+}
+
+/// A collection of named [`Prefab`]s, such as all of the different types of entities that can
+/// appear in a game's levels.
+#[derive(Debug, Clone, Default)]
+pub struct Prefabs {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl Prefabs {
+    /// Creates and returns a new, empty, collection of prefabs.
+    pub fn new() -> Self {
+        Prefabs {
+            prefabs: HashMap::new(),
+        }
+    }
+
+    /// Adds the given prefab with the given name, returning any prefab that previously had that
+    /// name.
+    pub fn add(&mut self, name: &str, prefab: Prefab) -> Option<Prefab> {
+        self.prefabs.insert(String::from(name), prefab)
+    }
+
+    /// Removes and returns the prefab with the given name, if there is one.
+    pub fn remove(&mut self, name: &str) -> Option<Prefab> {
+        self.prefabs.remove(name)
+    }
+
+    /// Returns the prefab with the given name, if there is one.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Returns true if there is a prefab with the given name.
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    /// Returns the number of prefabs in this collection.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.prefabs.len()
+    }
+
+    /// Returns true if this collection has no prefabs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.prefabs.is_empty()
+    }
+
+    /// Returns an iterator over the names of all of the prefabs in this collection.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs.keys().map(String::as_str)
+    }
+
+    /// Spawns a new entity from the prefab with the given name, returning its ID. See
+    /// [`Prefab::spawn`].
+    pub fn spawn(&self, name: &str, entities: &mut Entities) -> Result<EntityId, PrefabError> {
+        match self.prefabs.get(name) {
+            Some(prefab) => Ok(prefab.spawn(entities)),
+            None => Err(PrefabError::UnknownPrefab(String::from(name))),
+        }
+    }
+
+    /// Spawns a new entity from the prefab with the given name with some of its components
+    /// overridden, returning its ID. See [`Prefab::spawn_with`].
+    pub fn spawn_with(
+        &self,
+        name: &str,
+        entities: &mut Entities,
+        overrides: &Prefab,
+    ) -> Result<EntityId, PrefabError> {
+        match self.prefabs.get(name) {
+            Some(prefab) => Ok(prefab.spawn_with(entities, overrides)),
+            None => Err(PrefabError::UnknownPrefab(String::from(name))),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+type ComponentLoaderFn = fn(Value) -> Result<(TypeId, Box<dyn PrefabComponent>), serde_json::Error>;
+
+#[cfg(feature = "serde")]
+fn load_component<T: Component + Clone + DeserializeOwned>(
+    value: Value,
+) -> Result<(TypeId, Box<dyn PrefabComponent>), serde_json::Error> {
+    Ok((
+        TypeId::of::<T>(),
+        Box::new(serde_json::from_value::<T>(value)?),
+    ))
+}
+
+/// The component types that can appear in prefabs loaded from JSON, each registered with the name
+/// used for it in the JSON. Only available with the "serde" feature enabled.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Default)]
+pub struct PrefabComponentTypes {
+    loaders: HashMap<String, ComponentLoaderFn>,
+}
+
+#[cfg(feature = "serde")]
+impl PrefabComponentTypes {
+    /// Creates and returns a new set of component types with nothing registered yet.
+    pub fn new() -> Self {
+        PrefabComponentTypes {
+            loaders: HashMap::new(),
+        }
+    }
+
+    /// Registers the given component type so that it can be loaded from JSON using the given
+    /// name. The component type must be deserializable with serde.
+    pub fn register<T: Component + Clone + DeserializeOwned>(&mut self, name: &str) {
+        self.loaders
+            .insert(String::from(name), load_component::<T> as ComponentLoaderFn);
+    }
+
+    /// Returns true if a component type has been registered with the given name.
+    #[inline]
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.loaders.contains_key(name)
+    }
+
+    /// Loads a single [`Prefab`] from a JSON object mapping registered component type names to
+    /// the components' values.
+    pub fn load_prefab(&self, json: &Value) -> Result<Prefab, PrefabError> {
+        let components = match json {
+            Value::Object(components) => components,
+            _ => {
+                return Err(PrefabError::BadData(String::from(
+                    "Prefab is not a JSON object",
+                )))
+            }
+        };
+        let mut prefab = Prefab::new();
+        for (name, value) in components.iter() {
+            let loader = match self.loaders.get(name) {
+                Some(loader) => loader,
+                None => return Err(PrefabError::UnknownComponentType(name.clone())),
+            };
+            match loader(value.clone()) {
+                Ok((type_id, component)) => prefab.set_boxed(type_id, component),
+                Err(error) => {
+                    return Err(PrefabError::BadData(format!(
+                        "Component \"{}\": {}",
+                        name, error
+                    )))
+                }
+            }
+        }
+        Ok(prefab)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Prefabs {
+    /// Loads prefabs from JSON and adds them to this collection, replacing any existing prefabs
+    /// with the same names. Only available with the "serde" feature enabled. The JSON should be
+    /// an object mapping prefab names to objects which in turn map component type names (as
+    /// registered with the given [`PrefabComponentTypes`]) to the components' values. For
+    /// example:
+    ///
+    /// ```json
+    /// {
+    ///     "goblin": { "Position": [0, 0], "Health": 20 },
+    ///     "orc": { "Position": [0, 0], "Health": 50 }
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the JSON from
+    /// * `types`: the component types that can appear in the prefabs
+    ///
+    /// returns: the number of prefabs loaded
+    pub fn load_json_bytes<T: Read>(
+        &mut self,
+        reader: &mut T,
+        types: &PrefabComponentTypes,
+    ) -> Result<usize, PrefabError> {
+        let json: Value = match serde_json::from_reader(reader) {
+            Ok(json) => json,
+            Err(error) => return Err(PrefabError::BadData(error.to_string())),
+        };
+        let prefabs = match json {
+            Value::Object(prefabs) => prefabs,
+            _ => {
+                return Err(PrefabError::BadData(String::from(
+                    "Prefabs are not a JSON object",
+                )))
+            }
+        };
+
+        // load everything first so that nothing is added if any of the prefabs are bad
+        let mut loaded = Vec::with_capacity(prefabs.len());
+        for (name, json) in prefabs.iter() {
+            loaded.push((name, types.load_prefab(json)?));
+        }
+        let count = loaded.len();
+        for (name, prefab) in loaded {
+            self.add(name, prefab);
+        }
+        Ok(count)
+    }
+
+    /// Loads prefabs from a JSON file and adds them to this collection. See
+    /// [`Prefabs::load_json_bytes`].
+    pub fn load_json_file(
+        &mut self,
+        path: &Path,
+        types: &PrefabComponentTypes,
+    ) -> Result<usize, PrefabError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        self.load_json_bytes(&mut reader, types)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+    struct Position(i32, i32);
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+    struct Health(u32);
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    struct Name(&'static str);
+
+    #[test]
+    pub fn spawning_prefabs() {
+        let mut em = Entities::new();
+        let goblin = Prefab::new()
+            .with(Position(0, 0))
+            .with(Health(20))
+            .with(Health(25));
+        assert_eq!(2, goblin.len());
+        assert_eq!(Some(&Health(25)), goblin.get::<Health>());
+        assert!(!goblin.has::<Name>());
+
+        let a = goblin.spawn(&mut em);
+        let b = goblin.spawn_with(
+            &mut em,
+            &Prefab::new().with(Position(5, 6)).with(Name("Boss")),
+        );
+        assert_ne!(a, b);
+        {
+            let positions = em.components::<Position>().unwrap();
+            let healths = em.components::<Health>().unwrap();
+            assert_eq!(Position(0, 0), *positions.get(&a).unwrap());
+            assert_eq!(Health(25), *healths.get(&a).unwrap());
+            assert_eq!(Position(5, 6), *positions.get(&b).unwrap());
+            assert_eq!(Health(25), *healths.get(&b).unwrap());
+        }
+        assert!(!em.has_component::<Name>(a));
+        assert!(em.has_component::<Name>(b));
+
+        // entities get their own copies of the components
+        em.components_mut::<Health>()
+            .unwrap()
+            .get_mut(&a)
+            .unwrap()
+            .0 = 1;
+        assert_eq!(Some(&Health(25)), goblin.get::<Health>());
+
+        let mut prefabs = Prefabs::new();
+        assert_none!(prefabs.add("goblin", goblin));
+        let c = assert_ok!(prefabs.spawn("goblin", &mut em));
+        assert!(em.has_component::<Position>(c));
+        assert_matches!(
+            prefabs.spawn("orc", &mut em),
+            Err(PrefabError::UnknownPrefab(..))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn loading_prefabs() {
+        let mut types = PrefabComponentTypes::new();
+        types.register::<Position>("Position");
+        types.register::<Health>("Health");
+
+        let mut prefabs = Prefabs::new();
+        let json = r#"{
+            "goblin": { "Position": [1, 2], "Health": 20 },
+            "marker": { "Position": [0, 0] }
+        }"#;
+        assert_eq!(
+            2,
+            assert_ok!(prefabs.load_json_bytes(&mut json.as_bytes(), &types))
+        );
+        let goblin = prefabs.get("goblin").unwrap();
+        assert_eq!(Some(&Position(1, 2)), goblin.get::<Position>());
+        assert_eq!(Some(&Health(20)), goblin.get::<Health>());
+        assert_eq!(1, prefabs.get("marker").unwrap().len());
+
+        let json = r#"{ "ghost": { "Position": [0, 0], "Invisible": true } }"#;
+        assert_matches!(
+            prefabs.load_json_bytes(&mut json.as_bytes(), &types),
+            Err(PrefabError::UnknownComponentType(..))
+        );
+        let json = r#"{ "ghost": { "Health": "lots" } }"#;
+        assert_matches!(
+            prefabs.load_json_bytes(&mut json.as_bytes(), &types),
+            Err(PrefabError::BadData(..))
+        );
+        assert!(!prefabs.contains("ghost"));
+    }
+}
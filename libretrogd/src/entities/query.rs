@@ -0,0 +1,324 @@
+use std::marker::PhantomData;
+
+use crate::entities::*;
+
+/// A single component type that can be fetched by a [`Query`]. Implemented for `&T` (read-only
+/// access to components of type `T`) and `&mut T` (mutable access to components of type `T`).
+pub trait QueryParam {
+    /// The borrowed component store that components are fetched from.
+    type Store<'e>;
+    /// What is fetched for each entity, either `&T` or `&mut T`.
+    type Item<'q>;
+
+    /// Borrows the component store for this component type, or returns `None` if there is no
+    /// component store for it.
+    fn borrow_store(entities: &Entities) -> Option<Self::Store<'_>>;
+
+    /// Returns the number of components in the given store.
+    fn len(store: &Self::Store<'_>) -> usize;
+
+    /// Returns the IDs of all of the entities that have a component in the given store.
+    fn entities(store: &Self::Store<'_>) -> Vec<EntityId>;
+
+    /// Fetches the component for the given entity from the given store.
+    ///
+    /// # Safety
+    ///
+    /// `store` must point to a valid, borrowed component store that outlives `'q`, and no other
+    /// reference to the same entity's component may be alive while the returned one is.
+    unsafe fn fetch<'q>(store: *mut Self::Store<'_>, entity: EntityId) -> Option<Self::Item<'q>>;
+}
+
+impl<T: Component> QueryParam for &T {
+    type Store<'e> = RefComponents<'e, T>;
+    type Item<'q> = &'q T;
+
+    #[inline]
+    fn borrow_store(entities: &Entities) -> Option<Self::Store<'_>> {
+        entities.components::<T>()
+    }
+
+    #[inline]
+    fn len(store: &Self::Store<'_>) -> usize {
+        store.len()
+    }
+
+    #[inline]
+    fn entities(store: &Self::Store<'_>) -> Vec<EntityId> {
+        store.keys().copied().collect()
+    }
+
+    #[inline]
+    unsafe fn fetch<'q>(store: *mut Self::Store<'_>, entity: EntityId) -> Option<Self::Item<'q>> {
+        // the components themselves are 'static, it is only the store's borrow that is not
+        let components: *const HashMap<EntityId, T> = &**store;
+        (*components).get(&entity)
+    }
+}
+
+impl<T: Component> QueryParam for &mut T {
+    type Store<'e> = RefMutComponents<'e, T>;
+    type Item<'q> = &'q mut T;
+
+    #[inline]
+    fn borrow_store(entities: &Entities) -> Option<Self::Store<'_>> {
+        entities.components_mut::<T>()
+    }
+
+    #[inline]
+    fn len(store: &Self::Store<'_>) -> usize {
+        store.len()
+    }
+
+    #[inline]
+    fn entities(store: &Self::Store<'_>) -> Vec<EntityId> {
+        store.keys().copied().collect()
+    }
+
+    #[inline]
+    unsafe fn fetch<'q>(store: *mut Self::Store<'_>, entity: EntityId) -> Option<Self::Item<'q>> {
+        let components: *mut HashMap<EntityId, T> = &mut **store;
+        (*components).get_mut(&entity)
+    }
+}
+
+/// A set of component types to be fetched together for each entity that has all of them, via
+/// [`Entities::query`]. Implemented for tuples of up to 6 [`QueryParam`]s, for example
+/// `(&Position, &mut Velocity)`.
+pub trait Query {
+    /// The borrowed component stores that components are fetched from.
+    type Stores<'e>;
+    /// What is fetched for each entity, e.g. `(&Position, &mut Velocity)`.
+    type Item<'q>;
+
+    /// Borrows the component stores for all of the component types, or returns `None` if any
+    /// one of them has no component store (in which case no entity could match anyway).
+    fn borrow_stores(entities: &Entities) -> Option<Self::Stores<'_>>;
+
+    /// Returns the IDs of the entities that could match, taken from the smallest of the stores.
+    fn candidates(stores: &Self::Stores<'_>) -> Vec<EntityId>;
+
+    /// Fetches all of the components for the given entity, or returns `None` if the entity does
+    /// not have all of them.
+    ///
+    /// # Safety
+    ///
+    /// `stores` must point to valid, borrowed component stores that outlive `'q`, and no other
+    /// references to the same entity's components may be alive while the returned ones are.
+    unsafe fn fetch<'q>(stores: *mut Self::Stores<'_>, entity: EntityId) -> Option<Self::Item<'q>>;
+}
+
+macro_rules! impl_query_for_tuple {
+    ($($param:ident $index:tt),+) => {
+        impl<$($param: QueryParam),+> Query for ($($param,)+) {
+            type Stores<'e> = ($($param::Store<'e>,)+);
+            type Item<'q> = ($($param::Item<'q>,)+);
+
+            fn borrow_stores(entities: &Entities) -> Option<Self::Stores<'_>> {
+                Some(($($param::borrow_store(entities)?,)+))
+            }
+
+            fn candidates(stores: &Self::Stores<'_>) -> Vec<EntityId> {
+                let lengths = [$($param::len(&stores.$index)),+];
+                let smallest = lengths
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, len)| **len)
+                    .map_or(0, |(index, _)| index);
+                $(
+                    if smallest == $index {
+                        return $param::entities(&stores.$index);
+                    }
+                )+
+                Vec::new()
+            }
+
+            #[inline]
+            unsafe fn fetch<'q>(
+                stores: *mut Self::Stores<'_>,
+                entity: EntityId,
+            ) -> Option<Self::Item<'q>> {
+                Some(($($param::fetch(std::ptr::addr_of_mut!((*stores).$index), entity)?,)+))
+            }
+        }
+    };
+}
+
+impl_query_for_tuple!(A 0);
+impl_query_for_tuple!(A 0, B 1);
+impl_query_for_tuple!(A 0, B 1, C 2);
+impl_query_for_tuple!(A 0, B 1, C 2, D 3);
+impl_query_for_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_query_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// The component stores borrowed by [`Entities::query`]. Use [`QueryResult::iter`] to iterate
+/// over the entities that have all of the queried components. The component stores remain
+/// borrowed until this is dropped.
+pub struct QueryResult<'e, Q: Query> {
+    stores: Option<Q::Stores<'e>>,
+    candidates: Vec<EntityId>,
+}
+
+impl<'e, Q: Query> std::fmt::Debug for QueryResult<'e, Q> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryResult")
+            .field("candidates.len()", &self.candidates.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'e, Q: Query> QueryResult<'e, Q> {
+    fn new(entities: &'e Entities) -> Self {
+        let stores = Q::borrow_stores(entities);
+        let candidates = stores.as_ref().map_or_else(Vec::new, Q::candidates);
+        QueryResult { stores, candidates }
+    }
+
+    /// Returns an iterator over the ID and components of every entity that has all of the queried
+    /// components. The order entities are returned in is undefined.
+    pub fn iter(&mut self) -> QueryIter<'_, 'e, Q> {
+        let stores = match &mut self.stores {
+            Some(stores) => stores as *mut Q::Stores<'e>,
+            None => std::ptr::null_mut(),
+        };
+        QueryIter {
+            stores,
+            candidates: self.candidates.iter(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'q, 'e, Q: Query> IntoIterator for &'q mut QueryResult<'e, Q> {
+    type Item = (EntityId, Q::Item<'q>);
+    type IntoIter = QueryIter<'q, 'e, Q>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator returned by [`QueryResult::iter`].
+pub struct QueryIter<'q, 'e, Q: Query> {
+    stores: *mut Q::Stores<'e>,
+    candidates: std::slice::Iter<'q, EntityId>,
+    marker: PhantomData<&'q mut Q::Stores<'e>>,
+}
+
+impl<'q, 'e, Q: Query> Iterator for QueryIter<'q, 'e, Q> {
+    type Item = (EntityId, Q::Item<'q>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stores.is_null() {
+            return None;
+        }
+        for &entity in self.candidates.by_ref() {
+            // each entity ID is only ever returned once, and the stores stay mutably borrowed by
+            // this iterator, so components returned are never aliased
+            if let Some(components) = unsafe { Q::fetch(self.stores, entity) } {
+                return Some((entity, components));
+            }
+        }
+        None
+    }
+}
+
+impl Entities {
+    /// Borrows the component stores for all of the component types given, so that the entities
+    /// that have all of those components can be iterated over together, instead of fetching each
+    /// component store separately and looking up each entity's other components by hand. Each
+    /// component type is given as either `&T` or `&mut T` depending on whether the components
+    /// need to be modified.
+    ///
+    /// Iteration is driven by whichever of the component stores has the fewest components, so
+    /// only entities that could possibly match are looked at. Like [`Entities::components_mut`],
+    /// this will panic if the same component type is borrowed mutably more than once at the same
+    /// time.
+    ///
+    /// ```
+    /// use libretrogd::entities::*;
+    ///
+    /// struct Position(i32, i32);
+    /// struct Velocity(i32, i32);
+    ///
+    /// let mut entities = Entities::new();
+    /// let id = entities.new_entity();
+    /// entities.add_component(id, Position(1, 2));
+    /// entities.add_component(id, Velocity(3, 4));
+    ///
+    /// for (_entity, (position, velocity)) in &mut entities.query::<(&mut Position, &Velocity)>() {
+    ///     position.0 += velocity.0;
+    ///     position.1 += velocity.1;
+    /// }
+    /// ```
+    pub fn query<Q: Query>(&self) -> QueryResult<'_, Q> {
+        QueryResult::new(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    struct Position(i32, i32);
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    struct Velocity(i32, i32);
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    struct Health(u32);
+
+    #[test]
+    pub fn querying_multiple_components() {
+        let mut em = Entities::new();
+        let a = em.new_entity();
+        em.add_component(a, Position(1, 2));
+        em.add_component(a, Velocity(1, 1));
+        let b = em.new_entity();
+        em.add_component(b, Position(-3, 0));
+        em.add_component(b, Velocity(2, 0));
+        em.add_component(b, Health(10));
+        let c = em.new_entity();
+        em.add_component(c, Position(5, 5));
+
+        for (_entity, (position, velocity)) in &mut em.query::<(&mut Position, &Velocity)>() {
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+        }
+        {
+            let positions = em.components::<Position>().unwrap();
+            assert_eq!(Position(2, 3), *positions.get(&a).unwrap());
+            assert_eq!(Position(-1, 0), *positions.get(&b).unwrap());
+            assert_eq!(Position(5, 5), *positions.get(&c).unwrap());
+        }
+
+        let mut query = em.query::<(&Position, &Velocity, &mut Health)>();
+        let mut matched = query.iter().collect::<Vec<_>>();
+        assert_eq!(1, matched.len());
+        let (entity, (position, velocity, health)) = &mut matched[0];
+        assert_eq!(b, *entity);
+        assert_eq!(Position(-1, 0), **position);
+        assert_eq!(Velocity(2, 0), **velocity);
+        health.0 -= 5;
+        drop(matched);
+        drop(query);
+        assert_eq!(
+            Health(5),
+            *em.components::<Health>().unwrap().get(&b).unwrap()
+        );
+
+        let mut single = em.query::<(&Position,)>();
+        assert_eq!(3, single.iter().count());
+    }
+
+    #[test]
+    pub fn querying_missing_components() {
+        let mut em = Entities::new();
+        let a = em.new_entity();
+        em.add_component(a, Position(1, 2));
+
+        assert_eq!(0, em.query::<(&Position, &Velocity)>().iter().count());
+        em.remove_component::<Position>(a);
+        assert_eq!(0, em.query::<(&mut Position,)>().iter().count());
+    }
+}
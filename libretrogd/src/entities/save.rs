@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::entities::*;
+
+#[derive(Error, Debug)]
+pub enum EntitySaveError {
+    #[error("There is no component type registered with the name \"{0}\"")]
+    UnknownComponentType(String),
+
+    #[error("Bad or unsupported saved entity data: {0}")]
+    BadData(String),
+}
+
+type SavedComponents = Vec<(EntityId, Value)>;
+type ComponentSaverFn = fn(&Entities) -> Result<SavedComponents, serde_json::Error>;
+type ComponentLoaderFn = fn(&mut Entities, &SavedComponents) -> Result<(), serde_json::Error>;
+
+fn save_components<T: Component + Serialize>(
+    entities: &Entities,
+) -> Result<SavedComponents, serde_json::Error> {
+    let mut saved = Vec::new();
+    if let Some(components) = entities.components::<T>() {
+        for (entity, component) in components.iter() {
+            saved.push((*entity, serde_json::to_value(component)?));
+        }
+    }
+    saved.sort_by_key(|(entity, _)| *entity);
+    Ok(saved)
+}
+
+fn load_components<T: Component + DeserializeOwned>(
+    entities: &mut Entities,
+    saved: &SavedComponents,
+) -> Result<(), serde_json::Error> {
+    for (entity, value) in saved.iter() {
+        let component = serde_json::from_value::<T>(value.clone())?;
+        entities.add_component(*entity, component);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ComponentSaveType {
+    save: ComponentSaverFn,
+    load: ComponentLoaderFn,
+}
+
+/// A snapshot of all of the entities in an [`Entities`] instance along with all of their
+/// components whose types were registered with [`SaveComponentTypes`]. This can be serialized
+/// with serde as part of a game's save data, but as the components are held as JSON values, it
+/// must be serialized using a self-describing format (such as JSON, or [`crate::utils::savefile`]).
+/// Only available with the "serde" feature enabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedEntities {
+    next_id: EntityId,
+    entities: Vec<EntityId>,
+    components: BTreeMap<String, SavedComponents>,
+}
+
+impl SavedEntities {
+    /// Returns the number of entities that were saved.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns true if no entities were saved.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// The component types that are saved and loaded along with entities, each registered with the
+/// name used to identify it in the saved data. Components of any other types are left out when
+/// saving. Only available with the "serde" feature enabled.
+///
+/// ```
+/// use libretrogd::entities::*;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Health(u32);
+///
+/// let mut types = SaveComponentTypes::new();
+/// types.register::<Health>("Health");
+///
+/// let mut entities = Entities::new();
+/// let id = entities.new_entity();
+/// entities.add_component(id, Health(10));
+///
+/// let saved = types.save(&entities).unwrap();
+/// let loaded = types.load(&saved).unwrap();
+/// assert_eq!(Some(&Health(10)), loaded.components::<Health>().unwrap().get(&id));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SaveComponentTypes {
+    types: HashMap<String, ComponentSaveType>,
+}
+
+impl SaveComponentTypes {
+    /// Creates and returns a new set of component types with nothing registered yet.
+    pub fn new() -> Self {
+        SaveComponentTypes {
+            types: HashMap::new(),
+        }
+    }
+
+    /// Registers the given component type so that it is saved and loaded using the given name.
+    /// The component type must be serializable and deserializable with serde.
+    pub fn register<T: Component + Serialize + DeserializeOwned>(&mut self, name: &str) {
+        self.types.insert(
+            String::from(name),
+            ComponentSaveType {
+                save: save_components::<T>,
+                load: load_components::<T>,
+            },
+        );
+    }
+
+    /// Returns true if a component type has been registered with the given name.
+    #[inline]
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    /// Saves all of the given entities along with all of their components of the registered
+    /// types. Entity IDs are kept as-is, so components referring to other entities by ID remain
+    /// valid once loaded again.
+    ///
+    /// # Arguments
+    ///
+    /// * `entities`: the entities to be saved. None of its component stores can be mutably
+    ///   borrowed when this is called.
+    pub fn save(&self, entities: &Entities) -> Result<SavedEntities, EntitySaveError> {
+        let mut ids: Vec<EntityId> = entities.entities.iter().copied().collect();
+        ids.sort_unstable();
+        let mut components = BTreeMap::new();
+        for (name, save_type) in self.types.iter() {
+            match (save_type.save)(entities) {
+                Ok(saved) if saved.is_empty() => {}
+                Ok(saved) => {
+                    components.insert(name.clone(), saved);
+                }
+                Err(error) => {
+                    return Err(EntitySaveError::BadData(format!(
+                        "Component \"{}\": {}",
+                        name, error
+                    )))
+                }
+            }
+        }
+        Ok(SavedEntities {
+            next_id: entities.next_id.get(),
+            entities: ids,
+            components,
+        })
+    }
+
+    /// Loads and returns a new [`Entities`] instance with all of the entities and components
+    /// that were previously saved by [`SaveComponentTypes::save`].
+    ///
+    /// # Arguments
+    ///
+    /// * `saved`: the saved entities to be loaded
+    pub fn load(&self, saved: &SavedEntities) -> Result<Entities, EntitySaveError> {
+        let mut entities = Entities::new();
+        entities.entities.extend(saved.entities.iter().copied());
+        entities.next_id.set(saved.next_id);
+        for (name, components) in saved.components.iter() {
+            let save_type = match self.types.get(name) {
+                Some(save_type) => save_type,
+                None => return Err(EntitySaveError::UnknownComponentType(name.clone())),
+            };
+            if let Some((entity, _)) = components
+                .iter()
+                .find(|(entity, _)| !entities.has_entity(*entity))
+            {
+                return Err(EntitySaveError::BadData(format!(
+                    "Component \"{}\" belongs to an entity that was not saved: {}",
+                    name, entity
+                )));
+            }
+            if let Err(error) = (save_type.load)(&mut entities, components) {
+                return Err(EntitySaveError::BadData(format!(
+                    "Component \"{}\": {}",
+                    name, error
+                )));
+            }
+        }
+        Ok(entities)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+    struct Position(i32, i32);
+    #[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+    struct Target(EntityId);
+    #[derive(Debug, Eq, PartialEq)]
+    struct Sprite(usize);
+
+    #[test]
+    pub fn saving_and_loading_entities() {
+        let mut types = SaveComponentTypes::new();
+        types.register::<Position>("Position");
+        types.register::<Target>("Target");
+
+        let mut em = Entities::new();
+        let a = em.new_entity();
+        em.add_component(a, Position(1, 2));
+        em.add_component(a, Sprite(3));
+        let removed = em.new_entity();
+        let b = em.new_entity();
+        em.add_component(b, Position(-4, 5));
+        em.add_component(b, Target(a));
+        em.remove_entity(removed);
+
+        let saved = assert_ok!(types.save(&em));
+        assert_eq!(2, saved.len());
+        let json = serde_json::to_string(&saved).unwrap();
+        let saved: SavedEntities = serde_json::from_str(&json).unwrap();
+
+        let loaded = assert_ok!(types.load(&saved));
+        assert!(loaded.has_entity(a));
+        assert!(loaded.has_entity(b));
+        assert!(!loaded.has_entity(removed));
+        let positions = loaded.components::<Position>().unwrap();
+        assert_eq!(Some(&Position(1, 2)), positions.get(&a));
+        assert_eq!(Some(&Position(-4, 5)), positions.get(&b));
+        assert_eq!(
+            Some(&Target(a)),
+            loaded.components::<Target>().unwrap().get(&b)
+        );
+        assert!(!loaded.has_component::<Sprite>(a));
+        drop(positions);
+
+        // new entities don't re-use ids from before the save
+        let mut loaded = loaded;
+        assert_gt!(loaded.new_entity(), b);
+
+        let mut types = SaveComponentTypes::new();
+        types.register::<Position>("Position");
+        assert_matches!(
+            types.load(&saved),
+            Err(EntitySaveError::UnknownComponentType(name)) if name == "Target"
+        );
+    }
+}
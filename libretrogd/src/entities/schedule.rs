@@ -0,0 +1,275 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SystemOrderError {
+    #[error("Component systems have circular ordering constraints: {}", .0.join(", "))]
+    CircularOrdering(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct SystemOrder {
+    label: Option<String>,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+/// Returned when adding a component system to [`crate::entities::ComponentSystems`], to
+/// optionally give the system a label and constrain when it runs relative to other labelled
+/// systems. Systems with no ordering constraints between them run in the order they were added.
+///
+/// ```
+/// use libretrogd::entities::*;
+///
+/// struct Context {
+///     entities: Entities,
+/// }
+///
+/// fn input(context: &mut Context) {}
+/// fn movement(context: &mut Context) {}
+/// fn collision(context: &mut Context) {}
+///
+/// let mut cs = ComponentSystems::<Context, Context>::new();
+/// cs.add_update_system(collision).after("movement");
+/// cs.add_update_system(movement).label("movement").after("input");
+/// cs.add_update_system(input).label("input");
+/// // runs input, then movement, then collision
+/// cs.update(&mut Context { entities: Entities::new() });
+/// ```
+#[derive(Debug)]
+pub struct SystemOrdering<'a> {
+    order: &'a mut SystemOrder,
+}
+
+impl<'a> SystemOrdering<'a> {
+    /// Gives the system the given label, which other systems can refer to in their own ordering
+    /// constraints. More than one system can share the same label, in which case constraints
+    /// referring to that label apply to all of them, so a label can also be used to name a group
+    /// of systems (e.g. "physics").
+    pub fn label(self, label: &str) -> Self {
+        self.order.label = Some(String::from(label));
+        self
+    }
+
+    /// Makes the system run before all systems with the given label. Labels that no system has
+    /// are ignored.
+    pub fn before(self, label: &str) -> Self {
+        self.order.before.push(String::from(label));
+        self
+    }
+
+    /// Makes the system run after all systems with the given label. Labels that no system has
+    /// are ignored.
+    pub fn after(self, label: &str) -> Self {
+        self.order.after.push(String::from(label));
+        self
+    }
+}
+
+struct ScheduledSystem<T> {
+    f: fn(&mut T),
+    order: SystemOrder,
+}
+
+/// An ordered list of component system functions, used for each of the update and render
+/// systems in [`crate::entities::ComponentSystems`]. The order the systems are run in is
+/// worked out from their ordering constraints the first time they are run after any change.
+pub(crate) struct SystemSchedule<T> {
+    systems: Vec<ScheduledSystem<T>>,
+    order: Option<Vec<usize>>,
+}
+
+impl<T> SystemSchedule<T> {
+    pub fn new() -> Self {
+        SystemSchedule {
+            systems: Vec::new(),
+            order: None,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn add(&mut self, f: fn(&mut T)) -> SystemOrdering<'_> {
+        self.order = None;
+        self.systems.push(ScheduledSystem {
+            f,
+            order: SystemOrder::default(),
+        });
+        SystemOrdering {
+            order: &mut self.systems.last_mut().unwrap().order,
+        }
+    }
+
+    pub fn remove(&mut self, label: &str) -> usize {
+        let before_len = self.systems.len();
+        self.systems
+            .retain(|system| system.order.label.as_deref() != Some(label));
+        self.order = None;
+        before_len - self.systems.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.systems.clear();
+        self.order = None;
+    }
+
+    /// Returns the indices of all of the systems with the given label.
+    fn labelled<'a>(&'a self, label: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.systems
+            .iter()
+            .enumerate()
+            .filter(move |(_, system)| system.order.label.as_deref() == Some(label))
+            .map(|(index, _)| index)
+    }
+
+    /// Returns the indices of the systems in the order they should be run in. Each time, the
+    /// earliest added system that is not waiting on any other system still left is picked, so
+    /// that systems with no constraints between them keep the order they were added in.
+    fn resolve(&self) -> Result<Vec<usize>, SystemOrderError> {
+        let len = self.systems.len();
+        let mut successors = vec![Vec::new(); len];
+        let mut num_predecessors = vec![0usize; len];
+        for (index, system) in self.systems.iter().enumerate() {
+            for other in system
+                .order
+                .after
+                .iter()
+                .flat_map(|label| self.labelled(label))
+            {
+                if other != index {
+                    successors[other].push(index);
+                    num_predecessors[index] += 1;
+                }
+            }
+            for other in system
+                .order
+                .before
+                .iter()
+                .flat_map(|label| self.labelled(label))
+            {
+                if other != index {
+                    successors[index].push(other);
+                    num_predecessors[other] += 1;
+                }
+            }
+        }
+
+        let mut resolved = Vec::with_capacity(len);
+        let mut done = vec![false; len];
+        while resolved.len() < len {
+            match (0..len).find(|&index| !done[index] && num_predecessors[index] == 0) {
+                Some(next) => {
+                    done[next] = true;
+                    resolved.push(next);
+                    for &successor in successors[next].iter() {
+                        num_predecessors[successor] -= 1;
+                    }
+                }
+                None => {
+                    let stuck = (0..len)
+                        .filter(|&index| !done[index])
+                        .map(|index| match &self.systems[index].order.label {
+                            Some(label) => label.clone(),
+                            None => format!("#{}", index),
+                        })
+                        .collect();
+                    return Err(SystemOrderError::CircularOrdering(stuck));
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    pub fn check_order(&mut self) -> Result<(), SystemOrderError> {
+        if self.order.is_none() {
+            self.order = Some(self.resolve()?);
+        }
+        Ok(())
+    }
+
+    pub fn run(&mut self, context: &mut T) {
+        if let Err(error) = self.check_order() {
+            panic!("{}", error);
+        }
+        if let Some(order) = &self.order {
+            for &index in order.iter() {
+                (self.systems[index].f)(context);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    fn system_a(log: &mut Vec<&'static str>) {
+        log.push("a");
+    }
+
+    fn system_b(log: &mut Vec<&'static str>) {
+        log.push("b");
+    }
+
+    fn system_c(log: &mut Vec<&'static str>) {
+        log.push("c");
+    }
+
+    fn system_d(log: &mut Vec<&'static str>) {
+        log.push("d");
+    }
+
+    fn run(schedule: &mut SystemSchedule<Vec<&'static str>>) -> Vec<&'static str> {
+        let mut log = Vec::new();
+        schedule.run(&mut log);
+        log
+    }
+
+    #[test]
+    pub fn ordering_systems() {
+        let mut schedule = SystemSchedule::new();
+        schedule.add(system_a);
+        schedule.add(system_b);
+        schedule.add(system_c);
+        assert_eq!(vec!["a", "b", "c"], run(&mut schedule));
+
+        schedule.clear();
+        schedule.add(system_c).label("c").after("b");
+        schedule.add(system_b).label("b").after("a");
+        schedule.add(system_d).before("a").after("nothing");
+        schedule.add(system_a).label("a");
+        assert_eq!(vec!["d", "a", "b", "c"], run(&mut schedule));
+
+        // labels can be shared by groups of systems
+        schedule.clear();
+        schedule.add(system_d).after("physics");
+        schedule.add(system_a).label("physics");
+        schedule.add(system_b).label("physics");
+        schedule.add(system_c).before("physics");
+        assert_eq!(vec!["c", "a", "b", "d"], run(&mut schedule));
+
+        assert_eq!(2, schedule.remove("physics"));
+        assert_eq!(vec!["d", "c"], run(&mut schedule));
+    }
+
+    #[test]
+    pub fn circular_system_ordering() {
+        let mut schedule = SystemSchedule::new();
+        schedule.add(system_a).label("a").after("c");
+        schedule.add(system_b).label("b").after("a");
+        schedule.add(system_c).label("c").after("b");
+        schedule.add(system_d);
+        assert_matches!(
+            schedule.check_order(),
+            Err(SystemOrderError::CircularOrdering(systems)) if systems == vec!["a", "b", "c"]
+        );
+
+        schedule.remove("a");
+        assert_ok!(schedule.check_order());
+        assert_eq!(vec!["b", "c", "d"], run(&mut schedule));
+    }
+}
@@ -0,0 +1,537 @@
+use crate::graphics::*;
+
+/// How an [AnimationSequence] continues after its last frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AnimationLoopMode {
+    /// Plays through once and then stays on the last frame.
+    Once,
+    /// Starts over from the first frame.
+    Loop,
+    /// Plays backwards to the first frame, then forwards again, and so on.
+    PingPong,
+}
+
+/// A single frame of an [AnimationSequence].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AnimationFrame {
+    /// The index of the frame's region in the [BitmapAtlas] the animation is drawn from.
+    pub index: usize,
+    /// How long the frame is shown for, in seconds. Frames with no duration are shown until the
+    /// animation is restarted or another one is played.
+    pub duration: f32,
+}
+
+/// A named sequence of frames from a [BitmapAtlas], such as a character's walk cycle, which is
+/// played back by an [AnimationPlayer].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationSequence {
+    pub name: String,
+    pub loop_mode: AnimationLoopMode,
+    pub frames: Vec<AnimationFrame>,
+    /// Named events which are raised when the frame with the given index (within this sequence,
+    /// not the atlas) is shown, for example to play footstep sounds in time with a walk cycle.
+    pub events: Vec<(usize, String)>,
+}
+
+impl AnimationSequence {
+    /// Creates a new sequence with no frames.
+    pub fn new(name: &str, loop_mode: AnimationLoopMode) -> AnimationSequence {
+        AnimationSequence {
+            name: String::from(name),
+            loop_mode,
+            frames: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Creates a new sequence showing each of the given atlas regions for the same amount of
+    /// time.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the sequence
+    /// * `loop_mode`: how the sequence continues after its last frame
+    /// * `indices`: the indices of each frame's region in the atlas, in order
+    /// * `frame_duration`: how long each frame is shown for, in seconds
+    pub fn with_frames(
+        name: &str,
+        loop_mode: AnimationLoopMode,
+        indices: &[usize],
+        frame_duration: f32,
+    ) -> AnimationSequence {
+        let mut sequence = AnimationSequence::new(name, loop_mode);
+        for &index in indices.iter() {
+            sequence.add_frame(index, frame_duration);
+        }
+        sequence
+    }
+
+    /// Creates a new sequence from one of an Aseprite file's tags, using the durations of each
+    /// of the tagged frames. Reversed tags have their frames reversed. Tags which repeat once
+    /// use [AnimationLoopMode::Once], but any other number of repeats is treated as looping
+    /// forever.
+    ///
+    /// # Arguments
+    ///
+    /// * `file`: the Aseprite file the tag is from
+    /// * `tag`: the tag to create the sequence from
+    /// * `first_index`: the index in the atlas of the region for the file's first frame. The
+    ///   regions for all of the file's frames are expected to follow it in order, as they are
+    ///   when each of [AsepriteFile::flattened_frames] is added to a [BitmapAtlasBuilder].
+    pub fn from_aseprite_tag(
+        file: &AsepriteFile,
+        tag: &AsepriteTag,
+        first_index: usize,
+    ) -> AnimationSequence {
+        let loop_mode = match tag.direction {
+            AsepriteLoopDirection::PingPong | AsepriteLoopDirection::PingPongReverse => {
+                AnimationLoopMode::PingPong
+            }
+            _ if tag.repeat == 1 => AnimationLoopMode::Once,
+            _ => AnimationLoopMode::Loop,
+        };
+        let mut sequence = AnimationSequence::new(&tag.name, loop_mode);
+        let last_frame = tag.to_frame.min(file.frames.len().saturating_sub(1));
+        for frame_index in tag.from_frame..=last_frame {
+            sequence.add_frame(
+                first_index + frame_index,
+                file.frames[frame_index].duration_ms as f32 / 1000.0,
+            );
+        }
+        if matches!(
+            tag.direction,
+            AsepriteLoopDirection::Reverse | AsepriteLoopDirection::PingPongReverse
+        ) {
+            sequence.frames.reverse();
+        }
+        sequence
+    }
+
+    /// Adds a frame to the end of the sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: the index of the frame's region in the atlas
+    /// * `duration`: how long the frame is shown for, in seconds
+    pub fn add_frame(&mut self, index: usize, duration: f32) {
+        self.frames.push(AnimationFrame { index, duration });
+    }
+
+    /// Adds an event which is raised each time the given frame (an index within this sequence)
+    /// is shown.
+    pub fn add_event(&mut self, frame: usize, name: &str) {
+        self.events.push((frame, String::from(name)));
+    }
+
+    /// Returns the total duration of all of the sequence's frames, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// Plays back [AnimationSequence]s, keeping track of which frame to draw. Any number of named
+/// sequences can be added to a player, and one of them is played at a time.
+///
+/// ```ignore
+/// player.play("walk");
+/// player.update(delta);
+/// for event in player.take_events() {
+///     // play sounds, spawn effects, etc
+/// }
+/// dest.blit_atlas(BlitMethod::Transparent(0), &atlas, player.current_frame(), x, y);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    /// How fast sequences are played, where 1.0 is normal speed.
+    pub speed: f32,
+    sequences: Vec<AnimationSequence>,
+    current: Option<usize>,
+    frame: usize,
+    forwards: bool,
+    accumulator: f32,
+    paused: bool,
+    finished: bool,
+    events: Vec<String>,
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        AnimationPlayer::new()
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new() -> AnimationPlayer {
+        AnimationPlayer {
+            speed: 1.0,
+            sequences: Vec::new(),
+            current: None,
+            frame: 0,
+            forwards: true,
+            accumulator: 0.0,
+            paused: false,
+            finished: false,
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds a sequence which can then be played by name, replacing any existing sequence with
+    /// the same name. If the replaced sequence is playing, it is restarted.
+    pub fn add_sequence(&mut self, sequence: AnimationSequence) {
+        match self
+            .sequences
+            .iter()
+            .position(|existing| existing.name == sequence.name)
+        {
+            Some(index) => {
+                self.sequences[index] = sequence;
+                if self.current == Some(index) {
+                    self.start(index);
+                }
+            }
+            None => self.sequences.push(sequence),
+        }
+    }
+
+    /// Adds a sequence for each of an Aseprite file's tags. See
+    /// [AnimationSequence::from_aseprite_tag].
+    pub fn add_aseprite_tags(&mut self, file: &AsepriteFile, first_index: usize) {
+        for tag in file.tags.iter() {
+            self.add_sequence(AnimationSequence::from_aseprite_tag(file, tag, first_index));
+        }
+    }
+
+    /// Returns the sequence with the given name, if there is one.
+    pub fn sequence(&self, name: &str) -> Option<&AnimationSequence> {
+        self.sequences.iter().find(|sequence| sequence.name == name)
+    }
+
+    /// Returns all of the sequences which have been added.
+    #[inline]
+    pub fn sequences(&self) -> &[AnimationSequence] {
+        &self.sequences
+    }
+
+    /// Plays the sequence with the given name from its first frame. If that sequence is already
+    /// playing and has not finished, it carries on uninterrupted, so this can be called every
+    /// frame with whichever sequence should be shown. Returns false if there is no sequence with
+    /// the given name.
+    pub fn play(&mut self, name: &str) -> bool {
+        match self
+            .sequences
+            .iter()
+            .position(|sequence| sequence.name == name)
+        {
+            Some(index) => {
+                if self.current != Some(index) || self.finished {
+                    self.start(index);
+                }
+                self.paused = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Plays the current sequence again from its first frame.
+    pub fn restart(&mut self) {
+        if let Some(index) = self.current {
+            self.start(index);
+        }
+    }
+
+    /// Stops playing, leaving no sequence current.
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.finished = false;
+    }
+
+    /// Pauses the current sequence on the frame it is showing.
+    #[inline]
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes the current sequence after it has been paused.
+    #[inline]
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns true if a sequence is playing, and it is not paused or finished.
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some() && !self.paused && !self.finished
+    }
+
+    /// Returns true if the current sequence uses [AnimationLoopMode::Once] and has reached the
+    /// end of its last frame.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the sequence being played, if any.
+    pub fn current_sequence(&self) -> Option<&AnimationSequence> {
+        self.current.map(|index| &self.sequences[index])
+    }
+
+    /// Returns the index of the frame being shown within the current sequence.
+    #[inline]
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    /// Returns the atlas index of the frame being shown, which can be passed straight to
+    /// [Bitmap::blit_atlas]. Returns 0 if no sequence is being played.
+    pub fn current_frame(&self) -> usize {
+        self.current_sequence()
+            .and_then(|sequence| sequence.frames.get(self.frame))
+            .map_or(0, |frame| frame.index)
+    }
+
+    /// Returns the names of all of the events raised since this was last called, in the order
+    /// they were raised.
+    pub fn take_events(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn start(&mut self, index: usize) {
+        self.current = Some(index);
+        self.frame = 0;
+        self.forwards = true;
+        self.accumulator = 0.0;
+        self.finished = false;
+        self.raise_events();
+    }
+
+    fn raise_events(&mut self) {
+        if let Some(index) = self.current {
+            let frame = self.frame;
+            self.events.extend(
+                self.sequences[index]
+                    .events
+                    .iter()
+                    .filter(|(event_frame, _)| *event_frame == frame)
+                    .map(|(_, name)| name.clone()),
+            );
+        }
+    }
+
+    /// Moves on to the next frame of the given sequence, returning false if it has finished.
+    fn advance(&mut self, sequence: usize) -> bool {
+        let num_frames = self.sequences[sequence].frames.len();
+        match self.sequences[sequence].loop_mode {
+            AnimationLoopMode::Once => {
+                if self.frame + 1 >= num_frames {
+                    return false;
+                }
+                self.frame += 1;
+            }
+            AnimationLoopMode::Loop => self.frame = (self.frame + 1) % num_frames,
+            AnimationLoopMode::PingPong => {
+                if num_frames > 1 {
+                    if self.forwards && self.frame + 1 >= num_frames {
+                        self.forwards = false;
+                    } else if !self.forwards && self.frame == 0 {
+                        self.forwards = true;
+                    }
+                    if self.forwards {
+                        self.frame += 1;
+                    } else {
+                        self.frame -= 1;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Advances the current sequence by the given amount of time, in seconds, raising the events
+    /// for any frames that are moved on to.
+    pub fn update(&mut self, delta: f32) {
+        let sequence = match self.current {
+            Some(index) if !self.paused && !self.finished => index,
+            _ => return,
+        };
+        if self.sequences[sequence].frames.is_empty() {
+            return;
+        }
+
+        self.accumulator += delta * self.speed;
+        loop {
+            let duration = self.sequences[sequence].frames[self.frame].duration;
+            if duration <= 0.0 || self.accumulator < duration {
+                break;
+            }
+            self.accumulator -= duration;
+            if !self.advance(sequence) {
+                self.finished = true;
+                self.accumulator = 0.0;
+                break;
+            }
+            self.raise_events();
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn frames_shown(player: &mut AnimationPlayer, steps: usize, delta: f32) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                player.update(delta);
+                player.current_frame()
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn loop_modes() {
+        let mut player = AnimationPlayer::new();
+        player.add_sequence(AnimationSequence::with_frames(
+            "once",
+            AnimationLoopMode::Once,
+            &[4, 5, 6],
+            0.1,
+        ));
+        player.add_sequence(AnimationSequence::with_frames(
+            "loop",
+            AnimationLoopMode::Loop,
+            &[4, 5, 6],
+            0.1,
+        ));
+        player.add_sequence(AnimationSequence::with_frames(
+            "pingpong",
+            AnimationLoopMode::PingPong,
+            &[4, 5, 6],
+            0.1,
+        ));
+        assert_eq!(0, player.current_frame());
+        assert!(!player.is_playing());
+
+        assert!(player.play("once"));
+        assert_eq!(4, player.current_frame());
+        assert_eq!(vec![5, 6, 6, 6], frames_shown(&mut player, 4, 0.1001));
+        assert!(player.is_finished());
+        assert!(!player.is_playing());
+
+        assert!(player.play("loop"));
+        assert_eq!(vec![5, 6, 4, 5], frames_shown(&mut player, 4, 0.1001));
+        assert!(!player.is_finished());
+
+        assert!(player.play("pingpong"));
+        assert_eq!(vec![5, 6, 5, 4, 5, 6], frames_shown(&mut player, 6, 0.1001));
+
+        // large time steps skip over frames
+        assert!(player.play("loop"));
+        player.update(0.25);
+        assert_eq!(6, player.current_frame());
+        assert_eq!(2, player.frame_index());
+
+        assert!(!player.play("run"));
+        assert_eq!("loop", player.current_sequence().unwrap().name);
+    }
+
+    #[test]
+    pub fn playing_pausing_and_speed() {
+        let mut player = AnimationPlayer::new();
+        let mut sequence = AnimationSequence::new("idle", AnimationLoopMode::Loop);
+        sequence.add_frame(0, 0.5);
+        sequence.add_frame(1, 1.0);
+        assert_eq!(1.5, sequence.duration());
+        player.add_sequence(sequence);
+        player.add_sequence(AnimationSequence::with_frames(
+            "walk",
+            AnimationLoopMode::Loop,
+            &[2, 3],
+            0.25,
+        ));
+
+        player.play("idle");
+        player.update(0.6);
+        assert_eq!(1, player.current_frame());
+        // playing the same sequence again continues it
+        player.play("idle");
+        assert_eq!(1, player.current_frame());
+        player.restart();
+        assert_eq!(0, player.current_frame());
+
+        player.pause();
+        player.update(10.0);
+        assert_eq!(0, player.current_frame());
+        assert!(!player.is_playing());
+        player.resume();
+        assert!(player.is_playing());
+
+        player.speed = 2.0;
+        player.update(0.3);
+        assert_eq!(1, player.current_frame());
+
+        player.play("walk");
+        assert_eq!(2, player.current_frame());
+        player.stop();
+        assert!(player.current_sequence().is_none());
+        assert_eq!(0, player.current_frame());
+    }
+
+    #[test]
+    pub fn raising_events() {
+        let mut player = AnimationPlayer::new();
+        let mut sequence =
+            AnimationSequence::with_frames("walk", AnimationLoopMode::Loop, &[0, 1, 2, 3], 0.1);
+        sequence.add_event(0, "start");
+        sequence.add_event(1, "left_foot");
+        sequence.add_event(3, "right_foot");
+        player.add_sequence(sequence);
+
+        player.play("walk");
+        assert_eq!(vec!["start"], player.take_events());
+        assert!(player.take_events().is_empty());
+
+        player.update(0.15);
+        assert_eq!(vec!["left_foot"], player.take_events());
+        player.update(0.1);
+        assert!(player.take_events().is_empty());
+        player.update(0.2);
+        assert_eq!(vec!["right_foot", "start"], player.take_events());
+    }
+
+    #[test]
+    pub fn importing_aseprite_tags() -> Result<(), AsepriteError> {
+        let file = AsepriteFile::load_from_file(Path::new("./test-assets/test.aseprite"))?;
+        let mut player = AnimationPlayer::new();
+        player.add_aseprite_tags(&file, 10);
+        assert_eq!(file.tags.len(), player.sequences().len());
+
+        let walk = player.sequence("walk").unwrap();
+        assert_eq!(AnimationLoopMode::PingPong, walk.loop_mode);
+        assert_eq!(
+            vec![
+                AnimationFrame {
+                    index: 10,
+                    duration: 0.1
+                },
+                AnimationFrame {
+                    index: 11,
+                    duration: 0.15
+                },
+            ],
+            walk.frames
+        );
+
+        let mut tag = file.tag("walk").unwrap().clone();
+        tag.direction = AsepriteLoopDirection::Reverse;
+        tag.repeat = 1;
+        let sequence = AnimationSequence::from_aseprite_tag(&file, &tag, 0);
+        assert_eq!(AnimationLoopMode::Once, sequence.loop_mode);
+        assert_eq!(1, sequence.frames[0].index);
+        assert_eq!(0, sequence.frames[1].index);
+
+        Ok(())
+    }
+}
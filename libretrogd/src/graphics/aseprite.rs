@@ -0,0 +1,564 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use flate2::read::ZlibDecoder;
+use thiserror::Error;
+
+use crate::graphics::*;
+
+const FILE_MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+
+const CHUNK_OLD_PALETTE_1: u16 = 0x0004;
+const CHUNK_OLD_PALETTE_2: u16 = 0x0011;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_FLAG_VISIBLE: u16 = 1;
+
+const CEL_TYPE_RAW: u16 = 0;
+const CEL_TYPE_LINKED: u16 = 1;
+const CEL_TYPE_COMPRESSED: u16 = 2;
+
+#[derive(Error, Debug)]
+pub enum AsepriteError {
+    #[error("Bad or unsupported Aseprite file: {0}")]
+    BadFile(String),
+
+    #[error("Aseprite I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AsepriteLayerType {
+    Normal,
+    Group,
+    Tilemap,
+}
+
+/// A single layer from an Aseprite file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsepriteLayer {
+    pub name: String,
+    pub layer_type: AsepriteLayerType,
+    /// Whether this layer itself is marked as visible. Note that a visible layer may still be
+    /// hidden if one of its parent group layers is not visible, see [AsepriteFile::is_layer_shown].
+    pub visible: bool,
+    /// How deeply nested this layer is within group layers. Zero for top-level layers.
+    pub child_level: u16,
+    pub opacity: u8,
+}
+
+/// The image data for a single layer in a single frame. Cels are positioned relative to the
+/// top-left of the sprite canvas and are typically smaller than it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsepriteCel {
+    pub layer_index: usize,
+    pub x: i32,
+    pub y: i32,
+    pub opacity: u8,
+    pub bitmap: Bitmap,
+}
+
+/// A single frame from an Aseprite file, containing the cels for each layer with content in
+/// this frame.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsepriteFrame {
+    pub duration_ms: u32,
+    pub cels: Vec<AsepriteCel>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AsepriteLoopDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+/// A named range of frames, typically used to define an animation sequence.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsepriteTag {
+    pub name: String,
+    /// The first frame index of this tag.
+    pub from_frame: usize,
+    /// The last frame index of this tag (inclusive).
+    pub to_frame: usize,
+    pub direction: AsepriteLoopDirection,
+    /// The number of times the sequence should repeat, where zero means infinitely.
+    pub repeat: u16,
+}
+
+/// The contents of an indexed color mode Aseprite (.ase / .aseprite) file.
+#[derive(Debug, Clone)]
+pub struct AsepriteFile {
+    pub width: u32,
+    pub height: u32,
+    /// The palette index used for transparent pixels.
+    pub transparent_color: u8,
+    pub palette: Palette,
+    pub layers: Vec<AsepriteLayer>,
+    pub frames: Vec<AsepriteFrame>,
+    pub tags: Vec<AsepriteTag>,
+}
+
+fn read_string<T: ReadBytesExt>(reader: &mut T) -> Result<String, AsepriteError> {
+    let length = reader.read_u16::<LittleEndian>()? as usize;
+    let mut bytes = vec![0u8; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn read_layer_chunk(mut data: &[u8]) -> Result<AsepriteLayer, AsepriteError> {
+    let flags = data.read_u16::<LittleEndian>()?;
+    let layer_type = match data.read_u16::<LittleEndian>()? {
+        0 => AsepriteLayerType::Normal,
+        1 => AsepriteLayerType::Group,
+        2 => AsepriteLayerType::Tilemap,
+        other => {
+            return Err(AsepriteError::BadFile(format!(
+                "Unknown layer type: {}",
+                other
+            )));
+        }
+    };
+    let child_level = data.read_u16::<LittleEndian>()?;
+    let _default_width = data.read_u16::<LittleEndian>()?;
+    let _default_height = data.read_u16::<LittleEndian>()?;
+    let _blend_mode = data.read_u16::<LittleEndian>()?;
+    let opacity = data.read_u8()?;
+    let _reserved: [u8; 3] = [data.read_u8()?, data.read_u8()?, data.read_u8()?];
+    let name = read_string(&mut data)?;
+    Ok(AsepriteLayer {
+        name,
+        layer_type,
+        visible: (flags & LAYER_FLAG_VISIBLE) != 0,
+        child_level,
+        opacity,
+    })
+}
+
+fn read_cel_bitmap(data: &mut &[u8], compressed: bool) -> Result<Bitmap, AsepriteError> {
+    let width = data.read_u16::<LittleEndian>()? as u32;
+    let height = data.read_u16::<LittleEndian>()? as u32;
+    let mut bitmap = match Bitmap::new(width, height) {
+        Ok(bitmap) => bitmap,
+        Err(_) => {
+            return Err(AsepriteError::BadFile(String::from(
+                "Invalid cel dimensions",
+            )))
+        }
+    };
+    if compressed {
+        ZlibDecoder::new(*data).read_exact(bitmap.pixels_mut())?;
+    } else {
+        data.read_exact(bitmap.pixels_mut())?;
+    }
+    Ok(bitmap)
+}
+
+fn read_palette_chunk(mut data: &[u8], palette: &mut Palette) -> Result<(), AsepriteError> {
+    let _size = data.read_u32::<LittleEndian>()?;
+    let first = data.read_u32::<LittleEndian>()?;
+    let last = data.read_u32::<LittleEndian>()?;
+    let mut reserved = [0u8; 8];
+    data.read_exact(&mut reserved)?;
+    for index in first..=last {
+        let flags = data.read_u16::<LittleEndian>()?;
+        let r = data.read_u8()?;
+        let g = data.read_u8()?;
+        let b = data.read_u8()?;
+        let _a = data.read_u8()?;
+        if (flags & 1) != 0 {
+            read_string(&mut data)?;
+        }
+        if index < 256 {
+            palette[index as u8] = to_rgb32(r, g, b);
+        }
+    }
+    Ok(())
+}
+
+fn read_old_palette_chunk(mut data: &[u8], palette: &mut Palette) -> Result<(), AsepriteError> {
+    let num_packets = data.read_u16::<LittleEndian>()?;
+    let mut index = 0usize;
+    for _ in 0..num_packets {
+        index += data.read_u8()? as usize;
+        let count = match data.read_u8()? {
+            0 => 256,
+            n => n as usize,
+        };
+        for _ in 0..count {
+            let r = data.read_u8()?;
+            let g = data.read_u8()?;
+            let b = data.read_u8()?;
+            if index < 256 {
+                palette[index as u8] = to_rgb32(r, g, b);
+            }
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn read_tags_chunk(mut data: &[u8]) -> Result<Vec<AsepriteTag>, AsepriteError> {
+    let num_tags = data.read_u16::<LittleEndian>()?;
+    let mut reserved = [0u8; 8];
+    data.read_exact(&mut reserved)?;
+    let mut tags = Vec::with_capacity(num_tags as usize);
+    for _ in 0..num_tags {
+        let from_frame = data.read_u16::<LittleEndian>()? as usize;
+        let to_frame = data.read_u16::<LittleEndian>()? as usize;
+        let direction = match data.read_u8()? {
+            1 => AsepriteLoopDirection::Reverse,
+            2 => AsepriteLoopDirection::PingPong,
+            3 => AsepriteLoopDirection::PingPongReverse,
+            _ => AsepriteLoopDirection::Forward,
+        };
+        let repeat = data.read_u16::<LittleEndian>()?;
+        // 6 reserved bytes, 3 bytes for the (deprecated) tag color and 1 extra byte
+        let mut skipped = [0u8; 10];
+        data.read_exact(&mut skipped)?;
+        let name = read_string(&mut data)?;
+        tags.push(AsepriteTag {
+            name,
+            from_frame,
+            to_frame,
+            direction,
+            repeat,
+        });
+    }
+    Ok(tags)
+}
+
+impl AsepriteFile {
+    /// Loads an Aseprite file. Only files using the indexed color mode are supported. Tilemap
+    /// layers are loaded, but their cels are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the Aseprite file from
+    ///
+    /// returns: `Result<AsepriteFile, AsepriteError>`
+    pub fn load_from_bytes<T: ReadBytesExt>(reader: &mut T) -> Result<AsepriteFile, AsepriteError> {
+        let mut header = [0u8; 128];
+        reader.read_exact(&mut header)?;
+        let mut header_reader = &header[..];
+        let _file_size = header_reader.read_u32::<LittleEndian>()?;
+        if header_reader.read_u16::<LittleEndian>()? != FILE_MAGIC {
+            return Err(AsepriteError::BadFile(String::from(
+                "Unexpected magic number, probably not an Aseprite file",
+            )));
+        }
+        let num_frames = header_reader.read_u16::<LittleEndian>()?;
+        let width = header_reader.read_u16::<LittleEndian>()? as u32;
+        let height = header_reader.read_u16::<LittleEndian>()? as u32;
+        let color_depth = header_reader.read_u16::<LittleEndian>()?;
+        if color_depth != 8 {
+            return Err(AsepriteError::BadFile(String::from(
+                "Only indexed color mode Aseprite files are supported",
+            )));
+        }
+        if width == 0 || height == 0 {
+            return Err(AsepriteError::BadFile(String::from(
+                "Invalid sprite dimensions",
+            )));
+        }
+        // skip over the flags, deprecated speed and two reserved fields
+        let mut skipped = [0u8; 14];
+        header_reader.read_exact(&mut skipped)?;
+        let transparent_color = header_reader.read_u8()?;
+
+        let mut file = AsepriteFile {
+            width,
+            height,
+            transparent_color,
+            palette: Palette::new_with_default(0, 0, 0),
+            layers: Vec::new(),
+            frames: Vec::with_capacity(num_frames as usize),
+            tags: Vec::new(),
+        };
+
+        for frame_index in 0..num_frames as usize {
+            let frame_size = reader.read_u32::<LittleEndian>()?;
+            if reader.read_u16::<LittleEndian>()? != FRAME_MAGIC {
+                return Err(AsepriteError::BadFile(String::from(
+                    "Unexpected frame magic number",
+                )));
+            }
+            let old_num_chunks = reader.read_u16::<LittleEndian>()?;
+            let duration_ms = reader.read_u16::<LittleEndian>()? as u32;
+            let _reserved = reader.read_u16::<LittleEndian>()?;
+            let num_chunks = match reader.read_u32::<LittleEndian>()? {
+                0 => old_num_chunks as u32,
+                n => n,
+            };
+            if frame_size < 16 {
+                return Err(AsepriteError::BadFile(String::from("Invalid frame size")));
+            }
+
+            let mut frame = AsepriteFrame {
+                duration_ms,
+                cels: Vec::new(),
+            };
+
+            for _ in 0..num_chunks {
+                let chunk_size = reader.read_u32::<LittleEndian>()?;
+                let chunk_type = reader.read_u16::<LittleEndian>()?;
+                if chunk_size < 6 {
+                    return Err(AsepriteError::BadFile(String::from("Invalid chunk size")));
+                }
+                let mut data = vec![0u8; chunk_size as usize - 6];
+                reader.read_exact(&mut data)?;
+
+                match chunk_type {
+                    CHUNK_OLD_PALETTE_1 | CHUNK_OLD_PALETTE_2 => {
+                        read_old_palette_chunk(&data, &mut file.palette)?
+                    }
+                    CHUNK_PALETTE => read_palette_chunk(&data, &mut file.palette)?,
+                    CHUNK_LAYER => file.layers.push(read_layer_chunk(&data)?),
+                    CHUNK_TAGS => file.tags = read_tags_chunk(&data)?,
+                    CHUNK_CEL => {
+                        if let Some(cel) = file.read_cel_chunk(&data, frame_index)? {
+                            frame.cels.push(cel);
+                        }
+                    }
+                    // skip over anything else
+                    _ => {}
+                }
+            }
+
+            file.frames.push(frame);
+        }
+
+        Ok(file)
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<AsepriteFile, AsepriteError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_from_bytes(&mut reader)
+    }
+
+    fn read_cel_chunk(
+        &self,
+        mut data: &[u8],
+        frame_index: usize,
+    ) -> Result<Option<AsepriteCel>, AsepriteError> {
+        let layer_index = data.read_u16::<LittleEndian>()? as usize;
+        let x = data.read_i16::<LittleEndian>()? as i32;
+        let y = data.read_i16::<LittleEndian>()? as i32;
+        let opacity = data.read_u8()?;
+        let cel_type = data.read_u16::<LittleEndian>()?;
+        // z-index and reserved bytes
+        let mut skipped = [0u8; 7];
+        data.read_exact(&mut skipped)?;
+
+        if layer_index >= self.layers.len() {
+            return Err(AsepriteError::BadFile(format!(
+                "Cel references unknown layer: {}",
+                layer_index
+            )));
+        }
+
+        let bitmap = match cel_type {
+            CEL_TYPE_RAW => read_cel_bitmap(&mut data, false)?,
+            CEL_TYPE_COMPRESSED => read_cel_bitmap(&mut data, true)?,
+            CEL_TYPE_LINKED => {
+                let linked_frame = data.read_u16::<LittleEndian>()? as usize;
+                if linked_frame >= frame_index {
+                    return Err(AsepriteError::BadFile(String::from(
+                        "Linked cel references a later frame",
+                    )));
+                }
+                // a linked cel that references a frame without a cel for this layer is simply
+                // treated as if this frame also has no cel for this layer
+                return Ok(self.frames[linked_frame]
+                    .cels
+                    .iter()
+                    .find(|cel| cel.layer_index == layer_index)
+                    .cloned());
+            }
+            // compressed tilemaps are not supported
+            _ => return Ok(None),
+        };
+
+        Ok(Some(AsepriteCel {
+            layer_index,
+            x,
+            y,
+            opacity,
+            bitmap,
+        }))
+    }
+
+    /// Returns true if the given layer should be shown, which requires that the layer and all
+    /// of the group layers it is nested within are visible.
+    pub fn is_layer_shown(&self, layer_index: usize) -> bool {
+        let layer = &self.layers[layer_index];
+        if !layer.visible {
+            return false;
+        }
+        // walk backwards to find each parent group layer in turn
+        let mut level = layer.child_level;
+        for parent in self.layers[0..layer_index].iter().rev() {
+            if level == 0 {
+                break;
+            }
+            if parent.child_level < level {
+                if !parent.visible {
+                    return false;
+                }
+                level = parent.child_level;
+            }
+        }
+        true
+    }
+
+    fn draw_cel(&self, dest: &mut Bitmap, cel: &AsepriteCel) {
+        dest.blit(
+            BlitMethod::Transparent(self.transparent_color),
+            &cel.bitmap,
+            cel.x,
+            cel.y,
+        );
+    }
+
+    /// Returns a bitmap containing only the given layer's content for the given frame. Areas of
+    /// the bitmap not covered by the layer are filled with the transparent color.
+    pub fn layer_frame(&self, layer_index: usize, frame_index: usize) -> Bitmap {
+        // the sprite has non-zero dimensions, so this cannot fail
+        let mut bitmap = Bitmap::new(self.width, self.height).unwrap();
+        bitmap.clear(self.transparent_color);
+        for cel in self.frames[frame_index].cels.iter() {
+            if cel.layer_index == layer_index {
+                self.draw_cel(&mut bitmap, cel);
+            }
+        }
+        bitmap
+    }
+
+    /// Returns a bitmap containing all of the shown layers for the given frame flattened into a
+    /// single image. Areas not covered by any layer are filled with the transparent color. Layer
+    /// opacity and blend modes are not applied as they do not apply to indexed color images.
+    pub fn flattened_frame(&self, frame_index: usize) -> Bitmap {
+        // the sprite has non-zero dimensions, so this cannot fail
+        let mut bitmap = Bitmap::new(self.width, self.height).unwrap();
+        bitmap.clear(self.transparent_color);
+        let mut cels: Vec<&AsepriteCel> = self.frames[frame_index]
+            .cels
+            .iter()
+            .filter(|cel| {
+                self.layers[cel.layer_index].layer_type == AsepriteLayerType::Normal
+                    && self.is_layer_shown(cel.layer_index)
+            })
+            .collect();
+        cels.sort_by_key(|cel| cel.layer_index);
+        for cel in cels {
+            self.draw_cel(&mut bitmap, cel);
+        }
+        bitmap
+    }
+
+    /// Returns flattened bitmaps for every frame. See [AsepriteFile::flattened_frame].
+    pub fn flattened_frames(&self) -> Vec<Bitmap> {
+        (0..self.frames.len())
+            .map(|frame_index| self.flattened_frame(frame_index))
+            .collect()
+    }
+
+    /// Returns the tag with the given name, if there is one.
+    pub fn tag(&self, name: &str) -> Option<&AsepriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+
+    use super::*;
+
+    #[test]
+    pub fn load_file() -> Result<(), AsepriteError> {
+        let file = AsepriteFile::load_from_file(Path::new("./test-assets/test.aseprite"))?;
+        assert_eq!(8, file.width);
+        assert_eq!(8, file.height);
+        assert_eq!(0, file.transparent_color);
+        assert_eq!(to_rgb32(255, 0, 0), file.palette[1]);
+        assert_eq!(to_rgb32(0, 255, 0), file.palette[2]);
+        assert_eq!(to_rgb32(0, 0, 255), file.palette[3]);
+
+        let names: Vec<&str> = file
+            .layers
+            .iter()
+            .map(|layer| layer.name.as_str())
+            .collect();
+        assert_eq!(vec!["background", "hidden", "group", "sprite"], names);
+        assert_eq!(AsepriteLayerType::Group, file.layers[2].layer_type);
+        assert!(file.is_layer_shown(0));
+        assert!(!file.is_layer_shown(1));
+        assert!(file.is_layer_shown(3));
+
+        assert_eq!(2, file.frames.len());
+        assert_eq!(100, file.frames[0].duration_ms);
+        assert_eq!(150, file.frames[1].duration_ms);
+        assert_eq!(3, file.frames[0].cels.len());
+        // the linked cel from the first frame
+        assert_eq!(2, file.frames[1].cels.len());
+        assert_eq!(file.frames[0].cels[0], file.frames[1].cels[0]);
+
+        assert_eq!(2, file.tags.len());
+        let walk = file.tag("walk").unwrap();
+        assert_eq!(0, walk.from_frame);
+        assert_eq!(1, walk.to_frame);
+        assert_eq!(AsepriteLoopDirection::PingPong, walk.direction);
+        assert!(file.tag("run").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn layers_and_flattening() -> Result<(), AsepriteError> {
+        let file = AsepriteFile::load_from_file(Path::new("./test-assets/test.aseprite"))?;
+
+        let frame = file.flattened_frame(0);
+        assert_eq!(Some(1), frame.get_pixel(0, 0));
+        assert_eq!(Some(2), frame.get_pixel(2, 3));
+        assert_eq!(Some(2), frame.get_pixel(4, 4));
+        // transparent pixel in the sprite layer's cel shows the background layer through
+        assert_eq!(Some(1), frame.get_pixel(3, 3));
+        assert_eq!(Some(1), frame.get_pixel(7, 7));
+
+        let frame = file.flattened_frame(1);
+        assert_eq!(Some(1), frame.get_pixel(2, 3));
+        assert_eq!(Some(3), frame.get_pixel(4, 4));
+        assert_eq!(Some(3), frame.get_pixel(5, 5));
+
+        let layer = file.layer_frame(3, 0);
+        assert_eq!(Some(0), layer.get_pixel(0, 0));
+        assert_eq!(Some(2), layer.get_pixel(2, 3));
+        assert_eq!(Some(0), layer.get_pixel(3, 3));
+
+        // the hidden layer is still available on its own
+        let layer = file.layer_frame(1, 0);
+        assert_eq!(Some(5), layer.get_pixel(1, 1));
+
+        assert_eq!(2, file.flattened_frames().len());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_bad_file() {
+        let mut data: &[u8] = &[0u8; 200];
+        assert_matches!(
+            AsepriteFile::load_from_bytes(&mut data),
+            Err(AsepriteError::BadFile(..))
+        );
+    }
+}
@@ -118,6 +118,20 @@ pub enum BlitMethod {
         transparent_color: u8,
         blend_map: Rc<BlendMap>,
     },
+    /// Same as [BlitMethod::Solid] except that the drawn pixels are shaded to the given light
+    /// level using the color map given.
+    SolidColorMapped {
+        color_map: Rc<ColorMap>,
+        level: usize,
+    },
+    /// Same as [BlitMethod::Transparent] except that the drawn pixels are shaded to the given
+    /// light level using the color map given. The transparent color check is not affected by the
+    /// shading and is always done against the original source pixels.
+    TransparentColorMapped {
+        transparent_color: u8,
+        color_map: Rc<ColorMap>,
+        level: usize,
+    },
 }
 
 /// Clips the region for a source bitmap to be used in a subsequent blit operation. The source
@@ -225,38 +239,38 @@ fn get_flipped_blit_properties(
         x_inc = 1;
         src_start_x = src_region.x;
         src_start_y = src_region.y;
-        src_next_row_inc = (src.width - src_region.width) as isize;
+        src_next_row_inc = (src.pitch - src_region.width) as isize;
     } else if horizontal_flip && !vertical_flip {
         x_inc = -1;
         src_start_x = src_region.right();
         src_start_y = src_region.y;
-        src_next_row_inc = (src.width + src_region.width) as isize;
+        src_next_row_inc = (src.pitch + src_region.width) as isize;
     } else if !horizontal_flip && vertical_flip {
         x_inc = 1;
         src_start_x = src_region.x;
         src_start_y = src_region.bottom();
-        src_next_row_inc = -((src.width + src_region.width) as isize);
+        src_next_row_inc = -((src.pitch + src_region.width) as isize);
     } else {
         x_inc = -1;
         src_start_x = src_region.right();
         src_start_y = src_region.bottom();
-        src_next_row_inc = -((src.width - src_region.width) as isize);
+        src_next_row_inc = -((src.pitch - src_region.width) as isize);
     }
 
     (x_inc, src_start_x, src_start_y, src_next_row_inc)
 }
 
 #[inline]
-unsafe fn per_pixel_blit(
-    dest: &mut Bitmap,
+unsafe fn per_pixel_blit<P: PixelBuffer>(
+    dest: &mut Bitmap<P>,
     src: &Bitmap,
     src_region: &Rect,
     dest_x: i32,
     dest_y: i32,
     pixel_fn: impl Fn(*const u8, *mut u8),
 ) {
-    let src_next_row_inc = (src.width - src_region.width) as usize;
-    let dest_next_row_inc = (dest.width - src_region.width) as usize;
+    let src_next_row_inc = (src.pitch - src_region.width) as usize;
+    let dest_next_row_inc = (dest.pitch - src_region.width) as usize;
     let mut src_pixels = src.pixels_at_ptr_unchecked(src_region.x, src_region.y);
     let mut dest_pixels = dest.pixels_at_mut_ptr_unchecked(dest_x, dest_y);
 
@@ -273,8 +287,8 @@ unsafe fn per_pixel_blit(
 }
 
 #[inline]
-unsafe fn per_pixel_flipped_blit(
-    dest: &mut Bitmap,
+unsafe fn per_pixel_flipped_blit<P: PixelBuffer>(
+    dest: &mut Bitmap<P>,
     src: &Bitmap,
     src_region: &Rect,
     dest_x: i32,
@@ -283,7 +297,7 @@ unsafe fn per_pixel_flipped_blit(
     vertical_flip: bool,
     pixel_fn: impl Fn(*const u8, *mut u8),
 ) {
-    let dest_next_row_inc = (dest.width - src_region.width) as usize;
+    let dest_next_row_inc = (dest.pitch - src_region.width) as usize;
     let (x_inc, src_start_x, src_start_y, src_next_row_inc) =
         get_flipped_blit_properties(src, src_region, horizontal_flip, vertical_flip);
 
@@ -303,8 +317,8 @@ unsafe fn per_pixel_flipped_blit(
 }
 
 #[inline]
-unsafe fn per_pixel_rotozoom_blit(
-    dest: &mut Bitmap,
+unsafe fn per_pixel_rotozoom_blit<P: PixelBuffer>(
+    dest: &mut Bitmap<P>,
     src: &Bitmap,
     src_region: &Rect,
     dest_x: i32,
@@ -312,7 +326,7 @@ unsafe fn per_pixel_rotozoom_blit(
     angle: f32,
     scale_x: f32,
     scale_y: f32,
-    pixel_fn: impl Fn(u8, &mut Bitmap, i32, i32),
+    pixel_fn: impl Fn(u8, &mut Bitmap<P>, i32, i32),
 ) {
     // TODO: this isn't the best rotozoom algorithm i guess. it has some floating point issues
     //       that result in missing pixels/rows still in a few places. also the double pixel
@@ -360,11 +374,17 @@ unsafe fn per_pixel_rotozoom_blit(
     }
 }
 
-impl Bitmap {
-    pub unsafe fn solid_blit(&mut self, src: &Bitmap, src_region: &Rect, dest_x: i32, dest_y: i32) {
+impl<P: PixelBuffer> Bitmap<P> {
+    pub unsafe fn solid_blit<S: PixelBuffer>(
+        &mut self,
+        src: &Bitmap<S>,
+        src_region: &Rect,
+        dest_x: i32,
+        dest_y: i32,
+    ) {
         let src_row_length = src_region.width as usize;
-        let src_pitch = src.width as usize;
-        let dest_pitch = self.width as usize;
+        let src_pitch = src.pitch as usize;
+        let dest_pitch = self.pitch as usize;
         let mut src_pixels = src.pixels_at_ptr_unchecked(src_region.x, src_region.y);
         let mut dest_pixels = self.pixels_at_mut_ptr_unchecked(dest_x, dest_y);
 
@@ -395,6 +415,23 @@ impl Bitmap {
         );
     }
 
+    pub unsafe fn solid_color_mapped_blit(
+        &mut self,
+        src: &Bitmap,
+        src_region: &Rect,
+        dest_x: i32,
+        dest_y: i32,
+        color_map: Rc<ColorMap>,
+        level: usize,
+    ) {
+        per_pixel_blit(
+            self, src, src_region, dest_x, dest_y,
+            |src_pixels, dest_pixels| {
+                *dest_pixels = color_map.shade(*src_pixels, level);
+            }
+        );
+    }
+
     pub unsafe fn solid_flipped_blit(
         &mut self,
         src: &Bitmap,
@@ -509,6 +546,26 @@ impl Bitmap {
         );
     }
 
+    pub unsafe fn transparent_color_mapped_blit(
+        &mut self,
+        src: &Bitmap,
+        src_region: &Rect,
+        dest_x: i32,
+        dest_y: i32,
+        transparent_color: u8,
+        color_map: Rc<ColorMap>,
+        level: usize,
+    ) {
+        per_pixel_blit(
+            self, src, src_region, dest_x, dest_y,
+            |src_pixels, dest_pixels| {
+                if *src_pixels != transparent_color {
+                    *dest_pixels = color_map.shade(*src_pixels, level);
+                }
+            }
+        );
+    }
+
     pub unsafe fn transparent_flipped_blit(
         &mut self,
         src: &Bitmap,
@@ -940,10 +997,209 @@ impl Bitmap {
             },
             RotoZoomTransparentBlended { angle, scale_x, scale_y, transparent_color, blend_map } => {
                 self.rotozoom_transparent_blended_blit(src, src_region, dest_x, dest_y, angle, scale_x, scale_y, transparent_color, blend_map)
+            },
+            SolidColorMapped { color_map, level } => {
+                self.solid_color_mapped_blit(src, src_region, dest_x, dest_y, color_map, level)
+            },
+            TransparentColorMapped { transparent_color, color_map, level } => {
+                self.transparent_color_mapped_blit(src, src_region, dest_x, dest_y, transparent_color, color_map, level)
+            }
+        }
+    }
+
+    /// Same as [Bitmap::blit_region] except that if both the source and this destination bitmap
+    /// have different associated palettes, the source pixels are first remapped to the closest
+    /// matching colors in this bitmap's palette. Note that this allocates a temporary copy of the
+    /// source region on each call when remapping is required, so for repeated blits it is better
+    /// to convert the source bitmap once up-front via [Bitmap::remap_to_palette]. Also note that
+    /// any colors specified in the blit method (e.g. the transparent color) are used as-is and
+    /// are not remapped.
+    pub fn blit_region_remapped(
+        &mut self,
+        method: BlitMethod,
+        src: &Bitmap,
+        src_region: &Rect,
+        dest_x: i32,
+        dest_y: i32,
+    ) {
+        if let (Some(src_palette), Some(dest_palette)) = (&src.palette, &self.palette) {
+            if src_palette != dest_palette {
+                let mut src_region = *src_region;
+                if !src_region.clamp_to(&src.clip_region) {
+                    return;
+                }
+                let remapped = match Bitmap::from(src, &src_region) {
+                    Ok(mut remapped) => {
+                        remapped.remap_colors(&src_palette.remap_table_to(dest_palette));
+                        remapped
+                    }
+                    Err(_) => return,
+                };
+                let region = remapped.full_bounds();
+                self.blit_region(method, &remapped, &region, dest_x, dest_y);
+                return;
+            }
+        }
+        self.blit_region(method, src, src_region, dest_x, dest_y);
+    }
+
+    /// Same as [Bitmap::blit] except that source pixels are remapped to this bitmap's palette
+    /// if necessary. See [Bitmap::blit_region_remapped] for more details.
+    #[inline]
+    pub fn blit_remapped(&mut self, method: BlitMethod, src: &Bitmap, x: i32, y: i32) {
+        let src_region = Rect::new(0, 0, src.width, src.height);
+        self.blit_region_remapped(method, src, &src_region, x, y);
+    }
+
+    /// Blits a region of the source bitmap using a companion "alpha map" bitmap to determine how
+    /// each source pixel is blended with the destination. The alpha map is the same size as the
+    /// source bitmap, and each of its pixels holds an alpha level for the source pixel at the same
+    /// coordinates:
+    ///
+    /// * 0: the source pixel is not drawn at all
+    /// * 1 to `blend_maps.len()`: the source pixel is blended using the blend map at index
+    ///   `level - 1`, typically ordered from least to most opaque
+    /// * any higher level: the source pixel is drawn as-is
+    ///
+    /// This allows for soft-edged sprites like glows and smoke.
+    ///
+    /// # Arguments
+    ///
+    /// * `src`: the source bitmap to blit
+    /// * `alpha_map`: the alpha levels for each of the source bitmap's pixels
+    /// * `src_region`: the region of the source (and alpha map) bitmap to blit
+    /// * `dest_x`: the x coordinate on this bitmap to blit to
+    /// * `dest_y`: the y coordinate on this bitmap to blit to
+    /// * `blend_maps`: the blend maps to use for each alpha level, starting from level 1
+    pub fn blit_region_alpha_mapped(
+        &mut self,
+        src: &Bitmap,
+        alpha_map: &Bitmap,
+        src_region: &Rect,
+        mut dest_x: i32,
+        mut dest_y: i32,
+        blend_maps: &[Rc<BlendMap>],
+    ) {
+        let mut src_region = *src_region;
+        if !src_region.clamp_to(&src.clip_region) || !src_region.clamp_to(&alpha_map.full_bounds()) {
+            return;
+        }
+        if !clip_blit(self.clip_region(), &mut src_region, &mut dest_x, &mut dest_y, false, false) {
+            return;
+        }
+
+        for y in 0..src_region.height as i32 {
+            for x in 0..src_region.width as i32 {
+                let src_x = src_region.x + x;
+                let src_y = src_region.y + y;
+                // safety: the source region has been clamped to the bounds of both the source and
+                // alpha map bitmaps, and clipped to this bitmap's clip region
+                unsafe {
+                    let level = alpha_map.get_pixel_unchecked(src_x, src_y) as usize;
+                    if level == 0 {
+                        continue;
+                    }
+                    let src_pixel = src.get_pixel_unchecked(src_x, src_y);
+                    let dest_pixel = self.pixels_at_mut_ptr_unchecked(dest_x + x, dest_y + y);
+                    *dest_pixel = match blend_maps.get(level - 1) {
+                        Some(blend_map) => blend_map.blend(src_pixel, *dest_pixel).unwrap_or(src_pixel),
+                        None => src_pixel,
+                    };
+                }
             }
         }
     }
 
+    /// Same as [Bitmap::blit_region_alpha_mapped], but blits the entire source bitmap.
+    #[inline]
+    pub fn blit_alpha_mapped(&mut self, src: &Bitmap, alpha_map: &Bitmap, x: i32, y: i32, blend_maps: &[Rc<BlendMap>]) {
+        let src_region = Rect::new(0, 0, src.width, src.height);
+        self.blit_region_alpha_mapped(src, alpha_map, &src_region, x, y, blend_maps);
+    }
+
+    /// Blits a region of a source bitmap to this bitmap, transformed by an arbitrary affine
+    /// transformation. The matrix maps coordinates relative to the top-left of the source region
+    /// to coordinates on this bitmap. Unlike the [BlitMethod::RotoZoom] family of blits, every
+    /// destination pixel covered by the transformed source is drawn exactly once, so there are no
+    /// gaps. Use [Matrix3x2::new_rotozoom] to get the same placement as those blits.
+    ///
+    /// # Arguments
+    ///
+    /// * `src`: the source bitmap to blit from
+    /// * `src_region`: the region on the source bitmap to blit
+    /// * `matrix`: the transformation to apply to the source region
+    /// * `transparent_color`: if set, source pixels of this color are skipped
+    pub fn transformed_blit_region(
+        &mut self,
+        src: &Bitmap,
+        src_region: &Rect,
+        matrix: &Matrix3x2,
+        transparent_color: Option<u8>,
+    ) {
+        let mut src_region = *src_region;
+        if !src_region.clamp_to(src.clip_region()) {
+            return;
+        }
+        // a transformation that can't be inverted collapses the source down to a line or a point
+        let inverse = match matrix.invert() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        // find the bounds of the transformed source region on the destination
+        let width = src_region.width as f32;
+        let height = src_region.height as f32;
+        let corners = [
+            matrix.transform_point(Vector2::new(0.0, 0.0)),
+            matrix.transform_point(Vector2::new(width, 0.0)),
+            matrix.transform_point(Vector2::new(0.0, height)),
+            matrix.transform_point(Vector2::new(width, height)),
+        ];
+        let left = corners.iter().fold(f32::MAX, |acc, corner| acc.min(corner.x)).floor() as i32;
+        let top = corners.iter().fold(f32::MAX, |acc, corner| acc.min(corner.y)).floor() as i32;
+        let right = corners.iter().fold(f32::MIN, |acc, corner| acc.max(corner.x)).ceil() as i32;
+        let bottom = corners.iter().fold(f32::MIN, |acc, corner| acc.max(corner.y)).ceil() as i32;
+        if right <= left || bottom <= top {
+            return;
+        }
+        let dest_region = Rect::new(left, top, (right - left) as u32, (bottom - top) as u32);
+        let dest_region = match dest_region.intersection(self.clip_region()) {
+            Some(dest_region) => dest_region,
+            None => return,
+        };
+
+        // walk each destination pixel, sampling the source pixel under its center. the inverse
+        // transform is linear, so stepping one pixel to the right is a constant source step
+        let step = inverse.transform_vector(Vector2::new(1.0, 0.0));
+        for y in dest_region.y..=dest_region.bottom() {
+            let mut src_pos = inverse.transform_point(Vector2::new(
+                dest_region.x as f32 + 0.5,
+                y as f32 + 0.5,
+            ));
+            for x in dest_region.x..=dest_region.right() {
+                if src_pos.x >= 0.0 && src_pos.y >= 0.0 && src_pos.x < width && src_pos.y < height {
+                    unsafe {
+                        let pixel = src.get_pixel_unchecked(
+                            src_region.x + src_pos.x as i32,
+                            src_region.y + src_pos.y as i32,
+                        );
+                        if transparent_color != Some(pixel) {
+                            *self.pixels_at_mut_ptr_unchecked(x, y) = pixel;
+                        }
+                    }
+                }
+                src_pos += step;
+            }
+        }
+    }
+
+    /// Same as [Bitmap::transformed_blit_region], but blits the entire source bitmap.
+    #[inline]
+    pub fn transformed_blit(&mut self, src: &Bitmap, matrix: &Matrix3x2, transparent_color: Option<u8>) {
+        let src_region = Rect::new(0, 0, src.width, src.height);
+        self.transformed_blit_region(src, &src_region, matrix, transparent_color);
+    }
+
     #[inline]
     pub fn blit(&mut self, method: BlitMethod, src: &Bitmap, x: i32, y: i32) {
         let src_region = Rect::new(0, 0, src.width, src.height);
@@ -957,6 +1213,15 @@ impl Bitmap {
         }
     }
 
+    /// Blits the region with the given name from the atlas. Does nothing if the atlas has no
+    /// region with that name.
+    #[inline]
+    pub fn blit_atlas_named(&mut self, method: BlitMethod, src: &BitmapAtlas, name: &str, x: i32, y: i32) {
+        if let Some(src_region) = src.get_named(name) {
+            self.blit_region(method, src.bitmap(), src_region, x, y);
+        }
+    }
+
     #[inline]
     pub unsafe fn blit_unchecked(&mut self, method: BlitMethod, src: &Bitmap, x: i32, y: i32) {
         let src_region = Rect::new(0, 0, src.width, src.height);
@@ -1253,4 +1518,73 @@ pub mod tests {
         assert_eq!(0, x);
         assert_eq!(10, y);
     }
+
+    #[test]
+    pub fn alpha_mapped_blits() {
+        let mut half = BlendMap::new(5, 5);
+        half.set_mapping(5, 1, 50).unwrap();
+        let mut mostly = BlendMap::new(5, 5);
+        mostly.set_mapping(5, 1, 75).unwrap();
+        let blend_maps = [Rc::new(half), Rc::new(mostly)];
+
+        let mut src = Bitmap::new(4, 1).unwrap();
+        src.clear(5);
+        let mut alpha_map = Bitmap::new(4, 1).unwrap();
+        alpha_map.pixels_mut().copy_from_slice(&[0, 1, 2, 3]);
+
+        let mut dest = Bitmap::new(6, 2).unwrap();
+        dest.clear(1);
+        dest.blit_alpha_mapped(&src, &alpha_map, 0, 0, &blend_maps);
+        assert_eq!(&[1, 50, 75, 5, 1, 1], &dest.pixels()[0..6]);
+
+        // clipped against the destination
+        dest.clear(1);
+        dest.blit_alpha_mapped(&src, &alpha_map, 3, 1, &blend_maps);
+        assert_eq!(&[1, 1, 1, 1, 50, 75], &dest.pixels()[6..12]);
+        dest.clear(1);
+        dest.blit_alpha_mapped(&src, &alpha_map, -2, 0, &blend_maps);
+        assert_eq!(&[75, 5, 1, 1, 1, 1], &dest.pixels()[0..6]);
+
+        // an alpha map smaller than the source limits what is drawn
+        let alpha_map = Bitmap::new(2, 1).unwrap();
+        dest.clear(1);
+        let mut alpha_map = alpha_map;
+        alpha_map.clear(3);
+        dest.blit_alpha_mapped(&src, &alpha_map, 0, 0, &blend_maps);
+        assert_eq!(&[5, 5, 1, 1, 1, 1], &dest.pixels()[0..6]);
+    }
+
+    #[test]
+    pub fn transformed_blits() {
+        let mut src = Bitmap::new(2, 2).unwrap();
+        src.pixels_mut().copy_from_slice(&[1, 2, 3, 0]);
+        let mut dest = Bitmap::new(4, 4).unwrap();
+
+        // identity transform is the same as a normal blit
+        dest.transformed_blit(&src, &Matrix3x2::new_translation(1.0, 1.0), None);
+        assert_eq!(&[0, 0, 0, 0, 0, 1, 2, 0, 0, 3, 0, 0, 0, 0, 0, 0], dest.pixels());
+
+        // scaling covers every destination pixel with no gaps, and transparent pixels are skipped
+        dest.clear(9);
+        dest.transformed_blit(&src, &Matrix3x2::new_scaling(2.0, 2.0), Some(0));
+        assert_eq!(&[1, 1, 2, 2, 1, 1, 2, 2, 3, 3, 9, 9, 3, 3, 9, 9], dest.pixels());
+
+        // rotate 90 degrees clockwise about the source's center
+        dest.clear(0);
+        let matrix = Matrix3x2::new_rotozoom(2, 2, 0, 0, RADIANS_90, 1.0, 1.0);
+        dest.transformed_blit(&src, &matrix, None);
+        assert_eq!(&[3, 1, 0, 0, 0, 2, 0, 0], &dest.pixels()[0..8]);
+
+        // clipped against the destination, and using a source region
+        dest.clear(0);
+        dest.transformed_blit_region(&src, &Rect::new(1, 0, 1, 2), &Matrix3x2::new_translation(3.0, -1.0), None);
+        assert_eq!(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], dest.pixels());
+        dest.transformed_blit_region(&src, &Rect::new(0, 0, 1, 2), &Matrix3x2::new_translation(3.0, -1.0), None);
+        assert_eq!(&[0, 0, 0, 3, 0, 0, 0, 0], &dest.pixels()[0..8]);
+
+        // transforms that cannot be inverted draw nothing
+        dest.clear(0);
+        dest.transformed_blit(&src, &Matrix3x2::new_scaling(0.0, 2.0), None);
+        assert!(dest.pixels().iter().all(|&pixel| pixel == 0));
+    }
 }
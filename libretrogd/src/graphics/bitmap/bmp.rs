@@ -0,0 +1,453 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use thiserror::Error;
+
+use crate::graphics::*;
+use crate::utils::bytes::ReadFixedLengthByteArray;
+use crate::NUM_COLORS;
+
+const FILE_HEADER_SIZE: u32 = 14;
+const INFO_HEADER_SIZE: u32 = 40;
+
+const COMPRESSION_RGB: u32 = 0;
+const COMPRESSION_RLE8: u32 = 1;
+
+// RLE8 compressed pixel data can skip over any number of pixels, so its size says little about
+// the size of the image. the width and height of all BMP images are capped at this instead
+const BMP_MAX_DIMENSION: u32 = 16384;
+
+#[derive(Error, Debug)]
+pub enum BmpError {
+    #[error("Bad or unsupported BMP file: {0}")]
+    BadFile(String),
+
+    #[error("BMP I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Copy, Clone)]
+struct BmpFileHeader {
+    magic: [u8; 2],
+    file_size: u32,
+    reserved1: u16,
+    reserved2: u16,
+    pixel_data_offset: u32,
+}
+
+impl BmpFileHeader {
+    pub fn read<T: ReadBytesExt>(reader: &mut T) -> Result<Self, BmpError> {
+        Ok(BmpFileHeader {
+            magic: reader.read_bytes()?,
+            file_size: reader.read_u32::<LittleEndian>()?,
+            reserved1: reader.read_u16::<LittleEndian>()?,
+            reserved2: reader.read_u16::<LittleEndian>()?,
+            pixel_data_offset: reader.read_u32::<LittleEndian>()?,
+        })
+    }
+
+    pub fn write<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), BmpError> {
+        writer.write_all(&self.magic)?;
+        writer.write_u32::<LittleEndian>(self.file_size)?;
+        writer.write_u16::<LittleEndian>(self.reserved1)?;
+        writer.write_u16::<LittleEndian>(self.reserved2)?;
+        writer.write_u32::<LittleEndian>(self.pixel_data_offset)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct BmpInfoHeader {
+    size: u32,
+    width: i32,
+    height: i32,
+    planes: u16,
+    bpp: u16,
+    compression: u32,
+    image_size: u32,
+    horizontal_resolution: i32,
+    vertical_resolution: i32,
+    colors_used: u32,
+    colors_important: u32,
+}
+
+impl BmpInfoHeader {
+    pub fn read<T: ReadBytesExt>(reader: &mut T) -> Result<Self, BmpError> {
+        Ok(BmpInfoHeader {
+            size: reader.read_u32::<LittleEndian>()?,
+            width: reader.read_i32::<LittleEndian>()?,
+            height: reader.read_i32::<LittleEndian>()?,
+            planes: reader.read_u16::<LittleEndian>()?,
+            bpp: reader.read_u16::<LittleEndian>()?,
+            compression: reader.read_u32::<LittleEndian>()?,
+            image_size: reader.read_u32::<LittleEndian>()?,
+            horizontal_resolution: reader.read_i32::<LittleEndian>()?,
+            vertical_resolution: reader.read_i32::<LittleEndian>()?,
+            colors_used: reader.read_u32::<LittleEndian>()?,
+            colors_important: reader.read_u32::<LittleEndian>()?,
+        })
+    }
+
+    pub fn write<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), BmpError> {
+        writer.write_u32::<LittleEndian>(self.size)?;
+        writer.write_i32::<LittleEndian>(self.width)?;
+        writer.write_i32::<LittleEndian>(self.height)?;
+        writer.write_u16::<LittleEndian>(self.planes)?;
+        writer.write_u16::<LittleEndian>(self.bpp)?;
+        writer.write_u32::<LittleEndian>(self.compression)?;
+        writer.write_u32::<LittleEndian>(self.image_size)?;
+        writer.write_i32::<LittleEndian>(self.horizontal_resolution)?;
+        writer.write_i32::<LittleEndian>(self.vertical_resolution)?;
+        writer.write_u32::<LittleEndian>(self.colors_used)?;
+        writer.write_u32::<LittleEndian>(self.colors_important)?;
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes in a single row of uncompressed pixel data, including the
+/// padding needed to align each row to a 4 byte boundary.
+fn get_row_stride(width: u32, bpp: u16) -> usize {
+    let row_bits = width as usize * bpp as usize;
+    row_bits.div_ceil(32) * 4
+}
+
+/// Decodes RLE8 compressed pixel data into the bitmap. Rows are stored bottom-up, so `y` starts
+/// at the bottom row of the bitmap and moves upwards.
+fn read_rle8_pixels<T: ReadBytesExt>(reader: &mut T, bmp: &mut Bitmap) -> Result<(), BmpError> {
+    let mut x = 0i32;
+    let mut y = bmp.bottom() as i32;
+
+    loop {
+        let count = reader.read_u8()?;
+        let value = reader.read_u8()?;
+        if count > 0 {
+            // encoded run of the same pixel repeated
+            for _ in 0..count {
+                bmp.set_pixel(x, y, value);
+                x += 1;
+            }
+        } else {
+            match value {
+                // end of line
+                0 => {
+                    x = 0;
+                    y -= 1;
+                }
+                // end of bitmap
+                1 => return Ok(()),
+                // delta, skip over some pixels
+                2 => {
+                    x += reader.read_u8()? as i32;
+                    y -= reader.read_u8()? as i32;
+                }
+                // absolute run of individual pixels, padded to a 16-bit boundary
+                _ => {
+                    for _ in 0..value {
+                        let pixel = reader.read_u8()?;
+                        bmp.set_pixel(x, y, pixel);
+                        x += 1;
+                    }
+                    if value % 2 != 0 {
+                        reader.read_u8()?;
+                    }
+                }
+            }
+        }
+        if y < 0 {
+            return Ok(());
+        }
+    }
+}
+
+impl Bitmap {
+    /// Loads a Windows BMP image. Only 4-bit and 8-bit indexed images are supported, either
+    /// uncompressed or (for 8-bit images only) RLE8 compressed. Both bottom-up and top-down row
+    /// orders are supported.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the BMP image from
+    ///
+    /// returns: `Result<(Bitmap, Palette), BmpError>`
+    pub fn load_bmp_bytes<T: ReadBytesExt + Seek>(
+        reader: &mut T,
+    ) -> Result<(Bitmap, Palette), BmpError> {
+        let file_header = BmpFileHeader::read(reader)?;
+        if file_header.magic != *b"BM" {
+            return Err(BmpError::BadFile(String::from(
+                "Unexpected magic value, probably not a BMP file",
+            )));
+        }
+
+        let info_header = BmpInfoHeader::read(reader)?;
+        if info_header.size < INFO_HEADER_SIZE {
+            return Err(BmpError::BadFile(String::from(
+                "Only BMP files with a BITMAPINFOHEADER or newer are supported",
+            )));
+        }
+        if info_header.bpp != 4 && info_header.bpp != 8 {
+            return Err(BmpError::BadFile(String::from(
+                "Only 4-bit and 8-bit indexed BMP files are supported",
+            )));
+        }
+        let is_rle8 = match info_header.compression {
+            COMPRESSION_RGB => false,
+            COMPRESSION_RLE8 if info_header.bpp == 8 => true,
+            _ => {
+                return Err(BmpError::BadFile(String::from(
+                    "Unsupported BMP compression method",
+                )))
+            }
+        };
+        if info_header.width <= 0
+            || info_header.height == 0
+            || info_header.width as u32 > BMP_MAX_DIMENSION
+            || info_header.height.unsigned_abs() > BMP_MAX_DIMENSION
+        {
+            return Err(BmpError::BadFile(String::from(
+                "Invalid BMP image dimensions",
+            )));
+        }
+        let is_top_down = info_header.height < 0;
+        if is_top_down && is_rle8 {
+            return Err(BmpError::BadFile(String::from(
+                "Compressed BMP files cannot be top-down",
+            )));
+        }
+
+        // the color table immediately follows the info header, which may be one of the newer
+        // (larger) header versions that we otherwise ignore the extra fields of
+        let max_colors = 1u32 << info_header.bpp;
+        let num_colors = match info_header.colors_used {
+            0 => max_colors,
+            n if n <= max_colors => n,
+            _ => {
+                return Err(BmpError::BadFile(String::from(
+                    "Invalid number of palette colors",
+                )))
+            }
+        };
+        reader.seek(SeekFrom::Start(
+            (FILE_HEADER_SIZE + info_header.size) as u64,
+        ))?;
+        let mut palette = Palette::new_with_default(0, 0, 0);
+        for i in 0..num_colors {
+            let [b, g, r, _]: [u8; 4] = reader.read_bytes()?;
+            palette[i as u8] = to_rgb32(r, g, b);
+        }
+
+        let width = info_header.width as u32;
+        let height = info_header.height.unsigned_abs();
+        let stream_length = reader.seek(SeekFrom::End(0))?;
+        let pixel_data_length = stream_length.saturating_sub(file_header.pixel_data_offset as u64);
+        if !is_rle8
+            && (get_row_stride(width, info_header.bpp) as u64 * height as u64) > pixel_data_length
+        {
+            return Err(BmpError::BadFile(String::from(
+                "Pixel data is smaller than the BMP image dimensions require",
+            )));
+        }
+        let mut bmp = Bitmap::new(width, height)
+            .map_err(|err| BmpError::BadFile(format!("Invalid BMP image dimensions: {}", err)))?;
+
+        reader.seek(SeekFrom::Start(file_header.pixel_data_offset as u64))?;
+        if is_rle8 {
+            read_rle8_pixels(reader, &mut bmp)?;
+        } else {
+            let mut row = vec![0u8; get_row_stride(width, info_header.bpp)];
+            for i in 0..height {
+                reader.read_exact(&mut row)?;
+                let y = if is_top_down { i } else { height - 1 - i };
+                let dest = bmp.pixels_at_mut(0, y as i32).unwrap();
+                if info_header.bpp == 8 {
+                    dest[0..width as usize].copy_from_slice(&row[0..width as usize]);
+                } else {
+                    for (x, pixel) in dest[0..width as usize].iter_mut().enumerate() {
+                        let byte = row[x / 2];
+                        *pixel = if x % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+                    }
+                }
+            }
+        }
+
+        Ok((bmp, palette))
+    }
+
+    pub fn load_bmp_file(path: &Path) -> Result<(Bitmap, Palette), BmpError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_bmp_bytes(&mut reader)
+    }
+
+    /// Writes this bitmap as an uncompressed 8-bit indexed (bottom-up) Windows BMP image using
+    /// the given palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the BMP image to
+    /// * `palette`: the palette to be written along with the bitmap's pixels
+    ///
+    /// returns: `Result<(), BmpError>`
+    pub fn to_bmp_bytes<T: WriteBytesExt>(
+        &self,
+        writer: &mut T,
+        palette: &Palette,
+    ) -> Result<(), BmpError> {
+        let row_stride = get_row_stride(self.width(), 8);
+        let image_size = (row_stride * self.height() as usize) as u32;
+        let pixel_data_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE + (NUM_COLORS as u32 * 4);
+
+        let file_header = BmpFileHeader {
+            magic: *b"BM",
+            file_size: pixel_data_offset + image_size,
+            reserved1: 0,
+            reserved2: 0,
+            pixel_data_offset,
+        };
+        file_header.write(writer)?;
+
+        let info_header = BmpInfoHeader {
+            size: INFO_HEADER_SIZE,
+            width: self.width() as i32,
+            height: self.height() as i32,
+            planes: 1,
+            bpp: 8,
+            compression: COMPRESSION_RGB,
+            image_size,
+            horizontal_resolution: 2835,
+            vertical_resolution: 2835,
+            colors_used: NUM_COLORS as u32,
+            colors_important: 0,
+        };
+        info_header.write(writer)?;
+
+        for i in 0..=255 {
+            let (r, g, b) = from_rgb32(palette[i]);
+            writer.write_all(&[b, g, r, 0])?;
+        }
+
+        let padding = [0u8; 3];
+        let padding = &padding[0..(row_stride - self.width() as usize)];
+        for y in (0..self.height() as i32).rev() {
+            let row = self.pixels_at(0, y).unwrap();
+            writer.write_all(&row[0..self.width() as usize])?;
+            writer.write_all(padding)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bmp_file(&self, path: &Path, palette: &Palette) -> Result<(), BmpError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_bmp_bytes(&mut writer, palette)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::math::*;
+
+    pub static TEST_BMP_PIXELS_RAW: &[u8] =
+        include_bytes!("../../../test-assets/test_bmp_pixels_raw.bin");
+
+    fn dp2_palette() -> Palette {
+        Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal).unwrap()
+    }
+
+    #[test]
+    pub fn load_and_save() -> Result<(), BmpError> {
+        let dp2_palette = dp2_palette();
+        let tmp_dir = TempDir::new()?;
+
+        let (bmp, palette) = Bitmap::load_bmp_file(Path::new("./test-assets/test.bmp"))?;
+        assert_eq!(16, bmp.width());
+        assert_eq!(16, bmp.height());
+        assert_eq!(bmp.pixels(), TEST_BMP_PIXELS_RAW);
+        assert_eq!(palette, dp2_palette);
+
+        let save_path = tmp_dir.path().join("test_save.bmp");
+        bmp.to_bmp_file(&save_path, &palette)?;
+        let (reloaded_bmp, reloaded_palette) = Bitmap::load_bmp_file(&save_path)?;
+        assert_eq!(16, reloaded_bmp.width());
+        assert_eq!(16, reloaded_bmp.height());
+        assert_eq!(reloaded_bmp.pixels(), TEST_BMP_PIXELS_RAW);
+        assert_eq!(reloaded_palette, dp2_palette);
+
+        // odd width, to exercise row padding
+        let bmp = Bitmap::from(&bmp, &Rect::new(0, 0, 7, 5)).unwrap();
+        bmp.to_bmp_file(&save_path, &palette)?;
+        let (reloaded_bmp, _) = Bitmap::load_bmp_file(&save_path)?;
+        assert_eq!(bmp.pixels(), reloaded_bmp.pixels());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_rle8() -> Result<(), BmpError> {
+        let (bmp, palette) = Bitmap::load_bmp_file(Path::new("./test-assets/test_rle8.bmp"))?;
+        assert_eq!(16, bmp.width());
+        assert_eq!(16, bmp.height());
+        assert_eq!(bmp.pixels(), TEST_BMP_PIXELS_RAW);
+        assert_eq!(palette, dp2_palette());
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_4bit_top_down() -> Result<(), BmpError> {
+        let dp2_palette = dp2_palette();
+        let (bmp, palette) = Bitmap::load_bmp_file(Path::new("./test-assets/test_4bit.bmp"))?;
+        assert_eq!(7, bmp.width());
+        assert_eq!(3, bmp.height());
+        for y in 0..3 {
+            for x in 0..7 {
+                assert_eq!(Some(((x + y * 7) % 16) as u8), bmp.get_pixel(x, y));
+            }
+        }
+        for i in 0..16 {
+            assert_eq!(dp2_palette[i], palette[i]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_bad_files() {
+        let mut data = std::io::Cursor::new(b"definitely not a bmp file");
+        assert_matches!(
+            Bitmap::load_bmp_bytes(&mut data),
+            Err(BmpError::BadFile(..))
+        );
+    }
+
+    #[test]
+    pub fn load_files_with_unreasonable_dimensions() -> Result<(), BmpError> {
+        let mut data = Vec::new();
+        Bitmap::new(8, 8)
+            .unwrap()
+            .to_bmp_bytes(&mut data, &Palette::new())?;
+
+        // the width and height fields of the info header, which follows the file header
+        for (width, height) in [
+            (8i32, 9i32),
+            (9, 8),
+            (BMP_MAX_DIMENSION as i32 + 1, 1),
+            (1, -(BMP_MAX_DIMENSION as i32) - 1),
+            (i32::MAX, i32::MAX),
+        ] {
+            data[18..22].copy_from_slice(&width.to_le_bytes());
+            data[22..26].copy_from_slice(&height.to_le_bytes());
+            assert_matches!(
+                Bitmap::load_bmp_bytes(&mut std::io::Cursor::new(&data)),
+                Err(BmpError::BadFile(..))
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use crate::graphics::*;
+use crate::math::*;
+
+impl<P: PixelBuffer> Bitmap<P> {
+    /// Tests whether any non-transparent pixels of this bitmap overlap with any non-transparent
+    /// pixels of another bitmap, when both bitmaps are positioned at the given coordinates. This
+    /// is intended for pixel-perfect collision detection between sprites. Clipping regions are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: the x coordinate of this bitmap's top-left corner
+    /// * `y`: the y coordinate of this bitmap's top-left corner
+    /// * `other`: the other bitmap to test against
+    /// * `other_x`: the x coordinate of the other bitmap's top-left corner
+    /// * `other_y`: the y coordinate of the other bitmap's top-left corner
+    /// * `transparent_color`: the color of the pixels in both bitmaps to be treated as transparent
+    ///
+    /// returns: true if at least one pair of non-transparent pixels overlap
+    pub fn collides_with(
+        &self,
+        x: i32,
+        y: i32,
+        other: &Bitmap,
+        other_x: i32,
+        other_y: i32,
+        transparent_color: u8,
+    ) -> bool {
+        let this_bounds = Rect::new(x, y, self.width, self.height);
+        let other_bounds = Rect::new(other_x, other_y, other.width, other.height);
+        let mut overlap = this_bounds;
+        if !overlap.clamp_to(&other_bounds) {
+            return false;
+        }
+
+        let row_length = overlap.width as usize;
+        unsafe {
+            // safety: the overlapping region was clamped to lie within both bitmaps
+            let mut this_pixels = self.pixels_at_ptr_unchecked(overlap.x - x, overlap.y - y);
+            let mut other_pixels =
+                other.pixels_at_ptr_unchecked(overlap.x - other_x, overlap.y - other_y);
+            for _ in 0..overlap.height {
+                for i in 0..row_length {
+                    if *this_pixels.add(i) != transparent_color
+                        && *other_pixels.add(i) != transparent_color
+                    {
+                        return true;
+                    }
+                }
+                this_pixels = this_pixels.add(self.pitch as usize);
+                other_pixels = other_pixels.add(other.pitch as usize);
+            }
+        }
+
+        false
+    }
+
+    /// Tests whether any non-transparent pixel of this bitmap, positioned at the given
+    /// coordinates, lies at the given point. Clipping regions are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: the x coordinate of this bitmap's top-left corner
+    /// * `y`: the y coordinate of this bitmap's top-left corner
+    /// * `point_x`: the x coordinate of the point to test
+    /// * `point_y`: the y coordinate of the point to test
+    /// * `transparent_color`: the color of the pixels to be treated as transparent
+    pub fn collides_with_point(
+        &self,
+        x: i32,
+        y: i32,
+        point_x: i32,
+        point_y: i32,
+        transparent_color: u8,
+    ) -> bool {
+        let local_x = point_x - x;
+        let local_y = point_y - y;
+        if self.full_bounds().contains_point(local_x, local_y) {
+            unsafe { self.get_pixel_unchecked(local_x, local_y) != transparent_color }
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static DIAMOND_PIXELS: &[u8] = &[
+        0, 0, 1, 0, 0,
+        0, 1, 1, 1, 0,
+        1, 1, 1, 1, 1,
+        0, 1, 1, 1, 0,
+        0, 0, 1, 0, 0,
+    ];
+
+    fn make_diamond() -> Bitmap {
+        let mut bmp = Bitmap::new(5, 5).unwrap();
+        bmp.pixels_mut().copy_from_slice(DIAMOND_PIXELS);
+        bmp
+    }
+
+    #[test]
+    pub fn bitmap_collisions() {
+        let a = make_diamond();
+        let b = make_diamond();
+
+        assert!(a.collides_with(0, 0, &b, 0, 0, 0));
+        assert!(a.collides_with(10, 10, &b, 14, 10, 0));
+        assert!(!a.collides_with(10, 10, &b, 15, 10, 0));
+        // bounding boxes overlap at the corners, but only transparent pixels are touching
+        assert!(!a.collides_with(10, 10, &b, 13, 13, 0));
+        assert!(a.collides_with(10, 10, &b, 12, 12, 0));
+        assert!(a.collides_with(-2, -3, &b, -1, -1, 0));
+        // different transparent color, so every pixel is solid
+        assert!(a.collides_with(10, 10, &b, 14, 14, 2));
+    }
+
+    #[test]
+    pub fn point_collisions() {
+        let bmp = make_diamond();
+        assert!(bmp.collides_with_point(10, 10, 12, 12, 0));
+        assert!(!bmp.collides_with_point(10, 10, 10, 10, 0));
+        assert!(!bmp.collides_with_point(10, 10, 9, 12, 0));
+        assert!(bmp.collides_with_point(10, 10, 10, 12, 0));
+        assert!(!bmp.collides_with_point(10, 10, 15, 12, 0));
+    }
+}
@@ -0,0 +1,175 @@
+use std::cmp::max;
+
+use crate::graphics::*;
+use crate::math::*;
+
+/// Describes the differences found between two bitmaps via [Bitmap::diff_report].
+#[derive(Debug, Clone)]
+pub struct BitmapDiff {
+    /// The smallest region containing all of the differing pixels.
+    pub bounds: Rect,
+    /// The total number of differing pixels.
+    pub count: usize,
+    /// A bitmap sized to cover both of the compared bitmaps where each differing pixel is set to
+    /// 1 and all other pixels are set to 0.
+    pub mask: Bitmap,
+}
+
+impl<P: PixelBuffer> Bitmap<P> {
+    /// Calls the given function for every pixel location covering the combined area of this
+    /// bitmap and another, passing along the coordinates and whether the two bitmaps differ at
+    /// that location. Pixels that only exist in one of the two bitmaps are considered different.
+    fn for_each_diff_pixel(&self, other: &Bitmap, mut f: impl FnMut(i32, i32, bool)) {
+        let width = max(self.width, other.width) as i32;
+        let height = max(self.height, other.height) as i32;
+        let self_bounds = self.full_bounds();
+        let other_bounds = other.full_bounds();
+        for y in 0..height {
+            for x in 0..width {
+                let a = if self_bounds.contains_point(x, y) {
+                    Some(unsafe { self.get_pixel_unchecked(x, y) })
+                } else {
+                    None
+                };
+                let b = if other_bounds.contains_point(x, y) {
+                    Some(unsafe { other.get_pixel_unchecked(x, y) })
+                } else {
+                    None
+                };
+                f(x, y, a != b);
+            }
+        }
+    }
+
+    /// Compares this bitmap's pixels against another bitmap, ignoring clipping regions. If the
+    /// bitmaps are of different sizes, pixels that only exist in one of the two bitmaps are
+    /// considered different.
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: the bitmap to compare against
+    ///
+    /// returns: the smallest region containing all of the differing pixels, or None if both
+    /// bitmaps have identical pixels
+    pub fn diff(&self, other: &Bitmap) -> Option<Rect> {
+        let mut left = i32::MAX;
+        let mut top = i32::MAX;
+        let mut right = i32::MIN;
+        let mut bottom = i32::MIN;
+        self.for_each_diff_pixel(other, |x, y, different| {
+            if different {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x);
+                bottom = bottom.max(y);
+            }
+        });
+        if left == i32::MAX {
+            None
+        } else {
+            Some(Rect::from_coords(left, top, right, bottom))
+        }
+    }
+
+    /// Same as [Bitmap::diff] except that a more detailed report about the differing pixels is
+    /// returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: the bitmap to compare against
+    ///
+    /// returns: the differences found, or None if both bitmaps have identical pixels
+    pub fn diff_report(&self, other: &Bitmap) -> Option<BitmapDiff> {
+        let bounds = self.diff(other)?;
+        // both bitmaps have non-zero dimensions, so this cannot fail
+        let mut mask = Bitmap::new(max(self.width, other.width), max(self.height, other.height))
+            .unwrap();
+        let mut count = 0;
+        self.for_each_diff_pixel(other, |x, y, different| {
+            if different {
+                count += 1;
+                unsafe { mask.set_pixel_unchecked(x, y, 1) };
+            }
+        });
+        Some(BitmapDiff { bounds, count, mask })
+    }
+
+    /// Creates a bitmap useful for visually inspecting the differences between this bitmap and
+    /// another. The returned bitmap contains this bitmap's pixels with all of the pixels that
+    /// differ from the other bitmap drawn using the highlight color given instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: the bitmap to compare against
+    /// * `highlight_color`: the color to draw differing pixels with
+    /// * `background_color`: the color to draw pixels with which lie outside of this bitmap but
+    ///   within the other (only applicable if the bitmaps are of different sizes)
+    pub fn diff_visual(&self, other: &Bitmap, highlight_color: u8, background_color: u8) -> Bitmap {
+        // both bitmaps have non-zero dimensions, so this cannot fail
+        let mut result = Bitmap::new(max(self.width, other.width), max(self.height, other.height))
+            .unwrap();
+        result.clear(background_color);
+        unsafe { result.solid_blit(self, &self.full_bounds(), 0, 0) };
+        self.for_each_diff_pixel(other, |x, y, different| {
+            if different {
+                unsafe { result.set_pixel_unchecked(x, y, highlight_color) };
+            }
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn identical_bitmaps() {
+        let mut a = Bitmap::new(8, 8).unwrap();
+        a.clear(3);
+        let b = a.clone();
+        assert_eq!(None, a.diff(&b));
+        assert!(a.diff_report(&b).is_none());
+        assert_eq!(a, a.diff_visual(&b, 15, 0));
+    }
+
+    #[test]
+    pub fn differing_bitmaps() {
+        let a = Bitmap::new(8, 8).unwrap();
+        let mut b = a.clone();
+        b.set_pixel(2, 3, 1);
+        b.set_pixel(5, 1, 1);
+
+        assert_eq!(Some(Rect::new(2, 1, 4, 3)), a.diff(&b));
+
+        let report = a.diff_report(&b).unwrap();
+        assert_eq!(Rect::new(2, 1, 4, 3), report.bounds);
+        assert_eq!(2, report.count);
+        assert_eq!(Some(1), report.mask.get_pixel(2, 3));
+        assert_eq!(Some(1), report.mask.get_pixel(5, 1));
+        assert_eq!(Some(0), report.mask.get_pixel(0, 0));
+
+        let visual = a.diff_visual(&b, 15, 0);
+        assert_eq!(Some(15), visual.get_pixel(2, 3));
+        assert_eq!(Some(15), visual.get_pixel(5, 1));
+        assert_eq!(Some(0), visual.get_pixel(4, 4));
+    }
+
+    #[test]
+    pub fn differently_sized_bitmaps() {
+        let a = Bitmap::new(4, 4).unwrap();
+        let b = Bitmap::new(6, 4).unwrap();
+
+        assert_eq!(Some(Rect::new(4, 0, 2, 4)), a.diff(&b));
+        assert_eq!(Some(Rect::new(4, 0, 2, 4)), b.diff(&a));
+
+        let report = a.diff_report(&b).unwrap();
+        assert_eq!(8, report.count);
+        assert_eq!(6, report.mask.width());
+
+        let visual = a.diff_visual(&b, 15, 7);
+        assert_eq!(6, visual.width());
+        assert_eq!(Some(0), visual.get_pixel(0, 0));
+        assert_eq!(Some(15), visual.get_pixel(5, 3));
+    }
+}
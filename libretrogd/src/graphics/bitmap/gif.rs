@@ -0,0 +1,795 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use thiserror::Error;
+
+use crate::graphics::*;
+use crate::math::*;
+use crate::utils::bytes::ReadFixedLengthByteArray;
+
+const MAX_LZW_CODES: usize = 4096;
+const MAX_LZW_CODE_SIZE: u32 = 12;
+
+const EXTENSION_INTRODUCER: u8 = 0x21;
+const IMAGE_DESCRIPTOR_SEPARATOR: u8 = 0x2c;
+const TRAILER: u8 = 0x3b;
+const GRAPHIC_CONTROL_LABEL: u8 = 0xf9;
+const APPLICATION_LABEL: u8 = 0xff;
+
+#[derive(Error, Debug)]
+pub enum GifError {
+    #[error("Bad or unsupported GIF file: {0}")]
+    BadFile(String),
+
+    #[error("GIF I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+/// A single fully composited frame of an animated GIF.
+#[derive(Debug, Clone)]
+pub struct GifFrame {
+    /// The complete image for this frame, sized to the GIF's logical screen dimensions.
+    pub bitmap: Bitmap,
+    /// How long this frame should be displayed for, in milliseconds.
+    pub delay_ms: u32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct LogicalScreenDescriptor {
+    width: u16,
+    height: u16,
+    flags: u8,
+    background_color: u8,
+}
+
+impl LogicalScreenDescriptor {
+    pub fn read<T: ReadBytesExt>(reader: &mut T) -> Result<Self, GifError> {
+        let descriptor = LogicalScreenDescriptor {
+            width: reader.read_u16::<LittleEndian>()?,
+            height: reader.read_u16::<LittleEndian>()?,
+            flags: reader.read_u8()?,
+            background_color: reader.read_u8()?,
+        };
+        // pixel aspect ratio, which is not used
+        reader.read_u8()?;
+        Ok(descriptor)
+    }
+
+    pub fn has_global_color_table(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    pub fn global_color_table_size(&self) -> usize {
+        2 << (self.flags & 0x07)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ImageDescriptor {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+    flags: u8,
+}
+
+impl ImageDescriptor {
+    pub fn read<T: ReadBytesExt>(reader: &mut T) -> Result<Self, GifError> {
+        Ok(ImageDescriptor {
+            x: reader.read_u16::<LittleEndian>()?,
+            y: reader.read_u16::<LittleEndian>()?,
+            width: reader.read_u16::<LittleEndian>()?,
+            height: reader.read_u16::<LittleEndian>()?,
+            flags: reader.read_u8()?,
+        })
+    }
+
+    pub fn has_local_color_table(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    pub fn is_interlaced(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    pub fn local_color_table_size(&self) -> usize {
+        2 << (self.flags & 0x07)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum DisposalMethod {
+    None,
+    RestoreToBackground,
+    RestoreToPrevious,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct GraphicControl {
+    disposal_method: DisposalMethod,
+    delay_ms: u32,
+    transparent_color: Option<u8>,
+}
+
+impl Default for GraphicControl {
+    fn default() -> Self {
+        GraphicControl {
+            disposal_method: DisposalMethod::None,
+            delay_ms: 0,
+            transparent_color: None,
+        }
+    }
+}
+
+impl GraphicControl {
+    pub fn read(data: &[u8]) -> Result<Self, GifError> {
+        if data.len() < 4 {
+            return Err(GifError::BadFile(String::from(
+                "Graphic control extension is too short",
+            )));
+        }
+        let flags = data[0];
+        let disposal_method = match (flags >> 2) & 0x07 {
+            2 => DisposalMethod::RestoreToBackground,
+            3 => DisposalMethod::RestoreToPrevious,
+            _ => DisposalMethod::None,
+        };
+        let delay = u16::from_le_bytes([data[1], data[2]]) as u32;
+        let transparent_color = if flags & 0x01 != 0 {
+            Some(data[3])
+        } else {
+            None
+        };
+        Ok(GraphicControl {
+            disposal_method,
+            delay_ms: delay * 10,
+            transparent_color,
+        })
+    }
+}
+
+fn read_color_table<T: ReadBytesExt>(reader: &mut T, size: usize) -> Result<Palette, GifError> {
+    let mut palette = Palette::new_with_default(0, 0, 0);
+    for i in 0..size {
+        let [r, g, b]: [u8; 3] = reader.read_bytes()?;
+        palette[i as u8] = to_rgb32(r, g, b);
+    }
+    Ok(palette)
+}
+
+/// Reads a sequence of data sub-blocks, returning all of the data contained in them joined
+/// together.
+fn read_sub_blocks<T: ReadBytesExt>(reader: &mut T) -> Result<Vec<u8>, GifError> {
+    let mut data = Vec::new();
+    loop {
+        let size = reader.read_u8()? as usize;
+        if size == 0 {
+            return Ok(data);
+        }
+        let start = data.len();
+        data.resize(start + size, 0);
+        reader.read_exact(&mut data[start..])?;
+    }
+}
+
+/// Decompresses GIF LZW-compressed image data, stopping once the expected number of pixels have
+/// been decoded, the end of information code is found, or the data runs out.
+fn lzw_decode(min_code_size: u8, data: &[u8], expected_length: usize) -> Result<Vec<u8>, GifError> {
+    if !(2..=8).contains(&min_code_size) {
+        return Err(GifError::BadFile(format!(
+            "Invalid LZW minimum code size: {}",
+            min_code_size
+        )));
+    }
+
+    let clear_code = 1u16 << min_code_size;
+    let end_code = clear_code + 1;
+
+    let mut prefixes = [0u16; MAX_LZW_CODES];
+    let mut suffixes = [0u8; MAX_LZW_CODES];
+    let mut stack = Vec::with_capacity(MAX_LZW_CODES);
+    let mut output = Vec::with_capacity(expected_length);
+
+    let mut code_size = min_code_size as u32 + 1;
+    let mut next_code = end_code + 1;
+    let mut previous: Option<u16> = None;
+
+    let mut bits = 0u32;
+    let mut num_bits = 0u32;
+    let mut bytes = data.iter();
+
+    // writes out the string for the given code, returning the first byte of that string
+    let mut write_string = |code: u16, prefixes: &[u16], suffixes: &[u8], output: &mut Vec<u8>| {
+        stack.clear();
+        let mut code = code;
+        while code > end_code {
+            stack.push(suffixes[code as usize]);
+            code = prefixes[code as usize];
+        }
+        let first = code as u8;
+        output.push(first);
+        output.extend(stack.iter().rev());
+        first
+    };
+
+    while output.len() < expected_length {
+        while num_bits < code_size {
+            match bytes.next() {
+                Some(&byte) => {
+                    bits |= (byte as u32) << num_bits;
+                    num_bits += 8;
+                }
+                None => return Ok(output),
+            }
+        }
+        let code = (bits & ((1 << code_size) - 1)) as u16;
+        bits >>= code_size;
+        num_bits -= code_size;
+
+        if code == clear_code {
+            code_size = min_code_size as u32 + 1;
+            next_code = end_code + 1;
+            previous = None;
+            continue;
+        } else if code == end_code {
+            break;
+        }
+
+        match previous {
+            None => {
+                if code > clear_code {
+                    return Err(GifError::BadFile(String::from("Invalid LZW code")));
+                }
+                output.push(code as u8);
+            }
+            Some(previous_code) => {
+                let first = if code < next_code {
+                    write_string(code, &prefixes, &suffixes, &mut output)
+                } else if code == next_code {
+                    let first = write_string(previous_code, &prefixes, &suffixes, &mut output);
+                    output.push(first);
+                    first
+                } else {
+                    return Err(GifError::BadFile(String::from("Invalid LZW code")));
+                };
+
+                if (next_code as usize) < MAX_LZW_CODES {
+                    prefixes[next_code as usize] = previous_code;
+                    suffixes[next_code as usize] = first;
+                    next_code += 1;
+                    if next_code == (1 << code_size) && code_size < MAX_LZW_CODE_SIZE {
+                        code_size += 1;
+                    }
+                }
+            }
+        }
+        previous = Some(code);
+    }
+
+    Ok(output)
+}
+
+/// Writes out the given data as a sequence of data sub-blocks, followed by the block terminator.
+fn write_sub_blocks<T: WriteBytesExt>(writer: &mut T, data: &[u8]) -> Result<(), GifError> {
+    for block in data.chunks(255) {
+        writer.write_u8(block.len() as u8)?;
+        writer.write_all(block)?;
+    }
+    writer.write_u8(0)?;
+    Ok(())
+}
+
+/// Compresses image data using GIF's variant of LZW compression. Codes are always written
+/// using an 8-bit minimum code size.
+fn lzw_encode(pixels: &[u8]) -> Vec<u8> {
+    const MIN_CODE_SIZE: u32 = 8;
+    let clear_code = 1u16 << MIN_CODE_SIZE;
+    let end_code = clear_code + 1;
+
+    let mut output = Vec::with_capacity(pixels.len());
+    let mut bits = 0u32;
+    let mut num_bits = 0u32;
+    let mut write_code = |code: u16, code_size: u32, output: &mut Vec<u8>| {
+        bits |= (code as u32) << num_bits;
+        num_bits += code_size;
+        while num_bits >= 8 {
+            output.push(bits as u8);
+            bits >>= 8;
+            num_bits -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next_code = end_code + 1;
+    write_code(clear_code, code_size, &mut output);
+
+    let mut pixels_iter = pixels.iter();
+    if let Some(&first) = pixels_iter.next() {
+        let mut current = first as u16;
+        for &pixel in pixels_iter {
+            if let Some(&code) = table.get(&(current, pixel)) {
+                current = code;
+                continue;
+            }
+
+            write_code(current, code_size, &mut output);
+            if (next_code as usize) < MAX_LZW_CODES {
+                table.insert((current, pixel), next_code);
+                next_code += 1;
+                if next_code > (1 << code_size) && code_size < MAX_LZW_CODE_SIZE {
+                    code_size += 1;
+                }
+            } else {
+                // the code table is full, so start over with a fresh one
+                write_code(clear_code, code_size, &mut output);
+                table.clear();
+                code_size = MIN_CODE_SIZE + 1;
+                next_code = end_code + 1;
+            }
+            current = pixel as u16;
+        }
+        write_code(current, code_size, &mut output);
+    }
+
+    write_code(end_code, code_size, &mut output);
+    if num_bits > 0 {
+        output.push(bits as u8);
+    }
+    output
+}
+
+fn write_image<T: WriteBytesExt>(
+    writer: &mut T,
+    bitmap: &Bitmap,
+    delay_ms: Option<u32>,
+) -> Result<(), GifError> {
+    if let Some(delay_ms) = delay_ms {
+        writer.write_u8(EXTENSION_INTRODUCER)?;
+        writer.write_u8(GRAPHIC_CONTROL_LABEL)?;
+        let delay = (delay_ms / 10).min(u16::MAX as u32) as u16;
+        let [delay_low, delay_high] = delay.to_le_bytes();
+        write_sub_blocks(writer, &[0, delay_low, delay_high, 0])?;
+    }
+
+    writer.write_u8(IMAGE_DESCRIPTOR_SEPARATOR)?;
+    writer.write_u16::<LittleEndian>(0)?;
+    writer.write_u16::<LittleEndian>(0)?;
+    writer.write_u16::<LittleEndian>(bitmap.width() as u16)?;
+    writer.write_u16::<LittleEndian>(bitmap.height() as u16)?;
+    writer.write_u8(0)?;
+
+    let mut pixels = Vec::with_capacity((bitmap.width() * bitmap.height()) as usize);
+    for y in 0..bitmap.height() as i32 {
+        let row_start = bitmap.get_offset_to_xy(0, y);
+        pixels.extend_from_slice(&bitmap.pixels()[row_start..row_start + bitmap.width() as usize]);
+    }
+    writer.write_u8(8)?;
+    write_sub_blocks(writer, &lzw_encode(&pixels))?;
+    Ok(())
+}
+
+fn write_header<T: WriteBytesExt>(
+    writer: &mut T,
+    width: u32,
+    height: u32,
+    palette: &Palette,
+) -> Result<(), GifError> {
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(GifError::BadFile(String::from(
+            "Image is too large to be saved as a GIF",
+        )));
+    }
+    writer.write_all(b"GIF89a")?;
+    writer.write_u16::<LittleEndian>(width as u16)?;
+    writer.write_u16::<LittleEndian>(height as u16)?;
+    // global color table with 256 colors, 8 bits per primary color
+    writer.write_u8(0xf7)?;
+    writer.write_u8(0)?;
+    writer.write_u8(0)?;
+    for i in 0..=255 {
+        let (r, g, b) = from_rgb32(palette[i]);
+        writer.write_u8(r)?;
+        writer.write_u8(g)?;
+        writer.write_u8(b)?;
+    }
+    Ok(())
+}
+
+/// Returns the row ordering used by interlaced GIF images, where each entry is the destination
+/// row for the corresponding row of decoded image data.
+fn interlaced_rows(height: usize) -> Vec<usize> {
+    let mut rows = Vec::with_capacity(height);
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        rows.extend((start..height).step_by(step));
+    }
+    rows
+}
+
+impl Bitmap {
+    /// Loads all of the frames from a GIF file, which may or may not be animated. Each frame
+    /// returned is fully composited (with all frame disposal methods and transparency applied), so
+    /// can be displayed as-is. Frames which specify their own local color table have their pixels
+    /// remapped to the closest matching colors in the global color table, which is the palette
+    /// returned. If there is no global color table, the first frame's local color table is used
+    /// instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the GIF file from
+    ///
+    /// returns: `Result<(Vec<GifFrame>, Palette), GifError>`
+    pub fn load_gif_frames_bytes<T: ReadBytesExt>(
+        reader: &mut T,
+    ) -> Result<(Vec<GifFrame>, Palette), GifError> {
+        let signature: [u8; 6] = reader.read_bytes()?;
+        if &signature != b"GIF87a" && &signature != b"GIF89a" {
+            return Err(GifError::BadFile(String::from(
+                "Missing GIF signature, probably not a GIF file",
+            )));
+        }
+
+        let screen = LogicalScreenDescriptor::read(reader)?;
+        if screen.width == 0 || screen.height == 0 {
+            return Err(GifError::BadFile(String::from(
+                "Invalid GIF image dimensions",
+            )));
+        }
+        let mut palette = if screen.has_global_color_table() {
+            Some(read_color_table(reader, screen.global_color_table_size())?)
+        } else {
+            None
+        };
+
+        let mut canvas = Bitmap::new(screen.width as u32, screen.height as u32).unwrap();
+        canvas.clear(screen.background_color);
+
+        let mut frames = Vec::new();
+        let mut control = GraphicControl::default();
+
+        loop {
+            match reader.read_u8()? {
+                EXTENSION_INTRODUCER => {
+                    let label = reader.read_u8()?;
+                    let data = read_sub_blocks(reader)?;
+                    if label == GRAPHIC_CONTROL_LABEL {
+                        control = GraphicControl::read(&data)?;
+                    }
+                }
+                IMAGE_DESCRIPTOR_SEPARATOR => {
+                    let descriptor = ImageDescriptor::read(reader)?;
+                    let local_palette = if descriptor.has_local_color_table() {
+                        Some(read_color_table(
+                            reader,
+                            descriptor.local_color_table_size(),
+                        )?)
+                    } else {
+                        None
+                    };
+
+                    let min_code_size = reader.read_u8()?;
+                    let data = read_sub_blocks(reader)?;
+                    let width = descriptor.width as usize;
+                    let height = descriptor.height as usize;
+                    let mut pixels = lzw_decode(min_code_size, &data, width * height)?;
+                    pixels.resize(width * height, 0);
+
+                    let remap_table = match (&local_palette, &palette) {
+                        (Some(local_palette), Some(palette)) => {
+                            Some(local_palette.remap_table_to(palette))
+                        }
+                        (Some(local_palette), None) => {
+                            palette = Some(local_palette.clone());
+                            None
+                        }
+                        (None, Some(_)) => None,
+                        (None, None) => {
+                            return Err(GifError::BadFile(String::from(
+                                "No global or local color table found",
+                            )));
+                        }
+                    };
+
+                    let previous_canvas =
+                        if control.disposal_method == DisposalMethod::RestoreToPrevious {
+                            Some(canvas.clone())
+                        } else {
+                            None
+                        };
+
+                    let rows = if descriptor.is_interlaced() {
+                        interlaced_rows(height)
+                    } else {
+                        (0..height).collect()
+                    };
+                    for (src_row, dest_row) in pixels.chunks_exact(width.max(1)).zip(rows) {
+                        for (x, &pixel) in src_row.iter().enumerate() {
+                            if Some(pixel) == control.transparent_color {
+                                continue;
+                            }
+                            let pixel = match &remap_table {
+                                Some(remap_table) => remap_table[pixel as usize],
+                                None => pixel,
+                            };
+                            canvas.set_pixel(
+                                descriptor.x as i32 + x as i32,
+                                descriptor.y as i32 + dest_row as i32,
+                                pixel,
+                            );
+                        }
+                    }
+
+                    frames.push(GifFrame {
+                        bitmap: canvas.clone(),
+                        delay_ms: control.delay_ms,
+                    });
+
+                    match control.disposal_method {
+                        DisposalMethod::RestoreToBackground => {
+                            let region = Rect::new(
+                                descriptor.x as i32,
+                                descriptor.y as i32,
+                                descriptor.width as u32,
+                                descriptor.height as u32,
+                            );
+                            canvas.filled_rect(
+                                region.x,
+                                region.y,
+                                region.right(),
+                                region.bottom(),
+                                screen.background_color,
+                            );
+                        }
+                        DisposalMethod::RestoreToPrevious => {
+                            if let Some(previous_canvas) = previous_canvas {
+                                canvas = previous_canvas;
+                            }
+                        }
+                        DisposalMethod::None => {}
+                    }
+                    control = GraphicControl::default();
+                }
+                TRAILER => break,
+                other => {
+                    return Err(GifError::BadFile(format!(
+                        "Unexpected block type: {:#04x}",
+                        other
+                    )));
+                }
+            }
+        }
+
+        match (frames.is_empty(), palette) {
+            (false, Some(palette)) => Ok((frames, palette)),
+            _ => Err(GifError::BadFile(String::from("No image data found"))),
+        }
+    }
+
+    pub fn load_gif_frames_file(path: &Path) -> Result<(Vec<GifFrame>, Palette), GifError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_gif_frames_bytes(&mut reader)
+    }
+
+    /// Loads a GIF file as a single still image. If the GIF file is animated, only the first
+    /// frame is returned. See [Bitmap::load_gif_frames_bytes] for details on how the returned
+    /// palette is determined.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the GIF file from
+    ///
+    /// returns: `Result<(Bitmap, Palette), GifError>`
+    pub fn load_gif_bytes<T: ReadBytesExt>(reader: &mut T) -> Result<(Bitmap, Palette), GifError> {
+        let (frames, palette) = Self::load_gif_frames_bytes(reader)?;
+        // there is always at least one frame if loading succeeded
+        let first = frames.into_iter().next().unwrap();
+        Ok((first.bitmap, palette))
+    }
+
+    pub fn load_gif_file(path: &Path) -> Result<(Bitmap, Palette), GifError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_gif_bytes(&mut reader)
+    }
+
+    pub fn to_gif_bytes<T: WriteBytesExt>(
+        &self,
+        writer: &mut T,
+        palette: &Palette,
+    ) -> Result<(), GifError> {
+        write_header(writer, self.width, self.height, palette)?;
+        write_image(writer, self, None)?;
+        writer.write_u8(TRAILER)?;
+        Ok(())
+    }
+
+    pub fn to_gif_file(&self, path: &Path, palette: &Palette) -> Result<(), GifError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_gif_bytes(&mut writer, palette)
+    }
+
+    /// Writes an animated GIF from the given frames, all of which must have the same
+    /// dimensions. All frames share the single palette given. The animation is set to loop
+    /// forever.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the GIF file to
+    /// * `frames`: the frames of the animation, of which there must be at least one
+    /// * `palette`: the palette for all of the frames
+    ///
+    /// returns: `Result<(), GifError>`
+    pub fn save_gif_frames_bytes<T: WriteBytesExt>(
+        writer: &mut T,
+        frames: &[GifFrame],
+        palette: &Palette,
+    ) -> Result<(), GifError> {
+        let first = match frames.first() {
+            Some(first) => &first.bitmap,
+            None => return Err(GifError::BadFile(String::from("No frames to save"))),
+        };
+        if frames.iter().any(|frame| {
+            frame.bitmap.width() != first.width() || frame.bitmap.height() != first.height()
+        }) {
+            return Err(GifError::BadFile(String::from(
+                "All frames must have the same dimensions",
+            )));
+        }
+
+        write_header(writer, first.width(), first.height(), palette)?;
+
+        // netscape looping extension, with a loop count of 0 meaning "forever"
+        writer.write_u8(EXTENSION_INTRODUCER)?;
+        writer.write_u8(APPLICATION_LABEL)?;
+        writer.write_u8(11)?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        write_sub_blocks(writer, &[1, 0, 0])?;
+
+        for frame in frames.iter() {
+            write_image(writer, &frame.bitmap, Some(frame.delay_ms))?;
+        }
+        writer.write_u8(TRAILER)?;
+        Ok(())
+    }
+
+    pub fn save_gif_frames_file(
+        path: &Path,
+        frames: &[GifFrame],
+        palette: &Palette,
+    ) -> Result<(), GifError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        Self::save_gif_frames_bytes(&mut writer, frames, palette)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    pub static TEST_BMP_PIXELS_RAW: &[u8] =
+        include_bytes!("../../../test-assets/test_bmp_pixels_raw.bin");
+
+    #[test]
+    pub fn load_still_image() -> Result<(), GifError> {
+        let dp2_palette =
+            Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal)
+                .unwrap();
+
+        let (bmp, palette) = Bitmap::load_gif_file(Path::new("./test-assets/test.gif"))?;
+        assert_eq!(16, bmp.width());
+        assert_eq!(16, bmp.height());
+        assert_eq!(bmp.pixels(), TEST_BMP_PIXELS_RAW);
+        assert_eq!(palette, dp2_palette);
+
+        let (bmp, _) = Bitmap::load_gif_file(Path::new("./test-assets/test_interlaced.gif"))?;
+        assert_eq!(bmp.pixels(), TEST_BMP_PIXELS_RAW);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_animation() -> Result<(), GifError> {
+        let (frames, palette) =
+            Bitmap::load_gif_frames_file(Path::new("./test-assets/test_anim.gif"))?;
+        assert_eq!(3, frames.len());
+        assert_eq!(to_rgb32(255, 0, 0), palette[1]);
+
+        // first frame: full 8x8 image of color 1 with color 2 in the top-left 2x2 corner
+        let frame = &frames[0];
+        assert_eq!(100, frame.delay_ms);
+        assert_eq!(8, frame.bitmap.width());
+        assert_eq!(Some(2), frame.bitmap.get_pixel(0, 0));
+        assert_eq!(Some(2), frame.bitmap.get_pixel(1, 1));
+        assert_eq!(Some(1), frame.bitmap.get_pixel(2, 2));
+
+        // second frame: 4x4 sub-image at (4,4) where color 0 is transparent at (4,4). the rest
+        // of the sub-image uses the local color table's green (color 2), which should be
+        // remapped to the global palette's green (color 3)
+        let frame = &frames[1];
+        assert_eq!(250, frame.delay_ms);
+        assert_eq!(Some(2), frame.bitmap.get_pixel(0, 0));
+        assert_eq!(Some(1), frame.bitmap.get_pixel(4, 4));
+        assert_eq!(Some(3), frame.bitmap.get_pixel(5, 5));
+        assert_eq!(Some(3), frame.bitmap.get_pixel(7, 7));
+        assert_eq!(Some(1), frame.bitmap.get_pixel(3, 3));
+
+        // second frame was disposed of by restoring to the background (color 0)
+        let frame = &frames[2];
+        assert_eq!(Some(0), frame.bitmap.get_pixel(5, 5));
+        assert_eq!(Some(1), frame.bitmap.get_pixel(3, 3));
+        assert_eq!(Some(2), frame.bitmap.get_pixel(0, 1));
+        assert_eq!(Some(2), frame.bitmap.get_pixel(7, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_and_save() -> Result<(), GifError> {
+        let dp2_palette =
+            Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal)
+                .unwrap();
+        let tmp_dir = TempDir::new()?;
+
+        let (bmp, palette) = Bitmap::load_gif_file(Path::new("./test-assets/test.gif"))?;
+        let save_path = tmp_dir.path().join("test_save.gif");
+        bmp.to_gif_file(&save_path, &palette)?;
+        let (reloaded_bmp, reloaded_palette) = Bitmap::load_gif_file(&save_path)?;
+        assert_eq!(16, reloaded_bmp.width());
+        assert_eq!(16, reloaded_bmp.height());
+        assert_eq!(reloaded_bmp.pixels(), TEST_BMP_PIXELS_RAW);
+        assert_eq!(reloaded_palette, dp2_palette);
+
+        // large enough image with enough variety to force the lzw code table to fill up
+        let mut bmp = Bitmap::new(320, 240).unwrap();
+        for (i, pixel) in bmp.pixels_mut().iter_mut().enumerate() {
+            *pixel = ((i * 7) ^ (i / 13)) as u8;
+        }
+        let mut data = Vec::new();
+        bmp.to_gif_bytes(&mut data, &dp2_palette)?;
+        let (reloaded_bmp, _) = Bitmap::load_gif_bytes(&mut data.as_slice())?;
+        assert_eq!(bmp, reloaded_bmp);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn save_animation() -> Result<(), GifError> {
+        let (frames, palette) =
+            Bitmap::load_gif_frames_file(Path::new("./test-assets/test_anim.gif"))?;
+        let mut data = Vec::new();
+        Bitmap::save_gif_frames_bytes(&mut data, &frames, &palette)?;
+        let (reloaded_frames, reloaded_palette) =
+            Bitmap::load_gif_frames_bytes(&mut data.as_slice())?;
+        assert_eq!(palette, reloaded_palette);
+        assert_eq!(frames.len(), reloaded_frames.len());
+        for (frame, reloaded_frame) in frames.iter().zip(reloaded_frames.iter()) {
+            assert_eq!(frame.delay_ms, reloaded_frame.delay_ms);
+            assert_eq!(frame.bitmap, reloaded_frame.bitmap);
+        }
+
+        assert_matches!(
+            Bitmap::save_gif_frames_bytes(&mut data, &[], &palette),
+            Err(GifError::BadFile(..))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_bad_files() {
+        let mut data: &[u8] = b"definitely not a gif file";
+        assert_matches!(
+            Bitmap::load_gif_bytes(&mut data),
+            Err(GifError::BadFile(..))
+        );
+    }
+}
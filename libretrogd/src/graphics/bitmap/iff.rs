@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::io;
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::RangeInclusive;
 use std::path::Path;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -57,6 +58,63 @@ impl IffFormat {
     }
 }
 
+/// A color cycling range, as found in the CRNG chunks that Deluxe Paint writes to IFF files.
+/// These can be used with palette functions that operate on a range of colors, such as
+/// [Palette::rotate_colors], or driven automatically with a [ColorCycler].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IffColorRange {
+    /// The cycling speed, where a rate of 16384 means 60 steps per second.
+    pub rate: i16,
+    /// Whether color cycling is enabled for this range.
+    pub active: bool,
+    /// Whether colors should be cycled in the reverse direction.
+    pub reverse: bool,
+    /// The first palette color index in this range.
+    pub low: u8,
+    /// The last palette color index in this range (inclusive).
+    pub high: u8,
+}
+
+impl IffColorRange {
+    pub fn read<T: ReadBytesExt>(reader: &mut T) -> Result<Self, IffError> {
+        let _padding = reader.read_i16::<BigEndian>()?;
+        let rate = reader.read_i16::<BigEndian>()?;
+        let flags = reader.read_i16::<BigEndian>()?;
+        let low = reader.read_u8()?;
+        let high = reader.read_u8()?;
+        Ok(IffColorRange {
+            rate,
+            active: (flags & 1) != 0,
+            reverse: (flags & 2) != 0,
+            low,
+            high,
+        })
+    }
+
+    /// Returns the range of palette colors covered by this color range.
+    #[inline]
+    pub fn colors(&self) -> RangeInclusive<u8> {
+        self.low..=self.high
+    }
+
+    /// Returns the number of color cycling steps that should occur per second.
+    #[inline]
+    pub fn steps_per_second(&self) -> f32 {
+        (self.rate as f32 * 60.0) / 16384.0
+    }
+
+    /// Returns the step that should be used to rotate this range's colors for each cycle, taking
+    /// into account the cycling direction.
+    #[inline]
+    pub fn step(&self) -> i8 {
+        if self.reverse {
+            -1
+        } else {
+            1
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 #[repr(packed)]
 struct IffId {
@@ -254,6 +312,9 @@ fn load_planar_body<T: ReadBytesExt>(reader: &mut T, bmhd: &BMHDChunk) -> Result
 
     let row_bytes = (((bmhd.width + 15) >> 4) << 1) as usize;
     let mut buffer = vec![0u8; row_bytes];
+    // the bitplane rows are always padded to a multiple of 16 pixels, which may be wider than
+    // the bitmap itself, so we merge into a temporary row first
+    let mut row = vec![0u8; row_bytes * 8];
 
     for y in 0..bmhd.height {
         // planar data is stored for each bitplane in sequence for the scanline.
@@ -268,6 +329,7 @@ fn load_planar_body<T: ReadBytesExt>(reader: &mut T, bmhd: &BMHDChunk) -> Result
         // bitplanes together at the end of each line)
 
         // read all the bitplane rows per scanline
+        row.fill(0);
         for plane in 0..(bmhd.bitplanes as u32) {
             if bmhd.compress == 1 {
                 // decompress packed line for this bitplane only
@@ -284,13 +346,11 @@ fn load_planar_body<T: ReadBytesExt>(reader: &mut T, bmhd: &BMHDChunk) -> Result
             // the bitplanes have been loaded and merged in this way for this
             // scanline, the destination pointer will contain VGA-friendly
             // "chunky pixel"-format pixel data
-            merge_bitplane(
-                plane,
-                &buffer,
-                bitmap.pixels_at_mut(0, y as i32).unwrap(),
-                row_bytes,
-            );
+            merge_bitplane(plane, &buffer, &mut row, row_bytes);
         }
+
+        let dest = bitmap.pixels_at_mut(0, y as i32).unwrap();
+        dest[0..bmhd.width as usize].copy_from_slice(&row[0..bmhd.width as usize]);
     }
 
     Ok(bitmap)
@@ -370,6 +430,21 @@ impl Bitmap {
     pub fn load_iff_bytes<T: ReadBytesExt + Seek>(
         reader: &mut T,
     ) -> Result<(Bitmap, Palette), IffError> {
+        let (bitmap, palette, _) = Self::load_iff_bytes_with_color_ranges(reader)?;
+        Ok((bitmap, palette))
+    }
+
+    /// Same as [Bitmap::load_iff_bytes], but also returns any color cycling ranges found in the
+    /// file. Ranges which do not span at least two colors are not returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the IFF image from
+    ///
+    /// returns: `Result<(Bitmap, Palette, Vec<IffColorRange>), IffError>`
+    pub fn load_iff_bytes_with_color_ranges<T: ReadBytesExt + Seek>(
+        reader: &mut T,
+    ) -> Result<(Bitmap, Palette, Vec<IffColorRange>), IffError> {
         let form_chunk = FormChunkHeader::read(reader)?;
         if form_chunk.chunk_id.id != *b"FORM" {
             return Err(IffError::BadFile(String::from(
@@ -385,6 +460,7 @@ impl Bitmap {
         let mut bmhd: Option<BMHDChunk> = None;
         let mut palette: Option<Palette> = None;
         let mut bitmap: Option<Bitmap> = None;
+        let mut color_ranges = Vec::new();
 
         loop {
             let header = match SubChunkHeader::read(reader) {
@@ -401,21 +477,39 @@ impl Bitmap {
             // todo: process chunk here
             if header.chunk_id.id == *b"BMHD" {
                 bmhd = Some(BMHDChunk::read(reader)?);
-                if bmhd.as_ref().unwrap().bitplanes != 8 {
+                let bitplanes = bmhd.as_ref().unwrap().bitplanes;
+                if form_chunk.type_id.id == *b"PBM " && bitplanes != 8 {
+                    return Err(IffError::BadFile(String::from(
+                        "Only 8bpp PBM files are supported",
+                    )));
+                }
+                if !(1..=8).contains(&bitplanes) {
                     return Err(IffError::BadFile(String::from(
-                        "Only 8bpp files are supported",
+                        "Only 1-8bpp ILBM files are supported",
                     )));
                 }
                 if bmhd.as_ref().unwrap().masking == 1 {
                     return Err(IffError::BadFile(String::from("Masking is not supported")));
                 }
             } else if header.chunk_id.id == *b"CMAP" {
-                if header.size != 768 {
+                if header.size > 768 {
                     return Err(IffError::BadFile(String::from(
-                        "Only 256 color files are supported",
+                        "Only files with up to 256 colors are supported",
                     )));
                 }
-                palette = Some(Palette::load_from_bytes(reader, PaletteFormat::Normal)?)
+                let mut cmap = Palette::new_with_default(0, 0, 0);
+                for i in 0..(header.size / 3) {
+                    let r = reader.read_u8()?;
+                    let g = reader.read_u8()?;
+                    let b = reader.read_u8()?;
+                    cmap[i as u8] = to_rgb32(r, g, b);
+                }
+                palette = Some(cmap);
+            } else if header.chunk_id.id == *b"CRNG" {
+                let range = IffColorRange::read(reader)?;
+                if range.low < range.high {
+                    color_ranges.push(range);
+                }
             } else if header.chunk_id.id == *b"BODY" {
                 if let Some(bmhd) = &bmhd {
                     if form_chunk.type_id.id == *b"PBM " {
@@ -443,7 +537,7 @@ impl Bitmap {
             return Err(IffError::BadFile(String::from("No CMAP chunk was found")));
         }
 
-        Ok((bitmap.unwrap(), palette.unwrap()))
+        Ok((bitmap.unwrap(), palette.unwrap(), color_ranges))
     }
 
     pub fn load_iff_file(path: &Path) -> Result<(Bitmap, Palette), IffError> {
@@ -452,6 +546,14 @@ impl Bitmap {
         Self::load_iff_bytes(&mut reader)
     }
 
+    pub fn load_iff_file_with_color_ranges(
+        path: &Path,
+    ) -> Result<(Bitmap, Palette, Vec<IffColorRange>), IffError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_iff_bytes_with_color_ranges(&mut reader)
+    }
+
     pub fn to_iff_bytes<T: WriteBytesExt + Seek>(
         &self,
         writer: &mut T,
@@ -609,6 +711,73 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn load_color_ranges() -> Result<(), IffError> {
+        let (_bmp, _palette, ranges) =
+            Bitmap::load_iff_file_with_color_ranges(Path::new("./test-assets/test_ilbm.lbm"))?;
+        let colors: Vec<_> = ranges.iter().map(|range| range.colors()).collect();
+        assert_eq!(
+            vec![0x78..=0x7f, 0x20..=0x2f, 0x60..=0x6f, 0x90..=0x9f],
+            colors
+        );
+        assert!(ranges.iter().all(|range| !range.active));
+
+        let (_bmp, _palette, ranges) =
+            Bitmap::load_iff_file_with_color_ranges(Path::new("./test-assets/texture.lbm"))?;
+        assert_eq!(6, ranges.len());
+        assert_eq!(
+            IffColorRange {
+                rate: 0,
+                active: false,
+                reverse: true,
+                low: 0x85,
+                high: 0x86,
+            },
+            ranges[5]
+        );
+        assert_eq!(-1, ranges[5].step());
+
+        let range = IffColorRange {
+            rate: 16384,
+            active: true,
+            reverse: false,
+            low: 1,
+            high: 4,
+        };
+        assert_eq!(60.0, range.steps_per_second());
+        assert_eq!(1, range.step());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_fewer_bitplanes() -> Result<(), IffError> {
+        let dp2_palette =
+            Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal)
+                .unwrap();
+
+        let (bmp, palette, ranges) =
+            Bitmap::load_iff_file_with_color_ranges(Path::new("./test-assets/test_ilbm_5bpp.lbm"))?;
+        assert_eq!(20, bmp.width());
+        assert_eq!(4, bmp.height());
+        for y in 0..4 {
+            for x in 0..20 {
+                assert_eq!(Some(((x + y * 3) % 32) as u8), bmp.get_pixel(x, y));
+            }
+        }
+        for i in 0..32 {
+            assert_eq!(dp2_palette[i], palette[i]);
+        }
+        assert_eq!(to_rgb32(0, 0, 0), palette[32]);
+
+        assert_eq!(1, ranges.len());
+        assert!(ranges[0].active);
+        assert_eq!(2..=9, ranges[0].colors());
+        assert_eq!(30.0, ranges[0].steps_per_second());
+
+        Ok(())
+    }
+
     #[test]
     pub fn load_larger_image() -> Result<(), IffError> {
         let (bmp, _palette) = Bitmap::load_iff_file(Path::new("./test-assets/test_image.lbm"))?;
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use image::{DynamicImage, RgbaImage};
+
+use crate::graphics::*;
+
+impl Bitmap {
+    /// Converts any image loaded by the `image` crate into a Bitmap, mapping each pixel's color
+    /// to the closest matching color in the given palette (as determined by
+    /// [Palette::find_color]). Only available with the "image-interop" feature enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `image`: the image to convert
+    /// * `palette`: the palette to map the image's colors to
+    /// * `transparent_color`: if given, the color to use for any pixels which are mostly
+    ///   transparent (alpha below 128) instead of mapping them to the palette
+    ///
+    /// returns: `Result<Bitmap, BitmapError>`
+    pub fn from_dynamic_image(
+        image: &DynamicImage,
+        palette: &Palette,
+        transparent_color: Option<u8>,
+    ) -> Result<Bitmap, BitmapError> {
+        let rgba = image.to_rgba8();
+        let mut bmp = Bitmap::new(rgba.width(), rgba.height())?;
+
+        // typical images only use a limited number of distinct colors, so caching lookups saves
+        // a lot of repeated palette searches
+        let mut color_cache = HashMap::new();
+        for (pixel, src) in bmp.pixels_mut().iter_mut().zip(rgba.pixels()) {
+            let [r, g, b, a] = src.0;
+            *pixel = match transparent_color {
+                Some(transparent_color) if a < 128 => transparent_color,
+                _ => *color_cache
+                    .entry((r, g, b))
+                    .or_insert_with(|| palette.find_color(r, g, b)),
+            };
+        }
+        Ok(bmp)
+    }
+
+    /// Converts any image loaded by the `image` crate into a Bitmap, along with a palette
+    /// generated for it by quantizing the image's colors (see [quantize]). Only available with
+    /// the "image-interop" feature enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `image`: the image to convert
+    /// * `options`: the number of colors to generate, whether to dither and which color is
+    ///   transparent (if any)
+    ///
+    /// returns: `Result<(Bitmap, Palette), BitmapError>`
+    pub fn from_dynamic_image_quantized(
+        image: &DynamicImage,
+        options: &QuantizeOptions,
+    ) -> Result<(Bitmap, Palette), BitmapError> {
+        let rgba = image.to_rgba8();
+        let pixels: Vec<u32> = rgba
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                to_argb32(a, r, g, b)
+            })
+            .collect();
+        quantize(&pixels, rgba.width(), rgba.height(), options)
+    }
+
+    /// Converts this bitmap into an RGBA image usable with the `image` crate, for example to
+    /// save it in any format supported by that crate. Only available with the "image-interop"
+    /// feature enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to apply to this bitmap's pixels
+    /// * `transparent_color`: if given, pixels of this color are made fully transparent
+    ///
+    /// returns: `DynamicImage`
+    pub fn to_dynamic_image(
+        &self,
+        palette: &Palette,
+        transparent_color: Option<u8>,
+    ) -> DynamicImage {
+        let image = RgbaImage::from_fn(self.width, self.height, |x, y| {
+            // the coordinates given are always within this bitmap's bounds
+            let pixel = self.get_pixel(x as i32, y as i32).unwrap();
+            let (r, g, b) = from_rgb32(palette[pixel]);
+            let a = if Some(pixel) == transparent_color {
+                0
+            } else {
+                255
+            };
+            image::Rgba([r, g, b, a])
+        });
+        DynamicImage::ImageRgba8(image)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+
+    use image::{GrayImage, Luma, Rgb, RgbImage};
+
+    use super::*;
+
+    fn test_palette() -> Palette {
+        Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal).unwrap()
+    }
+
+    #[test]
+    pub fn convert_from_dynamic_image() -> Result<(), BitmapError> {
+        let palette = test_palette();
+        let (r, g, b) = from_rgb32(palette[120]);
+        let (r2, g2, b2) = from_rgb32(palette[127]);
+
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(4, 2, |x, _| {
+            if x < 2 {
+                Rgb([r, g, b])
+            } else {
+                Rgb([r2, g2, b2])
+            }
+        }));
+        let bmp = Bitmap::from_dynamic_image(&image, &palette, None)?;
+        assert_eq!(4, bmp.width());
+        assert_eq!(2, bmp.height());
+        assert_eq!(&[120, 120, 127, 127, 120, 120, 127, 127], bmp.pixels());
+
+        let image = DynamicImage::ImageLuma8(GrayImage::from_pixel(2, 2, Luma([255])));
+        let bmp = Bitmap::from_dynamic_image(&image, &palette, None)?;
+        assert_eq!(palette.find_color(255, 255, 255), bmp.pixels()[0]);
+
+        // round trip, with transparency
+        let mut bmp = Bitmap::new(3, 3).unwrap();
+        bmp.clear(0);
+        bmp.set_pixel(1, 1, 15);
+        let image = bmp.to_dynamic_image(&palette, Some(0));
+        let rgba = image.to_rgba8();
+        assert_eq!(0, rgba.get_pixel(0, 0).0[3]);
+        assert_eq!(255, rgba.get_pixel(1, 1).0[3]);
+        let converted = Bitmap::from_dynamic_image(&image, &palette, Some(0))?;
+        assert_eq!(bmp, converted);
+
+        let (quantized, quantized_palette) =
+            Bitmap::from_dynamic_image_quantized(&image, &QuantizeOptions::default())?;
+        assert_eq!(quantized_palette[quantized.pixels()[0]], palette[0]);
+        assert_eq!(quantized_palette[quantized.pixels()[4]], palette[15]);
+
+        let image = DynamicImage::ImageRgb8(RgbImage::new(0, 0));
+        assert!(Bitmap::from_dynamic_image(&image, &palette, None).is_err());
+
+        Ok(())
+    }
+}
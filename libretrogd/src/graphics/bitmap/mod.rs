@@ -1,4 +1,6 @@
 use std::fmt::Formatter;
+use std::io::Cursor;
+use std::ops::DerefMut;
 use std::path::Path;
 use std::slice;
 
@@ -6,16 +8,32 @@ use thiserror::Error;
 
 use crate::graphics::*;
 use crate::math::*;
+use crate::NUM_COLORS;
 
 pub use self::blit::*;
+pub use self::bmp::*;
+pub use self::diff::*;
+pub use self::gif::*;
 pub use self::iff::*;
 pub use self::pcx::*;
+pub use self::png::*;
 pub use self::primitives::*;
+pub use self::raw::*;
+pub use self::transform::*;
 
 pub mod blit;
+pub mod bmp;
+pub mod collision;
+pub mod diff;
+pub mod gif;
 pub mod iff;
+#[cfg(feature = "image-interop")]
+pub mod imageinterop;
 pub mod pcx;
+pub mod png;
 pub mod primitives;
+pub mod raw;
+pub mod transform;
 
 #[derive(Error, Debug)]
 pub enum BitmapError {
@@ -28,33 +46,68 @@ pub enum BitmapError {
     #[error("Unknown bitmap file type: {0}")]
     UnknownFileType(String),
 
+    #[error("Bitmap BMP file error")]
+    BmpError(#[from] bmp::BmpError),
+
+    #[error("Bitmap GIF file error")]
+    GifError(#[from] gif::GifError),
+
     #[error("Bitmap IFF file error")]
     IffError(#[from] iff::IffError),
 
     #[error("Bitmap PCX file error")]
     PcxError(#[from] pcx::PcxError),
+
+    #[error("Bitmap PNG file error")]
+    PngError(#[from] png::PngError),
+
+    #[error("Bitmap raw image file error")]
+    RawError(#[from] raw::RawError),
+}
+
+fn file_extension(path: &Path) -> Result<String, BitmapError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => Ok(extension.to_ascii_lowercase()),
+        None => Err(BitmapError::UnknownFileType(String::from(
+            "No file extension",
+        ))),
+    }
 }
 
 /// Container for 256 color 2D pixel/image data that can be rendered to the screen. Pixel data
 /// is stored as contiguous bytes, where each pixel is an index into a separate 256 color palette
-/// stored independently of the bitmap. The pixel data is not padded in any way, so the stride from
-/// one row to the next is always exactly equal to the bitmap width. Rendering operations provided
-/// here are done with respect to the bitmaps clipping region, where rendering outside of the
-/// clipping region is simply not performed / stops at the clipping boundary.
+/// stored independently of the bitmap. By default the pixel data is not padded in any way, so the
+/// stride from one row to the next (the "pitch") is exactly equal to the bitmap width. Bitmaps
+/// created with [Bitmap::new_with_alignment] instead have each row padded so that the pitch is a
+/// multiple of the requested alignment. Rendering operations provided here are done with respect
+/// to the bitmaps clipping region, where rendering outside of the clipping region is simply not
+/// performed / stops at the clipping boundary.
 #[derive(Clone, Eq, PartialEq)]
-pub struct Bitmap {
+pub struct Bitmap<P: PixelBuffer = Box<[u8]>> {
     width: u32,
     height: u32,
-    pixels: Box<[u8]>,
+    pitch: u32,
+    pixels: P,
     clip_region: Rect,
+    clip_region_stack: Vec<Rect>,
+    palette: Option<Palette>,
 }
 
-impl std::fmt::Debug for Bitmap {
+/// Storage that a [Bitmap]'s pixel data can be held in. Normally bitmaps own their pixel data
+/// (`Box<[u8]>`), but a bitmap can also be created around a mutably borrowed slice of pixels via
+/// [Bitmap::from_slice_mut], allowing rendering directly into memory owned by something else.
+pub trait PixelBuffer: DerefMut<Target = [u8]> {}
+
+impl<T: DerefMut<Target = [u8]>> PixelBuffer for T {}
+
+impl<P: PixelBuffer> std::fmt::Debug for Bitmap<P> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Bitmap")
             .field("width", &self.width)
             .field("height", &self.height)
+            .field("pitch", &self.pitch)
             .field("clip_region", &self.clip_region)
+            .field("has_palette", &self.palette.is_some())
             .finish_non_exhaustive()
     }
 }
@@ -69,20 +122,51 @@ impl Bitmap {
     ///
     /// returns: `Result<Bitmap, BitmapError>`
     pub fn new(width: u32, height: u32) -> Result<Bitmap, BitmapError> {
-        if width == 0 || height == 0 {
+        Self::new_with_alignment(width, height, 1)
+    }
+
+    /// Creates a new Bitmap with the specified dimensions, where each row of pixels is padded
+    /// such that the pitch (the number of bytes from the start of one row to the start of the
+    /// next) is a multiple of the given alignment. The padding bytes are never drawn to, but are
+    /// included in the slices returned by [Bitmap::pixels] and [Bitmap::pixels_mut].
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the bitmap in pixels
+    /// * `height`: the height of the bitmap in pixels
+    /// * `alignment`: the row alignment in bytes (e.g. 16 or 64). An alignment of 1 results in
+    ///   no row padding at all.
+    ///
+    /// returns: `Result<Bitmap, BitmapError>`
+    pub fn new_with_alignment(
+        width: u32,
+        height: u32,
+        alignment: u32,
+    ) -> Result<Bitmap, BitmapError> {
+        if width == 0 || height == 0 || alignment == 0 {
             return Err(BitmapError::InvalidDimensions);
         }
 
+        let pitch = width
+            .div_ceil(alignment)
+            .checked_mul(alignment)
+            .ok_or(BitmapError::InvalidDimensions)?;
+        let size = (pitch as usize)
+            .checked_mul(height as usize)
+            .ok_or(BitmapError::InvalidDimensions)?;
         Ok(Bitmap {
             width,
             height,
-            pixels: vec![0u8; (width * height) as usize].into_boxed_slice(),
+            pitch,
+            pixels: vec![0u8; size].into_boxed_slice(),
             clip_region: Rect {
                 x: 0,
                 y: 0,
                 width,
                 height,
             },
+            clip_region_stack: Vec::new(),
+            palette: None,
         })
     }
 
@@ -106,24 +190,105 @@ impl Bitmap {
     }
 
     pub fn load_file(path: &Path) -> Result<(Bitmap, Palette), BitmapError> {
-        if let Some(extension) = path.extension() {
-            let extension = extension.to_ascii_lowercase();
-            match extension.to_str() {
-                Some("pcx") => Ok(Self::load_pcx_file(path)?),
-                Some("iff") | Some("lbm") | Some("pbm") | Some("bbm") => {
-                    Ok(Self::load_iff_file(path)?)
-                }
-                _ => Err(BitmapError::UnknownFileType(String::from(
-                    "Unrecognized file extension",
-                ))),
-            }
-        } else {
-            Err(BitmapError::UnknownFileType(String::from(
-                "No file extension",
-            )))
+        match file_extension(path)?.as_str() {
+            "bmp" => Ok(Self::load_bmp_file(path)?),
+            "gif" => Ok(Self::load_gif_file(path)?),
+            "pcx" => Ok(Self::load_pcx_file(path)?),
+            "png" => Ok(Self::load_png_file(path, None)?),
+            "iff" | "lbm" | "pbm" | "bbm" => Ok(Self::load_iff_file(path)?),
+            _ => Err(BitmapError::UnknownFileType(String::from(
+                "Unrecognized file extension",
+            ))),
         }
     }
 
+    /// Loads and returns a bitmap from the contents of an image file that has already been read
+    /// into memory (for example, from an archive). The image format is determined from the
+    /// extension of the given file name in the same way as [`Bitmap::load_file`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: the contents of the image file
+    /// * `name`: the name of the image file
+    ///
+    /// returns: `Result<(Bitmap, Palette), BitmapError>`
+    pub fn load_bytes(data: &[u8], name: &Path) -> Result<(Bitmap, Palette), BitmapError> {
+        let mut reader = Cursor::new(data);
+        match file_extension(name)?.as_str() {
+            "bmp" => Ok(Self::load_bmp_bytes(&mut reader)?),
+            "gif" => Ok(Self::load_gif_bytes(&mut reader)?),
+            "pcx" => Ok(Self::load_pcx_bytes(&mut reader)?),
+            "png" => Ok(Self::load_png_bytes(&mut reader, None)?),
+            "iff" | "lbm" | "pbm" | "bbm" => Ok(Self::load_iff_bytes(&mut reader)?),
+            _ => Err(BitmapError::UnknownFileType(String::from(
+                "Unrecognized file extension",
+            ))),
+        }
+    }
+}
+
+impl<'a> Bitmap<&'a mut [u8]> {
+    /// Creates a new Bitmap which renders directly into the given externally owned pixel data
+    /// instead of allocating its own. The pixel data is expected to be laid out with each row
+    /// immediately following the previous one (that is, the pitch is equal to the width).
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the bitmap in pixels
+    /// * `height`: the height of the bitmap in pixels
+    /// * `pixels`: the pixel data to use, which must be at least `width * height` bytes long
+    ///
+    /// returns: `Result<Bitmap<&mut [u8]>, BitmapError>`
+    pub fn from_slice_mut(
+        width: u32,
+        height: u32,
+        pixels: &'a mut [u8],
+    ) -> Result<Bitmap<&'a mut [u8]>, BitmapError> {
+        Self::from_slice_mut_with_pitch(width, height, width, pixels)
+    }
+
+    /// Same as [Bitmap::from_slice_mut] except that the pixel data can have rows which are padded
+    /// out to the given pitch, such as with framebuffers handed out by SDL or emulator cores.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the bitmap in pixels
+    /// * `height`: the height of the bitmap in pixels
+    /// * `pitch`: the number of bytes from the start of one row to the start of the next, which
+    ///   must be at least equal to the width
+    /// * `pixels`: the pixel data to use, which must be at least `pitch * height` bytes long. Only
+    ///   the first `pitch * height` bytes are used, any bytes after that are never touched.
+    ///
+    /// returns: `Result<Bitmap<&mut [u8]>, BitmapError>`
+    pub fn from_slice_mut_with_pitch(
+        width: u32,
+        height: u32,
+        pitch: u32,
+        pixels: &'a mut [u8],
+    ) -> Result<Bitmap<&'a mut [u8]>, BitmapError> {
+        if width == 0 || height == 0 || pitch < width {
+            return Err(BitmapError::InvalidDimensions);
+        }
+        let size = (pitch as usize)
+            .checked_mul(height as usize)
+            .ok_or(BitmapError::InvalidDimensions)?;
+        if pixels.len() < size {
+            return Err(BitmapError::OutOfBounds);
+        }
+
+        Ok(Bitmap {
+            width,
+            height,
+            pitch,
+            pixels: &mut pixels[..size],
+            clip_region: Rect::new(0, 0, width, height),
+            clip_region_stack: Vec::new(),
+            palette: None,
+        })
+    }
+}
+
+impl<P: PixelBuffer> Bitmap<P> {
     /// Returns the width of the bitmap in pixels.
     #[inline]
     pub fn width(&self) -> u32 {
@@ -136,6 +301,13 @@ impl Bitmap {
         self.height
     }
 
+    /// Returns the number of bytes from the start of one row of pixels to the start of the next.
+    /// This is equal to the width unless the bitmap was created with row alignment padding.
+    #[inline]
+    pub fn pitch(&self) -> u32 {
+        self.pitch
+    }
+
     /// Returns the right x coordinate of the bitmap.
     #[inline]
     pub fn right(&self) -> u32 {
@@ -182,13 +354,116 @@ impl Bitmap {
         self.clip_region = self.full_bounds();
     }
 
-    /// Returns a reference to the raw pixels in this bitmap.
+    /// Saves the current clipping region and then sets a new clipping region which is the
+    /// intersection of the current clipping region and the region given. The previous clipping
+    /// region can be restored by calling [Bitmap::pop_clip_region]. Pushes can be nested.
+    ///
+    /// # Arguments
+    ///
+    /// * `region`: the new clipping region, which will be limited to the current clipping region
+    ///
+    /// returns: true if the new clipping region was pushed, or false if the region given lies
+    /// entirely outside of the current clipping region. In the latter case nothing is pushed (and
+    /// so [Bitmap::pop_clip_region] should not be called), the current clipping region is left
+    /// unchanged and any drawing intended for the region should be skipped.
+    pub fn push_clip_region(&mut self, region: &Rect) -> bool {
+        let mut new_region = *region;
+        if !new_region.clamp_to(&self.clip_region) {
+            return false;
+        }
+        self.clip_region_stack.push(self.clip_region);
+        self.clip_region = new_region;
+        true
+    }
+
+    /// Restores the clipping region that was current before the most recent call to
+    /// [Bitmap::push_clip_region].
+    ///
+    /// returns: the clipping region that was removed, or None if there was no pushed clipping
+    /// region to restore from (in which case the current clipping region is left unchanged)
+    pub fn pop_clip_region(&mut self) -> Option<Rect> {
+        if let Some(previous) = self.clip_region_stack.pop() {
+            let popped = self.clip_region;
+            self.clip_region = previous;
+            Some(popped)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the palette associated with this bitmap, if one has been set. Bitmaps do not have
+    /// an associated palette by default.
+    #[inline]
+    pub fn palette(&self) -> Option<&Palette> {
+        self.palette.as_ref()
+    }
+
+    /// Returns a mutable reference to the palette associated with this bitmap, if one has been
+    /// set.
+    #[inline]
+    pub fn palette_mut(&mut self) -> Option<&mut Palette> {
+        self.palette.as_mut()
+    }
+
+    /// Associates a palette with this bitmap, indicating the colors that this bitmap's pixel data
+    /// was drawn against, or removes any associated palette if None is given. This palette is not
+    /// used when rendering the bitmap, but is used to remap colors when blitting between bitmaps
+    /// that have different palettes (see [Bitmap::blit_region_remapped]).
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to associate with this bitmap, or None
+    pub fn set_palette(&mut self, palette: Option<Palette>) {
+        self.palette = palette;
+    }
+
+    /// Replaces every pixel in this bitmap with the corresponding color from the remap table
+    /// given, where each pixel's current color is used as the index into the table. This ignores
+    /// the current clipping region.
+    ///
+    /// # Arguments
+    ///
+    /// * `remap_table`: the 256 color lookup table to remap pixels with
+    pub fn remap_colors(&mut self, remap_table: &[u8; NUM_COLORS]) {
+        let width = self.width as usize;
+        for row in self.pixels.chunks_exact_mut(self.pitch as usize) {
+            for pixel in row[..width].iter_mut() {
+                *pixel = remap_table[*pixel as usize];
+            }
+        }
+    }
+
+    /// Converts this bitmap's pixel data so that it uses the closest matching colors from the
+    /// palette given, and then associates that palette with this bitmap. If this bitmap has no
+    /// associated palette, the pixel data cannot be converted and is left as-is.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to convert this bitmap's pixel data to
+    ///
+    /// returns: true if the pixel data was converted, false if this bitmap has no palette
+    pub fn remap_to_palette(&mut self, palette: &Palette) -> bool {
+        if let Some(current) = &self.palette {
+            if current != palette {
+                let remap_table = current.remap_table_to(palette);
+                self.remap_colors(&remap_table);
+            }
+            self.palette = Some(palette.clone());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns a reference to the raw pixels in this bitmap. If this bitmap has a pitch which is
+    /// larger than its width, this includes the padding bytes at the end of each row.
     #[inline]
     pub fn pixels(&self) -> &[u8] {
         &self.pixels
     }
 
-    /// Returns a mutable reference to the raw pixels in this bitmap.
+    /// Returns a mutable reference to the raw pixels in this bitmap. If this bitmap has a pitch
+    /// which is larger than its width, this includes the padding bytes at the end of each row.
     #[inline]
     pub fn pixels_mut(&mut self) -> &mut [u8] {
         &mut self.pixels
@@ -290,7 +565,7 @@ impl Bitmap {
     /// coordinates given are not checked for validity.
     #[inline]
     pub fn get_offset_to_xy(&self, x: i32, y: i32) -> usize {
-        ((y * self.width as i32) + x) as usize
+        ((y * self.pitch as i32) + x) as usize
     }
 
     /// Returns true if the coordinates given lie within the bitmaps clipping region.
@@ -311,9 +586,81 @@ impl Bitmap {
     /// * `dest`: destination 32-bit ARGB pixel buffer to copy converted pixels to
     /// * `palette`: the 256 colour palette to use during pixel conversion
     pub fn copy_as_argb_to(&self, dest: &mut [u32], palette: &Palette) {
-        for (src, dest) in self.pixels().iter().zip(dest.iter_mut()) {
-            *dest = palette[*src];
+        let width = self.width as usize;
+        let rows = self.pixels.chunks_exact(self.pitch as usize);
+        for (src_row, dest_row) in rows.zip(dest.chunks_mut(width)) {
+            for (src, dest) in src_row[0..width].iter().zip(dest_row.iter_mut()) {
+                *dest = palette[*src];
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(rename = "Bitmap")]
+struct SerializeBitmap<'a> {
+    width: u32,
+    height: u32,
+    pixels: Vec<&'a [u8]>,
+    palette: Option<&'a Palette>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Bitmap")]
+struct DeserializeBitmap {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec<u8>>,
+    palette: Option<Palette>,
+}
+
+/// Bitmaps are serialized as their dimensions, their pixels (as a sequence of rows, without any
+/// padding) and their associated palette, if any. The clipping region is not serialized, and is
+/// reset to the full bitmap when deserializing. Only available with the "serde" feature enabled.
+#[cfg(feature = "serde")]
+impl<P: PixelBuffer> serde::Serialize for Bitmap<P> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let width = self.width as usize;
+        let pitch = self.pitch as usize;
+        SerializeBitmap {
+            width: self.width,
+            height: self.height,
+            pixels: (0..self.height as usize)
+                .map(|y| &self.pixels[y * pitch..y * pitch + width])
+                .collect(),
+            palette: self.palette.as_ref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Bitmap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let data = DeserializeBitmap::deserialize(deserializer)?;
+        // checked before creating the bitmap so that its size is never taken from the dimensions
+        // alone, which may not match the amount of pixel data actually present
+        if data.pixels.len() != data.height as usize
+            || data
+                .pixels
+                .iter()
+                .any(|row| row.len() != data.width as usize)
+        {
+            return Err(D::Error::custom(
+                "Bitmap pixels do not match its dimensions",
+            ));
         }
+        let mut bitmap = Bitmap::new(data.width, data.height).map_err(D::Error::custom)?;
+        let rows = bitmap.pixels.chunks_exact_mut(bitmap.pitch as usize);
+        for (dest, src) in rows.zip(data.pixels.iter()) {
+            dest[0..src.len()].copy_from_slice(src);
+        }
+        bitmap.palette = data.palette;
+        Ok(bitmap)
     }
 }
 
@@ -373,6 +720,139 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn aligned_creation() {
+        assert_matches!(Bitmap::new_with_alignment(16, 8, 0), Err(BitmapError::InvalidDimensions));
+
+        let bmp = Bitmap::new(13, 4).unwrap();
+        assert_eq!(13, bmp.pitch());
+
+        let mut bmp = Bitmap::new_with_alignment(13, 4, 16).unwrap();
+        assert_eq!(13, bmp.width());
+        assert_eq!(16, bmp.pitch());
+        assert_eq!(64, bmp.pixels().len());
+        assert_eq!(16, bmp.get_offset_to_xy(0, 1));
+        assert_eq!(Rect::new(0, 0, 13, 4), *bmp.clip_region());
+
+        bmp.clear(0);
+        bmp.filled_rect(0, 0, 12, 3, 7);
+        for y in 0..4 {
+            let row = &bmp.pixels()[(y * 16)..((y + 1) * 16)];
+            assert_eq!(&[7u8; 13], &row[0..13]);
+            assert_eq!(&[0u8; 3], &row[13..16]);
+        }
+
+        let bmp = Bitmap::new_with_alignment(64, 2, 64).unwrap();
+        assert_eq!(64, bmp.pitch());
+    }
+
+    #[test]
+    pub fn borrowed_pixels() {
+        let mut buffer = vec![0u8; 32];
+        assert_matches!(
+            Bitmap::from_slice_mut(0, 4, &mut buffer),
+            Err(BitmapError::InvalidDimensions)
+        );
+        assert_matches!(
+            Bitmap::from_slice_mut(8, 5, &mut buffer),
+            Err(BitmapError::OutOfBounds)
+        );
+        assert_matches!(
+            Bitmap::from_slice_mut_with_pitch(8, 2, 4, &mut buffer),
+            Err(BitmapError::InvalidDimensions)
+        );
+
+        let mut src = Bitmap::new(2, 2).unwrap();
+        src.clear(9);
+
+        {
+            let mut bmp = Bitmap::from_slice_mut(8, 4, &mut buffer).unwrap();
+            assert_eq!(8, bmp.width());
+            assert_eq!(4, bmp.height());
+            bmp.clear(1);
+            bmp.set_pixel(0, 0, 2);
+            bmp.horiz_line(0, 7, 3, 4);
+            bmp.blit(BlitMethod::Solid, &src, 6, 0);
+        }
+
+        assert_eq!(&[2, 1, 1, 1, 1, 1, 9, 9], &buffer[0..8]);
+        assert_eq!(&[1, 1, 1, 1, 1, 1, 9, 9], &buffer[8..16]);
+        assert_eq!(&[4, 4, 4, 4, 4, 4, 4, 4], &buffer[24..32]);
+
+        let mut buffer = vec![0u8; 16];
+        {
+            let mut bmp = Bitmap::from_slice_mut_with_pitch(3, 2, 8, &mut buffer).unwrap();
+            bmp.clear(0);
+            bmp.filled_rect(0, 0, 2, 1, 5);
+        }
+        assert_eq!(&[5, 5, 5, 0, 0, 0, 0, 0, 5, 5, 5, 0, 0, 0, 0, 0], &buffer[..]);
+    }
+
+    #[test]
+    pub fn padding_is_left_untouched() {
+        let mut remap_table = [0u8; NUM_COLORS];
+        remap_table[1] = 2;
+
+        let mut bmp = Bitmap::new_with_alignment(3, 2, 4).unwrap();
+        bmp.pixels_mut().fill(9);
+        bmp.clear(1);
+        bmp.remap_colors(&remap_table);
+        assert_eq!(&[2, 2, 2, 9, 2, 2, 2, 9], bmp.pixels());
+
+        // bytes after the last row of a borrowed slice are not part of the bitmap
+        let mut buffer = vec![9u8; 12];
+        {
+            let mut bmp = Bitmap::from_slice_mut_with_pitch(3, 2, 4, &mut buffer).unwrap();
+            assert_eq!(8, bmp.pixels().len());
+            bmp.clear(1);
+            bmp.remap_colors(&remap_table);
+        }
+        assert_eq!(&[2, 2, 2, 9, 2, 2, 2, 9, 9, 9, 9, 9], &buffer[..]);
+
+        // pitch * height would wrap around to 1 if calculated as a u32
+        assert_matches!(
+            Bitmap::from_slice_mut_with_pitch(1, u32::MAX, u32::MAX, &mut buffer),
+            Err(BitmapError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    pub fn aligned_blits_and_conversion() {
+        let palette = Palette::new_vga_palette().unwrap();
+        let mut src = Bitmap::new_with_alignment(5, 3, 8).unwrap();
+        let mut expected = Bitmap::new(5, 3).unwrap();
+        for y in 0..3 {
+            for x in 0..5 {
+                src.set_pixel(x, y, (y * 5 + x) as u8);
+                expected.set_pixel(x, y, (y * 5 + x) as u8);
+            }
+        }
+
+        let mut dest = Bitmap::new_with_alignment(7, 5, 16).unwrap();
+        dest.blit(BlitMethod::Solid, &src, 1, 1);
+        for y in 0..3 {
+            for x in 0..5 {
+                assert_eq!(expected.get_pixel(x, y), dest.get_pixel(x + 1, y + 1));
+            }
+        }
+
+        dest.clear(0);
+        dest.blit(BlitMethod::Transparent(0), &src, 1, 1);
+        assert_eq!(Some(14), dest.get_pixel(5, 3));
+
+        dest.clear(0);
+        let method = BlitMethod::SolidFlipped { horizontal_flip: true, vertical_flip: true };
+        dest.blit(method, &src, 0, 0);
+        assert_eq!(Some(14), dest.get_pixel(0, 0));
+        assert_eq!(Some(0), dest.get_pixel(4, 2));
+
+        let mut argb = vec![0u32; 15];
+        src.copy_as_argb_to(&mut argb, &palette);
+        let mut expected_argb = vec![0u32; 15];
+        expected.copy_as_argb_to(&mut expected_argb, &palette);
+        assert_eq!(expected_argb, argb);
+    }
+
     #[test]
     pub fn copy_from() {
         let mut bmp = Bitmap::new(8, 8).unwrap();
@@ -451,6 +931,58 @@ pub mod tests {
         assert!(!bmp.is_xy_visible(16, 8));
     }
 
+    #[test]
+    pub fn clip_region_stack() {
+        let mut bmp = Bitmap::new(16, 8).unwrap();
+        assert_eq!(None, bmp.pop_clip_region());
+        assert_eq!(bmp.full_bounds(), *bmp.clip_region());
+
+        assert!(bmp.push_clip_region(&Rect::new(2, 2, 10, 4)));
+        assert_eq!(Rect::new(2, 2, 10, 4), *bmp.clip_region());
+
+        // nested push is limited to the current clip region
+        assert!(bmp.push_clip_region(&Rect::new(8, 0, 20, 20)));
+        assert_eq!(Rect::new(8, 2, 4, 4), *bmp.clip_region());
+        assert!(!bmp.is_xy_visible(7, 2));
+        assert!(bmp.is_xy_visible(8, 2));
+
+        // entirely outside of the current clip region, so nothing is pushed
+        assert!(!bmp.push_clip_region(&Rect::new(0, 0, 4, 4)));
+        assert_eq!(Rect::new(8, 2, 4, 4), *bmp.clip_region());
+
+        assert_eq!(Some(Rect::new(8, 2, 4, 4)), bmp.pop_clip_region());
+        assert_eq!(Rect::new(2, 2, 10, 4), *bmp.clip_region());
+        assert_eq!(Some(Rect::new(2, 2, 10, 4)), bmp.pop_clip_region());
+        assert_eq!(bmp.full_bounds(), *bmp.clip_region());
+        assert_eq!(None, bmp.pop_clip_region());
+    }
+
+    #[test]
+    pub fn palette_association_and_remapping() {
+        let mut bmp = Bitmap::new(4, 1).unwrap();
+        bmp.pixels_mut().copy_from_slice(&[0, 1, 2, 3]);
+        assert!(bmp.palette().is_none());
+        assert!(!bmp.remap_to_palette(&Palette::new()));
+
+        let mut source_palette = Palette::new();
+        source_palette[1] = to_rgb32(255, 0, 0);
+        source_palette[2] = to_rgb32(0, 255, 0);
+        source_palette[3] = to_rgb32(0, 0, 255);
+        let mut dest_palette = Palette::new();
+        dest_palette[10] = to_rgb32(0, 0, 250);
+        dest_palette[11] = to_rgb32(250, 0, 0);
+        dest_palette[12] = to_rgb32(0, 250, 0);
+
+        bmp.set_palette(Some(source_palette.clone()));
+        assert_eq!(Some(&source_palette), bmp.palette());
+        assert!(bmp.remap_to_palette(&dest_palette));
+        assert_eq!(&[0, 11, 12, 10], bmp.pixels());
+        assert_eq!(Some(&dest_palette), bmp.palette());
+
+        bmp.set_palette(None);
+        assert!(bmp.palette().is_none());
+    }
+
     #[test]
     pub fn pixels_at() {
         let mut bmp = Bitmap::new(8, 8).unwrap();
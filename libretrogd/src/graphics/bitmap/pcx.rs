@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Cursor, Seek, SeekFrom};
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
 use std::path::Path;
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
@@ -90,6 +90,34 @@ impl PcxHeader {
     }
 }
 
+/// The pixel format to use when saving PCX files.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PcxFormat {
+    /// 8-bit indexed color, with the 256 color palette appended to the end of the file.
+    Indexed8,
+    /// 24-bit truecolor, with the palette applied to each pixel as it is written. Useful for
+    /// viewers which do not handle VGA-style palettes well, at the cost of larger files.
+    TrueColor24,
+}
+
+/// Options controlling how PCX files are saved. The default is 8-bit indexed color with RLE
+/// compression, which is the format expected by [Bitmap::load_pcx_bytes].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PcxSaveOptions {
+    pub format: PcxFormat,
+    /// Whether scanlines are RLE compressed or stored as-is.
+    pub compressed: bool,
+}
+
+impl Default for PcxSaveOptions {
+    fn default() -> Self {
+        PcxSaveOptions {
+            format: PcxFormat::Indexed8,
+            compressed: true,
+        }
+    }
+}
+
 fn write_pcx_data<T: WriteBytesExt>(
     writer: &mut T,
     run_count: u8,
@@ -102,6 +130,73 @@ fn write_pcx_data<T: WriteBytesExt>(
     Ok(())
 }
 
+fn write_pcx_scanline<T: WriteBytesExt>(
+    writer: &mut T,
+    scanline: &[u8],
+    compressed: bool,
+) -> Result<(), PcxError> {
+    if !compressed {
+        writer.write_all(scanline)?;
+        return Ok(());
+    }
+
+    // runs that could have continued on to the next scanline are broken up, as per the pcx
+    // standard
+    let mut run_count = 0;
+    let mut run_pixel = 0;
+
+    for &pixel in scanline.iter() {
+        if run_count == 0 {
+            run_count = 1;
+            run_pixel = pixel;
+        } else {
+            if (pixel != run_pixel) || (run_count >= 63) {
+                write_pcx_data(writer, run_count, run_pixel)?;
+                run_count = 1;
+                run_pixel = pixel;
+            } else {
+                run_count += 1;
+            }
+        }
+    }
+
+    // end the scanline, writing out whatever run we might have had going
+    if run_count > 0 {
+        write_pcx_data(writer, run_count, run_pixel)?;
+    }
+    Ok(())
+}
+
+/// Reads all of the (possibly RLE compressed) scanlines, decoding them into the given buffer. Runs
+/// which continue across scanlines are handled, even though they are not supposed to be.
+fn read_pcx_scanlines<T: ReadBytesExt>(
+    reader: &mut T,
+    scanlines: &mut [u8],
+    compressed: bool,
+) -> Result<(), PcxError> {
+    if !compressed {
+        reader.read_exact(scanlines)?;
+        return Ok(());
+    }
+
+    let mut x = 0;
+    while x < scanlines.len() {
+        // read pixel or RLE count
+        let data = reader.read_u8()?;
+        let (count, pixel) = if (data & 0xc0) == 0xc0 {
+            // it was an RLE count, actual pixel is the next byte ...
+            ((data & 0x3f) as usize, reader.read_u8()?)
+        } else {
+            // it was just a single pixel
+            (1, data)
+        };
+        let count = count.min(scanlines.len() - x);
+        scanlines[x..x + count].fill(pixel);
+        x += count;
+    }
+    Ok(())
+}
+
 impl Bitmap {
     pub fn load_pcx_bytes<T: ReadBytesExt + Seek>(
         reader: &mut T,
@@ -118,12 +213,10 @@ impl Bitmap {
                 "Only version 5 PCX files are supported",
             )));
         }
-        if header.encoding != 1 {
-            return Err(PcxError::BadFile(String::from(
-                "Only RLE-compressed PCX files are supported",
-            )));
+        if header.encoding > 1 {
+            return Err(PcxError::BadFile(String::from("Unsupported PCX encoding")));
         }
-        if header.bpp != 8 {
+        if header.bpp != 8 || header.num_color_planes != 1 {
             return Err(PcxError::BadFile(String::from(
                 "Only 8-bit indexed (256 color palette) PCX files are supported",
             )));
@@ -138,40 +231,23 @@ impl Bitmap {
 
         let width = (header.x2 + 1) as u32;
         let height = (header.y2 + 1) as u32;
+        let bytes_per_line = header.bytes_per_line as usize;
+        if bytes_per_line < width as usize {
+            return Err(PcxError::BadFile(String::from(
+                "Invalid PCX bytes per line",
+            )));
+        }
         let mut bmp = Bitmap::new(width, height).unwrap();
-        let mut writer = Cursor::new(bmp.pixels_mut());
-
-        for _y in 0..height {
-            // read the next scanline's worth of pixels from the PCX file
-            let mut x: u32 = 0;
-            while x < (header.bytes_per_line as u32) {
-                let mut data: u8;
-                let mut count: u32;
-
-                // read pixel or RLE count
-                data = reader.read_u8()?;
-
-                if (data & 0xc0) == 0xc0 {
-                    // it was an RLE count, actual pixel is the next byte ...
-                    count = (data & 0x3f) as u32;
-                    data = reader.read_u8()?;
-                } else {
-                    // it was just a single pixel
-                    count = 1;
-                }
-
-                // write the current pixel value 'data' to the bitmap 'count' number of times
-                while count > 0 {
-                    if x <= width {
-                        writer.write_u8(data)?;
-                    } else {
-                        writer.seek(SeekFrom::Current(1))?;
-                    }
 
-                    x += 1;
-                    count -= 1;
-                }
-            }
+        let mut scanlines = vec![0u8; bytes_per_line * height as usize];
+        read_pcx_scanlines(reader, &mut scanlines, header.encoding == 1)?;
+        // scanlines may be padded, so only copy as much of each as the bitmap needs
+        for (dest, src) in bmp
+            .pixels_mut()
+            .chunks_exact_mut(width as usize)
+            .zip(scanlines.chunks_exact(bytes_per_line))
+        {
+            dest.copy_from_slice(&src[0..width as usize]);
         }
 
         // now read the palette data located at the end of the PCX file
@@ -203,10 +279,35 @@ impl Bitmap {
         writer: &mut T,
         palette: &Palette,
     ) -> Result<(), PcxError> {
+        self.to_pcx_bytes_with_options(writer, palette, &PcxSaveOptions::default())
+    }
+
+    /// Writes this bitmap out as a PCX file using the given options.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the PCX file to
+    /// * `palette`: the palette to save with, or apply to, the bitmap's pixels
+    /// * `options`: the pixel format and compression to use
+    ///
+    /// returns: `Result<(), PcxError>`
+    pub fn to_pcx_bytes_with_options<T: WriteBytesExt>(
+        &self,
+        writer: &mut T,
+        palette: &Palette,
+        options: &PcxSaveOptions,
+    ) -> Result<(), PcxError> {
+        // the pcx standard requires an even number of bytes per scanline
+        let bytes_per_line = (self.width + 1) & !1;
+        let num_color_planes = match options.format {
+            PcxFormat::Indexed8 => 1,
+            PcxFormat::TrueColor24 => 3,
+        };
+
         let header = PcxHeader {
             manufacturer: 10,
             version: 5,
-            encoding: 1,
+            encoding: if options.compressed { 1 } else { 0 },
             bpp: 8,
             x1: 0,
             y1: 0,
@@ -216,8 +317,8 @@ impl Bitmap {
             vertical_dpi: 200,
             ega_palette: [0u8; 48],
             reserved: 0,
-            num_color_planes: 1,
-            bytes_per_line: self.width() as u16,
+            num_color_planes,
+            bytes_per_line: bytes_per_line as u16,
             palette_type: 1,
             horizontal_size: self.width() as u16,
             vertical_size: self.height() as u16,
@@ -226,60 +327,68 @@ impl Bitmap {
         header.write(writer)?;
 
         let pixels = self.pixels();
-        let mut i = 0;
-
-        for _y in 0..=self.bottom() {
-            // write one scanline at a time. breaking runs that could have continued across
-            // scanlines in the process, as per the pcx standard
-
-            let mut run_count = 0;
-            let mut run_pixel = 0;
-
-            for _x in 0..=self.right() {
-                let pixel = pixels[i];
-                i += 1;
-
-                if run_count == 0 {
-                    run_count = 1;
-                    run_pixel = pixel;
-                } else {
-                    if (pixel != run_pixel) || (run_count >= 63) {
-                        write_pcx_data(writer, run_count, run_pixel)?;
-                        run_count = 1;
-                        run_pixel = pixel;
-                    } else {
-                        run_count += 1;
+        let mut scanline = vec![0u8; bytes_per_line as usize];
+
+        for y in 0..=self.bottom() {
+            // write one scanline at a time. truecolor images have a separate scanline for each
+            // of the red, green and blue components of the row
+            let row_start = self.get_offset_to_xy(0, y as i32);
+            let row = &pixels[row_start..(row_start + self.width as usize)];
+
+            match options.format {
+                PcxFormat::Indexed8 => {
+                    scanline[0..row.len()].copy_from_slice(row);
+                    write_pcx_scanline(writer, &scanline, options.compressed)?;
+                }
+                PcxFormat::TrueColor24 => {
+                    for component in 0..3 {
+                        for (dest, &pixel) in scanline.iter_mut().zip(row.iter()) {
+                            let (r, g, b) = from_rgb32(palette[pixel]);
+                            *dest = [r, g, b][component];
+                        }
+                        write_pcx_scanline(writer, &scanline, options.compressed)?;
                     }
                 }
             }
-
-            // end the scanline, writing out whatever run we might have had going
-            write_pcx_data(writer, run_count, run_pixel)?;
         }
 
-        // marker for beginning of palette data
-        writer.write_u8(0xc)?;
+        if options.format == PcxFormat::Indexed8 {
+            // marker for beginning of palette data
+            writer.write_u8(0xc)?;
 
-        for i in 0..=255 {
-            let argb = palette[i];
-            let (r, g, b) = from_rgb32(argb);
-            writer.write_u8(r)?;
-            writer.write_u8(g)?;
-            writer.write_u8(b)?;
+            for i in 0..=255 {
+                let argb = palette[i];
+                let (r, g, b) = from_rgb32(argb);
+                writer.write_u8(r)?;
+                writer.write_u8(g)?;
+                writer.write_u8(b)?;
+            }
         }
 
         Ok(())
     }
 
     pub fn to_pcx_file(&self, path: &Path, palette: &Palette) -> Result<(), PcxError> {
+        self.to_pcx_file_with_options(path, palette, &PcxSaveOptions::default())
+    }
+
+    pub fn to_pcx_file_with_options(
+        &self,
+        path: &Path,
+        palette: &Palette,
+        options: &PcxSaveOptions,
+    ) -> Result<(), PcxError> {
         let f = File::create(path)?;
         let mut writer = BufWriter::new(f);
-        self.to_pcx_bytes(&mut writer, palette)
+        self.to_pcx_bytes_with_options(&mut writer, palette, options)
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use std::io::Cursor;
+
+    use claim::assert_matches;
     use tempfile::TempDir;
 
     use super::*;
@@ -311,6 +420,66 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn save_with_options() -> Result<(), PcxError> {
+        let dp2_palette =
+            Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal)
+                .unwrap();
+        let tmp_dir = TempDir::new()?;
+
+        // odd width, so scanlines need padding
+        let mut bmp = Bitmap::new(15, 4).unwrap();
+        for (i, pixel) in bmp.pixels_mut().iter_mut().enumerate() {
+            *pixel = if i % 4 == 0 { 15 } else { 120 + (i % 8) as u8 };
+        }
+
+        for compressed in [true, false] {
+            let options = PcxSaveOptions {
+                format: PcxFormat::Indexed8,
+                compressed,
+            };
+            let save_path = tmp_dir.path().join("test_save_indexed.pcx");
+            bmp.to_pcx_file_with_options(&save_path, &dp2_palette, &options)?;
+            let (reloaded_bmp, reloaded_palette) = Bitmap::load_pcx_file(&save_path)?;
+            assert_eq!(bmp, reloaded_bmp);
+            assert_eq!(dp2_palette, reloaded_palette);
+        }
+
+        for compressed in [true, false] {
+            let options = PcxSaveOptions {
+                format: PcxFormat::TrueColor24,
+                compressed,
+            };
+            let mut data = Vec::new();
+            bmp.to_pcx_bytes_with_options(&mut data, &dp2_palette, &options)?;
+            let header = PcxHeader::read(&mut data.as_slice())?;
+            assert_eq!(3, header.num_color_planes);
+            assert_eq!(16, { header.bytes_per_line });
+            assert_eq!(if compressed { 1 } else { 0 }, header.encoding);
+
+            // no palette is appended to truecolor files
+            let mut scanlines = vec![0u8; 16 * 3 * 4];
+            let mut reader = &data[128..];
+            read_pcx_scanlines(&mut reader, &mut scanlines, compressed)?;
+            assert!(reader.is_empty());
+            for y in 0..4 {
+                for x in 0..15 {
+                    let color = dp2_palette[bmp.get_pixel(x, y).unwrap()];
+                    let line = &scanlines[(y as usize * 3 * 16)..];
+                    let x = x as usize;
+                    assert_eq!(from_rgb32(color), (line[x], line[16 + x], line[32 + x]));
+                }
+            }
+
+            assert_matches!(
+                Bitmap::load_pcx_bytes(&mut Cursor::new(data)),
+                Err(PcxError::BadFile(..))
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     pub fn load_larger_image() -> Result<(), PcxError> {
         let (bmp, _palette) = Bitmap::load_pcx_file(Path::new("./test-assets/test_image.pcx"))?;
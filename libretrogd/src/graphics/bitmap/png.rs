@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use thiserror::Error;
+
+use crate::graphics::*;
+use crate::utils::bytes::ReadFixedLengthByteArray;
+use crate::NUM_COLORS;
+
+const PNG_FILE_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+// the largest width or height allowed by the PNG specification
+const PNG_MAX_DIMENSION: u32 = i32::MAX as u32;
+// zlib cannot compress data to much less than 1/1032 of its size, so no more than this much
+// memory is reserved up-front for decompressing image data, whatever size the header claims
+const ZLIB_MAX_COMPRESSION_RATIO: usize = 1032;
+// the largest chunk data length allowed by the PNG specification
+const PNG_MAX_CHUNK_SIZE: u32 = i32::MAX as u32;
+
+#[derive(Error, Debug)]
+pub enum PngError {
+    #[error("Bad or unsupported PNG file: {0}")]
+    BadFile(String),
+
+    #[error("A palette is required to load non-indexed PNG files")]
+    PaletteRequired,
+
+    #[error("PNG I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ColorType {
+    Grayscale = 0,
+    Rgb = 2,
+    Indexed = 3,
+    GrayscaleAlpha = 4,
+    Rgba = 6,
+}
+
+impl ColorType {
+    pub fn from(value: u8) -> Result<Self, PngError> {
+        use ColorType::*;
+        match value {
+            0 => Ok(Grayscale),
+            2 => Ok(Rgb),
+            3 => Ok(Indexed),
+            4 => Ok(GrayscaleAlpha),
+            6 => Ok(Rgba),
+            _ => Err(PngError::BadFile(format!(
+                "Unsupported color type: {}",
+                value
+            ))),
+        }
+    }
+
+    pub fn num_channels(&self) -> usize {
+        use ColorType::*;
+        match self {
+            Grayscale | Indexed => 1,
+            GrayscaleAlpha => 2,
+            Rgb => 3,
+            Rgba => 4,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ChunkHeader {
+    size: u32,
+    name: [u8; 4],
+}
+
+impl ChunkHeader {
+    pub fn read<T: ReadBytesExt>(reader: &mut T) -> Result<Self, PngError> {
+        Ok(ChunkHeader {
+            size: reader.read_u32::<BigEndian>()?,
+            name: reader.read_bytes()?,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ImageHeaderChunk {
+    width: u32,
+    height: u32,
+    bpp: u8,
+    color_type: ColorType,
+    compression_method: u8,
+    filter_method: u8,
+    interlace_method: u8,
+}
+
+impl ImageHeaderChunk {
+    pub fn read(data: &[u8]) -> Result<Self, PngError> {
+        let mut reader = data;
+        Ok(ImageHeaderChunk {
+            width: reader.read_u32::<BigEndian>()?,
+            height: reader.read_u32::<BigEndian>()?,
+            bpp: reader.read_u8()?,
+            color_type: ColorType::from(reader.read_u8()?)?,
+            compression_method: reader.read_u8()?,
+            filter_method: reader.read_u8()?,
+            interlace_method: reader.read_u8()?,
+        })
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>, PngError> {
+        let mut data = Vec::with_capacity(13);
+        data.write_u32::<BigEndian>(self.width)?;
+        data.write_u32::<BigEndian>(self.height)?;
+        data.write_u8(self.bpp)?;
+        data.write_u8(self.color_type as u8)?;
+        data.write_u8(self.compression_method)?;
+        data.write_u8(self.filter_method)?;
+        data.write_u8(self.interlace_method)?;
+        Ok(data)
+    }
+
+    /// Returns the number of bytes occupied by a single (unfiltered) scanline of pixels, or
+    /// `None` if it is too large to be addressed.
+    fn scanline_length(&self) -> Option<usize> {
+        let bits = (self.width as usize)
+            .checked_mul(self.color_type.num_channels() * self.bpp as usize)?;
+        Some(bits.div_ceil(8))
+    }
+
+    /// Returns the number of bytes of (filtered) image data needed for the whole image, which is
+    /// each scanline preceded by its filter type byte, or `None` if it is too large to be
+    /// addressed.
+    fn image_data_length(&self) -> Option<usize> {
+        self.scanline_length()?
+            .checked_add(1)?
+            .checked_mul(self.height as usize)
+    }
+
+    /// Returns the number of bytes per complete pixel, rounded up to one, as used by the
+    /// scanline filters.
+    fn filter_bytes_per_pixel(&self) -> usize {
+        ((self.color_type.num_channels() * self.bpp as usize) / 8).max(1)
+    }
+}
+
+fn read_chunk_data<T: ReadBytesExt>(
+    reader: &mut T,
+    header: &ChunkHeader,
+) -> Result<Vec<u8>, PngError> {
+    if header.size > PNG_MAX_CHUNK_SIZE {
+        return Err(PngError::BadFile(format!(
+            "Chunk {:?} is too large: {}",
+            header.name, header.size
+        )));
+    }
+    // read only as much as is actually present, rather than trusting the chunk's length up-front
+    let mut data = Vec::new();
+    reader.take(header.size as u64).read_to_end(&mut data)?;
+    if data.len() != header.size as usize {
+        return Err(PngError::BadFile(format!(
+            "Chunk {:?} is truncated: expected {} bytes, found {}",
+            header.name,
+            header.size,
+            data.len()
+        )));
+    }
+
+    let expected_crc = reader.read_u32::<BigEndian>()?;
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header.name);
+    hasher.update(&data);
+    if hasher.finalize() != expected_crc {
+        return Err(PngError::BadFile(format!(
+            "Chunk {:?} has a bad CRC",
+            String::from_utf8_lossy(&header.name)
+        )));
+    }
+
+    Ok(data)
+}
+
+fn write_chunk<T: WriteBytesExt>(
+    writer: &mut T,
+    name: &[u8; 4],
+    data: &[u8],
+) -> Result<(), PngError> {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(name);
+    hasher.update(data);
+
+    writer.write_u32::<BigEndian>(data.len() as u32)?;
+    writer.write_all(name)?;
+    writer.write_all(data)?;
+    writer.write_u32::<BigEndian>(hasher.finalize())?;
+    Ok(())
+}
+
+#[inline]
+fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reverses the filter applied to a single scanline, in place. `previous` is the already
+/// unfiltered previous scanline (all zeros for the first scanline).
+fn unfilter_scanline(
+    filter: u8,
+    bpp: usize,
+    scanline: &mut [u8],
+    previous: &[u8],
+) -> Result<(), PngError> {
+    match filter {
+        0 => {}
+        1 => {
+            for i in bpp..scanline.len() {
+                scanline[i] = scanline[i].wrapping_add(scanline[i - bpp]);
+            }
+        }
+        2 => {
+            for (pixel, above) in scanline.iter_mut().zip(previous.iter()) {
+                *pixel = pixel.wrapping_add(*above);
+            }
+        }
+        3 => {
+            for i in 0..scanline.len() {
+                let left = if i >= bpp {
+                    scanline[i - bpp] as u16
+                } else {
+                    0
+                };
+                let above = previous[i] as u16;
+                scanline[i] = scanline[i].wrapping_add(((left + above) / 2) as u8);
+            }
+        }
+        4 => {
+            for i in 0..scanline.len() {
+                let (left, upper_left) = if i >= bpp {
+                    (scanline[i - bpp], previous[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                scanline[i] =
+                    scanline[i].wrapping_add(paeth_predictor(left, previous[i], upper_left));
+            }
+        }
+        _ => {
+            return Err(PngError::BadFile(format!(
+                "Unsupported scanline filter: {}",
+                filter
+            )))
+        }
+    }
+    Ok(())
+}
+
+impl Bitmap {
+    /// Loads a PNG image. 8-bit indexed images (as well as 1, 2 and 4-bit indexed images) are
+    /// loaded as-is along with their palette. Grayscale, Rgb and Rgba images can only be loaded
+    /// if a palette is provided, in which case each pixel is converted to the closest matching
+    /// color in that palette (any alpha channel is ignored) and that palette is returned. 16-bit
+    /// and interlaced images are not supported.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the PNG image from
+    /// * `palette`: the palette to convert non-indexed images with, if any
+    ///
+    /// returns: `Result<(Bitmap, Palette), PngError>`
+    pub fn load_png_bytes<T: ReadBytesExt>(
+        reader: &mut T,
+        palette: Option<&Palette>,
+    ) -> Result<(Bitmap, Palette), PngError> {
+        let signature: [u8; 8] = reader.read_bytes()?;
+        if signature != PNG_FILE_SIGNATURE {
+            return Err(PngError::BadFile(String::from("Missing PNG signature")));
+        }
+
+        let chunk_header = ChunkHeader::read(reader)?;
+        if chunk_header.name != *b"IHDR" {
+            return Err(PngError::BadFile(String::from("Expected IHDR chunk first")));
+        }
+        let header = ImageHeaderChunk::read(&read_chunk_data(reader, &chunk_header)?)?;
+
+        if header.width == 0
+            || header.height == 0
+            || header.width > PNG_MAX_DIMENSION
+            || header.height > PNG_MAX_DIMENSION
+        {
+            return Err(PngError::BadFile(String::from(
+                "Invalid PNG image dimensions",
+            )));
+        }
+        if header.compression_method != 0 || header.filter_method != 0 {
+            return Err(PngError::BadFile(String::from(
+                "Unknown compression or filter method",
+            )));
+        }
+        if header.interlace_method != 0 {
+            return Err(PngError::BadFile(String::from(
+                "Interlaced PNG files are not supported",
+            )));
+        }
+        let valid_bpp = match header.color_type {
+            ColorType::Indexed => matches!(header.bpp, 1 | 2 | 4 | 8),
+            _ => header.bpp == 8,
+        };
+        if !valid_bpp {
+            return Err(PngError::BadFile(format!(
+                "Unsupported bit depth {} for color type {:?}",
+                header.bpp, header.color_type
+            )));
+        }
+        if header.color_type != ColorType::Indexed && palette.is_none() {
+            return Err(PngError::PaletteRequired);
+        }
+
+        let mut file_palette: Option<Palette> = None;
+        let mut compressed_data = Vec::new();
+
+        loop {
+            let chunk_header = ChunkHeader::read(reader)?;
+            let data = read_chunk_data(reader, &chunk_header)?;
+            match &chunk_header.name {
+                b"PLTE" => {
+                    if data.len() % 3 != 0 || data.len() > NUM_COLORS * 3 {
+                        return Err(PngError::BadFile(String::from("Invalid PLTE chunk size")));
+                    }
+                    let mut loaded_palette = Palette::new_with_default(0, 0, 0);
+                    for (index, rgb) in data.chunks_exact(3).enumerate() {
+                        loaded_palette[index as u8] = to_rgb32(rgb[0], rgb[1], rgb[2]);
+                    }
+                    file_palette = Some(loaded_palette);
+                }
+                b"IDAT" => compressed_data.extend_from_slice(&data),
+                b"IEND" => break,
+                name if name[0].is_ascii_uppercase() => {
+                    return Err(PngError::BadFile(format!(
+                        "Unsupported critical chunk {:?}",
+                        String::from_utf8_lossy(name)
+                    )));
+                }
+                // skip over any other ancillary chunks
+                _ => {}
+            }
+        }
+
+        let palette = match header.color_type {
+            ColorType::Indexed => match file_palette {
+                Some(palette) => palette,
+                None => return Err(PngError::BadFile(String::from("Missing PLTE chunk"))),
+            },
+            // checked above that a palette was provided for non-indexed images
+            _ => palette.unwrap().clone(),
+        };
+
+        let (scanline_length, image_data_length) =
+            match (header.scanline_length(), header.image_data_length()) {
+                (Some(scanline_length), Some(image_data_length)) => {
+                    (scanline_length, image_data_length)
+                }
+                _ => {
+                    return Err(PngError::BadFile(String::from(
+                        "PNG image dimensions are too large",
+                    )))
+                }
+            };
+        // the image data is only trusted to be as large as the header claims once it has actually
+        // been decompressed, and anything past the end of it is never needed
+        let capacity = compressed_data
+            .len()
+            .saturating_mul(ZLIB_MAX_COMPRESSION_RATIO);
+        let mut image_data = Vec::with_capacity(image_data_length.min(capacity));
+        ZlibDecoder::new(compressed_data.as_slice())
+            .take(image_data_length as u64)
+            .read_to_end(&mut image_data)?;
+        if image_data.len() < image_data_length {
+            return Err(PngError::BadFile(String::from("Not enough image data")));
+        }
+
+        let mut bmp = match Bitmap::new(header.width, header.height) {
+            Ok(bmp) => bmp,
+            Err(error) => return Err(PngError::BadFile(error.to_string())),
+        };
+        let bpp = header.filter_bytes_per_pixel();
+        let mut previous = vec![0u8; scanline_length];
+        let mut color_cache = HashMap::new();
+
+        for (y, filtered) in image_data
+            .chunks_exact_mut(scanline_length + 1)
+            .take(header.height as usize)
+            .enumerate()
+        {
+            let (filter, scanline) = filtered.split_at_mut(1);
+            unfilter_scanline(filter[0], bpp, scanline, &previous)?;
+
+            let mut find_color = |r: u8, g: u8, b: u8| {
+                *color_cache
+                    .entry((r, g, b))
+                    .or_insert_with(|| palette.find_color(r, g, b))
+            };
+
+            let dest = bmp.pixels_at_mut(0, y as i32).unwrap();
+            let dest = &mut dest[0..header.width as usize];
+            match header.color_type {
+                ColorType::Indexed => {
+                    let pixels_per_byte = 8 / header.bpp as usize;
+                    let mask = ((1u16 << header.bpp) - 1) as u8;
+                    for (x, pixel) in dest.iter_mut().enumerate() {
+                        let byte = scanline[x / pixels_per_byte];
+                        let shift = 8 - header.bpp as usize * (1 + x % pixels_per_byte);
+                        *pixel = (byte >> shift) & mask;
+                    }
+                }
+                ColorType::Grayscale | ColorType::GrayscaleAlpha => {
+                    let channels = header.color_type.num_channels();
+                    for (pixel, src) in dest.iter_mut().zip(scanline.chunks_exact(channels)) {
+                        *pixel = find_color(src[0], src[0], src[0]);
+                    }
+                }
+                ColorType::Rgb | ColorType::Rgba => {
+                    let channels = header.color_type.num_channels();
+                    for (pixel, src) in dest.iter_mut().zip(scanline.chunks_exact(channels)) {
+                        *pixel = find_color(src[0], src[1], src[2]);
+                    }
+                }
+            }
+
+            previous.copy_from_slice(scanline);
+        }
+
+        Ok((bmp, palette))
+    }
+
+    pub fn load_png_file(
+        path: &Path,
+        palette: Option<&Palette>,
+    ) -> Result<(Bitmap, Palette), PngError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_png_bytes(&mut reader, palette)
+    }
+
+    /// Writes this bitmap as an 8-bit indexed PNG image using the given palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the PNG image to
+    /// * `palette`: the palette to be written along with the bitmap's pixels
+    ///
+    /// returns: `Result<(), PngError>`
+    pub fn to_png_bytes<T: WriteBytesExt>(
+        &self,
+        writer: &mut T,
+        palette: &Palette,
+    ) -> Result<(), PngError> {
+        writer.write_all(&PNG_FILE_SIGNATURE)?;
+
+        let header = ImageHeaderChunk {
+            width: self.width(),
+            height: self.height(),
+            bpp: 8,
+            color_type: ColorType::Indexed,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        write_chunk(writer, b"IHDR", &header.write()?)?;
+
+        let mut palette_data = Vec::with_capacity(NUM_COLORS * 3);
+        for i in 0..=255 {
+            let (r, g, b) = from_rgb32(palette[i]);
+            palette_data.extend_from_slice(&[r, g, b]);
+        }
+        write_chunk(writer, b"PLTE", &palette_data)?;
+
+        // each scanline is written out unfiltered (filter type 0)
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for y in 0..self.height() as i32 {
+            let row = self.pixels_at(0, y).unwrap();
+            encoder.write_u8(0)?;
+            encoder.write_all(&row[0..self.width() as usize])?;
+        }
+        write_chunk(writer, b"IDAT", &encoder.finish()?)?;
+
+        write_chunk(writer, b"IEND", &[])?;
+        Ok(())
+    }
+
+    pub fn to_png_file(&self, path: &Path, palette: &Palette) -> Result<(), PngError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_png_bytes(&mut writer, palette)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    pub static TEST_BMP_PIXELS_RAW: &[u8] =
+        include_bytes!("../../../test-assets/test_bmp_pixels_raw.bin");
+
+    #[test]
+    pub fn load_and_save() -> Result<(), PngError> {
+        let dp2_palette =
+            Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal)
+                .unwrap();
+        let tmp_dir = TempDir::new()?;
+
+        let (bmp, palette) =
+            Bitmap::load_png_file(Path::new("./test-assets/test_indexed.png"), None)?;
+        assert_eq!(16, bmp.width());
+        assert_eq!(16, bmp.height());
+        assert_eq!(bmp.pixels(), TEST_BMP_PIXELS_RAW);
+        assert_eq!(palette, dp2_palette);
+
+        let save_path = tmp_dir.path().join("test_save.png");
+        bmp.to_png_file(&save_path, &palette)?;
+        let (reloaded_bmp, reloaded_palette) = Bitmap::load_png_file(&save_path, None)?;
+        assert_eq!(16, reloaded_bmp.width());
+        assert_eq!(16, reloaded_bmp.height());
+        assert_eq!(reloaded_bmp.pixels(), TEST_BMP_PIXELS_RAW);
+        assert_eq!(reloaded_palette, dp2_palette);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_truecolor_with_palette() -> Result<(), PngError> {
+        let dp2_palette =
+            Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal)
+                .unwrap();
+
+        assert_matches!(
+            Bitmap::load_png_file(Path::new("./test-assets/test_rgb.png"), None),
+            Err(PngError::PaletteRequired)
+        );
+
+        for file in ["./test-assets/test_rgb.png", "./test-assets/test_rgba.png"] {
+            let (bmp, palette) = Bitmap::load_png_file(Path::new(file), Some(&dp2_palette))?;
+            assert_eq!(16, bmp.width());
+            assert_eq!(16, bmp.height());
+            assert_eq!(bmp.pixels(), TEST_BMP_PIXELS_RAW);
+            assert_eq!(palette, dp2_palette);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_bad_files() {
+        let mut data: &[u8] = b"definitely not a png file";
+        assert_matches!(
+            Bitmap::load_png_bytes(&mut data, None),
+            Err(PngError::BadFile(..))
+        );
+
+        let mut data = std::fs::read("./test-assets/test_indexed.png").unwrap();
+        // corrupt a byte within the IHDR chunk so that its CRC no longer matches
+        data[20] ^= 0xff;
+        assert_matches!(
+            Bitmap::load_png_bytes(&mut data.as_slice(), None),
+            Err(PngError::BadFile(..))
+        );
+    }
+
+    #[test]
+    pub fn load_files_with_unreasonable_dimensions() -> Result<(), PngError> {
+        fn png_with_dimensions(width: u32, height: u32) -> Result<Vec<u8>, PngError> {
+            let header = ImageHeaderChunk {
+                width,
+                height,
+                bpp: 8,
+                color_type: ColorType::Indexed,
+                compression_method: 0,
+                filter_method: 0,
+                interlace_method: 0,
+            };
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&[0u8; 64])?;
+
+            let mut data = PNG_FILE_SIGNATURE.to_vec();
+            write_chunk(&mut data, b"IHDR", &header.write()?)?;
+            write_chunk(&mut data, b"PLTE", &[0u8; 3])?;
+            write_chunk(&mut data, b"IDAT", &encoder.finish()?)?;
+            write_chunk(&mut data, b"IEND", &[])?;
+            Ok(data)
+        }
+
+        let data = png_with_dimensions(7, 7)?;
+        let (bmp, _) = Bitmap::load_png_bytes(&mut data.as_slice(), None)?;
+        assert_eq!(7, bmp.width());
+
+        // much larger than the image data actually present
+        for (width, height) in [
+            (8, 8),
+            (PNG_MAX_DIMENSION, PNG_MAX_DIMENSION),
+            (u32::MAX, 1),
+            (1, u32::MAX),
+        ] {
+            let data = png_with_dimensions(width, height)?;
+            assert_matches!(
+                Bitmap::load_png_bytes(&mut data.as_slice(), None),
+                Err(PngError::BadFile(..))
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_files_with_bad_chunk_lengths() -> Result<(), PngError> {
+        for size in [u32::MAX, PNG_MAX_CHUNK_SIZE, 64] {
+            let mut data = PNG_FILE_SIGNATURE.to_vec();
+            data.write_u32::<BigEndian>(size)?;
+            data.write_all(b"IHDR")?;
+            data.write_all(&[0u8; 13])?;
+            assert_matches!(
+                Bitmap::load_png_bytes(&mut data.as_slice(), None),
+                Err(PngError::BadFile(..))
+            );
+        }
+
+        Ok(())
+    }
+}
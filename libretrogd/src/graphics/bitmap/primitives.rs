@@ -3,10 +3,18 @@ use std::mem::swap;
 use crate::graphics::*;
 use crate::math::*;
 
-impl Bitmap {
+impl<P: PixelBuffer> Bitmap<P> {
     /// Fills the entire bitmap with the given color.
     pub fn clear(&mut self, color: u8) {
-        self.pixels.fill(color);
+        if self.pitch == self.width {
+            self.pixels.fill(color);
+        } else {
+            // leave the padding bytes at the end of each row alone
+            let width = self.width as usize;
+            for row in self.pixels.chunks_exact_mut(self.pitch as usize) {
+                row[..width].fill(color);
+            }
+        }
     }
 
     /// Sets the pixel at the given coordinates to the color specified. If the coordinates lie
@@ -33,6 +41,16 @@ impl Bitmap {
         }
     }
 
+    /// Shades the pixel at the given coordinates to the given light level using the specified
+    /// color map. If the coordinates lie outside of the bitmaps clipping region, no pixels will be
+    /// changed.
+    #[inline]
+    pub fn shade_pixel(&mut self, x: i32, y: i32, color_map: &ColorMap, level: usize) {
+        if let Some(pixels) = self.pixels_at_mut(x, y) {
+            pixels[0] = color_map.shade(pixels[0], level);
+        }
+    }
+
     /// Sets the pixel at the given coordinates to the color specified. The coordinates are not
     /// checked for validity, so it is up to you to ensure they lie within the bounds of the
     /// bitmap.
@@ -83,9 +101,44 @@ impl Bitmap {
 
     /// Renders the string of text using the font given.
     pub fn print_string<T: Font>(&mut self, text: &str, x: i32, y: i32, opts: FontRenderOpts, font: &T) {
+        self.print_string_passes(text, x, y, opts, font, None);
+    }
+
+    /// Renders the string of text using the font given, while also recognizing inline formatting
+    /// codes such as `{color:12}` which change the render options part way through the text. See
+    /// [`TextCodes`] for more details.
+    pub fn print_string_with_codes<T: Font>(&mut self, text: &str, x: i32, y: i32, opts: FontRenderOpts, font: &T, codes: &TextCodes) {
+        self.print_string_passes(text, x, y, opts, font, Some(codes));
+    }
+
+    fn print_string_passes<T: Font>(&mut self, text: &str, x: i32, y: i32, opts: FontRenderOpts, font: &T, codes: Option<&TextCodes>) {
+        // shadows and outlines are drawn for the entire string first, and then the text itself
+        // over top. otherwise the shadow or outline of one character could overwrite part of an
+        // adjacent character
+        if self.print_string_pass(text, x, y, opts, font, codes, false) {
+            self.print_string_pass(text, x, y, opts, font, codes, true);
+        }
+    }
+
+    /// Renders the string of text, returning true if any of the render options used for it had a
+    /// shadow or outline. If `foreground_only` is true, shadows and outlines are not drawn.
+    /// Inline formatting codes are only recognized if `codes` is given.
+    #[allow(clippy::too_many_arguments)]
+    fn print_string_pass<T: Font>(&mut self, text: &str, x: i32, y: i32, opts: FontRenderOpts, font: &T, codes: Option<&TextCodes>, foreground_only: bool) -> bool {
         let mut current_x = x;
         let mut current_y = y;
-        for ch in text.chars() {
+        let mut current_opts = opts;
+        let mut has_effects = false;
+        let mut remaining = text;
+        while let Some(ch) = remaining.chars().next() {
+            if let (Some(codes), '{') = (codes, ch) {
+                if let Some((new_opts, length)) = codes.parse_code(remaining, opts) {
+                    current_opts = new_opts;
+                    remaining = &remaining[length..];
+                    continue;
+                }
+            }
+            remaining = &remaining[ch.len_utf8()..];
             match ch {
                 ' ' => current_x += font.space_width() as i32,
                 '\n' => {
@@ -94,137 +147,31 @@ impl Bitmap {
                 }
                 '\r' => (),
                 otherwise => {
-                    self.print_char(otherwise, current_x, current_y, opts, font);
+                    has_effects |= current_opts.foreground() != current_opts;
+                    let char_opts = if foreground_only { current_opts.foreground() } else { current_opts };
+                    self.print_char(otherwise, current_x, current_y, char_opts, font);
                     current_x += font.character(otherwise).bounds().width as i32;
                 }
             }
         }
+        has_effects
     }
 
-    /// Draws a line from x1,y1 to x2,y2.
+    /// Draws a line from x1,y1 to x2,y2. The points drawn are the same as those returned by
+    /// [BresenhamIter].
     pub fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u8) {
-        let mut dx = x1;
-        let mut dy = y1;
-        let delta_x = x2 - x1;
-        let delta_y = y2 - y1;
-        let delta_x_abs = delta_x.abs();
-        let delta_y_abs = delta_y.abs();
-        let delta_x_sign = delta_x.signum();
-        let delta_y_sign = delta_y.signum();
-        let mut x = delta_x_abs / 2;
-        let mut y = delta_y_abs / 2;
-        let offset_x_inc = delta_x_sign;
-        let offset_y_inc = delta_y_sign * self.width as i32;
-
-        unsafe {
-            // safety: while we are blindly getting a pointer to this x/y coordinate, we don't
-            // write to it unless we know the coordinates are in bounds.
-            // TODO: should be ok ... ? or am i making too many assumptions about memory layout?
-            let mut dest = self.pixels_at_mut_ptr_unchecked(x1, y1);
-
-            if self.is_xy_visible(dx, dy) {
-                *dest = color;
-            }
-
-            if delta_x_abs >= delta_y_abs {
-                for _ in 0..delta_x_abs {
-                    y += delta_y_abs;
-
-                    if y >= delta_x_abs {
-                        y -= delta_x_abs;
-                        dy += delta_y_sign;
-                        dest = dest.offset(offset_y_inc as isize);
-                    }
-
-                    dx += delta_x_sign;
-                    dest = dest.offset(offset_x_inc as isize);
-
-                    if self.is_xy_visible(dx, dy) {
-                        *dest = color;
-                    }
-                }
-            } else {
-                for _ in 0..delta_y_abs {
-                    x += delta_x_abs;
-
-                    if x >= delta_y_abs {
-                        x -= delta_y_abs;
-                        dx += delta_x_sign;
-                        dest = dest.offset(offset_x_inc as isize);
-                    }
-
-                    dy += delta_y_sign;
-                    dest = dest.offset(offset_y_inc as isize);
-
-                    if self.is_xy_visible(dx, dy) {
-                        *dest = color;
-                    }
-                }
-            }
+        for (x, y) in BresenhamIter::new(x1, y1, x2, y2) {
+            self.set_pixel(x, y, color);
         }
     }
 
     /// Draws a line from x1,y1 to x2,y2 by blending the drawn pixels using the given blend map,
     /// or the color specified if the blend map does not include this color.
     pub fn blended_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u8, blend_map: &BlendMap) {
-        if let Some(blend_mapping) = blend_map.get_mapping(color) {
-            let mut dx = x1;
-            let mut dy = y1;
-            let delta_x = x2 - x1;
-            let delta_y = y2 - y1;
-            let delta_x_abs = delta_x.abs();
-            let delta_y_abs = delta_y.abs();
-            let delta_x_sign = delta_x.signum();
-            let delta_y_sign = delta_y.signum();
-            let mut x = delta_x_abs / 2;
-            let mut y = delta_y_abs / 2;
-            let offset_x_inc = delta_x_sign;
-            let offset_y_inc = delta_y_sign * self.width as i32;
-
-            unsafe {
-                // safety: while we are blindly getting a pointer to this x/y coordinate, we don't
-                // write to it unless we know the coordinates are in bounds.
-                // TODO: should be ok ... ? or am i making too many assumptions about memory layout?
-                let mut dest = self.pixels_at_mut_ptr_unchecked(x1, y1);
-
-                if self.is_xy_visible(dx, dy) {
-                    *dest = blend_mapping[*dest as usize];
-                }
-
-                if delta_x_abs >= delta_y_abs {
-                    for _ in 0..delta_x_abs {
-                        y += delta_y_abs;
-
-                        if y >= delta_x_abs {
-                            y -= delta_x_abs;
-                            dy += delta_y_sign;
-                            dest = dest.offset(offset_y_inc as isize);
-                        }
-
-                        dx += delta_x_sign;
-                        dest = dest.offset(offset_x_inc as isize);
-
-                        if self.is_xy_visible(dx, dy) {
-                            *dest = blend_mapping[*dest as usize];
-                        }
-                    }
-                } else {
-                    for _ in 0..delta_y_abs {
-                        x += delta_x_abs;
-
-                        if x >= delta_y_abs {
-                            x -= delta_y_abs;
-                            dx += delta_x_sign;
-                            dest = dest.offset(offset_x_inc as isize);
-                        }
-
-                        dy += delta_y_sign;
-                        dest = dest.offset(offset_y_inc as isize);
-
-                        if self.is_xy_visible(dx, dy) {
-                            *dest = blend_mapping[*dest as usize];
-                        }
-                    }
+        if let Some(blend_mapping) = blend_map.resolve_mapping(color) {
+            for (x, y) in BresenhamIter::new(x1, y1, x2, y2) {
+                if let Some(pixels) = self.pixels_at_mut(x, y) {
+                    pixels[0] = blend_mapping[pixels[0] as usize];
                 }
             }
         } else {
@@ -246,7 +193,7 @@ impl Bitmap {
     /// Draws a horizontal line from x1,y to x2,y by blending the drawn pixels using the given
     /// blend map, or the color specified if the blend map does not include this color.
     pub fn blended_horiz_line(&mut self, x1: i32, x2: i32, y: i32, color: u8, blend_map: &BlendMap) {
-        if let Some(blend_mapping) = blend_map.get_mapping(color) {
+        if let Some(blend_mapping) = blend_map.resolve_mapping(color) {
             let mut region = Rect::from_coords(x1, y, x2, y);
             if region.clamp_to(&self.clip_region) {
                 unsafe {
@@ -269,7 +216,7 @@ impl Bitmap {
                 let mut dest = self.pixels_at_mut_ptr_unchecked(region.x, region.y);
                 for _ in 0..region.height {
                     *dest = color;
-                    dest = dest.add(self.width as usize);
+                    dest = dest.add(self.pitch as usize);
                 }
             }
         }
@@ -278,14 +225,14 @@ impl Bitmap {
     /// Draws a vertical line from x,y1 to x,y2 by blending the drawn pixels using the given blend
     /// map, or the color specified if the blend map does not include this color.
     pub fn blended_vert_line(&mut self, x: i32, y1: i32, y2: i32, color: u8, blend_map: &BlendMap) {
-        if let Some(blend_mapping) = blend_map.get_mapping(color) {
+        if let Some(blend_mapping) = blend_map.resolve_mapping(color) {
             let mut region = Rect::from_coords(x, y1, x, y2);
             if region.clamp_to(&self.clip_region) {
                 unsafe {
                     let mut dest = self.pixels_at_mut_ptr_unchecked(region.x, region.y);
                     for _ in 0..region.height {
                         *dest = blend_mapping[*dest as usize];
-                        dest = dest.add(self.width as usize);
+                        dest = dest.add(self.pitch as usize);
                     }
                 }
             }
@@ -338,7 +285,7 @@ impl Bitmap {
                 let mut dest = self.pixels_at_mut_ptr_unchecked(region.x, region.y);
                 for _ in 0..region.height {
                     *dest = color;
-                    dest = dest.add(self.width as usize);
+                    dest = dest.add(self.pitch as usize);
                 }
             }
         }
@@ -349,7 +296,7 @@ impl Bitmap {
                 let mut dest = self.pixels_at_mut_ptr_unchecked(region.right(), region.y);
                 for _ in 0..region.height {
                     *dest = color;
-                    dest = dest.add(self.width as usize);
+                    dest = dest.add(self.pitch as usize);
                 }
             }
         }
@@ -360,7 +307,7 @@ impl Bitmap {
     /// The box is drawn by blending the drawn pixels using the given blend map, or the color
     /// specified if the blend map does not include this color.
     pub fn blended_rect(&mut self, mut x1: i32, mut y1: i32, mut x2: i32, mut y2: i32, color: u8, blend_map: &BlendMap) {
-        if let Some(blend_mapping) = blend_map.get_mapping(color) {
+        if let Some(blend_mapping) = blend_map.resolve_mapping(color) {
             // note: need to manually do all this instead of just relying on Rect::from_coords (which
             // could otherwise figure all this out for us) mainly just because we need the post-swap
             // x1,y1,x2,y2 values for post-region-clamping comparison purposes ...
@@ -413,7 +360,7 @@ impl Bitmap {
                     let mut dest = self.pixels_at_mut_ptr_unchecked(region.x, region.y);
                     for _ in 0..region.height {
                         *dest = blend_mapping[*dest as usize];
-                        dest = dest.add(self.width as usize);
+                        dest = dest.add(self.pitch as usize);
                     }
                 }
             }
@@ -424,7 +371,7 @@ impl Bitmap {
                     let mut dest = self.pixels_at_mut_ptr_unchecked(region.right(), region.y);
                     for _ in 0..region.height {
                         *dest = blend_mapping[*dest as usize];
-                        dest = dest.add(self.width as usize);
+                        dest = dest.add(self.pitch as usize);
                     }
                 }
             }
@@ -442,7 +389,7 @@ impl Bitmap {
                 let mut dest = self.pixels_at_mut_ptr_unchecked(region.x, region.y);
                 for _ in 0..region.height {
                     dest.write_bytes(color, region.width as usize);
-                    dest = dest.add(self.width as usize);
+                    dest = dest.add(self.pitch as usize);
                 }
             }
         }
@@ -453,7 +400,7 @@ impl Bitmap {
     /// filled box is draw by blending the drawn pixels using the given blend map, or the color
     /// specified if the blend map does not include this color.
     pub fn blended_filled_rect(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u8, blend_map: &BlendMap) {
-        if let Some(blend_mapping) = blend_map.get_mapping(color) {
+        if let Some(blend_mapping) = blend_map.resolve_mapping(color) {
             let mut region = Rect::from_coords(x1, y1, x2, y2);
             if region.clamp_to(&self.clip_region) {
                 unsafe {
@@ -463,7 +410,7 @@ impl Bitmap {
                             let dest_x = dest.offset(x as isize);
                             *dest_x = blend_mapping[*dest_x as usize];
                         }
-                        dest = dest.add(self.width as usize);
+                        dest = dest.add(self.pitch as usize);
                     }
                 }
             }
@@ -472,6 +419,26 @@ impl Bitmap {
         }
     }
 
+    /// Shades all of the pixels within the box (rectangle) formed by the points x1,y1 and x2,y2
+    /// to the given light level using the specified color map, assuming the points are specifying
+    /// the top-left and bottom-right corners respectively. Useful for shadows and other lighting
+    /// effects applied over what has already been drawn.
+    pub fn shaded_filled_rect(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color_map: &ColorMap, level: usize) {
+        let mut region = Rect::from_coords(x1, y1, x2, y2);
+        if region.clamp_to(&self.clip_region) {
+            unsafe {
+                let mut dest = self.pixels_at_mut_ptr_unchecked(region.x, region.y);
+                for _ in 0..region.height {
+                    for x in 0..region.width as usize {
+                        let dest_x = dest.add(x);
+                        *dest_x = color_map.shade(*dest_x, level);
+                    }
+                    dest = dest.add(self.pitch as usize);
+                }
+            }
+        }
+    }
+
     /// Draws the outline of a circle formed by the center point and radius given.
     pub fn circle(&mut self, center_x: i32, center_y: i32, radius: u32, color: u8) {
         // TODO: optimize
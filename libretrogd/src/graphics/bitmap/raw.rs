@@ -0,0 +1,193 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use thiserror::Error;
+
+use crate::graphics::*;
+
+#[derive(Error, Debug)]
+pub enum RawError {
+    #[error("Bad raw image: {0}")]
+    BadFile(String),
+
+    #[error("Raw image palette data error")]
+    BadPalette(#[from] PaletteError),
+
+    #[error("Raw image I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+impl Bitmap {
+    /// Loads a raw, headerless, 8-bit image. Such images are simply a sequence of pixels, one
+    /// byte each, stored a row at a time with no padding between rows. Since there is no header,
+    /// the dimensions of the image must be known ahead of time. Any data beyond what is needed
+    /// for an image of the given dimensions is not read.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the raw image from
+    /// * `width`: the width of the image in pixels (e.g. 320 for a VGA mode 13h screen dump)
+    /// * `height`: the height of the image in pixels (e.g. 200 for a VGA mode 13h screen dump)
+    ///
+    /// returns: `Result<Bitmap, RawError>`
+    pub fn load_raw_bytes<T: ReadBytesExt>(
+        reader: &mut T,
+        width: u32,
+        height: u32,
+    ) -> Result<Bitmap, RawError> {
+        let mut bmp = match Bitmap::new(width, height) {
+            Ok(bmp) => bmp,
+            Err(_) => {
+                return Err(RawError::BadFile(String::from(
+                    "Invalid raw image dimensions",
+                )))
+            }
+        };
+        reader.read_exact(bmp.pixels_mut())?;
+        Ok(bmp)
+    }
+
+    pub fn load_raw_file(path: &Path, width: u32, height: u32) -> Result<Bitmap, RawError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_raw_bytes(&mut reader, width, height)
+    }
+
+    /// Loads a raw, headerless, 8-bit image (see [Bitmap::load_raw_bytes]) along with a separate
+    /// palette file containing 256 colors worth of RGB triplets in the given format.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the raw image file to load
+    /// * `width`: the width of the image in pixels
+    /// * `height`: the height of the image in pixels
+    /// * `palette_path`: the path of the palette file to load
+    /// * `palette_format`: the format of the palette file's RGB triplets
+    ///
+    /// returns: `Result<(Bitmap, Palette), RawError>`
+    pub fn load_raw_file_with_palette(
+        path: &Path,
+        width: u32,
+        height: u32,
+        palette_path: &Path,
+        palette_format: PaletteFormat,
+    ) -> Result<(Bitmap, Palette), RawError> {
+        let bmp = Self::load_raw_file(path, width, height)?;
+        let palette = Palette::load_from_file(palette_path, palette_format)?;
+        Ok((bmp, palette))
+    }
+
+    /// Writes this bitmap's pixels out as a raw, headerless, 8-bit image. No padding is written
+    /// between rows, even if this bitmap's rows are padded.
+    pub fn to_raw_bytes<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), RawError> {
+        let pixels = self.pixels();
+        for y in 0..self.height as i32 {
+            let row_start = self.get_offset_to_xy(0, y);
+            writer.write_all(&pixels[row_start..(row_start + self.width as usize)])?;
+        }
+        Ok(())
+    }
+
+    pub fn to_raw_file(&self, path: &Path) -> Result<(), RawError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_raw_bytes(&mut writer)
+    }
+
+    /// Writes this bitmap's pixels out as a raw, headerless, 8-bit image (see
+    /// [Bitmap::to_raw_bytes]) and the given palette to a separate palette file in the given
+    /// format.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the raw image file to write
+    /// * `palette_path`: the path of the palette file to write
+    /// * `palette`: the palette to write
+    /// * `palette_format`: the format to write the palette file's RGB triplets in
+    ///
+    /// returns: `Result<(), RawError>`
+    pub fn to_raw_file_with_palette(
+        &self,
+        path: &Path,
+        palette_path: &Path,
+        palette: &Palette,
+        palette_format: PaletteFormat,
+    ) -> Result<(), RawError> {
+        self.to_raw_file(path)?;
+        palette.to_file(palette_path, palette_format)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    pub static TEST_BMP_PIXELS_RAW: &[u8] =
+        include_bytes!("../../../test-assets/test_bmp_pixels_raw.bin");
+
+    #[test]
+    pub fn load_and_save() -> Result<(), RawError> {
+        let tmp_dir = TempDir::new()?;
+
+        let (bmp, palette) = Bitmap::load_raw_file_with_palette(
+            Path::new("./test-assets/test_bmp_pixels_raw.bin"),
+            16,
+            16,
+            Path::new("./test-assets/dp2.pal"),
+            PaletteFormat::Normal,
+        )?;
+        assert_eq!(16, bmp.width());
+        assert_eq!(16, bmp.height());
+        assert_eq!(bmp.pixels(), TEST_BMP_PIXELS_RAW);
+
+        let save_path = tmp_dir.path().join("test_save.raw");
+        let palette_path = tmp_dir.path().join("test_save.pal");
+        bmp.to_raw_file_with_palette(&save_path, &palette_path, &palette, PaletteFormat::Vga)?;
+        let (reloaded_bmp, reloaded_palette) = Bitmap::load_raw_file_with_palette(
+            &save_path,
+            16,
+            16,
+            &palette_path,
+            PaletteFormat::Vga,
+        )?;
+        assert_eq!(bmp, reloaded_bmp);
+        assert_eq!(768, std::fs::metadata(&palette_path)?.len());
+        for i in 0..=255 {
+            let (r, g, b) = from_rgb32(palette[i]);
+            let (reloaded_r, reloaded_g, reloaded_b) = from_rgb32(reloaded_palette[i]);
+            assert_eq!(
+                (r / 4 * 4, g / 4 * 4, b / 4 * 4),
+                (reloaded_r, reloaded_g, reloaded_b)
+            );
+        }
+
+        // rows are written without any padding
+        let mut aligned = Bitmap::new_with_alignment(5, 3, 4).unwrap();
+        aligned.clear(7);
+        let mut data = Vec::new();
+        aligned.to_raw_bytes(&mut data)?;
+        assert_eq!(vec![7u8; 15], data);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_bad_files() {
+        let mut data: &[u8] = &[1, 2, 3];
+        assert_matches!(
+            Bitmap::load_raw_bytes(&mut data, 2, 2),
+            Err(RawError::IOError(..))
+        );
+        let mut data: &[u8] = &[1, 2, 3];
+        assert_matches!(
+            Bitmap::load_raw_bytes(&mut data, 0, 2),
+            Err(RawError::BadFile(..))
+        );
+    }
+}
@@ -0,0 +1,493 @@
+use crate::graphics::*;
+use crate::math::*;
+
+/// Specifies where the existing image content is placed within a resized bitmap canvas.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CanvasAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl CanvasAnchor {
+    /// Returns the top-left coordinates that content of the given old size should be placed at
+    /// within a canvas of the given new size when aligned using this anchor. The returned
+    /// coordinates can be negative if the new canvas size is smaller than the old one.
+    pub fn offset_for(&self, old_width: u32, old_height: u32, new_width: u32, new_height: u32) -> (i32, i32) {
+        let dx = new_width as i32 - old_width as i32;
+        let dy = new_height as i32 - old_height as i32;
+        use CanvasAnchor::*;
+        let x = match self {
+            TopLeft | Left | BottomLeft => 0,
+            Top | Center | Bottom => dx / 2,
+            TopRight | Right | BottomRight => dx,
+        };
+        let y = match self {
+            TopLeft | Top | TopRight => 0,
+            Left | Center | Right => dy / 2,
+            BottomLeft | Bottom | BottomRight => dy,
+        };
+        (x, y)
+    }
+}
+
+impl<P: PixelBuffer> Bitmap<P> {
+    /// Flips the entire bitmap's pixel data horizontally (mirrored left-to-right) in place. This
+    /// ignores the current clipping region.
+    pub fn flip_horizontal(&mut self) {
+        let width = self.width as usize;
+        for row in self.pixels.chunks_exact_mut(self.pitch as usize) {
+            row[0..width].reverse();
+        }
+    }
+
+    /// Flips the entire bitmap's pixel data vertically (mirrored top-to-bottom) in place. This
+    /// ignores the current clipping region.
+    pub fn flip_vertical(&mut self) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let pitch = self.pitch as usize;
+        for y in 0..(height / 2) {
+            let (top, bottom) = self.pixels.split_at_mut((height - 1 - y) * pitch);
+            top[(y * pitch)..(y * pitch + width)].swap_with_slice(&mut bottom[0..width]);
+        }
+    }
+
+    /// Returns a copy of this bitmap rotated clockwise by the given number of 90 degree turns.
+    /// Negative turns rotate counter-clockwise. The returned bitmap will have its width and height
+    /// swapped for an odd number of turns.
+    ///
+    /// # Arguments
+    ///
+    /// * `turns`: the number of 90 degree clockwise rotations to perform
+    pub fn rotated_90(&self, turns: i32) -> Bitmap {
+        let turns = turns.rem_euclid(4);
+        let (new_width, new_height) = if turns % 2 == 0 {
+            (self.width, self.height)
+        } else {
+            (self.height, self.width)
+        };
+
+        // the source bitmap has non-zero dimensions, so this cannot fail
+        let mut result = Bitmap::new(new_width, new_height).unwrap();
+        let src_width = self.width as usize;
+        let src_height = self.height as usize;
+        let dest_pitch = result.pitch as usize;
+        for (y, row) in self.pixels.chunks_exact(self.pitch as usize).enumerate() {
+            for (x, pixel) in row[0..src_width].iter().enumerate() {
+                let (dest_x, dest_y) = match turns {
+                    0 => (x, y),
+                    1 => (src_height - 1 - y, x),
+                    2 => (src_width - 1 - x, src_height - 1 - y),
+                    _ => (y, src_width - 1 - x),
+                };
+                result.pixels[(dest_y * dest_pitch) + dest_x] = *pixel;
+            }
+        }
+        result
+    }
+
+    /// Returns a copy of this bitmap with a different canvas size. The existing image is copied
+    /// as-is (not scaled) into the new canvas, positioned according to the anchor given. Any area
+    /// of the new canvas not covered by the existing image is filled with the given color. If the
+    /// new canvas is smaller, the image is cropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_width`: the width of the new canvas in pixels
+    /// * `new_height`: the height of the new canvas in pixels
+    /// * `fill_color`: the color to fill the uncovered areas of the new canvas with
+    /// * `anchor`: where to position the existing image within the new canvas
+    ///
+    /// returns: `Result<Bitmap, BitmapError>`
+    pub fn resized_canvas(
+        &self,
+        new_width: u32,
+        new_height: u32,
+        fill_color: u8,
+        anchor: CanvasAnchor,
+    ) -> Result<Bitmap, BitmapError> {
+        let mut result = Bitmap::new(new_width, new_height)?;
+        result.clear(fill_color);
+        let (mut x, mut y) = anchor.offset_for(self.width, self.height, new_width, new_height);
+        // not using blit_region() here as we want to ignore this bitmap's clipping region
+        let mut src_region = self.full_bounds();
+        if clip_blit(&result.full_bounds(), &mut src_region, &mut x, &mut y, false, false) {
+            unsafe { result.solid_blit(self, &src_region, x, y) };
+        }
+        Ok(result)
+    }
+
+    /// Returns a copy of this bitmap scaled to the new dimensions given using nearest-neighbour
+    /// sampling. This ignores the current clipping region.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_width`: the width of the scaled bitmap in pixels
+    /// * `new_height`: the height of the scaled bitmap in pixels
+    ///
+    /// returns: `Result<Bitmap, BitmapError>`
+    pub fn scaled(&self, new_width: u32, new_height: u32) -> Result<Bitmap, BitmapError> {
+        let mut result = Bitmap::new(new_width, new_height)?;
+        let src_width = self.width as usize;
+        let src_pitch = self.pitch as usize;
+        for (y, dest_row) in result.pixels.chunks_exact_mut(new_width as usize).enumerate() {
+            let src_y = (y * self.height as usize) / new_height as usize;
+            let src_row = &self.pixels[(src_y * src_pitch)..(src_y * src_pitch + src_width)];
+            for (x, pixel) in dest_row.iter_mut().enumerate() {
+                let src_x = (x * src_width) / new_width as usize;
+                *pixel = src_row[src_x];
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns a copy of this bitmap scaled to twice its size using the Scale2x (a.k.a. AdvMAME2x)
+    /// algorithm, which smooths diagonal edges while keeping the crisp look of pixel art. This
+    /// ignores the current clipping region.
+    pub fn scale2x(&self) -> Bitmap {
+        // the source bitmap has non-zero dimensions, so this cannot fail
+        let mut result = Bitmap::new(self.width * 2, self.height * 2).unwrap();
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let b = self.get_clamped_pixel(x, y - 1);
+                let d = self.get_clamped_pixel(x - 1, y);
+                let e = self.get_clamped_pixel(x, y);
+                let f = self.get_clamped_pixel(x + 1, y);
+                let h = self.get_clamped_pixel(x, y + 1);
+
+                let (e0, e1, e2, e3) = if b != h && d != f {
+                    (
+                        if d == b { d } else { e },
+                        if b == f { f } else { e },
+                        if d == h { d } else { e },
+                        if h == f { f } else { e },
+                    )
+                } else {
+                    (e, e, e, e)
+                };
+
+                let dest_x = x * 2;
+                let dest_y = y * 2;
+                unsafe {
+                    result.set_pixel_unchecked(dest_x, dest_y, e0);
+                    result.set_pixel_unchecked(dest_x + 1, dest_y, e1);
+                    result.set_pixel_unchecked(dest_x, dest_y + 1, e2);
+                    result.set_pixel_unchecked(dest_x + 1, dest_y + 1, e3);
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns a copy of this bitmap scaled to three times its size using the Scale3x (a.k.a.
+    /// AdvMAME3x) algorithm, which smooths diagonal edges while keeping the crisp look of pixel
+    /// art. This ignores the current clipping region.
+    pub fn scale3x(&self) -> Bitmap {
+        // the source bitmap has non-zero dimensions, so this cannot fail
+        let mut result = Bitmap::new(self.width * 3, self.height * 3).unwrap();
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let a = self.get_clamped_pixel(x - 1, y - 1);
+                let b = self.get_clamped_pixel(x, y - 1);
+                let c = self.get_clamped_pixel(x + 1, y - 1);
+                let d = self.get_clamped_pixel(x - 1, y);
+                let e = self.get_clamped_pixel(x, y);
+                let f = self.get_clamped_pixel(x + 1, y);
+                let g = self.get_clamped_pixel(x - 1, y + 1);
+                let h = self.get_clamped_pixel(x, y + 1);
+                let i = self.get_clamped_pixel(x + 1, y + 1);
+
+                let out = if b != h && d != f {
+                    [
+                        if d == b { d } else { e },
+                        if (d == b && e != c) || (b == f && e != a) { b } else { e },
+                        if b == f { f } else { e },
+                        if (d == b && e != g) || (d == h && e != a) { d } else { e },
+                        e,
+                        if (b == f && e != i) || (h == f && e != c) { f } else { e },
+                        if d == h { d } else { e },
+                        if (d == h && e != i) || (h == f && e != g) { h } else { e },
+                        if h == f { f } else { e },
+                    ]
+                } else {
+                    [e; 9]
+                };
+
+                let dest_x = x * 3;
+                let dest_y = y * 3;
+                for (index, color) in out.iter().enumerate() {
+                    let index = index as i32;
+                    unsafe {
+                        result.set_pixel_unchecked(dest_x + (index % 3), dest_y + (index / 3), *color);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Finds the smallest region of this bitmap which contains all of the pixels that are not the
+    /// given transparent color. This ignores the current clipping region.
+    ///
+    /// # Arguments
+    ///
+    /// * `transparent_color`: the color of the pixels to be treated as transparent
+    ///
+    /// returns: the region containing all non-transparent pixels, or None if the entire bitmap
+    /// consists of only transparent pixels
+    pub fn opaque_bounds(&self, transparent_color: u8) -> Option<Rect> {
+        let width = self.width as usize;
+        let is_opaque_row = |row: &[u8]| row.iter().any(|&pixel| pixel != transparent_color);
+        let rows = || self.pixels.chunks_exact(self.pitch as usize).map(|row| &row[0..width]);
+
+        let top = rows().position(is_opaque_row)?;
+        // there is at least one opaque row, so searching from the bottom will always find one
+        let rows_from_bottom = rows().rev().position(is_opaque_row)?;
+        let bottom = self.height as usize - 1 - rows_from_bottom;
+
+        let mut left = width;
+        let mut right = 0;
+        for row in rows().skip(top).take(bottom - top + 1) {
+            if let Some(x) = row.iter().position(|&pixel| pixel != transparent_color) {
+                left = left.min(x);
+            }
+            if let Some(x) = row.iter().rposition(|&pixel| pixel != transparent_color) {
+                right = right.max(x);
+            }
+        }
+
+        Some(Rect::from_coords(left as i32, top as i32, right as i32, bottom as i32))
+    }
+
+    /// Returns a copy of this bitmap with all fully transparent borders cropped away. This
+    /// ignores the current clipping region.
+    ///
+    /// # Arguments
+    ///
+    /// * `transparent_color`: the color of the pixels to be treated as transparent
+    ///
+    /// returns: a tuple containing the trimmed bitmap and the x and y offset of the trimmed
+    /// bitmap's top-left corner within this bitmap, or None if the entire bitmap consists of only
+    /// transparent pixels
+    pub fn trimmed(&self, transparent_color: u8) -> Option<(Bitmap, (i32, i32))> {
+        let region = self.opaque_bounds(transparent_color)?;
+        let mut result = Bitmap::new(region.width, region.height).ok()?;
+        unsafe { result.solid_blit(self, &region, 0, 0) };
+        Some((result, (region.x, region.y)))
+    }
+
+    /// Gets the pixel at the given coordinates, where the coordinates are first clamped to the
+    /// full bitmap boundaries (ignoring the clipping region).
+    #[inline]
+    fn get_clamped_pixel(&self, x: i32, y: i32) -> u8 {
+        let x = x.clamp(0, self.right() as i32);
+        let y = y.clamp(0, self.bottom() as i32);
+        unsafe { self.get_pixel_unchecked(x, y) }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static RAW_BMP_PIXELS: &[u8] = &[
+        1, 2, 3,
+        4, 5, 6,
+    ];
+
+    fn make_bitmap() -> Bitmap {
+        let mut bmp = Bitmap::new(3, 2).unwrap();
+        bmp.pixels_mut().copy_from_slice(RAW_BMP_PIXELS);
+        bmp
+    }
+
+    #[test]
+    pub fn flips() {
+        let mut bmp = make_bitmap();
+        bmp.flip_horizontal();
+        assert_eq!(&[3, 2, 1, 6, 5, 4], bmp.pixels());
+
+        let mut bmp = make_bitmap();
+        bmp.flip_vertical();
+        assert_eq!(&[4, 5, 6, 1, 2, 3], bmp.pixels());
+
+        let mut bmp = Bitmap::new(2, 3).unwrap();
+        bmp.pixels_mut().copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+        bmp.flip_vertical();
+        assert_eq!(&[5, 6, 3, 4, 1, 2], bmp.pixels());
+
+        // padding bytes at the end of each row should be left untouched
+        let mut bmp = Bitmap::new_with_alignment(3, 2, 4).unwrap();
+        bmp.pixels_mut().copy_from_slice(&[1, 2, 3, 9, 4, 5, 6, 9]);
+        bmp.flip_horizontal();
+        assert_eq!(&[3, 2, 1, 9, 6, 5, 4, 9], bmp.pixels());
+        bmp.flip_vertical();
+        assert_eq!(&[6, 5, 4, 9, 3, 2, 1, 9], bmp.pixels());
+        assert_eq!(&[4, 1, 5, 2, 6, 3], bmp.rotated_90(-1).pixels());
+        assert_eq!(Some(Rect::new(0, 0, 3, 2)), bmp.opaque_bounds(9));
+    }
+
+    #[test]
+    pub fn rotations() {
+        let bmp = make_bitmap();
+
+        let rotated = bmp.rotated_90(0);
+        assert_eq!(bmp, rotated);
+
+        let rotated = bmp.rotated_90(1);
+        assert_eq!(2, rotated.width());
+        assert_eq!(3, rotated.height());
+        assert_eq!(&[4, 1, 5, 2, 6, 3], rotated.pixels());
+
+        let rotated = bmp.rotated_90(2);
+        assert_eq!(3, rotated.width());
+        assert_eq!(2, rotated.height());
+        assert_eq!(&[6, 5, 4, 3, 2, 1], rotated.pixels());
+
+        let rotated = bmp.rotated_90(-1);
+        assert_eq!(2, rotated.width());
+        assert_eq!(3, rotated.height());
+        assert_eq!(&[3, 6, 2, 5, 1, 4], rotated.pixels());
+        assert_eq!(rotated, bmp.rotated_90(3));
+    }
+
+    #[test]
+    pub fn canvas_resizing() {
+        let bmp = make_bitmap();
+
+        let resized = bmp.resized_canvas(5, 3, 9, CanvasAnchor::TopLeft).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                1, 2, 3, 9, 9,
+                4, 5, 6, 9, 9,
+                9, 9, 9, 9, 9,
+            ],
+            resized.pixels()
+        );
+
+        let resized = bmp.resized_canvas(5, 4, 9, CanvasAnchor::Center).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                9, 9, 9, 9, 9,
+                9, 1, 2, 3, 9,
+                9, 4, 5, 6, 9,
+                9, 9, 9, 9, 9,
+            ],
+            resized.pixels()
+        );
+
+        let resized = bmp.resized_canvas(2, 1, 9, CanvasAnchor::BottomRight).unwrap();
+        assert_eq!(&[5, 6], resized.pixels());
+
+        assert!(bmp.resized_canvas(0, 1, 9, CanvasAnchor::Center).is_err());
+    }
+
+    #[test]
+    pub fn nearest_scaling() {
+        let bmp = make_bitmap();
+
+        let scaled = bmp.scaled(6, 4).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                1, 1, 2, 2, 3, 3,
+                1, 1, 2, 2, 3, 3,
+                4, 4, 5, 5, 6, 6,
+                4, 4, 5, 5, 6, 6,
+            ],
+            scaled.pixels()
+        );
+
+        let scaled = bmp.scaled(2, 1).unwrap();
+        assert_eq!(&[1, 2], scaled.pixels());
+
+        assert!(bmp.scaled(0, 0).is_err());
+    }
+
+    #[test]
+    pub fn scale2x_and_scale3x() {
+        let mut bmp = Bitmap::new(2, 2).unwrap();
+        bmp.pixels_mut().copy_from_slice(&[1, 0, 0, 1]);
+
+        let scaled = bmp.scale2x();
+        assert_eq!(4, scaled.width());
+        assert_eq!(4, scaled.height());
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                1, 1, 0, 0,
+                1, 0, 1, 0,
+                0, 1, 0, 1,
+                0, 0, 1, 1,
+            ],
+            scaled.pixels()
+        );
+
+        let mut bmp = Bitmap::new(3, 3).unwrap();
+        bmp.pixels_mut().copy_from_slice(&[0, 1, 0, 1, 0, 0, 0, 0, 0]);
+
+        let scaled = bmp.scale2x();
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                0, 0, 1, 1, 0, 0,
+                0, 1, 1, 1, 0, 0,
+                1, 1, 1, 0, 0, 0,
+                1, 1, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0,
+            ],
+            scaled.pixels()
+        );
+
+        let scaled = bmp.scale3x();
+        assert_eq!(9, scaled.width());
+        assert_eq!(9, scaled.height());
+        assert_eq!(1, scaled.get_pixel(3, 2).unwrap());
+        assert_eq!(0, scaled.get_pixel(4, 4).unwrap());
+        assert_eq!(1, scaled.get_pixel(2, 3).unwrap());
+        assert_eq!(0, scaled.get_pixel(8, 8).unwrap());
+    }
+
+    #[test]
+    pub fn opaque_bounds_and_trimming() {
+        let mut bmp = Bitmap::new(6, 5).unwrap();
+        assert_eq!(None, bmp.opaque_bounds(0));
+        assert_eq!(None, bmp.trimmed(0));
+        assert_eq!(Some(bmp.full_bounds()), bmp.opaque_bounds(1));
+
+        #[rustfmt::skip]
+        bmp.pixels_mut().copy_from_slice(&[
+            0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0,
+            0, 2, 0, 0, 0, 0,
+            0, 0, 0, 3, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(Some(Rect::new(1, 1, 3, 3)), bmp.opaque_bounds(0));
+
+        let (trimmed, offset) = bmp.trimmed(0).unwrap();
+        assert_eq!((1, 1), offset);
+        assert_eq!(3, trimmed.width());
+        assert_eq!(3, trimmed.height());
+        #[rustfmt::skip]
+        assert_eq!(
+            &[
+                0, 1, 0,
+                2, 0, 0,
+                0, 0, 3,
+            ],
+            trimmed.pixels()
+        );
+    }
+}
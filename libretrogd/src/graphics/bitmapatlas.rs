@@ -1,5 +1,10 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
 use std::ops::Index;
+use std::path::Path;
 
+use serde_json::Value;
 use thiserror::Error;
 
 use crate::graphics::*;
@@ -9,6 +14,54 @@ use crate::math::*;
 pub enum BitmapAtlasError {
     #[error("Region is out of bounds for the Bitmap used by the BitmapAtlas")]
     OutOfBounds,
+
+    #[error("A region named \"{0}\" already exists in the BitmapAtlas")]
+    DuplicateName(String),
+
+    #[error("Not enough space in the BitmapAtlas to fit all of the bitmaps")]
+    NotEnoughSpace,
+
+    #[error("Bad or unsupported atlas metadata: {0}")]
+    BadMetadata(String),
+
+    #[error("BitmapAtlas metadata I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+fn json_u32(value: &Value, field: &str) -> Result<u32, BitmapAtlasError> {
+    match value.get(field).and_then(Value::as_u64) {
+        Some(n) if n <= u32::MAX as u64 => Ok(n as u32),
+        _ => Err(BitmapAtlasError::BadMetadata(format!(
+            "Missing or invalid \"{}\" field",
+            field
+        ))),
+    }
+}
+
+fn json_frame_rect(entry: &Value) -> Result<Rect, BitmapAtlasError> {
+    if entry
+        .get("rotated")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        return Err(BitmapAtlasError::BadMetadata(String::from(
+            "Rotated frames are not supported",
+        )));
+    }
+    let frame = match entry.get("frame") {
+        Some(frame) => frame,
+        None => {
+            return Err(BitmapAtlasError::BadMetadata(String::from(
+                "Frame entry is missing \"frame\" object",
+            )))
+        }
+    };
+    Ok(Rect::new(
+        json_u32(frame, "x")? as i32,
+        json_u32(frame, "y")? as i32,
+        json_u32(frame, "w")?,
+        json_u32(frame, "h")?,
+    ))
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -16,6 +69,7 @@ pub struct BitmapAtlas {
     bitmap: Bitmap,
     bounds: Rect,
     tiles: Vec<Rect>,
+    names: HashMap<String, usize>,
 }
 
 impl BitmapAtlas {
@@ -25,6 +79,7 @@ impl BitmapAtlas {
             bitmap,
             bounds,
             tiles: Vec::new(),
+            names: HashMap::new(),
         }
     }
 
@@ -37,6 +92,23 @@ impl BitmapAtlas {
         Ok(self.tiles.len() - 1)
     }
 
+    /// Adds a new region to this atlas which can also be looked up by the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name for the new region, which must not already be used in this atlas
+    /// * `rect`: the region, which must lie entirely within the atlas bitmap
+    ///
+    /// returns: the index of the newly added region
+    pub fn add_named(&mut self, name: &str, rect: Rect) -> Result<usize, BitmapAtlasError> {
+        if self.names.contains_key(name) {
+            return Err(BitmapAtlasError::DuplicateName(String::from(name)));
+        }
+        let index = self.add(rect)?;
+        self.names.insert(String::from(name), index);
+        Ok(index)
+    }
+
     pub fn add_grid(
         &mut self,
         tile_width: u32,
@@ -93,8 +165,73 @@ impl BitmapAtlas {
         Ok(self.tiles.len() - 1)
     }
     
+    /// Adds regions from JSON metadata in the format written by TexturePacker (and other tools
+    /// such as Aseprite which use the same format). Both the "hash" and "array" variants of the
+    /// format are supported. Each frame is added as a named region using its filename. With the
+    /// "hash" variant, regions are added in order of their names.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the JSON metadata from
+    ///
+    /// returns: the index of the last region added
+    pub fn add_from_json_bytes<T: Read>(
+        &mut self,
+        reader: &mut T,
+    ) -> Result<usize, BitmapAtlasError> {
+        let json: Value = match serde_json::from_reader(reader) {
+            Ok(json) => json,
+            Err(error) => return Err(BitmapAtlasError::BadMetadata(error.to_string())),
+        };
+
+        let mut frames = Vec::new();
+        match json.get("frames") {
+            Some(Value::Object(map)) => {
+                for (name, entry) in map.iter() {
+                    frames.push((name.clone(), json_frame_rect(entry)?));
+                }
+            }
+            Some(Value::Array(array)) => {
+                for entry in array.iter() {
+                    let name = match entry.get("filename").and_then(Value::as_str) {
+                        Some(name) => String::from(name),
+                        None => {
+                            return Err(BitmapAtlasError::BadMetadata(String::from(
+                                "Frame entry is missing \"filename\"",
+                            )))
+                        }
+                    };
+                    frames.push((name, json_frame_rect(entry)?));
+                }
+            }
+            _ => {
+                return Err(BitmapAtlasError::BadMetadata(String::from(
+                    "Missing \"frames\" object or array",
+                )))
+            }
+        }
+        if frames.is_empty() {
+            return Err(BitmapAtlasError::BadMetadata(String::from(
+                "No frames defined",
+            )));
+        }
+
+        let mut index = 0;
+        for (name, rect) in frames {
+            index = self.add_named(&name, rect)?;
+        }
+        Ok(index)
+    }
+
+    pub fn add_from_json_file(&mut self, path: &Path) -> Result<usize, BitmapAtlasError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        self.add_from_json_bytes(&mut reader)
+    }
+
     pub fn clear(&mut self) {
-        self.tiles.clear()
+        self.tiles.clear();
+        self.names.clear();
     }
 
     #[inline]
@@ -107,6 +244,18 @@ impl BitmapAtlas {
         self.tiles.get(index)
     }
 
+    /// Returns the index of the region with the given name, if there is one.
+    #[inline]
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns the region with the given name, if there is one.
+    #[inline]
+    pub fn get_named(&self, name: &str) -> Option<&Rect> {
+        self.index_of(name).and_then(|index| self.tiles.get(index))
+    }
+
     #[inline]
     pub fn bitmap(&self) -> &Bitmap {
         &self.bitmap
@@ -162,6 +311,86 @@ pub mod tests {
         assert_eq!(2, atlas.len());
     }
 
+    #[test]
+    pub fn adding_named_rects() {
+        let bmp = Bitmap::new(64, 64).unwrap();
+        let mut atlas = BitmapAtlas::new(bmp);
+
+        assert_eq!(0, atlas.add(Rect::new(0, 0, 8, 8)).unwrap());
+        assert_eq!(
+            1,
+            atlas.add_named("player", Rect::new(8, 0, 16, 16)).unwrap()
+        );
+        assert_eq!(Some(1), atlas.index_of("player"));
+        assert_eq!(Some(&Rect::new(8, 0, 16, 16)), atlas.get_named("player"));
+        assert_eq!(None, atlas.index_of("enemy"));
+        assert_eq!(None, atlas.get_named("enemy"));
+
+        assert_matches!(
+            atlas.add_named("player", Rect::new(0, 16, 8, 8)),
+            Err(BitmapAtlasError::DuplicateName(..))
+        );
+        assert_matches!(
+            atlas.add_named("enemy", Rect::new(60, 0, 8, 8)),
+            Err(BitmapAtlasError::OutOfBounds)
+        );
+        assert_eq!(None, atlas.index_of("enemy"));
+        assert_eq!(2, atlas.len());
+
+        atlas.clear();
+        assert_eq!(None, atlas.index_of("player"));
+    }
+
+    #[test]
+    pub fn adding_from_json() {
+        let bmp = Bitmap::new(64, 64).unwrap();
+        let mut atlas = BitmapAtlas::new(bmp);
+
+        assert_eq!(
+            2,
+            atlas
+                .add_from_json_file(Path::new("./test-assets/test_atlas.json"))
+                .unwrap()
+        );
+        assert_eq!(3, atlas.len());
+        assert_eq!(
+            Some(&Rect::new(0, 0, 16, 16)),
+            atlas.get_named("hero_0.png")
+        );
+        assert_eq!(
+            Some(&Rect::new(16, 0, 16, 16)),
+            atlas.get_named("hero_1.png")
+        );
+        assert_eq!(Some(2), atlas.index_of("sword.png"));
+        assert_eq!(Rect::new(0, 16, 8, 24), atlas[2]);
+
+        let mut atlas = BitmapAtlas::new(Bitmap::new(64, 64).unwrap());
+        let mut json: &[u8] = br#"{"frames": [
+            {"filename": "b", "frame": {"x": 8, "y": 8, "w": 8, "h": 8}},
+            {"filename": "a", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}}
+        ]}"#;
+        assert_eq!(1, atlas.add_from_json_bytes(&mut json).unwrap());
+        assert_eq!(Some(0), atlas.index_of("b"));
+        assert_eq!(Some(1), atlas.index_of("a"));
+
+        let mut json: &[u8] =
+            br#"{"frames": [{"filename": "c", "frame": {"x": 60, "y": 0, "w": 8, "h": 8}}]}"#;
+        assert_matches!(
+            atlas.add_from_json_bytes(&mut json),
+            Err(BitmapAtlasError::OutOfBounds)
+        );
+        let mut json: &[u8] = br#"{"meta": {}}"#;
+        assert_matches!(
+            atlas.add_from_json_bytes(&mut json),
+            Err(BitmapAtlasError::BadMetadata(..))
+        );
+        let mut json: &[u8] = b"not json";
+        assert_matches!(
+            atlas.add_from_json_bytes(&mut json),
+            Err(BitmapAtlasError::BadMetadata(..))
+        );
+    }
+
     #[test]
     pub fn adding_grid() {
         let bmp = Bitmap::new(64, 64).unwrap();
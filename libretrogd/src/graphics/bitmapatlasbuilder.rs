@@ -0,0 +1,317 @@
+use crate::graphics::*;
+use crate::math::*;
+
+/// A horizontal span of the "skyline" formed by the tops of all of the bitmaps packed so far.
+#[derive(Debug, Copy, Clone)]
+struct SkylineSegment {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+/// Packs bitmaps into rectangles using the "skyline bottom-left" heuristic, which places each
+/// new rectangle at the position along the skyline where its bottom edge would be lowest.
+struct SkylinePacker {
+    max_width: u32,
+    max_height: u32,
+    skyline: Vec<SkylineSegment>,
+}
+
+impl SkylinePacker {
+    fn new(max_width: u32, max_height: u32) -> SkylinePacker {
+        SkylinePacker {
+            max_width,
+            max_height,
+            skyline: vec![SkylineSegment {
+                x: 0,
+                y: 0,
+                width: max_width,
+            }],
+        }
+    }
+
+    /// Returns the y coordinate a rectangle of the given width would be placed at if its left
+    /// edge was aligned with the skyline segment at the given index, or None if it does not fit.
+    fn fits(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.skyline[index].x;
+        if x + width > self.max_width {
+            return None;
+        }
+        let mut y = 0;
+        let mut remaining = width as i64;
+        for segment in self.skyline[index..].iter() {
+            if remaining <= 0 {
+                break;
+            }
+            y = y.max(segment.y);
+            remaining -= segment.width as i64;
+        }
+        if y + height > self.max_height {
+            None
+        } else {
+            Some(y)
+        }
+    }
+
+    /// Finds a position for a rectangle with the given dimensions, updating the skyline to
+    /// account for it. `padded_width` and `padded_height` are the dimensions the rectangle
+    /// occupies in the skyline, while `width` and `height` are only used to check if the
+    /// rectangle fits within the maximum dimensions.
+    fn insert(
+        &mut self,
+        width: u32,
+        height: u32,
+        padded_width: u32,
+        padded_height: u32,
+    ) -> Option<(u32, u32)> {
+        let mut best: Option<(usize, u32)> = None;
+        for index in 0..self.skyline.len() {
+            if let Some(y) = self.fits(index, width, height) {
+                let is_better = match best {
+                    Some((_, best_y)) => y < best_y,
+                    None => true,
+                };
+                if is_better {
+                    best = Some((index, y));
+                }
+            }
+        }
+
+        let (index, y) = best?;
+        let x = self.skyline[index].x;
+        self.skyline.insert(
+            index,
+            SkylineSegment {
+                x,
+                y: y + padded_height,
+                width: padded_width,
+            },
+        );
+
+        // shrink or remove the segments now covered by the one just inserted
+        let right = x + padded_width;
+        let next = index + 1;
+        while next < self.skyline.len() {
+            let segment = self.skyline[next];
+            if segment.x >= right {
+                break;
+            }
+            let segment_right = segment.x + segment.width;
+            if segment_right <= right {
+                self.skyline.remove(next);
+            } else {
+                self.skyline[next].x = right;
+                self.skyline[next].width = segment_right - right;
+                break;
+            }
+        }
+
+        // merge neighbouring segments at the same height
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+        Some((x, y))
+    }
+}
+
+/// Collects many separate bitmaps and packs them together into a single [BitmapAtlas]. Using
+/// a single large bitmap for many small sprites can be both more convenient to manage and
+/// faster to blit from.
+#[derive(Debug, Clone)]
+pub struct BitmapAtlasBuilder {
+    max_width: u32,
+    max_height: u32,
+    padding: u32,
+    entries: Vec<(Option<String>, Bitmap)>,
+}
+
+impl BitmapAtlasBuilder {
+    /// Creates a new atlas builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_width`: the maximum width of the atlas bitmap to be built
+    /// * `max_height`: the maximum height of the atlas bitmap to be built
+    /// * `padding`: the number of pixels of empty space to leave between each packed bitmap
+    ///
+    /// returns: `BitmapAtlasBuilder`
+    pub fn new(max_width: u32, max_height: u32, padding: u32) -> BitmapAtlasBuilder {
+        BitmapAtlasBuilder {
+            max_width,
+            max_height,
+            padding,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a bitmap to be packed. Returns the index its region will have in the built atlas.
+    pub fn add(&mut self, bitmap: Bitmap) -> usize {
+        self.entries.push((None, bitmap));
+        self.entries.len() - 1
+    }
+
+    /// Adds a bitmap to be packed which can be looked up by the given name in the built atlas.
+    /// Returns the index its region will have in the built atlas.
+    pub fn add_named(&mut self, name: &str, bitmap: Bitmap) -> usize {
+        self.entries.push((Some(String::from(name)), bitmap));
+        self.entries.len() - 1
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Packs all of the added bitmaps into a new atlas. The atlas bitmap is only as large as it
+    /// needs to be to hold all of the packed bitmaps, and any unused space is filled with the
+    /// given color. Regions in the returned atlas have the same indices as returned when each
+    /// bitmap was added to this builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `fill_color`: the color to fill unused space in the atlas bitmap with
+    ///
+    /// returns: `Result<BitmapAtlas, BitmapAtlasError>`
+    pub fn build(&self, fill_color: u8) -> Result<BitmapAtlas, BitmapAtlasError> {
+        // packing the largest bitmaps first gives much better results
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by(|&a, &b| {
+            let a = &self.entries[a].1;
+            let b = &self.entries[b].1;
+            b.height()
+                .cmp(&a.height())
+                .then_with(|| b.width().cmp(&a.width()))
+        });
+
+        let mut packer = SkylinePacker::new(self.max_width, self.max_height);
+        let mut positions = vec![(0, 0); self.entries.len()];
+        let mut atlas_width = 1;
+        let mut atlas_height = 1;
+        for index in order {
+            let bitmap = &self.entries[index].1;
+            let (width, height) = (bitmap.width(), bitmap.height());
+            let (x, y) =
+                match packer.insert(width, height, width + self.padding, height + self.padding) {
+                    Some(position) => position,
+                    None => return Err(BitmapAtlasError::NotEnoughSpace),
+                };
+            positions[index] = (x, y);
+            atlas_width = atlas_width.max(x + width);
+            atlas_height = atlas_height.max(y + height);
+        }
+
+        // these dimensions are always non-zero, so this cannot fail
+        let mut atlas_bitmap = Bitmap::new(atlas_width, atlas_height).unwrap();
+        atlas_bitmap.clear(fill_color);
+        for ((_, bitmap), &(x, y)) in self.entries.iter().zip(positions.iter()) {
+            atlas_bitmap.blit(BlitMethod::Solid, bitmap, x as i32, y as i32);
+        }
+
+        let mut atlas = BitmapAtlas::new(atlas_bitmap);
+        for ((name, bitmap), &(x, y)) in self.entries.iter().zip(positions.iter()) {
+            let rect = Rect::new(x as i32, y as i32, bitmap.width(), bitmap.height());
+            match name {
+                Some(name) => atlas.add_named(name, rect)?,
+                None => atlas.add(rect)?,
+            };
+        }
+        Ok(atlas)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    fn filled_bitmap(width: u32, height: u32, color: u8) -> Bitmap {
+        let mut bitmap = Bitmap::new(width, height).unwrap();
+        bitmap.clear(color);
+        bitmap
+    }
+
+    #[test]
+    pub fn packing_bitmaps() {
+        let mut builder = BitmapAtlasBuilder::new(64, 64, 1);
+        assert!(builder.is_empty());
+        let sizes = [
+            (16, 16),
+            (8, 24),
+            (32, 8),
+            (4, 4),
+            (16, 16),
+            (20, 10),
+            (7, 3),
+        ];
+        for (i, &(width, height)) in sizes.iter().enumerate() {
+            assert_eq!(i, builder.add(filled_bitmap(width, height, i as u8 + 1)));
+        }
+        assert_eq!(7, builder.add_named("dot", filled_bitmap(1, 1, 42)));
+        assert_eq!(8, builder.len());
+
+        let atlas = builder.build(0).unwrap();
+        assert_eq!(8, atlas.len());
+        assert_eq!(Some(7), atlas.index_of("dot"));
+        assert!(atlas.bitmap().width() <= 64);
+        assert!(atlas.bitmap().height() <= 64);
+
+        for i in 0..atlas.len() {
+            let rect = atlas[i];
+            if i < sizes.len() {
+                assert_eq!(sizes[i], (rect.width, rect.height));
+            }
+            // each region contains only the pixels of the bitmap it was packed from
+            let expected = if i == 7 { 42 } else { i as u8 + 1 };
+            for y in rect.y..=rect.bottom() {
+                for x in rect.x..=rect.right() {
+                    assert_eq!(Some(expected), atlas.bitmap().get_pixel(x, y));
+                }
+            }
+            // and no two regions overlap, even with the padding included
+            for j in (i + 1)..atlas.len() {
+                let other = atlas[j];
+                let padded = Rect::new(rect.x, rect.y, rect.width + 1, rect.height + 1);
+                assert!(!padded.overlaps(&other), "{:?} overlaps {:?}", rect, other);
+            }
+        }
+    }
+
+    #[test]
+    pub fn packing_fills_available_space() {
+        let mut builder = BitmapAtlasBuilder::new(32, 32, 0);
+        for _ in 0..16 {
+            builder.add(filled_bitmap(8, 8, 1));
+        }
+        let atlas = builder.build(0).unwrap();
+        assert_eq!(32, atlas.bitmap().width());
+        assert_eq!(32, atlas.bitmap().height());
+
+        builder.add(filled_bitmap(1, 1, 1));
+        assert_matches!(builder.build(0), Err(BitmapAtlasError::NotEnoughSpace));
+
+        let mut builder = BitmapAtlasBuilder::new(32, 32, 0);
+        builder.add(filled_bitmap(33, 1, 1));
+        assert_matches!(builder.build(0), Err(BitmapAtlasError::NotEnoughSpace));
+    }
+
+    #[test]
+    pub fn packing_with_duplicate_names() {
+        let mut builder = BitmapAtlasBuilder::new(32, 32, 0);
+        builder.add_named("a", filled_bitmap(8, 8, 1));
+        builder.add_named("a", filled_bitmap(8, 8, 2));
+        assert_matches!(builder.build(0), Err(BitmapAtlasError::DuplicateName(..)));
+    }
+}
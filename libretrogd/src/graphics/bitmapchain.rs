@@ -0,0 +1,189 @@
+use thiserror::Error;
+
+use crate::graphics::*;
+
+#[derive(Error, Debug)]
+pub enum BitmapChainError {
+    #[error("A BitmapChain needs at least 2 pages")]
+    InvalidPageCount,
+
+    #[error("BitmapChain page creation error")]
+    BitmapError(#[from] BitmapError),
+}
+
+/// Owns a fixed number of same-sized bitmap "pages" which are cycled through, allowing for
+/// double or triple buffered off-screen compositing. Rendering is always done to the "back" page,
+/// while the "front" page holds the most recently completed frame. Calling [BitmapChain::swap]
+/// promotes the back page to be the new front page and moves on to the next page in the chain as
+/// the new back page. Because the previous frames are retained, this can also be used for
+/// feedback effects where the current frame is drawn using the pixels of a previous one.
+#[derive(Debug, Clone)]
+pub struct BitmapChain {
+    pages: Vec<Bitmap>,
+    current: usize,
+}
+
+impl BitmapChain {
+    /// Creates a new chain with the given number of pages, all having the same dimensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_pages`: the number of pages in the chain (must be at least 2)
+    /// * `width`: the width of each page in pixels
+    /// * `height`: the height of each page in pixels
+    pub fn new(num_pages: usize, width: u32, height: u32) -> Result<BitmapChain, BitmapChainError> {
+        if num_pages < 2 {
+            return Err(BitmapChainError::InvalidPageCount);
+        }
+
+        let mut pages = Vec::with_capacity(num_pages);
+        for _ in 0..num_pages {
+            pages.push(Bitmap::new(width, height)?);
+        }
+        Ok(BitmapChain { pages, current: 0 })
+    }
+
+    /// Returns the number of pages in this chain.
+    #[inline]
+    pub fn num_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns the width of each of the pages in this chain.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.pages[0].width()
+    }
+
+    /// Returns the height of each of the pages in this chain.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.pages[0].height()
+    }
+
+    #[inline]
+    fn index_of_previous(&self, frames_ago: usize) -> usize {
+        let len = self.pages.len();
+        (self.current + len - (frames_ago % len)) % len
+    }
+
+    /// Returns the front page, which is the most recently completed page (the back page as it
+    /// was before the last call to [BitmapChain::swap]).
+    #[inline]
+    pub fn front(&self) -> &Bitmap {
+        &self.pages[self.index_of_previous(1)]
+    }
+
+    /// Returns the back page, which is the page that should be rendered to for the current frame.
+    #[inline]
+    pub fn back(&self) -> &Bitmap {
+        &self.pages[self.current]
+    }
+
+    /// Returns a mutable reference to the back page, which is the page that should be rendered to
+    /// for the current frame.
+    #[inline]
+    pub fn back_mut(&mut self) -> &mut Bitmap {
+        &mut self.pages[self.current]
+    }
+
+    /// Returns the front page along with a mutable reference to the back page at the same time,
+    /// allowing the back page to be drawn using the contents of the previous frame.
+    pub fn front_and_back_mut(&mut self) -> (&Bitmap, &mut Bitmap) {
+        let front = self.index_of_previous(1);
+        let back = self.current;
+        if front < back {
+            let (a, b) = self.pages.split_at_mut(back);
+            (&a[front], &mut b[0])
+        } else {
+            let (a, b) = self.pages.split_at_mut(front);
+            (&b[0], &mut a[back])
+        }
+    }
+
+    /// Returns the page that was the back page the given number of swaps ago. Zero returns the
+    /// current back page, one returns the front page, and so on. This wraps around the number of
+    /// pages in the chain.
+    #[inline]
+    pub fn previous(&self, frames_ago: usize) -> &Bitmap {
+        &self.pages[self.index_of_previous(frames_ago)]
+    }
+
+    /// Promotes the current back page to be the new front page, and advances to the next page in
+    /// the chain to be the new back page. The contents of the new back page are left as-is (it
+    /// will contain whatever was last drawn to it).
+    pub fn swap(&mut self) {
+        self.current = (self.current + 1) % self.pages.len();
+    }
+
+    /// Returns an iterator over all of the pages in this chain, in their internal storage order.
+    pub fn pages(&self) -> impl Iterator<Item = &Bitmap> {
+        self.pages.iter()
+    }
+
+    /// Returns a mutable iterator over all of the pages in this chain, in their internal storage
+    /// order. Useful for initializing all pages at once.
+    pub fn pages_mut(&mut self) -> impl Iterator<Item = &mut Bitmap> {
+        self.pages.iter_mut()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+
+    use super::*;
+
+    #[test]
+    pub fn creation() {
+        assert_matches!(BitmapChain::new(1, 16, 16), Err(BitmapChainError::InvalidPageCount));
+        assert_matches!(
+            BitmapChain::new(2, 0, 16),
+            Err(BitmapChainError::BitmapError(BitmapError::InvalidDimensions))
+        );
+
+        let chain = BitmapChain::new(3, 16, 8).unwrap();
+        assert_eq!(3, chain.num_pages());
+        assert_eq!(16, chain.width());
+        assert_eq!(8, chain.height());
+    }
+
+    #[test]
+    pub fn swapping() {
+        let mut chain = BitmapChain::new(3, 4, 4).unwrap();
+
+        chain.back_mut().clear(1);
+        chain.swap();
+        assert_eq!(Some(1), chain.front().get_pixel(0, 0));
+        assert_eq!(Some(0), chain.back().get_pixel(0, 0));
+
+        chain.back_mut().clear(2);
+        chain.swap();
+        assert_eq!(Some(2), chain.front().get_pixel(0, 0));
+        assert_eq!(Some(1), chain.previous(2).get_pixel(0, 0));
+        assert_eq!(Some(0), chain.back().get_pixel(0, 0));
+
+        chain.back_mut().clear(3);
+        chain.swap();
+        assert_eq!(Some(3), chain.front().get_pixel(0, 0));
+        // wrapped back around to the first page
+        assert_eq!(Some(1), chain.back().get_pixel(0, 0));
+    }
+
+    #[test]
+    pub fn feedback() {
+        let mut chain = BitmapChain::new(2, 4, 4).unwrap();
+        chain.back_mut().set_pixel(1, 1, 5);
+        chain.swap();
+
+        for _ in 0..3 {
+            let (front, back) = chain.front_and_back_mut();
+            back.blit(BlitMethod::Solid, front, 0, 0);
+            let color = back.get_pixel(1, 1).unwrap();
+            back.set_pixel(1, 1, color + 1);
+            chain.swap();
+        }
+
+        assert_eq!(Some(8), chain.front().get_pixel(1, 1));
+    }
+}
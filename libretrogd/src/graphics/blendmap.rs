@@ -1,9 +1,11 @@
+use std::collections::HashMap;
 use std::fmt::Formatter;
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
+use std::sync::Arc;
 
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use thiserror::Error;
 
 use crate::graphics::*;
@@ -22,11 +24,53 @@ pub enum BlendMapError {
     IOError(#[from] std::io::Error),
 }
 
+const BLENDMAP_FILE_VERSION: u8 = 1;
+
+/// Calculates a checksum (32-bit FNV-1a) of all of the colors in a palette, used to detect if a
+/// cached blend map was generated for a different palette.
+fn palette_checksum(palette: &Palette) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for color in 0..=255 {
+        for byte in palette[color].to_le_bytes() {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x01000193);
+        }
+    }
+    // zero is reserved to mean "unknown palette"
+    hash.max(1)
+}
+
 /// A lookup table used by [`BlendMap`]s. This table stores destination color to blend color
 /// mappings, where the indices are the destination colors and the values at those indices are the
 /// blend colors.
 pub type BlendMapping = [u8; 256];
 
+const fn identity_mapping() -> BlendMapping {
+    let mut mapping = [0u8; 256];
+    let mut index = 0;
+    while index < 256 {
+        mapping[index] = index as u8;
+        index += 1;
+    }
+    mapping
+}
+
+/// Blend mapping that maps every destination color to itself, used for
+/// [`BlendMapFallback::Destination`].
+static IDENTITY_MAPPING: BlendMapping = identity_mapping();
+
+/// What a [`BlendMap`] does when asked to blend a source color that it has no mappings for.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMapFallback {
+    /// The source color is drawn as-is, with no blending. This is the default.
+    Source,
+    /// The destination color is left as-is, as if nothing had been drawn.
+    Destination,
+    /// The blending is done by another blend map instead, which may have its own fallback.
+    BlendMap(Arc<BlendMap>),
+}
+
 /// A blend map containing a lookup table to match source colors with destination colors to
 /// produce blended colors.
 ///
@@ -36,12 +80,15 @@ pub type BlendMapping = [u8; 256];
 /// * **blended color**: the final drawn color, found by looking up the source and destination colors
 ///
 /// A blend map will not necessarily have mappings for all possible 256 source colors. But for each
-/// source color, it will have 256 destination to blended color mappings.
+/// source color, it will have 256 destination to blended color mappings. What happens for source
+/// colors without mappings is determined by the blend map's [`BlendMapFallback`], which allows
+/// small blend maps covering only a few colors to be chained together.
 #[derive(Clone, Eq, PartialEq)]
 pub struct BlendMap {
     start_color: u8,
     end_color: u8,
     mapping: Box<[BlendMapping]>,
+    fallback: BlendMapFallback,
 }
 
 impl std::fmt::Debug for BlendMap {
@@ -49,6 +96,7 @@ impl std::fmt::Debug for BlendMap {
         f.debug_struct("BlendMap")
             .field("start_color", &self.start_color)
             .field("end_color", &self.end_color)
+            .field("fallback", &self.fallback)
             .finish_non_exhaustive()
     }
 }
@@ -68,6 +116,7 @@ impl BlendMap {
             start_color,
             end_color,
             mapping: vec![[0u8; 256]; num_colors].into_boxed_slice(),
+            fallback: BlendMapFallback::Source,
         }
     }
 
@@ -143,19 +192,85 @@ impl BlendMap {
     /// Because simple palette searches are being used to build the blending table, results will
     /// vary palette to palette. There will not always be a perfect blend color available.
     pub fn new_translucency_map(blend_r: f32, blend_g: f32, blend_b: f32, palette: &Palette) -> Self {
+        Self::new_rgb_blend_map(palette, |(source_r, source_g, source_b), (dest_r, dest_g, dest_b)| {
+            (
+                lerp(dest_r as f32, source_r as f32, blend_r) as u8,
+                lerp(dest_g as f32, source_g as f32, blend_g) as u8,
+                lerp(dest_b as f32, source_b as f32, blend_b) as u8,
+            )
+        })
+    }
+
+    /// Creates and returns a new [`BlendMap`] which can be used to draw all 256 colors over top of
+    /// every other color with the given opacity, where 0.0 results in the source color being
+    /// totally transparent and 1.0, totally opaque. This is the same as calling
+    /// [`BlendMap::new_translucency_map`] with the same ratio for all three color components, and
+    /// is similarly SLOW.
+    pub fn new_alpha_map(alpha: f32, palette: &Palette) -> Self {
+        Self::new_translucency_map(alpha, alpha, alpha, palette)
+    }
+
+    /// Creates and returns a new [`BlendMap`] which adds the source color's RGB components,
+    /// scaled by the given strength, to the destination color's, saturating at 255. Useful for
+    /// light and glow effects, such as fire, explosions and lasers. This method is SLOW!
+    pub fn new_additive_map(strength: f32, palette: &Palette) -> Self {
+        Self::new_rgb_blend_map(palette, |(source_r, source_g, source_b), (dest_r, dest_g, dest_b)| {
+            (
+                dest_r.saturating_add((source_r as f32 * strength) as u8),
+                dest_g.saturating_add((source_g as f32 * strength) as u8),
+                dest_b.saturating_add((source_b as f32 * strength) as u8),
+            )
+        })
+    }
+
+    /// Creates and returns a new [`BlendMap`] which subtracts the source color's RGB components,
+    /// scaled by the given strength, from the destination color's, saturating at 0. Useful for
+    /// darkening effects. This method is SLOW!
+    pub fn new_subtractive_map(strength: f32, palette: &Palette) -> Self {
+        Self::new_rgb_blend_map(palette, |(source_r, source_g, source_b), (dest_r, dest_g, dest_b)| {
+            (
+                dest_r.saturating_sub((source_r as f32 * strength) as u8),
+                dest_g.saturating_sub((source_g as f32 * strength) as u8),
+                dest_b.saturating_sub((source_b as f32 * strength) as u8),
+            )
+        })
+    }
+
+    /// Creates and returns a new [`BlendMap`] which multiplies the source and destination colors'
+    /// RGB components together (as fractions of 255). Drawing white leaves the destination
+    /// unchanged, while drawing darker colors darkens and tints it, making this useful for
+    /// shadows. This method is SLOW!
+    pub fn new_multiply_map(palette: &Palette) -> Self {
+        Self::new_rgb_blend_map(palette, |(source_r, source_g, source_b), (dest_r, dest_g, dest_b)| {
+            (
+                ((source_r as u32 * dest_r as u32) / 255) as u8,
+                ((source_g as u32 * dest_g as u32) / 255) as u8,
+                ((source_b as u32 * dest_b as u32) / 255) as u8,
+            )
+        })
+    }
+
+    /// Creates and returns a new [`BlendMap`] which maps every source and destination color
+    /// combination to the closest palette match for the RGB color returned by the given function,
+    /// which is called with the RGB colors of the source and destination colors.
+    ///
+    /// Many source/destination combinations typically result in the same RGB color, so palette
+    /// searches are cached. Even so, this method can still be SLOW for a full 256 color palette.
+    pub fn new_rgb_blend_map(
+        palette: &Palette,
+        f: impl Fn((u8, u8, u8), (u8, u8, u8)) -> (u8, u8, u8)
+    ) -> Self {
+        let mut cache = HashMap::new();
         let mut blend_map = BlendMap::new(0, 255);
         for source in 0..=255 {
-            let (source_r, source_g, source_b) = from_rgb32(palette[source]);
+            let source_rgb = from_rgb32(palette[source]);
             let mapping = blend_map.get_mapping_mut(source).unwrap();
             for dest in 0..=255 {
-                let (dest_r, dest_g, dest_b) = from_rgb32(palette[dest]);
-
-                let find_r = lerp(dest_r as f32, source_r as f32, blend_r) as u8;
-                let find_g = lerp(dest_g as f32, source_g as f32, blend_g) as u8;
-                let find_b = lerp(dest_b as f32, source_b as f32, blend_b) as u8;
-
-                let result_c = palette.find_color(find_r, find_g, find_b);
-                mapping[dest as usize] = result_c;
+                let dest_rgb = from_rgb32(palette[dest]);
+                let (find_r, find_g, find_b) = f(source_rgb, dest_rgb);
+                mapping[dest as usize] = *cache
+                    .entry((find_r, find_g, find_b))
+                    .or_insert_with(|| palette.find_color(find_r, find_g, find_b));
             }
         }
         blend_map
@@ -173,6 +288,24 @@ impl BlendMap {
         self.end_color
     }
 
+    /// Returns this blend map with the given fallback behaviour for source colors that it has no
+    /// mappings for.
+    pub fn with_fallback(mut self, fallback: BlendMapFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// The behaviour of this blend map for source colors that it has no mappings for.
+    #[inline]
+    pub fn fallback(&self) -> &BlendMapFallback {
+        &self.fallback
+    }
+
+    #[inline]
+    pub fn set_fallback(&mut self, fallback: BlendMapFallback) {
+        self.fallback = fallback;
+    }
+
     /// Returns true if the given source color is mapped in this blend map.
     #[inline]
     pub fn is_mapped(&self, color: u8) -> bool {
@@ -213,6 +346,22 @@ impl BlendMap {
         }
     }
 
+    /// Returns a reference to the destination-to-blend color mapping table to use for the given
+    /// source color, taking this blend map's fallback into account if it has no mappings for that
+    /// source color. Returns `None` if the source color should be drawn as-is.
+    #[inline]
+    pub fn resolve_mapping(&self, color: u8) -> Option<&BlendMapping> {
+        if let Some(mapping) = self.get_mapping(color) {
+            Some(mapping)
+        } else {
+            match &self.fallback {
+                BlendMapFallback::Source => None,
+                BlendMapFallback::Destination => Some(&IDENTITY_MAPPING),
+                BlendMapFallback::BlendMap(blend_map) => blend_map.resolve_mapping(color),
+            }
+        }
+    }
+
     /// Sets the blend color mapping for the given source color and destination color combination.
     pub fn set_mapping(&mut self, source_color: u8, dest_color: u8, blended_color: u8) -> Result<(), BlendMapError> {
         if let Some(mapping) = self.get_mapping_mut(source_color) {
@@ -239,14 +388,11 @@ impl BlendMap {
     }
 
     /// Returns the blend color for the given source and destination colors. If the source color
-    /// is not in this blend map, `None` is returned.
+    /// is not in this blend map, this blend map's fallback determines the result, with `None`
+    /// returned if the source color should be drawn as-is.
     #[inline]
     pub fn blend(&self, source_color: u8, dest_color: u8) -> Option<u8> {
-        if let Some(mapping) = self.get_mapping(source_color) {
-            Some(mapping[dest_color as usize])
-        } else {
-            None
-        }
+        self.resolve_mapping(source_color).map(|mapping| mapping[dest_color as usize])
     }
 
     pub fn load_from_file(path: &Path) -> Result<Self, BlendMapError> {
@@ -255,14 +401,37 @@ impl BlendMap {
         Self::load_from_bytes(&mut reader)
     }
 
+    /// Loads a blend map previously written out by [`BlendMap::to_bytes`]. Blend maps written out
+    /// by older versions, which lack a version number in their header, can also be loaded. Since
+    /// fallbacks are not saved, the loaded blend map always has a [`BlendMapFallback::Source`]
+    /// fallback.
     pub fn load_from_bytes<T: ReadBytesExt>(reader: &mut T) -> Result<Self, BlendMapError> {
+        let (blend_map, _) = Self::load_from_bytes_with_checksum(reader)?;
+        Ok(blend_map)
+    }
+
+    /// Loads a blend map along with the checksum of the palette it was generated for, which will
+    /// be zero if that is unknown.
+    fn load_from_bytes_with_checksum<T: ReadBytesExt>(reader: &mut T) -> Result<(Self, u32), BlendMapError> {
         let ident: [u8; 4] = reader.read_bytes()?;
-        if ident != *b"BMap" {
+        let palette_checksum = if ident == *b"BMAP" {
+            let version = reader.read_u8()?;
+            if version > BLENDMAP_FILE_VERSION {
+                return Err(BlendMapError::BadFile(format!("Unsupported version: {}", version)));
+            }
+            reader.read_u32::<LittleEndian>()?
+        } else if ident == *b"BMap" {
+            // original unversioned format
+            0
+        } else {
             return Err(BlendMapError::BadFile(String::from("Unrecognized header")));
-        }
+        };
 
         let start_color = reader.read_u8()?;
         let end_color = reader.read_u8()?;
+        if start_color > end_color {
+            return Err(BlendMapError::BadFile(String::from("Invalid source color range")));
+        }
         let num_maps = end_color as usize - start_color as usize + 1;
 
         let mut maps = Vec::with_capacity(num_maps);
@@ -271,11 +440,13 @@ impl BlendMap {
             maps.push(map);
         }
 
-        Ok(BlendMap {
+        let blend_map = BlendMap {
             start_color,
             end_color,
-            mapping: maps.into_boxed_slice()
-        })
+            mapping: maps.into_boxed_slice(),
+            fallback: BlendMapFallback::Source,
+        };
+        Ok((blend_map, palette_checksum))
     }
 
     pub fn to_file(&self, path: &Path) -> Result<(), BlendMapError> {
@@ -284,8 +455,15 @@ impl BlendMap {
         self.to_bytes(&mut writer)
     }
 
+    /// Writes this blend map out, with a small header including a format version number.
     pub fn to_bytes<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), BlendMapError> {
-        writer.write_all(b"BMap")?;
+        self.to_bytes_with_checksum(writer, 0)
+    }
+
+    fn to_bytes_with_checksum<T: WriteBytesExt>(&self, writer: &mut T, palette_checksum: u32) -> Result<(), BlendMapError> {
+        writer.write_all(b"BMAP")?;
+        writer.write_u8(BLENDMAP_FILE_VERSION)?;
+        writer.write_u32::<LittleEndian>(palette_checksum)?;
         writer.write_u8(self.start_color)?;
         writer.write_u8(self.end_color)?;
         for map in self.mapping.iter() {
@@ -293,6 +471,107 @@ impl BlendMap {
         }
         Ok(())
     }
+
+    /// Loads a previously cached blend map from the given file, or if that is not possible,
+    /// generates it using the function provided and then caches it to that file for next time.
+    /// Since generating blend maps can be very slow, this is useful for doing the generation only
+    /// once, on the first run of a game. The cached file also records a checksum of the palette
+    /// given, so that if the palette changes, the blend map is automatically re-generated.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to load the cached blend map from or save it to
+    /// * `palette`: the palette the blend map is to be generated for
+    /// * `generate`: function which generates the blend map for the given palette, for example
+    ///   `|palette| BlendMap::new_alpha_map(0.5, palette)`
+    ///
+    /// returns: the blend map, or an error only if a newly generated blend map could not be saved
+    pub fn load_or_generate(
+        path: &Path,
+        palette: &Palette,
+        generate: impl FnOnce(&Palette) -> BlendMap,
+    ) -> Result<Self, BlendMapError> {
+        let checksum = palette_checksum(palette);
+        if let Ok(f) = File::open(path) {
+            let mut reader = BufReader::new(f);
+            if let Ok((blend_map, cached_checksum)) = Self::load_from_bytes_with_checksum(&mut reader) {
+                if cached_checksum == checksum {
+                    return Ok(blend_map);
+                }
+            }
+        }
+
+        let blend_map = generate(palette);
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        blend_map.to_bytes_with_checksum(&mut writer, checksum)?;
+        Ok(blend_map)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(rename = "BlendMap")]
+struct SerializeBlendMap<'a> {
+    start_color: u8,
+    end_color: u8,
+    mapping: Vec<&'a [u8]>,
+    fallback: &'a BlendMapFallback,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "BlendMap")]
+struct DeserializeBlendMap {
+    start_color: u8,
+    end_color: u8,
+    mapping: Vec<Vec<u8>>,
+    fallback: BlendMapFallback,
+}
+
+/// Blend maps are serialized along with their fallback, unlike [`BlendMap::to_bytes`]. Any blend
+/// maps shared between fallbacks will no longer be shared once deserialized. Only available with
+/// the "serde" feature enabled.
+#[cfg(feature = "serde")]
+impl serde::Serialize for BlendMap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializeBlendMap {
+            start_color: self.start_color,
+            end_color: self.end_color,
+            mapping: self.mapping.iter().map(|mapping| &mapping[..]).collect(),
+            fallback: &self.fallback,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BlendMap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let data = DeserializeBlendMap::deserialize(deserializer)?;
+        if data.start_color > data.end_color {
+            return Err(D::Error::custom("Invalid source color range"));
+        }
+        let num_maps = data.end_color as usize - data.start_color as usize + 1;
+        if data.mapping.len() != num_maps {
+            return Err(D::Error::invalid_length(data.mapping.len(), &"one mapping per source color"));
+        }
+        let mut mapping = Vec::with_capacity(num_maps);
+        for map in data.mapping {
+            match BlendMapping::try_from(map) {
+                Ok(map) => mapping.push(map),
+                Err(map) => return Err(D::Error::invalid_length(map.len(), &"256 destination colors")),
+            }
+        }
+        Ok(BlendMap {
+            start_color: data.start_color,
+            end_color: data.end_color,
+            mapping: mapping.into_boxed_slice(),
+            fallback: data.fallback,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -376,6 +655,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn fallbacks() -> Result<(), BlendMapError> {
+        let mut fallback_map = BlendMap::new(20, 20);
+        fallback_map.set_mapping(20, 1, 42)?;
+        let fallback_map = Arc::new(fallback_map);
+
+        let mut blend_map = BlendMap::new(10, 10);
+        blend_map.set_mapping(10, 1, 11)?;
+        assert_eq!(&BlendMapFallback::Source, blend_map.fallback());
+        assert_none!(blend_map.blend(20, 1));
+        assert_none!(blend_map.resolve_mapping(20));
+
+        let blend_map = blend_map.with_fallback(BlendMapFallback::BlendMap(fallback_map.clone()));
+        assert_eq!(Some(11), blend_map.blend(10, 1));
+        assert_eq!(Some(42), blend_map.blend(20, 1));
+        assert_none!(blend_map.blend(30, 1));
+
+        let mut blend_map = blend_map;
+        blend_map.set_fallback(BlendMapFallback::Destination);
+        assert_eq!(Some(1), blend_map.blend(20, 1));
+        assert_eq!(Some(77), blend_map.blend(30, 77));
+
+        // fallbacks can be chained
+        let chained = BlendMap::new(0, 0).with_fallback(BlendMapFallback::BlendMap(Arc::new(blend_map)));
+        assert_eq!(Some(11), chained.blend(10, 1));
+        assert_eq!(Some(77), chained.blend(30, 77));
+
+        // primitives respect fallbacks too
+        let mut bmp = Bitmap::new(4, 1).unwrap();
+        bmp.clear(1);
+        bmp.blended_filled_rect(0, 0, 1, 0, 30, &chained);
+        bmp.blended_filled_rect(2, 0, 3, 0, 10, &chained);
+        assert_eq!(&[1, 1, 11, 11], bmp.pixels());
+
+        Ok(())
+    }
+
     #[test]
     pub fn bulk_mappings() -> Result<(), BlendMapError> {
         let mut blend_map = BlendMap::new(0, 7);
@@ -395,6 +711,65 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn generated_translucency_maps() -> Result<(), BlendMapError> {
+        let mut palette = Palette::new_with_default(255, 255, 255);
+        palette[0] = to_rgb32(0, 0, 0);
+        palette[1] = to_rgb32(200, 0, 0);
+        palette[2] = to_rgb32(0, 0, 200);
+        palette[3] = to_rgb32(100, 0, 100);
+        palette[4] = to_rgb32(150, 0, 50);
+
+        let blend_map = BlendMap::new_alpha_map(0.5, &palette);
+        assert_eq!(0, blend_map.start_color());
+        assert_eq!(255, blend_map.end_color());
+        assert_eq!(Some(3), blend_map.blend(1, 2));
+        assert_eq!(Some(3), blend_map.blend(2, 1));
+        assert_eq!(Some(1), blend_map.blend(1, 1));
+
+        let blend_map = BlendMap::new_alpha_map(0.75, &palette);
+        assert_eq!(Some(4), blend_map.blend(1, 2));
+        assert_eq!(blend_map, BlendMap::new_translucency_map(0.75, 0.75, 0.75, &palette));
+
+        let blend_map = BlendMap::new_alpha_map(0.0, &palette);
+        for source in 0..=255 {
+            assert_eq!(Some(2), blend_map.blend(source, 2));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn generated_arithmetic_maps() -> Result<(), BlendMapError> {
+        let mut palette = Palette::new_with_default(0, 0, 0);
+        palette[1] = to_rgb32(100, 50, 0);
+        palette[2] = to_rgb32(200, 100, 0);
+        palette[3] = to_rgb32(255, 255, 255);
+        palette[4] = to_rgb32(50, 25, 0);
+        palette[5] = to_rgb32(128, 128, 128);
+        palette[6] = to_rgb32(255, 150, 0);
+
+        let blend_map = BlendMap::new_additive_map(1.0, &palette);
+        assert_eq!(Some(2), blend_map.blend(1, 1));
+        assert_eq!(Some(6), blend_map.blend(1, 6));
+        assert_eq!(Some(3), blend_map.blend(3, 1));
+        let blend_map = BlendMap::new_additive_map(0.5, &palette);
+        assert_eq!(Some(2), blend_map.blend(2, 1));
+
+        let blend_map = BlendMap::new_subtractive_map(1.0, &palette);
+        assert_eq!(Some(1), blend_map.blend(1, 2));
+        assert_eq!(Some(0), blend_map.blend(2, 1));
+        let blend_map = BlendMap::new_subtractive_map(0.5, &palette);
+        assert_eq!(Some(1), blend_map.blend(2, 2));
+
+        let blend_map = BlendMap::new_multiply_map(&palette);
+        assert_eq!(Some(2), blend_map.blend(3, 2));
+        assert_eq!(Some(0), blend_map.blend(0, 2));
+        assert_eq!(Some(1), blend_map.blend(5, 2));
+
+        Ok(())
+    }
+
     #[test]
     fn load_and_save() -> Result<(), BlendMapError> {
         let tmp_dir = TempDir::new()?;
@@ -411,6 +786,53 @@ mod tests {
         let loaded_blend_map = BlendMap::load_from_file(&save_path)?;
         assert!(blend_map == loaded_blend_map, "loaded BlendMap is not the same as the original");
 
+        // older, unversioned, blend maps can still be loaded
+        let mut data = Vec::from(*b"BMap");
+        data.extend_from_slice(&[5, 5]);
+        data.extend_from_slice(&[7u8; 256]);
+        let loaded_blend_map = BlendMap::load_from_bytes(&mut data.as_slice())?;
+        assert_eq!(5, loaded_blend_map.start_color());
+        assert_eq!(Some(7), loaded_blend_map.blend(5, 100));
+
+        let mut data = Vec::new();
+        blend_map.to_bytes(&mut data)?;
+        data[4] = 99;
+        assert_matches!(
+            BlendMap::load_from_bytes(&mut data.as_slice()),
+            Err(BlendMapError::BadFile(..))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cached_generation() -> Result<(), BlendMapError> {
+        let tmp_dir = TempDir::new()?;
+        let path = tmp_dir.path().join("cached.blendmap");
+        let mut palette = Palette::new_with_default(0, 0, 0);
+        palette[1] = to_rgb32(255, 255, 255);
+
+        let generate = |palette: &Palette| {
+            let mut blend_map = BlendMap::new(0, 0);
+            blend_map.set_mapping(0, 0, palette.find_color(255, 255, 255)).unwrap();
+            blend_map
+        };
+
+        // generated on first use
+        let blend_map = BlendMap::load_or_generate(&path, &palette, generate)?;
+        assert_eq!(Some(1), blend_map.blend(0, 0));
+        assert!(path.exists());
+
+        // loaded from the cache afterwards
+        let blend_map = BlendMap::load_or_generate(&path, &palette, |_| panic!("should not be generated"))?;
+        assert_eq!(Some(1), blend_map.blend(0, 0));
+
+        // and re-generated if the palette changes
+        palette[2] = to_rgb32(255, 255, 255);
+        palette[1] = to_rgb32(0, 0, 0);
+        let blend_map = BlendMap::load_or_generate(&path, &palette, generate)?;
+        assert_eq!(Some(2), blend_map.blend(0, 0));
+
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,426 @@
+use crate::graphics::*;
+use crate::math::*;
+use crate::utils::prng::Prng;
+
+const SHAKE_SEED: u64 = 0x5eed;
+
+#[derive(Debug, Clone)]
+struct CameraShake {
+    intensity: f32,
+    duration: f32,
+    remaining: f32,
+}
+
+/// A 2D camera used to convert between world coordinates and screen coordinates for scrolling
+/// games. The camera's position is the world coordinate shown at the center of its viewport,
+/// which is the region of the screen (or any other destination bitmap) that the world is
+/// rendered to. A zoom factor can be applied, where a zoom of 2.0 means one world unit covers two
+/// screen pixels.
+///
+/// Optionally the camera can be limited to stay within some world bounds, and can be shaken.
+/// Shaking uses its own [Prng] with a fixed seed, so it is the same every time for the same
+/// sequence of updates.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    position: Vector2,
+    zoom: f32,
+    viewport: Rect,
+    bounds: Option<Rect>,
+    shake: Option<CameraShake>,
+    shake_offset: Vector2,
+    prng: Prng,
+}
+
+impl Camera {
+    /// Creates a new camera positioned at the world origin with no zoom.
+    ///
+    /// # Arguments
+    ///
+    /// * `viewport`: the region of the screen that the camera renders to
+    pub fn new(viewport: Rect) -> Camera {
+        Camera {
+            position: Vector2::ZERO,
+            zoom: 1.0,
+            viewport,
+            bounds: None,
+            shake: None,
+            shake_offset: Vector2::ZERO,
+            prng: Prng::new(SHAKE_SEED),
+        }
+    }
+
+    /// Returns the world coordinates shown at the center of the viewport.
+    #[inline]
+    pub fn position(&self) -> Vector2 {
+        self.position
+    }
+
+    /// Moves the camera so that the given world coordinates are shown at the center of the
+    /// viewport. If the camera has bounds, the position will be adjusted to stay within them.
+    pub fn set_position(&mut self, position: Vector2) {
+        self.position = position;
+        self.clamp_to_bounds();
+    }
+
+    /// Moves the camera by the given amount, in world units.
+    #[inline]
+    pub fn move_by(&mut self, delta: Vector2) {
+        self.set_position(self.position + delta);
+    }
+
+    /// Moves the camera so that its top-left corner shows the given world coordinates. This is
+    /// usually the simplest way to work with a camera when zoom is not used.
+    pub fn set_top_left(&mut self, position: Vector2) {
+        self.set_position(position + self.half_view_size());
+    }
+
+    #[inline]
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Sets the zoom factor. Values below 1.0 zoom out and values above 1.0 zoom in. Values that
+    /// are zero or negative are ignored.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if zoom > 0.0 {
+            self.zoom = zoom;
+            self.clamp_to_bounds();
+        }
+    }
+
+    #[inline]
+    pub fn viewport(&self) -> &Rect {
+        &self.viewport
+    }
+
+    /// Sets the region of the screen that the camera renders to.
+    pub fn set_viewport(&mut self, viewport: Rect) {
+        self.viewport = viewport;
+        self.clamp_to_bounds();
+    }
+
+    #[inline]
+    pub fn bounds(&self) -> Option<&Rect> {
+        self.bounds.as_ref()
+    }
+
+    /// Limits the camera so that it never shows anything outside of the given world bounds (e.g.
+    /// the extents of a tile map), or removes the limit if `None`. If the bounds are smaller than
+    /// the camera's view, the camera will be centered on them.
+    pub fn set_bounds(&mut self, bounds: Option<Rect>) {
+        self.bounds = bounds;
+        self.clamp_to_bounds();
+    }
+
+    /// Starts shaking the camera, replacing any shake already in progress. The shake fades out
+    /// over the given duration as [Camera::update] is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `intensity`: the maximum distance the view is moved while shaking, in screen pixels
+    /// * `duration`: how long the shake lasts for, in seconds
+    pub fn shake(&mut self, intensity: f32, duration: f32) {
+        if intensity > 0.0 && duration > 0.0 {
+            self.shake = Some(CameraShake {
+                intensity,
+                duration,
+                remaining: duration,
+            });
+        } else {
+            self.stop_shaking();
+        }
+    }
+
+    /// Immediately stops any camera shake in progress.
+    pub fn stop_shaking(&mut self) {
+        self.shake = None;
+        self.shake_offset = Vector2::ZERO;
+    }
+
+    #[inline]
+    pub fn is_shaking(&self) -> bool {
+        self.shake.is_some()
+    }
+
+    /// Returns how far the view is currently moved by shaking, in screen pixels.
+    #[inline]
+    pub fn shake_offset(&self) -> Vector2 {
+        self.shake_offset
+    }
+
+    /// Updates the camera shake, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta`: the amount of time elapsed since the last update, in seconds
+    pub fn update(&mut self, delta: f32) {
+        if let Some(shake) = &mut self.shake {
+            shake.remaining -= delta;
+            if shake.remaining <= 0.0 {
+                self.stop_shaking();
+            } else {
+                let intensity = shake.intensity * (shake.remaining / shake.duration);
+                self.shake_offset = Vector2::new(
+                    self.prng.rand_range(-intensity..=intensity),
+                    self.prng.rand_range(-intensity..=intensity),
+                );
+            }
+        }
+    }
+
+    /// Converts world coordinates to screen coordinates, including any camera shake.
+    pub fn world_to_screen(&self, world: Vector2) -> Vector2 {
+        (world - self.position) * self.zoom + self.viewport_center() + self.shake_offset
+    }
+
+    /// Converts screen coordinates to world coordinates, including any camera shake. For example,
+    /// to find what part of the world the mouse cursor is over.
+    pub fn screen_to_world(&self, screen: Vector2) -> Vector2 {
+        (screen - self.viewport_center() - self.shake_offset) / self.zoom + self.position
+    }
+
+    /// Converts integer world coordinates to integer screen coordinates, rounding down. Useful
+    /// for getting the screen position to draw something at.
+    pub fn world_to_screen_point(&self, x: i32, y: i32) -> (i32, i32) {
+        let screen = self
+            .world_to_screen(Vector2::new(x as f32, y as f32))
+            .floor_to_int();
+        (screen.x, screen.y)
+    }
+
+    /// Returns a matrix that converts world coordinates to screen coordinates, including zoom and
+    /// any camera shake. Useful with [Bitmap::transformed_blit] to draw zoomed images.
+    pub fn transform(&self) -> Matrix3x2 {
+        let center = self.viewport_center() + self.shake_offset;
+        Matrix3x2::new_translation(center.x, center.y)
+            * Matrix3x2::new_scaling(self.zoom, self.zoom)
+            * Matrix3x2::new_translation(-self.position.x, -self.position.y)
+    }
+
+    /// Returns the region of the world that is visible through the viewport, rounded outwards to
+    /// whole world units. Camera shake is not included. Useful for skipping drawing of things
+    /// that are off-screen.
+    pub fn visible_rect(&self) -> Rect {
+        let half_size = self.half_view_size();
+        let top_left = (self.position - half_size).floor_to_int();
+        let bottom_right = self.position + half_size;
+        Rect::new(
+            top_left.x,
+            top_left.y,
+            (bottom_right.x.ceil() as i32 - top_left.x) as u32,
+            (bottom_right.y.ceil() as i32 - top_left.y) as u32,
+        )
+    }
+
+    /// Returns true if any part of the given world region is visible through the viewport.
+    #[inline]
+    pub fn is_visible(&self, world_rect: &Rect) -> bool {
+        self.visible_rect().overlaps(world_rect)
+    }
+
+    /// Blits a source bitmap to the destination at the given world coordinates. Only the position
+    /// is converted; the image is drawn at its original size regardless of zoom. Use
+    /// [Camera::transform] with [Bitmap::transformed_blit] to draw images with zoom applied.
+    pub fn blit(
+        &self,
+        dest: &mut Bitmap,
+        method: BlitMethod,
+        src: &Bitmap,
+        world_x: i32,
+        world_y: i32,
+    ) {
+        let (x, y) = self.world_to_screen_point(world_x, world_y);
+        dest.blit(method, src, x, y);
+    }
+
+    /// Same as [Camera::blit], but blits a region of the source bitmap.
+    pub fn blit_region(
+        &self,
+        dest: &mut Bitmap,
+        method: BlitMethod,
+        src: &Bitmap,
+        src_region: &Rect,
+        world_x: i32,
+        world_y: i32,
+    ) {
+        let (x, y) = self.world_to_screen_point(world_x, world_y);
+        dest.blit_region(method, src, src_region, x, y);
+    }
+
+    /// Same as [Camera::blit], but blits a tile or sprite from a [BitmapAtlas].
+    pub fn blit_atlas(
+        &self,
+        dest: &mut Bitmap,
+        method: BlitMethod,
+        src: &BitmapAtlas,
+        index: usize,
+        world_x: i32,
+        world_y: i32,
+    ) {
+        let (x, y) = self.world_to_screen_point(world_x, world_y);
+        dest.blit_atlas(method, src, index, x, y);
+    }
+
+    #[inline]
+    fn viewport_center(&self) -> Vector2 {
+        Vector2::new(
+            self.viewport.x as f32 + self.viewport.width as f32 * 0.5,
+            self.viewport.y as f32 + self.viewport.height as f32 * 0.5,
+        )
+    }
+
+    /// Returns half of the size of the viewport, in world units.
+    #[inline]
+    fn half_view_size(&self) -> Vector2 {
+        Vector2::new(
+            self.viewport.width as f32 * 0.5 / self.zoom,
+            self.viewport.height as f32 * 0.5 / self.zoom,
+        )
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        if let Some(bounds) = &self.bounds {
+            let half_size = self.half_view_size();
+            let clamp_axis = |position: f32, start: i32, length: u32, half_size: f32| {
+                let start = start as f32;
+                let end = start + length as f32;
+                if end - start <= half_size * 2.0 {
+                    (start + end) * 0.5
+                } else {
+                    position.clamp(start + half_size, end - half_size)
+                }
+            };
+            self.position = Vector2::new(
+                clamp_axis(self.position.x, bounds.x, bounds.width, half_size.x),
+                clamp_axis(self.position.y, bounds.y, bounds.height, half_size.y),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn world_and_screen_coordinates() {
+        let mut camera = Camera::new(Rect::new(0, 0, 320, 240));
+        camera.set_top_left(Vector2::new(100.0, 50.0));
+        assert!(camera
+            .position()
+            .nearly_equal(Vector2::new(260.0, 170.0), 0.0001));
+        assert_eq!((0, 0), camera.world_to_screen_point(100, 50));
+        assert_eq!((-10, 20), camera.world_to_screen_point(90, 70));
+        let world = camera.screen_to_world(Vector2::new(319.0, 239.0));
+        assert!(world.nearly_equal(Vector2::new(419.0, 289.0), 0.0001));
+        assert_eq!(Rect::new(100, 50, 320, 240), camera.visible_rect());
+        assert!(camera.is_visible(&Rect::new(90, 40, 11, 11)));
+        assert!(!camera.is_visible(&Rect::new(90, 40, 10, 10)));
+
+        // zoomed in, with a viewport that doesn't cover the whole screen
+        let mut camera = Camera::new(Rect::new(10, 20, 100, 50));
+        camera.set_position(Vector2::new(0.0, 0.0));
+        camera.set_zoom(2.0);
+        assert_eq!((60, 45), camera.world_to_screen_point(0, 0));
+        assert_eq!((80, 35), camera.world_to_screen_point(10, -5));
+        let world = camera.screen_to_world(Vector2::new(10.0, 20.0));
+        assert!(world.nearly_equal(Vector2::new(-25.0, -12.5), 0.0001));
+        assert_eq!(Rect::new(-25, -13, 50, 26), camera.visible_rect());
+
+        let screen = camera.transform() * Vector2::new(10.0, -5.0);
+        assert!(screen.nearly_equal(camera.world_to_screen(Vector2::new(10.0, -5.0)), 0.0001));
+
+        camera.set_zoom(0.0);
+        assert_eq!(2.0, camera.zoom());
+    }
+
+    #[test]
+    pub fn bounds() {
+        let mut camera = Camera::new(Rect::new(0, 0, 100, 50));
+        camera.set_bounds(Some(Rect::new(0, 0, 400, 300)));
+        camera.set_position(Vector2::new(-100.0, 1000.0));
+        assert!(camera
+            .position()
+            .nearly_equal(Vector2::new(50.0, 275.0), 0.0001));
+        camera.move_by(Vector2::new(100.0, -100.0));
+        assert!(camera
+            .position()
+            .nearly_equal(Vector2::new(150.0, 175.0), 0.0001));
+
+        // bounds smaller than the view are centered on
+        camera.set_zoom(0.1);
+        assert!(camera
+            .position()
+            .nearly_equal(Vector2::new(200.0, 150.0), 0.0001));
+
+        camera.set_bounds(None);
+        camera.set_position(Vector2::new(-100.0, 1000.0));
+        assert!(camera
+            .position()
+            .nearly_equal(Vector2::new(-100.0, 1000.0), 0.0001));
+    }
+
+    #[test]
+    pub fn shaking() {
+        let mut camera = Camera::new(Rect::new(0, 0, 320, 240));
+        assert!(!camera.is_shaking());
+        camera.shake(4.0, 1.0);
+        assert!(camera.is_shaking());
+
+        let mut offsets = Vec::new();
+        for _ in 0..9 {
+            camera.update(0.1);
+            let offset = camera.shake_offset();
+            assert!(offset.x.abs() <= 4.0 && offset.y.abs() <= 4.0);
+            offsets.push(offset);
+        }
+        assert!(offsets.iter().any(|offset| !offset.almost_zero(0.0001)));
+        let (x, y) = camera.world_to_screen_point(0, 0);
+        let expected = (Vector2::new(160.0, 120.0) + camera.shake_offset()).floor_to_int();
+        assert_eq!((expected.x, expected.y), (x, y));
+
+        camera.update(0.2);
+        assert!(!camera.is_shaking());
+        assert_eq!(Vector2::ZERO, camera.shake_offset());
+
+        // shaking is deterministic
+        let mut other = Camera::new(Rect::new(0, 0, 320, 240));
+        other.shake(4.0, 1.0);
+        other.update(0.1);
+        assert_eq!(offsets[0], other.shake_offset());
+    }
+
+    #[test]
+    pub fn blits_at_world_coordinates() {
+        let mut src = Bitmap::new(2, 2).unwrap();
+        src.clear(7);
+        let mut dest = Bitmap::new(8, 8).unwrap();
+        let mut camera = Camera::new(Rect::new(0, 0, 8, 8));
+        camera.set_top_left(Vector2::new(100.0, 100.0));
+
+        camera.blit(&mut dest, BlitMethod::Solid, &src, 103, 104);
+        assert_eq!(Some(7), dest.get_pixel(3, 4));
+        assert_eq!(Some(7), dest.get_pixel(4, 5));
+        assert_eq!(Some(0), dest.get_pixel(2, 4));
+
+        dest.clear(0);
+        camera.blit_region(
+            &mut dest,
+            BlitMethod::Solid,
+            &src,
+            &Rect::new(0, 0, 1, 1),
+            99,
+            100,
+        );
+        assert!(dest.pixels().iter().all(|&pixel| pixel == 0));
+        camera.blit_region(
+            &mut dest,
+            BlitMethod::Solid,
+            &src,
+            &Rect::new(0, 0, 1, 1),
+            107,
+            107,
+        );
+        assert_eq!(Some(7), dest.get_pixel(7, 7));
+    }
+}
@@ -0,0 +1,271 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::graphics::*;
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("Unsupported capture file type: {0}")]
+    UnknownFileType(String),
+
+    #[error("No frames have been recorded")]
+    NoFrames,
+
+    #[error("Capture bitmap save error")]
+    BitmapError(#[from] BitmapError),
+
+    #[error("Capture I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+fn save_bitmap(bitmap: &Bitmap, palette: &Palette, path: &Path) -> Result<(), CaptureError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("bmp") => bitmap
+            .to_bmp_file(path, palette)
+            .map_err(|e| BitmapError::from(e).into()),
+        Some("gif") => bitmap
+            .to_gif_file(path, palette)
+            .map_err(|e| BitmapError::from(e).into()),
+        Some("pcx") => bitmap
+            .to_pcx_file(path, palette)
+            .map_err(|e| BitmapError::from(e).into()),
+        Some("png") => bitmap
+            .to_png_file(path, palette)
+            .map_err(|e| BitmapError::from(e).into()),
+        Some(other) => Err(CaptureError::UnknownFileType(String::from(other))),
+        None => Err(CaptureError::UnknownFileType(String::from(
+            "No file extension",
+        ))),
+    }
+}
+
+/// Saves a screenshot of the given bitmap using the given palette. The file format is chosen
+/// based on the extension of the path given, which can be any of "bmp", "gif", "pcx" or "png".
+///
+/// # Arguments
+///
+/// * `bitmap`: the bitmap to capture, typically the screen bitmap
+/// * `palette`: the palette the bitmap is currently being displayed with
+/// * `path`: the path of the file to write the screenshot to
+///
+/// returns: `Result<(), CaptureError>`
+pub fn capture_screenshot(
+    bitmap: &Bitmap,
+    palette: &Palette,
+    path: &Path,
+) -> Result<(), CaptureError> {
+    save_bitmap(bitmap, palette, path)
+}
+
+/// Accumulates copies of bitmaps (typically the screen, once per frame) so that they can be
+/// written out afterwards as an animated GIF or as a sequence of image files.
+#[derive(Debug, Clone)]
+pub struct FrameRecorder {
+    frames: Vec<GifFrame>,
+    palette: Option<Palette>,
+    max_frames: usize,
+}
+
+impl FrameRecorder {
+    /// Creates a new recorder which will keep at most the given number of the most recently
+    /// recorded frames, discarding older ones as needed.
+    pub fn new(max_frames: usize) -> FrameRecorder {
+        FrameRecorder {
+            frames: Vec::new(),
+            palette: None,
+            max_frames: max_frames.max(1),
+        }
+    }
+
+    /// Records a copy of the given bitmap as the next frame.
+    ///
+    /// All recorded frames share a single palette, which is the palette given when the first
+    /// frame was recorded. If a later frame is recorded with a different palette, its pixels are
+    /// remapped to the closest matching colors in that first palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitmap`: the bitmap to record
+    /// * `palette`: the palette the bitmap is currently being displayed with
+    /// * `delay_ms`: how long this frame should be shown for when played back
+    pub fn record(&mut self, bitmap: &Bitmap, palette: &Palette, delay_ms: u32) {
+        let mut frame = bitmap.clone();
+        frame.reset_clip_region();
+        match &self.palette {
+            Some(recording_palette) => {
+                if recording_palette != palette {
+                    frame.remap_colors(&palette.remap_table_to(recording_palette));
+                }
+            }
+            None => self.palette = Some(palette.clone()),
+        }
+
+        if self.frames.len() >= self.max_frames {
+            self.frames.remove(0);
+        }
+        self.frames.push(GifFrame {
+            bitmap: frame,
+            delay_ms,
+        });
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    #[inline]
+    pub fn frames(&self) -> &[GifFrame] {
+        &self.frames
+    }
+
+    /// The palette shared by all recorded frames, or None if nothing has been recorded yet.
+    #[inline]
+    pub fn palette(&self) -> Option<&Palette> {
+        self.palette.as_ref()
+    }
+
+    /// Discards all recorded frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.palette = None;
+    }
+
+    /// Writes all of the recorded frames out as a single looping animated GIF.
+    pub fn save_gif(&self, path: &Path) -> Result<(), CaptureError> {
+        match &self.palette {
+            Some(palette) if !self.frames.is_empty() => {
+                Bitmap::save_gif_frames_file(path, &self.frames, palette)
+                    .map_err(|e| BitmapError::from(e).into())
+            }
+            _ => Err(CaptureError::NoFrames),
+        }
+    }
+
+    /// Writes each of the recorded frames out as a separate image file in the given directory.
+    /// Files are named using the given prefix followed by the frame number (padded with zeros
+    /// to 5 digits) and the given extension, which determines the file format in the same way as
+    /// [capture_screenshot]. For example, "frame_00000.pcx".
+    ///
+    /// # Arguments
+    ///
+    /// * `directory`: the directory to write the files to, which must already exist
+    /// * `prefix`: the prefix to use for each file name
+    /// * `extension`: the file extension (without the leading period) for each file
+    ///
+    /// returns: the paths of all of the files written, in frame order
+    pub fn save_sequence(
+        &self,
+        directory: &Path,
+        prefix: &str,
+        extension: &str,
+    ) -> Result<Vec<PathBuf>, CaptureError> {
+        let palette = match &self.palette {
+            Some(palette) if !self.frames.is_empty() => palette,
+            _ => return Err(CaptureError::NoFrames),
+        };
+        let mut paths = Vec::with_capacity(self.frames.len());
+        for (index, frame) in self.frames.iter().enumerate() {
+            let path = directory.join(format!("{}{:05}.{}", prefix, index, extension));
+            save_bitmap(&frame.bitmap, palette, &path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn test_palette() -> Palette {
+        Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal).unwrap()
+    }
+
+    #[test]
+    pub fn screenshots() -> Result<(), CaptureError> {
+        let tmp_dir = TempDir::new()?;
+        let palette = test_palette();
+        let mut bmp = Bitmap::new(16, 8).unwrap();
+        bmp.clear(3);
+        bmp.set_pixel(5, 5, 127);
+
+        for extension in ["bmp", "gif", "pcx", "png", "PNG"] {
+            let path = tmp_dir.path().join(format!("screenshot.{}", extension));
+            capture_screenshot(&bmp, &palette, &path)?;
+            let (loaded_bmp, loaded_palette) = Bitmap::load_file(&path)?;
+            assert_eq!(bmp, loaded_bmp);
+            assert_eq!(palette, loaded_palette);
+        }
+
+        let path = tmp_dir.path().join("screenshot.jpg");
+        assert_matches!(
+            capture_screenshot(&bmp, &palette, &path),
+            Err(CaptureError::UnknownFileType(..))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn recording_frames() -> Result<(), CaptureError> {
+        let tmp_dir = TempDir::new()?;
+        let palette = test_palette();
+        let mut recorder = FrameRecorder::new(3);
+        assert!(recorder.is_empty());
+        assert_matches!(
+            recorder.save_gif(&tmp_dir.path().join("empty.gif")),
+            Err(CaptureError::NoFrames)
+        );
+
+        let mut bmp = Bitmap::new(8, 8).unwrap();
+        for color in 1..=4 {
+            bmp.clear(color);
+            recorder.record(&bmp, &palette, 20 * color as u32);
+        }
+        // only the most recent 3 frames are kept
+        assert_eq!(3, recorder.len());
+        assert_eq!(Some(2), recorder.frames()[0].bitmap.get_pixel(0, 0));
+        assert_eq!(Some(4), recorder.frames()[2].bitmap.get_pixel(0, 0));
+
+        // a frame with a different palette gets remapped to the recording palette
+        let mut other_palette = palette.clone();
+        other_palette[200] = palette[15];
+        bmp.clear(200);
+        recorder.record(&bmp, &other_palette, 20);
+        assert_eq!(Some(15), recorder.frames()[2].bitmap.get_pixel(0, 0));
+
+        let path = tmp_dir.path().join("recording.gif");
+        recorder.save_gif(&path)?;
+        let (frames, loaded_palette) = Bitmap::load_gif_frames_file(&path).unwrap();
+        assert_eq!(palette, loaded_palette);
+        assert_eq!(3, frames.len());
+        assert_eq!(60, frames[0].delay_ms);
+        assert_eq!(recorder.frames()[1].bitmap, frames[1].bitmap);
+
+        let paths = recorder.save_sequence(tmp_dir.path(), "frame_", "pcx")?;
+        assert_eq!(3, paths.len());
+        assert_eq!(tmp_dir.path().join("frame_00002.pcx"), paths[2]);
+        let (bmp, _) = Bitmap::load_file(&paths[2])?;
+        assert_eq!(Some(15), bmp.get_pixel(0, 0));
+
+        recorder.clear();
+        assert!(recorder.is_empty());
+        assert!(recorder.palette().is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use crate::graphics::*;
+
+/// A single range of palette colors being cycled by a [ColorCycler].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorCycle {
+    /// The first palette color index in this range.
+    pub low: u8,
+    /// The last palette color index in this range (inclusive).
+    pub high: u8,
+    /// The number of color cycling steps that should occur per second.
+    pub steps_per_second: f32,
+    /// Whether colors should be cycled in the reverse direction.
+    pub reverse: bool,
+    /// Whether color cycling is enabled for this range.
+    pub active: bool,
+    accumulator: f32,
+}
+
+impl ColorCycle {
+    /// Creates a new, active, color cycling range.
+    ///
+    /// # Arguments
+    ///
+    /// * `low`: the first palette color index in this range
+    /// * `high`: the last palette color index in this range (inclusive)
+    /// * `steps_per_second`: how many times per second this range's colors are rotated by one
+    /// * `reverse`: true to cycle colors towards lower indices instead of higher ones
+    pub fn new(low: u8, high: u8, steps_per_second: f32, reverse: bool) -> ColorCycle {
+        ColorCycle {
+            low,
+            high,
+            steps_per_second,
+            reverse,
+            active: true,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Advances this range's timing by the given amount of time, returning the number of steps
+    /// (and direction) that its colors should be rotated by as a result.
+    fn update(&mut self, delta: f32) -> i32 {
+        if !self.active || self.steps_per_second <= 0.0 || self.low >= self.high {
+            return 0;
+        }
+        self.accumulator += delta * self.steps_per_second;
+        let steps = self.accumulator.floor();
+        self.accumulator -= steps;
+        if self.reverse {
+            -(steps as i32)
+        } else {
+            steps as i32
+        }
+    }
+}
+
+impl From<&IffColorRange> for ColorCycle {
+    fn from(value: &IffColorRange) -> Self {
+        ColorCycle {
+            low: value.low,
+            high: value.high,
+            steps_per_second: value.steps_per_second(),
+            reverse: value.reverse,
+            active: value.active,
+            accumulator: 0.0,
+        }
+    }
+}
+
+/// Drives palette color cycling for any number of color ranges simultaneously, each with its own
+/// rate and direction, based on elapsed time. Color ranges can be set up manually or taken
+/// directly from the CRNG data loaded from IFF files (see
+/// [Bitmap::load_iff_file_with_color_ranges]).
+#[derive(Debug, Clone, Default)]
+pub struct ColorCycler {
+    cycles: Vec<ColorCycle>,
+}
+
+impl ColorCycler {
+    pub fn new() -> ColorCycler {
+        ColorCycler { cycles: Vec::new() }
+    }
+
+    /// Creates a new cycler using the given IFF color ranges. Ranges which are not marked active
+    /// are still added, but will not be cycled unless they are activated.
+    pub fn from_iff_ranges(ranges: &[IffColorRange]) -> ColorCycler {
+        ColorCycler {
+            cycles: ranges.iter().map(ColorCycle::from).collect(),
+        }
+    }
+
+    /// Adds a color range to be cycled, returning its index.
+    pub fn add(&mut self, cycle: ColorCycle) -> usize {
+        self.cycles.push(cycle);
+        self.cycles.len() - 1
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.cycles.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.cycles.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&ColorCycle> {
+        self.cycles.get(index)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut ColorCycle> {
+        self.cycles.get_mut(index)
+    }
+
+    pub fn clear(&mut self) {
+        self.cycles.clear();
+    }
+
+    /// Advances all of the active color ranges by the given amount of elapsed time, rotating the
+    /// colors in the given palette as needed. This is intended to be called once per frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette whose colors are to be cycled
+    /// * `delta`: the amount of time elapsed since the last update, in seconds
+    ///
+    /// returns: true if any colors in the palette were changed, false otherwise
+    pub fn update(&mut self, palette: &mut Palette, delta: f32) -> bool {
+        let mut changed = false;
+        for cycle in self.cycles.iter_mut() {
+            let steps = cycle.update(delta);
+            if steps != 0 {
+                palette.rotate_range(cycle.low..=cycle.high, steps);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn greyscale_palette() -> Palette {
+        let mut palette = Palette::new();
+        for i in 0..=255 {
+            palette[i] = to_rgb32(i, i, i);
+        }
+        palette
+    }
+
+    #[test]
+    pub fn cycling_multiple_ranges() {
+        let mut palette = greyscale_palette();
+        let mut cycler = ColorCycler::new();
+        assert!(cycler.is_empty());
+        cycler.add(ColorCycle::new(10, 13, 10.0, false));
+        let slow = cycler.add(ColorCycle::new(20, 23, 3.0, true));
+        assert_eq!(2, cycler.len());
+
+        // not enough time has passed for either range to step
+        assert!(!cycler.update(&mut palette, 0.05));
+        assert_eq!(greyscale_palette(), palette);
+
+        // the first range steps once, the second not at all yet
+        assert!(cycler.update(&mut palette, 0.1));
+        assert_eq!(to_rgb32(13, 13, 13), palette[10]);
+        assert_eq!(to_rgb32(20, 20, 20), palette[20]);
+
+        // the first range steps three more times (wrapping back to the start), the second once
+        assert!(cycler.update(&mut palette, 0.32));
+        assert_eq!(to_rgb32(10, 10, 10), palette[10]);
+        assert_eq!(to_rgb32(21, 21, 21), palette[20]);
+        assert_eq!(to_rgb32(20, 20, 20), palette[23]);
+
+        // inactive ranges are not cycled
+        cycler.get_mut(slow).unwrap().active = false;
+        assert!(cycler.update(&mut palette, 1.0));
+        assert_eq!(to_rgb32(21, 21, 21), palette[20]);
+        assert_eq!(to_rgb32(12, 12, 12), palette[10]);
+    }
+
+    #[test]
+    pub fn cycling_iff_ranges() {
+        let ranges = [
+            IffColorRange {
+                rate: 16384,
+                active: true,
+                reverse: false,
+                low: 1,
+                high: 4,
+            },
+            IffColorRange {
+                rate: 16384,
+                active: false,
+                reverse: false,
+                low: 5,
+                high: 8,
+            },
+        ];
+        let mut palette = greyscale_palette();
+        let mut cycler = ColorCycler::from_iff_ranges(&ranges);
+        assert_eq!(2, cycler.len());
+        assert_eq!(
+            Some(60.0),
+            cycler.get(0).map(|cycle| cycle.steps_per_second)
+        );
+
+        assert!(cycler.update(&mut palette, 1.0 / 60.0));
+        assert_eq!(to_rgb32(4, 4, 4), palette[1]);
+        assert_eq!(to_rgb32(5, 5, 5), palette[5]);
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::fmt::Formatter;
+
+use crate::graphics::*;
+
+/// A lookup table used by [`ColorMap`]s. The indices are the original colors and the values at
+/// those indices are the shaded colors.
+pub type ColorMapping = [u8; 256];
+
+/// A set of lookup tables (a "colormap", as used by games like Doom and Quake) which map each
+/// palette color to the closest matching palette color at a number of different light levels.
+/// This allows lighting and shading effects to be applied to 8-bit graphics with a single lookup
+/// per pixel.
+///
+/// Light levels range from 0, where all colors are fully darkened (or fully tinted), up to
+/// `num_levels - 1`, where all colors are left unchanged.
+#[derive(Clone, Eq, PartialEq)]
+pub struct ColorMap {
+    levels: Box<[ColorMapping]>,
+}
+
+impl std::fmt::Debug for ColorMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ColorMap")
+            .field("num_levels", &self.levels.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ColorMap {
+    /// Creates and returns a new [`ColorMap`] for the given palette which darkens colors towards
+    /// black as the light level decreases.
+    ///
+    /// This method is SLOW! It searches the palette for the closest match for every palette color
+    /// at every light level.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to generate the colormap from
+    /// * `num_levels`: the number of light levels to generate, which must be at least 2
+    pub fn new(palette: &Palette, num_levels: usize) -> Self {
+        Self::new_tinted(palette, num_levels, 255, 255, 255)
+    }
+
+    /// Creates and returns a new [`ColorMap`] for the given palette which darkens colors towards
+    /// black as the light level decreases, while also tinting them with the given color. The
+    /// tint is applied multiplicatively, so a tint of 255,255,255 is the same as no tint at all,
+    /// while 255,128,128 would make everything look lit by a reddish light.
+    ///
+    /// This method is SLOW! It searches the palette for the closest match for every palette color
+    /// at every light level.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to generate the colormap from
+    /// * `num_levels`: the number of light levels to generate, which must be at least 2
+    /// * `tint_r`: the red component (0-255) of the tint color
+    /// * `tint_g`: the green component (0-255) of the tint color
+    /// * `tint_b`: the blue component (0-255) of the tint color
+    pub fn new_tinted(
+        palette: &Palette,
+        num_levels: usize,
+        tint_r: u8,
+        tint_g: u8,
+        tint_b: u8,
+    ) -> Self {
+        let num_levels = num_levels.max(2);
+        let tint_r = tint_r as f32 / 255.0;
+        let tint_g = tint_g as f32 / 255.0;
+        let tint_b = tint_b as f32 / 255.0;
+        let is_tinted = tint_r < 1.0 || tint_g < 1.0 || tint_b < 1.0;
+
+        // many palette colors end up shading to the same rgb values (especially at darker light
+        // levels), so caching the palette searches saves a lot of time
+        let mut cache = HashMap::new();
+        let mut levels = vec![[0u8; 256]; num_levels].into_boxed_slice();
+        for (level, mapping) in levels.iter_mut().enumerate() {
+            let brightness = level as f32 / (num_levels - 1) as f32;
+            for (color, shaded) in mapping.iter_mut().enumerate() {
+                if brightness >= 1.0 && !is_tinted {
+                    // full brightness should always leave colors exactly as they are
+                    *shaded = color as u8;
+                    continue;
+                }
+                let (r, g, b) = from_rgb32(palette[color as u8]);
+                let r = (r as f32 * brightness * tint_r).round() as u8;
+                let g = (g as f32 * brightness * tint_g).round() as u8;
+                let b = (b as f32 * brightness * tint_b).round() as u8;
+                *shaded = *cache
+                    .entry((r, g, b))
+                    .or_insert_with(|| palette.find_nearest(r, g, b));
+            }
+        }
+        ColorMap { levels }
+    }
+
+    /// The number of light levels in this colormap.
+    #[inline]
+    pub fn num_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Returns the lookup table for the given light level, or None if this colormap does not
+    /// have that light level.
+    #[inline]
+    pub fn get_mapping(&self, level: usize) -> Option<&ColorMapping> {
+        self.levels.get(level)
+    }
+
+    /// Returns a mutable reference to the lookup table for the given light level, or None if this
+    /// colormap does not have that light level. Useful for hand-tweaking generated tables.
+    #[inline]
+    pub fn get_mapping_mut(&mut self, level: usize) -> Option<&mut ColorMapping> {
+        self.levels.get_mut(level)
+    }
+
+    /// Returns the given color as shaded at the given light level. Light levels beyond those in
+    /// this colormap are treated as the brightest level.
+    #[inline]
+    pub fn shade(&self, color: u8, level: usize) -> u8 {
+        let level = level.min(self.levels.len() - 1);
+        self.levels[level][color as usize]
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+    use std::rc::Rc;
+
+    use super::*;
+
+    fn test_palette() -> Palette {
+        Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal).unwrap()
+    }
+
+    #[test]
+    pub fn create() {
+        let palette = test_palette();
+        let color_map = ColorMap::new(&palette, 8);
+        assert_eq!(8, color_map.num_levels());
+        assert!(color_map.get_mapping(8).is_none());
+
+        // the brightest level is an identity mapping, the darkest maps everything to black
+        let black = palette.find_nearest(0, 0, 0);
+        for color in 0..=255 {
+            assert_eq!(color, color_map.shade(color, 7));
+            assert_eq!(color, color_map.shade(color, 100));
+            assert_eq!(black, color_map.shade(color, 0));
+        }
+
+        // in between, colors get darker
+        let (r, g, b) = from_rgb32(palette[15]);
+        let (dark_r, dark_g, dark_b) = from_rgb32(palette[color_map.shade(15, 3)]);
+        assert!(luminance(dark_r, dark_g, dark_b) < luminance(r, g, b));
+
+        let mut color_map = ColorMap::new_tinted(&palette, 2, 255, 0, 0);
+        let (_, g, b) = from_rgb32(palette[color_map.shade(15, 1)]);
+        assert_eq!((0, 0), (g, b));
+        color_map.get_mapping_mut(1).unwrap()[15] = 42;
+        assert_eq!(42, color_map.shade(15, 1));
+    }
+
+    #[test]
+    pub fn blits_and_primitives() {
+        let palette = test_palette();
+        let color_map = Rc::new(ColorMap::new(&palette, 4));
+        let black = color_map.shade(15, 0);
+
+        let mut src = Bitmap::new(2, 2).unwrap();
+        src.clear(15);
+        src.set_pixel(1, 1, 0);
+
+        let mut dest = Bitmap::new(4, 4).unwrap();
+        dest.clear(7);
+        let method = BlitMethod::SolidColorMapped {
+            color_map: color_map.clone(),
+            level: 2,
+        };
+        dest.blit(method, &src, 0, 0);
+        assert_eq!(Some(color_map.shade(15, 2)), dest.get_pixel(0, 0));
+        assert_eq!(Some(color_map.shade(0, 2)), dest.get_pixel(1, 1));
+        assert_eq!(Some(7), dest.get_pixel(2, 2));
+
+        dest.clear(7);
+        let method = BlitMethod::TransparentColorMapped {
+            transparent_color: 0,
+            color_map: color_map.clone(),
+            level: 0,
+        };
+        dest.blit(method, &src, 2, 2);
+        assert_eq!(Some(black), dest.get_pixel(2, 2));
+        assert_eq!(Some(7), dest.get_pixel(3, 3));
+
+        dest.clear(15);
+        dest.shade_pixel(0, 0, &color_map, 0);
+        dest.shade_pixel(-1, 0, &color_map, 0);
+        assert_eq!(Some(black), dest.get_pixel(0, 0));
+        dest.shaded_filled_rect(2, 2, 10, 10, &color_map, 3);
+        assert_eq!(Some(15), dest.get_pixel(3, 3));
+        dest.shaded_filled_rect(2, 2, 10, 10, &color_map, 0);
+        assert_eq!(Some(15), dest.get_pixel(1, 1));
+        assert_eq!(Some(black), dest.get_pixel(2, 2));
+        assert_eq!(Some(black), dest.get_pixel(3, 3));
+    }
+}
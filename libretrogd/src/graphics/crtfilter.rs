@@ -0,0 +1,291 @@
+use crate::graphics::*;
+
+/// The default amount that [CrtFilters] up-scale their output by.
+pub const DEFAULT_CRT_FILTER_SCALE: u32 = 3;
+
+/// A single post-processing filter that approximates some visual characteristic of a CRT monitor.
+/// See [CrtFilters].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CrtFilter {
+    /// Darkens the gaps between each row of pixels, by the given intensity (0.0 to 1.0).
+    Scanlines { intensity: f32 },
+
+    /// Masks each column of output pixels to either red, green or blue, in repeating vertical
+    /// stripes, like the phosphors of an aperture grille CRT. The intensity (0.0 to 1.0) controls
+    /// how much the other two color components are darkened by.
+    ApertureGrille { intensity: f32 },
+
+    /// Curves the image outwards from its center, like the bulging glass of a CRT. The amount
+    /// controls how strong the curvature is. Around 0.1 is subtle, while values closer to 1.0
+    /// are extreme. Areas curved away from the edges of the output are filled with black.
+    BarrelDistortion { amount: f32 },
+
+    /// Smears colors horizontally into the pixels to their right, similar to the color bleeding
+    /// of a composite NTSC signal. The amount (0.0 to 1.0) controls how much of each pixel's
+    /// color carries over.
+    ColorBleed { amount: f32 },
+}
+
+/// A list of [CrtFilter]s applied in order to the final 32-bit ARGB output. Since most of these
+/// filters need more than one output pixel per source pixel to look right, the source image is
+/// first up-scaled by an integer scale factor.
+#[derive(Debug, Clone)]
+pub struct CrtFilters {
+    filters: Vec<CrtFilter>,
+    scale: u32,
+    buffer: Vec<u32>,
+}
+
+impl Default for CrtFilters {
+    fn default() -> Self {
+        CrtFilters::new(DEFAULT_CRT_FILTER_SCALE)
+    }
+}
+
+impl CrtFilters {
+    /// Creates a new, empty, list of filters.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale`: the amount to up-scale the source image by before filtering. must be at least 1
+    pub fn new(scale: u32) -> CrtFilters {
+        CrtFilters {
+            filters: Vec::new(),
+            scale: scale.max(1),
+            buffer: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Sets the amount to up-scale the source image by before filtering. Must be at least 1.
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale.max(1);
+    }
+
+    /// Returns the filters that will be applied, in order.
+    #[inline]
+    pub fn filters(&self) -> &[CrtFilter] {
+        &self.filters
+    }
+
+    /// Returns the filters that will be applied, in order, allowing them to be added, removed,
+    /// re-ordered or have their parameters changed.
+    #[inline]
+    pub fn filters_mut(&mut self) -> &mut Vec<CrtFilter> {
+        &mut self.filters
+    }
+
+    /// Adds a filter, to be applied after all other filters already added.
+    #[inline]
+    pub fn push(&mut self, filter: CrtFilter) {
+        self.filters.push(filter);
+    }
+
+    /// Removes all filters.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.filters.clear();
+    }
+
+    /// Returns true if there are no filters to be applied.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Returns the size of the output produced for a source image of the given size.
+    #[inline]
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        (width * self.scale, height * self.scale)
+    }
+
+    /// Up-scales a source image and applies all filters to it, writing the result to `dest`.
+    ///
+    /// # Arguments
+    ///
+    /// * `src`: the source 32-bit ARGB pixels
+    /// * `width`: the width of the source image
+    /// * `height`: the height of the source image
+    /// * `dest`: the destination 32-bit ARGB pixels, which must be large enough to hold an image
+    ///   of the size returned by [CrtFilters::output_size]
+    pub fn apply(&mut self, src: &[u32], width: u32, height: u32, dest: &mut [u32]) {
+        let (output_width, output_height) = self.output_size(width, height);
+        let output_width = output_width as usize;
+        let output_height = output_height as usize;
+        let scale = self.scale as usize;
+        let width = width as usize;
+
+        let dest = &mut dest[0..(output_width * output_height)];
+        for (y, dest_row) in dest.chunks_exact_mut(output_width).enumerate() {
+            let src_row = &src[((y / scale) * width)..((y / scale) * width + width)];
+            for (x, pixel) in dest_row.iter_mut().enumerate() {
+                *pixel = src_row[x / scale];
+            }
+        }
+
+        for filter in self.filters.iter() {
+            match *filter {
+                CrtFilter::Scanlines { intensity } => {
+                    let brightness = 1.0 - intensity.clamp(0.0, 1.0);
+                    for (y, dest_row) in dest.chunks_exact_mut(output_width).enumerate() {
+                        let is_gap = if scale == 1 {
+                            y % 2 == 1
+                        } else {
+                            y % scale == scale - 1
+                        };
+                        if is_gap {
+                            for pixel in dest_row.iter_mut() {
+                                *pixel = scale_rgb(*pixel, brightness, brightness, brightness);
+                            }
+                        }
+                    }
+                }
+                CrtFilter::ApertureGrille { intensity } => {
+                    let dim = 1.0 - intensity.clamp(0.0, 1.0);
+                    for dest_row in dest.chunks_exact_mut(output_width) {
+                        for (x, pixel) in dest_row.iter_mut().enumerate() {
+                            *pixel = match x % 3 {
+                                0 => scale_rgb(*pixel, 1.0, dim, dim),
+                                1 => scale_rgb(*pixel, dim, 1.0, dim),
+                                _ => scale_rgb(*pixel, dim, dim, 1.0),
+                            };
+                        }
+                    }
+                }
+                CrtFilter::BarrelDistortion { amount } => {
+                    self.buffer.clear();
+                    self.buffer.extend_from_slice(dest);
+                    for (y, dest_row) in dest.chunks_exact_mut(output_width).enumerate() {
+                        let v = ((y as f32 + 0.5) / output_height as f32) * 2.0 - 1.0;
+                        for (x, pixel) in dest_row.iter_mut().enumerate() {
+                            let u = ((x as f32 + 0.5) / output_width as f32) * 2.0 - 1.0;
+                            let distortion = 1.0 + amount * ((u * u) + (v * v));
+                            let src_u = u * distortion;
+                            let src_v = v * distortion;
+                            *pixel = if src_u.abs() > 1.0 || src_v.abs() > 1.0 {
+                                0xff000000
+                            } else {
+                                let src_x = ((src_u + 1.0) * 0.5 * output_width as f32) as usize;
+                                let src_y = ((src_v + 1.0) * 0.5 * output_height as f32) as usize;
+                                self.buffer[src_y.min(output_height - 1) * output_width
+                                    + src_x.min(output_width - 1)]
+                            };
+                        }
+                    }
+                }
+                CrtFilter::ColorBleed { amount } => {
+                    let amount = amount.clamp(0.0, 1.0);
+                    for dest_row in dest.chunks_exact_mut(output_width) {
+                        let mut previous = dest_row[0];
+                        for pixel in dest_row.iter_mut() {
+                            previous = lerp_rgb(*pixel, previous, amount);
+                            *pixel = previous;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn scale_rgb(argb: u32, r_factor: f32, g_factor: f32, b_factor: f32) -> u32 {
+    let (a, r, g, b) = from_argb32(argb);
+    to_argb32(
+        a,
+        (r as f32 * r_factor) as u8,
+        (g as f32 * g_factor) as u8,
+        (b as f32 * b_factor) as u8,
+    )
+}
+
+#[inline]
+fn lerp_rgb(from: u32, to: u32, t: f32) -> u32 {
+    let (a, r1, g1, b1) = from_argb32(from);
+    let (_, r2, g2, b2) = from_argb32(to);
+    let lerp = |c1: u8, c2: u8| (c1 as f32 + (c2 as f32 - c1 as f32) * t).round() as u8;
+    to_argb32(a, lerp(r1, r2), lerp(g1, g2), lerp(b1, b2))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const WHITE: u32 = 0xffffffff;
+    const RED: u32 = 0xffff0000;
+    const BLACK: u32 = 0xff000000;
+
+    #[test]
+    pub fn upscales_without_filters() {
+        let mut filters = CrtFilters::new(2);
+        assert!(filters.is_empty());
+        assert_eq!((4, 2), filters.output_size(2, 1));
+
+        let mut dest = vec![0u32; 8];
+        filters.apply(&[RED, WHITE], 2, 1, &mut dest);
+        assert_eq!(vec![RED, RED, WHITE, WHITE, RED, RED, WHITE, WHITE], dest);
+
+        filters.set_scale(0);
+        assert_eq!(1, filters.scale());
+    }
+
+    #[test]
+    pub fn scanlines() {
+        let mut filters = CrtFilters::new(2);
+        filters.push(CrtFilter::Scanlines { intensity: 1.0 });
+        let mut dest = vec![0u32; 4];
+        filters.apply(&[WHITE], 1, 1, &mut dest);
+        assert_eq!(vec![WHITE, WHITE, BLACK, BLACK], dest);
+
+        filters.set_scale(3);
+        filters.filters_mut()[0] = CrtFilter::Scanlines { intensity: 0.5 };
+        let mut dest = vec![0u32; 9];
+        filters.apply(&[WHITE], 1, 1, &mut dest);
+        assert_eq!(WHITE, dest[3]);
+        assert_eq!(0xff7f7f7f, dest[6]);
+    }
+
+    #[test]
+    pub fn aperture_grille() {
+        let mut filters = CrtFilters::new(3);
+        filters.push(CrtFilter::ApertureGrille { intensity: 1.0 });
+        let mut dest = vec![0u32; 9];
+        filters.apply(&[WHITE], 1, 1, &mut dest);
+        assert_eq!(&[0xffff0000, 0xff00ff00, 0xff0000ff], &dest[0..3]);
+    }
+
+    #[test]
+    pub fn barrel_distortion() {
+        let src = [RED, WHITE, WHITE, RED];
+
+        let mut filters = CrtFilters::new(4);
+        filters.push(CrtFilter::BarrelDistortion { amount: 0.0 });
+        let mut undistorted = vec![0u32; 64];
+        filters.apply(&src, 2, 2, &mut undistorted);
+        let mut expected = vec![0u32; 64];
+        CrtFilters::new(4).apply(&src, 2, 2, &mut expected);
+        assert_eq!(expected, undistorted);
+
+        filters.filters_mut()[0] = CrtFilter::BarrelDistortion { amount: 0.5 };
+        let mut distorted = vec![0u32; 64];
+        filters.apply(&src, 2, 2, &mut distorted);
+        // corners are curved away entirely, while the center is unaffected
+        assert_eq!(BLACK, distorted[0]);
+        assert_eq!(BLACK, distorted[63]);
+        assert_eq!(undistorted[27], distorted[27]);
+        assert_eq!(undistorted[36], distorted[36]);
+    }
+
+    #[test]
+    pub fn color_bleed() {
+        let mut filters = CrtFilters::new(1);
+        filters.push(CrtFilter::ColorBleed { amount: 0.5 });
+        let mut dest = vec![0u32; 4];
+        filters.apply(&[RED, BLACK, BLACK, WHITE], 4, 1, &mut dest);
+        assert_eq!(vec![RED, 0xff800000, 0xff400000, 0xffa08080], dest);
+    }
+}
@@ -0,0 +1,580 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use thiserror::Error;
+
+use crate::graphics::*;
+
+const FLI_MAGIC: u16 = 0xaf11;
+const FLC_MAGIC: u16 = 0xaf12;
+
+const FRAME_MAGIC: u16 = 0xf1fa;
+const PREFIX_MAGIC: u16 = 0xf100;
+
+const CHUNK_COLOR_256: u16 = 4;
+const CHUNK_DELTA_FLC: u16 = 7;
+const CHUNK_COLOR_64: u16 = 11;
+const CHUNK_DELTA_FLI: u16 = 12;
+const CHUNK_BLACK: u16 = 13;
+const CHUNK_BYTE_RUN: u16 = 15;
+const CHUNK_FLI_COPY: u16 = 16;
+
+#[derive(Error, Debug)]
+pub enum FlicError {
+    #[error("Bad or unsupported FLI/FLC file: {0}")]
+    BadFile(String),
+
+    #[error("FLI/FLC I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+/// Decoded frame data is simply a width x height sequence of pixels. This wraps it to make
+/// bounds-checked writes from (potentially broken) delta chunks easy.
+struct FrameWriter<'a> {
+    pixels: &'a mut [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> FrameWriter<'a> {
+    #[inline]
+    fn offset(&self, x: usize, y: usize, count: usize) -> Result<usize, FlicError> {
+        if y >= self.height || x + count > self.width {
+            Err(FlicError::BadFile(String::from(
+                "Frame data extends outside the image",
+            )))
+        } else {
+            Ok(y * self.width + x)
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, count: usize, color: u8) -> Result<(), FlicError> {
+        let offset = self.offset(x, y, count)?;
+        self.pixels[offset..offset + count].fill(color);
+        Ok(())
+    }
+
+    fn copy<T: ReadBytesExt>(
+        &mut self,
+        x: usize,
+        y: usize,
+        count: usize,
+        reader: &mut T,
+    ) -> Result<(), FlicError> {
+        let offset = self.offset(x, y, count)?;
+        reader.read_exact(&mut self.pixels[offset..offset + count])?;
+        Ok(())
+    }
+}
+
+fn read_color_chunk(
+    mut data: &[u8],
+    palette: &mut Palette,
+    six_bit: bool,
+) -> Result<(), FlicError> {
+    let num_packets = data.read_u16::<LittleEndian>()?;
+    let mut index = 0usize;
+    for _ in 0..num_packets {
+        index += data.read_u8()? as usize;
+        let count = match data.read_u8()? {
+            0 => 256,
+            n => n as usize,
+        };
+        for _ in 0..count {
+            let mut r = data.read_u8()?;
+            let mut g = data.read_u8()?;
+            let mut b = data.read_u8()?;
+            if six_bit {
+                // scale 0-63 up to 0-255
+                r = ((r.min(63) as u32 * 255) / 63) as u8;
+                g = ((g.min(63) as u32 * 255) / 63) as u8;
+                b = ((b.min(63) as u32 * 255) / 63) as u8;
+            }
+            if index < 256 {
+                palette[index as u8] = to_rgb32(r, g, b);
+            }
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn read_byte_run_chunk(mut data: &[u8], frame: &mut FrameWriter) -> Result<(), FlicError> {
+    for y in 0..frame.height {
+        // the packet count is unreliable, so ignore it and just decode until the line is full
+        let _num_packets = data.read_u8()?;
+        let mut x = 0;
+        while x < frame.width {
+            let count = data.read_i8()?;
+            if count >= 0 {
+                let count = count as usize;
+                let color = data.read_u8()?;
+                frame.fill(x, y, count, color)?;
+                x += count;
+            } else {
+                let count = (-(count as i32)) as usize;
+                frame.copy(x, y, count, &mut data)?;
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_delta_fli_chunk(mut data: &[u8], frame: &mut FrameWriter) -> Result<(), FlicError> {
+    let first_line = data.read_u16::<LittleEndian>()? as usize;
+    let num_lines = data.read_u16::<LittleEndian>()? as usize;
+    for y in first_line..(first_line + num_lines) {
+        let num_packets = data.read_u8()?;
+        let mut x = 0;
+        for _ in 0..num_packets {
+            x += data.read_u8()? as usize;
+            let count = data.read_i8()?;
+            if count >= 0 {
+                let count = count as usize;
+                frame.copy(x, y, count, &mut data)?;
+                x += count;
+            } else {
+                let count = (-(count as i32)) as usize;
+                let color = data.read_u8()?;
+                frame.fill(x, y, count, color)?;
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_delta_flc_chunk(mut data: &[u8], frame: &mut FrameWriter) -> Result<(), FlicError> {
+    let num_lines = data.read_u16::<LittleEndian>()?;
+    let mut y = 0usize;
+    for _ in 0..num_lines {
+        // each line starts with optional skip / last pixel opcodes before the packet count
+        let num_packets = loop {
+            let opcode = data.read_u16::<LittleEndian>()?;
+            match opcode & 0xc000 {
+                0xc000 => y += (-(opcode as i16) as i32) as usize,
+                0x8000 => {
+                    let last = frame.width - 1;
+                    frame.fill(last, y, 1, opcode as u8)?;
+                }
+                0x0000 => break opcode,
+                _ => {
+                    return Err(FlicError::BadFile(String::from(
+                        "Invalid line opcode in delta chunk",
+                    )))
+                }
+            }
+        };
+        let mut x = 0;
+        for _ in 0..num_packets {
+            x += data.read_u8()? as usize;
+            let count = data.read_i8()?;
+            if count >= 0 {
+                let count = count as usize * 2;
+                frame.copy(x, y, count, &mut data)?;
+                x += count;
+            } else {
+                let count = (-(count as i32)) as usize;
+                let a = data.read_u8()?;
+                let b = data.read_u8()?;
+                for _ in 0..count {
+                    frame.fill(x, y, 1, a)?;
+                    frame.fill(x + 1, y, 1, b)?;
+                    x += 2;
+                }
+            }
+        }
+        y += 1;
+    }
+    Ok(())
+}
+
+/// Plays back Autodesk Animator FLI and Animator Pro FLC animations, decoding one frame at a
+/// time from the underlying reader.
+#[derive(Debug)]
+pub struct FlicPlayer<R: Read + Seek> {
+    reader: R,
+    width: u32,
+    height: u32,
+    num_frames: u32,
+    frame_delay_ms: u32,
+    first_frame_offset: u64,
+    second_frame_offset: Option<u64>,
+    current_frame: Option<u32>,
+    frame: Bitmap,
+    palette: Palette,
+}
+
+impl FlicPlayer<BufReader<File>> {
+    pub fn load_file(path: &Path) -> Result<Self, FlicError> {
+        let f = File::open(path)?;
+        FlicPlayer::new(BufReader::new(f))
+    }
+}
+
+impl<R: Read + Seek> FlicPlayer<R> {
+    /// Prepares an FLI or FLC animation for playback. No frames are decoded until the first
+    /// call to [FlicPlayer::advance] or [FlicPlayer::next_frame].
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to stream the animation from
+    ///
+    /// returns: `Result<FlicPlayer<R>, FlicError>`
+    pub fn new(mut reader: R) -> Result<Self, FlicError> {
+        let mut header = [0u8; 128];
+        reader.read_exact(&mut header)?;
+        let mut header_reader = &header[..];
+
+        let _file_size = header_reader.read_u32::<LittleEndian>()?;
+        let magic = header_reader.read_u16::<LittleEndian>()?;
+        if magic != FLI_MAGIC && magic != FLC_MAGIC {
+            return Err(FlicError::BadFile(String::from(
+                "Unexpected magic number, probably not an FLI/FLC file",
+            )));
+        }
+        let num_frames = header_reader.read_u16::<LittleEndian>()? as u32;
+        let width = header_reader.read_u16::<LittleEndian>()? as u32;
+        let height = header_reader.read_u16::<LittleEndian>()? as u32;
+        let depth = header_reader.read_u16::<LittleEndian>()?;
+        let _flags = header_reader.read_u16::<LittleEndian>()?;
+        let speed = header_reader.read_u32::<LittleEndian>()?;
+        if depth != 8 {
+            return Err(FlicError::BadFile(String::from(
+                "Only 8-bit FLI/FLC files are supported",
+            )));
+        }
+        if num_frames == 0 {
+            return Err(FlicError::BadFile(String::from("No frames")));
+        }
+
+        let frame = match Bitmap::new(width, height) {
+            Ok(frame) => frame,
+            Err(_) => {
+                return Err(FlicError::BadFile(String::from("Invalid dimensions")));
+            }
+        };
+
+        let (frame_delay_ms, first_frame_offset, second_frame_offset) = if magic == FLI_MAGIC {
+            // FLI speed is in 1/70th of a second "jiffies"
+            (speed * 1000 / 70, 128, None)
+        } else {
+            let mut offsets = &header[80..88];
+            let first = offsets.read_u32::<LittleEndian>()? as u64;
+            let second = offsets.read_u32::<LittleEndian>()? as u64;
+            (
+                speed,
+                if first == 0 { 128 } else { first },
+                if second == 0 { None } else { Some(second) },
+            )
+        };
+
+        Ok(FlicPlayer {
+            reader,
+            width,
+            height,
+            num_frames,
+            frame_delay_ms,
+            first_frame_offset,
+            second_frame_offset,
+            current_frame: None,
+            frame,
+            palette: Palette::new_with_default(0, 0, 0),
+        })
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn num_frames(&self) -> u32 {
+        self.num_frames
+    }
+
+    /// The default delay between frames in milliseconds. Individual frames in FLC files can
+    /// override this, see [FlicPlayer::advance].
+    #[inline]
+    pub fn frame_delay_ms(&self) -> u32 {
+        self.frame_delay_ms
+    }
+
+    /// The index of the most recently decoded frame, or None if no frames have been decoded yet.
+    #[inline]
+    pub fn current_frame(&self) -> Option<u32> {
+        self.current_frame
+    }
+
+    /// The most recently decoded frame.
+    #[inline]
+    pub fn frame(&self) -> &Bitmap {
+        &self.frame
+    }
+
+    /// The palette as of the most recently decoded frame.
+    #[inline]
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Restarts playback so that the next frame decoded is the first one.
+    pub fn rewind(&mut self) {
+        self.current_frame = None;
+    }
+
+    /// Decodes the next frame of the animation, looping back to the first frame after the last
+    /// one. Uses the "ring frame" stored at the end of the file to do this, if present.
+    ///
+    /// returns: a tuple of the delay in milliseconds before the next frame should be shown, and
+    /// whether the palette was changed by this frame
+    pub fn advance(&mut self) -> Result<(u32, bool), FlicError> {
+        let next_frame = match self.current_frame {
+            Some(frame) if frame + 1 < self.num_frames => frame + 1,
+            Some(_) => {
+                // the ring frame, if present, turns the last frame back into the first frame
+                let position = self.reader.stream_position()?;
+                let end = self.reader.seek(SeekFrom::End(0))?;
+                self.reader.seek(SeekFrom::Start(position))?;
+                if position < end {
+                    let result = self.read_frame()?;
+                    if let Some(offset) = self.second_frame_offset {
+                        self.reader.seek(SeekFrom::Start(offset))?;
+                    }
+                    self.current_frame = Some(0);
+                    return Ok(result);
+                }
+                self.restart()?;
+                0
+            }
+            None => {
+                self.restart()?;
+                0
+            }
+        };
+
+        let result = self.read_frame()?;
+        if next_frame == 0 && self.second_frame_offset.is_none() {
+            self.second_frame_offset = Some(self.reader.stream_position()?);
+        }
+        self.current_frame = Some(next_frame);
+        Ok(result)
+    }
+
+    /// Decodes the next frame of the animation (see [FlicPlayer::advance]) and draws it to the
+    /// top-left of the given destination bitmap, also copying the animation's palette to the
+    /// given palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest`: the bitmap to draw the decoded frame to
+    /// * `palette`: the palette to update with the animation's palette
+    ///
+    /// returns: a tuple of the delay in milliseconds before the next frame should be shown, and
+    /// whether the palette was changed by this frame
+    pub fn next_frame<P: PixelBuffer>(
+        &mut self,
+        dest: &mut Bitmap<P>,
+        palette: &mut Palette,
+    ) -> Result<(u32, bool), FlicError> {
+        let result = self.advance()?;
+        dest.blit(BlitMethod::Solid, &self.frame, 0, 0);
+        *palette = self.palette.clone();
+        Ok(result)
+    }
+
+    fn restart(&mut self) -> Result<(), FlicError> {
+        self.reader.seek(SeekFrom::Start(self.first_frame_offset))?;
+        self.frame.clear(0);
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<(u32, bool), FlicError> {
+        // skip over any prefix chunks
+        let (size, data) = loop {
+            let size = self.reader.read_u32::<LittleEndian>()?;
+            let chunk_type = self.reader.read_u16::<LittleEndian>()?;
+            if size < 6 {
+                return Err(FlicError::BadFile(String::from("Invalid chunk size")));
+            }
+            // read only as much as is actually present, rather than trusting the chunk's size
+            let mut data = Vec::new();
+            (&mut self.reader)
+                .take(size as u64 - 6)
+                .read_to_end(&mut data)?;
+            if data.len() != size as usize - 6 {
+                return Err(FlicError::BadFile(String::from("Truncated chunk")));
+            }
+            match chunk_type {
+                FRAME_MAGIC => break (size, data),
+                PREFIX_MAGIC => continue,
+                _ => return Err(FlicError::BadFile(String::from("Expected a frame chunk"))),
+            }
+        };
+        if size < 16 {
+            return Err(FlicError::BadFile(String::from("Invalid frame size")));
+        }
+
+        let mut reader = &data[..];
+        let num_chunks = reader.read_u16::<LittleEndian>()?;
+        let delay = match reader.read_u16::<LittleEndian>()? {
+            0 => self.frame_delay_ms,
+            delay => delay as u32,
+        };
+        let mut skipped = [0u8; 6];
+        reader.read_exact(&mut skipped)?;
+
+        let mut frame = FrameWriter {
+            pixels: self.frame.pixels_mut(),
+            width: self.width as usize,
+            height: self.height as usize,
+        };
+        let mut palette_changed = false;
+        for _ in 0..num_chunks {
+            let chunk_size = reader.read_u32::<LittleEndian>()? as usize;
+            let chunk_type = reader.read_u16::<LittleEndian>()?;
+            if chunk_size < 6 || chunk_size - 6 > reader.len() {
+                return Err(FlicError::BadFile(String::from("Invalid chunk size")));
+            }
+            let (chunk, rest) = reader.split_at(chunk_size - 6);
+            reader = rest;
+
+            match chunk_type {
+                CHUNK_COLOR_256 => {
+                    read_color_chunk(chunk, &mut self.palette, false)?;
+                    palette_changed = true;
+                }
+                CHUNK_COLOR_64 => {
+                    read_color_chunk(chunk, &mut self.palette, true)?;
+                    palette_changed = true;
+                }
+                CHUNK_DELTA_FLC => read_delta_flc_chunk(chunk, &mut frame)?,
+                CHUNK_DELTA_FLI => read_delta_fli_chunk(chunk, &mut frame)?,
+                CHUNK_BLACK => frame.pixels.fill(0),
+                CHUNK_BYTE_RUN => read_byte_run_chunk(chunk, &mut frame)?,
+                CHUNK_FLI_COPY => {
+                    let mut chunk = chunk;
+                    chunk.read_exact(frame.pixels)?;
+                }
+                // postage stamps and anything else we don't know about can be skipped
+                _ => {}
+            }
+        }
+
+        Ok((delay, palette_changed))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+
+    use super::*;
+
+    fn expected_frames() -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        let mut frame: Vec<u8> = (0..32).map(|i| (i % 8) as u8 + 1).collect();
+        frames.push(frame.clone());
+        frame[8..12].copy_from_slice(&[20, 21, 20, 21]);
+        frame[7] = 30;
+        frame[31] = 31;
+        frames.push(frame.clone());
+        frame[17..20].copy_from_slice(&[40, 41, 42]);
+        frame[26..30].fill(50);
+        frames.push(frame);
+        frames
+    }
+
+    #[test]
+    pub fn play_flc() -> Result<(), FlicError> {
+        let expected = expected_frames();
+        let mut player = FlicPlayer::load_file(Path::new("./test-assets/test.flc"))?;
+        assert_eq!(8, player.width());
+        assert_eq!(4, player.height());
+        assert_eq!(3, player.num_frames());
+        assert_eq!(50, player.frame_delay_ms());
+        assert_eq!(None, player.current_frame());
+
+        let mut dest = Bitmap::new(16, 16).unwrap();
+        let mut palette = Palette::new();
+
+        assert_eq!((50, true), player.next_frame(&mut dest, &mut palette)?);
+        assert_eq!(Some(0), player.current_frame());
+        assert_eq!(to_rgb32(255, 0, 0), palette[1]);
+        assert_eq!(to_rgb32(0, 0, 255), palette[3]);
+        assert_eq!(expected[0], player.frame().pixels());
+        assert_eq!(Some(1), dest.get_pixel(0, 0));
+        assert_eq!(Some(8), dest.get_pixel(7, 3));
+        assert_eq!(Some(0), dest.get_pixel(8, 0));
+
+        assert_eq!((50, false), player.advance()?);
+        assert_eq!(expected[1], player.frame().pixels());
+        assert_eq!((120, false), player.advance()?);
+        assert_eq!(Some(2), player.current_frame());
+        assert_eq!(expected[2], player.frame().pixels());
+
+        // loops back around via the ring frame
+        for i in 0..6 {
+            player.advance()?;
+            assert_eq!(Some(i % 3), player.current_frame());
+            assert_eq!(expected[i as usize % 3], player.frame().pixels());
+        }
+
+        player.rewind();
+        player.advance()?;
+        assert_eq!(Some(0), player.current_frame());
+        assert_eq!(expected[0], player.frame().pixels());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn play_fli() -> Result<(), FlicError> {
+        let mut player = FlicPlayer::load_file(Path::new("./test-assets/test.fli"))?;
+        assert_eq!(4, player.width());
+        assert_eq!(2, player.height());
+        assert_eq!(2, player.num_frames());
+        assert_eq!(100, player.frame_delay_ms());
+
+        assert_eq!((100, true), player.advance()?);
+        assert_eq!(to_rgb32(255, 0, 0), player.palette()[1]);
+        assert_eq!(to_rgb32(0, 0, 0), player.palette()[2]);
+        assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], player.frame().pixels());
+        assert_eq!((100, false), player.advance()?);
+        assert_eq!(&[0; 8], player.frame().pixels());
+
+        // no ring frame in this file, so it is restarted from the beginning
+        player.advance()?;
+        assert_eq!(Some(0), player.current_frame());
+        assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], player.frame().pixels());
+        player.advance()?;
+        assert_eq!(Some(1), player.current_frame());
+        assert_eq!(&[0; 8], player.frame().pixels());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_bad_file() {
+        let data = std::io::Cursor::new(vec![0u8; 200]);
+        assert_matches!(FlicPlayer::new(data), Err(FlicError::BadFile(..)));
+    }
+
+    #[test]
+    pub fn play_file_with_bad_chunk_size() -> Result<(), FlicError> {
+        let mut data = std::fs::read("./test-assets/test.fli")?;
+        // the size of the first frame chunk, which immediately follows the header
+        data[128..132].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut player = FlicPlayer::new(std::io::Cursor::new(data))?;
+        assert_matches!(player.advance(), Err(FlicError::BadFile(..)));
+        Ok(())
+    }
+}
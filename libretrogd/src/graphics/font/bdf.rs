@@ -0,0 +1,268 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use byteorder::ReadBytesExt;
+
+use crate::graphics::*;
+
+/// A single glyph as defined in a BDF font file, before being positioned within a character cell.
+struct BdfGlyph {
+    encoding: i32,
+    advance: i32,
+    width: i32,
+    height: i32,
+    x_offset: i32,
+    y_offset: i32,
+    rows: Vec<Vec<u8>>,
+}
+
+fn parse_bdf_numbers(values: &str, line_number: usize) -> Result<Vec<i32>, FontError> {
+    values
+        .split_whitespace()
+        .map(|value| value.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| FontError::InvalidFile(format!("Invalid number on line {}", line_number)))
+}
+
+fn parse_bdf_hex_row(row: &str, line_number: usize) -> Result<Vec<u8>, FontError> {
+    let row = row.as_bytes();
+    if !row.len().is_multiple_of(2) {
+        return Err(FontError::InvalidFile(format!(
+            "Invalid bitmap data on line {}",
+            line_number
+        )));
+    }
+    row.chunks(2)
+        .map(|digits| {
+            std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| {
+                    FontError::InvalidFile(format!("Invalid bitmap data on line {}", line_number))
+                })
+        })
+        .collect()
+}
+
+impl BitmaskFont {
+    /// Loads a font from a BDF (Glyph Bitmap Distribution Format) font file. Characters 0-255 are
+    /// treated as Latin-1, and any characters beyond those are mapped to the Unicode characters
+    /// they are encoded as. Characters not defined by the font are rendered using the font's
+    /// `DEFAULT_CHAR` if it has one, or otherwise as blank characters.
+    pub fn load_bdf_file(path: &Path) -> Result<BitmaskFont, FontError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+
+        BitmaskFont::load_bdf_from_bytes(&mut reader)
+    }
+
+    /// Loads a font from the text of a BDF (Glyph Bitmap Distribution Format) font file. See
+    /// [BitmaskFont::load_bdf_file] for more details.
+    pub fn load_bdf_from_bytes<T: ReadBytesExt>(reader: &mut T) -> Result<BitmaskFont, FontError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut lines = text.lines().map(|line| line.trim()).enumerate();
+
+        match lines.next() {
+            Some((_, line)) if line.starts_with("STARTFONT") => (),
+            _ => {
+                return Err(FontError::InvalidFile(String::from(
+                    "Missing STARTFONT header",
+                )))
+            }
+        }
+
+        let mut bounding_box = None;
+        let mut ascent = None;
+        let mut descent = None;
+        let mut default_char = None;
+        let mut glyphs = Vec::new();
+        let mut glyph: Option<BdfGlyph> = None;
+
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let (keyword, values) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match keyword {
+                "FONTBOUNDINGBOX" => bounding_box = Some(parse_bdf_numbers(values, line_number)?),
+                "FONT_ASCENT" => ascent = parse_bdf_numbers(values, line_number)?.first().copied(),
+                "FONT_DESCENT" => {
+                    descent = parse_bdf_numbers(values, line_number)?.first().copied()
+                }
+                "DEFAULT_CHAR" => {
+                    default_char = parse_bdf_numbers(values, line_number)?.first().copied()
+                }
+                "STARTCHAR" => {
+                    glyph = Some(BdfGlyph {
+                        encoding: -1,
+                        advance: 0,
+                        width: 0,
+                        height: 0,
+                        x_offset: 0,
+                        y_offset: 0,
+                        rows: Vec::new(),
+                    })
+                }
+                "ENCODING" | "DWIDTH" | "BBX" | "BITMAP" | "ENDCHAR" => {
+                    let current = glyph.as_mut().ok_or_else(|| {
+                        FontError::InvalidFile(format!(
+                            "{} outside of a character on line {}",
+                            keyword, line_number
+                        ))
+                    })?;
+                    let numbers = parse_bdf_numbers(values, line_number)?;
+                    match (keyword, numbers.as_slice()) {
+                        ("ENCODING", [encoding, ..]) => current.encoding = *encoding,
+                        ("DWIDTH", [advance, ..]) => current.advance = *advance,
+                        ("BBX", [width, height, x_offset, y_offset]) => {
+                            current.width = *width;
+                            current.height = *height;
+                            current.x_offset = *x_offset;
+                            current.y_offset = *y_offset;
+                        }
+                        ("BITMAP", _) => {
+                            for _ in 0..current.height {
+                                match lines.next() {
+                                    Some((index, row)) => {
+                                        current.rows.push(parse_bdf_hex_row(row, index + 1)?)
+                                    }
+                                    None => {
+                                        return Err(FontError::InvalidFile(String::from(
+                                            "Unexpected end of bitmap data",
+                                        )))
+                                    }
+                                }
+                            }
+                        }
+                        ("ENDCHAR", _) => glyphs.extend(glyph.take()),
+                        _ => {
+                            return Err(FontError::InvalidFile(format!(
+                                "Invalid {} on line {}",
+                                keyword, line_number
+                            )))
+                        }
+                    }
+                }
+                "ENDFONT" => break,
+                _ => (),
+            }
+        }
+
+        let bounding_box = match bounding_box.as_deref() {
+            Some(&[_, height, _, y_offset]) => (height, y_offset),
+            _ => {
+                return Err(FontError::InvalidFile(String::from(
+                    "Missing or invalid FONTBOUNDINGBOX",
+                )))
+            }
+        };
+        let ascent = ascent.unwrap_or(bounding_box.0 + bounding_box.1);
+        let descent = descent.unwrap_or(-bounding_box.1);
+        let line_height = ascent + descent;
+        if line_height <= 0 || line_height > u8::MAX as i32 {
+            return Err(FontError::InvalidFile(format!(
+                "Unsupported line height: {}",
+                line_height
+            )));
+        }
+
+        let mut characters: Vec<Option<BitmaskCharacter>> = vec![None; NUM_CHARS];
+        let mut extra_characters = Vec::new();
+        for glyph in glyphs.iter() {
+            if glyph.encoding < 0 {
+                continue;
+            }
+
+            // position the glyph within a character cell that is the full height of the font with
+            // the baseline at the same place for every character
+            let pixel_width = (glyph.x_offset + glyph.width).max(0) as usize;
+            let bytes_per_row = pixel_width.div_ceil(8).max(1);
+            let mut bytes = vec![0u8; bytes_per_row * line_height as usize];
+            let top = ascent - (glyph.y_offset + glyph.height);
+            for (glyph_y, row) in glyph.rows.iter().enumerate() {
+                let y = top + glyph_y as i32;
+                if y < 0 || y >= line_height {
+                    continue;
+                }
+                for glyph_x in 0..glyph.width {
+                    let x = glyph.x_offset + glyph_x;
+                    let is_set = row
+                        .get(glyph_x as usize / 8)
+                        .is_some_and(|byte| byte & (0x80 >> (glyph_x % 8)) != 0);
+                    if x >= 0 && is_set {
+                        bytes[y as usize * bytes_per_row + x as usize / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+            }
+            let character = BitmaskCharacter::from_rows(
+                bytes,
+                bytes_per_row,
+                pixel_width as u32,
+                glyph.advance.max(0) as u32,
+            );
+            if glyph.encoding < NUM_CHARS as i32 {
+                characters[glyph.encoding as usize] = Some(character);
+            } else if let Some(ch) = char::from_u32(glyph.encoding as u32) {
+                extra_characters.push((ch, character));
+            }
+        }
+
+        let fallback = match default_char {
+            Some(default_char) if (0..NUM_CHARS as i32).contains(&default_char) => {
+                characters[default_char as usize].clone()
+            }
+            _ => None,
+        }
+        .unwrap_or_else(|| BitmaskCharacter::from_rows(vec![0], 1, 0, 0));
+        let characters = characters
+            .into_iter()
+            .map(|character| character.unwrap_or_else(|| fallback.clone()))
+            .collect();
+
+        let mut font = BitmaskFont::from_characters(characters, line_height as u8);
+        for (ch, character) in extra_characters {
+            font.add_character(ch, character);
+        }
+        match default_char.and_then(|default_char| char::from_u32(default_char as u32)) {
+            Some(default_char) => font.set_fallback_char(default_char),
+            None => font.set_fallback_char(' '),
+        }
+        Ok(font)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    #[test]
+    pub fn load_bdf_font() -> Result<(), FontError> {
+        let font = BitmaskFont::load_bdf_file(Path::new("./test-assets/test.bdf"))?;
+        assert_eq!(8, font.line_height());
+        assert_eq!(4, font.space_width());
+        assert_eq!((10, 8), font.measure("Ag", FontRenderOpts::None));
+        // undefined characters use the DEFAULT_CHAR, which is 'A' in this font
+        assert_eq!(font.character('A'), font.character('Z'));
+        assert_eq!(font.character('A'), font.character('€'));
+        // characters beyond the first 256 are mapped by their unicode encoding
+        assert!(font.has_character('Ā'));
+        assert_eq!((5, 8), font.measure("Ā", FontRenderOpts::None));
+
+        let mut bmp = Bitmap::new(10, 8).unwrap();
+        bmp.print_string("Ag", 0, 0, FontRenderOpts::Color(1), &font);
+        // top of the 'A', which sits on the baseline at y = 5
+        assert_eq!(Some(1), bmp.get_pixel(1, 1));
+        assert_eq!(Some(0), bmp.get_pixel(0, 1));
+        assert_eq!(Some(1), bmp.get_pixel(0, 5));
+        // the 'g' descends below the baseline
+        assert_eq!(Some(1), bmp.get_pixel(8, 7));
+        assert_eq!(Some(0), bmp.get_pixel(5, 7));
+
+        let result = BitmaskFont::load_bdf_from_bytes(&mut "not a bdf font".as_bytes());
+        assert_matches!(result, Err(FontError::InvalidFile(..)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use byteorder::ReadBytesExt;
+
+use crate::graphics::*;
+
+/// The largest character height supported by [BitmaskFont::load_raw_file].
+pub const MAX_RAW_CHAR_HEIGHT: u8 = 32;
+
+impl BitmaskFont {
+    /// Loads a raw 256 character, 8 pixel wide font, such as the 8x8, 8x14 and 8x16 fonts dumped
+    /// from a VGA BIOS or used by DOS programs. The character height is determined from the size
+    /// of the file, which must be an exact multiple of 256 bytes.
+    pub fn load_raw_file(path: &Path) -> Result<BitmaskFont, FontError> {
+        let f = File::open(path)?;
+        let length = f.metadata()?.len();
+        if length == 0
+            || length % NUM_CHARS as u64 != 0
+            || length / NUM_CHARS as u64 > MAX_RAW_CHAR_HEIGHT as u64
+        {
+            return Err(FontError::InvalidFile(format!(
+                "Raw font file size of {} bytes does not match any supported character height",
+                length
+            )));
+        }
+        let char_height = (length / NUM_CHARS as u64) as u8;
+        let mut reader = BufReader::new(f);
+
+        BitmaskFont::load_raw_from_bytes(&mut reader, char_height)
+    }
+
+    /// Loads a raw 256 character, 8 pixel wide font, such as the 8x8, 8x14 and 8x16 fonts dumped
+    /// from a VGA BIOS or used by DOS programs.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the raw font data, which is `char_height` bytes per character, one byte per row
+    /// * `char_height`: the height in pixels of each character
+    pub fn load_raw_from_bytes<T: ReadBytesExt>(
+        reader: &mut T,
+        char_height: u8,
+    ) -> Result<BitmaskFont, FontError> {
+        if char_height == 0 {
+            return Err(FontError::InvalidFile(String::from(
+                "Character height must be at least 1",
+            )));
+        }
+
+        let mut characters = Vec::with_capacity(NUM_CHARS);
+        for _ in 0..NUM_CHARS {
+            let mut bytes = vec![0u8; char_height as usize];
+            reader.read_exact(&mut bytes)?;
+            characters.push(BitmaskCharacter::from_rows(
+                bytes,
+                1,
+                CHAR_FIXED_WIDTH as u32,
+                CHAR_FIXED_WIDTH as u32,
+            ));
+        }
+
+        Ok(BitmaskFont::from_characters(characters, char_height))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+
+    use claim::*;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    pub fn load_raw_font() -> Result<(), FontError> {
+        // a fake 8x16 font where every character is a box outline
+        let mut glyph = [0x81u8; 16];
+        glyph[0] = 0xff;
+        glyph[15] = 0xff;
+        let data = glyph.repeat(NUM_CHARS);
+
+        let font = BitmaskFont::load_raw_from_bytes(&mut Cursor::new(&data), 16)?;
+        assert_eq!(16, font.line_height());
+        assert_eq!(8, font.space_width());
+        assert_eq!((16, 16), font.measure("AB", FontRenderOpts::None));
+
+        let mut bmp = Bitmap::new(8, 16).unwrap();
+        bmp.print_char('A', 0, 0, FontRenderOpts::Color(1), &font);
+        assert_eq!(Some(1), bmp.get_pixel(3, 0));
+        assert_eq!(Some(1), bmp.get_pixel(0, 8));
+        assert_eq!(Some(0), bmp.get_pixel(3, 8));
+        assert_eq!(Some(1), bmp.get_pixel(7, 15));
+
+        // too tall to be saved in the standard font format
+        assert_matches!(
+            font.to_bytes(&mut Vec::new()),
+            Err(FontError::UnsupportedFormat(..))
+        );
+
+        let tmp_dir = TempDir::new()?;
+        let path = tmp_dir.path().join("test_8x14.fnt");
+        std::fs::write(&path, &data[0..14 * NUM_CHARS])?;
+        let font = BitmaskFont::load_raw_file(&path)?;
+        assert_eq!(14, font.line_height());
+
+        std::fs::write(&path, &data[0..100])?;
+        assert_matches!(
+            BitmaskFont::load_raw_file(&path),
+            Err(FontError::InvalidFile(..))
+        );
+
+        Ok(())
+    }
+}
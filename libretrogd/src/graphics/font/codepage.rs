@@ -0,0 +1,82 @@
+/// A character set (or "code page") which maps each of the 256 characters in a font to a Unicode
+/// character, allowing text containing those Unicode characters to be rendered with the font.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Codepage {
+    /// ISO-8859-1 ("Latin-1"), where characters 0-255 are the same as Unicode code points 0-255.
+    /// This is the default for all fonts.
+    Latin1,
+    /// IBM PC code page 437, the original character set of the IBM PC and the VGA BIOS font,
+    /// including box drawing, block and line characters. Characters 0-31 and 127 map to their
+    /// graphical symbols (smiley faces, card suits, arrows, etc) rather than control characters.
+    Cp437,
+}
+
+impl Codepage {
+    /// Returns the Unicode character for the given font character index in this code page.
+    pub fn char_at(&self, index: u8) -> char {
+        match self {
+            Codepage::Latin1 => char::from(index),
+            Codepage::Cp437 => CP437_CHARS[index as usize],
+        }
+    }
+
+    /// Returns the font character index for the given Unicode character in this code page, or None
+    /// if this code page does not include that character.
+    pub fn index_of(&self, ch: char) -> Option<u8> {
+        match self {
+            Codepage::Latin1 => u8::try_from(ch).ok(),
+            // printable ascii characters are the same in both, so skip searching for them
+            Codepage::Cp437 if (' '..='~').contains(&ch) => Some(ch as u8),
+            Codepage::Cp437 => CP437_CHARS
+                .iter()
+                .position(|c| *c == ch)
+                .map(|index| index as u8),
+        }
+    }
+}
+
+#[rustfmt::skip]
+static CP437_CHARS: [char; 256] = [
+    '\0', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+    ' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?',
+    '@', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O',
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '[', '\\', ']', '^', '_',
+    '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~', '⌂',
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn codepage_mappings() {
+        for index in 0..=255 {
+            assert_eq!(
+                Some(index),
+                Codepage::Latin1.index_of(Codepage::Latin1.char_at(index))
+            );
+            assert_eq!(
+                Some(index),
+                Codepage::Cp437.index_of(Codepage::Cp437.char_at(index))
+            );
+        }
+        assert_eq!('A', Codepage::Cp437.char_at(b'A'));
+        assert_eq!('│', Codepage::Cp437.char_at(0xb3));
+        assert_eq!('é', Codepage::Latin1.char_at(0xe9));
+        assert_eq!(Some(0x82), Codepage::Cp437.index_of('é'));
+        assert_eq!(Some(0xdb), Codepage::Cp437.index_of('█'));
+        assert_eq!(None, Codepage::Cp437.index_of('³'));
+        assert_eq!(None, Codepage::Latin1.index_of('█'));
+    }
+}
@@ -0,0 +1,238 @@
+use std::path::Path;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::graphics::*;
+
+const NE_RESOURCE_TYPE_FONT: u16 = 0x8008;
+
+fn read_u8_at(bytes: &[u8], offset: usize) -> Result<u8, FontError> {
+    bytes
+        .get(offset)
+        .copied()
+        .ok_or_else(|| FontError::InvalidFile(String::from("Unexpected end of font data")))
+}
+
+fn read_u16_at(bytes: &[u8], offset: usize) -> Result<u16, FontError> {
+    match bytes.get(offset..offset + 2) {
+        Some(bytes) => Ok(LittleEndian::read_u16(bytes)),
+        None => Err(FontError::InvalidFile(String::from(
+            "Unexpected end of font data",
+        ))),
+    }
+}
+
+fn read_u32_at(bytes: &[u8], offset: usize) -> Result<u32, FontError> {
+    match bytes.get(offset..offset + 4) {
+        Some(bytes) => Ok(LittleEndian::read_u32(bytes)),
+        None => Err(FontError::InvalidFile(String::from(
+            "Unexpected end of font data",
+        ))),
+    }
+}
+
+/// Returns the location (offset and length) of every font resource found in the resource table
+/// of a Windows "New Executable" (NE) format file.
+fn find_ne_font_resources(bytes: &[u8]) -> Result<Vec<(usize, usize)>, FontError> {
+    if bytes.get(0..2) != Some(b"MZ") {
+        return Err(FontError::InvalidFile(String::from(
+            "Missing MZ executable header",
+        )));
+    }
+    let ne_offset = read_u32_at(bytes, 0x3c)? as usize;
+    if bytes.get(ne_offset..ne_offset + 2) != Some(b"NE") {
+        return Err(FontError::InvalidFile(String::from(
+            "Missing NE executable header",
+        )));
+    }
+
+    let mut offset = ne_offset + read_u16_at(bytes, ne_offset + 0x24)? as usize;
+    let alignment_shift = read_u16_at(bytes, offset)?;
+    if alignment_shift > 16 {
+        return Err(FontError::InvalidFile(format!(
+            "Invalid resource alignment shift: {}",
+            alignment_shift
+        )));
+    }
+    offset += 2;
+
+    let mut resources = Vec::new();
+    loop {
+        let type_id = read_u16_at(bytes, offset)?;
+        if type_id == 0 {
+            break;
+        }
+        let count = read_u16_at(bytes, offset + 2)? as usize;
+        offset += 8;
+        for _ in 0..count {
+            if type_id == NE_RESOURCE_TYPE_FONT {
+                let resource_offset = (read_u16_at(bytes, offset)? as usize) << alignment_shift;
+                let resource_length = (read_u16_at(bytes, offset + 2)? as usize) << alignment_shift;
+                resources.push((resource_offset, resource_length));
+            }
+            offset += 12;
+        }
+    }
+    Ok(resources)
+}
+
+impl BitmaskFont {
+    /// Loads all of the fonts found in a Windows .FON font file. These files typically contain the
+    /// same typeface at several different sizes. Only bitmap (raster) fonts are supported.
+    pub fn load_fon_file(path: &Path) -> Result<Vec<BitmaskFont>, FontError> {
+        let bytes = std::fs::read(path)?;
+        BitmaskFont::load_fon_from_bytes(&bytes)
+    }
+
+    /// Loads all of the fonts found in the bytes of a Windows .FON font file. These files
+    /// typically contain the same typeface at several different sizes. Only bitmap (raster) fonts
+    /// are supported.
+    pub fn load_fon_from_bytes(bytes: &[u8]) -> Result<Vec<BitmaskFont>, FontError> {
+        let resources = find_ne_font_resources(bytes)?;
+        if resources.is_empty() {
+            return Err(FontError::InvalidFile(String::from(
+                "No font resources found",
+            )));
+        }
+        resources
+            .into_iter()
+            .map(
+                |(offset, length)| match bytes.get(offset..offset + length) {
+                    Some(resource) => BitmaskFont::load_winfnt_from_bytes(resource),
+                    None => Err(FontError::InvalidFile(String::from(
+                        "Font resource lies outside of the file",
+                    ))),
+                },
+            )
+            .collect()
+    }
+
+    /// Loads a single Windows bitmap (raster) font from the bytes of a version 2.0 or 3.0 Windows
+    /// .FNT font resource, as found inside .FON files. Characters outside of the range of
+    /// characters defined by the font are rendered using the font's default character.
+    pub fn load_winfnt_from_bytes(bytes: &[u8]) -> Result<BitmaskFont, FontError> {
+        let version = read_u16_at(bytes, 0)?;
+        let char_table_offset = match version {
+            0x0200 => 118,
+            0x0300 => 148,
+            _ => {
+                return Err(FontError::InvalidFile(format!(
+                    "Unsupported FNT version: {:#06x}",
+                    version
+                )))
+            }
+        };
+        let font_type = read_u16_at(bytes, 66)?;
+        if font_type & 1 != 0 {
+            return Err(FontError::InvalidFile(String::from(
+                "Vector fonts are not supported",
+            )));
+        }
+        let height = read_u16_at(bytes, 88)?;
+        if height == 0 || height > u8::MAX as u16 {
+            return Err(FontError::InvalidFile(format!(
+                "Unsupported character height: {}",
+                height
+            )));
+        }
+        let first_char = read_u8_at(bytes, 95)?;
+        let last_char = read_u8_at(bytes, 96)?;
+        if last_char < first_char {
+            return Err(FontError::InvalidFile(String::from(
+                "Invalid character range",
+            )));
+        }
+        let default_char = first_char
+            .saturating_add(read_u8_at(bytes, 97)?)
+            .min(last_char);
+
+        let mut glyphs = Vec::with_capacity((last_char - first_char) as usize + 1);
+        for i in 0..=(last_char - first_char) as usize {
+            let (width, offset) = if version == 0x0200 {
+                let entry = char_table_offset + i * 4;
+                (
+                    read_u16_at(bytes, entry)? as usize,
+                    read_u16_at(bytes, entry + 2)? as usize,
+                )
+            } else {
+                let entry = char_table_offset + i * 6;
+                (
+                    read_u16_at(bytes, entry)? as usize,
+                    read_u32_at(bytes, entry + 2)? as usize,
+                )
+            };
+
+            // glyph bitmaps are stored as columns of bytes, each 8 pixels wide and the full height
+            // of the font, from left to right
+            let bytes_per_row = width.div_ceil(8).max(1);
+            let mut rows = vec![0u8; bytes_per_row * height as usize];
+            for column in 0..width.div_ceil(8) {
+                for y in 0..height as usize {
+                    rows[y * bytes_per_row + column] =
+                        read_u8_at(bytes, offset + column * height as usize + y)?;
+                }
+            }
+            glyphs.push(BitmaskCharacter::from_rows(
+                rows,
+                bytes_per_row,
+                width as u32,
+                width as u32,
+            ));
+        }
+
+        let characters = (0..NUM_CHARS)
+            .map(|ch| {
+                if (first_char as usize..=last_char as usize).contains(&ch) {
+                    glyphs[ch - first_char as usize].clone()
+                } else {
+                    glyphs[(default_char - first_char) as usize].clone()
+                }
+            })
+            .collect();
+
+        Ok(BitmaskFont::from_characters(characters, height as u8))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    #[test]
+    pub fn load_fon_font() -> Result<(), FontError> {
+        let fonts = BitmaskFont::load_fon_file(Path::new("./test-assets/test.fon"))?;
+        assert_eq!(1, fonts.len());
+        let font = &fonts[0];
+        assert_eq!(8, font.line_height());
+        assert_eq!(8, font.space_width());
+        assert_eq!((18, 8), font.measure("AW", FontRenderOpts::None));
+        // characters outside of the font's range use the default character
+        assert_eq!(font.character('?'), font.character('\u{1}'));
+
+        // glyphs match those of the vga font they were taken from, except for the wider 'W'
+        let vga_font = BitmaskFont::new_vga_font()?;
+        let mut expected = Bitmap::new(8, 8).unwrap();
+        let mut bmp = Bitmap::new(8, 8).unwrap();
+        expected.print_char('A', 0, 0, FontRenderOpts::Color(1), &vga_font);
+        bmp.print_char('A', 0, 0, FontRenderOpts::Color(1), font);
+        assert_eq!(expected.pixels(), bmp.pixels());
+
+        let mut bmp = Bitmap::new(10, 8).unwrap();
+        bmp.print_char('W', 0, 0, FontRenderOpts::Color(1), font);
+        assert_eq!(Some(1), bmp.get_pixel(8, 0));
+        assert_eq!(Some(1), bmp.get_pixel(9, 7));
+
+        assert_matches!(
+            BitmaskFont::load_fon_from_bytes(&[0u8; 64]),
+            Err(FontError::InvalidFile(..))
+        );
+        assert_matches!(
+            BitmaskFont::load_winfnt_from_bytes(&[0u8; 8]),
+            Err(FontError::InvalidFile(..))
+        );
+
+        Ok(())
+    }
+}
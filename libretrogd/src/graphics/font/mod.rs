@@ -0,0 +1,800 @@
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor};
+use std::path::Path;
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use thiserror::Error;
+
+use crate::graphics::*;
+use crate::math::*;
+
+pub use self::bios::*;
+pub use self::codepage::*;
+#[cfg(feature = "ttf")]
+pub use self::ttf::*;
+
+pub mod bdf;
+pub mod bios;
+pub mod codepage;
+pub mod fon;
+#[cfg(feature = "ttf")]
+pub mod ttf;
+
+pub static VGA_FONT_BYTES: &[u8] = include_bytes!("../../../assets/vga.fnt");
+
+pub const NUM_CHARS: usize = 256;
+pub const CHAR_HEIGHT: usize = 8;
+pub const CHAR_FIXED_WIDTH: usize = 8;
+
+#[derive(Error, Debug)]
+pub enum FontError {
+    #[error("Invalid font file: {0}")]
+    InvalidFile(String),
+
+    #[error("Font cannot be saved in this format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Font I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FontRenderOpts {
+    Color(u8),
+    /// Draws text with a drop-shadow behind it. The shadow is the text itself drawn using
+    /// `shadow_color`, offset by `x_offset` and `y_offset` pixels.
+    Shadow {
+        color: u8,
+        shadow_color: u8,
+        x_offset: i8,
+        y_offset: i8,
+    },
+    /// Draws text with a 1 pixel outline around it using `outline_color`.
+    Outline {
+        color: u8,
+        outline_color: u8,
+    },
+    None,
+}
+
+impl FontRenderOpts {
+    /// Returns the same render options with any shadow or outline removed, leaving only the
+    /// color of the text itself.
+    pub(crate) fn foreground(self) -> FontRenderOpts {
+        match self {
+            FontRenderOpts::Shadow { color, .. } => FontRenderOpts::Color(color),
+            FontRenderOpts::Outline { color, .. } => FontRenderOpts::Color(color),
+            otherwise => otherwise,
+        }
+    }
+
+    /// Returns the extra space in pixels, as left, top, right and bottom amounts, that text drawn
+    /// with these render options extends outside of the space taken up by the text itself.
+    pub(crate) fn extents(&self) -> (u32, u32, u32, u32) {
+        match *self {
+            FontRenderOpts::Shadow {
+                x_offset, y_offset, ..
+            } => {
+                let x_offset = x_offset as i32;
+                let y_offset = y_offset as i32;
+                (
+                    (-x_offset).max(0) as u32,
+                    (-y_offset).max(0) as u32,
+                    x_offset.max(0) as u32,
+                    y_offset.max(0) as u32,
+                )
+            }
+            FontRenderOpts::Outline { .. } => (1, 1, 1, 1),
+            _ => (0, 0, 0, 0),
+        }
+    }
+}
+
+/// A set of inline formatting codes which can be embedded in strings of text passed to
+/// [`Bitmap::print_string_with_codes`] to change how the remaining text is rendered part way
+/// through the string. [`Bitmap::print_string`] does not recognize any codes, and renders them
+/// as-is. Codes are enclosed in braces, and the following are always available:
+///
+/// * `{color:N}`: switches to drawing text with color N (0-255)
+/// * `{reset}`: switches back to the render options the string was originally printed with
+///
+/// Additional custom codes can be registered with [`TextCodes::register`]. For example,
+/// registering "highlight" would allow `{highlight}` to be used. Anything enclosed in braces which
+/// does not match any code is rendered as-is.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TextCodes {
+    codes: HashMap<String, FontRenderOpts>,
+}
+
+impl TextCodes {
+    pub fn new() -> TextCodes {
+        TextCodes {
+            codes: HashMap::new(),
+        }
+    }
+
+    /// Registers a custom code which switches to the given render options when used.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the code's name, used in text as `{name}`
+    /// * `opts`: the render options to switch to
+    pub fn register(&mut self, name: &str, opts: FontRenderOpts) {
+        self.codes.insert(name.to_string(), opts);
+    }
+
+    /// Removes a previously registered custom code, returning its render options if it existed.
+    pub fn unregister(&mut self, name: &str) -> Option<FontRenderOpts> {
+        self.codes.remove(name)
+    }
+
+    /// Returns the render options for the given custom code, if it has been registered.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<FontRenderOpts> {
+        self.codes.get(name).copied()
+    }
+
+    /// Attempts to parse a code found at the very beginning of the given text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: the text, which should begin with a code such as `{color:5}`
+    /// * `original_opts`: the render options the text was originally being rendered with
+    ///
+    /// returns: the render options to switch to and the length in bytes of the code, or None if
+    /// the text does not begin with a recognized code
+    pub fn parse_code(
+        &self,
+        text: &str,
+        original_opts: FontRenderOpts,
+    ) -> Option<(FontRenderOpts, usize)> {
+        let inner = text.strip_prefix('{')?;
+        let end = inner.find(['}', '{', '\n'])?;
+        if !inner[end..].starts_with('}') {
+            return None;
+        }
+        let code = &inner[..end];
+        let opts = if code == "reset" {
+            original_opts
+        } else if let Some(color) = code.strip_prefix("color:") {
+            FontRenderOpts::Color(color.trim().parse().ok()?)
+        } else {
+            self.get(code)?
+        };
+        Some((opts, end + 2))
+    }
+
+    /// Returns a copy of the given text with all recognized codes removed.
+    pub fn strip(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut remaining = text;
+        while let Some(ch) = remaining.chars().next() {
+            if ch == '{' {
+                if let Some((_, length)) = self.parse_code(remaining, FontRenderOpts::None) {
+                    remaining = &remaining[length..];
+                    continue;
+                }
+            }
+            result.push(ch);
+            remaining = &remaining[ch.len_utf8()..];
+        }
+        result
+    }
+}
+
+pub trait Character {
+    fn bounds(&self) -> &Rect;
+    fn draw<P: PixelBuffer>(&self, dest: &mut Bitmap<P>, x: i32, y: i32, opts: FontRenderOpts);
+}
+
+pub trait Font {
+    type CharacterType: Character;
+
+    /// Returns the character to render for the given Unicode character. If the font does not
+    /// have that character, a fallback character is returned instead.
+    fn character(&self, ch: char) -> &Self::CharacterType;
+
+    /// Returns true if the font has the given Unicode character (that is, [Font::character] will
+    /// not need to return a fallback character for it).
+    fn has_character(&self, ch: char) -> bool;
+    fn space_width(&self) -> u8;
+    fn line_height(&self) -> u8;
+    fn measure(&self, text: &str, opts: FontRenderOpts) -> (u32, u32);
+
+    /// Same as [Font::measure], except that any of the given inline formatting codes found in
+    /// the text are not included in the measurement.
+    fn measure_with_codes(
+        &self,
+        text: &str,
+        opts: FontRenderOpts,
+        codes: &TextCodes,
+    ) -> (u32, u32) {
+        self.measure(&codes.strip(text), opts)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BitmaskCharacter {
+    /// Rows of glyph pixels, each row being `bytes_per_row` bytes. The most significant bit of
+    /// each byte is the leftmost pixel.
+    bytes: Box<[u8]>,
+    bytes_per_row: usize,
+    /// The width of the glyph's pixels, which may be wider than the width in `bounds` (which is
+    /// the distance to advance to the next character).
+    pixel_width: u32,
+    bounds: Rect,
+}
+
+impl Character for BitmaskCharacter {
+    #[inline]
+    fn bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    fn draw<P: PixelBuffer>(&self, dest: &mut Bitmap<P>, x: i32, y: i32, opts: FontRenderOpts) {
+        match opts {
+            FontRenderOpts::Color(color) => self.draw_glyph(dest, x, y, color),
+            FontRenderOpts::Shadow {
+                color,
+                shadow_color,
+                x_offset,
+                y_offset,
+            } => {
+                self.draw_glyph(dest, x + x_offset as i32, y + y_offset as i32, shadow_color);
+                self.draw_glyph(dest, x, y, color);
+            }
+            FontRenderOpts::Outline {
+                color,
+                outline_color,
+            } => {
+                for offset_y in -1..=1 {
+                    for offset_x in -1..=1 {
+                        if offset_x != 0 || offset_y != 0 {
+                            self.draw_glyph(dest, x + offset_x, y + offset_y, outline_color);
+                        }
+                    }
+                }
+                self.draw_glyph(dest, x, y, color);
+            }
+            FontRenderOpts::None => self.draw_glyph(dest, x, y, 0),
+        }
+    }
+}
+
+impl BitmaskCharacter {
+    /// Creates a character from rows of glyph pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: the rows of glyph pixels, `bytes_per_row` bytes each, with the most significant
+    ///   bit of each byte being the leftmost pixel
+    /// * `bytes_per_row`: the number of bytes in each row, which must be at least 1
+    /// * `pixel_width`: the width in pixels of the glyph
+    /// * `width`: the distance in pixels to advance to the next character when rendering text
+    fn from_rows(
+        bytes: Vec<u8>,
+        bytes_per_row: usize,
+        pixel_width: u32,
+        width: u32,
+    ) -> BitmaskCharacter {
+        BitmaskCharacter {
+            bytes: bytes.into_boxed_slice(),
+            bytes_per_row,
+            pixel_width,
+            // height is filled in when the font is created
+            bounds: Rect::new(0, 0, width, 0),
+        }
+    }
+
+    fn draw_glyph<P: PixelBuffer>(&self, dest: &mut Bitmap<P>, x: i32, y: i32, color: u8) {
+        // out of bounds check
+        if ((x + self.pixel_width.max(self.bounds.width) as i32) < dest.clip_region().x)
+            || ((y + self.bounds.height as i32) < dest.clip_region().y)
+            || (x >= dest.clip_region().right())
+            || (y >= dest.clip_region().bottom())
+        {
+            return;
+        }
+
+        // TODO: i'm sure this can be optimized, lol
+        let rows = self.bytes.chunks_exact(self.bytes_per_row);
+        for (char_y, row) in rows.take(self.bounds.height as usize).enumerate() {
+            for char_x in 0..self.pixel_width as usize {
+                if row[char_x / 8] & (0x80 >> (char_x % 8)) > 0 {
+                    dest.set_pixel(x + char_x as i32, y + char_y as i32, color);
+                }
+            }
+        }
+    }
+}
+
+/// The character used by default for rendering characters that a font does not have.
+pub const DEFAULT_FALLBACK_CHAR: char = '?';
+
+/// A font made up of 1-bit (on/off) pixel characters. Characters 0-255 of the font are mapped to
+/// Unicode characters using the font's [Codepage], which is [Codepage::Latin1] by default. For
+/// fonts like the VGA BIOS font, [Codepage::Cp437] can be used instead to access box drawing and
+/// other symbols via their Unicode characters. Fonts may also have additional characters beyond
+/// the first 256, such as those loaded from BDF fonts, which are mapped to Unicode characters
+/// individually.
+#[derive(Clone, Eq, PartialEq)]
+pub struct BitmaskFont {
+    characters: Box<[BitmaskCharacter]>,
+    char_map: HashMap<char, usize>,
+    codepage: Codepage,
+    fallback: usize,
+    line_height: u8,
+    space_width: u8,
+}
+
+impl std::fmt::Debug for BitmaskFont {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BitmaskFont")
+            .field("line_height", &self.line_height)
+            .field("space_width", &self.space_width)
+            .field("codepage", &self.codepage)
+            .field("characters.len()", &self.characters.len())
+            .field("char_map.len()", &self.char_map.len())
+            .finish()
+    }
+}
+
+impl BitmaskFont {
+    pub fn new_vga_font() -> Result<BitmaskFont, FontError> {
+        BitmaskFont::load_from_bytes(&mut Cursor::new(VGA_FONT_BYTES))
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<BitmaskFont, FontError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+
+        BitmaskFont::load_from_bytes(&mut reader)
+    }
+
+    pub fn load_from_bytes<T: ReadBytesExt>(reader: &mut T) -> Result<BitmaskFont, FontError> {
+        let mut characters: Vec<BitmaskCharacter> = Vec::with_capacity(NUM_CHARS);
+
+        // read character bitmap data
+        for _ in 0..NUM_CHARS {
+            let mut buffer = [0u8; CHAR_HEIGHT];
+            reader.read_exact(&mut buffer)?;
+            let character = BitmaskCharacter {
+                bytes: Box::new(buffer),
+                bytes_per_row: 1,
+                // widths and bounds are filled in below. ugh.
+                pixel_width: 0,
+                bounds: Rect {
+                    x: 0,
+                    y: 0,
+                    width: 0,
+                    height: 0,
+                },
+            };
+            characters.push(character);
+        }
+
+        // read character widths (used for rendering)
+        for character in characters.iter_mut() {
+            character.bounds.width = reader.read_u8()? as u32;
+            character.pixel_width = character.bounds.width.min(CHAR_FIXED_WIDTH as u32);
+        }
+
+        // read global font height (used for rendering)
+        let line_height = reader.read_u8()?;
+
+        Ok(BitmaskFont::from_characters(characters, line_height))
+    }
+
+    /// Creates a font from a full set of [NUM_CHARS] characters, setting each character's height
+    /// to the line height given.
+    fn from_characters(mut characters: Vec<BitmaskCharacter>, line_height: u8) -> BitmaskFont {
+        for character in characters.iter_mut() {
+            character.bounds.height = line_height as u32;
+        }
+
+        let space_width = characters[' ' as usize].bounds.width as u8;
+
+        BitmaskFont {
+            characters: characters.into_boxed_slice(),
+            char_map: HashMap::new(),
+            codepage: Codepage::Latin1,
+            fallback: DEFAULT_FALLBACK_CHAR as usize,
+            line_height,
+            space_width,
+        }
+    }
+
+    /// Adds a character beyond the first 256 to this font, rendered for the given Unicode
+    /// character.
+    fn add_character(&mut self, ch: char, character: BitmaskCharacter) {
+        let mut characters = std::mem::take(&mut self.characters).into_vec();
+        let mut character = character;
+        character.bounds.height = self.line_height as u32;
+        characters.push(character);
+        self.char_map.insert(ch, characters.len() - 1);
+        self.characters = characters.into_boxed_slice();
+    }
+
+    /// Returns the index of the character to render for the given Unicode character.
+    fn character_index(&self, ch: char) -> usize {
+        match self.char_map.get(&ch) {
+            Some(index) => *index,
+            None => match self.codepage.index_of(ch) {
+                Some(index) => index as usize,
+                None => self.fallback,
+            },
+        }
+    }
+
+    /// Returns the code page used to map characters 0-255 of this font to Unicode characters.
+    #[inline]
+    pub fn codepage(&self) -> Codepage {
+        self.codepage
+    }
+
+    /// Changes the code page used to map characters 0-255 of this font to Unicode characters.
+    /// Characters mapped individually with [BitmaskFont::map_char] are unaffected.
+    #[inline]
+    pub fn set_codepage(&mut self, codepage: Codepage) {
+        self.codepage = codepage;
+    }
+
+    /// Maps a Unicode character to be rendered using the same font character as another Unicode
+    /// character. For example, this can be used to render curly quotes with a font that only
+    /// has straight quotes.
+    ///
+    /// # Arguments
+    ///
+    /// * `ch`: the Unicode character to map
+    /// * `existing_ch`: the Unicode character whose font character `ch` should be rendered with
+    pub fn map_char(&mut self, ch: char, existing_ch: char) {
+        let index = self.character_index(existing_ch);
+        self.char_map.insert(ch, index);
+    }
+
+    /// Sets the character rendered in place of any Unicode characters this font does not have.
+    /// This is [DEFAULT_FALLBACK_CHAR] unless changed.
+    pub fn set_fallback_char(&mut self, ch: char) {
+        self.fallback = self.character_index(ch);
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<(), FontError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_bytes(&mut writer)
+    }
+
+    /// Writes this font in the same format read by [BitmaskFont::load_from_bytes]. Only the first
+    /// 256 characters are written, and the code page and any individual character mappings are
+    /// not saved.
+    pub fn to_bytes<T: WriteBytesExt>(&self, writer: &mut T) -> Result<(), FontError> {
+        // fonts loaded from other formats can have glyphs that are too large for this format
+        let fits = self.characters.iter().all(|character| {
+            character.bytes_per_row == 1
+                && character.bytes.len() <= CHAR_HEIGHT
+                && character.bounds.width <= u8::MAX as u32
+        });
+        if !fits || self.line_height as usize > CHAR_HEIGHT {
+            return Err(FontError::UnsupportedFormat(format!(
+                "Characters must be at most {}x{} pixels",
+                CHAR_FIXED_WIDTH, CHAR_HEIGHT
+            )));
+        }
+
+        // write character bitmap data
+        for i in 0..NUM_CHARS {
+            let mut buffer = [0u8; CHAR_HEIGHT];
+            buffer[0..self.characters[i].bytes.len()].copy_from_slice(&self.characters[i].bytes);
+            writer.write_all(&buffer)?;
+        }
+
+        // write character widths
+        for i in 0..NUM_CHARS {
+            writer.write_u8(self.characters[i].bounds.width as u8)?;
+        }
+
+        // write global font height
+        writer.write_u8(self.line_height)?;
+
+        Ok(())
+    }
+}
+
+impl Font for BitmaskFont {
+    type CharacterType = BitmaskCharacter;
+
+    #[inline]
+    fn character(&self, ch: char) -> &Self::CharacterType {
+        &self.characters[self.character_index(ch)]
+    }
+
+    #[inline]
+    fn has_character(&self, ch: char) -> bool {
+        self.char_map.contains_key(&ch) || self.codepage.index_of(ch).is_some()
+    }
+
+    #[inline]
+    fn space_width(&self) -> u8 {
+        self.space_width
+    }
+
+    #[inline]
+    fn line_height(&self) -> u8 {
+        self.line_height
+    }
+
+    fn measure(&self, text: &str, opts: FontRenderOpts) -> (u32, u32) {
+        measure_rendered_text(self, text, opts)
+    }
+}
+
+/// Measures the given text as it would be rendered using the font given. This is the
+/// implementation of [Font::measure] shared by this crate's fonts.
+fn measure_rendered_text<T: Font>(font: &T, text: &str, opts: FontRenderOpts) -> (u32, u32) {
+    if text.is_empty() {
+        return (0, 0);
+    }
+    let mut height = 0;
+    let mut width = 0;
+    let mut x = 0;
+    // trimming whitespace off the end because it won't be rendered (since it's whitespace)
+    // and thus, won't contribute to visible rendered output (what we're measuring)
+    for ch in text.trim_end().chars() {
+        match ch {
+            '\n' => {
+                if x == 0 {
+                    height += font.line_height() as u32;
+                }
+                width = std::cmp::max(width, x);
+                x = 0;
+            },
+            '\r' => (),
+            ch => {
+                if x == 0 {
+                    height += font.line_height() as u32;
+                }
+                x += font.character(ch).bounds().width;
+            }
+        }
+    }
+    width = std::cmp::max(width, x);
+    if width == 0 || height == 0 {
+        return (width, height);
+    }
+    let (left, top, right, bottom) = opts.extents();
+    (width + left + right, height + top + bottom)
+}
+
+/// Returns a copy of the given text with line breaks inserted between words so that no line is
+/// wider than the maximum width given when rendered with the font given. Words that are wider than
+/// the maximum width by themselves are left on a line of their own.
+///
+/// # Arguments
+///
+/// * `font`: the font the text will be rendered with
+/// * `text`: the text to wrap
+/// * `max_width`: the maximum width in pixels of each line of text
+pub fn word_wrap<T: Font>(font: &T, text: &str, max_width: u32) -> String {
+    wrap_words(font, text, max_width, None)
+}
+
+/// Same as [word_wrap], except that any of the given inline formatting codes (see [TextCodes])
+/// found in the text are not included when measuring words, for text which will be rendered with
+/// [Bitmap::print_string_with_codes].
+pub fn word_wrap_with_codes<T: Font>(
+    font: &T,
+    text: &str,
+    max_width: u32,
+    codes: &TextCodes,
+) -> String {
+    wrap_words(font, text, max_width, Some(codes))
+}
+
+fn wrap_words<T: Font>(font: &T, text: &str, max_width: u32, codes: Option<&TextCodes>) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (line_index, line) in text.split('\n').enumerate() {
+        if line_index > 0 {
+            result.push('\n');
+        }
+        let mut line_width = 0;
+        for (word_index, word) in line.split(' ').enumerate() {
+            let stripped;
+            let measured_word = match codes {
+                Some(codes) => {
+                    stripped = codes.strip(word);
+                    stripped.as_str()
+                }
+                None => word,
+            };
+            let word_width = measure_rendered_text(font, measured_word, FontRenderOpts::None).0;
+            if word_index > 0 {
+                let space_width = font.space_width() as u32;
+                if line_width > 0 && line_width + space_width + word_width > max_width {
+                    result.push('\n');
+                    line_width = 0;
+                } else {
+                    result.push(' ');
+                    line_width += space_width;
+                }
+            }
+            result.push_str(word);
+            line_width += word_width;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn load_font() -> Result<(), FontError> {
+        let font = BitmaskFont::load_from_file(Path::new("./assets/vga.fnt"))?;
+        assert_eq!(256, font.characters.len());
+        assert_eq!(CHAR_FIXED_WIDTH as u8, font.space_width);
+        for character in font.characters.iter() {
+            assert_eq!(CHAR_FIXED_WIDTH as u8, character.bounds.width as u8);
+            assert_eq!(CHAR_HEIGHT, character.bytes.len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn measure_text() -> Result<(), FontError> {
+        {
+            let font = BitmaskFont::load_from_file(Path::new("./assets/vga.fnt"))?;
+
+            assert_eq!((40, 8), font.measure("Hello", FontRenderOpts::None));
+            assert_eq!((40, 16), font.measure("Hello\nthere", FontRenderOpts::None));
+            assert_eq!((88, 24), font.measure("longer line\nshort\nthe end", FontRenderOpts::None));
+            assert_eq!((0, 0), font.measure("", FontRenderOpts::None));
+            assert_eq!((0, 0), font.measure(" ", FontRenderOpts::None));
+            assert_eq!((40, 16), font.measure("\nhello", FontRenderOpts::None));
+            assert_eq!((0, 0), font.measure("\n", FontRenderOpts::None));
+            assert_eq!((40, 8), font.measure("hello\n", FontRenderOpts::None));
+            assert_eq!((40, 24), font.measure("hello\n\nthere", FontRenderOpts::None));
+        }
+
+        {
+            let font = BitmaskFont::load_from_file(Path::new("./test-assets/small.fnt"))?;
+
+            assert_eq!((22, 7), font.measure("Hello", FontRenderOpts::None));
+            assert_eq!((24, 14), font.measure("Hello\nthere", FontRenderOpts::None));
+            assert_eq!((50, 21), font.measure("longer line\nshort\nthe end", FontRenderOpts::None));
+            assert_eq!((0, 0), font.measure("", FontRenderOpts::None));
+            assert_eq!((0, 0), font.measure(" ", FontRenderOpts::None));
+            assert_eq!((21, 14), font.measure("\nhello", FontRenderOpts::None));
+            assert_eq!((0, 0), font.measure("\n", FontRenderOpts::None));
+            assert_eq!((21, 7), font.measure("hello\n", FontRenderOpts::None));
+            assert_eq!((24, 21), font.measure("hello\n\nthere", FontRenderOpts::None));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn unicode_characters() -> Result<(), FontError> {
+        let mut font = BitmaskFont::new_vga_font()?;
+        assert_eq!(Codepage::Latin1, font.codepage());
+        assert!(font.has_character('A'));
+        assert!(font.has_character('\u{b3}'));
+        assert!(!font.has_character('│'));
+        // unknown characters are rendered with the fallback character instead of panicking
+        assert_eq!(font.character('?'), font.character('│'));
+        assert_eq!(font.character('?'), font.character('€'));
+
+        font.set_codepage(Codepage::Cp437);
+        assert!(font.has_character('│'));
+        assert!(!font.has_character('\u{b3}'));
+        assert_eq!(&font.characters[0xb3], font.character('│'));
+        assert_eq!(&font.characters[0x82], font.character('é'));
+        assert_eq!(&font.characters[b'A' as usize], font.character('A'));
+
+        font.map_char('“', '"');
+        assert!(font.has_character('“'));
+        assert_eq!(font.character('"'), font.character('“'));
+        font.set_fallback_char('■');
+        assert_eq!(&font.characters[0xfe], font.character('€'));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn wrap_text() -> Result<(), FontError> {
+        let font = BitmaskFont::new_vga_font()?;
+        assert_eq!("hello\nworld", word_wrap(&font, "hello world", 80));
+        assert_eq!("hello world", word_wrap(&font, "hello world", 88));
+        assert_eq!("a b\nc d\n\ne", word_wrap(&font, "a b c d\n\ne", 24));
+        assert_eq!("{color:1}aa\nbb", word_wrap_with_codes(&font, "{color:1}aa bb", 32, &TextCodes::new()));
+        assert_eq!("{color:1}a\nb", word_wrap(&font, "{color:1}a b", 88));
+        assert_eq!("{color:1}a b", word_wrap_with_codes(&font, "{color:1}a b", 88, &TextCodes::new()));
+        assert_eq!("toolongword\nx", word_wrap(&font, "toolongword x", 32));
+        assert_eq!("", word_wrap(&font, "", 32));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn text_codes() -> Result<(), FontError> {
+        let mut codes = TextCodes::new();
+        codes.register("warn", FontRenderOpts::Color(4));
+        let original = FontRenderOpts::Color(15);
+
+        assert_eq!(Some((FontRenderOpts::Color(12), 10)), codes.parse_code("{color:12}abc", original));
+        assert_eq!(Some((original, 7)), codes.parse_code("{reset}", original));
+        assert_eq!(Some((FontRenderOpts::Color(4), 6)), codes.parse_code("{warn}", original));
+        assert_eq!(None, codes.parse_code("{color:300}", original));
+        assert_eq!(None, codes.parse_code("{unknown}", original));
+        assert_eq!(None, codes.parse_code("{warn", original));
+        assert_eq!(None, codes.parse_code("{{warn}", original));
+        assert_eq!(None, codes.parse_code("warn}", original));
+
+        assert_eq!("a {b} c{", codes.strip("{warn}a {b} {color:1}c{reset}{"));
+        assert_eq!(Some(FontRenderOpts::Color(4)), codes.unregister("warn"));
+        assert_eq!("{warn}x", codes.strip("{warn}x"));
+
+        let font = BitmaskFont::new_vga_font()?;
+        assert_eq!((40, 8), font.measure_with_codes("He{color:3}llo", FontRenderOpts::None, &codes));
+
+        let mut bmp = Bitmap::new(24, 8).unwrap();
+        codes.register("warn", FontRenderOpts::Color(4));
+        bmp.print_string_with_codes("|{warn}|{reset}|", 0, 0, original, &font, &codes);
+        assert_eq!(Some(15), bmp.get_pixel(3, 0));
+        assert_eq!(Some(4), bmp.get_pixel(11, 0));
+        assert_eq!(Some(15), bmp.get_pixel(19, 0));
+        bmp.clear(0);
+        bmp.print_string_with_codes("{color:9}|", 0, 0, original, &font, &codes);
+        assert_eq!(Some(9), bmp.get_pixel(3, 0));
+
+        // codes are only recognized when asked for
+        assert_eq!((80, 8), font.measure("{color:9}|", FontRenderOpts::None));
+        let mut bmp = Bitmap::new(80, 8).unwrap();
+        bmp.print_string("{color:9}|", 0, 0, original, &font);
+        assert!(bmp.pixels().iter().all(|&pixel| pixel == 0 || pixel == 15));
+        assert_eq!(Some(15), bmp.get_pixel(75, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn shadow_and_outline() -> Result<(), FontError> {
+        let font = BitmaskFont::new_vga_font()?;
+        let shadow = FontRenderOpts::Shadow {
+            color: 15,
+            shadow_color: 1,
+            x_offset: 1,
+            y_offset: 1,
+        };
+        let outline = FontRenderOpts::Outline {
+            color: 15,
+            outline_color: 2,
+        };
+        assert_eq!((17, 9), font.measure("||", shadow));
+        assert_eq!((18, 10), font.measure("||", outline));
+        assert_eq!((0, 0), font.measure("", outline));
+
+        // '|' in the vga font is a 2 pixel wide vertical line at x = 3, broken in two at y = 3
+        let mut bmp = Bitmap::new(16, 8).unwrap();
+        bmp.print_string("||", 0, 0, shadow, &font);
+        assert_eq!(Some(15), bmp.get_pixel(4, 2));
+        assert_eq!(Some(1), bmp.get_pixel(5, 2));
+        assert_eq!(Some(1), bmp.get_pixel(4, 3));
+        assert_eq!(Some(15), bmp.get_pixel(12, 2));
+        assert_eq!(Some(1), bmp.get_pixel(13, 2));
+
+        bmp.clear(0);
+        bmp.print_string("||", 0, 0, outline, &font);
+        assert_eq!(Some(2), bmp.get_pixel(2, 2));
+        assert_eq!(Some(15), bmp.get_pixel(3, 2));
+        assert_eq!(Some(2), bmp.get_pixel(5, 2));
+        assert_eq!(Some(2), bmp.get_pixel(3, 3));
+        assert_eq!(Some(2), bmp.get_pixel(10, 2));
+        assert_eq!(Some(0), bmp.get_pixel(7, 2));
+
+        Ok(())
+    }
+}
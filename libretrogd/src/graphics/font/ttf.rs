@@ -0,0 +1,381 @@
+use std::fmt::Formatter;
+
+use ab_glyph::{point, Font as TtfFont, FontRef, PxScale, ScaleFont};
+
+use crate::graphics::*;
+use crate::math::*;
+
+/// A single character rasterized from a TrueType font, as coverage amounts (0.0 to 1.0) for each
+/// pixel in a character cell that is the full line height of the font.
+struct RasterizedCharacter {
+    advance: u32,
+    pixel_width: u32,
+    coverage: Vec<f32>,
+}
+
+/// Rasterizes characters 0-255 (as Latin-1 / Unicode code points) of a TrueType font at the given
+/// pixel size, returning them along with the line height.
+fn rasterize_ttf(
+    font_data: &[u8],
+    pixel_size: f32,
+) -> Result<(Vec<RasterizedCharacter>, u8), FontError> {
+    let font = FontRef::try_from_slice(font_data)
+        .map_err(|err| FontError::InvalidFile(err.to_string()))?;
+    let scale = PxScale::from(pixel_size);
+    let scaled_font = font.as_scaled(scale);
+    let ascent = scaled_font.ascent();
+    let line_height = (scaled_font.ascent() - scaled_font.descent()).ceil();
+    if !(1.0..=u8::MAX as f32).contains(&line_height) {
+        return Err(FontError::InvalidFile(format!(
+            "Unsupported line height: {}",
+            line_height
+        )));
+    }
+    let line_height = line_height as u8;
+
+    let mut characters = Vec::with_capacity(NUM_CHARS);
+    for code in 0..NUM_CHARS {
+        let ch = char::from(code as u8);
+        if ch.is_control() {
+            characters.push(RasterizedCharacter {
+                advance: 0,
+                pixel_width: 0,
+                coverage: Vec::new(),
+            });
+            continue;
+        }
+
+        let glyph_id = font.glyph_id(ch);
+        let advance = scaled_font.h_advance(glyph_id).round().max(0.0) as u32;
+        let glyph = glyph_id.with_scale_and_position(scale, point(0.0, ascent));
+        let mut character = RasterizedCharacter {
+            advance,
+            pixel_width: 0,
+            coverage: Vec::new(),
+        };
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            let pixel_width = bounds.max.x.ceil().max(0.0) as u32;
+            let mut coverage = vec![0.0; pixel_width as usize * line_height as usize];
+            outlined.draw(|x, y, amount| {
+                let x = bounds.min.x as i32 + x as i32;
+                let y = bounds.min.y as i32 + y as i32;
+                if x >= 0 && y >= 0 && x < pixel_width as i32 && y < line_height as i32 {
+                    coverage[y as usize * pixel_width as usize + x as usize] = amount;
+                }
+            });
+            character.pixel_width = pixel_width;
+            character.coverage = coverage;
+        }
+        characters.push(character);
+    }
+
+    Ok((characters, line_height))
+}
+
+impl BitmaskFont {
+    /// Rasterizes a TrueType font at the given pixel size into a new [BitmaskFont]. Characters
+    /// 0-255 are rasterized as their equivalent Unicode code points (that is, as Latin-1). Only
+    /// available with the "ttf" feature enabled.
+    ///
+    /// This is SLOW and should only be done once at load time, after which the font renders just
+    /// like any other [BitmaskFont].
+    ///
+    /// # Arguments
+    ///
+    /// * `font_data`: the contents of a TrueType (.ttf) or OpenType (.otf) font file
+    /// * `pixel_size`: the height in pixels to rasterize the font at
+    /// * `threshold`: the minimum amount of coverage (0-255) a pixel must have for it to be
+    ///   included in a character
+    pub fn bake_ttf(
+        font_data: &[u8],
+        pixel_size: f32,
+        threshold: u8,
+    ) -> Result<BitmaskFont, FontError> {
+        let (rasterized, line_height) = rasterize_ttf(font_data, pixel_size)?;
+        let threshold = (threshold.max(1) as f32) / 255.0;
+        let characters = rasterized
+            .into_iter()
+            .map(|character| {
+                let bytes_per_row = (character.pixel_width as usize).div_ceil(8).max(1);
+                let mut bytes = vec![0u8; bytes_per_row * line_height as usize];
+                if character.pixel_width > 0 {
+                    let rows = character
+                        .coverage
+                        .chunks_exact(character.pixel_width as usize);
+                    for (y, row) in rows.enumerate() {
+                        for (x, amount) in row.iter().enumerate() {
+                            if *amount >= threshold {
+                                bytes[y * bytes_per_row + x / 8] |= 0x80 >> (x % 8);
+                            }
+                        }
+                    }
+                }
+                BitmaskCharacter::from_rows(
+                    bytes,
+                    bytes_per_row,
+                    character.pixel_width,
+                    character.advance,
+                )
+            })
+            .collect();
+        Ok(BitmaskFont::from_characters(characters, line_height))
+    }
+}
+
+/// A character in an [AntialiasedFont], made up of pixels that are each either transparent or one
+/// of the colors of the palette ramp the font was created with.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AntialiasedCharacter {
+    /// Rows of pixel colors, `pixel_width` pixels each. 0 is transparent.
+    pixels: Box<[u8]>,
+    pixel_width: u32,
+    bounds: Rect,
+}
+
+impl Character for AntialiasedCharacter {
+    #[inline]
+    fn bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    /// Draws this character using the colors it was created with when the render options are
+    /// [FontRenderOpts::None]. Otherwise, every non-transparent pixel is drawn using the color(s)
+    /// given in the render options, as with a [BitmaskCharacter].
+    fn draw<P: PixelBuffer>(&self, dest: &mut Bitmap<P>, x: i32, y: i32, opts: FontRenderOpts) {
+        match opts {
+            FontRenderOpts::Color(color) => self.draw_pixels(dest, x, y, Some(color)),
+            FontRenderOpts::Shadow {
+                color,
+                shadow_color,
+                x_offset,
+                y_offset,
+            } => {
+                self.draw_pixels(
+                    dest,
+                    x + x_offset as i32,
+                    y + y_offset as i32,
+                    Some(shadow_color),
+                );
+                self.draw_pixels(dest, x, y, Some(color));
+            }
+            FontRenderOpts::Outline {
+                color,
+                outline_color,
+            } => {
+                for offset_y in -1..=1 {
+                    for offset_x in -1..=1 {
+                        if offset_x != 0 || offset_y != 0 {
+                            self.draw_pixels(dest, x + offset_x, y + offset_y, Some(outline_color));
+                        }
+                    }
+                }
+                self.draw_pixels(dest, x, y, Some(color));
+            }
+            FontRenderOpts::None => self.draw_pixels(dest, x, y, None),
+        }
+    }
+}
+
+impl AntialiasedCharacter {
+    fn draw_pixels<P: PixelBuffer>(&self, dest: &mut Bitmap<P>, x: i32, y: i32, color: Option<u8>) {
+        if self.pixel_width == 0 {
+            return;
+        }
+        let rows = self.pixels.chunks_exact(self.pixel_width as usize);
+        for (char_y, row) in rows.enumerate() {
+            for (char_x, pixel) in row.iter().enumerate() {
+                if *pixel != 0 {
+                    dest.set_pixel(
+                        x + char_x as i32,
+                        y + char_y as i32,
+                        color.unwrap_or(*pixel),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// A font rasterized from a TrueType font with anti-aliasing, where the partially covered pixels
+/// along the edges of each character are drawn using a ramp of palette colors. Only available with
+/// the "ttf" feature enabled.
+///
+/// The ramp colors are drawn when rendering text with [FontRenderOpts::None]. Any other render
+/// options draw the characters using a single color, as with a [BitmaskFont].
+#[derive(Clone, Eq, PartialEq)]
+pub struct AntialiasedFont {
+    characters: Box<[AntialiasedCharacter]>,
+    line_height: u8,
+    space_width: u8,
+}
+
+impl std::fmt::Debug for AntialiasedFont {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AntialiasedFont")
+            .field("line_height", &self.line_height)
+            .field("space_width", &self.space_width)
+            .field("characters.len()", &self.characters.len())
+            .finish()
+    }
+}
+
+impl AntialiasedFont {
+    /// Rasterizes a TrueType font at the given pixel size into a new [AntialiasedFont].
+    /// Characters 0-255 are rasterized as their equivalent Unicode code points (that is, as
+    /// Latin-1).
+    ///
+    /// This is SLOW and should only be done once at load time, after which rendering text is done
+    /// using only the baked 8-bit palette colors.
+    ///
+    /// # Arguments
+    ///
+    /// * `font_data`: the contents of a TrueType (.ttf) or OpenType (.otf) font file
+    /// * `pixel_size`: the height in pixels to rasterize the font at
+    /// * `ramp`: the palette colors to draw pixels with, ordered from least covered (the faintest
+    ///   edge pixels) to fully covered. Must not be empty and must not contain color 0, which is
+    ///   used for transparent pixels.
+    pub fn bake_ttf(
+        font_data: &[u8],
+        pixel_size: f32,
+        ramp: &[u8],
+    ) -> Result<AntialiasedFont, FontError> {
+        if ramp.is_empty() || ramp.contains(&0) {
+            return Err(FontError::InvalidFile(String::from(
+                "Palette ramp must not be empty or contain color 0",
+            )));
+        }
+        let (rasterized, line_height) = rasterize_ttf(font_data, pixel_size)?;
+        let characters = rasterized
+            .into_iter()
+            .map(|character| {
+                let pixels = character
+                    .coverage
+                    .iter()
+                    .map(
+                        |amount| match (amount * ramp.len() as f32).round() as usize {
+                            0 => 0,
+                            level => ramp[level.min(ramp.len()) - 1],
+                        },
+                    )
+                    .collect::<Vec<u8>>();
+                AntialiasedCharacter {
+                    pixels: pixels.into_boxed_slice(),
+                    pixel_width: character.pixel_width,
+                    bounds: Rect::new(0, 0, character.advance, line_height as u32),
+                }
+            })
+            .collect::<Vec<AntialiasedCharacter>>();
+        let space_width = characters[' ' as usize].bounds.width as u8;
+
+        Ok(AntialiasedFont {
+            characters: characters.into_boxed_slice(),
+            line_height,
+            space_width,
+        })
+    }
+}
+
+impl Font for AntialiasedFont {
+    type CharacterType = AntialiasedCharacter;
+
+    #[inline]
+    fn character(&self, ch: char) -> &Self::CharacterType {
+        match self.characters.get(ch as usize) {
+            Some(character) => character,
+            None => &self.characters[DEFAULT_FALLBACK_CHAR as usize],
+        }
+    }
+
+    #[inline]
+    fn has_character(&self, ch: char) -> bool {
+        (ch as usize) < self.characters.len()
+    }
+
+    #[inline]
+    fn space_width(&self) -> u8 {
+        self.space_width
+    }
+
+    #[inline]
+    fn line_height(&self) -> u8 {
+        self.line_height
+    }
+
+    fn measure(&self, text: &str, opts: FontRenderOpts) -> (u32, u32) {
+        super::measure_rendered_text(self, text, opts)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::path::Path;
+
+    use claim::*;
+
+    use super::*;
+
+    fn test_font_data() -> Vec<u8> {
+        // a tiny font with only a space, 'I' and 'L', with an ascent of 800 and a descent of 200
+        // font units, and 1000 units per em
+        std::fs::read(Path::new("./test-assets/test.ttf")).unwrap()
+    }
+
+    #[test]
+    pub fn bake_bitmask_font() -> Result<(), FontError> {
+        let font = BitmaskFont::bake_ttf(&test_font_data(), 10.0, 128)?;
+        assert_eq!(10, font.line_height());
+        assert_eq!(3, font.space_width());
+        assert_eq!((10, 10), font.measure("IL", FontRenderOpts::None));
+
+        // 'I' is a rectangle 2 pixels wide and 7 pixels tall, sitting on the baseline at y = 8
+        let mut bmp = Bitmap::new(10, 10).unwrap();
+        bmp.print_string("IL", 0, 0, FontRenderOpts::Color(1), &font);
+        assert_eq!(Some(0), bmp.get_pixel(0, 4));
+        assert_eq!(Some(1), bmp.get_pixel(1, 1));
+        assert_eq!(Some(1), bmp.get_pixel(2, 7));
+        assert_eq!(Some(0), bmp.get_pixel(3, 4));
+        assert_eq!(Some(0), bmp.get_pixel(1, 8));
+        // the foot of the 'L'
+        assert_eq!(Some(1), bmp.get_pixel(8, 7));
+        assert_eq!(Some(0), bmp.get_pixel(8, 5));
+
+        assert_matches!(
+            BitmaskFont::bake_ttf(&[0u8; 16], 10.0, 128),
+            Err(FontError::InvalidFile(..))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn bake_antialiased_font() -> Result<(), FontError> {
+        let ramp = [10, 11, 12, 13];
+        let font = AntialiasedFont::bake_ttf(&test_font_data(), 10.0, &ramp)?;
+        assert_eq!(10, font.line_height());
+        assert_eq!((10, 10), font.measure("IL", FontRenderOpts::None));
+
+        // the stem of the 'L' is 1.5 pixels wide, so it has a partially covered edge
+        let mut bmp = Bitmap::new(10, 10).unwrap();
+        bmp.print_string("L", 0, 0, FontRenderOpts::None, &font);
+        assert_eq!(Some(13), bmp.get_pixel(1, 3));
+        assert_eq!(Some(11), bmp.get_pixel(2, 3));
+        assert_eq!(Some(0), bmp.get_pixel(3, 3));
+
+        bmp.clear(0);
+        bmp.print_string("L", 0, 0, FontRenderOpts::Color(1), &font);
+        assert_eq!(Some(1), bmp.get_pixel(1, 3));
+        assert_eq!(Some(1), bmp.get_pixel(2, 3));
+
+        assert_matches!(
+            AntialiasedFont::bake_ttf(&test_font_data(), 10.0, &[]),
+            Err(FontError::InvalidFile(..))
+        );
+        assert_matches!(
+            AntialiasedFont::bake_ttf(&test_font_data(), 10.0, &[0, 1]),
+            Err(FontError::InvalidFile(..))
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+use crate::graphics::*;
+use crate::math::*;
+
+/// How the light from a [PointLight] fades out towards the edge of its radius.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum LightFalloff {
+    /// The light is equally bright all the way out to its radius, where it stops.
+    Constant,
+    /// The light fades out evenly with distance.
+    #[default]
+    Linear,
+    /// The light stays bright close to the center and fades out quickly further away, which
+    /// looks more like a torch or lamp.
+    Quadratic,
+}
+
+/// A circular light which can be added to a [LightMap].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointLight {
+    /// The center of the light, in the [LightMap]'s coordinates.
+    pub position: Vector2,
+    /// How far the light reaches, in pixels.
+    pub radius: f32,
+    /// How bright the light is at its center, from 0.0 to 1.0, where 1.0 is full brightness.
+    pub intensity: f32,
+    /// How the light fades out towards its radius.
+    pub falloff: LightFalloff,
+}
+
+impl PointLight {
+    /// Creates a new light at full intensity which fades out linearly.
+    #[inline]
+    pub fn new(position: Vector2, radius: f32) -> PointLight {
+        PointLight {
+            position,
+            radius,
+            intensity: 1.0,
+            falloff: LightFalloff::Linear,
+        }
+    }
+
+    /// Returns how much light this light adds at the given distance from its center.
+    pub fn amount_at(&self, distance: f32) -> f32 {
+        if distance >= self.radius {
+            return 0.0;
+        }
+        let t = 1.0 - distance / self.radius;
+        let amount = match self.falloff {
+            LightFalloff::Constant => 1.0,
+            LightFalloff::Linear => t,
+            LightFalloff::Quadratic => t * t,
+        };
+        amount * self.intensity
+    }
+}
+
+/// A buffer of light levels covering an area, used to light a scene drawn with 8-bit graphics by
+/// darkening each drawn pixel through a [ColorMap] according to how much light falls on it.
+///
+/// The light map is divided into square cells which each have a single light level, measured
+/// from 0.0 (dark) to 1.0 (full brightness). A cell size of 1 gives per-pixel lighting, while a
+/// cell size matching a [TileMap]'s tile size gives blockier per-tile lighting which is quicker
+/// to update. Each frame, the light map is reset to the ambient light level with
+/// [LightMap::clear], lights are added to it, and then it is applied to the finished frame with
+/// [LightMap::apply].
+///
+/// A light map can either cover the screen, with lights positioned in screen coordinates, or
+/// cover a whole level, with lights positioned in world coordinates and the light map applied at
+/// the screen position of the world's origin (see [Camera::world_to_screen_point]). The camera's
+/// zoom is not applied.
+///
+/// ```
+/// use libretrogd::graphics::*;
+/// use libretrogd::math::*;
+///
+/// let palette = Palette::new_vga_palette().unwrap();
+/// let color_map = ColorMap::new(&palette, 16);
+///
+/// let mut screen = Bitmap::new(320, 200).unwrap();
+/// let mut light_map = LightMap::new(320, 200, 1);
+/// light_map.ambient = 0.2;
+///
+/// // once per frame, after drawing everything that should be lit
+/// light_map.clear();
+/// light_map.add_light(&PointLight::new(Vector2::new(160.0, 100.0), 64.0));
+/// light_map.apply(&mut screen, &color_map, 0, 0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightMap {
+    /// The light level everywhere that isn't lit by a light, including everything outside of the
+    /// light map, from 0.0 to 1.0.
+    pub ambient: f32,
+    width: u32,
+    height: u32,
+    cell_size: u32,
+    columns: u32,
+    rows: u32,
+    light: Box<[f32]>,
+}
+
+impl LightMap {
+    /// Creates a new light map which is completely dark.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the area covered, in pixels
+    /// * `height`: the height of the area covered, in pixels
+    /// * `cell_size`: the width and height of each cell, in pixels
+    pub fn new(width: u32, height: u32, cell_size: u32) -> LightMap {
+        let cell_size = cell_size.max(1);
+        let columns = width.div_ceil(cell_size);
+        let rows = height.div_ceil(cell_size);
+        LightMap {
+            ambient: 0.0,
+            width,
+            height,
+            cell_size,
+            columns,
+            rows,
+            light: vec![0.0; (columns * rows) as usize].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the width of the area covered, in pixels.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the area covered, in pixels.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    /// Returns the number of columns of cells.
+    #[inline]
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    /// Returns the number of rows of cells.
+    #[inline]
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Returns the light levels of every cell, row by row.
+    #[inline]
+    pub fn cells(&self) -> &[f32] {
+        &self.light
+    }
+
+    /// Returns mutable access to the light levels of every cell, row by row. Useful for adding
+    /// other kinds of lights, or for light which is blocked by walls.
+    #[inline]
+    pub fn cells_mut(&mut self) -> &mut [f32] {
+        &mut self.light
+    }
+
+    /// Resets every cell to the ambient light level.
+    pub fn clear(&mut self) {
+        let ambient = self.ambient.clamp(0.0, 1.0);
+        self.light.fill(ambient);
+    }
+
+    /// Adds the light from the given light to every cell it reaches. The amount of light added to
+    /// a cell is based on the distance from the light to the cell's center. Light from multiple
+    /// lights adds together, up to full brightness.
+    pub fn add_light(&mut self, light: &PointLight) {
+        if light.radius <= 0.0 || light.intensity <= 0.0 || self.light.is_empty() {
+            return;
+        }
+
+        let cell_size = self.cell_size as f32;
+        let to_cell =
+            |value: f32, max: u32| ((value / cell_size).floor() as i32).clamp(0, max as i32 - 1);
+        let left = to_cell(light.position.x - light.radius, self.columns);
+        let right = to_cell(light.position.x + light.radius, self.columns);
+        let top = to_cell(light.position.y - light.radius, self.rows);
+        let bottom = to_cell(light.position.y + light.radius, self.rows);
+
+        for row in top..=bottom {
+            let center_y = (row as f32 + 0.5) * cell_size;
+            for column in left..=right {
+                let center_x = (column as f32 + 0.5) * cell_size;
+                let distance = Vector2::new(center_x, center_y).distance(&light.position);
+                let amount = light.amount_at(distance);
+                if amount > 0.0 {
+                    let cell =
+                        &mut self.light[(row as u32 * self.columns + column as u32) as usize];
+                    *cell = (*cell + amount).min(1.0);
+                }
+            }
+        }
+    }
+
+    /// Returns the light level at the given position, in pixels. Positions outside of the light
+    /// map get the ambient light level.
+    pub fn light_at(&self, x: i32, y: i32) -> f32 {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            let column = x as u32 / self.cell_size;
+            let row = y as u32 / self.cell_size;
+            self.light[(row * self.columns + column) as usize]
+        } else {
+            self.ambient.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Lights the destination bitmap's clip region by shading every pixel through the colormap,
+    /// at the colormap light level matching the light level of the cell the pixel is in.
+    /// Pixels outside of the light map are shaded using the ambient light level.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest`: the bitmap to light, usually the screen once everything has been drawn to it
+    /// * `color_map`: the colormap to shade pixels with. Full brightness uses its brightest level.
+    /// * `x`: the x position in the destination bitmap of the light map's top-left corner
+    /// * `y`: the y position in the destination bitmap of the light map's top-left corner
+    pub fn apply(&self, dest: &mut Bitmap, color_map: &ColorMap, x: i32, y: i32) {
+        let max_level = (color_map.num_levels() - 1) as f32;
+        let to_level = |light: f32| (light * max_level).round() as usize;
+        let ambient_level = to_level(self.ambient.clamp(0.0, 1.0));
+
+        let clip_region = *dest.clip_region();
+        let pitch = dest.pitch() as usize;
+        let pixels = dest.pixels_mut();
+        for dest_y in clip_region.y..=clip_region.bottom() {
+            let row_start = dest_y as usize * pitch;
+            let map_y = dest_y - y;
+            let row = if (0..self.height as i32).contains(&map_y) {
+                Some(map_y as u32 / self.cell_size)
+            } else {
+                None
+            };
+            for dest_x in clip_region.x..=clip_region.right() {
+                let map_x = dest_x - x;
+                let level = match row {
+                    Some(row) if (0..self.width as i32).contains(&map_x) => {
+                        let column = map_x as u32 / self.cell_size;
+                        to_level(self.light[(row * self.columns + column) as usize])
+                    }
+                    _ => ambient_level,
+                };
+                let pixel = &mut pixels[row_start + dest_x as usize];
+                *pixel = color_map.shade(*pixel, level);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // a colormap which turns every color into 100 plus the light level, to easily see which
+    // level each pixel was shaded with
+    fn test_color_map() -> ColorMap {
+        let mut color_map = ColorMap::new(&Palette::new(), 5);
+        for level in 0..5 {
+            color_map
+                .get_mapping_mut(level)
+                .unwrap()
+                .fill(100 + level as u8);
+        }
+        color_map
+    }
+
+    #[test]
+    pub fn adding_lights() {
+        let mut light_map = LightMap::new(24, 16, 1);
+        assert_eq!(24, light_map.columns());
+        assert_eq!(16, light_map.rows());
+        assert!(light_map.cells().iter().all(|&light| light == 0.0));
+
+        light_map.ambient = 0.25;
+        light_map.clear();
+        assert_eq!(0.25, light_map.light_at(0, 0));
+        assert_eq!(0.25, light_map.light_at(-1, 100));
+
+        let mut light = PointLight::new(Vector2::new(8.5, 8.5), 8.0);
+        light_map.add_light(&light);
+        assert_eq!(1.0, light_map.light_at(8, 8));
+        assert_eq!(0.75, light_map.light_at(12, 8));
+        assert_eq!(0.25, light_map.light_at(17, 8));
+
+        light_map.clear();
+        light.falloff = LightFalloff::Quadratic;
+        light_map.add_light(&light);
+        assert_eq!(0.5, light_map.light_at(12, 8));
+
+        light_map.clear();
+        light.falloff = LightFalloff::Constant;
+        light.intensity = 0.5;
+        light_map.add_light(&light);
+        assert_eq!(0.75, light_map.light_at(12, 8));
+        assert_eq!(0.75, light_map.light_at(8, 15));
+        assert_eq!(0.25, light_map.light_at(8, 0));
+
+        // lights add together
+        light.position = Vector2::new(12.5, 8.5);
+        light_map.add_light(&light);
+        assert_eq!(1.0, light_map.light_at(12, 8));
+        assert_eq!(0.75, light_map.light_at(16, 12));
+
+        // lights far outside of the light map
+        light.position = Vector2::new(-100.0, -100.0);
+        light_map.add_light(&light);
+    }
+
+    #[test]
+    pub fn per_tile_lighting() {
+        let mut light_map = LightMap::new(65, 64, 16);
+        assert_eq!(5, light_map.columns());
+        assert_eq!(4, light_map.rows());
+
+        let mut light = PointLight::new(Vector2::new(24.0, 24.0), 16.0);
+        light.falloff = LightFalloff::Constant;
+        light.intensity = 0.5;
+        light_map.add_light(&light);
+        assert_eq!(0.5, light_map.light_at(20, 30));
+        assert_eq!(0.5, light_map.light_at(16, 16));
+        assert_eq!(0.0, light_map.light_at(32, 16));
+        assert_eq!(0.0, light_map.light_at(64, 16));
+        assert_eq!(0.0, light_map.light_at(65, 16));
+    }
+
+    #[test]
+    pub fn applying_lighting() {
+        let color_map = test_color_map();
+        let mut light_map = LightMap::new(8, 8, 1);
+        light_map.add_light(&PointLight::new(Vector2::new(4.5, 4.5), 4.0));
+
+        let mut dest = Bitmap::new(8, 8).unwrap();
+        light_map.apply(&mut dest, &color_map, 0, 0);
+        assert_eq!(Some(104), dest.get_pixel(4, 4));
+        assert_eq!(Some(102), dest.get_pixel(6, 4));
+        assert_eq!(Some(101), dest.get_pixel(7, 4));
+        assert_eq!(Some(100), dest.get_pixel(0, 0));
+
+        // positioned part way into the destination, with the rest of it lit by the ambient light
+        light_map.ambient = 0.5;
+        light_map.clear();
+        light_map.add_light(&PointLight::new(Vector2::new(4.5, 4.5), 4.0));
+        let mut dest = Bitmap::new(16, 16).unwrap();
+        dest.set_clip_region(&Rect::new(0, 0, 10, 10));
+        light_map.apply(&mut dest, &color_map, 4, 4);
+        assert_eq!(Some(104), dest.get_pixel(8, 8));
+        assert_eq!(Some(102), dest.get_pixel(4, 4));
+        assert_eq!(Some(102), dest.get_pixel(0, 0));
+        dest.reset_clip_region();
+        assert_eq!(Some(0), dest.get_pixel(10, 8));
+        assert_eq!(Some(0), dest.get_pixel(15, 15));
+    }
+}
@@ -1,12 +1,60 @@
+pub use self::animation::*;
+pub use self::aseprite::*;
 pub use self::bitmap::*;
 pub use self::bitmapatlas::*;
+pub use self::bitmapatlasbuilder::*;
+pub use self::bitmapchain::*;
 pub use self::blendmap::*;
+pub use self::camera::*;
+pub use self::capture::*;
+pub use self::colorcycler::*;
+pub use self::colormap::*;
+#[cfg(feature = "crt-filters")]
+pub use self::crtfilter::*;
+pub use self::flic::*;
 pub use self::font::*;
+pub use self::lighting::*;
 pub use self::palette::*;
+pub use self::palettefader::*;
+pub use self::quantize::*;
+#[cfg(feature = "raycaster")]
+pub use self::raycaster::*;
+pub use self::rastereffects::*;
+pub use self::render3d::*;
+pub use self::revealtext::*;
+pub use self::rgbabitmap::*;
+pub use self::textcache::*;
+#[cfg(feature = "tiled")]
+pub use self::tiled::*;
+pub use self::tilemap::*;
 
+pub mod animation;
+pub mod aseprite;
 pub mod bitmap;
 pub mod bitmapatlas;
+pub mod bitmapatlasbuilder;
+pub mod bitmapchain;
 pub mod blendmap;
+pub mod camera;
+pub mod capture;
+pub mod colorcycler;
+pub mod colormap;
+#[cfg(feature = "crt-filters")]
+pub mod crtfilter;
+pub mod flic;
 pub mod font;
+pub mod lighting;
 pub mod palette;
+pub mod palettefader;
+pub mod quantize;
+#[cfg(feature = "raycaster")]
+pub mod raycaster;
+pub mod rastereffects;
+pub mod render3d;
+pub mod revealtext;
+pub mod rgbabitmap;
+pub mod textcache;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+pub mod tilemap;
 
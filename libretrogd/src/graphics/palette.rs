@@ -1,6 +1,6 @@
 use std::cmp::min;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Cursor};
+use std::io::{BufReader, BufWriter, Cursor, Read};
 use std::ops::{Bound, Index, IndexMut, RangeBounds};
 use std::path::Path;
 
@@ -15,6 +15,22 @@ use crate::utils::abs_diff;
 pub trait ColorRange: RangeBounds<u8> + Iterator<Item = u8> {}
 impl<T> ColorRange for T where T: RangeBounds<u8> + Iterator<Item = u8> {}
 
+/// Returns the first and last (inclusive) color indices covered by the given color range.
+pub(crate) fn color_range_bounds<T: ColorRange>(colors: &T) -> (u8, u8) {
+    use Bound::*;
+    let start = match colors.start_bound() {
+        Excluded(&start) => start + 1,
+        Included(&start) => start,
+        Unbounded => 0,
+    };
+    let end = match colors.end_bound() {
+        Excluded(&end) => end - 1,
+        Included(&end) => end,
+        Unbounded => 255,
+    };
+    (start, end)
+}
+
 pub static VGA_PALETTE_BYTES: &[u8] = include_bytes!("../../assets/vga.pal");
 
 /// Converts a set of individual ARGB components to a combined 32-bit color value, packed into
@@ -155,6 +171,138 @@ pub fn greyscale(r: u8, b: u8, g: u8) -> u8 {
     (brightness(luminance(r, g, b)) * 255.0) as u8
 }
 
+/// Converts the given RGB color to HSV (hue, saturation, value).
+///
+/// # Arguments
+///
+/// * `r`: the red component (0-255)
+/// * `g`: the green component (0-255)
+/// * `b`: the blue component (0-255)
+///
+/// returns: the hue in degrees (0.0-360.0), and the saturation and value (0.0-1.0)
+pub fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let r = r as f32 / 255.0;
+    let g = g as f32 / 255.0;
+    let b = b as f32 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * (((b - r) / delta) + 2.0)
+    } else {
+        60.0 * (((r - g) / delta) + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    (hue, saturation, max)
+}
+
+/// Converts the given HSV (hue, saturation, value) color to RGB.
+///
+/// # Arguments
+///
+/// * `h`: the hue in degrees. Values outside of 0.0-360.0 wrap around.
+/// * `s`: the saturation (0.0-1.0)
+/// * `v`: the value (0.0-1.0)
+///
+/// returns: the red, green and blue components (0-255)
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
+    let h = h.rem_euclid(360.0);
+    let s = s.clamp(0.0, 1.0);
+    let v = v.clamp(0.0, 1.0);
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    (
+        ((r + m) * 255.0).round() as u8,
+        ((g + m) * 255.0).round() as u8,
+        ((b + m) * 255.0).round() as u8,
+    )
+}
+
+/// Converts the given RGB color to HSL (hue, saturation, lightness).
+///
+/// # Arguments
+///
+/// * `r`: the red component (0-255)
+/// * `g`: the green component (0-255)
+/// * `b`: the blue component (0-255)
+///
+/// returns: the hue in degrees (0.0-360.0), and the saturation and lightness (0.0-1.0)
+pub fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let (hue, _, value) = rgb_to_hsv(r, g, b);
+    let min = r.min(g).min(b) as f32 / 255.0;
+    let lightness = (value + min) / 2.0;
+    let saturation = if lightness <= 0.0 || lightness >= 1.0 {
+        0.0
+    } else {
+        (value - lightness) / lightness.min(1.0 - lightness)
+    };
+    (hue, saturation, lightness)
+}
+
+/// Converts the given HSL (hue, saturation, lightness) color to RGB.
+///
+/// # Arguments
+///
+/// * `h`: the hue in degrees. Values outside of 0.0-360.0 wrap around.
+/// * `s`: the saturation (0.0-1.0)
+/// * `l`: the lightness (0.0-1.0)
+///
+/// returns: the red, green and blue components (0-255)
+pub fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    let s = s.clamp(0.0, 1.0);
+    let l = l.clamp(0.0, 1.0);
+    let value = l + s * l.min(1.0 - l);
+    let saturation = if value <= 0.0 {
+        0.0
+    } else {
+        2.0 * (1.0 - l / value)
+    };
+    hsv_to_rgb(h, saturation, value)
+}
+
+/// Returns the fraction to use for the color at the given position of a gradient of `count`
+/// colors, such that the first color is 0.0 and the last color is 1.0.
+fn gradient_fraction(position: usize, count: usize) -> f32 {
+    if count > 1 {
+        position as f32 / (count - 1) as f32
+    } else {
+        0.0
+    }
+}
+
+#[inline]
+fn lerp_component(a: u8, b: u8, t: f32) -> u8 {
+    (a as f32 + (b as f32 - a as f32) * t).round() as u8
+}
+
+/// Converts a 6-bit (0-63) color component, as used by the VGA DAC, to an 8-bit (0-255) color
+/// component by multiplying it by 4, e.g. 63 becomes 252.
+#[inline]
+pub fn component_6bit_to_8bit(value: u8) -> u8 {
+    (value & 0x3f) << 2
+}
+
+/// Converts an 8-bit (0-255) color component to a 6-bit (0-63) color component, as used by the
+/// VGA DAC. This is the inverse of [component_6bit_to_8bit].
+#[inline]
+pub fn component_8bit_to_6bit(value: u8) -> u8 {
+    value >> 2
+}
+
 // vga bios (0-63) format
 fn read_256color_6bit_palette<T: ReadBytesExt>(
     reader: &mut T,
@@ -164,7 +312,16 @@ fn read_256color_6bit_palette<T: ReadBytesExt>(
         let r = reader.read_u8()?;
         let g = reader.read_u8()?;
         let b = reader.read_u8()?;
-        *color = to_rgb32(r * 4, g * 4, b * 4);
+        if r > 63 || g > 63 || b > 63 {
+            return Err(PaletteError::BadFile(String::from(
+                "Color component out of range for a 6-bit palette",
+            )));
+        }
+        *color = to_rgb32(
+            component_6bit_to_8bit(r),
+            component_6bit_to_8bit(g),
+            component_6bit_to_8bit(b),
+        );
     }
     Ok(colors)
 }
@@ -175,9 +332,9 @@ fn write_256color_6bit_palette<T: WriteBytesExt>(
 ) -> Result<(), PaletteError> {
     for color in colors.iter() {
         let (r, g, b) = from_rgb32(*color);
-        writer.write_u8(r / 4)?;
-        writer.write_u8(g / 4)?;
-        writer.write_u8(b / 4)?;
+        writer.write_u8(component_8bit_to_6bit(r))?;
+        writer.write_u8(component_8bit_to_6bit(g))?;
+        writer.write_u8(component_8bit_to_6bit(b))?;
     }
     Ok(())
 }
@@ -209,17 +366,167 @@ fn write_256color_8bit_palette<T: WriteBytesExt>(
     Ok(())
 }
 
+// jasc-pal (paint shop pro) text format
+fn read_jasc_palette<T: ReadBytesExt>(reader: &mut T) -> Result<[u32; NUM_COLORS], PaletteError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut lines = text.lines().map(|line| line.trim());
+    if lines.next() != Some("JASC-PAL") {
+        return Err(PaletteError::BadFile(String::from(
+            "Missing JASC-PAL signature",
+        )));
+    }
+    // version, which is always "0100"
+    lines.next();
+    let count = match lines.next().and_then(|line| line.parse::<usize>().ok()) {
+        Some(count) if count <= NUM_COLORS => count,
+        _ => {
+            return Err(PaletteError::BadFile(String::from(
+                "Invalid JASC-PAL color count",
+            )))
+        }
+    };
+
+    let mut colors = [to_rgb32(0, 0, 0); NUM_COLORS];
+    for color in colors.iter_mut().take(count) {
+        match lines.next().and_then(parse_text_rgb) {
+            Some((r, g, b)) => *color = to_rgb32(r, g, b),
+            None => {
+                return Err(PaletteError::BadFile(String::from(
+                    "Invalid or missing JASC-PAL color",
+                )))
+            }
+        }
+    }
+    Ok(colors)
+}
+
+fn write_jasc_palette<T: WriteBytesExt>(
+    writer: &mut T,
+    colors: &[u32; NUM_COLORS],
+) -> Result<(), PaletteError> {
+    write!(writer, "JASC-PAL\r\n0100\r\n{}\r\n", NUM_COLORS)?;
+    for color in colors.iter() {
+        let (r, g, b) = from_rgb32(*color);
+        write!(writer, "{} {} {}\r\n", r, g, b)?;
+    }
+    Ok(())
+}
+
+// gimp .gpl text format
+fn read_gimp_palette<T: ReadBytesExt>(reader: &mut T) -> Result<[u32; NUM_COLORS], PaletteError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut lines = text.lines().map(|line| line.trim());
+    if lines.next() != Some("GIMP Palette") {
+        return Err(PaletteError::BadFile(String::from(
+            "Missing GIMP Palette signature",
+        )));
+    }
+
+    let mut colors = [to_rgb32(0, 0, 0); NUM_COLORS];
+    let mut count = 0;
+    for line in lines {
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        match parse_text_rgb(line) {
+            Some((r, g, b)) => {
+                // any colors beyond what we can fit in the palette are ignored
+                if count < NUM_COLORS {
+                    colors[count] = to_rgb32(r, g, b);
+                    count += 1;
+                }
+            }
+            None => {
+                return Err(PaletteError::BadFile(format!(
+                    "Invalid GIMP Palette color: {}",
+                    line
+                )))
+            }
+        }
+    }
+    Ok(colors)
+}
+
+fn write_gimp_palette<T: WriteBytesExt>(
+    writer: &mut T,
+    colors: &[u32; NUM_COLORS],
+) -> Result<(), PaletteError> {
+    writeln!(writer, "GIMP Palette\nName: libretrogd\nColumns: 16\n#")?;
+    for (index, color) in colors.iter().enumerate() {
+        let (r, g, b) = from_rgb32(*color);
+        writeln!(writer, "{:3} {:3} {:3}\tIndex {}", r, g, b, index)?;
+    }
+    Ok(())
+}
+
+// adobe .act format (normal format, optionally followed by a color count and transparent index)
+fn read_act_palette<T: ReadBytesExt>(reader: &mut T) -> Result<[u32; NUM_COLORS], PaletteError> {
+    let mut colors = read_256color_8bit_palette(reader)?;
+    let mut trailer = Vec::new();
+    reader.read_to_end(&mut trailer)?;
+    if trailer.len() >= 2 {
+        let count = u16::from_be_bytes([trailer[0], trailer[1]]) as usize;
+        if count > 0 && count < NUM_COLORS {
+            for color in colors[count..].iter_mut() {
+                *color = to_rgb32(0, 0, 0);
+            }
+        }
+    }
+    Ok(colors)
+}
+
+fn write_act_palette<T: WriteBytesExt>(
+    writer: &mut T,
+    colors: &[u32; NUM_COLORS],
+) -> Result<(), PaletteError> {
+    write_256color_8bit_palette(writer, colors)?;
+    // all 256 colors are used, and there is no transparent color
+    writer.write_all(&[0x01, 0x00, 0xff, 0xff])?;
+    Ok(())
+}
+
+/// Parses the first three whitespace-separated integers on a line as RGB values.
+fn parse_text_rgb(line: &str) -> Option<(u8, u8, u8)> {
+    let mut values = line.split_whitespace().map(|value| value.parse::<u8>());
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Some((r, g, b)),
+        _ => None,
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum PaletteError {
+    #[error("Bad or unsupported palette file: {0}")]
+    BadFile(String),
+
+    #[error("Unknown palette file type: {0}")]
+    UnknownFileType(String),
+
     #[error("Palette I/O error")]
     IOError(#[from] std::io::Error),
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PaletteFormat {
-    /// Individual RGB components in 6-bits (0-63) for VGA BIOS compatibility
+    /// Individual RGB components in 6-bits (0-63) for VGA BIOS compatibility, as found in most
+    /// DOS-era palette dumps. Components are scaled up to 8-bits when loaded (see
+    /// [component_6bit_to_8bit]) and back down to 6-bits when saved.
     Vga,
-    /// Individual RGB components in 8-bits (0-255)
+    /// Individual RGB components in 8-bits (0-255), used as-is.
     Normal,
+    /// JASC-PAL text format, as used by Paint Shop Pro and many other tools
+    Jasc,
+    /// GIMP .gpl text format
+    Gimp,
+    /// Adobe Color Table (.act) format. The same as [PaletteFormat::Normal], but optionally
+    /// followed by the number of colors used and the transparent color index.
+    Act,
 }
 
 /// Contains a 256 color palette, and provides methods useful for working with palettes. The
@@ -262,6 +569,53 @@ impl Palette {
         Self::load_from_bytes(&mut reader, format)
     }
 
+    /// Loads and returns a Palette from a palette file on disk, automatically detecting which
+    /// format it is in. JASC-PAL and GIMP palettes are detected by their signatures, Adobe Color
+    /// Table palettes by their ".act" file extension. Any other file is assumed to be in the
+    /// [PaletteFormat::Normal] format if it is the right size. Palettes in the
+    /// [PaletteFormat::Vga] format cannot be reliably detected, and so must be loaded with
+    /// [Palette::load_from_file] instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the palette file to be loaded
+    pub fn load_file(path: &Path) -> Result<Palette, PaletteError> {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        Self::load_bytes(&data, path)
+    }
+
+    /// Loads and returns a Palette from the contents of a palette file that has already been read
+    /// into memory (for example, from an archive), automatically detecting which format it is in
+    /// the same way as [Palette::load_file].
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: the contents of the palette file
+    /// * `name`: the name of the palette file
+    pub fn load_bytes(data: &[u8], name: &Path) -> Result<Palette, PaletteError> {
+        let format = if data.starts_with(b"JASC-PAL") {
+            PaletteFormat::Jasc
+        } else if data.starts_with(b"GIMP Palette") {
+            PaletteFormat::Gimp
+        } else {
+            let extension = name
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_ascii_lowercase());
+            match extension.as_deref() {
+                Some("act") => PaletteFormat::Act,
+                _ if data.len() == NUM_COLORS * 3 => PaletteFormat::Normal,
+                _ => {
+                    return Err(PaletteError::UnknownFileType(String::from(
+                        "Could not detect palette file format",
+                    )))
+                }
+            }
+        };
+        Self::load_from_bytes(&mut Cursor::new(data), format)
+    }
+
     /// Loads and returns a Palette from a reader. The data being loaded is expected to be the same
     /// as if the palette was being loaded from a file on disk.
     ///
@@ -276,6 +630,9 @@ impl Palette {
         let colors = match format {
             PaletteFormat::Vga => read_256color_6bit_palette(reader)?,
             PaletteFormat::Normal => read_256color_8bit_palette(reader)?,
+            PaletteFormat::Jasc => read_jasc_palette(reader)?,
+            PaletteFormat::Gimp => read_gimp_palette(reader)?,
+            PaletteFormat::Act => read_act_palette(reader)?,
         };
         Ok(Palette { colors })
     }
@@ -306,6 +663,9 @@ impl Palette {
         match format {
             PaletteFormat::Vga => write_256color_6bit_palette(writer, &self.colors),
             PaletteFormat::Normal => write_256color_8bit_palette(writer, &self.colors),
+            PaletteFormat::Jasc => write_jasc_palette(writer, &self.colors),
+            PaletteFormat::Gimp => write_gimp_palette(writer, &self.colors),
+            PaletteFormat::Act => write_act_palette(writer, &self.colors),
         }
     }
 
@@ -446,6 +806,148 @@ impl Palette {
         }
     }
 
+    /// Fills a range of colors in the palette with a gradient which linearly interpolates the RGB
+    /// components of the two colors given. The first color in the range is set to `start` and
+    /// the last to `end`.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to fill with the gradient
+    /// * `start`: the 32-bit packed color to begin the gradient with
+    /// * `end`: the 32-bit packed color to end the gradient with
+    pub fn gradient_rgb<T: ColorRange>(&mut self, colors: T, start: u32, end: u32) {
+        let (start_r, start_g, start_b) = from_rgb32(start);
+        let (end_r, end_g, end_b) = from_rgb32(end);
+        let (first, last) = color_range_bounds(&colors);
+        let count = (last as usize + 1).saturating_sub(first as usize);
+        for (position, color) in (first..=last).enumerate() {
+            let t = gradient_fraction(position, count);
+            self[color] = to_rgb32(
+                lerp_component(start_r, end_r, t),
+                lerp_component(start_g, end_g, t),
+                lerp_component(start_b, end_b, t),
+            );
+        }
+    }
+
+    /// Fills a range of colors in the palette with a gradient which interpolates between the two
+    /// colors given in HSV space, taking the shortest way around the hue "circle". This usually
+    /// gives more vivid intermediate colors than [Palette::gradient_rgb] does for colors with
+    /// very different hues.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to fill with the gradient
+    /// * `start`: the 32-bit packed color to begin the gradient with
+    /// * `end`: the 32-bit packed color to end the gradient with
+    pub fn gradient_hsv<T: ColorRange>(&mut self, colors: T, start: u32, end: u32) {
+        let (r, g, b) = from_rgb32(start);
+        let (start_h, start_s, start_v) = rgb_to_hsv(r, g, b);
+        let (r, g, b) = from_rgb32(end);
+        let (end_h, end_s, end_v) = rgb_to_hsv(r, g, b);
+        let mut hue_diff = end_h - start_h;
+        if hue_diff > 180.0 {
+            hue_diff -= 360.0;
+        } else if hue_diff < -180.0 {
+            hue_diff += 360.0;
+        }
+
+        let (first, last) = color_range_bounds(&colors);
+        let count = (last as usize + 1).saturating_sub(first as usize);
+        for (position, color) in (first..=last).enumerate() {
+            let t = gradient_fraction(position, count);
+            let (r, g, b) = hsv_to_rgb(
+                start_h + hue_diff * t,
+                start_s + (end_s - start_s) * t,
+                start_v + (end_v - start_v) * t,
+            );
+            self[color] = to_rgb32(r, g, b);
+        }
+    }
+
+    /// Fills a range of colors in the palette with a grey-scale ramp, going from black at the
+    /// first color in the range to white at the last.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to fill with the ramp
+    pub fn greyscale_ramp<T: ColorRange>(&mut self, colors: T) {
+        self.gradient_rgb(colors, to_rgb32(0, 0, 0), to_rgb32(255, 255, 255));
+    }
+
+    /// Copies a range of colors to another location in the palette, scaling the brightness of
+    /// each copied color by the given factor. Useful for building several copies of a ramp at
+    /// different brightness levels. Any colors which would be copied past the end of the palette
+    /// are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to be copied
+    /// * `dest`: the color index to begin writing the copied colors at
+    /// * `brightness`: the factor to multiply each color's RGB components by, where 1.0 leaves
+    ///   them unchanged, 0.5 halves them, etc. Results are clamped to 0-255.
+    pub fn copy_colors_scaled<T: ColorRange>(&mut self, colors: T, dest: u8, brightness: f32) {
+        let (first, last) = color_range_bounds(&colors);
+        let scale = |component: u8| (component as f32 * brightness).round().clamp(0.0, 255.0) as u8;
+        // copy out first, in case the source and destination overlap
+        let source: Vec<u32> = (first..=last).map(|color| self[color]).collect();
+        for (offset, color) in source.into_iter().enumerate() {
+            let index = dest as usize + offset;
+            if index >= NUM_COLORS {
+                break;
+            }
+            let (r, g, b) = from_rgb32(color);
+            self.colors[index] = to_rgb32(scale(r), scale(g), scale(b));
+        }
+    }
+
+    /// Applies the given function to the HSV values of each color in a range of colors.
+    fn transform_hsv<T: ColorRange>(
+        &mut self,
+        colors: T,
+        f: impl Fn(f32, f32, f32) -> (f32, f32, f32),
+    ) {
+        for color in colors {
+            let (r, g, b) = from_rgb32(self[color]);
+            let (h, s, v) = rgb_to_hsv(r, g, b);
+            let (h, s, v) = f(h, s, v);
+            let (r, g, b) = hsv_to_rgb(h, s, v);
+            self[color] = to_rgb32(r, g, b);
+        }
+    }
+
+    /// Shifts the hue of a range of colors in the palette by the given number of degrees.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to be adjusted
+    /// * `amount`: the number of degrees (and direction) to shift the hue of each color by
+    pub fn adjust_hue<T: ColorRange>(&mut self, colors: T, amount: f32) {
+        self.transform_hsv(colors, |h, s, v| (h + amount, s, v));
+    }
+
+    /// Adjusts the saturation of a range of colors in the palette by the given amount. Results
+    /// are clamped, so a large enough negative amount will turn all colors grey.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to be adjusted
+    /// * `amount`: the amount (-1.0 to 1.0) to add to each color's saturation
+    pub fn adjust_saturation<T: ColorRange>(&mut self, colors: T, amount: f32) {
+        self.transform_hsv(colors, |h, s, v| (h, s + amount, v));
+    }
+
+    /// Adjusts the brightness (the HSV "value") of a range of colors in the palette by the given
+    /// amount. Results are clamped, so a large enough negative amount will turn all colors black.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to be adjusted
+    /// * `amount`: the amount (-1.0 to 1.0) to add to each color's brightness
+    pub fn adjust_brightness<T: ColorRange>(&mut self, colors: T, amount: f32) {
+        self.transform_hsv(colors, |h, s, v| (h, s, v + amount));
+    }
+
     /// Rotates a range of colors in the palette by a given amount.
     ///
     /// # Arguments
@@ -453,18 +955,8 @@ impl Palette {
     /// * `colors`: the range of colors to be rotated
     /// * `step`: the number of positions (and direction) to rotate all colors by
     pub fn rotate_colors<T: ColorRange>(&mut self, colors: T, step: i8) {
-        use Bound::*;
-        let start = match colors.start_bound() {
-            Excluded(&start) => start + 1,
-            Included(&start) => start,
-            Unbounded => 0,
-        } as usize;
-        let end = match colors.end_bound() {
-            Excluded(&end) => end - 1,
-            Included(&end) => end,
-            Unbounded => 255,
-        } as usize;
-        let subset = &mut self.colors[start..=end];
+        let (start, end) = color_range_bounds(&colors);
+        let subset = &mut self.colors[start as usize..=end as usize];
         match step.signum() {
             -1 => subset.rotate_left(step.abs() as usize),
             1 => subset.rotate_right(step.abs() as usize),
@@ -472,12 +964,61 @@ impl Palette {
         }
     }
 
+    /// Rotates a range of colors in the palette by a given amount. Unlike
+    /// [Palette::rotate_colors], the step can be any amount, including ones larger than the
+    /// number of colors in the range, in which case it wraps around.
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to be rotated
+    /// * `step`: the number of positions (and direction) to rotate all colors by. Positive values
+    ///   move each color to a higher index, negative values to a lower index.
+    pub fn rotate_range<T: ColorRange>(&mut self, colors: T, step: i32) {
+        let (start, end) = color_range_bounds(&colors);
+        if start > end {
+            return;
+        }
+        let subset = &mut self.colors[start as usize..=end as usize];
+        let amount = step.rem_euclid(subset.len() as i32) as usize;
+        subset.rotate_right(amount);
+    }
+
     /// Finds and returns the index of the closest color in this palette to the RGB values provided.
     /// This will not always return great results. It depends largely on the palette and the RGB
     /// values being searched (for example, searching for bright green 0,255,0 in a palette which
     /// contains no green hues at all is not likely to return a useful result).
     pub fn find_color(&self, r: u8, g: u8, b: u8) -> u8 {
-        let mut closest_distance = 255 * 3;
+        self.find_closest(r, g, b, |this_r, this_g, this_b| {
+            // this comparison method is using the sRGB Euclidean formula described here:
+            // https://en.wikipedia.org/wiki/Color_difference
+            abs_diff(this_r, r) as u32 + abs_diff(this_g, g) as u32 + abs_diff(this_b, b) as u32
+        })
+    }
+
+    /// Finds and returns the index of the color in this palette which looks the closest to the RGB
+    /// values provided. Unlike [Palette::find_color], which treats differences in each color
+    /// component equally, this uses a perceptually weighted distance ("redmean", a low-cost
+    /// approximation described at https://en.wikipedia.org/wiki/Color_difference) which better
+    /// matches how the human eye judges color differences. It is a little slower as a result.
+    pub fn find_nearest(&self, r: u8, g: u8, b: u8) -> u8 {
+        self.find_closest(r, g, b, |this_r, this_g, this_b| {
+            let red_mean = (r as u32 + this_r as u32) / 2;
+            let diff_r = abs_diff(this_r, r) as u32;
+            let diff_g = abs_diff(this_g, g) as u32;
+            let diff_b = abs_diff(this_b, b) as u32;
+
+            (((512 + red_mean) * diff_r * diff_r) >> 8)
+                + 4 * diff_g * diff_g
+                + (((767 - red_mean) * diff_b * diff_b) >> 8)
+        })
+    }
+
+    /// Returns the index of the first color in this palette that exactly matches the RGB values
+    /// provided, or otherwise the one with the lowest distance as returned by the given function,
+    /// which is called with each color's RGB values.
+    #[inline]
+    fn find_closest(&self, r: u8, g: u8, b: u8, distance: impl Fn(u8, u8, u8) -> u32) -> u8 {
+        let mut closest_distance = u32::MAX;
         let mut closest = 0;
 
         for (index, color) in self.colors.iter().enumerate() {
@@ -485,24 +1026,57 @@ impl Palette {
 
             if r == this_r && g == this_g && b == this_b {
                 return index as u8;
-            } else {
-                // this comparison method is using the sRGB Euclidean formula described here:
-                // https://en.wikipedia.org/wiki/Color_difference
-
-                let distance = abs_diff(this_r, r) as u32
-                    + abs_diff(this_g, g) as u32
-                    + abs_diff(this_b, b) as u32;
-
-                if distance < closest_distance {
-                    closest = index as u8;
-                    closest_distance = distance;
-                }
+            }
+            let this_distance = distance(this_r, this_g, this_b);
+            if this_distance < closest_distance {
+                closest = index as u8;
+                closest_distance = this_distance;
             }
         }
 
         closest
     }
 
+    /// Builds a color remap table which maps each color in this palette to the index of the
+    /// perceptually closest color in the other palette given (as determined by
+    /// [Palette::find_nearest]). The resulting table can be used with [Bitmap::remap_colors] to
+    /// convert art drawn with this palette over to the other palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: the palette to map this palette's colors into
+    ///
+    /// returns: a 256 color lookup table, indexed by this palette's color indices
+    #[inline]
+    pub fn build_remap_table(&self, other: &Palette) -> [u8; NUM_COLORS] {
+        self.remap_table_using(|r, g, b| other.find_nearest(r, g, b))
+    }
+
+    /// Builds a color remap table which maps each color in this palette to the index of the
+    /// closest matching color in the other palette given (as determined by
+    /// [Palette::find_color]).
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: the palette to map this palette's colors into
+    ///
+    /// returns: a 256 color lookup table, indexed by this palette's color indices
+    #[inline]
+    pub fn remap_table_to(&self, other: &Palette) -> [u8; NUM_COLORS] {
+        self.remap_table_using(|r, g, b| other.find_color(r, g, b))
+    }
+
+    /// Builds a color remap table which maps each color in this palette to the index returned by
+    /// the given color matching function for that color's RGB values.
+    fn remap_table_using(&self, find: impl Fn(u8, u8, u8) -> u8) -> [u8; NUM_COLORS] {
+        let mut table = [0u8; NUM_COLORS];
+        for (index, color) in self.colors.iter().enumerate() {
+            let (r, g, b) = from_rgb32(*color);
+            table[index] = find(r, g, b);
+        }
+        table
+    }
+
     /// Debug helper that draws this palette to the given bitmap as a 16x16 pixel grid, where each
     /// pixel is one of the colors from this palette, in ascending order, left-to-right,
     /// top-to-bottom. The coordinates given specify the top-left coordinate on the destination
@@ -534,8 +1108,32 @@ impl IndexMut<u8> for Palette {
     }
 }
 
+/// Palettes are serialized as a sequence of all 256 colors, each a 32-bit packed ARGB value.
+/// Only available with the "serde" feature enabled.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Palette {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.colors.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Palette {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let colors = Vec::<u32>::deserialize(deserializer)?;
+        match colors.try_into() {
+            Ok(colors) => Ok(Palette { colors }),
+            Err(colors) => Err(serde::de::Error::invalid_length(
+                colors.len(),
+                &"256 colors",
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use claim::assert_matches;
     use tempfile::TempDir;
 
     use super::*;
@@ -613,6 +1211,229 @@ mod tests {
         let reloaded_palette = Palette::load_from_file(&save_path, PaletteFormat::Normal)?;
         assert_eq!(palette, reloaded_palette);
 
+        // jasc, gimp and act formats, also checking that they are auto-detected
+
+        for (format, filename) in [
+            (PaletteFormat::Jasc, "test_save_jasc.pal"),
+            (PaletteFormat::Gimp, "test_save_gimp.gpl"),
+            (PaletteFormat::Act, "test_save_act.act"),
+            (PaletteFormat::Normal, "test_save_normal.bin"),
+        ] {
+            let save_path = tmp_dir.path().join(filename);
+            palette.to_file(&save_path, format)?;
+            let reloaded_palette = Palette::load_from_file(&save_path, format)?;
+            assert_eq!(palette, reloaded_palette);
+            let reloaded_palette = Palette::load_file(&save_path)?;
+            assert_eq!(palette, reloaded_palette);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn rotating_colors() {
+        let mut palette = Palette::new();
+        for i in 0..=255 {
+            palette[i] = to_rgb32(i, i, i);
+        }
+
+        palette.rotate_range(10..=13, 1);
+        assert_eq!(to_rgb32(13, 13, 13), palette[10]);
+        assert_eq!(to_rgb32(10, 10, 10), palette[11]);
+        assert_eq!(to_rgb32(9, 9, 9), palette[9]);
+        assert_eq!(to_rgb32(14, 14, 14), palette[14]);
+
+        palette.rotate_range(10..=13, -1);
+        assert_eq!(to_rgb32(10, 10, 10), palette[10]);
+
+        // steps larger than the range wrap around
+        palette.rotate_range(10..14, 9);
+        assert_eq!(to_rgb32(13, 13, 13), palette[10]);
+        palette.rotate_range(10..14, -9);
+        assert_eq!(to_rgb32(10, 10, 10), palette[10]);
+
+        palette.rotate_colors(10..=13, -1);
+        assert_eq!(to_rgb32(11, 11, 11), palette[10]);
+        assert_eq!(to_rgb32(10, 10, 10), palette[13]);
+    }
+
+    #[test]
+    fn nearest_colors_and_remap_tables() -> Result<(), PaletteError> {
+        let palette =
+            Palette::load_from_file(Path::new("./test-assets/dp2.pal"), PaletteFormat::Normal)?;
+
+        // exact matches are always found
+        for i in [0, 15, 120, 127] {
+            let (r, g, b) = from_rgb32(palette[i]);
+            assert_eq!(i, palette.find_nearest(r, g, b));
+        }
+
+        // green differences are weighted more heavily than blue differences, so a slightly
+        // different green is a worse match than a slightly different blue
+        let mut palette = Palette::new_with_default(0, 0, 0);
+        palette[1] = to_rgb32(100, 110, 100);
+        palette[2] = to_rgb32(100, 100, 112);
+        assert_eq!(2, palette.find_nearest(100, 100, 100));
+        assert_eq!(1, palette.find_color(100, 100, 100));
+
+        let mut other = Palette::new_with_default(255, 255, 255);
+        other[7] = to_rgb32(0, 0, 0);
+        other[8] = to_rgb32(101, 101, 110);
+        let table = palette.build_remap_table(&other);
+        assert_eq!(7, table[0]);
+        assert_eq!(8, table[1]);
+        assert_eq!(8, table[2]);
+        assert_eq!(7, table[255]);
+        assert_eq!(table, palette.remap_table_to(&other));
+
+        Ok(())
+    }
+
+    #[test]
+    fn hsv_conversions() {
+        assert_eq!((0.0, 1.0, 1.0), rgb_to_hsv(255, 0, 0));
+        assert_eq!((120.0, 1.0, 1.0), rgb_to_hsv(0, 255, 0));
+        assert_eq!((240.0, 1.0, 1.0), rgb_to_hsv(0, 0, 255));
+        assert_eq!((0.0, 0.0, 0.0), rgb_to_hsv(0, 0, 0));
+        assert_eq!((0.0, 0.0, 1.0), rgb_to_hsv(255, 255, 255));
+
+        assert_eq!((255, 0, 0), hsv_to_rgb(0.0, 1.0, 1.0));
+        assert_eq!((255, 0, 0), hsv_to_rgb(360.0, 1.0, 1.0));
+        assert_eq!((255, 255, 0), hsv_to_rgb(60.0, 1.0, 1.0));
+        assert_eq!((0, 128, 128), hsv_to_rgb(180.0, 1.0, 0.5));
+        for (r, g, b) in [(12, 34, 56), (200, 100, 50), (77, 77, 77), (1, 254, 128)] {
+            let (h, s, v) = rgb_to_hsv(r, g, b);
+            assert_eq!((r, g, b), hsv_to_rgb(h, s, v));
+        }
+    }
+
+    #[test]
+    fn six_bit_components() -> Result<(), PaletteError> {
+        assert_eq!(0, component_6bit_to_8bit(0));
+        assert_eq!(168, component_6bit_to_8bit(42));
+        assert_eq!(252, component_6bit_to_8bit(63));
+        for value in 0..=63 {
+            assert_eq!(value, component_8bit_to_6bit(component_6bit_to_8bit(value)));
+        }
+
+        let mut data = vec![63u8; 768];
+        data[767] = 64;
+        assert_matches!(
+            Palette::load_from_bytes(&mut data.as_slice(), PaletteFormat::Vga),
+            Err(PaletteError::BadFile(..))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn hsl_conversions() {
+        assert_eq!((0.0, 1.0, 0.5), rgb_to_hsl(255, 0, 0));
+        assert_eq!((0.0, 0.0, 0.0), rgb_to_hsl(0, 0, 0));
+        assert_eq!((0.0, 0.0, 1.0), rgb_to_hsl(255, 255, 255));
+
+        assert_eq!((255, 0, 0), hsl_to_rgb(0.0, 1.0, 0.5));
+        assert_eq!((255, 128, 128), hsl_to_rgb(0.0, 1.0, 0.75));
+        assert_eq!((128, 128, 128), hsl_to_rgb(90.0, 0.0, 0.5));
+        for (r, g, b) in [(12, 34, 56), (200, 100, 50), (77, 77, 77), (1, 254, 128)] {
+            let (h, s, l) = rgb_to_hsl(r, g, b);
+            assert_eq!((r, g, b), hsl_to_rgb(h, s, l));
+        }
+    }
+
+    #[test]
+    fn adjusting_colors() {
+        let mut palette = Palette::new_with_default(255, 0, 0);
+
+        palette.adjust_hue(0..=9, 120.0);
+        assert_eq!(to_rgb32(0, 255, 0), palette[0]);
+        assert_eq!(to_rgb32(0, 255, 0), palette[9]);
+        assert_eq!(to_rgb32(255, 0, 0), palette[10]);
+        palette.adjust_hue(0..=9, -240.0);
+        assert_eq!(to_rgb32(0, 0, 255), palette[0]);
+
+        palette.adjust_saturation(10..=10, -0.5);
+        assert_eq!(to_rgb32(255, 128, 128), palette[10]);
+        palette.adjust_saturation(10..=10, -2.0);
+        assert_eq!(to_rgb32(255, 255, 255), palette[10]);
+
+        palette.adjust_brightness(20..=20, -0.5);
+        assert_eq!(to_rgb32(128, 0, 0), palette[20]);
+        palette.adjust_brightness(20..=20, -2.0);
+        assert_eq!(to_rgb32(0, 0, 0), palette[20]);
+        assert_eq!(to_rgb32(255, 0, 0), palette[21]);
+    }
+
+    #[test]
+    fn generating_ramps() {
+        let mut palette = Palette::new();
+
+        palette.greyscale_ramp(0..=255);
+        for i in 0..=255 {
+            assert_eq!(to_rgb32(i, i, i), palette[i]);
+        }
+
+        palette.gradient_rgb(10..=14, to_rgb32(0, 100, 200), to_rgb32(40, 100, 0));
+        assert_eq!(to_rgb32(0, 100, 200), palette[10]);
+        assert_eq!(to_rgb32(10, 100, 150), palette[11]);
+        assert_eq!(to_rgb32(20, 100, 100), palette[12]);
+        assert_eq!(to_rgb32(40, 100, 0), palette[14]);
+        assert_eq!(to_rgb32(15, 15, 15), palette[15]);
+
+        // red to blue goes the short way around the hue circle, through magenta
+        palette.gradient_hsv(20..23, to_rgb32(255, 0, 0), to_rgb32(0, 0, 255));
+        assert_eq!(to_rgb32(255, 0, 0), palette[20]);
+        assert_eq!(to_rgb32(255, 0, 255), palette[21]);
+        assert_eq!(to_rgb32(0, 0, 255), palette[22]);
+
+        palette.gradient_rgb(30..=30, to_rgb32(1, 2, 3), to_rgb32(4, 5, 6));
+        assert_eq!(to_rgb32(1, 2, 3), palette[30]);
+
+        palette.copy_colors_scaled(10..=14, 100, 0.5);
+        assert_eq!(to_rgb32(0, 50, 100), palette[100]);
+        assert_eq!(to_rgb32(20, 50, 0), palette[104]);
+        assert_eq!(to_rgb32(105, 105, 105), palette[105]);
+        palette.copy_colors_scaled(10..=14, 253, 2.0);
+        assert_eq!(to_rgb32(0, 200, 255), palette[253]);
+        assert_eq!(to_rgb32(20, 200, 255), palette[254]);
+        assert_eq!(to_rgb32(40, 200, 200), palette[255]);
+    }
+
+    #[test]
+    fn load_other_formats() -> Result<(), PaletteError> {
+        let palette = Palette::load_file(Path::new("./test-assets/test.gpl"))?;
+        assert_eq!(to_rgb32(0, 0, 0), palette[0]);
+        assert_eq!(to_rgb32(255, 0, 0), palette[1]);
+        assert_eq!(to_rgb32(12, 34, 56), palette[2]);
+        assert_eq!(to_rgb32(0, 0, 0), palette[3]);
+
+        let palette = Palette::load_file(Path::new("./test-assets/test_jasc.pal"))?;
+        assert_eq!(to_rgb32(255, 255, 255), palette[0]);
+        assert_eq!(to_rgb32(1, 2, 3), palette[1]);
+        assert_eq!(to_rgb32(0, 0, 0), palette[2]);
+
+        let mut data: &[u8] = b"JASC-PAL\n0100\n3\n1 2 3\n";
+        assert_matches!(
+            Palette::load_from_bytes(&mut data, PaletteFormat::Jasc),
+            Err(PaletteError::BadFile(..))
+        );
+        let mut data: &[u8] = b"GIMP Palette\nfoo bar baz\n";
+        assert_matches!(
+            Palette::load_from_bytes(&mut data, PaletteFormat::Gimp),
+            Err(PaletteError::BadFile(..))
+        );
+        assert_matches!(
+            Palette::load_file(Path::new("./test-assets/test.gif")),
+            Err(PaletteError::UnknownFileType(..))
+        );
+
+        // act files may only use some of their colors
+        let mut data = vec![7u8; 768];
+        data.extend_from_slice(&[0x00, 0x02, 0xff, 0xff]);
+        let palette = Palette::load_from_bytes(&mut data.as_slice(), PaletteFormat::Act)?;
+        assert_eq!(to_rgb32(7, 7, 7), palette[1]);
+        assert_eq!(to_rgb32(0, 0, 0), palette[2]);
+
         Ok(())
     }
 }
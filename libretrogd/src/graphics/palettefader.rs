@@ -0,0 +1,200 @@
+use crate::graphics::*;
+
+/// Fades the colors of a palette from their starting values towards a target (such as black,
+/// white or another palette) over a fixed number of steps, one step per call to
+/// [PaletteFader::step]. Typically one step is run per frame, which makes this suitable for
+/// fade-ins, fade-outs, cross-fades between palettes and effects like lightning flashes.
+///
+/// Unlike [Palette::fade_colors_toward_rgb], which moves each color component by a fixed amount
+/// per call, every color faded by a PaletteFader reaches its target on the very same step.
+#[derive(Debug, Clone)]
+pub struct PaletteFader {
+    source: Palette,
+    target: Palette,
+    start: u8,
+    end: u8,
+    num_steps: u32,
+    current_step: u32,
+}
+
+impl PaletteFader {
+    /// Creates a fader which fades all colors from the source palette towards the colors in the
+    /// target palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: the palette colors to start the fade from
+    /// * `target`: the palette colors to fade towards
+    /// * `num_steps`: the number of steps the fade should take to complete
+    pub fn fade_to_palette(source: &Palette, target: &Palette, num_steps: u32) -> PaletteFader {
+        PaletteFader {
+            source: source.clone(),
+            target: target.clone(),
+            start: 0,
+            end: 255,
+            num_steps: num_steps.max(1),
+            current_step: 0,
+        }
+    }
+
+    /// Creates a fader which fades all colors from the source palette towards the given RGB
+    /// values.
+    ///
+    /// # Arguments
+    ///
+    /// * `source`: the palette colors to start the fade from
+    /// * `r`: the target red component (0-255) to fade towards
+    /// * `g`: the target green component (0-255) to fade towards
+    /// * `b`: the target blue component (0-255) to fade towards
+    /// * `num_steps`: the number of steps the fade should take to complete
+    pub fn fade_to_rgb(source: &Palette, r: u8, g: u8, b: u8, num_steps: u32) -> PaletteFader {
+        Self::fade_to_palette(source, &Palette::new_with_default(r, g, b), num_steps)
+    }
+
+    /// Creates a fader which fades all colors from the source palette towards black.
+    pub fn fade_to_black(source: &Palette, num_steps: u32) -> PaletteFader {
+        Self::fade_to_rgb(source, 0, 0, 0, num_steps)
+    }
+
+    /// Creates a fader which fades all colors from the source palette towards white.
+    pub fn fade_to_white(source: &Palette, num_steps: u32) -> PaletteFader {
+        Self::fade_to_rgb(source, 255, 255, 255, num_steps)
+    }
+
+    /// Restricts this fader to only fading the given range of colors. Colors outside of this
+    /// range are never modified by [PaletteFader::step].
+    ///
+    /// # Arguments
+    ///
+    /// * `colors`: the range of colors to be faded
+    pub fn with_colors<T: ColorRange>(mut self, colors: T) -> PaletteFader {
+        let (start, end) = color_range_bounds(&colors);
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    /// Returns a new fader which fades the same range of colors back from this fader's target to
+    /// its source over the same number of steps. Useful for effects like lightning flashes which
+    /// fade towards white and then back again.
+    pub fn reversed(&self) -> PaletteFader {
+        PaletteFader {
+            source: self.target.clone(),
+            target: self.source.clone(),
+            start: self.start,
+            end: self.end,
+            num_steps: self.num_steps,
+            current_step: 0,
+        }
+    }
+
+    /// Runs the next step of the fade, writing the faded colors into the given palette. This is
+    /// intended to be called once per frame until the fade is complete. Calling this after the
+    /// fade has completed just writes the final target colors again.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to write the faded colors into
+    ///
+    /// returns: true if the fade has completed, false otherwise
+    pub fn step(&mut self, palette: &mut Palette) -> bool {
+        if self.current_step < self.num_steps {
+            self.current_step += 1;
+        }
+        palette.lerp(
+            self.start..=self.end,
+            &self.source,
+            &self.target,
+            self.progress(),
+        );
+        self.is_complete()
+    }
+
+    /// Returns true if all of the steps of this fade have been run.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.current_step >= self.num_steps
+    }
+
+    /// Returns how far along this fade is, as a fraction between 0.0 and 1.0.
+    #[inline]
+    pub fn progress(&self) -> f32 {
+        self.current_step as f32 / self.num_steps as f32
+    }
+
+    #[inline]
+    pub fn current_step(&self) -> u32 {
+        self.current_step
+    }
+
+    #[inline]
+    pub fn num_steps(&self) -> u32 {
+        self.num_steps
+    }
+
+    /// Restarts this fade from the beginning. The palette being faded is not modified until the
+    /// next call to [PaletteFader::step].
+    pub fn reset(&mut self) {
+        self.current_step = 0;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn fade_to_black_and_back() {
+        let source = Palette::new_with_default(200, 100, 40);
+        let mut palette = source.clone();
+
+        let mut fader = PaletteFader::fade_to_black(&source, 4);
+        assert!(!fader.is_complete());
+        assert!(!fader.step(&mut palette));
+        assert_eq!(to_rgb32(150, 75, 30), palette[0]);
+        assert_eq!(to_rgb32(150, 75, 30), palette[255]);
+        assert!(!fader.step(&mut palette));
+        assert_eq!(to_rgb32(100, 50, 20), palette[10]);
+        assert_eq!(0.5, fader.progress());
+        assert!(!fader.step(&mut palette));
+        assert!(fader.step(&mut palette));
+        assert!(fader.is_complete());
+        assert_eq!(to_rgb32(0, 0, 0), palette[10]);
+        // stepping past the end keeps the target colors
+        assert!(fader.step(&mut palette));
+        assert_eq!(4, fader.current_step());
+        assert_eq!(to_rgb32(0, 0, 0), palette[10]);
+
+        let mut fader = fader.reversed();
+        assert!(!fader.step(&mut palette));
+        assert_eq!(to_rgb32(50, 25, 10), palette[10]);
+        while !fader.step(&mut palette) {}
+        assert_eq!(source, palette);
+
+        fader.reset();
+        assert_eq!(0, fader.current_step());
+        assert!(!fader.is_complete());
+    }
+
+    #[test]
+    pub fn fade_restricted_range() {
+        let source = Palette::new_with_default(0, 0, 0);
+        let mut target = Palette::new_with_default(0, 0, 0);
+        target[20] = to_rgb32(10, 20, 30);
+        let mut palette = source.clone();
+
+        let mut fader = PaletteFader::fade_to_white(&source, 2).with_colors(10..20);
+        assert!(!fader.step(&mut palette));
+        assert!(fader.step(&mut palette));
+        assert_eq!(to_rgb32(0, 0, 0), palette[9]);
+        assert_eq!(to_rgb32(255, 255, 255), palette[10]);
+        assert_eq!(to_rgb32(255, 255, 255), palette[19]);
+        assert_eq!(to_rgb32(0, 0, 0), palette[20]);
+
+        let mut palette = source.clone();
+        let mut fader = PaletteFader::fade_to_palette(&source, &target, 0).with_colors(20..=20);
+        assert_eq!(1, fader.num_steps());
+        assert!(fader.step(&mut palette));
+        assert_eq!(target, palette);
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+
+use crate::graphics::*;
+use crate::NUM_COLORS;
+
+/// Options controlling how truecolor pixel data is converted to indexed color.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct QuantizeOptions {
+    /// The maximum number of colors to generate a palette with, from 1 to 256 (including the
+    /// transparent color, if one is used).
+    pub num_colors: usize,
+    /// Whether to apply Floyd-Steinberg dithering when mapping pixels to palette colors.
+    pub dither: bool,
+    /// If given, pixels which are mostly transparent (alpha below 128) are all mapped to this
+    /// color index, which is then not used for any other colors.
+    pub transparent_color: Option<u8>,
+}
+
+impl Default for QuantizeOptions {
+    fn default() -> Self {
+        QuantizeOptions {
+            num_colors: NUM_COLORS,
+            dither: false,
+            transparent_color: None,
+        }
+    }
+}
+
+#[inline]
+fn is_transparent(argb: u32, transparent_color: Option<u8>) -> bool {
+    transparent_color.is_some() && (argb >> 24) < 128
+}
+
+/// A box of colors (and how many pixels use each of them) for median cut quantization.
+struct ColorBox {
+    colors: Vec<([u8; 3], u32)>,
+}
+
+impl ColorBox {
+    /// Returns the channel with the widest range of values in this box, and that range.
+    fn widest_channel(&self) -> (usize, u8) {
+        let mut min = [255u8; 3];
+        let mut max = [0u8; 3];
+        for (color, _) in self.colors.iter() {
+            for channel in 0..3 {
+                min[channel] = min[channel].min(color[channel]);
+                max[channel] = max[channel].max(color[channel]);
+            }
+        }
+        (0..3)
+            .map(|channel| (channel, max[channel] - min[channel]))
+            .max_by_key(|&(_, range)| range)
+            .unwrap()
+    }
+
+    /// Splits this box into two at the median (by pixel count) of its widest channel.
+    fn split(mut self) -> (ColorBox, ColorBox) {
+        let (channel, _) = self.widest_channel();
+        self.colors
+            .sort_unstable_by_key(|(color, _)| color[channel]);
+        let total: u64 = self.colors.iter().map(|&(_, count)| count as u64).sum();
+        let mut running = 0u64;
+        let mut split_at = self.colors.len() / 2;
+        for (index, &(_, count)) in self.colors.iter().enumerate() {
+            running += count as u64;
+            if running * 2 >= total {
+                split_at = index + 1;
+                break;
+            }
+        }
+        let split_at = split_at.clamp(1, self.colors.len() - 1);
+        let other = self.colors.split_off(split_at);
+        (self, ColorBox { colors: other })
+    }
+
+    /// Returns the average color of this box, weighted by pixel count.
+    fn average(&self) -> [u8; 3] {
+        let mut sums = [0u64; 3];
+        let mut total = 0u64;
+        for &(color, count) in self.colors.iter() {
+            for channel in 0..3 {
+                sums[channel] += color[channel] as u64 * count as u64;
+            }
+            total += count as u64;
+        }
+        let total = total.max(1);
+        [
+            (sums[0] / total) as u8,
+            (sums[1] / total) as u8,
+            (sums[2] / total) as u8,
+        ]
+    }
+}
+
+/// Returns the palette indices that generated colors are assigned to, in order.
+fn available_indices(transparent_color: Option<u8>) -> impl Iterator<Item = u8> {
+    (0..=255u8).filter(move |&index| Some(index) != transparent_color)
+}
+
+/// Generates a palette for the given 32-bit (0xAARRGGBB) pixel data using median cut
+/// quantization. Colors are assigned to the palette in order from index 0 (skipping over the
+/// transparent color, if one is given in the options). Any unused palette entries are black.
+///
+/// # Arguments
+///
+/// * `pixels`: the pixel data to generate a palette for
+/// * `options`: the number of colors to generate and which color is transparent (if any)
+///
+/// returns: the generated palette, and the number of colors generated
+pub fn generate_palette(pixels: &[u32], options: &QuantizeOptions) -> (Palette, usize) {
+    let mut counts: HashMap<u32, u32> = HashMap::new();
+    for &argb in pixels.iter() {
+        if !is_transparent(argb, options.transparent_color) {
+            *counts.entry(argb & 0x00ffffff).or_insert(0) += 1;
+        }
+    }
+
+    let max_colors = match options.transparent_color {
+        Some(_) => options.num_colors.clamp(1, NUM_COLORS) - 1,
+        None => options.num_colors.clamp(1, NUM_COLORS),
+    };
+
+    let mut boxes = Vec::new();
+    if !counts.is_empty() {
+        let colors = counts
+            .into_iter()
+            .map(|(rgb, count)| {
+                let (r, g, b) = from_rgb32(rgb);
+                ([r, g, b], count)
+            })
+            .collect();
+        boxes.push(ColorBox { colors });
+    }
+
+    while boxes.len() < max_colors {
+        // always split whichever box currently covers the widest range of colors
+        let candidate = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, color_box)| color_box.colors.len() > 1)
+            .max_by_key(|(_, color_box)| color_box.widest_channel().1)
+            .map(|(index, _)| index);
+        match candidate {
+            Some(index) => {
+                let (a, b) = boxes.swap_remove(index).split();
+                boxes.push(a);
+                boxes.push(b);
+            }
+            None => break,
+        }
+    }
+
+    let mut palette = Palette::new_with_default(0, 0, 0);
+    for (color_box, index) in boxes
+        .iter()
+        .zip(available_indices(options.transparent_color))
+    {
+        let [r, g, b] = color_box.average();
+        palette[index] = to_rgb32(r, g, b);
+    }
+    (palette, boxes.len())
+}
+
+/// Finds the closest color (by squared euclidean distance) out of the given candidates.
+fn closest_color(candidates: &[(u8, [i32; 3])], color: [i32; 3]) -> u8 {
+    let mut closest = 0;
+    let mut closest_distance = i32::MAX;
+    for &(index, candidate) in candidates.iter() {
+        let dr = candidate[0] - color[0];
+        let dg = candidate[1] - color[1];
+        let db = candidate[2] - color[2];
+        let distance = dr * dr + dg * dg + db * db;
+        if distance < closest_distance {
+            closest = index;
+            closest_distance = distance;
+            if distance == 0 {
+                break;
+            }
+        }
+    }
+    closest
+}
+
+/// Maps 32-bit (0xAARRGGBB) pixel data to the closest colors in the given palette, optionally
+/// applying Floyd-Steinberg dithering (according to the options given) to reduce banding.
+///
+/// # Arguments
+///
+/// * `pixels`: the pixel data to convert, a row at a time with no padding
+/// * `width`: the width of the pixel data
+/// * `height`: the height of the pixel data
+/// * `palette`: the palette to map the pixel data to
+/// * `num_colors`: how many of the palette's colors to map to. Only this many colors, starting
+///   from index 0 and skipping over the transparent color (if any), will be used.
+/// * `options`: whether to dither, and which color is transparent (if any). The number of
+///   colors specified in the options is not used.
+///
+/// returns: `Result<Bitmap, BitmapError>`
+pub fn map_to_palette(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    palette: &Palette,
+    num_colors: usize,
+    options: &QuantizeOptions,
+) -> Result<Bitmap, BitmapError> {
+    if pixels.len() < (width * height) as usize {
+        return Err(BitmapError::OutOfBounds);
+    }
+    let mut bmp = Bitmap::new(width, height)?;
+
+    let candidates: Vec<(u8, [i32; 3])> = available_indices(options.transparent_color)
+        .take(num_colors.clamp(1, NUM_COLORS))
+        .map(|index| {
+            let (r, g, b) = from_rgb32(palette[index]);
+            (index, [r as i32, g as i32, b as i32])
+        })
+        .collect();
+    let mut color_cache: HashMap<[i32; 3], u8> = HashMap::new();
+
+    let width = width as usize;
+    // accumulated error for the current and next rows, with an extra entry on each side so that
+    // error can be diffused past the edges without any special cases
+    let mut errors = vec![[0i32; 3]; width + 2];
+    let mut next_errors = vec![[0i32; 3]; width + 2];
+
+    for (src_row, dest_row) in pixels
+        .chunks_exact(width)
+        .zip(bmp.pixels_mut().chunks_exact_mut(width))
+    {
+        for (x, (&argb, dest)) in src_row.iter().zip(dest_row.iter_mut()).enumerate() {
+            if is_transparent(argb, options.transparent_color) {
+                *dest = options.transparent_color.unwrap();
+                continue;
+            }
+
+            let (r, g, b) = from_rgb32(argb);
+            let mut color = [r as i32, g as i32, b as i32];
+            if options.dither {
+                for channel in 0..3 {
+                    color[channel] = (color[channel] + errors[x + 1][channel] / 16).clamp(0, 255);
+                }
+            }
+
+            let index = *color_cache
+                .entry(color)
+                .or_insert_with(|| closest_color(&candidates, color));
+            *dest = index;
+
+            if options.dither {
+                let (pr, pg, pb) = from_rgb32(palette[index]);
+                let mapped = [pr as i32, pg as i32, pb as i32];
+                for channel in 0..3 {
+                    let error = color[channel] - mapped[channel];
+                    errors[x + 2][channel] += error * 7;
+                    next_errors[x][channel] += error * 3;
+                    next_errors[x + 1][channel] += error * 5;
+                    next_errors[x + 2][channel] += error;
+                }
+            }
+        }
+
+        std::mem::swap(&mut errors, &mut next_errors);
+        next_errors.fill([0; 3]);
+    }
+
+    Ok(bmp)
+}
+
+/// Converts 32-bit (0xAARRGGBB) pixel data into an indexed color Bitmap, along with a palette
+/// generated for it using median cut quantization (see [generate_palette]).
+///
+/// # Arguments
+///
+/// * `pixels`: the pixel data to convert, a row at a time with no padding
+/// * `width`: the width of the pixel data
+/// * `height`: the height of the pixel data
+/// * `options`: the number of colors to generate, whether to dither and which color is
+///   transparent (if any)
+///
+/// returns: `Result<(Bitmap, Palette), BitmapError>`
+pub fn quantize(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    options: &QuantizeOptions,
+) -> Result<(Bitmap, Palette), BitmapError> {
+    let (palette, num_colors) = generate_palette(pixels, options);
+    let bmp = map_to_palette(pixels, width, height, &palette, num_colors, options)?;
+    Ok((bmp, palette))
+}
+
+impl RgbaBitmap {
+    /// Converts this bitmap into an indexed color Bitmap, along with a palette generated for it.
+    /// See [quantize] for more details.
+    pub fn quantize(&self, options: &QuantizeOptions) -> (Bitmap, Palette) {
+        // the dimensions of an existing RgbaBitmap are always valid, so this cannot fail
+        quantize(self.pixels(), self.width(), self.height(), options).unwrap()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> Vec<u32> {
+        let mut pixels = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(to_rgb32((x * 255 / (width - 1)) as u8, (y * 4) as u8, 128));
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    pub fn few_colors_are_preserved_exactly() -> Result<(), BitmapError> {
+        let colors = [
+            to_rgb32(255, 0, 0),
+            to_rgb32(0, 255, 0),
+            to_rgb32(0, 0, 255),
+            to_rgb32(10, 20, 30),
+        ];
+        let pixels: Vec<u32> = (0..64).map(|i| colors[(i * 7) % 4]).collect();
+
+        let options = QuantizeOptions::default();
+        let (bmp, palette) = quantize(&pixels, 8, 8, &options)?;
+        assert_eq!(8, bmp.width());
+        assert_eq!(8, bmp.height());
+        for (&index, &argb) in bmp.pixels().iter().zip(pixels.iter()) {
+            assert_eq!(argb, palette[index]);
+        }
+        let (_, num_colors) = generate_palette(&pixels, &options);
+        assert_eq!(4, num_colors);
+
+        // dithering has no effect when every color is exact
+        let options = QuantizeOptions {
+            dither: true,
+            ..options
+        };
+        let (dithered_bmp, _) = quantize(&pixels, 8, 8, &options)?;
+        assert_eq!(bmp, dithered_bmp);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn reduces_colors() -> Result<(), BitmapError> {
+        let pixels = gradient(64, 16);
+        for dither in [false, true] {
+            let options = QuantizeOptions {
+                num_colors: 8,
+                dither,
+                transparent_color: None,
+            };
+            let (bmp, palette) = quantize(&pixels, 64, 16, &options)?;
+            assert!(bmp.pixels().iter().all(|&index| index < 8));
+            assert!((8..=255).all(|index| palette[index] == to_rgb32(0, 0, 0)));
+
+            // the average error over the whole image should be fairly small
+            let total_error: u32 = bmp
+                .pixels()
+                .iter()
+                .zip(pixels.iter())
+                .map(|(&index, &argb)| {
+                    let (r1, g1, b1) = from_rgb32(argb);
+                    let (r2, g2, b2) = from_rgb32(palette[index]);
+                    (r1 as i32 - r2 as i32).unsigned_abs()
+                        + (g1 as i32 - g2 as i32).unsigned_abs()
+                        + (b1 as i32 - b2 as i32).unsigned_abs()
+                })
+                .sum();
+            assert!(total_error / (pixels.len() as u32) < 40);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn transparency() -> Result<(), BitmapError> {
+        let mut rgba = RgbaBitmap::new(4, 4).unwrap();
+        rgba.clear(to_argb32(0, 0, 0, 0));
+        rgba.filled_rect(1, 1, 2, 2, to_argb32(255, 200, 100, 50));
+        let options = QuantizeOptions {
+            num_colors: 16,
+            dither: true,
+            transparent_color: Some(0),
+        };
+        let (bmp, palette) = rgba.quantize(&options);
+        assert_eq!(Some(0), bmp.get_pixel(0, 0));
+        assert_eq!(Some(1), bmp.get_pixel(1, 1));
+        assert_eq!(to_rgb32(200, 100, 50), palette[1]);
+
+        assert_matches!(
+            quantize(&[0u32; 3], 2, 2, &options),
+            Err(BitmapError::OutOfBounds)
+        );
+
+        Ok(())
+    }
+}
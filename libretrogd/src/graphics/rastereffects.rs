@@ -0,0 +1,190 @@
+use crate::graphics::*;
+
+/// A set of palette color changes which take effect starting at a specific scanline.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RasterPaletteChange {
+    /// The scanline (y coordinate) at which these changes take effect.
+    pub scanline: u32,
+    /// The palette colors to change, as pairs of color index and 32-bit packed color.
+    pub colors: Vec<(u8, u32)>,
+}
+
+/// A list of palette changes to be made part way through converting an 8-bit bitmap (typically
+/// the screen) to 32-bit pixels, as if the palette were being changed mid-frame while the display
+/// was being drawn. This allows for old-school "raster" effects like copper bars, or using one
+/// palette for the sky and a different one below the horizon.
+///
+/// Changes are cumulative and persist for the remainder of the frame once made, just as if the
+/// palette had been changed on real hardware. The palette given for the conversion is never
+/// itself modified.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct RasterEffects {
+    changes: Vec<RasterPaletteChange>,
+}
+
+impl RasterEffects {
+    pub fn new() -> RasterEffects {
+        RasterEffects {
+            changes: Vec::new(),
+        }
+    }
+
+    /// Returns the changes for the given scanline, adding an empty set of changes for it first
+    /// if there were none, while keeping all changes sorted by scanline.
+    fn changes_at(&mut self, scanline: u32) -> &mut Vec<(u8, u32)> {
+        let index = match self
+            .changes
+            .binary_search_by_key(&scanline, |change| change.scanline)
+        {
+            Ok(index) => index,
+            Err(index) => {
+                let change = RasterPaletteChange {
+                    scanline,
+                    colors: Vec::new(),
+                };
+                self.changes.insert(index, change);
+                index
+            }
+        };
+        &mut self.changes[index].colors
+    }
+
+    /// Changes a single palette color starting at the given scanline.
+    ///
+    /// # Arguments
+    ///
+    /// * `scanline`: the scanline (y coordinate) at which the change takes effect
+    /// * `color`: the palette color index to change
+    /// * `argb`: the 32-bit packed color to change it to
+    pub fn set_color(&mut self, scanline: u32, color: u8, argb: u32) {
+        self.changes_at(scanline).push((color, argb));
+    }
+
+    /// Changes a range of palette colors starting at the given scanline, taking the new colors
+    /// from the same indices in the palette given.
+    ///
+    /// # Arguments
+    ///
+    /// * `scanline`: the scanline (y coordinate) at which the change takes effect
+    /// * `colors`: the range of palette colors to change
+    /// * `palette`: the palette to take the new colors from
+    pub fn set_colors<T: ColorRange>(&mut self, scanline: u32, colors: T, palette: &Palette) {
+        let changes = self.changes_at(scanline);
+        for color in colors {
+            changes.push((color, palette[color]));
+        }
+    }
+
+    /// Switches to an entirely different palette starting at the given scanline.
+    pub fn set_palette(&mut self, scanline: u32, palette: &Palette) {
+        self.set_colors(scanline, 0..=255, palette);
+    }
+
+    /// Returns all of the palette changes, sorted by scanline.
+    #[inline]
+    pub fn changes(&self) -> &[RasterPaletteChange] {
+        &self.changes
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Removes all palette changes.
+    pub fn clear(&mut self) {
+        self.changes.clear();
+    }
+}
+
+impl<P: PixelBuffer> Bitmap<P> {
+    /// Same as [Bitmap::copy_as_argb_to], except that the given palette changes are applied as
+    /// the conversion reaches each of the scanlines they are specified for.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest`: destination 32-bit ARGB pixel buffer to copy converted pixels to
+    /// * `palette`: the 256 colour palette to begin the pixel conversion with
+    /// * `effects`: the palette changes to make part way through the conversion
+    pub fn copy_as_argb_to_with_raster_effects(
+        &self,
+        dest: &mut [u32],
+        palette: &Palette,
+        effects: &RasterEffects,
+    ) {
+        if effects.is_empty() {
+            self.copy_as_argb_to(dest, palette);
+            return;
+        }
+
+        let mut palette = palette.clone();
+        let mut changes = effects.changes().iter().peekable();
+        let width = self.width() as usize;
+        let rows = self.pixels().chunks_exact(self.pitch() as usize);
+        for (y, (src_row, dest_row)) in rows.zip(dest.chunks_mut(width)).enumerate() {
+            while let Some(change) = changes.next_if(|change| change.scanline as usize <= y) {
+                for &(color, argb) in change.colors.iter() {
+                    palette[color] = argb;
+                }
+            }
+            for (src, dest) in src_row[0..width].iter().zip(dest_row.iter_mut()) {
+                *dest = palette[*src];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn raster_palette_changes() {
+        let mut palette = Palette::new();
+        palette[1] = to_rgb32(255, 0, 0);
+        palette[2] = to_rgb32(0, 255, 0);
+        let mut other_palette = Palette::new_with_default(0, 0, 255);
+        other_palette[2] = to_rgb32(255, 255, 255);
+
+        let mut bmp = Bitmap::new(2, 5).unwrap();
+        bmp.clear(1);
+        bmp.set_pixel(1, 0, 2);
+        bmp.set_pixel(1, 4, 2);
+
+        let mut effects = RasterEffects::new();
+        assert!(effects.is_empty());
+        effects.set_palette(4, &other_palette);
+        effects.set_color(2, 1, to_rgb32(1, 2, 3));
+        effects.set_color(2, 1, to_rgb32(4, 5, 6));
+        effects.set_colors(3, 1..=1, &palette);
+        assert_eq!(
+            vec![2, 3, 4],
+            effects
+                .changes()
+                .iter()
+                .map(|change| change.scanline)
+                .collect::<Vec<_>>()
+        );
+
+        let mut argb = [0u32; 10];
+        bmp.copy_as_argb_to_with_raster_effects(&mut argb, &palette, &effects);
+        #[rustfmt::skip]
+        assert_eq!(
+            [
+                palette[1], palette[2],
+                palette[1], palette[1],
+                to_rgb32(4, 5, 6), to_rgb32(4, 5, 6),
+                palette[1], palette[1],
+                other_palette[1], other_palette[2],
+            ],
+            argb
+        );
+
+        // with no changes, the conversion is the same as a normal one
+        effects.clear();
+        let mut expected = [0u32; 10];
+        bmp.copy_as_argb_to(&mut expected, &palette);
+        bmp.copy_as_argb_to_with_raster_effects(&mut argb, &palette, &effects);
+        assert_eq!(expected, argb);
+    }
+}
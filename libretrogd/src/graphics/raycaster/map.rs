@@ -0,0 +1,356 @@
+use crate::math::*;
+
+/// The side of a wall cell which was hit by a ray.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WallFace {
+    /// The side facing towards negative y, hit by rays travelling towards positive y.
+    North,
+    /// The side facing towards positive y, hit by rays travelling towards negative y.
+    South,
+    /// The side facing towards positive x, hit by rays travelling towards negative x.
+    East,
+    /// The side facing towards negative x, hit by rays travelling towards positive x.
+    West,
+}
+
+/// Where a ray cast with [RaycastMap::cast_ray] hit a wall.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RayHit {
+    /// The x coordinate of the wall cell which was hit.
+    pub cell_x: i32,
+    /// The y coordinate of the wall cell which was hit.
+    pub cell_y: i32,
+    /// The value of the wall cell which was hit.
+    pub wall: u8,
+    pub face: WallFace,
+    /// The point where the ray hit the wall.
+    pub point: Vector2,
+    /// How far along the ray the wall was hit, in multiples of the ray's direction vector. This
+    /// is the euclidean distance if the direction was a unit vector.
+    pub distance: f32,
+    /// Where along the wall face the ray hit, from 0.0 to 1.0, going from left to right as seen
+    /// when facing the wall. Used to pick the column of the wall's texture to draw.
+    pub texture_x: f32,
+}
+
+/// A grid of wall cells for a [super::Raycaster] to draw. Each cell is 1.0 units wide and tall,
+/// so the cell a position lies within is found by rounding its coordinates down. A cell value of
+/// zero is empty space, and any other value is a wall, drawn using the region with index
+/// `value - 1` in the wall [crate::graphics::BitmapAtlas] given to [super::Raycaster::render].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RaycastMap {
+    width: u32,
+    height: u32,
+    cells: Box<[u8]>,
+}
+
+impl RaycastMap {
+    /// Creates a new map of the given size, with all cells empty.
+    pub fn new(width: u32, height: u32) -> RaycastMap {
+        RaycastMap {
+            width,
+            height,
+            cells: vec![0u8; (width * height) as usize].into_boxed_slice(),
+        }
+    }
+
+    /// Creates a new map of the given size from existing cell values, in rows from top to
+    /// bottom. Returns `None` if there are not exactly `width * height` cells.
+    pub fn from_cells(width: u32, height: u32, cells: Vec<u8>) -> Option<RaycastMap> {
+        if cells.len() != (width * height) as usize {
+            return None;
+        }
+        Some(RaycastMap {
+            width,
+            height,
+            cells: cells.into_boxed_slice(),
+        })
+    }
+
+    /// Returns the width of the map, in cells.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the map, in cells.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns all of the map's cells, in rows from top to bottom.
+    #[inline]
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    #[inline]
+    fn index_of(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            Some((y as u32 * self.width + x as u32) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value of the cell at the given coordinates, or `None` if they are outside of
+    /// the map.
+    #[inline]
+    pub fn get(&self, x: i32, y: i32) -> Option<u8> {
+        self.index_of(x, y).map(|index| self.cells[index])
+    }
+
+    /// Sets the value of the cell at the given coordinates. Returns false if they are outside of
+    /// the map.
+    pub fn set(&mut self, x: i32, y: i32, value: u8) -> bool {
+        match self.index_of(x, y) {
+            Some(index) => {
+                self.cells[index] = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if the cell at the given coordinates is a wall. Everything outside of the map
+    /// is treated as a wall.
+    #[inline]
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).map_or(true, |value| value != 0)
+    }
+
+    /// Casts a ray through the map, stepping through every cell it passes through until it hits a
+    /// wall. The cell the ray starts in is not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin`: the point to cast the ray from
+    /// * `direction`: the direction of the ray, which does not need to be normalized
+    /// * `max_distance`: how far along the ray to look for walls, in multiples of `direction`
+    ///
+    /// returns: where the ray hit a wall, or `None` if it left the map or went further than
+    /// `max_distance` without hitting anything
+    pub fn cast_ray(
+        &self,
+        origin: Vector2,
+        direction: Vector2,
+        max_distance: f32,
+    ) -> Option<RayHit> {
+        let mut cell_x = origin.x.floor() as i32;
+        let mut cell_y = origin.y.floor() as i32;
+
+        // how far along the ray it is between each vertical and horizontal cell boundary, and
+        // how far along it is to the first one of each
+        let delta_x = if direction.x == 0.0 {
+            f32::INFINITY
+        } else {
+            (1.0 / direction.x).abs()
+        };
+        let delta_y = if direction.y == 0.0 {
+            f32::INFINITY
+        } else {
+            (1.0 / direction.y).abs()
+        };
+        let (step_x, mut next_x) = if direction.x < 0.0 {
+            (-1, (origin.x - cell_x as f32) * delta_x)
+        } else {
+            (1, (cell_x as f32 + 1.0 - origin.x) * delta_x)
+        };
+        let (step_y, mut next_y) = if direction.y < 0.0 {
+            (-1, (origin.y - cell_y as f32) * delta_y)
+        } else {
+            (1, (cell_y as f32 + 1.0 - origin.y) * delta_y)
+        };
+
+        loop {
+            let (distance, face) = if next_x < next_y {
+                let distance = next_x;
+                next_x += delta_x;
+                cell_x += step_x;
+                (
+                    distance,
+                    if step_x > 0 {
+                        WallFace::West
+                    } else {
+                        WallFace::East
+                    },
+                )
+            } else {
+                let distance = next_y;
+                next_y += delta_y;
+                cell_y += step_y;
+                (
+                    distance,
+                    if step_y > 0 {
+                        WallFace::North
+                    } else {
+                        WallFace::South
+                    },
+                )
+            };
+            if distance > max_distance {
+                return None;
+            }
+            let wall = match self.get(cell_x, cell_y) {
+                Some(0) => continue,
+                Some(wall) => wall,
+                None => return None,
+            };
+
+            let point = origin + direction * distance;
+            let texture_x = match face {
+                WallFace::West => point.y - point.y.floor(),
+                WallFace::East => 1.0 - (point.y - point.y.floor()),
+                WallFace::North => 1.0 - (point.x - point.x.floor()),
+                WallFace::South => point.x - point.x.floor(),
+            };
+            return Some(RayHit {
+                cell_x,
+                cell_y,
+                wall,
+                face,
+                point,
+                distance,
+                texture_x,
+            });
+        }
+    }
+
+    /// Returns true if a square with the given center, extending `radius` units out from it in
+    /// each direction, overlaps any walls.
+    pub fn overlaps_wall(&self, center: Vector2, radius: f32) -> bool {
+        let left = (center.x - radius).floor() as i32;
+        let right = (center.x + radius).floor() as i32;
+        let top = (center.y - radius).floor() as i32;
+        let bottom = (center.y + radius).floor() as i32;
+        (top..=bottom).any(|y| (left..=right).any(|x| self.is_wall(x, y)))
+    }
+
+    /// Moves something (such as the player) through the map, keeping it from moving into any
+    /// walls. Movement along each axis is checked separately, so that moving diagonally into a
+    /// wall slides along it. Treats the thing being moved as a square as wide as twice the given
+    /// radius, which is simple and works well enough in a grid of square walls.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: where the thing being moved currently is
+    /// * `delta`: how far to move it. This should be less than a cell in each direction.
+    /// * `radius`: how close it can get to walls
+    ///
+    /// returns: the new position
+    pub fn move_with_collision(&self, position: Vector2, delta: Vector2, radius: f32) -> Vector2 {
+        let mut position = position;
+        let moved = Vector2::new(position.x + delta.x, position.y);
+        if !self.overlaps_wall(moved, radius) {
+            position = moved;
+        }
+        let moved = Vector2::new(position.x, position.y + delta.y);
+        if !self.overlaps_wall(moved, radius) {
+            position = moved;
+        }
+        position
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // a 5x5 room with walls around the outside and a pillar in the middle of the top edge
+    fn test_map() -> RaycastMap {
+        let mut map = RaycastMap::new(5, 5);
+        for i in 0..5 {
+            map.set(i, 0, 1);
+            map.set(i, 4, 1);
+            map.set(0, i, 1);
+            map.set(4, i, 1);
+        }
+        map.set(2, 1, 2);
+        map
+    }
+
+    #[test]
+    pub fn cells() {
+        let map = test_map();
+        assert_eq!(5, map.width());
+        assert_eq!(Some(2), map.get(2, 1));
+        assert_eq!(Some(0), map.get(1, 1));
+        assert_eq!(None, map.get(5, 1));
+        assert!(map.is_wall(0, 2));
+        assert!(!map.is_wall(2, 2));
+        assert!(map.is_wall(-1, 2));
+
+        assert!(RaycastMap::from_cells(2, 2, vec![0, 1, 1]).is_none());
+        let map = RaycastMap::from_cells(2, 2, vec![0, 1, 1, 0]).unwrap();
+        assert_eq!(Some(1), map.get(0, 1));
+    }
+
+    #[test]
+    pub fn casting_rays() {
+        let map = test_map();
+        let origin = Vector2::new(2.5, 2.5);
+
+        let hit = map.cast_ray(origin, Vector2::new(1.0, 0.0), 100.0).unwrap();
+        assert_eq!((4, 2), (hit.cell_x, hit.cell_y));
+        assert_eq!(1, hit.wall);
+        assert_eq!(WallFace::West, hit.face);
+        assert_eq!(1.5, hit.distance);
+        assert_eq!(Vector2::new(4.0, 2.5), hit.point);
+        assert_eq!(0.5, hit.texture_x);
+
+        let hit = map
+            .cast_ray(origin, Vector2::new(0.0, -1.0), 100.0)
+            .unwrap();
+        assert_eq!((2, 1), (hit.cell_x, hit.cell_y));
+        assert_eq!(2, hit.wall);
+        assert_eq!(WallFace::South, hit.face);
+        assert_eq!(0.5, hit.distance);
+
+        let hit = map
+            .cast_ray(origin, Vector2::new(-1.0, 0.5), 100.0)
+            .unwrap();
+        assert_eq!((0, 3), (hit.cell_x, hit.cell_y));
+        assert_eq!(WallFace::East, hit.face);
+        assert_eq!(1.5, hit.distance);
+        assert_eq!(0.75, hit.texture_x);
+
+        let hit = map
+            .cast_ray(Vector2::new(1.25, 2.5), Vector2::new(0.0, 2.0), 100.0)
+            .unwrap();
+        assert_eq!(WallFace::North, hit.face);
+        assert_eq!(0.75, hit.distance);
+        assert_eq!(0.75, hit.texture_x);
+
+        assert!(map.cast_ray(origin, Vector2::new(1.0, 0.0), 1.0).is_none());
+
+        // rays leaving a map with no walls around its edges do not hit anything
+        let map = RaycastMap::new(3, 3);
+        assert!(map
+            .cast_ray(origin, Vector2::new(1.0, 1.0), 100.0)
+            .is_none());
+    }
+
+    #[test]
+    pub fn moving_with_collision() {
+        let map = test_map();
+        let position = Vector2::new(1.5, 2.5);
+
+        assert_eq!(
+            Vector2::new(1.75, 2.5),
+            map.move_with_collision(position, Vector2::new(0.25, 0.0), 0.25)
+        );
+        // blocked by the wall to the left, but still slides vertically
+        assert_eq!(
+            Vector2::new(1.5, 2.75),
+            map.move_with_collision(position, Vector2::new(-0.5, 0.25), 0.25)
+        );
+        // blocked by the pillar above
+        assert_eq!(
+            Vector2::new(2.5, 2.5),
+            map.move_with_collision(Vector2::new(2.5, 2.5), Vector2::new(0.0, -0.5), 0.25)
+        );
+        assert!(map.overlaps_wall(Vector2::new(1.2, 2.5), 0.25));
+        assert!(!map.overlaps_wall(Vector2::new(1.3, 2.5), 0.25));
+    }
+}
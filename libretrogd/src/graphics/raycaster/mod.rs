@@ -0,0 +1,5 @@
+pub use self::map::*;
+pub use self::render::*;
+
+pub mod map;
+pub mod render;
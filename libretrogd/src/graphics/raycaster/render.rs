@@ -0,0 +1,471 @@
+use std::rc::Rc;
+
+use crate::graphics::*;
+use crate::math::*;
+
+/// Where a [Raycaster] draws the view from.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RaycastCamera {
+    /// The position of the camera, in map cells.
+    pub position: Vector2,
+    /// The direction the camera is facing, in radians. 0.0 faces towards positive x, and (as
+    /// with the rest of the library) positive angles turn towards positive y.
+    pub angle: f32,
+    /// The horizontal field of view, in radians.
+    pub fov: f32,
+}
+
+impl RaycastCamera {
+    /// Creates a new camera with a 66 degree field of view.
+    pub fn new(position: Vector2, angle: f32) -> RaycastCamera {
+        RaycastCamera {
+            position,
+            angle,
+            fov: 66.0 * PI_OVER_180,
+        }
+    }
+
+    /// Returns the unit vector the camera is facing along.
+    #[inline]
+    pub fn direction(&self) -> Vector2 {
+        Vector2::from_angle(self.angle)
+    }
+
+    /// Returns the camera plane: the vector from the center of the view to its right edge, at a
+    /// distance of 1.0 in front of the camera. Rays for each column of the view are cast along
+    /// `direction() + plane() * x`, where `x` goes from -1.0 at the left edge to 1.0 at the right.
+    #[inline]
+    pub fn plane(&self) -> Vector2 {
+        self.direction().perp() * (self.fov * 0.5).tan()
+    }
+}
+
+/// A sprite drawn by [Raycaster::render_sprites], such as an item or enemy.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RaycastSprite {
+    /// The position of the sprite, in map cells.
+    pub position: Vector2,
+    /// The index of the sprite's region in the atlas it is drawn from.
+    pub index: usize,
+    /// How tall the sprite is, where 1.0 is the height of a wall. Sprites always stand on the
+    /// floor, and keep the aspect ratio of their atlas region.
+    pub scale: f32,
+}
+
+impl RaycastSprite {
+    #[inline]
+    pub fn new(position: Vector2, index: usize) -> RaycastSprite {
+        RaycastSprite {
+            position,
+            index,
+            scale: 1.0,
+        }
+    }
+}
+
+/// Draws first-person views of a [RaycastMap] in the style of Wolfenstein 3D. Walls are drawn one
+/// column at a time by casting a ray for each column of the view, and their distances are kept in
+/// a depth buffer which sprites are then clipped against.
+///
+/// Everything is drawn within the destination bitmap's current clip region, so setting a clip
+/// region before rendering can be used to draw a smaller view (for example, to leave room for a
+/// status bar).
+///
+/// ```ignore
+/// raycaster.render(&mut system.video, &map, &walls, &camera);
+/// raycaster.render_sprites(&mut system.video, &sprites, &sprite_atlas, &camera);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Raycaster {
+    /// The color used for the ceiling, above the walls.
+    pub ceiling_color: u8,
+    /// The color used for the floor, below the walls.
+    pub floor_color: u8,
+    /// The color in sprite images which is not drawn.
+    pub transparent_color: u8,
+    /// How far away (in map cells) walls can be seen.
+    pub max_distance: f32,
+    /// If set, walls and sprites are shaded darker the further away they are, using the light
+    /// levels of this color map.
+    pub color_map: Option<Rc<ColorMap>>,
+    /// How far away (in map cells) walls and sprites are fully darkened, when using a color map.
+    pub shade_distance: f32,
+    depth: Vec<f32>,
+}
+
+impl Default for Raycaster {
+    fn default() -> Self {
+        Raycaster::new()
+    }
+}
+
+impl Raycaster {
+    pub fn new() -> Raycaster {
+        Raycaster {
+            ceiling_color: 0,
+            floor_color: 0,
+            transparent_color: 0,
+            max_distance: 64.0,
+            color_map: None,
+            shade_distance: 16.0,
+            depth: Vec::new(),
+        }
+    }
+
+    /// Returns the distance to the wall drawn in each column of the view by the last call to
+    /// [Raycaster::render], from left to right. This is the distance perpendicular to the camera
+    /// plane (not the distance to the camera itself), which keeps walls from appearing curved
+    /// with a "fisheye" effect. Columns without any wall in sight have infinite distance.
+    #[inline]
+    pub fn depth_buffer(&self) -> &[f32] {
+        &self.depth
+    }
+
+    /// Returns the color map light level to use for things at the given distance.
+    fn light_level(&self, color_map: &ColorMap, distance: f32) -> usize {
+        let brightness = (1.0 - distance / self.shade_distance.max(f32::EPSILON)).clamp(0.0, 1.0);
+        (brightness * (color_map.num_levels() - 1) as f32).round() as usize
+    }
+
+    /// Returns the distance from the camera to the projection plane, in pixels, for a view of the
+    /// given width. Something 1.0 units tall at a distance of 1.0 is drawn this many pixels tall.
+    #[inline]
+    fn projection_distance(view_width: u32, camera: &RaycastCamera) -> f32 {
+        (view_width as f32 * 0.5) / (camera.fov * 0.5).tan()
+    }
+
+    /// Draws the walls, floor and ceiling of the map as seen by the camera, filling the whole of
+    /// the destination bitmap's clip region. Walls are drawn using the regions of the given atlas,
+    /// with the region at index `value - 1` used for walls with the cell value `value`. Walls
+    /// whose region is not in the atlas are drawn in a solid color, using the cell value as the
+    /// color.
+    pub fn render(
+        &mut self,
+        dest: &mut Bitmap,
+        map: &RaycastMap,
+        walls: &BitmapAtlas,
+        camera: &RaycastCamera,
+    ) {
+        let view = *dest.clip_region();
+        self.depth.clear();
+        self.depth.resize(view.width as usize, f32::INFINITY);
+        if view.width == 0 || view.height == 0 {
+            return;
+        }
+
+        let direction = camera.direction();
+        let plane = camera.plane();
+        let projection = Self::projection_distance(view.width, camera);
+        let center_y = view.y as f32 + view.height as f32 * 0.5;
+        let dest_pitch = dest.pitch() as usize;
+        let textures = walls.bitmap().pixels();
+        let texture_pitch = walls.bitmap().pitch() as usize;
+        let color_map = self.color_map.clone();
+
+        for column in 0..view.width {
+            let x = view.x + column as i32;
+            let camera_x = 2.0 * (column as f32 + 0.5) / view.width as f32 - 1.0;
+            let ray = direction + plane * camera_x;
+
+            // rows from wall_top up to (but not including) wall_bottom show the wall. everything
+            // above that is ceiling, and everything below is floor
+            let (wall_top, wall_bottom) =
+                match map.cast_ray(camera.position, ray, self.max_distance) {
+                    Some(hit) => {
+                        // the distance along a ray built from the camera plane is already the
+                        // perpendicular distance, which avoids the fisheye effect
+                        let distance = hit.distance.max(f32::EPSILON);
+                        self.depth[column as usize] = distance;
+                        let height = projection / distance;
+                        let top = center_y - height * 0.5;
+                        let wall_top =
+                            ((top - 0.5).ceil() as i32).clamp(view.y, view.y + view.height as i32);
+                        let wall_bottom = ((top + height - 0.5).ceil() as i32)
+                            .clamp(wall_top, view.y + view.height as i32);
+
+                        let level = color_map
+                            .as_ref()
+                            .map(|color_map| self.light_level(color_map, distance));
+                        let region = walls
+                            .get(hit.wall as usize - 1)
+                            .filter(|region| region.width > 0 && region.height > 0);
+                        let pixels = dest.pixels_mut();
+                        for y in wall_top..wall_bottom {
+                            let color = match region {
+                                Some(region) => {
+                                    let texture_x = ((hit.texture_x * region.width as f32) as u32)
+                                        .min(region.width - 1);
+                                    let texture_y = (((y as f32 + 0.5 - top) / height
+                                        * region.height as f32)
+                                        as u32)
+                                        .min(region.height - 1);
+                                    textures[(region.y as usize + texture_y as usize)
+                                        * texture_pitch
+                                        + region.x as usize
+                                        + texture_x as usize]
+                                }
+                                None => hit.wall,
+                            };
+                            pixels[y as usize * dest_pitch + x as usize] = match (&color_map, level)
+                            {
+                                (Some(color_map), Some(level)) => color_map.shade(color, level),
+                                _ => color,
+                            };
+                        }
+                        (wall_top, wall_bottom)
+                    }
+                    None => {
+                        let middle = center_y as i32;
+                        (middle, middle)
+                    }
+                };
+
+            let pixels = dest.pixels_mut();
+            for y in view.y..wall_top {
+                pixels[y as usize * dest_pitch + x as usize] = self.ceiling_color;
+            }
+            for y in wall_bottom..(view.y + view.height as i32) {
+                pixels[y as usize * dest_pitch + x as usize] = self.floor_color;
+            }
+        }
+    }
+
+    /// Draws sprites into the view drawn by the last call to [Raycaster::render], which must have
+    /// been drawn with the same camera and clip region. Sprites are sorted so that further away
+    /// sprites are drawn first, and each column of each sprite is only drawn where it is in front
+    /// of the wall in that column of the view.
+    pub fn render_sprites(
+        &self,
+        dest: &mut Bitmap,
+        sprites: &[RaycastSprite],
+        atlas: &BitmapAtlas,
+        camera: &RaycastCamera,
+    ) {
+        let view = *dest.clip_region();
+        if view.width == 0 || view.height == 0 || self.depth.len() != view.width as usize {
+            return;
+        }
+
+        let direction = camera.direction();
+        let right = direction.perp();
+        let projection = Self::projection_distance(view.width, camera);
+        let center_x = view.x as f32 + view.width as f32 * 0.5;
+        let center_y = view.y as f32 + view.height as f32 * 0.5;
+        let dest_pitch = dest.pitch() as usize;
+        let source = atlas.bitmap().pixels();
+        let source_pitch = atlas.bitmap().pitch() as usize;
+        let color_map = self.color_map.clone();
+
+        // find how far in front of the camera each sprite is, skipping those behind it, and then
+        // draw them from furthest to nearest
+        let mut visible: Vec<(f32, f32, &RaycastSprite)> = sprites
+            .iter()
+            .filter_map(|sprite| {
+                let relative = sprite.position - camera.position;
+                let depth = relative.dot(&direction);
+                (depth > 0.01).then(|| (depth, relative.dot(&right), sprite))
+            })
+            .collect();
+        visible.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (depth, lateral, sprite) in visible {
+            let region = match atlas.get(sprite.index) {
+                Some(region) if region.width > 0 && region.height > 0 => region,
+                _ => continue,
+            };
+            let height = projection / depth * sprite.scale;
+            let width = height * region.width as f32 / region.height as f32;
+            let left = center_x + lateral * projection / depth - width * 0.5;
+            let bottom = center_y + projection / depth * 0.5;
+            let top = bottom - height;
+
+            let view_right = view.x + view.width as i32;
+            let view_bottom = view.y + view.height as i32;
+            let first_x = ((left - 0.5).ceil() as i32).max(view.x);
+            let last_x = ((left + width - 0.5).ceil() as i32).min(view_right);
+            let first_y = ((top - 0.5).ceil() as i32).max(view.y);
+            let last_y = ((bottom - 0.5).ceil() as i32).min(view_bottom);
+            let level = color_map
+                .as_ref()
+                .map(|color_map| self.light_level(color_map, depth));
+            let pixels = dest.pixels_mut();
+
+            for x in first_x..last_x {
+                if depth >= self.depth[(x - view.x) as usize] {
+                    continue;
+                }
+                let source_x = (((x as f32 + 0.5 - left) / width * region.width as f32) as u32)
+                    .min(region.width - 1);
+                for y in first_y..last_y {
+                    let source_y = (((y as f32 + 0.5 - top) / height * region.height as f32)
+                        as u32)
+                        .min(region.height - 1);
+                    let color = source[(region.y as usize + source_y as usize) * source_pitch
+                        + region.x as usize
+                        + source_x as usize];
+                    if color == self.transparent_color {
+                        continue;
+                    }
+                    pixels[y as usize * dest_pitch + x as usize] = match (&color_map, level) {
+                        (Some(color_map), Some(level)) => color_map.shade(color, level),
+                        _ => color,
+                    };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const CEILING: u8 = 9;
+    const FLOOR: u8 = 10;
+
+    // a 5x5 room with walls around the outside
+    fn test_map() -> RaycastMap {
+        let mut map = RaycastMap::new(5, 5);
+        for i in 0..5 {
+            map.set(i, 0, 1);
+            map.set(i, 4, 1);
+            map.set(0, i, 1);
+            map.set(4, i, 1);
+        }
+        map
+    }
+
+    // an atlas with a 4x4 wall texture whose columns are colored 1 to 4 from left to right, and a
+    // 4x4 sprite colored 5 with a transparent left-most column
+    fn test_atlas() -> BitmapAtlas {
+        let mut bitmap = Bitmap::new(8, 4).unwrap();
+        for x in 0..4 {
+            bitmap.vert_line(x, 0, 3, x as u8 + 1);
+        }
+        bitmap.filled_rect(5, 0, 7, 3, 5);
+        let mut atlas = BitmapAtlas::new(bitmap);
+        atlas.add_grid(4, 4).unwrap();
+        atlas
+    }
+
+    fn test_raycaster() -> Raycaster {
+        let mut raycaster = Raycaster::new();
+        raycaster.ceiling_color = CEILING;
+        raycaster.floor_color = FLOOR;
+        raycaster
+    }
+
+    #[test]
+    pub fn camera() {
+        let camera = RaycastCamera {
+            position: Vector2::new(1.0, 1.0),
+            angle: RADIANS_90,
+            fov: RADIANS_90,
+        };
+        assert!(camera
+            .direction()
+            .nearly_equal(Vector2::new(0.0, 1.0), 0.0001));
+        // facing down the screen, the right edge of the view is towards negative x
+        assert!(camera.plane().nearly_equal(Vector2::new(-1.0, 0.0), 0.0001));
+    }
+
+    #[test]
+    pub fn rendering_walls() {
+        let map = test_map();
+        let atlas = test_atlas();
+        let mut raycaster = test_raycaster();
+        let camera = RaycastCamera {
+            position: Vector2::new(2.5, 2.5),
+            angle: 0.0,
+            fov: RADIANS_90,
+        };
+        let mut dest = Bitmap::new(32, 20).unwrap();
+        raycaster.render(&mut dest, &map, &atlas, &camera);
+
+        // facing straight at a flat wall, which is the same distance away in every column
+        assert_eq!(32, raycaster.depth_buffer().len());
+        for &depth in raycaster.depth_buffer()[1..31].iter() {
+            assert!(nearly_equal(1.5, depth, 0.0001));
+        }
+
+        // the wall is 16 / 1.5 pixels tall, centered vertically
+        assert_eq!(Some(CEILING), dest.get_pixel(16, 0));
+        assert_eq!(Some(CEILING), dest.get_pixel(16, 4));
+        assert_eq!(Some(3), dest.get_pixel(16, 5));
+        assert_eq!(Some(3), dest.get_pixel(16, 14));
+        assert_eq!(Some(FLOOR), dest.get_pixel(16, 15));
+        assert_eq!(Some(FLOOR), dest.get_pixel(16, 19));
+
+        // texture columns across the wall
+        assert_eq!(Some(2), dest.get_pixel(4, 10));
+        assert_eq!(Some(4), dest.get_pixel(9, 10));
+
+        // only draws within the clip region
+        let mut dest = Bitmap::new(32, 20).unwrap();
+        dest.set_clip_region(&Rect::new(8, 0, 16, 20));
+        raycaster.render(&mut dest, &map, &atlas, &camera);
+        assert_eq!(16, raycaster.depth_buffer().len());
+        assert_eq!(Some(0), dest.get_pixel(7, 10));
+        assert_eq!(Some(0), dest.get_pixel(24, 10));
+        assert_ne!(Some(0), dest.get_pixel(8, 10));
+    }
+
+    #[test]
+    pub fn rendering_sprites() {
+        let map = test_map();
+        let atlas = test_atlas();
+        let mut raycaster = test_raycaster();
+        let camera = RaycastCamera {
+            position: Vector2::new(2.5, 2.5),
+            angle: 0.0,
+            fov: RADIANS_90,
+        };
+        let mut dest = Bitmap::new(32, 20).unwrap();
+
+        // a sprite 1.0 units in front of the camera is 16 pixels tall and wide, and stands on the
+        // floor
+        raycaster.render(&mut dest, &map, &atlas, &camera);
+        let sprites = [
+            RaycastSprite::new(Vector2::new(3.5, 2.5), 1),
+            // behind the camera
+            RaycastSprite::new(Vector2::new(1.5, 2.5), 0),
+        ];
+        raycaster.render_sprites(&mut dest, &sprites, &atlas, &camera);
+        assert_eq!(Some(5), dest.get_pixel(16, 10));
+        assert_eq!(Some(5), dest.get_pixel(12, 2));
+        assert_eq!(Some(5), dest.get_pixel(23, 17));
+        assert_eq!(Some(CEILING), dest.get_pixel(12, 1));
+        assert_eq!(Some(FLOOR), dest.get_pixel(23, 18));
+        assert_eq!(Some(2), dest.get_pixel(24, 10));
+        // the transparent column shows the wall behind it
+        assert_eq!(Some(4), dest.get_pixel(9, 10));
+
+        // sprites behind walls are not drawn
+        raycaster.render(&mut dest, &map, &atlas, &camera);
+        let sprites = [RaycastSprite::new(Vector2::new(4.5, 2.5), 1)];
+        raycaster.render_sprites(&mut dest, &sprites, &atlas, &camera);
+        assert_eq!(Some(3), dest.get_pixel(16, 10));
+    }
+
+    #[test]
+    pub fn shading_by_distance() {
+        let map = test_map();
+        let atlas = test_atlas();
+        let mut raycaster = test_raycaster();
+        let palette = Palette::new_vga_palette().unwrap();
+        let color_map = Rc::new(ColorMap::new(&palette, 4));
+        raycaster.color_map = Some(color_map.clone());
+        raycaster.shade_distance = 3.0;
+        let camera = RaycastCamera {
+            position: Vector2::new(2.5, 2.5),
+            angle: 0.0,
+            fov: RADIANS_90,
+        };
+        let mut dest = Bitmap::new(32, 20).unwrap();
+        raycaster.render(&mut dest, &map, &atlas, &camera);
+
+        // the wall is halfway to the shade distance
+        assert_eq!(Some(color_map.shade(3, 2)), dest.get_pixel(16, 10));
+        assert_eq!(Some(CEILING), dest.get_pixel(16, 0));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::math::*;
+
+/// A single triangle of a [Mesh].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MeshFace {
+    /// The indices of the face's three vertices in the mesh's vertex list. The front of the face
+    /// is the side from which these vertices appear in clockwise order. Only the front of a face
+    /// is drawn when back-face culling is enabled, and its lighting is based on the direction the
+    /// front is facing.
+    pub indices: [usize; 3],
+    /// The palette color of the face, used when it is not textured.
+    pub color: u8,
+    /// The index of the region in the renderer's texture atlas to draw the face with, or `None`
+    /// to fill it with `color` instead.
+    pub texture: Option<usize>,
+    /// The texture coordinates of each vertex, where (0.0, 0.0) is the top-left corner of the
+    /// texture region and (1.0, 1.0) is the bottom-right. Coordinates outside of this range wrap
+    /// around, repeating the texture.
+    pub uvs: [Vector2; 3],
+}
+
+/// A 3D model made up of triangles, which can be drawn with a [super::Renderer3d]. Vertex
+/// coordinates use a left-handed system: positive x is to the right, positive y is up and
+/// positive z is forward (away from the viewer).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Vector3>,
+    pub faces: Vec<MeshFace>,
+}
+
+impl Mesh {
+    /// Creates a new, empty mesh.
+    pub fn new() -> Mesh {
+        Mesh::default()
+    }
+
+    /// Creates a new cube mesh centered on the origin, with every face filled with the same
+    /// color. Each side of the cube has its own set of vertices, so that smooth (gouraud) shading
+    /// still leaves the edges of the cube sharp.
+    ///
+    /// # Arguments
+    ///
+    /// * `size`: how long each edge of the cube is
+    /// * `color`: the palette color of the cube's faces
+    pub fn new_cube(size: f32, color: u8) -> Mesh {
+        let mut mesh = Mesh::new();
+        for corners in cube_sides(size) {
+            mesh.add_quad(corners, color);
+        }
+        mesh
+    }
+
+    /// Creates a new cube mesh centered on the origin, with every side drawn using the whole of
+    /// the same texture, upright when seen from any of the four sides around the y axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `size`: how long each edge of the cube is
+    /// * `texture`: the index of the texture region to draw the cube with
+    pub fn new_textured_cube(size: f32, texture: usize) -> Mesh {
+        let mut mesh = Mesh::new();
+        for corners in cube_sides(size) {
+            mesh.add_textured_quad(corners, texture);
+        }
+        mesh
+    }
+
+    /// Adds a vertex to the mesh, returning its index.
+    pub fn add_vertex(&mut self, vertex: Vector3) -> usize {
+        self.vertices.push(vertex);
+        self.vertices.len() - 1
+    }
+
+    /// Adds a triangle, filled with a single color, using vertices which have already been added.
+    /// The vertices should be in clockwise order as seen from the front of the face.
+    pub fn add_face(&mut self, a: usize, b: usize, c: usize, color: u8) {
+        self.faces.push(MeshFace {
+            indices: [a, b, c],
+            color,
+            texture: None,
+            uvs: [Vector2::ZERO; 3],
+        });
+    }
+
+    /// Adds a textured triangle using vertices which have already been added. The vertices should
+    /// be in clockwise order as seen from the front of the face.
+    pub fn add_textured_face(
+        &mut self,
+        a: usize,
+        b: usize,
+        c: usize,
+        texture: usize,
+        uvs: [Vector2; 3],
+    ) {
+        self.faces.push(MeshFace {
+            indices: [a, b, c],
+            color: 0,
+            texture: Some(texture),
+            uvs,
+        });
+    }
+
+    /// Adds four new vertices and two triangles covering the quad between them, filled with a
+    /// single color. The corners should be in clockwise order as seen from the front of the quad,
+    /// and all lie on the same plane.
+    pub fn add_quad(&mut self, corners: [Vector3; 4], color: u8) {
+        let [a, b, c, d] = corners.map(|corner| self.add_vertex(corner));
+        self.add_face(a, b, c, color);
+        self.add_face(a, c, d, color);
+    }
+
+    /// Adds four new vertices and two triangles covering the quad between them, drawn with the
+    /// whole of the given texture region. The corners should be in clockwise order as seen from
+    /// the front of the quad, starting with the one where the top-left of the texture should go.
+    pub fn add_textured_quad(&mut self, corners: [Vector3; 4], texture: usize) {
+        let [a, b, c, d] = corners.map(|corner| self.add_vertex(corner));
+        let top_left = Vector2::new(0.0, 0.0);
+        let top_right = Vector2::new(1.0, 0.0);
+        let bottom_right = Vector2::new(1.0, 1.0);
+        let bottom_left = Vector2::new(0.0, 1.0);
+        self.add_textured_face(a, b, c, texture, [top_left, top_right, bottom_right]);
+        self.add_textured_face(a, c, d, texture, [top_left, bottom_right, bottom_left]);
+    }
+
+    /// Calculates the normal of each face of the mesh: the unit vector pointing out of the front
+    /// of the face.
+    pub fn face_normals(&self) -> Vec<Vector3> {
+        self.faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.indices.map(|index| self.vertices[index]);
+                face_normal(a, b, c)
+            })
+            .collect()
+    }
+
+    /// Calculates a normal for each vertex of the mesh by averaging the normals of all of the
+    /// faces which use it, as needed for smooth (gouraud) shading. Vertices which are not used by
+    /// any faces get a zero vector.
+    pub fn vertex_normals(&self) -> Vec<Vector3> {
+        let mut normals = vec![Vector3::ZERO; self.vertices.len()];
+        for (face, normal) in self.faces.iter().zip(self.face_normals()) {
+            for &index in face.indices.iter() {
+                normals[index] += normal;
+            }
+        }
+        for normal in normals.iter_mut() {
+            if !normal.almost_zero(0.000001) {
+                *normal = normal.normalize();
+            }
+        }
+        normals
+    }
+}
+
+/// Returns the unit vector pointing out of the front of the triangle with the given corners,
+/// which are in clockwise order as seen from the front. Degenerate triangles get a zero vector.
+pub(crate) fn face_normal(a: Vector3, b: Vector3, c: Vector3) -> Vector3 {
+    let normal = (b - a).cross(&(c - a));
+    if normal.almost_zero(0.000001) {
+        Vector3::ZERO
+    } else {
+        normal.normalize()
+    }
+}
+
+// the corners of each side of a cube, in clockwise order as seen from outside of the cube,
+// starting from the top-left corner
+#[rustfmt::skip]
+fn cube_sides(size: f32) -> [[Vector3; 4]; 6] {
+    let h = size * 0.5;
+    [
+        // front (-z)
+        [Vector3::new(-h, h, -h), Vector3::new(h, h, -h), Vector3::new(h, -h, -h), Vector3::new(-h, -h, -h)],
+        // right (+x)
+        [Vector3::new(h, h, -h), Vector3::new(h, h, h), Vector3::new(h, -h, h), Vector3::new(h, -h, -h)],
+        // back (+z)
+        [Vector3::new(h, h, h), Vector3::new(-h, h, h), Vector3::new(-h, -h, h), Vector3::new(h, -h, h)],
+        // left (-x)
+        [Vector3::new(-h, h, h), Vector3::new(-h, h, -h), Vector3::new(-h, -h, -h), Vector3::new(-h, -h, h)],
+        // top (+y)
+        [Vector3::new(-h, h, h), Vector3::new(h, h, h), Vector3::new(h, h, -h), Vector3::new(-h, h, -h)],
+        // bottom (-y)
+        [Vector3::new(-h, -h, -h), Vector3::new(h, -h, -h), Vector3::new(h, -h, h), Vector3::new(-h, -h, h)],
+    ]
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn building_meshes() {
+        let mut mesh = Mesh::new();
+        let a = mesh.add_vertex(Vector3::new(0.0, 1.0, 0.0));
+        let b = mesh.add_vertex(Vector3::new(1.0, -1.0, 0.0));
+        let c = mesh.add_vertex(Vector3::new(-1.0, -1.0, 0.0));
+        mesh.add_face(a, b, c, 5);
+        assert_eq!([0, 1, 2], mesh.faces[0].indices);
+        assert_eq!(5, mesh.faces[0].color);
+        assert_eq!(None, mesh.faces[0].texture);
+
+        // clockwise as seen from negative z, so the face points towards negative z
+        let normals = mesh.face_normals();
+        assert!(normals[0].nearly_equal(Vector3::new(0.0, 0.0, -1.0), 0.0001));
+
+        mesh.add_textured_quad(
+            [
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, -1.0),
+                Vector3::new(0.0, 0.0, -1.0),
+            ],
+            2,
+        );
+        assert_eq!(7, mesh.vertices.len());
+        assert_eq!(3, mesh.faces.len());
+        assert_eq!(Some(2), mesh.faces[2].texture);
+        assert_eq!(Vector2::new(1.0, 1.0), mesh.faces[2].uvs[1]);
+        assert_eq!(Vector2::new(0.0, 1.0), mesh.faces[2].uvs[2]);
+    }
+
+    #[test]
+    pub fn cube_normals() {
+        let cube = Mesh::new_cube(2.0, 1);
+        assert_eq!(24, cube.vertices.len());
+        assert_eq!(12, cube.faces.len());
+
+        // every face should point away from the center of the cube
+        for (face, normal) in cube.faces.iter().zip(cube.face_normals()) {
+            let center = face
+                .indices
+                .iter()
+                .fold(Vector3::ZERO, |sum, &index| sum + cube.vertices[index])
+                / 3.0;
+            assert!(nearly_equal(normal.length(), 1.0, 0.0001));
+            assert!(normal.dot(&center) > 0.0);
+        }
+
+        // each side has its own vertices, so vertex normals match the side they belong to
+        let normals = cube.vertex_normals();
+        assert!(normals[0].nearly_equal(Vector3::new(0.0, 0.0, -1.0), 0.0001));
+        assert!(normals[4].nearly_equal(Vector3::new(1.0, 0.0, 0.0), 0.0001));
+    }
+}
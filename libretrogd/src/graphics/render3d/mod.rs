@@ -0,0 +1,7 @@
+pub use self::mesh::*;
+pub use self::rasterize::*;
+pub use self::renderer::*;
+
+pub mod mesh;
+pub mod rasterize;
+pub mod renderer;
@@ -0,0 +1,298 @@
+use crate::graphics::*;
+
+/// A triangle vertex which has been projected onto the screen, ready to be drawn by
+/// [fill_triangle].
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct RasterVertex {
+    /// The x screen coordinate, in pixels.
+    pub x: f32,
+    /// The y screen coordinate, in pixels.
+    pub y: f32,
+    /// The reciprocal of the vertex's distance in front of the camera. Interpolating this
+    /// (rather than the distance itself) across the triangle is what keeps depth testing and
+    /// texture mapping correct under perspective projection.
+    pub inv_z: f32,
+    /// The brightness of the vertex, from 0.0 to 1.0.
+    pub light: f32,
+    /// The horizontal texture coordinate.
+    pub u: f32,
+    /// The vertical texture coordinate.
+    pub v: f32,
+}
+
+/// A per-pixel depth buffer (a.k.a. z-buffer), used to draw only the nearest of any overlapping
+/// triangles regardless of the order they are drawn in. Stores the reciprocal of each pixel's
+/// depth, so larger values are closer to the camera, and 0.0 is infinitely far away.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthBuffer {
+    width: u32,
+    height: u32,
+    values: Box<[f32]>,
+}
+
+impl DepthBuffer {
+    /// Creates a new depth buffer of the given size, with every pixel infinitely far away.
+    pub fn new(width: u32, height: u32) -> DepthBuffer {
+        DepthBuffer {
+            width,
+            height,
+            values: vec![0.0; (width * height) as usize].into_boxed_slice(),
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Resets every pixel back to being infinitely far away.
+    pub fn clear(&mut self) {
+        self.values.fill(0.0);
+    }
+
+    /// Returns the stored reciprocal depth of the given pixel, or `None` if it is outside of the
+    /// buffer.
+    pub fn get(&self, x: i32, y: i32) -> Option<f32> {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            Some(self.values[(y as u32 * self.width + x as u32) as usize])
+        } else {
+            None
+        }
+    }
+}
+
+// the edge function: twice the signed area of the triangle a, b, p. positive when p is to the
+// right of the edge going from a to b (in screen coordinates, where y increases downwards).
+#[inline]
+fn edge(a: &RasterVertex, b: &RasterVertex, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+// whether an edge of a clockwise triangle is a top or left edge. pixel centers lying exactly on
+// an edge are only drawn for top and left edges, so that triangles sharing an edge neither leave
+// gaps between them nor draw those pixels twice.
+#[inline]
+fn is_top_left(a: &RasterVertex, b: &RasterVertex) -> bool {
+    (a.y == b.y && b.x > a.x) || b.y < a.y
+}
+
+#[inline]
+fn is_inside(w: f32, top_left: bool) -> bool {
+    w > 0.0 || (w == 0.0 && top_left)
+}
+
+/// Fills a triangle on the destination bitmap, clipped to its clip region, interpolating the
+/// light level and texture coordinates of the vertices across it with perspective correction.
+/// The color of each pixel is chosen by the given shader function. Triangles can be in either
+/// clockwise or counter-clockwise order.
+///
+/// # Arguments
+///
+/// * `dest`: the bitmap to draw the triangle on
+/// * `vertices`: the triangle's vertices, in screen coordinates
+/// * `depth_buffer`: if provided, pixels are only drawn if they are closer to the camera than
+///   whatever has already been drawn there, and the depth buffer is updated with any pixels that
+///   were drawn. This must be the same size as `dest`.
+/// * `shader`: called with the interpolated light level and texture coordinates for each pixel,
+///   returning the color to draw the pixel with, or `None` to leave the pixel as it is
+pub fn fill_triangle<F>(
+    dest: &mut Bitmap,
+    vertices: &[RasterVertex; 3],
+    mut depth_buffer: Option<&mut DepthBuffer>,
+    mut shader: F,
+) where
+    F: FnMut(f32, f32, f32) -> Option<u8>,
+{
+    let [a, mut b, mut c] = *vertices;
+    let mut area = edge(&a, &b, c.x, c.y);
+    if area == 0.0 || !area.is_finite() {
+        return;
+    }
+    if area < 0.0 {
+        std::mem::swap(&mut b, &mut c);
+        area = -area;
+    }
+
+    // only pixels whose centers are inside the triangle are drawn
+    let clip_region = *dest.clip_region();
+    let min_x = ((a.x.min(b.x).min(c.x) - 0.5).ceil() as i32).max(clip_region.x);
+    let max_x = ((a.x.max(b.x).max(c.x) - 0.5).ceil() as i32).min(clip_region.right() + 1);
+    let min_y = ((a.y.min(b.y).min(c.y) - 0.5).ceil() as i32).max(clip_region.y);
+    let max_y = ((a.y.max(b.y).max(c.y) - 0.5).ceil() as i32).min(clip_region.bottom() + 1);
+    if min_x >= max_x || min_y >= max_y {
+        return;
+    }
+
+    let top_left_a = is_top_left(&b, &c);
+    let top_left_b = is_top_left(&c, &a);
+    let top_left_c = is_top_left(&a, &b);
+    let inverse_area = 1.0 / area;
+
+    // the attributes divided by depth can be interpolated linearly in screen space
+    let light = [a.light * a.inv_z, b.light * b.inv_z, c.light * c.inv_z];
+    let u = [a.u * a.inv_z, b.u * b.inv_z, c.u * c.inv_z];
+    let v = [a.v * a.inv_z, b.v * b.inv_z, c.v * c.inv_z];
+
+    let pitch = dest.pitch() as usize;
+    let depth_width = dest.width() as usize;
+    let pixels = dest.pixels_mut();
+    for y in min_y..max_y {
+        let py = y as f32 + 0.5;
+        for x in min_x..max_x {
+            let px = x as f32 + 0.5;
+            let wa = edge(&b, &c, px, py);
+            let wb = edge(&c, &a, px, py);
+            let wc = edge(&a, &b, px, py);
+            if !(is_inside(wa, top_left_a)
+                && is_inside(wb, top_left_b)
+                && is_inside(wc, top_left_c))
+            {
+                continue;
+            }
+
+            let (wa, wb, wc) = (wa * inverse_area, wb * inverse_area, wc * inverse_area);
+            let inv_z = wa * a.inv_z + wb * b.inv_z + wc * c.inv_z;
+            let depth_offset = y as usize * depth_width + x as usize;
+            if let Some(depth_buffer) = depth_buffer.as_deref() {
+                if inv_z <= depth_buffer.values[depth_offset] {
+                    continue;
+                }
+            }
+
+            let z = 1.0 / inv_z;
+            let pixel_light = (wa * light[0] + wb * light[1] + wc * light[2]) * z;
+            let pixel_u = (wa * u[0] + wb * u[1] + wc * u[2]) * z;
+            let pixel_v = (wa * v[0] + wb * v[1] + wc * v[2]) * z;
+            if let Some(color) = shader(pixel_light, pixel_u, pixel_v) {
+                pixels[y as usize * pitch + x as usize] = color;
+                if let Some(depth_buffer) = depth_buffer.as_deref_mut() {
+                    depth_buffer.values[depth_offset] = inv_z;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::math::*;
+
+    use super::*;
+
+    fn vertex(x: f32, y: f32) -> RasterVertex {
+        RasterVertex {
+            x,
+            y,
+            inv_z: 1.0,
+            light: 1.0,
+            u: 0.0,
+            v: 0.0,
+        }
+    }
+
+    fn count_color(bitmap: &Bitmap, color: u8) -> usize {
+        bitmap
+            .pixels()
+            .iter()
+            .filter(|&&pixel| pixel == color)
+            .count()
+    }
+
+    #[test]
+    pub fn filling_triangles() {
+        let mut bmp = Bitmap::new(16, 16).unwrap();
+
+        // a right-angled triangle covering half of an 8x8 square
+        let triangle = [vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(0.0, 8.0)];
+        fill_triangle(&mut bmp, &triangle, None, |_, _, _| Some(1));
+        assert_eq!(Some(1), bmp.get_pixel(0, 0));
+        assert_eq!(Some(1), bmp.get_pixel(6, 0));
+        assert_eq!(Some(1), bmp.get_pixel(0, 6));
+        // pixel centers exactly on the diagonal edge are left for the other half of the square
+        assert_eq!(Some(0), bmp.get_pixel(7, 0));
+        assert_eq!(Some(0), bmp.get_pixel(8, 0));
+        assert_eq!(28, count_color(&bmp, 1));
+
+        // the other half of the square, drawn counter-clockwise, must fill exactly the remaining
+        // pixels without overlapping the first half
+        let triangle = [vertex(8.0, 0.0), vertex(0.0, 8.0), vertex(8.0, 8.0)];
+        fill_triangle(&mut bmp, &triangle, None, |_, _, _| Some(2));
+        assert_eq!(64, count_color(&bmp, 1) + count_color(&bmp, 2));
+        assert_eq!(28, count_color(&bmp, 1));
+        assert_eq!(Some(2), bmp.get_pixel(7, 0));
+        assert_eq!(Some(2), bmp.get_pixel(7, 7));
+        assert_eq!(Some(0), bmp.get_pixel(8, 8));
+
+        // degenerate triangles draw nothing
+        let mut bmp = Bitmap::new(16, 16).unwrap();
+        let triangle = [vertex(0.0, 0.0), vertex(4.0, 4.0), vertex(8.0, 8.0)];
+        fill_triangle(&mut bmp, &triangle, None, |_, _, _| Some(1));
+        assert_eq!(0, count_color(&bmp, 1));
+    }
+
+    #[test]
+    pub fn clipping_triangles() {
+        let mut bmp = Bitmap::new(16, 16).unwrap();
+        bmp.set_clip_region(&Rect::new(4, 4, 8, 8));
+
+        let triangle = [
+            vertex(-100.0, -100.0),
+            vertex(100.0, -100.0),
+            vertex(0.0, 100.0),
+        ];
+        fill_triangle(&mut bmp, &triangle, None, |_, _, _| Some(1));
+        assert_eq!(64, count_color(&bmp, 1));
+        assert_eq!(Some(0), bmp.get_pixel(3, 4));
+        assert_eq!(Some(1), bmp.get_pixel(4, 4));
+        assert_eq!(Some(1), bmp.get_pixel(11, 11));
+        assert_eq!(Some(0), bmp.get_pixel(12, 11));
+    }
+
+    #[test]
+    pub fn interpolating_and_depth_testing() {
+        let mut bmp = Bitmap::new(8, 8).unwrap();
+        let mut depth_buffer = DepthBuffer::new(8, 8);
+
+        // covering the whole bitmap, with u going from 0.0 on the left to 8.0 on the right
+        let mut near = [vertex(0.0, 0.0), vertex(16.0, 0.0), vertex(0.0, 16.0)];
+        near[1].u = 16.0;
+        fill_triangle(&mut bmp, &near, Some(&mut depth_buffer), |_, u, _| {
+            Some(u as u8)
+        });
+        assert_eq!(Some(0), bmp.get_pixel(0, 3));
+        assert_eq!(Some(3), bmp.get_pixel(3, 3));
+        assert_eq!(Some(7), bmp.get_pixel(7, 0));
+        assert_eq!(Some(1.0), depth_buffer.get(3, 3));
+
+        // further away, so hidden behind what has already been drawn
+        let far = [
+            RasterVertex {
+                inv_z: 0.5,
+                ..vertex(0.0, 0.0)
+            },
+            RasterVertex {
+                inv_z: 0.5,
+                ..vertex(16.0, 0.0)
+            },
+            RasterVertex {
+                inv_z: 0.5,
+                ..vertex(0.0, 16.0)
+            },
+        ];
+        fill_triangle(&mut bmp, &far, Some(&mut depth_buffer), |_, _, _| Some(10));
+        assert_eq!(0, count_color(&bmp, 10));
+
+        // pixels the shader skips are not drawn and do not update the depth buffer
+        depth_buffer.clear();
+        fill_triangle(&mut bmp, &far, Some(&mut depth_buffer), |_, _, _| None);
+        assert_eq!(Some(0.0), depth_buffer.get(3, 3));
+        fill_triangle(&mut bmp, &far, Some(&mut depth_buffer), |_, _, _| Some(10));
+        assert_eq!(Some(10), bmp.get_pixel(3, 3));
+        assert_eq!(Some(0.5), depth_buffer.get(3, 3));
+    }
+}
@@ -0,0 +1,713 @@
+use std::rc::Rc;
+
+use crate::graphics::*;
+use crate::math::*;
+
+use super::mesh::face_normal;
+
+/// How the faces of a mesh are lit by a [Renderer3d].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ShadingMode {
+    /// Faces are drawn at full brightness, ignoring the light.
+    Unlit,
+    /// Each face is lit evenly, based on the direction it is facing.
+    Flat,
+    /// Lighting is calculated at each vertex, using the average direction of the faces around
+    /// it, and blended smoothly across each face.
+    Gouraud,
+}
+
+/// How a [Renderer3d] keeps nearer faces from being drawn over by faces further away.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DepthSorting {
+    /// Faces are sorted by their average distance and drawn from furthest to nearest. This is
+    /// cheap, but faces which intersect or overlap in complicated ways can be drawn in the wrong
+    /// order.
+    Painter,
+    /// A per-pixel depth buffer is used, so faces are always correctly hidden behind whatever is
+    /// in front of them.
+    DepthBuffer,
+}
+
+// a vertex in view space, along with the attributes which are interpolated across faces
+#[derive(Debug, Copy, Clone, Default)]
+struct ClipVertex {
+    position: Vector3,
+    light: f32,
+    u: f32,
+    v: f32,
+}
+
+impl ClipVertex {
+    fn lerp(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            position: self.position.lerp(&other.position, t),
+            light: self.light + (other.light - self.light) * t,
+            u: self.u + (other.u - self.u) * t,
+            v: self.v + (other.v - self.v) * t,
+        }
+    }
+}
+
+// clips a triangle against the near plane, keeping the parts with z >= near. returns the
+// vertices of the remaining polygon (in the same winding order) and how many there are. clipping
+// a triangle against a single plane leaves either nothing, a triangle or a quad.
+fn clip_to_near_plane(triangle: &[ClipVertex; 3], near: f32) -> ([ClipVertex; 4], usize) {
+    let mut output = [ClipVertex::default(); 4];
+    let mut count = 0;
+    for (i, current) in triangle.iter().enumerate() {
+        let next = &triangle[(i + 1) % 3];
+        let current_inside = current.position.z >= near;
+        let next_inside = next.position.z >= near;
+        if current_inside {
+            output[count] = *current;
+            count += 1;
+        }
+        if current_inside != next_inside {
+            let t = (near - current.position.z) / (next.position.z - current.position.z);
+            output[count] = current.lerp(next, t);
+            count += 1;
+        }
+    }
+    (output, count)
+}
+
+#[derive(Debug, Copy, Clone)]
+struct QueuedTriangle {
+    vertices: [RasterVertex; 3],
+    color: u8,
+    texture: Option<usize>,
+    depth: f32,
+}
+
+/// Draws 3D [Mesh]es onto bitmaps using software rendering, in the style of early 3D games: solid
+/// colored or textured triangles, lit with flat or gouraud shading using a [ColorMap].
+///
+/// The camera sits at the origin of view space, looking along positive z. Meshes are placed in
+/// the world using a transform stack (in the style of old OpenGL), which is combined with the
+/// view matrix to move their vertices into view space. Triangles are clipped against the near
+/// plane and then projected onto the destination bitmap's clip region.
+///
+/// Drawing is done in three steps: [Renderer3d::begin] starts a new frame, [Renderer3d::draw_mesh]
+/// queues up the triangles of each mesh, and [Renderer3d::finish] draws them all.
+///
+/// ```ignore
+/// renderer.look_at(eye, target, Vector3::Y_AXIS);
+/// renderer.begin(&system.video);
+/// renderer.load_identity();
+/// renderer.translate(0.0, 0.0, 10.0);
+/// renderer.rotate_y(angle);
+/// renderer.draw_mesh(&ship, ShadingMode::Flat);
+/// renderer.finish(&mut system.video, Some(&textures));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Renderer3d {
+    /// The horizontal field of view, in radians.
+    pub fov: f32,
+    /// How far in front of the camera the near plane is. Anything closer than this is clipped.
+    pub near: f32,
+    pub depth_sorting: DepthSorting,
+    /// Whether faces seen from behind are skipped.
+    pub backface_culling: bool,
+    /// The direction the light is shining in, in world space.
+    pub light_direction: Vector3,
+    /// How bright faces facing away from the light are, from 0.0 to 1.0.
+    pub ambient_light: f32,
+    /// The color map providing the light levels used for shading. Without one, everything is
+    /// drawn at full brightness.
+    pub color_map: Option<Rc<ColorMap>>,
+    /// The color in textures which is not drawn.
+    pub transparent_color: Option<u8>,
+    view: Matrix4x4,
+    transform: Matrix4x4,
+    transform_stack: Vec<Matrix4x4>,
+    viewport: Rect,
+    triangles: Vec<QueuedTriangle>,
+    depth_buffer: DepthBuffer,
+}
+
+impl Default for Renderer3d {
+    fn default() -> Self {
+        Renderer3d::new()
+    }
+}
+
+impl Renderer3d {
+    pub fn new() -> Renderer3d {
+        Renderer3d {
+            fov: 60.0 * PI_OVER_180,
+            near: 0.1,
+            depth_sorting: DepthSorting::DepthBuffer,
+            backface_culling: true,
+            light_direction: Vector3::new(0.0, -1.0, 1.0).normalize(),
+            ambient_light: 0.25,
+            color_map: None,
+            transparent_color: None,
+            view: Matrix4x4::IDENTITY,
+            transform: Matrix4x4::IDENTITY,
+            transform_stack: Vec::new(),
+            viewport: Rect::new(0, 0, 0, 0),
+            triangles: Vec::new(),
+            depth_buffer: DepthBuffer::new(0, 0),
+        }
+    }
+
+    /// Returns the view matrix, which moves points from world space into view space.
+    #[inline]
+    pub fn view(&self) -> &Matrix4x4 {
+        &self.view
+    }
+
+    /// Sets the view matrix, which moves points from world space into view space.
+    #[inline]
+    pub fn set_view(&mut self, view: Matrix4x4) {
+        self.view = view;
+    }
+
+    /// Sets the view matrix so that the camera is at `eye`, looking towards `target`.
+    pub fn look_at(&mut self, eye: Vector3, target: Vector3, up: Vector3) {
+        self.view = Matrix4x4::new_look_at(eye, target, up);
+    }
+
+    /// Returns the current transform, which moves the vertices of meshes being drawn into world
+    /// space.
+    #[inline]
+    pub fn transform(&self) -> &Matrix4x4 {
+        &self.transform
+    }
+
+    /// Resets the current transform to the identity matrix.
+    #[inline]
+    pub fn load_identity(&mut self) {
+        self.transform = Matrix4x4::IDENTITY;
+    }
+
+    /// Saves a copy of the current transform on the transform stack.
+    #[inline]
+    pub fn push_transform(&mut self) {
+        self.transform_stack.push(self.transform);
+    }
+
+    /// Restores the current transform to the one most recently saved with
+    /// [Renderer3d::push_transform]. Returns false (leaving the current transform unchanged) if
+    /// the transform stack is empty.
+    pub fn pop_transform(&mut self) -> bool {
+        match self.transform_stack.pop() {
+            Some(transform) => {
+                self.transform = transform;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Combines the given matrix with the current transform. As with the other transform methods,
+    /// the new transform is applied to vertices before any of the existing ones, so transforms
+    /// should be given going from the world down to the mesh (e.g. translate, then rotate).
+    #[inline]
+    pub fn multiply_transform(&mut self, matrix: &Matrix4x4) {
+        self.transform *= *matrix;
+    }
+
+    #[inline]
+    pub fn translate(&mut self, x: f32, y: f32, z: f32) {
+        self.multiply_transform(&Matrix4x4::new_translation(x, y, z));
+    }
+
+    #[inline]
+    pub fn rotate_x(&mut self, radians: f32) {
+        self.multiply_transform(&Matrix4x4::new_rotation_x(radians));
+    }
+
+    #[inline]
+    pub fn rotate_y(&mut self, radians: f32) {
+        self.multiply_transform(&Matrix4x4::new_rotation_y(radians));
+    }
+
+    #[inline]
+    pub fn rotate_z(&mut self, radians: f32) {
+        self.multiply_transform(&Matrix4x4::new_rotation_z(radians));
+    }
+
+    #[inline]
+    pub fn scale(&mut self, x: f32, y: f32, z: f32) {
+        self.multiply_transform(&Matrix4x4::new_scaling(x, y, z));
+    }
+
+    /// Returns the depth buffer used for the last frame when using
+    /// [DepthSorting::DepthBuffer]. This is the same size as the destination bitmap.
+    #[inline]
+    pub fn depth_buffer(&self) -> &DepthBuffer {
+        &self.depth_buffer
+    }
+
+    /// Returns the number of triangles queued up to be drawn by [Renderer3d::finish].
+    #[inline]
+    pub fn num_queued_triangles(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Starts a new frame which will be drawn on the given bitmap, within its current clip
+    /// region. Any triangles still queued from a previous frame are discarded.
+    pub fn begin(&mut self, dest: &Bitmap) {
+        self.viewport = *dest.clip_region();
+        self.triangles.clear();
+        if self.depth_sorting == DepthSorting::DepthBuffer {
+            if self.depth_buffer.width() != dest.width()
+                || self.depth_buffer.height() != dest.height()
+            {
+                self.depth_buffer = DepthBuffer::new(dest.width(), dest.height());
+            } else {
+                self.depth_buffer.clear();
+            }
+        }
+    }
+
+    fn light_intensity(&self, normal: Vector3, light_direction: Vector3) -> f32 {
+        let diffuse = (-normal.dot(&light_direction)).max(0.0);
+        self.ambient_light + (1.0 - self.ambient_light) * diffuse
+    }
+
+    /// Queues up the triangles of a mesh to be drawn, using the current view and transform.
+    /// Triangles which are behind the camera or (if back-face culling is enabled) facing away
+    /// from it are skipped.
+    pub fn draw_mesh(&mut self, mesh: &Mesh, shading: ShadingMode) {
+        if self.viewport.width == 0 || self.viewport.height == 0 {
+            return;
+        }
+
+        let model_view = self.view * self.transform;
+        let vertices: Vec<Vector3> = mesh.vertices.iter().map(|&v| model_view * v).collect();
+        let face_normals: Vec<Vector3> = mesh
+            .faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.indices.map(|index| vertices[index]);
+                face_normal(a, b, c)
+            })
+            .collect();
+
+        let light_direction = self.view.transform_direction(self.light_direction);
+        let light_direction = if light_direction.almost_zero(0.000001) {
+            light_direction
+        } else {
+            light_direction.normalize()
+        };
+        let vertex_lights: Vec<f32> = if shading == ShadingMode::Gouraud {
+            let mut normals = vec![Vector3::ZERO; vertices.len()];
+            for (face, normal) in mesh.faces.iter().zip(face_normals.iter()) {
+                for &index in face.indices.iter() {
+                    normals[index] += *normal;
+                }
+            }
+            normals
+                .iter()
+                .map(|normal| {
+                    let normal = if normal.almost_zero(0.000001) {
+                        *normal
+                    } else {
+                        normal.normalize()
+                    };
+                    self.light_intensity(normal, light_direction)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let focal_length = (self.viewport.width as f32 * 0.5) / (self.fov * 0.5).tan();
+        let center_x = self.viewport.x as f32 + self.viewport.width as f32 * 0.5;
+        let center_y = self.viewport.y as f32 + self.viewport.height as f32 * 0.5;
+
+        for (face, normal) in mesh.faces.iter().zip(face_normals.iter()) {
+            let positions = face.indices.map(|index| vertices[index]);
+            // the camera is at the origin, so the face is seen from behind if its normal points
+            // away from the camera
+            if self.backface_culling && normal.dot(&positions[0]) >= 0.0 {
+                continue;
+            }
+
+            let lights = match shading {
+                ShadingMode::Unlit => [1.0; 3],
+                ShadingMode::Flat => [self.light_intensity(*normal, light_direction); 3],
+                ShadingMode::Gouraud => face.indices.map(|index| vertex_lights[index]),
+            };
+            let triangle: [ClipVertex; 3] = std::array::from_fn(|i| ClipVertex {
+                position: positions[i],
+                light: lights[i],
+                u: face.uvs[i].x,
+                v: face.uvs[i].y,
+            });
+
+            let (polygon, count) = clip_to_near_plane(&triangle, self.near);
+            if count < 3 {
+                continue;
+            }
+            let projected = polygon.map(|vertex| {
+                let inv_z = 1.0 / vertex.position.z;
+                RasterVertex {
+                    x: center_x + vertex.position.x * focal_length * inv_z,
+                    y: center_y - vertex.position.y * focal_length * inv_z,
+                    inv_z,
+                    light: vertex.light,
+                    u: vertex.u,
+                    v: vertex.v,
+                }
+            });
+
+            let depth = (positions[0].z + positions[1].z + positions[2].z) / 3.0;
+            for edge in projected[1..count].windows(2) {
+                self.triangles.push(QueuedTriangle {
+                    vertices: [projected[0], edge[0], edge[1]],
+                    color: face.color,
+                    texture: face.texture,
+                    depth,
+                });
+            }
+        }
+    }
+
+    /// Draws all of the triangles queued up since [Renderer3d::begin] onto the given bitmap,
+    /// which should be the same one passed to [Renderer3d::begin]. Textured faces are drawn using
+    /// the regions of the given atlas, and are filled with their color instead if there is no
+    /// atlas or their region is not in it.
+    pub fn finish(&mut self, dest: &mut Bitmap, textures: Option<&BitmapAtlas>) {
+        let mut triangles = std::mem::take(&mut self.triangles);
+        let mut depth_buffer = match self.depth_sorting {
+            DepthSorting::Painter => {
+                // stable sort, so faces at the same depth are drawn in the order they were queued
+                triangles.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+                None
+            }
+            DepthSorting::DepthBuffer => {
+                if self.depth_buffer.width() != dest.width()
+                    || self.depth_buffer.height() != dest.height()
+                {
+                    self.depth_buffer = DepthBuffer::new(dest.width(), dest.height());
+                }
+                Some(&mut self.depth_buffer)
+            }
+        };
+
+        let color_map = self.color_map.as_deref();
+        let max_level = color_map.map_or(0, |color_map| color_map.num_levels() - 1);
+        let transparent_color = self.transparent_color;
+
+        for triangle in triangles.iter() {
+            let texture = match (textures, triangle.texture) {
+                (Some(atlas), Some(index)) => match atlas.get(index) {
+                    Some(region) if region.width > 0 && region.height > 0 => {
+                        Some((atlas.bitmap(), *region))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            let color = triangle.color;
+            fill_triangle(
+                dest,
+                &triangle.vertices,
+                depth_buffer.as_deref_mut(),
+                |light, u, v| {
+                    let color = match texture {
+                        Some((bitmap, region)) => {
+                            let x = ((u * region.width as f32).floor() as i32)
+                                .rem_euclid(region.width as i32);
+                            let y = ((v * region.height as f32).floor() as i32)
+                                .rem_euclid(region.height as i32);
+                            let offset = (region.y + y) as usize * bitmap.pitch() as usize
+                                + (region.x + x) as usize;
+                            let texel = bitmap.pixels()[offset];
+                            if transparent_color == Some(texel) {
+                                return None;
+                            }
+                            texel
+                        }
+                        None => color,
+                    };
+                    Some(match color_map {
+                        Some(color_map) => {
+                            let level = (light.clamp(0.0, 1.0) * max_level as f32).round();
+                            color_map.shade(color, level as usize)
+                        }
+                        None => color,
+                    })
+                },
+            );
+        }
+
+        triangles.clear();
+        self.triangles = triangles;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // a square facing towards negative z (and so towards the camera) at the given depth
+    fn square(z: f32, half_size: f32, color: u8) -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.add_quad(
+            [
+                Vector3::new(-half_size, half_size, z),
+                Vector3::new(half_size, half_size, z),
+                Vector3::new(half_size, -half_size, z),
+                Vector3::new(-half_size, -half_size, z),
+            ],
+            color,
+        );
+        mesh
+    }
+
+    // with a 90 degree field of view on a 64x64 bitmap, something 1.0 units wide at a distance of
+    // 1.0 is drawn 32 pixels wide
+    fn test_renderer() -> Renderer3d {
+        let mut renderer = Renderer3d::new();
+        renderer.fov = RADIANS_90;
+        renderer
+    }
+
+    fn draw(renderer: &mut Renderer3d, dest: &mut Bitmap, meshes: &[(&Mesh, ShadingMode)]) {
+        renderer.begin(dest);
+        for (mesh, shading) in meshes.iter() {
+            renderer.draw_mesh(mesh, *shading);
+        }
+        renderer.finish(dest, None);
+    }
+
+    #[test]
+    pub fn transform_stack() {
+        let mut renderer = Renderer3d::new();
+        assert_eq!(Matrix4x4::IDENTITY, *renderer.transform());
+
+        renderer.translate(1.0, 2.0, 3.0);
+        renderer.push_transform();
+        renderer.scale(2.0, 2.0, 2.0);
+        renderer.rotate_y(RADIANS_90);
+        let v = *renderer.transform() * Vector3::new(1.0, 0.0, 0.0);
+        assert!(v.nearly_equal(Vector3::new(1.0, 2.0, 1.0), 0.0001));
+
+        assert!(renderer.pop_transform());
+        let v = *renderer.transform() * Vector3::new(1.0, 0.0, 0.0);
+        assert!(v.nearly_equal(Vector3::new(2.0, 2.0, 3.0), 0.0001));
+        assert!(!renderer.pop_transform());
+
+        renderer.load_identity();
+        assert_eq!(Matrix4x4::IDENTITY, *renderer.transform());
+    }
+
+    #[test]
+    pub fn drawing_meshes() {
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        let mut renderer = test_renderer();
+        let cube = Mesh::new_cube(2.0, 3);
+
+        // the front of the cube is 4.0 units away, so it is drawn 16 pixels wide
+        renderer.begin(&dest);
+        renderer.translate(0.0, 0.0, 5.0);
+        renderer.draw_mesh(&cube, ShadingMode::Unlit);
+        // only the two triangles of the front side are facing the camera
+        assert_eq!(2, renderer.num_queued_triangles());
+        renderer.finish(&mut dest, None);
+        assert_eq!(0, renderer.num_queued_triangles());
+
+        assert_eq!(Some(3), dest.get_pixel(32, 32));
+        assert_eq!(Some(3), dest.get_pixel(24, 24));
+        assert_eq!(Some(3), dest.get_pixel(39, 39));
+        assert_eq!(Some(0), dest.get_pixel(23, 32));
+        assert_eq!(Some(0), dest.get_pixel(40, 32));
+
+        // moving the camera around to look at the cube from the side gives the same view
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        renderer.look_at(
+            Vector3::new(-5.0, 0.0, 5.0),
+            Vector3::new(0.0, 0.0, 5.0),
+            Vector3::Y_AXIS,
+        );
+        renderer.load_identity();
+        draw(&mut renderer, &mut dest, &[(&cube, ShadingMode::Unlit)]);
+        assert_eq!(Some(0), dest.get_pixel(32, 32));
+        renderer.translate(0.0, 0.0, 5.0);
+        draw(&mut renderer, &mut dest, &[(&cube, ShadingMode::Unlit)]);
+        assert_eq!(Some(3), dest.get_pixel(24, 24));
+        assert_eq!(Some(3), dest.get_pixel(39, 39));
+        assert_eq!(Some(0), dest.get_pixel(40, 32));
+    }
+
+    #[test]
+    pub fn backface_culling() {
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        let mut renderer = test_renderer();
+
+        // seen from behind
+        let mut mesh = square(4.0, 1.0, 5);
+        for face in mesh.faces.iter_mut() {
+            face.indices.swap(1, 2);
+        }
+
+        draw(&mut renderer, &mut dest, &[(&mesh, ShadingMode::Unlit)]);
+        assert_eq!(Some(0), dest.get_pixel(32, 32));
+
+        renderer.backface_culling = false;
+        draw(&mut renderer, &mut dest, &[(&mesh, ShadingMode::Unlit)]);
+        assert_eq!(Some(5), dest.get_pixel(32, 32));
+    }
+
+    #[test]
+    pub fn depth_sorting() {
+        let near = square(3.0, 1.0, 1);
+        let far = square(6.0, 4.0, 2);
+
+        for depth_sorting in [DepthSorting::Painter, DepthSorting::DepthBuffer] {
+            let mut dest = Bitmap::new(64, 64).unwrap();
+            let mut renderer = test_renderer();
+            renderer.depth_sorting = depth_sorting;
+
+            // the nearer square is drawn first, but still ends up in front
+            draw(
+                &mut renderer,
+                &mut dest,
+                &[(&near, ShadingMode::Unlit), (&far, ShadingMode::Unlit)],
+            );
+            assert_eq!(Some(1), dest.get_pixel(32, 32), "{:?}", depth_sorting);
+            assert_eq!(Some(2), dest.get_pixel(15, 32), "{:?}", depth_sorting);
+            assert_eq!(Some(0), dest.get_pixel(2, 2), "{:?}", depth_sorting);
+        }
+    }
+
+    #[test]
+    pub fn clipping_near_plane() {
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        let mut renderer = test_renderer();
+
+        // a floor below the camera, stretching from behind it off into the distance
+        let mut floor = Mesh::new();
+        floor.add_quad(
+            [
+                Vector3::new(-10.0, -1.0, 20.0),
+                Vector3::new(10.0, -1.0, 20.0),
+                Vector3::new(10.0, -1.0, -10.0),
+                Vector3::new(-10.0, -1.0, -10.0),
+            ],
+            7,
+        );
+        draw(&mut renderer, &mut dest, &[(&floor, ShadingMode::Unlit)]);
+
+        // the far edge is drawn just below the middle of the view
+        assert_eq!(Some(0), dest.get_pixel(32, 30));
+        assert_eq!(Some(7), dest.get_pixel(32, 40));
+        assert_eq!(Some(7), dest.get_pixel(0, 63));
+        assert_eq!(Some(7), dest.get_pixel(63, 63));
+
+        // entirely behind the camera
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        renderer.begin(&dest);
+        renderer.translate(0.0, 0.0, -25.0);
+        renderer.draw_mesh(&floor, ShadingMode::Unlit);
+        assert_eq!(0, renderer.num_queued_triangles());
+        renderer.finish(&mut dest, None);
+        assert!(dest.pixels().iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    pub fn textured_faces() {
+        let mut texture = Bitmap::new(2, 2).unwrap();
+        texture.set_pixel(0, 0, 1);
+        texture.set_pixel(1, 0, 2);
+        texture.set_pixel(0, 1, 3);
+        texture.set_pixel(1, 1, 4);
+        let mut atlas = BitmapAtlas::new(texture);
+        atlas.add(Rect::new(0, 0, 2, 2)).unwrap();
+
+        // exactly fills the view
+        let mut mesh = Mesh::new();
+        mesh.add_textured_quad(
+            [
+                Vector3::new(-2.0, 2.0, 2.0),
+                Vector3::new(2.0, 2.0, 2.0),
+                Vector3::new(2.0, -2.0, 2.0),
+                Vector3::new(-2.0, -2.0, 2.0),
+            ],
+            0,
+        );
+
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        let mut renderer = test_renderer();
+        renderer.transparent_color = Some(4);
+        renderer.begin(&dest);
+        renderer.draw_mesh(&mesh, ShadingMode::Unlit);
+        renderer.finish(&mut dest, Some(&atlas));
+        assert_eq!(Some(1), dest.get_pixel(10, 10));
+        assert_eq!(Some(2), dest.get_pixel(50, 10));
+        assert_eq!(Some(3), dest.get_pixel(10, 50));
+        assert_eq!(Some(0), dest.get_pixel(50, 50));
+
+        // without the atlas, the face color is used instead
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        mesh.faces[0].color = 9;
+        mesh.faces[1].color = 9;
+        draw(&mut renderer, &mut dest, &[(&mesh, ShadingMode::Unlit)]);
+        assert_eq!(Some(9), dest.get_pixel(50, 50));
+    }
+
+    #[test]
+    pub fn shading() {
+        let palette = Palette::new_vga_palette().unwrap();
+        let color_map = Rc::new(ColorMap::new(&palette, 8));
+        let mut renderer = test_renderer();
+        renderer.color_map = Some(color_map.clone());
+        renderer.ambient_light = 0.0;
+        let mesh = square(5.0, 1.0, 15);
+
+        // lit head-on
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        renderer.light_direction = Vector3::new(0.0, 0.0, 1.0);
+        draw(&mut renderer, &mut dest, &[(&mesh, ShadingMode::Flat)]);
+        assert_eq!(Some(color_map.shade(15, 7)), dest.get_pixel(32, 32));
+
+        // at 45 degrees
+        renderer.light_direction = Vector3::new(1.0, 0.0, 1.0);
+        draw(&mut renderer, &mut dest, &[(&mesh, ShadingMode::Flat)]);
+        assert_eq!(Some(color_map.shade(15, 5)), dest.get_pixel(32, 32));
+
+        // from the side, leaving only the ambient light
+        renderer.light_direction = Vector3::new(1.0, 0.0, 0.0);
+        draw(&mut renderer, &mut dest, &[(&mesh, ShadingMode::Flat)]);
+        assert_eq!(Some(color_map.shade(15, 0)), dest.get_pixel(32, 32));
+        draw(&mut renderer, &mut dest, &[(&mesh, ShadingMode::Unlit)]);
+        assert_eq!(Some(15), dest.get_pixel(32, 32));
+
+        // a wedge pointing towards the camera, with the left side facing the light and the right
+        // side facing away from it
+        let mut wedge = Mesh::new();
+        for vertex in [
+            Vector3::new(-2.0, 2.0, 6.0),
+            Vector3::new(0.0, 2.0, 4.0),
+            Vector3::new(0.0, -2.0, 4.0),
+            Vector3::new(-2.0, -2.0, 6.0),
+            Vector3::new(2.0, 2.0, 6.0),
+            Vector3::new(2.0, -2.0, 6.0),
+        ] {
+            wedge.add_vertex(vertex);
+        }
+        wedge.add_face(0, 1, 2, 15);
+        wedge.add_face(0, 2, 3, 15);
+        wedge.add_face(1, 4, 5, 15);
+        wedge.add_face(1, 5, 2, 15);
+        renderer.light_direction = Vector3::new(1.0, 0.0, 1.0);
+
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        draw(&mut renderer, &mut dest, &[(&wedge, ShadingMode::Flat)]);
+        assert_eq!(Some(color_map.shade(15, 7)), dest.get_pixel(22, 32));
+        assert_eq!(Some(color_map.shade(15, 7)), dest.get_pixel(31, 32));
+        assert_eq!(Some(color_map.shade(15, 0)), dest.get_pixel(40, 32));
+
+        // the vertices along the middle edge are shared by both sides, so the light fades
+        // towards the middle
+        let mut dest = Bitmap::new(64, 64).unwrap();
+        draw(&mut renderer, &mut dest, &[(&wedge, ShadingMode::Gouraud)]);
+        assert_eq!(Some(color_map.shade(15, 7)), dest.get_pixel(22, 32));
+        assert_ne!(Some(color_map.shade(15, 7)), dest.get_pixel(31, 32));
+    }
+}
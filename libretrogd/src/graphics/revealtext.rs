@@ -0,0 +1,213 @@
+use crate::graphics::*;
+
+/// Reveals a string of text one character at a time over time, like a typewriter or teletype, as
+/// commonly used for dialogue in RPGs. Inline formatting codes (see [TextCodes]) in the text are
+/// skipped over and never partially revealed.
+#[derive(Debug, Clone)]
+pub struct RevealText {
+    text: String,
+    /// Each revealable character, along with the length (in bytes) of the text up to and
+    /// including that character.
+    reveal_points: Vec<(char, usize)>,
+    revealed: usize,
+    chars_per_second: f32,
+    accumulator: f32,
+}
+
+impl RevealText {
+    /// Creates a new [RevealText] for the given text, with nothing revealed yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: the text to reveal
+    /// * `chars_per_second`: how many characters to reveal each second
+    pub fn new(text: &str, chars_per_second: f32) -> RevealText {
+        let codes = TextCodes::new();
+        let mut reveal_points = Vec::with_capacity(text.len());
+        let mut offset = 0;
+        while let Some(ch) = text[offset..].chars().next() {
+            if ch == '{' {
+                if let Some((_, length)) = codes.parse_code(&text[offset..], FontRenderOpts::None) {
+                    offset += length;
+                    continue;
+                }
+            }
+            offset += ch.len_utf8();
+            reveal_points.push((ch, offset));
+        }
+
+        RevealText {
+            text: text.to_string(),
+            reveal_points,
+            revealed: 0,
+            chars_per_second,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Same as [RevealText::new], but first wraps the text to fit within the given width using
+    /// [word_wrap]. Wrapping before revealing any of the text ensures that words do not jump to
+    /// the next line part way through being revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: the text to reveal
+    /// * `chars_per_second`: how many characters to reveal each second
+    /// * `font`: the font the text will be drawn with
+    /// * `max_width`: the maximum width in pixels of each line of text
+    pub fn new_wrapped<T: Font>(
+        text: &str,
+        chars_per_second: f32,
+        font: &T,
+        max_width: u32,
+    ) -> RevealText {
+        RevealText::new(
+            &word_wrap_with_codes(font, text, max_width, &TextCodes::new()),
+            chars_per_second,
+        )
+    }
+
+    /// Reveals more of the text based on the amount of time elapsed. The given function is called
+    /// for each character as it is revealed, which can be used to play a sound for each one.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta`: the amount of time elapsed since the last update, in seconds
+    /// * `on_reveal`: function called with each character that was revealed
+    ///
+    /// returns: the number of characters that were revealed
+    pub fn update(&mut self, delta: f32, mut on_reveal: impl FnMut(char)) -> usize {
+        if self.is_complete() {
+            return 0;
+        }
+        self.accumulator += delta * self.chars_per_second;
+        let mut count = 0;
+        while self.accumulator >= 1.0 && !self.is_complete() {
+            self.accumulator -= 1.0;
+            on_reveal(self.reveal_points[self.revealed].0);
+            self.revealed += 1;
+            count += 1;
+        }
+        if self.is_complete() {
+            self.accumulator = 0.0;
+        }
+        count
+    }
+
+    /// Immediately reveals all of the remaining text, without calling any reveal functions. This
+    /// is useful for letting the player skip ahead.
+    pub fn reveal_all(&mut self) {
+        self.revealed = self.reveal_points.len();
+        self.accumulator = 0.0;
+    }
+
+    /// Hides all of the text again, so that it can be revealed from the start.
+    pub fn reset(&mut self) {
+        self.revealed = 0;
+        self.accumulator = 0.0;
+    }
+
+    /// Returns true if all of the text has been revealed.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.revealed >= self.reveal_points.len()
+    }
+
+    /// Returns the full text, including the parts not revealed yet.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the part of the text that has been revealed so far.
+    pub fn visible_text(&self) -> &str {
+        match self.revealed {
+            0 => "",
+            revealed => &self.text[0..self.reveal_points[revealed - 1].1],
+        }
+    }
+
+    /// The number of characters revealed so far.
+    #[inline]
+    pub fn revealed_count(&self) -> usize {
+        self.revealed
+    }
+
+    /// The total number of characters to be revealed.
+    #[inline]
+    pub fn total_count(&self) -> usize {
+        self.reveal_points.len()
+    }
+
+    #[inline]
+    pub fn chars_per_second(&self) -> f32 {
+        self.chars_per_second
+    }
+
+    #[inline]
+    pub fn set_chars_per_second(&mut self, chars_per_second: f32) {
+        self.chars_per_second = chars_per_second;
+    }
+
+    /// Draws the part of the text that has been revealed so far, the same as
+    /// [Bitmap::print_string_with_codes].
+    pub fn draw<T: Font, P: PixelBuffer>(
+        &self,
+        dest: &mut Bitmap<P>,
+        x: i32,
+        y: i32,
+        opts: FontRenderOpts,
+        font: &T,
+    ) {
+        dest.print_string_with_codes(self.visible_text(), x, y, opts, font, &TextCodes::new());
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn reveal_over_time() {
+        let mut text = RevealText::new("Hi {color:4}yo!", 10.0);
+        assert_eq!(6, text.total_count());
+        assert_eq!("", text.visible_text());
+
+        let mut revealed = String::new();
+        assert_eq!(0, text.update(0.05, |ch| revealed.push(ch)));
+        assert_eq!(1, text.update(0.05, |ch| revealed.push(ch)));
+        assert_eq!("H", text.visible_text());
+        assert_eq!(2, text.update(0.2, |ch| revealed.push(ch)));
+        assert_eq!("Hi ", text.visible_text());
+        // formatting codes are revealed along with the character following them
+        assert_eq!(1, text.update(0.1, |ch| revealed.push(ch)));
+        assert_eq!("Hi {color:4}y", text.visible_text());
+        assert_eq!(2, text.update(10.0, |ch| revealed.push(ch)));
+        assert_eq!("Hi yo!", revealed);
+        assert!(text.is_complete());
+        assert_eq!(text.text(), text.visible_text());
+        assert_eq!(0, text.update(1.0, |_| panic!("nothing left to reveal")));
+
+        text.reset();
+        assert_eq!(0, text.revealed_count());
+        text.reveal_all();
+        assert!(text.is_complete());
+    }
+
+    #[test]
+    pub fn wrapped_reveal() -> Result<(), FontError> {
+        let font = BitmaskFont::new_vga_font()?;
+        let mut text = RevealText::new_wrapped("hello world", 100.0, &font, 80);
+        assert_eq!("hello\nworld", text.text());
+        text.update(0.07, |_| {});
+        assert_eq!("hello\nw", text.visible_text());
+
+        let mut expected = Bitmap::new(64, 16).unwrap();
+        let mut actual = Bitmap::new(64, 16).unwrap();
+        expected.print_string("hello\nw", 0, 0, FontRenderOpts::Color(15), &font);
+        text.draw(&mut actual, 0, 0, FontRenderOpts::Color(15), &font);
+        assert_eq!(expected.pixels(), actual.pixels());
+
+        Ok(())
+    }
+}
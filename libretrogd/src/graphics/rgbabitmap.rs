@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::fmt::Formatter;
+
+use crate::graphics::*;
+use crate::math::*;
+
+/// Blit methods supported by [RgbaBitmap]s.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RgbaBlitMethod {
+    /// Solid blit, no transparency or other per-pixel adjustments.
+    Solid,
+    /// Transparent blit, the specified source color pixels are skipped. The color is specified
+    /// as a 32-bit packed value in the format 0xAARRGGBB.
+    Transparent(u32),
+    /// Source pixels are alpha-blended onto the destination using each source pixel's alpha
+    /// component.
+    AlphaBlended,
+}
+
+/// Blends a 32-bit packed source color onto a destination color using the alpha component of the
+/// source color. The returned color always has an alpha component of 255.
+#[inline]
+pub fn blend_argb32_source_alpha(src: u32, dest: u32) -> u32 {
+    let (alpha, _, _, _) = from_argb32(src);
+    match alpha {
+        0 => dest | 0xff000000,
+        255 => src,
+        _ => lerp_rgb32(dest, src, alpha as f32 / 255.0),
+    }
+}
+
+/// Container for 32-bit "truecolor" 2D pixel/image data, where each pixel is stored as a packed
+/// 0xAARRGGBB value. This works much the same way as [Bitmap] (and shares the same coordinate
+/// system, clipping and blit clipping behaviour), but is intended for cases where a truecolor
+/// compositing pass is wanted, such as for final output post-processing. Use
+/// [RgbaBitmap::from_bitmap] and [RgbaBitmap::to_bitmap] to convert to/from indexed bitmaps.
+///
+/// This is a separate type from [Bitmap] and does not share its drawing code. Only the small set
+/// of primitives and blits that a compositing pass needs is provided: pixels, horizontal and
+/// vertical lines, rectangles, solid, transparent and alpha-blended blits of other RGBA bitmaps,
+/// and blits of indexed bitmaps through a palette. Anything more involved, such as text, circles,
+/// or rotated and scaled blits, should be drawn on an indexed [Bitmap] first and then converted or
+/// blitted over.
+#[derive(Clone, Eq, PartialEq)]
+pub struct RgbaBitmap {
+    width: u32,
+    height: u32,
+    pixels: Box<[u32]>,
+    clip_region: Rect,
+}
+
+impl std::fmt::Debug for RgbaBitmap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RgbaBitmap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("clip_region", &self.clip_region)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RgbaBitmap {
+    /// Creates a new RgbaBitmap with the specified dimensions, with all pixels initialized to
+    /// opaque black.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the bitmap in pixels
+    /// * `height`: the height of the bitmap in pixels
+    ///
+    /// returns: `Result<RgbaBitmap, BitmapError>`
+    pub fn new(width: u32, height: u32) -> Result<RgbaBitmap, BitmapError> {
+        if width == 0 || height == 0 {
+            return Err(BitmapError::InvalidDimensions);
+        }
+
+        Ok(RgbaBitmap {
+            width,
+            height,
+            pixels: vec![to_rgb32(0, 0, 0); (width * height) as usize].into_boxed_slice(),
+            clip_region: Rect::new(0, 0, width, height),
+        })
+    }
+
+    /// Creates a new RgbaBitmap by converting an indexed bitmap using the given palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitmap`: the indexed bitmap to convert
+    /// * `palette`: the palette to look up the indexed bitmap's colors in
+    pub fn from_bitmap(bitmap: &Bitmap, palette: &Palette) -> RgbaBitmap {
+        // the source bitmap has non-zero dimensions, so this cannot fail
+        let mut result = RgbaBitmap::new(bitmap.width(), bitmap.height()).unwrap();
+        bitmap.copy_as_argb_to(&mut result.pixels, palette);
+        result
+    }
+
+    /// Converts this bitmap to an indexed bitmap, using the closest matching colors from the
+    /// given palette. Alpha components are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette`: the palette to match colors against
+    pub fn to_bitmap(&self, palette: &Palette) -> Bitmap {
+        // the source bitmap has non-zero dimensions, so this cannot fail
+        let mut result = Bitmap::new(self.width, self.height).unwrap();
+        let mut matches = HashMap::new();
+        for (src, dest) in self.pixels.iter().zip(result.pixels_mut().iter_mut()) {
+            let rgb = *src | 0xff000000;
+            *dest = *matches.entry(rgb).or_insert_with(|| {
+                let (r, g, b) = from_rgb32(rgb);
+                palette.find_color(r, g, b)
+            });
+        }
+        result
+    }
+
+    /// Returns the width of the bitmap in pixels.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the bitmap in pixels.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the right x coordinate of the bitmap.
+    #[inline]
+    pub fn right(&self) -> u32 {
+        self.width - 1
+    }
+
+    /// Returns the bottom x coordinate of the bitmap.
+    #[inline]
+    pub fn bottom(&self) -> u32 {
+        self.height - 1
+    }
+
+    /// Returns the current clipping region set on this bitmap.
+    #[inline]
+    pub fn clip_region(&self) -> &Rect {
+        &self.clip_region
+    }
+
+    /// Returns a rect representing the full bitmap boundaries, ignoring the current clipping
+    /// region set on this bitmap.
+    #[inline]
+    pub fn full_bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    /// Sets a new clipping region on this bitmap. The region will be automatically clamped to
+    /// the maximum bitmap boundaries if the supplied region extends beyond it.
+    pub fn set_clip_region(&mut self, region: &Rect) {
+        self.clip_region = *region;
+        self.clip_region.clamp_to(&self.full_bounds());
+    }
+
+    /// Resets the bitmaps clipping region back to the default (full boundaries of the bitmap).
+    pub fn reset_clip_region(&mut self) {
+        self.clip_region = self.full_bounds();
+    }
+
+    /// Returns a reference to the raw pixels in this bitmap.
+    #[inline]
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// Returns a mutable reference to the raw pixels in this bitmap.
+    #[inline]
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        &mut self.pixels
+    }
+
+    /// Returns an offset corresponding to the coordinates of the pixel given on this bitmap that
+    /// can be used with a reference to the raw pixels in this bitmap to access that pixel. The
+    /// coordinates given are not checked for validity.
+    #[inline]
+    pub fn get_offset_to_xy(&self, x: i32, y: i32) -> usize {
+        ((y * self.width as i32) + x) as usize
+    }
+
+    /// Returns true if the coordinates given lie within the bitmaps clipping region.
+    #[inline]
+    pub fn is_xy_visible(&self, x: i32, y: i32) -> bool {
+        (x >= self.clip_region.x)
+            && (y >= self.clip_region.y)
+            && (x <= self.clip_region.right())
+            && (y <= self.clip_region.bottom())
+    }
+
+    /// Copies the entire pixel data from this bitmap to a destination expecting 32-bit
+    /// ARGB-format pixel data.
+    pub fn copy_as_argb_to(&self, dest: &mut [u32]) {
+        let length = dest.len().min(self.pixels.len());
+        dest[0..length].copy_from_slice(&self.pixels[0..length]);
+    }
+
+    /// Fills the entire bitmap with the given color.
+    pub fn clear(&mut self, color: u32) {
+        self.pixels.fill(color);
+    }
+
+    /// Sets the pixel at the given coordinates to the color specified. If the coordinates lie
+    /// outside of the bitmaps clipping region, no pixels will be changed.
+    #[inline]
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: u32) {
+        if self.is_xy_visible(x, y) {
+            let offset = self.get_offset_to_xy(x, y);
+            self.pixels[offset] = color;
+        }
+    }
+
+    /// Gets the pixel at the given coordinates. If the coordinates lie outside of the bitmaps
+    /// clipping region, None is returned.
+    #[inline]
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<u32> {
+        if self.is_xy_visible(x, y) {
+            Some(self.pixels[self.get_offset_to_xy(x, y)])
+        } else {
+            None
+        }
+    }
+
+    /// Draws a horizontal line from x1,y to x2,y.
+    pub fn horiz_line(&mut self, x1: i32, x2: i32, y: i32, color: u32) {
+        let mut region = Rect::from_coords(x1, y, x2, y);
+        if region.clamp_to(&self.clip_region) {
+            let start = self.get_offset_to_xy(region.x, region.y);
+            self.pixels[start..(start + region.width as usize)].fill(color);
+        }
+    }
+
+    /// Draws a vertical line from x,y1 to x,y2.
+    pub fn vert_line(&mut self, x: i32, y1: i32, y2: i32, color: u32) {
+        let mut region = Rect::from_coords(x, y1, x, y2);
+        if region.clamp_to(&self.clip_region) {
+            for y in region.y..=region.bottom() {
+                let offset = self.get_offset_to_xy(region.x, y);
+                self.pixels[offset] = color;
+            }
+        }
+    }
+
+    /// Draws an empty box (rectangle) using the points x1,y1 and x2,y2 to form the box to be
+    /// drawn, assuming they are specifying the top-left and bottom-right corners respectively.
+    pub fn rect(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) {
+        self.horiz_line(x1, x2, y1, color);
+        self.horiz_line(x1, x2, y2, color);
+        self.vert_line(x1, y1, y2, color);
+        self.vert_line(x2, y1, y2, color);
+    }
+
+    /// Draws a filled box (rectangle) using the points x1,y1 and x2,y2 to form the box to be
+    /// drawn, assuming they are specifying the top-left and bottom-right corners respectively.
+    pub fn filled_rect(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) {
+        let mut region = Rect::from_coords(x1, y1, x2, y2);
+        if region.clamp_to(&self.clip_region) {
+            for y in region.y..=region.bottom() {
+                let start = self.get_offset_to_xy(region.x, y);
+                self.pixels[start..(start + region.width as usize)].fill(color);
+            }
+        }
+    }
+
+    /// Blits a region of another RgbaBitmap onto this one. The source region and the
+    /// destination location are clipped in the same way as with [Bitmap::blit_region].
+    pub fn blit_region(
+        &mut self,
+        method: RgbaBlitMethod,
+        src: &RgbaBitmap,
+        src_region: &Rect,
+        mut dest_x: i32,
+        mut dest_y: i32,
+    ) {
+        let mut src_region = *src_region;
+        if !src_region.clamp_to(&src.clip_region) {
+            return;
+        }
+        if !clip_blit(&self.clip_region, &mut src_region, &mut dest_x, &mut dest_y, false, false) {
+            return;
+        }
+
+        let width = src_region.width as usize;
+        for row in 0..src_region.height as i32 {
+            let src_offset = src.get_offset_to_xy(src_region.x, src_region.y + row);
+            let dest_offset = self.get_offset_to_xy(dest_x, dest_y + row);
+            let src_pixels = &src.pixels[src_offset..(src_offset + width)];
+            let dest_pixels = &mut self.pixels[dest_offset..(dest_offset + width)];
+            match method {
+                RgbaBlitMethod::Solid => dest_pixels.copy_from_slice(src_pixels),
+                RgbaBlitMethod::Transparent(transparent_color) => {
+                    for (src, dest) in src_pixels.iter().zip(dest_pixels.iter_mut()) {
+                        if *src != transparent_color {
+                            *dest = *src;
+                        }
+                    }
+                }
+                RgbaBlitMethod::AlphaBlended => {
+                    for (src, dest) in src_pixels.iter().zip(dest_pixels.iter_mut()) {
+                        *dest = blend_argb32_source_alpha(*src, *dest);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Blits the entirety of another RgbaBitmap onto this one.
+    #[inline]
+    pub fn blit(&mut self, method: RgbaBlitMethod, src: &RgbaBitmap, x: i32, y: i32) {
+        let src_region = Rect::new(0, 0, src.width, src.height);
+        self.blit_region(method, src, &src_region, x, y);
+    }
+
+    /// Blits an indexed bitmap onto this one, converting its pixels using the palette given. If
+    /// a transparent color is given, source pixels of that palette color index are skipped.
+    pub fn blit_indexed(
+        &mut self,
+        src: &Bitmap,
+        palette: &Palette,
+        transparent_color: Option<u8>,
+        mut dest_x: i32,
+        mut dest_y: i32,
+    ) {
+        let mut src_region = *src.clip_region();
+        if !clip_blit(&self.clip_region, &mut src_region, &mut dest_x, &mut dest_y, false, false) {
+            return;
+        }
+
+        for row in 0..src_region.height as i32 {
+            for col in 0..src_region.width as i32 {
+                let pixel =
+                    unsafe { src.get_pixel_unchecked(src_region.x + col, src_region.y + row) };
+                if Some(pixel) != transparent_color {
+                    let offset = self.get_offset_to_xy(dest_x + col, dest_y + row);
+                    self.pixels[offset] = palette[pixel];
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::assert_matches;
+
+    use super::*;
+
+    #[test]
+    pub fn creation_and_pixels() {
+        assert_matches!(RgbaBitmap::new(0, 8), Err(BitmapError::InvalidDimensions));
+
+        let mut bmp = RgbaBitmap::new(8, 4).unwrap();
+        assert_eq!(8, bmp.width());
+        assert_eq!(4, bmp.height());
+        assert_eq!(Some(0xff000000), bmp.get_pixel(0, 0));
+
+        bmp.set_pixel(2, 3, 0xff112233);
+        assert_eq!(Some(0xff112233), bmp.get_pixel(2, 3));
+        assert_eq!(None, bmp.get_pixel(8, 0));
+
+        bmp.filled_rect(1, 1, 2, 2, 0xffffffff);
+        assert_eq!(Some(0xffffffff), bmp.get_pixel(1, 1));
+        assert_eq!(Some(0xffffffff), bmp.get_pixel(2, 2));
+        assert_eq!(Some(0xff000000), bmp.get_pixel(3, 3));
+    }
+
+    #[test]
+    pub fn indexed_conversions() {
+        let palette = Palette::new_vga_palette().unwrap();
+        let mut indexed = Bitmap::new(4, 1).unwrap();
+        indexed.pixels_mut().copy_from_slice(&[0, 1, 4, 15]);
+
+        let rgba = RgbaBitmap::from_bitmap(&indexed, &palette);
+        assert_eq!(&[palette[0], palette[1], palette[4], palette[15]], rgba.pixels());
+
+        let converted = rgba.to_bitmap(&palette);
+        assert_eq!(indexed.pixels(), converted.pixels());
+    }
+
+    #[test]
+    pub fn blits() {
+        let mut src = RgbaBitmap::new(2, 2).unwrap();
+        src.pixels_mut().copy_from_slice(&[0xffff0000, 0x00000000, 0x80ffffff, 0xff00ff00]);
+
+        let mut dest = RgbaBitmap::new(4, 4).unwrap();
+        dest.blit(RgbaBlitMethod::Solid, &src, 3, 3);
+        assert_eq!(Some(0xffff0000), dest.get_pixel(3, 3));
+
+        let mut dest = RgbaBitmap::new(4, 4).unwrap();
+        dest.blit(RgbaBlitMethod::Transparent(0x00000000), &src, 0, 0);
+        assert_eq!(Some(0xffff0000), dest.get_pixel(0, 0));
+        assert_eq!(Some(0xff000000), dest.get_pixel(1, 0));
+
+        let mut dest = RgbaBitmap::new(4, 4).unwrap();
+        dest.blit(RgbaBlitMethod::AlphaBlended, &src, 0, 0);
+        assert_eq!(Some(0xff000000), dest.get_pixel(1, 0));
+        assert_eq!(Some(0xff808080), dest.get_pixel(0, 1));
+
+        let palette = Palette::new_vga_palette().unwrap();
+        let mut indexed = Bitmap::new(2, 1).unwrap();
+        indexed.pixels_mut().copy_from_slice(&[0, 4]);
+        let mut dest = RgbaBitmap::new(4, 4).unwrap();
+        dest.clear(0xffffffff);
+        dest.blit_indexed(&indexed, &palette, Some(0), 1, 1);
+        assert_eq!(Some(0xffffffff), dest.get_pixel(1, 1));
+        assert_eq!(Some(palette[4]), dest.get_pixel(2, 1));
+    }
+}
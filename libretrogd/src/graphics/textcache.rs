@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use crate::graphics::*;
+use crate::math::*;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct TextCacheKey {
+    text: String,
+    font: usize,
+    opts: FontRenderOpts,
+}
+
+/// A string of text that has been rendered into a bitmap by a [TextCache].
+#[derive(Debug, Clone)]
+struct CachedText {
+    bitmap: Bitmap,
+    /// The area of the bitmap that the text was rendered into. Bitmaps are re-used, so this may
+    /// be smaller than the bitmap itself.
+    region: Rect,
+    /// The color of all the pixels in the rendered region that are not part of the text.
+    transparent_color: u8,
+    /// How far up and to the left the rendered text extends beyond the coordinates it was printed
+    /// at, due to shadows or outlines.
+    offset_x: i32,
+    offset_y: i32,
+}
+
+/// Caches strings of text after rendering them into bitmaps, so that drawing the same text again
+/// later is a single blit instead of drawing each character individually. This is useful for
+/// large amounts of text that do not change often, such as pages of dialogue or menus.
+///
+/// Text is cached by the string itself, the font and the render options. Fonts are identified by
+/// their address in memory, so if a font is modified, moved or dropped, any text cached for it
+/// should be invalidated with [TextCache::invalidate_font] or [TextCache::clear].
+///
+/// Bitmaps for invalidated text are kept and re-used for rendering other text.
+#[derive(Debug, Clone, Default)]
+pub struct TextCache {
+    entries: HashMap<TextCacheKey, CachedText>,
+    pool: Vec<Bitmap>,
+}
+
+fn font_id<T: Font>(font: &T) -> usize {
+    font as *const T as usize
+}
+
+impl TextCache {
+    pub fn new() -> TextCache {
+        TextCache {
+            entries: HashMap::new(),
+            pool: Vec::new(),
+        }
+    }
+
+    /// Renders the string of text using the font given, the same as [Bitmap::print_string],
+    /// except that the text is only rendered the first time. Afterwards the previously rendered
+    /// text is drawn instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest`: the bitmap to draw the text on
+    /// * `text`: the text to draw
+    /// * `x`: the x coordinate to draw the text at
+    /// * `y`: the y coordinate to draw the text at
+    /// * `opts`: the render options to draw the text with
+    /// * `font`: the font to draw the text with
+    pub fn print_string<T: Font, P: PixelBuffer>(
+        &mut self,
+        dest: &mut Bitmap<P>,
+        text: &str,
+        x: i32,
+        y: i32,
+        opts: FontRenderOpts,
+        font: &T,
+    ) {
+        let key = TextCacheKey {
+            text: text.to_string(),
+            font: font_id(font),
+            opts,
+        };
+        if !self.entries.contains_key(&key) {
+            let cached = self.render(text, opts, font);
+            self.entries.insert(key.clone(), cached);
+        }
+        if let Some(cached) = self.entries.get(&key) {
+            dest.blit_region(
+                BlitMethod::Transparent(cached.transparent_color),
+                &cached.bitmap,
+                &cached.region,
+                x - cached.offset_x,
+                y - cached.offset_y,
+            );
+        }
+    }
+
+    /// Returns true if the given text has been cached for the font and render options given.
+    pub fn contains<T: Font>(&self, text: &str, opts: FontRenderOpts, font: &T) -> bool {
+        self.entries.contains_key(&TextCacheKey {
+            text: text.to_string(),
+            font: font_id(font),
+            opts,
+        })
+    }
+
+    /// Removes the given text, previously cached for the font and render options given, from the
+    /// cache. Returns true if the text was cached.
+    pub fn invalidate<T: Font>(&mut self, text: &str, opts: FontRenderOpts, font: &T) -> bool {
+        let key = TextCacheKey {
+            text: text.to_string(),
+            font: font_id(font),
+            opts,
+        };
+        match self.entries.remove(&key) {
+            Some(cached) => {
+                self.pool.push(cached.bitmap);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes all text cached for the given font from the cache.
+    pub fn invalidate_font<T: Font>(&mut self, font: &T) {
+        let font = font_id(font);
+        let keys: Vec<TextCacheKey> = self
+            .entries
+            .keys()
+            .filter(|key| key.font == font)
+            .cloned()
+            .collect();
+        for key in keys {
+            if let Some(cached) = self.entries.remove(&key) {
+                self.pool.push(cached.bitmap);
+            }
+        }
+    }
+
+    /// Removes all text from the cache.
+    pub fn clear(&mut self) {
+        self.pool
+            .extend(self.entries.drain().map(|(_, cached)| cached.bitmap));
+    }
+
+    /// Returns the number of strings of text currently cached.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns a bitmap from the pool at least as large as the size given, or a new one if there
+    /// are none.
+    fn take_bitmap(&mut self, width: u32, height: u32) -> Bitmap {
+        let pooled = self
+            .pool
+            .iter()
+            .position(|bitmap| bitmap.width() >= width && bitmap.height() >= height);
+        match pooled {
+            Some(index) => self.pool.swap_remove(index),
+            None => Bitmap::new(width, height).unwrap(),
+        }
+    }
+
+    fn render<T: Font>(&mut self, text: &str, opts: FontRenderOpts, font: &T) -> CachedText {
+        let (left, top, right, bottom) = opts.extents();
+        let (width, height) = font.measure(text, opts);
+        // rendered text can extend a little past what is measured, such as characters with pixels
+        // past their width, so leave some room for that
+        let width = (width + left + right + font.line_height() as u32).max(1);
+        let height = (height + top + bottom)
+            .max(font.line_height() as u32)
+            .max(1);
+        let region = Rect::new(0, 0, width, height);
+
+        // the text could be rendered using any color, so it is rendered twice on top of two
+        // different background colors to figure out which pixels are part of the text, and then
+        // an unused color can be picked as the transparent color
+        let mut bitmap = self.take_bitmap(width, height);
+        let mut other = self.take_bitmap(width, height);
+        bitmap.set_clip_region(&region);
+        other.set_clip_region(&region);
+        bitmap.filled_rect(0, 0, width as i32 - 1, height as i32 - 1, 0);
+        other.filled_rect(0, 0, width as i32 - 1, height as i32 - 1, 255);
+        bitmap.print_string(text, left as i32, top as i32, opts, font);
+        other.print_string(text, left as i32, top as i32, opts, font);
+
+        let mut used_colors = [false; 256];
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let color = bitmap.get_pixel(x, y).unwrap_or(0);
+                if color != 0 || other.get_pixel(x, y) != Some(255) {
+                    used_colors[color as usize] = true;
+                }
+            }
+        }
+        let transparent_color = used_colors.iter().position(|used| !used).unwrap_or(0) as u8;
+        if transparent_color != 0 {
+            for y in 0..height as i32 {
+                for x in 0..width as i32 {
+                    if bitmap.get_pixel(x, y) == Some(0) && other.get_pixel(x, y) == Some(255) {
+                        bitmap.set_pixel(x, y, transparent_color);
+                    }
+                }
+            }
+        }
+
+        bitmap.set_clip_region(&bitmap.full_bounds());
+        other.set_clip_region(&other.full_bounds());
+        self.pool.push(other);
+
+        CachedText {
+            bitmap,
+            region,
+            transparent_color,
+            offset_x: left as i32,
+            offset_y: top as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn cached_text_matches_uncached_text() -> Result<(), FontError> {
+        let font = BitmaskFont::new_vga_font()?;
+        let mut cache = TextCache::new();
+        assert!(cache.is_empty());
+
+        let outline = FontRenderOpts::Outline {
+            color: 0,
+            outline_color: 15,
+        };
+        let cases = [
+            ("Hello, world!", FontRenderOpts::Color(15)),
+            ("multiple\nlines", FontRenderOpts::Color(0)),
+            ("outlined", outline),
+            ("{color:4}", FontRenderOpts::Color(15)),
+        ];
+
+        for (text, opts) in cases {
+            let mut expected = Bitmap::new(128, 32).unwrap();
+            let mut actual = Bitmap::new(128, 32).unwrap();
+            expected.clear(7);
+            actual.clear(7);
+            expected.print_string(text, 10, 5, opts, &font);
+            cache.print_string(&mut actual, text, 10, 5, opts, &font);
+            assert_eq!(
+                expected.pixels(),
+                actual.pixels(),
+                "first render of {:?}",
+                text
+            );
+
+            // and once more, using the previously cached text
+            assert!(cache.contains(text, opts, &font));
+            actual.clear(7);
+            cache.print_string(&mut actual, text, 10, 5, opts, &font);
+            assert_eq!(
+                expected.pixels(),
+                actual.pixels(),
+                "cached render of {:?}",
+                text
+            );
+        }
+        assert_eq!(4, cache.len());
+
+        assert!(cache.invalidate("outlined", outline, &font));
+        assert!(!cache.invalidate("outlined", outline, &font));
+        assert!(!cache.contains("outlined", outline, &font));
+        assert_eq!(3, cache.len());
+
+        let other_font = BitmaskFont::new_vga_font()?;
+        let mut bmp = Bitmap::new(128, 32).unwrap();
+        cache.print_string(
+            &mut bmp,
+            "Hello, world!",
+            0,
+            0,
+            FontRenderOpts::Color(15),
+            &other_font,
+        );
+        assert_eq!(4, cache.len());
+        cache.invalidate_font(&font);
+        assert_eq!(1, cache.len());
+        cache.clear();
+        assert!(cache.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1355 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use roxmltree::{Document, Node};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::graphics::*;
+use crate::math::*;
+
+const GID_FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+const GID_FLIPPED_VERTICALLY: u32 = 0x40000000;
+const GID_FLIPPED_DIAGONALLY: u32 = 0x20000000;
+const GID_ROTATED_HEXAGONAL: u32 = 0x10000000;
+const GID_MASK: u32 = !(GID_FLIPPED_HORIZONTALLY
+    | GID_FLIPPED_VERTICALLY
+    | GID_FLIPPED_DIAGONALLY
+    | GID_ROTATED_HEXAGONAL);
+
+#[derive(Error, Debug)]
+pub enum TiledError {
+    #[error("Bad or unsupported Tiled map: {0}")]
+    BadFile(String),
+
+    #[error("Tiled map XML error")]
+    XmlError(#[from] roxmltree::Error),
+
+    #[error("Tiled map JSON error")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Tiled tileset image error")]
+    BitmapError(#[from] BitmapError),
+
+    #[error("Tiled map I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+/// The value of a custom property set on a Tiled map, layer or object.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TiledPropertyValue {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// A color, as 0xAARRGGBB.
+    Color(u32),
+    /// A file path, as written in the map (usually relative to the map file).
+    File(String),
+    /// The ID of an object in the map, or 0 for none.
+    Object(u32),
+}
+
+impl TiledPropertyValue {
+    /// Returns the value of a string or file property.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TiledPropertyValue::String(value) | TiledPropertyValue::File(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an int or object property.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            TiledPropertyValue::Int(value) => Some(*value),
+            TiledPropertyValue::Object(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a float or int property.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            TiledPropertyValue::Float(value) => Some(*value),
+            TiledPropertyValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a bool property.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            TiledPropertyValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Custom properties, by name.
+pub type TiledProperties = HashMap<String, TiledPropertyValue>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TiledObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    /// A closed polygon, with points relative to the object's position.
+    Polygon(Vec<Vector2>),
+    /// An open line, with points relative to the object's position.
+    Polyline(Vec<Vector2>),
+}
+
+/// An object from one of a Tiled map's object layers, such as a spawn point or trigger area.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    /// The object's class (called its type in versions of Tiled before 1.9).
+    pub class: String,
+    /// The name of the object layer this object is from.
+    pub layer: String,
+    /// The object's x coordinate, in pixels.
+    pub x: f32,
+    /// The object's y coordinate, in pixels. Note that Tiled positions tile objects by their
+    /// bottom-left corner, but all other objects by their top-left corner.
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// The object's rotation (clockwise, around its position) in degrees.
+    pub rotation: f32,
+    pub visible: bool,
+    pub shape: TiledObjectShape,
+    /// The tile shown for tile objects, with its index into the [TileMap]'s tileset.
+    pub tile: Option<Tile>,
+    pub properties: TiledProperties,
+}
+
+impl TiledObject {
+    /// Returns the custom property with the given name, if it is set.
+    #[inline]
+    pub fn property(&self, name: &str) -> Option<&TiledPropertyValue> {
+        self.properties.get(name)
+    }
+}
+
+/// A map loaded from a file saved by the [Tiled](https://www.mapeditor.org/) map editor, in
+/// either its TMX (XML) or JSON formats.
+///
+/// All of the map's tile layers (including those nested within group layers) become layers of
+/// the [TileMap], in the same order, and all of the objects from its object layers are collected
+/// into a single list. All of the tilesets (which can be external files) are combined into a
+/// single [BitmapAtlas], so the tile indices used in the [TileMap] do not match Tiled's tile
+/// IDs. The first tile layer is drawn solid and all of the others use color 0 as transparent.
+/// Tile animations are supported, using the duration of the first frame for all frames.
+///
+/// Since tileset images are loaded with [Bitmap::load_file], they must be 256 color images, and
+/// they should all use the same palette. Only orthogonal, non-infinite maps are supported.
+/// Tilesets made from a collection of separate images, image layers, object templates, and
+/// tiles flipped diagonally or rotated are not supported.
+#[derive(Debug, Clone)]
+pub struct TiledMap {
+    pub map: TileMap,
+    /// The palette of the first tileset image, or `None` if the map has no tilesets.
+    pub palette: Option<Palette>,
+    pub objects: Vec<TiledObject>,
+    pub properties: TiledProperties,
+}
+
+impl TiledMap {
+    /// Loads a Tiled map file. Files with a "json" or "tmj" extension are loaded as JSON, and all
+    /// others are loaded as TMX. External tilesets and tileset images are loaded from paths
+    /// relative to the map file.
+    pub fn load_file(path: &Path) -> Result<TiledMap, TiledError> {
+        let text = std::fs::read_to_string(path)?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        if is_json_path(path) {
+            Self::load_json(&text, base_dir)
+        } else {
+            Self::load_tmx(&text, base_dir)
+        }
+    }
+
+    /// Loads a Tiled map from the contents of a TMX (XML) file. External tilesets and tileset
+    /// images are loaded from paths relative to the given directory.
+    pub fn load_tmx(text: &str, base_dir: &Path) -> Result<TiledMap, TiledError> {
+        parse_tmx(text, base_dir)?.build()
+    }
+
+    /// Loads a Tiled map from the contents of a JSON file. External tilesets and tileset images
+    /// are loaded from paths relative to the given directory.
+    pub fn load_json(text: &str, base_dir: &Path) -> Result<TiledMap, TiledError> {
+        parse_json(text, base_dir)?.build()
+    }
+
+    /// Returns the first object with the given name, if any.
+    pub fn object_named(&self, name: &str) -> Option<&TiledObject> {
+        self.objects.iter().find(|object| object.name == name)
+    }
+
+    /// Returns all of the objects with the given class.
+    pub fn objects_of_class<'a>(&'a self, class: &'a str) -> impl Iterator<Item = &'a TiledObject> {
+        self.objects
+            .iter()
+            .filter(move |object| object.class == class)
+    }
+}
+
+fn is_json_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+        .is_some_and(|extension| matches!(extension.as_str(), "json" | "tmj" | "tsj"))
+}
+
+/// A tileset as it is in the map file, before its image is loaded.
+struct RawTileset {
+    first_gid: u32,
+    tile_width: u32,
+    tile_height: u32,
+    spacing: u32,
+    margin: u32,
+    columns: u32,
+    tile_count: u32,
+    image: PathBuf,
+    /// The local ID of each animated tile, the local IDs of its frames, and the duration of its
+    /// first frame in milliseconds.
+    animations: Vec<(u32, Vec<u32>, u32)>,
+}
+
+enum RawLayer {
+    Tiles {
+        name: String,
+        visible: bool,
+        parallax: Vector2,
+        gids: Vec<u32>,
+    },
+    /// Objects along with the global tile ID of tile objects.
+    Objects(Vec<(TiledObject, Option<u32>)>),
+}
+
+/// A map as it is in the map file, before its tileset images are loaded.
+struct RawMap {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<RawTileset>,
+    layers: Vec<RawLayer>,
+    properties: TiledProperties,
+}
+
+/// Converts a global tile ID (which includes flip flags) from the map file to a [Tile], using
+/// the index each tileset's tiles start at in the combined tileset atlas.
+fn gid_to_tile(tilesets: &[RawTileset], bases: &[usize], gid: u32) -> Result<Tile, TiledError> {
+    let id = gid & GID_MASK;
+    if id == 0 {
+        return Ok(Tile::EMPTY);
+    }
+    let mut flags = 0;
+    if gid & GID_FLIPPED_HORIZONTALLY != 0 {
+        flags |= TILE_FLAG_FLIP_X;
+    }
+    if gid & GID_FLIPPED_VERTICALLY != 0 {
+        flags |= TILE_FLAG_FLIP_Y;
+    }
+    match tilesets.iter().rposition(|tileset| tileset.first_gid <= id) {
+        Some(index) if id - tilesets[index].first_gid < tilesets[index].tile_count => {
+            Ok(Tile::with_flags(
+                (bases[index] + (id - tilesets[index].first_gid) as usize) as i32,
+                flags,
+            ))
+        }
+        _ => Err(TiledError::BadFile(format!(
+            "Tile ID {} is not in any tileset",
+            id
+        ))),
+    }
+}
+
+impl RawMap {
+    fn build(self) -> Result<TiledMap, TiledError> {
+        let RawMap {
+            width,
+            height,
+            tile_width,
+            tile_height,
+            mut tilesets,
+            layers,
+            properties,
+        } = self;
+        tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+        let mut images = Vec::with_capacity(tilesets.len());
+        let mut palette = None;
+        for tileset in tilesets.iter() {
+            let (image, image_palette) = Bitmap::load_file(&tileset.image)?;
+            palette.get_or_insert(image_palette);
+            images.push(image);
+        }
+
+        // all of the tileset images are stacked one above the other in a single atlas
+        let mut map = TileMap::new(width, height, tile_width, tile_height);
+        let mut bases = Vec::with_capacity(tilesets.len());
+        if !images.is_empty() {
+            let atlas_width = images.iter().map(|image| image.width()).max().unwrap_or(1);
+            let atlas_height = images.iter().map(|image| image.height()).sum();
+            let mut atlas_bitmap = Bitmap::new(atlas_width, atlas_height)?;
+            let mut y = 0;
+            for image in images.iter() {
+                atlas_bitmap.blit(BlitMethod::Solid, image, 0, y as i32);
+                y += image.height();
+            }
+
+            let mut atlas = BitmapAtlas::new(atlas_bitmap);
+            let mut y = 0;
+            for (tileset, image) in tilesets.iter().zip(images.iter()) {
+                bases.push(atlas.len());
+                let columns = tileset.columns.max(1);
+                for id in 0..tileset.tile_count {
+                    let (column, row) = (id % columns, id / columns);
+                    let region = Rect::new(
+                        (tileset.margin + column * (tileset.tile_width + tileset.spacing)) as i32,
+                        (y + tileset.margin + row * (tileset.tile_height + tileset.spacing)) as i32,
+                        tileset.tile_width,
+                        tileset.tile_height,
+                    );
+                    if region.bottom() >= (y + image.height()) as i32 || atlas.add(region).is_err()
+                    {
+                        return Err(TiledError::BadFile(format!(
+                            "Tileset image {} is too small for its tiles",
+                            tileset.image.display()
+                        )));
+                    }
+                }
+                y += image.height();
+            }
+            map.set_tileset(Some(atlas));
+        }
+
+        for (tileset, base) in tilesets.iter().zip(bases.iter()) {
+            for (id, frames, duration) in tileset.animations.iter() {
+                // animations are keyed by their first frame, so start at the animated tile itself
+                if let Some(start) = frames.iter().position(|frame| frame == id) {
+                    let frames = frames[start..]
+                        .iter()
+                        .chain(frames[..start].iter())
+                        .map(|frame| (base + *frame as usize) as i32)
+                        .collect();
+                    map.add_animation(frames, *duration as f32 / 1000.0);
+                }
+            }
+        }
+
+        let mut objects = Vec::new();
+        for layer in layers {
+            match layer {
+                RawLayer::Tiles {
+                    name,
+                    visible,
+                    parallax,
+                    gids,
+                } => {
+                    if gids.len() != (width * height) as usize {
+                        return Err(TiledError::BadFile(format!(
+                            "Tile layer \"{}\" has {} tiles, expected {}",
+                            name,
+                            gids.len(),
+                            width * height
+                        )));
+                    }
+                    let is_first = map.num_layers() == 0;
+                    let index = map.add_layer(&name);
+                    let tile_layer = map.layer_mut(index).unwrap();
+                    tile_layer.visible = visible;
+                    tile_layer.parallax = parallax;
+                    tile_layer.transparent_color = if is_first { None } else { Some(0) };
+                    for (tile, gid) in tile_layer.tiles_mut().iter_mut().zip(gids) {
+                        *tile = gid_to_tile(&tilesets, &bases, gid)?;
+                    }
+                }
+                RawLayer::Objects(layer_objects) => {
+                    for (mut object, gid) in layer_objects {
+                        object.tile = match gid {
+                            Some(gid) => Some(gid_to_tile(&tilesets, &bases, gid)?),
+                            None => None,
+                        };
+                        objects.push(object);
+                    }
+                }
+            }
+        }
+
+        Ok(TiledMap {
+            map,
+            palette,
+            objects,
+            properties,
+        })
+    }
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, TiledError> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => {
+                return Err(TiledError::BadFile(String::from(
+                    "Invalid base64 tile layer data",
+                )))
+            }
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Decodes base64 encoded (and optionally compressed) tile layer data into global tile IDs.
+fn decode_gids(text: &str, compression: Option<&str>) -> Result<Vec<u32>, TiledError> {
+    let encoded = decode_base64(text)?;
+    let mut bytes = Vec::new();
+    match compression {
+        None | Some("") => bytes = encoded,
+        Some("zlib") => {
+            ZlibDecoder::new(encoded.as_slice()).read_to_end(&mut bytes)?;
+        }
+        Some("gzip") => {
+            GzDecoder::new(encoded.as_slice()).read_to_end(&mut bytes)?;
+        }
+        Some(other) => {
+            return Err(TiledError::BadFile(format!(
+                "Unsupported tile layer compression: {}",
+                other
+            )))
+        }
+    }
+    if bytes.len() % 4 != 0 {
+        return Err(TiledError::BadFile(String::from(
+            "Tile layer data has a partial tile ID",
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+        .collect())
+}
+
+fn parse_csv(text: &str) -> Result<Vec<u32>, TiledError> {
+    text.split(',')
+        .map(|gid| gid.trim())
+        .filter(|gid| !gid.is_empty())
+        .map(|gid| {
+            gid.parse().map_err(|_| {
+                TiledError::BadFile(format!("Invalid tile ID in CSV tile layer data: {}", gid))
+            })
+        })
+        .collect()
+}
+
+fn parse_points(text: &str) -> Result<Vec<Vector2>, TiledError> {
+    text.split_whitespace()
+        .map(|point| {
+            match point
+                .split_once(',')
+                .map(|(x, y)| (x.parse::<f32>(), y.parse::<f32>()))
+            {
+                Some((Ok(x), Ok(y))) => Ok(Vector2::new(x, y)),
+                _ => Err(TiledError::BadFile(format!("Invalid point: {}", point))),
+            }
+        })
+        .collect()
+}
+
+fn parse_color(text: &str) -> Result<u32, TiledError> {
+    let hex = text.trim_start_matches('#');
+    match (hex.len(), u32::from_str_radix(hex, 16)) {
+        (0, _) => Ok(0),
+        (6, Ok(color)) => Ok(0xff000000 | color),
+        (8, Ok(color)) => Ok(color),
+        _ => Err(TiledError::BadFile(format!("Invalid color: {}", text))),
+    }
+}
+
+/// Parses a property's value from its text, returning `None` for types of properties which are
+/// not supported.
+fn parse_property(
+    property_type: &str,
+    value: &str,
+) -> Result<Option<TiledPropertyValue>, TiledError> {
+    let invalid = || {
+        TiledError::BadFile(format!(
+            "Invalid {} property value: {}",
+            property_type, value
+        ))
+    };
+    Ok(Some(match property_type {
+        "string" => TiledPropertyValue::String(String::from(value)),
+        "file" => TiledPropertyValue::File(String::from(value)),
+        "int" => TiledPropertyValue::Int(value.parse().map_err(|_| invalid())?),
+        "float" => TiledPropertyValue::Float(value.parse().map_err(|_| invalid())?),
+        "bool" => TiledPropertyValue::Bool(value.parse().map_err(|_| invalid())?),
+        "color" => TiledPropertyValue::Color(parse_color(value)?),
+        "object" => TiledPropertyValue::Object(value.parse().map_err(|_| invalid())?),
+        _ => return Ok(None),
+    }))
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn xml_parse<T: FromStr>(node: Node, name: &str) -> Result<T, TiledError> {
+    match node.attribute(name) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| TiledError::BadFile(format!("Invalid \"{}\" attribute: {}", name, value))),
+        None => Err(TiledError::BadFile(format!(
+            "Missing \"{}\" attribute on <{}>",
+            name,
+            node.tag_name().name()
+        ))),
+    }
+}
+
+fn xml_parse_or<T: FromStr>(node: Node, name: &str, default: T) -> Result<T, TiledError> {
+    if node.has_attribute(name) {
+        xml_parse(node, name)
+    } else {
+        Ok(default)
+    }
+}
+
+fn xml_string(node: Node, name: &str) -> String {
+    String::from(node.attribute(name).unwrap_or(""))
+}
+
+fn xml_child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn xml_properties(node: Node) -> Result<TiledProperties, TiledError> {
+    let mut properties = HashMap::new();
+    if let Some(list) = xml_child(node, "properties") {
+        for property in list
+            .children()
+            .filter(|child| child.has_tag_name("property"))
+        {
+            // multi-line string values are stored as the element's text instead of an attribute
+            let value = match property.attribute("value") {
+                Some(value) => value,
+                None => property.text().unwrap_or(""),
+            };
+            let property_type = property.attribute("type").unwrap_or("string");
+            if let Some(value) = parse_property(property_type, value)? {
+                properties.insert(xml_string(property, "name"), value);
+            }
+        }
+    }
+    Ok(properties)
+}
+
+fn parse_tmx(text: &str, base_dir: &Path) -> Result<RawMap, TiledError> {
+    let document = Document::parse(text)?;
+    let root = document.root_element();
+    if !root.has_tag_name("map") {
+        return Err(TiledError::BadFile(String::from("Missing <map> element")));
+    }
+    if root.attribute("orientation").unwrap_or("orthogonal") != "orthogonal" {
+        return Err(TiledError::BadFile(String::from(
+            "Only orthogonal maps are supported",
+        )));
+    }
+    if xml_parse_or(root, "infinite", 0u8)? != 0 {
+        return Err(TiledError::BadFile(String::from(
+            "Infinite maps are not supported",
+        )));
+    }
+
+    let mut map = RawMap {
+        width: xml_parse(root, "width")?,
+        height: xml_parse(root, "height")?,
+        tile_width: xml_parse(root, "tilewidth")?,
+        tile_height: xml_parse(root, "tileheight")?,
+        tilesets: Vec::new(),
+        layers: Vec::new(),
+        properties: xml_properties(root)?,
+    };
+    for child in root.children().filter(|child| child.is_element()) {
+        if child.has_tag_name("tileset") {
+            map.tilesets.push(xml_tileset(child, base_dir)?);
+        } else {
+            xml_layers(child, true, Vector2::new(1.0, 1.0), &mut map.layers)?;
+        }
+    }
+    Ok(map)
+}
+
+fn xml_tileset(node: Node, base_dir: &Path) -> Result<RawTileset, TiledError> {
+    let first_gid = xml_parse(node, "firstgid")?;
+    match node.attribute("source") {
+        Some(source) => {
+            let path = base_dir.join(source);
+            let text = std::fs::read_to_string(&path)?;
+            let tileset_dir = path.parent().unwrap_or_else(|| Path::new(""));
+            if is_json_path(&path) {
+                json_tileset_contents(&serde_json::from_str(&text)?, first_gid, tileset_dir)
+            } else {
+                let document = Document::parse(&text)?;
+                xml_tileset_contents(document.root_element(), first_gid, tileset_dir)
+            }
+        }
+        None => xml_tileset_contents(node, first_gid, base_dir),
+    }
+}
+
+fn xml_tileset_contents(
+    node: Node,
+    first_gid: u32,
+    base_dir: &Path,
+) -> Result<RawTileset, TiledError> {
+    if !node.has_tag_name("tileset") {
+        return Err(TiledError::BadFile(String::from(
+            "Missing <tileset> element",
+        )));
+    }
+    let image = match xml_child(node, "image") {
+        Some(image) => image,
+        None => {
+            return Err(TiledError::BadFile(String::from(
+                "Tilesets made from a collection of images are not supported",
+            )))
+        }
+    };
+
+    let mut animations = Vec::new();
+    for tile in node.children().filter(|child| child.has_tag_name("tile")) {
+        if let Some(animation) = xml_child(tile, "animation") {
+            let frames: Vec<Node> = animation
+                .children()
+                .filter(|child| child.has_tag_name("frame"))
+                .collect();
+            let frame_ids = frames
+                .iter()
+                .map(|frame| xml_parse(*frame, "tileid"))
+                .collect::<Result<Vec<u32>, TiledError>>()?;
+            let duration = match frames.first() {
+                Some(frame) => xml_parse(*frame, "duration")?,
+                None => 0,
+            };
+            animations.push((xml_parse(tile, "id")?, frame_ids, duration));
+        }
+    }
+
+    Ok(RawTileset {
+        first_gid,
+        tile_width: xml_parse(node, "tilewidth")?,
+        tile_height: xml_parse(node, "tileheight")?,
+        spacing: xml_parse_or(node, "spacing", 0)?,
+        margin: xml_parse_or(node, "margin", 0)?,
+        columns: xml_parse(node, "columns")?,
+        tile_count: xml_parse(node, "tilecount")?,
+        image: base_dir.join(xml_string(image, "source")),
+        animations,
+    })
+}
+
+fn xml_layers(
+    node: Node,
+    parent_visible: bool,
+    parent_parallax: Vector2,
+    layers: &mut Vec<RawLayer>,
+) -> Result<(), TiledError> {
+    let visible = parent_visible && xml_parse_or(node, "visible", 1u8)? != 0;
+    let parallax = Vector2::new(
+        parent_parallax.x * xml_parse_or(node, "parallaxx", 1.0)?,
+        parent_parallax.y * xml_parse_or(node, "parallaxy", 1.0)?,
+    );
+    match node.tag_name().name() {
+        "layer" => {
+            let data = match xml_child(node, "data") {
+                Some(data) => data,
+                None => {
+                    return Err(TiledError::BadFile(String::from(
+                        "Tile layer is missing <data>",
+                    )))
+                }
+            };
+            if xml_child(data, "chunk").is_some() {
+                return Err(TiledError::BadFile(String::from(
+                    "Infinite maps are not supported",
+                )));
+            }
+            let gids = match data.attribute("encoding") {
+                Some("csv") => parse_csv(data.text().unwrap_or(""))?,
+                Some("base64") => {
+                    decode_gids(data.text().unwrap_or(""), data.attribute("compression"))?
+                }
+                Some(other) => {
+                    return Err(TiledError::BadFile(format!(
+                        "Unsupported tile layer encoding: {}",
+                        other
+                    )))
+                }
+                None => data
+                    .children()
+                    .filter(|child| child.has_tag_name("tile"))
+                    .map(|tile| xml_parse_or(tile, "gid", 0))
+                    .collect::<Result<Vec<u32>, TiledError>>()?,
+            };
+            layers.push(RawLayer::Tiles {
+                name: xml_string(node, "name"),
+                visible,
+                parallax,
+                gids,
+            });
+        }
+        "objectgroup" => {
+            let name = xml_string(node, "name");
+            let objects = node
+                .children()
+                .filter(|child| child.has_tag_name("object"))
+                .map(|object| xml_object(object, &name, visible))
+                .collect::<Result<Vec<_>, TiledError>>()?;
+            layers.push(RawLayer::Objects(objects));
+        }
+        "group" => {
+            for child in node.children().filter(|child| child.is_element()) {
+                xml_layers(child, visible, parallax, layers)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn xml_object(
+    node: Node,
+    layer: &str,
+    layer_visible: bool,
+) -> Result<(TiledObject, Option<u32>), TiledError> {
+    let shape = if xml_child(node, "ellipse").is_some() {
+        TiledObjectShape::Ellipse
+    } else if xml_child(node, "point").is_some() {
+        TiledObjectShape::Point
+    } else if let Some(polygon) = xml_child(node, "polygon") {
+        TiledObjectShape::Polygon(parse_points(polygon.attribute("points").unwrap_or(""))?)
+    } else if let Some(polyline) = xml_child(node, "polyline") {
+        TiledObjectShape::Polyline(parse_points(polyline.attribute("points").unwrap_or(""))?)
+    } else {
+        TiledObjectShape::Rectangle
+    };
+    let class = node
+        .attribute("class")
+        .or_else(|| node.attribute("type"))
+        .unwrap_or("");
+    let gid = if node.has_attribute("gid") {
+        Some(xml_parse(node, "gid")?)
+    } else {
+        None
+    };
+    let object = TiledObject {
+        id: xml_parse_or(node, "id", 0)?,
+        name: xml_string(node, "name"),
+        class: String::from(class),
+        layer: String::from(layer),
+        x: xml_parse_or(node, "x", 0.0)?,
+        y: xml_parse_or(node, "y", 0.0)?,
+        width: xml_parse_or(node, "width", 0.0)?,
+        height: xml_parse_or(node, "height", 0.0)?,
+        rotation: xml_parse_or(node, "rotation", 0.0)?,
+        visible: layer_visible && xml_parse_or(node, "visible", 1u8)? != 0,
+        shape,
+        tile: None,
+        properties: xml_properties(node)?,
+    };
+    Ok((object, gid))
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn json_u32(value: &Value, field: &str) -> Result<u32, TiledError> {
+    match value.get(field).and_then(Value::as_u64) {
+        Some(n) if n <= u32::MAX as u64 => Ok(n as u32),
+        _ => Err(TiledError::BadFile(format!(
+            "Missing or invalid \"{}\" field",
+            field
+        ))),
+    }
+}
+
+fn json_u32_or(value: &Value, field: &str, default: u32) -> Result<u32, TiledError> {
+    if value.get(field).is_some() {
+        json_u32(value, field)
+    } else {
+        Ok(default)
+    }
+}
+
+fn json_f32_or(value: &Value, field: &str, default: f32) -> f32 {
+    value
+        .get(field)
+        .and_then(Value::as_f64)
+        .map_or(default, |n| n as f32)
+}
+
+fn json_bool_or(value: &Value, field: &str, default: bool) -> bool {
+    value.get(field).and_then(Value::as_bool).unwrap_or(default)
+}
+
+fn json_str<'a>(value: &'a Value, field: &str) -> &'a str {
+    value.get(field).and_then(Value::as_str).unwrap_or("")
+}
+
+fn json_array<'a>(value: &'a Value, field: &str) -> &'a [Value] {
+    value
+        .get(field)
+        .and_then(Value::as_array)
+        .map_or(&[], |array| array.as_slice())
+}
+
+fn json_properties(value: &Value) -> Result<TiledProperties, TiledError> {
+    let mut properties = HashMap::new();
+    for property in json_array(value, "properties") {
+        let property_type = match json_str(property, "type") {
+            "" => "string",
+            property_type => property_type,
+        };
+        let value = match property.get("value") {
+            Some(Value::String(value)) => value.clone(),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        };
+        if let Some(value) = parse_property(property_type, &value)? {
+            properties.insert(String::from(json_str(property, "name")), value);
+        }
+    }
+    Ok(properties)
+}
+
+fn parse_json(text: &str, base_dir: &Path) -> Result<RawMap, TiledError> {
+    let root: Value = serde_json::from_str(text)?;
+    if !matches!(json_str(&root, "orientation"), "" | "orthogonal") {
+        return Err(TiledError::BadFile(String::from(
+            "Only orthogonal maps are supported",
+        )));
+    }
+    if json_bool_or(&root, "infinite", false) {
+        return Err(TiledError::BadFile(String::from(
+            "Infinite maps are not supported",
+        )));
+    }
+
+    let mut map = RawMap {
+        width: json_u32(&root, "width")?,
+        height: json_u32(&root, "height")?,
+        tile_width: json_u32(&root, "tilewidth")?,
+        tile_height: json_u32(&root, "tileheight")?,
+        tilesets: Vec::new(),
+        layers: Vec::new(),
+        properties: json_properties(&root)?,
+    };
+    for tileset in json_array(&root, "tilesets") {
+        map.tilesets.push(json_tileset(tileset, base_dir)?);
+    }
+    for layer in json_array(&root, "layers") {
+        json_layers(layer, true, Vector2::new(1.0, 1.0), &mut map.layers)?;
+    }
+    Ok(map)
+}
+
+fn json_tileset(value: &Value, base_dir: &Path) -> Result<RawTileset, TiledError> {
+    let first_gid = json_u32(value, "firstgid")?;
+    match value.get("source").and_then(Value::as_str) {
+        Some(source) => {
+            let path = base_dir.join(source);
+            let text = std::fs::read_to_string(&path)?;
+            let tileset_dir = path.parent().unwrap_or_else(|| Path::new(""));
+            if is_json_path(&path) {
+                json_tileset_contents(&serde_json::from_str(&text)?, first_gid, tileset_dir)
+            } else {
+                let document = Document::parse(&text)?;
+                xml_tileset_contents(document.root_element(), first_gid, tileset_dir)
+            }
+        }
+        None => json_tileset_contents(value, first_gid, base_dir),
+    }
+}
+
+fn json_tileset_contents(
+    value: &Value,
+    first_gid: u32,
+    base_dir: &Path,
+) -> Result<RawTileset, TiledError> {
+    let image = match value.get("image").and_then(Value::as_str) {
+        Some(image) => image,
+        None => {
+            return Err(TiledError::BadFile(String::from(
+                "Tilesets made from a collection of images are not supported",
+            )))
+        }
+    };
+
+    let mut animations = Vec::new();
+    for tile in json_array(value, "tiles") {
+        let frames = json_array(tile, "animation");
+        if frames.is_empty() {
+            continue;
+        }
+        let frame_ids = frames
+            .iter()
+            .map(|frame| json_u32(frame, "tileid"))
+            .collect::<Result<Vec<u32>, TiledError>>()?;
+        animations.push((
+            json_u32(tile, "id")?,
+            frame_ids,
+            json_u32(&frames[0], "duration")?,
+        ));
+    }
+
+    Ok(RawTileset {
+        first_gid,
+        tile_width: json_u32(value, "tilewidth")?,
+        tile_height: json_u32(value, "tileheight")?,
+        spacing: json_u32_or(value, "spacing", 0)?,
+        margin: json_u32_or(value, "margin", 0)?,
+        columns: json_u32(value, "columns")?,
+        tile_count: json_u32(value, "tilecount")?,
+        image: base_dir.join(image),
+        animations,
+    })
+}
+
+fn json_layers(
+    value: &Value,
+    parent_visible: bool,
+    parent_parallax: Vector2,
+    layers: &mut Vec<RawLayer>,
+) -> Result<(), TiledError> {
+    let visible = parent_visible && json_bool_or(value, "visible", true);
+    let parallax = Vector2::new(
+        parent_parallax.x * json_f32_or(value, "parallaxx", 1.0),
+        parent_parallax.y * json_f32_or(value, "parallaxy", 1.0),
+    );
+    match json_str(value, "type") {
+        "tilelayer" => {
+            if value.get("chunks").is_some() {
+                return Err(TiledError::BadFile(String::from(
+                    "Infinite maps are not supported",
+                )));
+            }
+            let gids = match value.get("data") {
+                Some(Value::Array(gids)) => gids
+                    .iter()
+                    .map(|gid| match gid.as_u64() {
+                        Some(gid) if gid <= u32::MAX as u64 => Ok(gid as u32),
+                        _ => Err(TiledError::BadFile(format!("Invalid tile ID: {}", gid))),
+                    })
+                    .collect::<Result<Vec<u32>, TiledError>>()?,
+                Some(Value::String(text)) if json_str(value, "encoding") == "base64" => {
+                    decode_gids(text, value.get("compression").and_then(Value::as_str))?
+                }
+                _ => {
+                    return Err(TiledError::BadFile(String::from(
+                        "Missing or unsupported tile layer data",
+                    )))
+                }
+            };
+            layers.push(RawLayer::Tiles {
+                name: String::from(json_str(value, "name")),
+                visible,
+                parallax,
+                gids,
+            });
+        }
+        "objectgroup" => {
+            let name = json_str(value, "name");
+            let objects = json_array(value, "objects")
+                .iter()
+                .map(|object| json_object(object, name, visible))
+                .collect::<Result<Vec<_>, TiledError>>()?;
+            layers.push(RawLayer::Objects(objects));
+        }
+        "group" => {
+            for layer in json_array(value, "layers") {
+                json_layers(layer, visible, parallax, layers)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn json_points(value: &Value, field: &str) -> Vec<Vector2> {
+    json_array(value, field)
+        .iter()
+        .map(|point| Vector2::new(json_f32_or(point, "x", 0.0), json_f32_or(point, "y", 0.0)))
+        .collect()
+}
+
+fn json_object(
+    value: &Value,
+    layer: &str,
+    layer_visible: bool,
+) -> Result<(TiledObject, Option<u32>), TiledError> {
+    let shape = if json_bool_or(value, "ellipse", false) {
+        TiledObjectShape::Ellipse
+    } else if json_bool_or(value, "point", false) {
+        TiledObjectShape::Point
+    } else if value.get("polygon").is_some() {
+        TiledObjectShape::Polygon(json_points(value, "polygon"))
+    } else if value.get("polyline").is_some() {
+        TiledObjectShape::Polyline(json_points(value, "polyline"))
+    } else {
+        TiledObjectShape::Rectangle
+    };
+    let class = match json_str(value, "class") {
+        "" => json_str(value, "type"),
+        class => class,
+    };
+    let gid = if value.get("gid").is_some() {
+        Some(json_u32(value, "gid")?)
+    } else {
+        None
+    };
+    let object = TiledObject {
+        id: json_u32_or(value, "id", 0)?,
+        name: String::from(json_str(value, "name")),
+        class: String::from(class),
+        layer: String::from(layer),
+        x: json_f32_or(value, "x", 0.0),
+        y: json_f32_or(value, "y", 0.0),
+        width: json_f32_or(value, "width", 0.0),
+        height: json_f32_or(value, "height", 0.0),
+        rotation: json_f32_or(value, "rotation", 0.0),
+        visible: layer_visible && json_bool_or(value, "visible", true),
+        shape,
+        tile: None,
+        properties: json_properties(value)?,
+    };
+    Ok((object, gid))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Writes a 8x8 tileset image with four 4x4 tiles, each filled with its tile index plus one.
+    fn write_tileset_image(dir: &Path, name: &str) {
+        let mut bitmap = Bitmap::new(8, 8).unwrap();
+        for index in 0..4 {
+            let (x, y) = ((index % 2) * 4, (index / 2) * 4);
+            bitmap.filled_rect(x, y, x + 3, y + 3, index as u8 + 1);
+        }
+        let palette = Palette::new_vga_palette().unwrap();
+        bitmap.to_pcx_file(&dir.join(name), &palette).unwrap();
+    }
+
+    const TMX_MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="4" tileheight="4" infinite="0">
+ <properties>
+  <property name="music" value="level1.ogg"/>
+  <property name="gravity" type="float" value="9.8"/>
+ </properties>
+ <tileset firstgid="1" name="tiles" tilewidth="4" tileheight="4" tilecount="4" columns="2">
+  <image source="tiles.pcx" width="8" height="8"/>
+  <tile id="2">
+   <animation>
+    <frame tileid="2" duration="250"/>
+    <frame tileid="3" duration="250"/>
+   </animation>
+  </tile>
+ </tileset>
+ <tileset firstgid="5" source="more.tsx"/>
+ <layer id="1" name="ground" width="2" height="2">
+  <data encoding="csv">
+1,2,
+3,0
+</data>
+ </layer>
+ <group id="2" name="group" parallaxx="0.5">
+  <layer id="3" name="details" width="2" height="2" visible="0" parallaxx="0.5" parallaxy="0.25">
+   <data encoding="base64" compression="zlib">eJxjZGBgYGKAACDdAAAAwACG</data>
+  </layer>
+  <layer id="4" name="more" width="2" height="2">
+   <data>
+    <tile gid="5"/>
+    <tile gid="8"/>
+    <tile/>
+    <tile gid="3221225477"/>
+   </data>
+  </layer>
+ </group>
+ <objectgroup id="5" name="things">
+  <object id="1" name="start" type="spawn" x="4" y="6.5">
+   <properties>
+    <property name="health" type="int" value="10"/>
+    <property name="boss" type="bool" value="true"/>
+    <property name="tint" type="color" value="#ff102030"/>
+    <property name="target" type="object" value="2"/>
+    <property name="notes">Line one
+Line two</property>
+   </properties>
+   <point/>
+  </object>
+  <object id="2" name="area" class="trigger" x="0" y="0" width="8" height="4">
+   <polygon points="0,0 8,0 4,4"/>
+  </object>
+  <object id="3" gid="6" x="2" y="8" width="4" height="4"/>
+ </objectgroup>
+</map>
+"##;
+
+    const TSX_TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="more" tilewidth="4" tileheight="4" tilecount="4" columns="2">
+ <image source="more.pcx" width="8" height="8"/>
+</tileset>
+"#;
+
+    const JSON_MAP: &str = r##"{
+ "orientation": "orthogonal", "width": 2, "height": 2, "tilewidth": 4, "tileheight": 4,
+ "infinite": false,
+ "properties": [
+  { "name": "music", "type": "string", "value": "level1.ogg" },
+  { "name": "gravity", "type": "float", "value": 9.8 }
+ ],
+ "tilesets": [
+  { "firstgid": 1, "name": "tiles", "tilewidth": 4, "tileheight": 4, "tilecount": 4,
+    "columns": 2, "image": "tiles.pcx", "imagewidth": 8, "imageheight": 8,
+    "tiles": [
+     { "id": 2, "animation": [ { "tileid": 2, "duration": 250 }, { "tileid": 3, "duration": 250 } ] }
+    ] },
+  { "firstgid": 5, "source": "more.tsx" }
+ ],
+ "layers": [
+  { "type": "tilelayer", "name": "ground", "width": 2, "height": 2, "data": [1, 2, 3, 0] },
+  { "type": "group", "name": "group", "parallaxx": 0.5, "layers": [
+   { "type": "tilelayer", "name": "details", "width": 2, "height": 2, "visible": false,
+     "parallaxx": 0.5, "parallaxy": 0.25, "encoding": "base64",
+     "data": "AQAAAAIAAAAAAAAAAgAAgA==" },
+   { "type": "tilelayer", "name": "more", "width": 2, "height": 2,
+     "data": [5, 8, 0, 3221225477] }
+  ] },
+  { "type": "objectgroup", "name": "things", "objects": [
+   { "id": 1, "name": "start", "type": "spawn", "x": 4, "y": 6.5, "width": 0, "height": 0,
+     "rotation": 0, "visible": true, "point": true,
+     "properties": [
+      { "name": "health", "type": "int", "value": 10 },
+      { "name": "boss", "type": "bool", "value": true },
+      { "name": "tint", "type": "color", "value": "#ff102030" },
+      { "name": "target", "type": "object", "value": 2 },
+      { "name": "notes", "type": "string", "value": "Line one\nLine two" }
+     ] },
+   { "id": 2, "name": "area", "class": "trigger", "x": 0, "y": 0, "width": 8, "height": 4,
+     "polygon": [ { "x": 0, "y": 0 }, { "x": 8, "y": 0 }, { "x": 4, "y": 4 } ] },
+   { "id": 3, "gid": 6, "name": "", "x": 2, "y": 8, "width": 4, "height": 4 }
+  ] }
+ ]
+}"##;
+
+    fn write_map_files(dir: &Path) {
+        write_tileset_image(dir, "tiles.pcx");
+        write_tileset_image(dir, "more.pcx");
+        std::fs::write(dir.join("map.tmx"), TMX_MAP).unwrap();
+        std::fs::write(dir.join("map.json"), JSON_MAP).unwrap();
+        std::fs::write(dir.join("more.tsx"), TSX_TILESET).unwrap();
+    }
+
+    fn check_map(tiled: &TiledMap) {
+        let map = &tiled.map;
+        assert_eq!(2, map.width());
+        assert_eq!(4, map.tile_width());
+        assert_eq!(8, map.tileset().unwrap().len());
+        assert_eq!(Some(&Rect::new(4, 12, 4, 4)), map.tileset().unwrap().get(7));
+        assert_some!(&tiled.palette);
+        assert_eq!(
+            Some(&TiledPropertyValue::String(String::from("level1.ogg"))),
+            tiled.properties.get("music")
+        );
+        assert_eq!(
+            Some(9.8),
+            tiled
+                .properties
+                .get("gravity")
+                .and_then(|value| value.as_float())
+        );
+
+        assert_eq!(3, map.num_layers());
+        let ground = map.layer(0).unwrap();
+        assert_eq!("ground", ground.name);
+        assert_eq!(None, ground.transparent_color);
+        assert_eq!(
+            &[Tile::new(0), Tile::new(1), Tile::new(2), Tile::EMPTY],
+            ground.tiles()
+        );
+        let details = map.layer(1).unwrap();
+        assert!(!details.visible);
+        assert_eq!(Some(0), details.transparent_color);
+        assert_eq!(Vector2::new(0.25, 0.25), details.parallax);
+        assert_eq!(
+            &[
+                Tile::new(0),
+                Tile::new(1),
+                Tile::EMPTY,
+                Tile::with_flags(1, TILE_FLAG_FLIP_X)
+            ],
+            details.tiles()
+        );
+        let more = map.layer(2).unwrap();
+        assert!(more.visible);
+        assert_eq!(Vector2::new(0.5, 1.0), more.parallax);
+        assert_eq!(
+            &[
+                Tile::new(4),
+                Tile::new(7),
+                Tile::EMPTY,
+                Tile::with_flags(4, TILE_FLAG_FLIP_X | TILE_FLAG_FLIP_Y)
+            ],
+            more.tiles()
+        );
+
+        let mut map = map.clone();
+        assert_eq!(2, map.animated_index(2));
+        map.update(0.25);
+        assert_eq!(3, map.animated_index(2));
+
+        assert_eq!(3, tiled.objects.len());
+        let start = tiled.object_named("start").unwrap();
+        assert_eq!("spawn", start.class);
+        assert_eq!("things", start.layer);
+        assert_eq!(6.5, start.y);
+        assert_eq!(TiledObjectShape::Point, start.shape);
+        assert_eq!(
+            Some(10),
+            start.property("health").and_then(|value| value.as_int())
+        );
+        assert_eq!(
+            Some(true),
+            start.property("boss").and_then(|value| value.as_bool())
+        );
+        assert_eq!(
+            Some(&TiledPropertyValue::Color(0xff102030)),
+            start.property("tint")
+        );
+        assert_eq!(
+            Some(&TiledPropertyValue::Object(2)),
+            start.property("target")
+        );
+        assert_eq!(
+            Some("Line one\nLine two"),
+            start.property("notes").and_then(|value| value.as_str())
+        );
+        let area = tiled.objects_of_class("trigger").next().unwrap();
+        assert_eq!(
+            TiledObjectShape::Polygon(vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(8.0, 0.0),
+                Vector2::new(4.0, 4.0)
+            ]),
+            area.shape
+        );
+        assert_eq!(Some(Tile::new(5)), tiled.objects[2].tile);
+        assert_eq!(None, start.tile);
+    }
+
+    #[test]
+    pub fn load_tmx_file() -> Result<(), TiledError> {
+        let tmp_dir = TempDir::new()?;
+        write_map_files(tmp_dir.path());
+        let tiled = TiledMap::load_file(&tmp_dir.path().join("map.tmx"))?;
+        check_map(&tiled);
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_json_file() -> Result<(), TiledError> {
+        let tmp_dir = TempDir::new()?;
+        write_map_files(tmp_dir.path());
+        let tiled = TiledMap::load_file(&tmp_dir.path().join("map.json"))?;
+        check_map(&tiled);
+        Ok(())
+    }
+
+    #[test]
+    pub fn load_bad_maps() {
+        let dir = Path::new("");
+        assert_matches!(
+            TiledMap::load_tmx("<map", dir),
+            Err(TiledError::XmlError(..))
+        );
+        assert_matches!(
+            TiledMap::load_json("{", dir),
+            Err(TiledError::JsonError(..))
+        );
+        assert_matches!(
+            TiledMap::load_tmx(
+                r#"<map orientation="isometric" width="1" height="1" tilewidth="4" tileheight="4"/>"#,
+                dir
+            ),
+            Err(TiledError::BadFile(..))
+        );
+        assert_matches!(
+            TiledMap::load_json(
+                r#"{ "width": 1, "height": 1, "tilewidth": 4, "tileheight": 4, "infinite": true }"#,
+                dir
+            ),
+            Err(TiledError::BadFile(..))
+        );
+        // a tile layer with the wrong number of tiles
+        assert_matches!(
+            TiledMap::load_tmx(
+                r#"<map width="2" height="1" tilewidth="4" tileheight="4">
+                    <layer name="a"><data encoding="csv">0</data></layer>
+                   </map>"#,
+                dir
+            ),
+            Err(TiledError::BadFile(..))
+        );
+        // a tile that is not in any tileset
+        assert_matches!(
+            TiledMap::load_json(
+                r#"{ "width": 1, "height": 1, "tilewidth": 4, "tileheight": 4,
+                     "layers": [ { "type": "tilelayer", "name": "a", "data": [1] } ] }"#,
+                dir
+            ),
+            Err(TiledError::BadFile(..))
+        );
+    }
+
+    #[test]
+    pub fn decoding_tile_data() {
+        assert_eq!(
+            vec![1, 2, 0, 0x80000002],
+            decode_gids("AQAAAAIA\nAAAAAAAAAgAAgA==", None).unwrap()
+        );
+        assert_eq!(
+            vec![1, 2, 0, 0x80000002],
+            decode_gids("eJxjZGBgYGKAACDdAAAAwACG", Some("zlib")).unwrap()
+        );
+        assert_matches!(
+            decode_gids("AQAAAAIAAAAAAAAAAgAAgA==", Some("zstd")),
+            Err(TiledError::BadFile(..))
+        );
+        assert_matches!(decode_gids("AQAA!", None), Err(TiledError::BadFile(..)));
+        assert_eq!(vec![1, 22, 3], parse_csv(" 1,22,\n3\n").unwrap());
+        assert_err!(parse_csv("1,x"));
+    }
+}
@@ -0,0 +1,607 @@
+use std::collections::HashMap;
+
+use crate::graphics::*;
+use crate::math::*;
+
+/// Flag for a [Tile] to be drawn flipped horizontally.
+pub const TILE_FLAG_FLIP_X: u8 = 0x01;
+/// Flag for a [Tile] to be drawn flipped vertically.
+pub const TILE_FLAG_FLIP_Y: u8 = 0x02;
+/// Flag for a [Tile] that is solid, for games to use for collision checks. Not used for rendering.
+pub const TILE_FLAG_SOLID: u8 = 0x04;
+/// Flag for a [Tile] that is a one-way platform, which can only be collided with from above. Not
+/// used for rendering.
+pub const TILE_FLAG_ONE_WAY: u8 = 0x08;
+
+/// A single tile within a [TileLayer].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Tile {
+    /// The index of the tile within the [TileMap]'s tileset, or a negative number for no tile.
+    pub index: i32,
+    /// Any combination of the `TILE_FLAG_` constants. Bits not used by those constants can be
+    /// used for anything a game needs.
+    pub flags: u8,
+}
+
+impl Tile {
+    /// A tile that is not drawn.
+    pub const EMPTY: Tile = Tile {
+        index: -1,
+        flags: 0,
+    };
+
+    /// Creates a new tile using the given tileset index, with no flags set.
+    #[inline]
+    pub fn new(index: i32) -> Tile {
+        Tile { index, flags: 0 }
+    }
+
+    /// Creates a new tile using the given tileset index and flags.
+    #[inline]
+    pub fn with_flags(index: i32, flags: u8) -> Tile {
+        Tile { index, flags }
+    }
+
+    /// Returns true if this tile is not drawn.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.index < 0
+    }
+
+    /// Returns true if all of the given flags are set on this tile.
+    #[inline]
+    pub fn has_flags(&self, flags: u8) -> bool {
+        self.flags & flags == flags
+    }
+}
+
+impl Default for Tile {
+    fn default() -> Self {
+        Tile::EMPTY
+    }
+}
+
+/// A single layer of tiles within a [TileMap]. All layers in a map have the same size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileLayer {
+    /// The name of the layer, which can be used to find it with [TileMap::layer_index].
+    pub name: String,
+    /// How far this layer scrolls relative to the camera. 1.0 scrolls with the camera, values
+    /// below 1.0 scroll slower (for distant backgrounds) and 0.0 does not scroll at all.
+    pub parallax: Vector2,
+    /// The color in the tileset which is not drawn for this layer's tiles, or `None` if the tiles
+    /// are drawn solid (usually the case for the bottom-most layer).
+    pub transparent_color: Option<u8>,
+    /// Whether the layer is drawn.
+    pub visible: bool,
+    width: u32,
+    height: u32,
+    tiles: Box<[Tile]>,
+}
+
+impl TileLayer {
+    fn new(name: &str, width: u32, height: u32) -> TileLayer {
+        TileLayer {
+            name: String::from(name),
+            parallax: Vector2::new(1.0, 1.0),
+            transparent_color: None,
+            visible: true,
+            width,
+            height,
+            tiles: vec![Tile::EMPTY; (width * height) as usize].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the width of the layer, in tiles.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the layer, in tiles.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns all of the layer's tiles, in rows from top to bottom.
+    #[inline]
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    /// Returns all of the layer's tiles, in rows from top to bottom.
+    #[inline]
+    pub fn tiles_mut(&mut self) -> &mut [Tile] {
+        &mut self.tiles
+    }
+
+    #[inline]
+    fn index_of(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            Some((y as u32 * self.width + x as u32) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the tile at the given tile coordinates, or `None` if they are outside of the layer.
+    #[inline]
+    pub fn get(&self, x: i32, y: i32) -> Option<&Tile> {
+        self.index_of(x, y).map(|index| &self.tiles[index])
+    }
+
+    /// Returns the tile at the given tile coordinates, or `None` if they are outside of the layer.
+    #[inline]
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut Tile> {
+        self.index_of(x, y).map(|index| &mut self.tiles[index])
+    }
+
+    /// Changes the tile at the given tile coordinates. Returns false if they are outside of the
+    /// layer, in which case nothing is changed.
+    pub fn set(&mut self, x: i32, y: i32, tile: Tile) -> bool {
+        match self.get_mut(x, y) {
+            Some(existing) => {
+                *existing = tile;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Changes all of the tiles in the given region (in tile coordinates) to the given tile. Any
+    /// part of the region outside of the layer is ignored.
+    pub fn fill(&mut self, region: &Rect, tile: Tile) {
+        let mut region = *region;
+        if !region.clamp_to(&Rect::new(0, 0, self.width, self.height)) {
+            return;
+        }
+        for y in region.y..=region.bottom() {
+            for x in region.x..=region.right() {
+                self.set(x, y, tile);
+            }
+        }
+    }
+}
+
+/// An animated tile. Whenever the first frame's tileset index appears in a [TileMap], the
+/// tileset index of the current frame is drawn instead.
+#[derive(Debug, Clone, PartialEq)]
+struct TileAnimation {
+    frames: Vec<i32>,
+    frame_duration: f32,
+    current: usize,
+    accumulator: f32,
+}
+
+/// A map made up of one or more layers of tiles, all of the same size, which are drawn using
+/// the tiles in a tileset [BitmapAtlas]. Layers are drawn in order, so the first layer is the
+/// bottom-most. Tiles can be animated, cycling through a sequence of tileset indices over time.
+///
+/// Rendering is done through a [Camera], only drawing the tiles that are visible within its
+/// viewport. Each layer has its own parallax factor controlling how fast it scrolls relative to
+/// the camera. The camera's zoom is not applied; tiles are always drawn at their original size.
+///
+/// ```
+/// use libretrogd::graphics::*;
+/// use libretrogd::math::*;
+///
+/// let tileset = BitmapAtlas::new(Bitmap::new(64, 16).unwrap());
+/// let mut map = TileMap::new(100, 50, 16, 16);
+/// map.set_tileset(Some(tileset));
+/// map.tileset_mut().unwrap().add_grid(16, 16).unwrap();
+///
+/// let background = map.add_layer("background");
+/// map.layer_mut(background).unwrap().parallax = Vector2::new(0.5, 0.5);
+/// let ground = map.add_layer("ground");
+/// map.layer_mut(ground).unwrap().transparent_color = Some(0);
+/// map.layer_mut(ground).unwrap().set(3, 4, Tile::new(1));
+///
+/// // water, cycling through tiles 2 and 3
+/// map.add_animation(vec![2, 3], 0.25);
+///
+/// let mut screen = Bitmap::new(320, 200).unwrap();
+/// let mut camera = Camera::new(screen.full_bounds());
+/// camera.set_bounds(Some(map.bounds()));
+/// map.update(0.016);
+/// map.render(&mut screen, &camera);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMap {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    layers: Vec<TileLayer>,
+    tileset: Option<BitmapAtlas>,
+    animations: HashMap<i32, TileAnimation>,
+}
+
+impl TileMap {
+    /// Creates a new map with no layers and no tileset.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the map, in tiles
+    /// * `height`: the height of the map, in tiles
+    /// * `tile_width`: the width of each tile, in pixels
+    /// * `tile_height`: the height of each tile, in pixels
+    pub fn new(width: u32, height: u32, tile_width: u32, tile_height: u32) -> TileMap {
+        TileMap {
+            width,
+            height,
+            tile_width: tile_width.max(1),
+            tile_height: tile_height.max(1),
+            layers: Vec::new(),
+            tileset: None,
+            animations: HashMap::new(),
+        }
+    }
+
+    /// Returns the width of the map, in tiles.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the map, in tiles.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the width of each tile, in pixels.
+    #[inline]
+    pub fn tile_width(&self) -> u32 {
+        self.tile_width
+    }
+
+    /// Returns the height of each tile, in pixels.
+    #[inline]
+    pub fn tile_height(&self) -> u32 {
+        self.tile_height
+    }
+
+    /// Returns the area covered by the whole map, in world (pixel) coordinates. Useful with
+    /// [Camera::set_bounds] to keep the camera from showing anything outside of the map.
+    #[inline]
+    pub fn bounds(&self) -> Rect {
+        Rect::new(
+            0,
+            0,
+            self.width * self.tile_width,
+            self.height * self.tile_height,
+        )
+    }
+
+    /// Returns the tileset that tiles are drawn from, if one has been set.
+    #[inline]
+    pub fn tileset(&self) -> Option<&BitmapAtlas> {
+        self.tileset.as_ref()
+    }
+
+    /// Returns the tileset that tiles are drawn from, if one has been set.
+    #[inline]
+    pub fn tileset_mut(&mut self) -> Option<&mut BitmapAtlas> {
+        self.tileset.as_mut()
+    }
+
+    /// Sets the tileset that tiles are drawn from. Each tile's index is the index of a region
+    /// within the atlas, and each region should be the same size as the map's tiles.
+    pub fn set_tileset(&mut self, tileset: Option<BitmapAtlas>) {
+        self.tileset = tileset;
+    }
+
+    /// Adds a new layer, with all of its tiles empty, on top of any existing layers.
+    ///
+    /// returns: the index of the new layer
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        self.layers
+            .push(TileLayer::new(name, self.width, self.height));
+        self.layers.len() - 1
+    }
+
+    /// Removes the layer with the given index, returning it. Layers above it move down by one.
+    pub fn remove_layer(&mut self, index: usize) -> Option<TileLayer> {
+        if index < self.layers.len() {
+            Some(self.layers.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of layers.
+    #[inline]
+    pub fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    #[inline]
+    pub fn layer(&self, index: usize) -> Option<&TileLayer> {
+        self.layers.get(index)
+    }
+
+    #[inline]
+    pub fn layer_mut(&mut self, index: usize) -> Option<&mut TileLayer> {
+        self.layers.get_mut(index)
+    }
+
+    /// Returns all of the layers, from the bottom-most up.
+    #[inline]
+    pub fn layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+
+    /// Returns the index of the first layer with the given name, if any.
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    /// Converts world (pixel) coordinates to the coordinates of the tile at that location. The
+    /// returned coordinates may be outside of the map.
+    #[inline]
+    pub fn world_to_tile(&self, world_x: i32, world_y: i32) -> (i32, i32) {
+        (
+            world_x.div_euclid(self.tile_width as i32),
+            world_y.div_euclid(self.tile_height as i32),
+        )
+    }
+
+    /// Converts tile coordinates to the world (pixel) coordinates of that tile's top-left corner.
+    #[inline]
+    pub fn tile_to_world(&self, x: i32, y: i32) -> (i32, i32) {
+        (x * self.tile_width as i32, y * self.tile_height as i32)
+    }
+
+    /// Returns the tile at the given world (pixel) coordinates on the given layer, or `None` if
+    /// the layer does not exist or the coordinates are outside of the map.
+    pub fn tile_at_world(&self, layer: usize, world_x: i32, world_y: i32) -> Option<&Tile> {
+        let (x, y) = self.world_to_tile(world_x, world_y);
+        self.layers.get(layer).and_then(|layer| layer.get(x, y))
+    }
+
+    /// Animates a tile, replacing any existing animation for the same tile. Wherever the first
+    /// frame's tileset index is used in the map, each of the frames are shown in turn instead,
+    /// as [TileMap::update] is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames`: the tileset indices to show, in order. Ignored if empty.
+    /// * `frame_duration`: how long each frame is shown for, in seconds
+    pub fn add_animation(&mut self, frames: Vec<i32>, frame_duration: f32) {
+        if let Some(&first) = frames.first() {
+            self.animations.insert(
+                first,
+                TileAnimation {
+                    frames,
+                    frame_duration,
+                    current: 0,
+                    accumulator: 0.0,
+                },
+            );
+        }
+    }
+
+    /// Removes the animation for the tile with the given tileset index (the first frame given to
+    /// [TileMap::add_animation]), returning true if it was removed.
+    pub fn remove_animation(&mut self, index: i32) -> bool {
+        self.animations.remove(&index).is_some()
+    }
+
+    /// Advances all tile animations by the given amount of time, in seconds.
+    pub fn update(&mut self, delta: f32) {
+        for animation in self.animations.values_mut() {
+            if animation.frame_duration <= 0.0 {
+                continue;
+            }
+            animation.accumulator += delta;
+            while animation.accumulator >= animation.frame_duration {
+                animation.accumulator -= animation.frame_duration;
+                animation.current = (animation.current + 1) % animation.frames.len();
+            }
+        }
+    }
+
+    /// Returns the tileset index that is currently drawn for the given tileset index, which is
+    /// different if it is animated.
+    #[inline]
+    pub fn animated_index(&self, index: i32) -> i32 {
+        match self.animations.get(&index) {
+            Some(animation) => animation.frames[animation.current],
+            None => index,
+        }
+    }
+
+    /// Draws all of the visible layers, in order, into the camera's viewport on the destination
+    /// bitmap. Nothing is drawn if no tileset has been set.
+    pub fn render(&self, dest: &mut Bitmap, camera: &Camera) {
+        for (index, layer) in self.layers.iter().enumerate() {
+            if layer.visible {
+                self.render_layer(dest, camera, index);
+            }
+        }
+    }
+
+    /// Draws a single layer into the camera's viewport on the destination bitmap, even if the
+    /// layer is not visible. Useful to draw other things (such as sprites) between layers.
+    /// Nothing is drawn if no tileset has been set.
+    pub fn render_layer(&self, dest: &mut Bitmap, camera: &Camera, index: usize) {
+        let (layer, tileset) = match (self.layers.get(index), &self.tileset) {
+            (Some(layer), Some(tileset)) => (layer, tileset),
+            _ => return,
+        };
+        let viewport = *camera.viewport();
+        if !dest.push_clip_region(&viewport) {
+            return;
+        }
+
+        // the world coordinates (adjusted for parallax) shown at the top-left of the viewport
+        let position = camera.position();
+        let shake = camera.shake_offset();
+        let scroll = Vector2::new(
+            position.x * layer.parallax.x - viewport.width as f32 * 0.5 - shake.x,
+            position.y * layer.parallax.y - viewport.height as f32 * 0.5 - shake.y,
+        )
+        .floor_to_int();
+
+        let (tile_width, tile_height) = (self.tile_width as i32, self.tile_height as i32);
+        let first_x = scroll.x.div_euclid(tile_width).max(0);
+        let first_y = scroll.y.div_euclid(tile_height).max(0);
+        let last_x = (scroll.x + viewport.width as i32 - 1)
+            .div_euclid(tile_width)
+            .min(self.width as i32 - 1);
+        let last_y = (scroll.y + viewport.height as i32 - 1)
+            .div_euclid(tile_height)
+            .min(self.height as i32 - 1);
+
+        for y in first_y..=last_y {
+            for x in first_x..=last_x {
+                let tile = layer.tiles[(y * self.width as i32 + x) as usize];
+                if tile.is_empty() {
+                    continue;
+                }
+                let region = match tileset.get(self.animated_index(tile.index) as usize) {
+                    Some(region) => region,
+                    None => continue,
+                };
+                let horizontal_flip = tile.has_flags(TILE_FLAG_FLIP_X);
+                let vertical_flip = tile.has_flags(TILE_FLAG_FLIP_Y);
+                let method = match layer.transparent_color {
+                    Some(transparent_color) => BlitMethod::TransparentFlipped {
+                        transparent_color,
+                        horizontal_flip,
+                        vertical_flip,
+                    },
+                    None => BlitMethod::SolidFlipped {
+                        horizontal_flip,
+                        vertical_flip,
+                    },
+                };
+                dest.blit_region(
+                    method,
+                    tileset.bitmap(),
+                    region,
+                    viewport.x + x * tile_width - scroll.x,
+                    viewport.y + y * tile_height - scroll.y,
+                );
+            }
+        }
+
+        dest.pop_clip_region();
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn new_map() -> TileMap {
+        // each tile in the tileset is a solid 4x4 block of its index plus one, with a single
+        // pixel of color 0 in the top-left corner, except for the last tile which is all solid
+        let mut bitmap = Bitmap::new(16, 4).unwrap();
+        for index in 0..4 {
+            bitmap.filled_rect(index * 4, 0, index * 4 + 3, 3, index as u8 + 1);
+            if index < 3 {
+                bitmap.set_pixel(index * 4, 0, 0);
+            }
+        }
+        let mut tileset = BitmapAtlas::new(bitmap);
+        tileset.add_grid(4, 4).unwrap();
+
+        let mut map = TileMap::new(8, 6, 4, 4);
+        map.set_tileset(Some(tileset));
+        map
+    }
+
+    #[test]
+    pub fn editing_layers() {
+        let mut map = new_map();
+        assert_eq!(Rect::new(0, 0, 32, 24), map.bounds());
+        let a = map.add_layer("a");
+        let b = map.add_layer("b");
+        assert_eq!(2, map.num_layers());
+        assert_eq!(Some(b), map.layer_index("b"));
+        assert_eq!(None, map.layer_index("c"));
+
+        let layer = map.layer_mut(a).unwrap();
+        assert!(layer.get(0, 0).unwrap().is_empty());
+        assert!(layer.set(7, 5, Tile::with_flags(2, TILE_FLAG_SOLID)));
+        assert!(!layer.set(8, 5, Tile::new(2)));
+        assert_eq!(None, layer.get(-1, 0));
+        layer.fill(&Rect::new(-2, -2, 4, 4), Tile::new(1));
+        assert_eq!(Some(&Tile::new(1)), layer.get(1, 1));
+        assert!(layer.get(2, 2).unwrap().is_empty());
+
+        assert_eq!((1, 2), map.world_to_tile(7, 8));
+        assert_eq!((-1, 0), map.world_to_tile(-1, 3));
+        assert_eq!((4, 8), map.tile_to_world(1, 2));
+        let tile = map.tile_at_world(a, 31, 23).unwrap();
+        assert!(tile.has_flags(TILE_FLAG_SOLID));
+        assert_eq!(None, map.tile_at_world(a, 32, 23));
+        assert_eq!(None, map.tile_at_world(5, 0, 0));
+
+        let removed = map.remove_layer(a).unwrap();
+        assert_eq!("a", removed.name);
+        assert_eq!(Some(0), map.layer_index("b"));
+    }
+
+    #[test]
+    pub fn animating_tiles() {
+        let mut map = new_map();
+        map.add_animation(vec![1, 2, 3], 0.5);
+        assert_eq!(1, map.animated_index(1));
+        assert_eq!(0, map.animated_index(0));
+        map.update(0.25);
+        assert_eq!(1, map.animated_index(1));
+        map.update(0.25);
+        assert_eq!(2, map.animated_index(1));
+        map.update(1.0);
+        assert_eq!(1, map.animated_index(1));
+        assert!(map.remove_animation(1));
+        map.update(0.5);
+        assert_eq!(1, map.animated_index(1));
+    }
+
+    #[test]
+    pub fn rendering() {
+        let mut map = new_map();
+        let bottom = map.add_layer("bottom");
+        map.layer_mut(bottom)
+            .unwrap()
+            .fill(&Rect::new(0, 0, 8, 6), Tile::new(3));
+        let top = map.add_layer("top");
+        let layer = map.layer_mut(top).unwrap();
+        layer.transparent_color = Some(0);
+        layer.set(1, 1, Tile::new(1));
+        layer.set(2, 1, Tile::with_flags(1, TILE_FLAG_FLIP_X));
+
+        // world coordinates end up at the same screen coordinates with this camera
+        let mut screen = Bitmap::new(20, 20).unwrap();
+        screen.clear(99);
+        let mut camera = Camera::new(Rect::new(2, 2, 12, 12));
+        camera.set_top_left(Vector2::new(2.0, 2.0));
+        map.render(&mut screen, &camera);
+
+        // outside of the viewport
+        assert_eq!(Some(99), screen.get_pixel(1, 1));
+        assert_eq!(Some(99), screen.get_pixel(14, 14));
+        // only the bottom layer
+        assert_eq!(Some(4), screen.get_pixel(2, 2));
+        // the top layer, with its transparent pixel showing the bottom layer through
+        assert_eq!(Some(4), screen.get_pixel(4, 4));
+        assert_eq!(Some(2), screen.get_pixel(5, 5));
+        assert_eq!(Some(2), screen.get_pixel(8, 4));
+        assert_eq!(Some(4), screen.get_pixel(11, 4));
+
+        // animated, with the top layer not scrolling at all
+        map.add_animation(vec![1, 2], 1.0);
+        map.update(1.0);
+        map.layer_mut(top).unwrap().parallax = Vector2::new(0.0, 0.0);
+        screen.clear(99);
+        map.render(&mut screen, &camera);
+        assert_eq!(Some(4), screen.get_pixel(9, 9));
+        assert_eq!(Some(4), screen.get_pixel(12, 12));
+        assert_eq!(Some(3), screen.get_pixel(13, 13));
+        assert_eq!(Some(3), screen.get_pixel(12, 13));
+    }
+}
@@ -0,0 +1,690 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use sdl2::keyboard::Scancode;
+use sdl2::mouse::MouseButton;
+
+use crate::graphics::*;
+use crate::math::*;
+use crate::system::*;
+
+pub mod widgets;
+
+const PADDING: i32 = 2;
+const SPACING: i32 = 3;
+const DEFAULT_ITEM_WIDTH: u32 = 96;
+
+/// Identifies a single widget or window. Derived from the widget's label and the IDs of the
+/// windows (and [`Ui::with_id`] calls) that it is within.
+pub type WidgetId = u64;
+
+/// The palette colors that a [`Gui`] is drawn with.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct GuiTheme {
+    pub window: u8,
+    pub border: u8,
+    pub title_bar: u8,
+    pub title_text: u8,
+    pub text: u8,
+    pub widget: u8,
+    pub widget_hot: u8,
+    pub widget_active: u8,
+    pub highlight: u8,
+    pub highlight_text: u8,
+}
+
+impl Default for GuiTheme {
+    /// Returns a theme suitable for use with the standard VGA palette.
+    fn default() -> Self {
+        GuiTheme {
+            window: 7,
+            border: 0,
+            title_bar: 1,
+            title_text: 15,
+            text: 0,
+            widget: 15,
+            widget_hot: 11,
+            widget_active: 3,
+            highlight: 9,
+            highlight_text: 15,
+        }
+    }
+}
+
+/// The state of the input devices used by a [`Gui`] for a single frame. Usually created from
+/// the [`System`]'s input devices with [`GuiInput::from_system`], but can also be filled in
+/// directly, such as to drive a [`Gui`] from a gamepad-controlled cursor.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct GuiInput {
+    /// The x coordinate of the mouse cursor, in the same coordinates as the bitmap being drawn to.
+    pub mouse_x: i32,
+    /// The y coordinate of the mouse cursor, in the same coordinates as the bitmap being drawn to.
+    pub mouse_y: i32,
+    /// True if the left mouse button is being held down.
+    pub mouse_down: bool,
+    /// True if the left mouse button was just pressed.
+    pub mouse_pressed: bool,
+    /// True if the left mouse button was just released.
+    pub mouse_released: bool,
+    /// Text typed during this frame, which is added to the focused text field.
+    pub typed: String,
+    /// True if backspace was just pressed.
+    pub backspace: bool,
+    /// True if enter was just pressed.
+    pub enter: bool,
+    /// True if the up key was just pressed.
+    pub up: bool,
+    /// True if the down key was just pressed.
+    pub down: bool,
+}
+
+impl GuiInput {
+    /// Returns the current state of the given input devices. Typed text is only available while
+    /// text input mode is active (see [`Gui::update_text_input`]).
+    pub fn from_devices(mouse: &Mouse, keyboard: &Keyboard, text_input: &TextInput) -> Self {
+        let button = MouseButton::Left as usize;
+        GuiInput {
+            mouse_x: mouse.x(),
+            mouse_y: mouse.y(),
+            mouse_down: mouse.is_button_down(button),
+            mouse_pressed: mouse.is_button_pressed(button),
+            mouse_released: mouse.is_button_released(button),
+            typed: String::from(text_input.typed()),
+            backspace: keyboard.is_key_pressed(Scancode::Backspace),
+            enter: keyboard.is_key_pressed(Scancode::Return) || text_input.is_submitted(),
+            up: keyboard.is_key_pressed(Scancode::Up),
+            down: keyboard.is_key_pressed(Scancode::Down),
+        }
+    }
+
+    /// Returns the current state of the [`System`]'s input devices.
+    pub fn from_system(system: &System) -> Self {
+        Self::from_devices(&system.mouse, &system.keyboard, &system.text_input)
+    }
+}
+
+/// The result of checking the mouse against a widget's area.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+struct Interaction {
+    hovered: bool,
+    pressed: bool,
+    held: bool,
+    clicked: bool,
+}
+
+/// A small immediate-mode GUI for things like options menus, editors and debug tools, drawn
+/// entirely with [`Bitmap`] primitives and a [`Font`]. Rather than building a tree of widget
+/// objects, widgets are drawn and checked for input by calling methods on a [`Ui`] each frame,
+/// inside of [`Gui::frame`]. A [`Gui`] only holds the small amount of state that needs to carry
+/// over between frames, such as which widget is being clicked, which text field has focus and
+/// where each window has been dragged to. The values being edited are owned by the caller.
+///
+/// Widgets are identified by their labels, so labels must be unique within each window. Any part
+/// of a label after "##" is not shown, which allows widgets with the same visible label (or no
+/// visible label at all) to still be given unique labels. Windows are drawn in the order they are
+/// given, with no overlap handling, so windows should generally not overlap each other.
+///
+/// ```no_run
+/// use libretrogd::gui::*;
+/// use libretrogd::system::*;
+///
+/// let mut system = SystemBuilder::new().window_title("Options").build().unwrap();
+/// let mut gui = Gui::new();
+/// let mut fullscreen = false;
+/// let mut volume = 10;
+/// let mut name = String::from("Player");
+///
+/// loop {
+///     system.do_events();
+///     gui.update_text_input(&mut system);
+///     system.video.clear(0);
+///
+///     let input = GuiInput::from_system(&system);
+///     gui.frame(&mut system.video, &system.font, input, |ui| {
+///         ui.window("Options", 10, 10, 200, 120, |ui| {
+///             ui.checkbox("Fullscreen", &mut fullscreen);
+///             ui.slider("Volume", &mut volume, 0, 10);
+///             ui.text_field("Name", &mut name);
+///             if ui.button("OK") {
+///                 // ...
+///             }
+///         });
+///     });
+///
+///     system.display().unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Gui {
+    pub theme: GuiTheme,
+    input: GuiInput,
+    active: Option<WidgetId>,
+    focused: Option<WidgetId>,
+    focused_seen: bool,
+    drag_offset: (i32, i32),
+    windows: HashMap<WidgetId, Rect>,
+    scroll: HashMap<WidgetId, usize>,
+    mouse_over: bool,
+    mouse_over_next: bool,
+    started_text_input: bool,
+    ticks: u32,
+}
+
+impl Gui {
+    /// Creates and returns a new GUI, using the default theme.
+    pub fn new() -> Self {
+        Gui::default()
+    }
+
+    /// Returns true if the mouse cursor was over a window or widget during the last frame, in
+    /// which case mouse clicks should probably not also be handled by the game itself.
+    #[inline]
+    pub fn is_mouse_over(&self) -> bool {
+        self.mouse_over
+    }
+
+    /// Returns true if a text field currently has focus, in which case keyboard input should
+    /// probably not also be handled by the game itself.
+    #[inline]
+    pub fn wants_text_input(&self) -> bool {
+        self.focused.is_some()
+    }
+
+    /// Starts or stops the [`System`]'s text input mode, so that it is only active while a text
+    /// field has focus. Should be called once per frame, after [`System::do_events`]. Text input
+    /// mode that was started by something else is left alone.
+    pub fn update_text_input(&mut self, system: &mut System) {
+        if self.wants_text_input() && !system.text_input.is_active() {
+            system.start_text_input();
+            self.started_text_input = true;
+        } else if !self.wants_text_input() && self.started_text_input {
+            system.stop_text_input();
+            self.started_text_input = false;
+        }
+    }
+
+    /// Forgets the positions that all windows have been dragged to, so that they are shown at
+    /// their initial positions again.
+    pub fn reset_windows(&mut self) {
+        self.windows.clear();
+    }
+
+    /// Draws and handles input for a single frame of the GUI. All widgets and windows for the
+    /// frame are given by the function passed in.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest`: the bitmap to draw the GUI onto (typically the screen)
+    /// * `font`: the font to draw all text with
+    /// * `input`: the state of the input devices for this frame
+    /// * `f`: the function which adds all of the windows and widgets for this frame
+    ///
+    /// returns: whatever was returned by the function passed in
+    pub fn frame<T: Font, R>(
+        &mut self,
+        dest: &mut Bitmap,
+        font: &T,
+        input: GuiInput,
+        f: impl FnOnce(&mut Ui<T>) -> R,
+    ) -> R {
+        self.input = input;
+        self.focused_seen = false;
+        self.mouse_over_next = false;
+        self.ticks = self.ticks.wrapping_add(1);
+
+        let region = *dest.clip_region();
+        let mut ui = Ui {
+            gui: self,
+            dest,
+            font,
+            layout: Layout {
+                region,
+                cursor_y: region.y,
+                next_position: None,
+                last_item: None,
+                item_width: DEFAULT_ITEM_WIDTH,
+                parent_id: 0,
+                visible: true,
+            },
+        };
+        let result = f(&mut ui);
+
+        // clicking on anything other than the focused text field (including nothing at all)
+        // takes focus away from it
+        if self.input.mouse_pressed && self.active != self.focused {
+            self.focused = None;
+        }
+        if !self.focused_seen {
+            self.focused = None;
+        }
+        if !self.input.mouse_down {
+            self.active = None;
+        }
+        self.mouse_over = self.mouse_over_next;
+        result
+    }
+}
+
+/// Returns the part of a widget label that should be shown, which excludes anything after "##".
+fn visible_label(label: &str) -> &str {
+    match label.find("##") {
+        Some(index) => &label[..index],
+        None => label,
+    }
+}
+
+/// Where the next widget is placed within the current window (or the whole bitmap, outside of
+/// any window), saved and restored around each window.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    region: Rect,
+    cursor_y: i32,
+    next_position: Option<(i32, i32)>,
+    last_item: Option<Rect>,
+    item_width: u32,
+    parent_id: WidgetId,
+    visible: bool,
+}
+
+/// Adds windows and widgets to a [`Gui`] for a single frame. See [`Gui::frame`].
+///
+/// Widgets are laid out one below the other, starting from the top-left of the current window
+/// (or the whole bitmap, outside of any window). [`Ui::same_line`] places the next widget to
+/// the right of the previous one instead.
+pub struct Ui<'a, T: Font> {
+    gui: &'a mut Gui,
+    dest: &'a mut Bitmap,
+    font: &'a T,
+    layout: Layout,
+}
+
+impl<'a, T: Font> Ui<'a, T> {
+    /// Returns the theme the GUI is being drawn with.
+    #[inline]
+    pub fn theme(&self) -> &GuiTheme {
+        &self.gui.theme
+    }
+
+    /// Returns the width (in pixels) used by sliders, text fields and list boxes.
+    #[inline]
+    pub fn item_width(&self) -> u32 {
+        self.layout.item_width
+    }
+
+    /// Changes the width (in pixels) used by sliders, text fields and list boxes added after
+    /// this, until the end of the current window.
+    pub fn set_item_width(&mut self, width: u32) {
+        self.layout.item_width = width.max(1);
+    }
+
+    /// Returns the width (in pixels) of the current window's content area (or the whole bitmap,
+    /// outside of any window).
+    #[inline]
+    pub fn available_width(&self) -> u32 {
+        self.layout.region.width
+    }
+
+    /// Places the next widget to the right of the previous one, instead of below it.
+    pub fn same_line(&mut self) {
+        self.layout.next_position = self
+            .layout
+            .last_item
+            .map(|last| (last.x + last.width as i32 + SPACING, last.y));
+    }
+
+    /// Adds extra vertical space before the next widget.
+    pub fn spacing(&mut self, pixels: u32) {
+        self.layout.cursor_y += pixels as i32;
+        self.layout.next_position = None;
+    }
+
+    /// Moves where the next widget is placed, relative to the top-left of the current window's
+    /// content area (or the whole bitmap, outside of any window).
+    pub fn set_cursor(&mut self, x: i32, y: i32) {
+        let (x, y) = (self.layout.region.x + x, self.layout.region.y + y);
+        self.layout.next_position = Some((x, y));
+        self.layout.cursor_y = y;
+    }
+
+    /// Runs the given function with the given value mixed into the IDs of all of the widgets
+    /// added by it, so that widgets with the same label can be told apart, such as when adding
+    /// the same widgets once for each item in a list.
+    pub fn with_id<R>(&mut self, id: impl Hash, f: impl FnOnce(&mut Self) -> R) -> R {
+        let parent_id = self.layout.parent_id;
+        self.layout.parent_id = self.id(id);
+        let result = f(self);
+        self.layout.parent_id = parent_id;
+        result
+    }
+
+    /// Adds a window, which can be dragged around by its title bar. Widgets added by the given
+    /// function are placed inside the window, and are cut off if they do not fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `title`: the window's title, which also identifies it
+    /// * `x`: the x coordinate the window is initially shown at
+    /// * `y`: the y coordinate the window is initially shown at
+    /// * `width`: the width of the window, in pixels
+    /// * `height`: the height of the window, in pixels, including the title bar
+    /// * `f`: the function which adds the window's widgets
+    ///
+    /// returns: whatever was returned by the function passed in
+    pub fn window<R>(
+        &mut self,
+        title: &str,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let id = self.id(title);
+        let title_height = self.item_height();
+        let height = height.max(title_height);
+        let position = self
+            .gui
+            .windows
+            .get(&id)
+            .map_or((x, y), |rect| (rect.x, rect.y));
+        let title_bar = Rect::new(position.0, position.1, width, title_height);
+
+        let interaction = self.interact(id, &title_bar);
+        let (mouse_x, mouse_y) = (self.gui.input.mouse_x, self.gui.input.mouse_y);
+        if interaction.pressed {
+            self.gui.drag_offset = (mouse_x - position.0, mouse_y - position.1);
+        }
+        let position = if interaction.held {
+            (
+                mouse_x - self.gui.drag_offset.0,
+                mouse_y - self.gui.drag_offset.1,
+            )
+        } else {
+            position
+        };
+        let rect = Rect::new(position.0, position.1, width, height);
+        self.gui.windows.insert(id, rect);
+        if self.layout.visible && rect.contains_point(mouse_x, mouse_y) {
+            self.gui.mouse_over_next = true;
+        }
+
+        let theme = self.gui.theme;
+        self.draw_frame(&rect, theme.window, theme.border);
+        let title_bar = Rect::new(
+            rect.x + 1,
+            rect.y + 1,
+            width.saturating_sub(2),
+            title_height - 1,
+        );
+        if self.layout.visible && self.dest.push_clip_region(&title_bar) {
+            self.dest.filled_rect(
+                title_bar.x,
+                title_bar.y,
+                title_bar.right(),
+                title_bar.bottom(),
+                theme.title_bar,
+            );
+            self.print(
+                visible_label(title),
+                rect.x + PADDING + 1,
+                rect.y + PADDING,
+                theme.title_text,
+            );
+            self.dest.pop_clip_region();
+        }
+
+        let content = Rect::new(
+            rect.x + PADDING + 1,
+            rect.y + title_height as i32 + PADDING,
+            width.saturating_sub((PADDING as u32 + 1) * 2),
+            (height - title_height).saturating_sub(PADDING as u32 * 2 + 1),
+        );
+        let saved = self.layout;
+        let clipped = self.layout.visible && self.dest.push_clip_region(&content);
+        self.layout = Layout {
+            region: content,
+            cursor_y: content.y,
+            next_position: None,
+            last_item: None,
+            item_width: saved.item_width,
+            parent_id: id,
+            visible: clipped,
+        };
+
+        let result = f(self);
+
+        if clipped {
+            self.dest.pop_clip_region();
+        }
+        self.layout = saved;
+        result
+    }
+
+    fn id(&self, value: impl Hash) -> WidgetId {
+        let mut hasher = DefaultHasher::new();
+        self.layout.parent_id.hash(&mut hasher);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[inline]
+    fn item_height(&self) -> u32 {
+        self.font.line_height() as u32 + PADDING as u32 * 2
+    }
+
+    #[inline]
+    fn measure(&self, text: &str) -> (u32, u32) {
+        self.font.measure(text, FontRenderOpts::Color(0))
+    }
+
+    fn print(&mut self, text: &str, x: i32, y: i32, color: u8) {
+        if self.layout.visible {
+            self.dest
+                .print_string(text, x, y, FontRenderOpts::Color(color), self.font);
+        }
+    }
+
+    fn draw_frame(&mut self, rect: &Rect, fill: u8, border: u8) {
+        if self.layout.visible && rect.width > 0 && rect.height > 0 {
+            self.dest
+                .filled_rect(rect.x, rect.y, rect.right(), rect.bottom(), fill);
+            self.dest
+                .rect(rect.x, rect.y, rect.right(), rect.bottom(), border);
+        }
+    }
+
+    /// Returns the area for the next widget, which has the given size, and advances the layout
+    /// past it.
+    fn next_rect(&mut self, width: u32, height: u32) -> Rect {
+        let layout = &mut self.layout;
+        let (x, y) = layout
+            .next_position
+            .take()
+            .unwrap_or((layout.region.x, layout.cursor_y));
+        let rect = Rect::new(x, y, width, height);
+        layout.cursor_y = layout.cursor_y.max(y + height as i32 + SPACING);
+        layout.last_item = Some(rect);
+        rect
+    }
+
+    fn interact(&mut self, id: WidgetId, rect: &Rect) -> Interaction {
+        let input = &self.gui.input;
+        let (mouse_x, mouse_y) = (input.mouse_x, input.mouse_y);
+        let (mouse_down, mouse_pressed, mouse_released) =
+            (input.mouse_down, input.mouse_pressed, input.mouse_released);
+
+        let hovered = self.layout.visible
+            && rect.contains_point(mouse_x, mouse_y)
+            && self.dest.clip_region().contains_point(mouse_x, mouse_y)
+            && (self.gui.active.is_none() || self.gui.active == Some(id));
+        if hovered {
+            self.gui.mouse_over_next = true;
+        }
+        let pressed = hovered && mouse_pressed;
+        if pressed {
+            self.gui.active = Some(id);
+        }
+        let is_active = self.gui.active == Some(id);
+        Interaction {
+            hovered,
+            pressed,
+            held: is_active && mouse_down,
+            clicked: is_active && hovered && mouse_released,
+        }
+    }
+
+    /// Returns the color to fill a widget with, depending on how the mouse is interacting with it.
+    fn widget_color(&self, interaction: &Interaction) -> u8 {
+        if interaction.held && interaction.hovered {
+            self.gui.theme.widget_active
+        } else if interaction.hovered {
+            self.gui.theme.widget_hot
+        } else {
+            self.gui.theme.widget
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn vga_font() -> BitmaskFont {
+        BitmaskFont::new_vga_font().unwrap()
+    }
+
+    pub fn click_at(x: i32, y: i32) -> [GuiInput; 2] {
+        [
+            GuiInput {
+                mouse_x: x,
+                mouse_y: y,
+                mouse_down: true,
+                mouse_pressed: true,
+                ..Default::default()
+            },
+            GuiInput {
+                mouse_x: x,
+                mouse_y: y,
+                mouse_released: true,
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    pub fn visible_labels() {
+        assert_eq!("OK", visible_label("OK"));
+        assert_eq!("OK", visible_label("OK##dialog"));
+        assert_eq!("", visible_label("##hidden"));
+    }
+
+    #[test]
+    pub fn laying_out_widgets() {
+        let mut gui = Gui::new();
+        let font = vga_font();
+        let mut screen = Bitmap::new(320, 200).unwrap();
+        let item_height = font.line_height() as i32 + PADDING * 2;
+
+        gui.frame(&mut screen, &font, GuiInput::default(), |ui| {
+            let a = ui.next_rect(10, 10);
+            assert_eq!(Rect::new(0, 0, 10, 10), a);
+            let b = ui.next_rect(20, 5);
+            assert_eq!(Rect::new(0, 10 + SPACING, 20, 5), b);
+            ui.same_line();
+            let c = ui.next_rect(8, 8);
+            assert_eq!(Rect::new(20 + SPACING, 10 + SPACING, 8, 8), c);
+            let d = ui.next_rect(1, 1);
+            assert_eq!(Rect::new(0, 10 + SPACING + 8 + SPACING, 1, 1), d);
+
+            ui.set_cursor(50, 60);
+            assert_eq!(Rect::new(50, 60, 4, 4), ui.next_rect(4, 4));
+
+            ui.window("Test", 100, 100, 80, 60, |ui| {
+                assert_eq!(80 - (PADDING as u32 + 1) * 2, ui.available_width());
+                let rect = ui.next_rect(10, 10);
+                assert_eq!(
+                    Rect::new(100 + PADDING + 1, 100 + item_height + PADDING, 10, 10),
+                    rect
+                );
+            });
+            assert_eq!(Rect::new(0, 64 + SPACING, 2, 2), ui.next_rect(2, 2));
+        });
+    }
+
+    #[test]
+    pub fn dragging_windows() {
+        let mut gui = Gui::new();
+        let font = vga_font();
+        let mut screen = Bitmap::new(320, 200).unwrap();
+
+        let inputs = [
+            GuiInput {
+                mouse_x: 15,
+                mouse_y: 12,
+                mouse_down: true,
+                mouse_pressed: true,
+                ..Default::default()
+            },
+            GuiInput {
+                mouse_x: 45,
+                mouse_y: 32,
+                mouse_down: true,
+                ..Default::default()
+            },
+            GuiInput {
+                mouse_x: 45,
+                mouse_y: 32,
+                mouse_released: true,
+                ..Default::default()
+            },
+            GuiInput {
+                mouse_x: 200,
+                mouse_y: 150,
+                ..Default::default()
+            },
+        ];
+        for input in inputs {
+            gui.frame(&mut screen, &font, input, |ui| {
+                ui.window("Window", 10, 10, 50, 40, |_ui| {});
+            });
+        }
+        assert!(!gui.is_mouse_over());
+
+        screen.clear(99);
+        gui.frame(&mut screen, &font, GuiInput::default(), |ui| {
+            ui.window("Window", 10, 10, 50, 40, |_ui| {});
+        });
+        assert_eq!(Some(99), screen.get_pixel(39, 29));
+        assert_eq!(Some(gui.theme.border), screen.get_pixel(40, 30));
+        assert_eq!(Some(gui.theme.border), screen.get_pixel(89, 69));
+        assert_eq!(Some(gui.theme.window), screen.get_pixel(60, 60));
+
+        gui.reset_windows();
+        gui.frame(&mut screen, &font, GuiInput::default(), |ui| {
+            ui.window("Window", 10, 10, 50, 40, |_ui| {});
+        });
+        assert_eq!(Some(gui.theme.border), screen.get_pixel(10, 10));
+    }
+
+    #[test]
+    pub fn mouse_over() {
+        let mut gui = Gui::new();
+        let font = vga_font();
+        let mut screen = Bitmap::new(320, 200).unwrap();
+
+        let input = GuiInput {
+            mouse_x: 20,
+            mouse_y: 30,
+            ..Default::default()
+        };
+        gui.frame(&mut screen, &font, input.clone(), |ui| {
+            ui.window("Window", 10, 10, 50, 40, |_ui| {});
+        });
+        assert!(gui.is_mouse_over());
+        gui.frame(&mut screen, &font, input, |ui| {
+            ui.window("Window", 100, 100, 50, 40, |_ui| {});
+        });
+        assert!(!gui.is_mouse_over());
+    }
+}
@@ -0,0 +1,567 @@
+use crate::graphics::*;
+use crate::gui::*;
+
+const CHECK_MARK_INSET: u32 = 3;
+const SLIDER_THUMB_WIDTH: u32 = 6;
+const SCROLLBAR_WIDTH: u32 = 6;
+const MIN_SCROLLBAR_THUMB_HEIGHT: u32 = 4;
+const TEXT_CURSOR_BLINK_TICKS: u32 = 16;
+
+/// Returns the area of a list box's scrollbar thumb, sized and positioned to match how much of
+/// the list is visible and how far it is scrolled.
+fn scrollbar_thumb(
+    bar: &Rect,
+    scroll: usize,
+    max_scroll: usize,
+    rows: usize,
+    count: usize,
+) -> Rect {
+    let height = ((bar.height as usize * rows / count.max(1)) as u32)
+        .clamp(MIN_SCROLLBAR_THUMB_HEIGHT.min(bar.height), bar.height);
+    let travel = (bar.height - height) as usize;
+    let y = bar.y + (travel * scroll / max_scroll.max(1)) as i32;
+    Rect::new(bar.x, y, bar.width, height)
+}
+
+impl<'a, T: Font> Ui<'a, T> {
+    /// Returns the width needed to show a widget's label to the right of it, if it has one.
+    fn label_width(&self, text: &str) -> u32 {
+        match self.measure(text) {
+            (0, _) => 0,
+            (width, _) => width + PADDING as u32 * 2,
+        }
+    }
+
+    /// Adds some text. Text with line breaks in it is shown on multiple lines.
+    pub fn label(&mut self, text: &str) {
+        let (width, height) = self.measure(text);
+        let height = (height + PADDING as u32 * 2).max(self.item_height());
+        let rect = self.next_rect(width, height);
+        let color = self.gui.theme.text;
+        self.print(text, rect.x, rect.y + PADDING, color);
+    }
+
+    /// Adds a button.
+    ///
+    /// returns: true if the button was clicked
+    pub fn button(&mut self, label: &str) -> bool {
+        let id = self.id(label);
+        let text = visible_label(label);
+        let (text_width, _) = self.measure(text);
+        let height = self.item_height();
+        let rect = self.next_rect(text_width + PADDING as u32 * 4, height);
+        let interaction = self.interact(id, &rect);
+
+        let theme = self.gui.theme;
+        let fill = self.widget_color(&interaction);
+        self.draw_frame(&rect, fill, theme.border);
+        self.print(text, rect.x + PADDING * 2, rect.y + PADDING, theme.text);
+        interaction.clicked
+    }
+
+    /// Adds a checkbox, which toggles the given value when clicked.
+    ///
+    /// returns: true if the value was changed
+    pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        let id = self.id(label);
+        let text = visible_label(label);
+        let size = self.item_height();
+        let label_width = self.label_width(text);
+        let rect = self.next_rect(size + label_width, size);
+        let interaction = self.interact(id, &rect);
+        if interaction.clicked {
+            *value = !*value;
+        }
+
+        let theme = self.gui.theme;
+        let fill = self.widget_color(&interaction);
+        self.draw_frame(&Rect::new(rect.x, rect.y, size, size), fill, theme.border);
+        if *value {
+            let mark = Rect::new(
+                rect.x + CHECK_MARK_INSET as i32,
+                rect.y + CHECK_MARK_INSET as i32,
+                size.saturating_sub(CHECK_MARK_INSET * 2),
+                size.saturating_sub(CHECK_MARK_INSET * 2),
+            );
+            self.draw_frame(&mark, theme.highlight, theme.highlight);
+        }
+        self.print(
+            text,
+            rect.x + size as i32 + PADDING * 2,
+            rect.y + PADDING,
+            theme.text,
+        );
+        interaction.clicked
+    }
+
+    /// Adds a slider for choosing a whole number between `min` and `max` (inclusive), by clicking
+    /// on it and dragging.
+    ///
+    /// returns: true if the value was changed
+    pub fn slider(&mut self, label: &str, value: &mut i32, min: i32, max: i32) -> bool {
+        let range = (max as i64 - min as i64).max(0);
+        let (track, interaction, dragged) = self.slider_input(label);
+        let previous = *value;
+        if let Some(fraction) = dragged {
+            *value = (min as i64 + (range as f32 * fraction).round() as i64) as i32;
+        }
+        let fraction = if range > 0 {
+            (*value as i64 - min as i64) as f32 / range as f32
+        } else {
+            0.0
+        };
+        self.draw_slider(label, &track, &interaction, fraction, &value.to_string());
+        *value != previous
+    }
+
+    /// Adds a slider for choosing a number between `min` and `max` (inclusive), by clicking on it
+    /// and dragging.
+    ///
+    /// returns: true if the value was changed
+    pub fn slider_f32(&mut self, label: &str, value: &mut f32, min: f32, max: f32) -> bool {
+        let range = (max - min).max(0.0);
+        let (track, interaction, dragged) = self.slider_input(label);
+        let previous = *value;
+        if let Some(fraction) = dragged {
+            *value = min + range * fraction;
+        }
+        let fraction = if range > 0.0 {
+            (*value - min) / range
+        } else {
+            0.0
+        };
+        self.draw_slider(
+            label,
+            &track,
+            &interaction,
+            fraction,
+            &format!("{:.2}", value),
+        );
+        *value != previous
+    }
+
+    /// Lays out a slider and checks it for input.
+    ///
+    /// returns: the slider's track, how the mouse is interacting with it, and the position (from
+    /// 0.0 to 1.0) it is being dragged to, if it is being dragged
+    fn slider_input(&mut self, label: &str) -> (Rect, Interaction, Option<f32>) {
+        let id = self.id(label);
+        let width = self.layout.item_width;
+        let height = self.item_height();
+        let label_width = self.label_width(visible_label(label));
+        let rect = self.next_rect(width + label_width, height);
+        let track = Rect::new(rect.x, rect.y, width, height);
+        let interaction = self.interact(id, &track);
+
+        let dragged = if interaction.held {
+            let travel = width.saturating_sub(SLIDER_THUMB_WIDTH).max(1) as f32;
+            let offset = self.gui.input.mouse_x - track.x - SLIDER_THUMB_WIDTH as i32 / 2;
+            Some((offset as f32 / travel).clamp(0.0, 1.0))
+        } else {
+            None
+        };
+        (track, interaction, dragged)
+    }
+
+    fn draw_slider(
+        &mut self,
+        label: &str,
+        track: &Rect,
+        interaction: &Interaction,
+        fraction: f32,
+        value_text: &str,
+    ) {
+        let theme = self.gui.theme;
+        let fill = self.widget_color(interaction);
+        self.draw_frame(track, fill, theme.border);
+
+        let travel = track.width.saturating_sub(SLIDER_THUMB_WIDTH) as f32;
+        let thumb = Rect::new(
+            track.x + (travel * fraction.clamp(0.0, 1.0)) as i32,
+            track.y,
+            SLIDER_THUMB_WIDTH.min(track.width),
+            track.height,
+        );
+        self.draw_frame(&thumb, theme.highlight, theme.border);
+
+        let (text_width, _) = self.measure(value_text);
+        let text_x = track.x + (track.width as i32 - text_width as i32) / 2;
+        self.print(value_text, text_x, track.y + PADDING, theme.text);
+        self.print(
+            visible_label(label),
+            track.x + track.width as i32 + PADDING * 2,
+            track.y + PADDING,
+            theme.text,
+        );
+    }
+
+    /// Adds a single-line text field for editing the given text. Clicking on the text field gives
+    /// it focus, after which typed text is added to the end of it and backspace removes the last
+    /// character. Pressing enter or clicking elsewhere takes focus away from it again. See
+    /// [`Gui::update_text_input`] for enabling text input while a text field has focus.
+    ///
+    /// returns: true if the text was changed
+    pub fn text_field(&mut self, label: &str, text: &mut String) -> bool {
+        let id = self.id(label);
+        let label_text = visible_label(label);
+        let width = self.layout.item_width;
+        let height = self.item_height();
+        let label_width = self.label_width(label_text);
+        let rect = self.next_rect(width + label_width, height);
+        let field = Rect::new(rect.x, rect.y, width, height);
+        let interaction = self.interact(id, &field);
+        if interaction.pressed {
+            self.gui.focused = Some(id);
+        }
+
+        let focused = self.gui.focused == Some(id);
+        let mut changed = false;
+        if focused {
+            self.gui.focused_seen = true;
+            let input = &self.gui.input;
+            let length = text.len();
+            text.extend(input.typed.chars().filter(|c| !c.is_control()));
+            changed = text.len() != length;
+            if input.backspace && text.pop().is_some() {
+                changed = true;
+            }
+            if input.enter {
+                self.gui.focused = None;
+            }
+        }
+
+        let theme = self.gui.theme;
+        let border = if focused {
+            theme.highlight
+        } else {
+            theme.border
+        };
+        self.draw_frame(&field, theme.widget, border);
+
+        // show the end of the text if it is too long to fit
+        let show_cursor = focused && (self.gui.ticks / TEXT_CURSOR_BLINK_TICKS) % 2 == 0;
+        let mut shown = format!("{}{}", text, if show_cursor { "_" } else { "" });
+        let max_width = width.saturating_sub(PADDING as u32 * 2);
+        while !shown.is_empty() && self.measure(&shown).0 > max_width {
+            shown.remove(0);
+        }
+        self.print(&shown, field.x + PADDING, field.y + PADDING, theme.text);
+        self.print(
+            label_text,
+            field.x + width as i32 + PADDING * 2,
+            field.y + PADDING,
+            theme.text,
+        );
+        changed
+    }
+
+    /// Adds a list box showing the given items, which can be selected by clicking on them, or
+    /// with the up and down keys while the mouse is over the list box. If there are more items
+    /// than fit, a scrollbar is shown which pages through the items when clicked above or below
+    /// its thumb.
+    ///
+    /// # Arguments
+    ///
+    /// * `label`: the list box's label, shown to the right of it
+    /// * `items`: the items to show
+    /// * `selected`: the index of the selected item, if any
+    /// * `rows`: the number of items that are visible at once
+    ///
+    /// returns: true if the selected item was changed
+    pub fn list_box<S: AsRef<str>>(
+        &mut self,
+        label: &str,
+        items: &[S],
+        selected: &mut Option<usize>,
+        rows: usize,
+    ) -> bool {
+        let id = self.id(label);
+        let label_text = visible_label(label);
+        let rows = rows.max(1);
+        let line_height = self.font.line_height() as u32;
+        let width = self.layout.item_width;
+        let height = line_height * rows as u32 + PADDING as u32 * 2;
+        let label_width = self.label_width(label_text);
+        let rect = self.next_rect(width + label_width, height);
+        let list = Rect::new(rect.x, rect.y, width, height);
+        let interaction = self.interact(id, &list);
+
+        let max_scroll = items.len().saturating_sub(rows);
+        let mut scroll = self
+            .gui
+            .scroll
+            .get(&id)
+            .copied()
+            .unwrap_or(0)
+            .min(max_scroll);
+        let scrollbar = if max_scroll > 0 {
+            Some(Rect::new(
+                list.x + width.saturating_sub(SCROLLBAR_WIDTH) as i32,
+                list.y,
+                SCROLLBAR_WIDTH.min(width),
+                height,
+            ))
+        } else {
+            None
+        };
+        let items_width = width.saturating_sub(scrollbar.map_or(0, |bar| bar.width - 1) + 2);
+        let items_area = Rect::new(
+            list.x + 1,
+            list.y + PADDING,
+            items_width,
+            line_height * rows as u32,
+        );
+
+        let previous = *selected;
+        let input = &self.gui.input;
+        let (mouse_x, mouse_y) = (input.mouse_x, input.mouse_y);
+        if interaction.pressed {
+            match scrollbar {
+                Some(bar) if bar.contains_point(mouse_x, mouse_y) => {
+                    let thumb = scrollbar_thumb(&bar, scroll, max_scroll, rows, items.len());
+                    if mouse_y < thumb.y {
+                        scroll = scroll.saturating_sub(rows);
+                    } else if mouse_y > thumb.bottom() {
+                        scroll = (scroll + rows).min(max_scroll);
+                    }
+                }
+                _ => {
+                    if items_area.contains_point(mouse_x, mouse_y) {
+                        let index =
+                            scroll + ((mouse_y - items_area.y) / line_height as i32) as usize;
+                        if index < items.len() {
+                            *selected = Some(index);
+                        }
+                    }
+                }
+            }
+        } else if interaction.hovered && !items.is_empty() && (input.up || input.down) {
+            let last = items.len() - 1;
+            let index = match (*selected, input.up) {
+                (None, _) => 0,
+                (Some(index), true) => index.min(last).saturating_sub(1),
+                (Some(index), false) => (index + 1).min(last),
+            };
+            *selected = Some(index);
+            // keep the newly selected item visible
+            if index < scroll {
+                scroll = index;
+            } else if index >= scroll + rows {
+                scroll = index + 1 - rows;
+            }
+        }
+        self.gui.scroll.insert(id, scroll);
+
+        let theme = self.gui.theme;
+        self.draw_frame(&list, theme.widget, theme.border);
+        if self.layout.visible && self.dest.push_clip_region(&items_area) {
+            for (index, item) in items.iter().enumerate().skip(scroll).take(rows) {
+                let y = items_area.y + (index - scroll) as i32 * line_height as i32;
+                let color = if *selected == Some(index) {
+                    self.dest.filled_rect(
+                        items_area.x,
+                        y,
+                        items_area.right(),
+                        y + line_height as i32 - 1,
+                        theme.highlight,
+                    );
+                    theme.highlight_text
+                } else {
+                    theme.text
+                };
+                self.print(item.as_ref(), items_area.x + PADDING, y, color);
+            }
+            self.dest.pop_clip_region();
+        }
+        if let Some(bar) = scrollbar {
+            self.draw_frame(&bar, theme.window, theme.border);
+            let thumb = scrollbar_thumb(&bar, scroll, max_scroll, rows, items.len());
+            self.draw_frame(&thumb, theme.highlight, theme.border);
+        }
+        self.print(
+            label_text,
+            list.x + width as i32 + PADDING * 2,
+            list.y + PADDING,
+            theme.text,
+        );
+        *selected != previous
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::gui::tests::*;
+
+    use super::*;
+
+    fn run<R>(gui: &mut Gui, input: GuiInput, f: impl FnOnce(&mut Ui<BitmaskFont>) -> R) -> R {
+        let font = vga_font();
+        let mut screen = Bitmap::new(320, 200).unwrap();
+        gui.frame(&mut screen, &font, input, f)
+    }
+
+    #[test]
+    pub fn buttons() {
+        let mut gui = Gui::new();
+        let [press, release] = click_at(5, 5);
+        assert!(!run(&mut gui, press, |ui| ui.button("OK")));
+        assert!(gui.is_mouse_over());
+        assert!(run(&mut gui, release.clone(), |ui| ui.button("OK")));
+        assert!(!run(&mut gui, release, |ui| ui.button("OK")));
+
+        // releasing over a different button than the one pressed does not click either of them
+        let [press, _] = click_at(5, 5);
+        run(&mut gui, press, |ui| {
+            ui.button("A");
+            ui.button("B");
+        });
+        let [_, release] = click_at(5, 20);
+        let clicked = run(&mut gui, release, |ui| (ui.button("A"), ui.button("B")));
+        assert_eq!((false, false), clicked);
+    }
+
+    #[test]
+    pub fn checkboxes() {
+        let mut gui = Gui::new();
+        let mut value = false;
+        for input in click_at(5, 5) {
+            run(&mut gui, input, |ui| ui.checkbox("Enabled", &mut value));
+        }
+        assert!(value);
+        for input in click_at(5, 5) {
+            run(&mut gui, input, |ui| ui.checkbox("Enabled", &mut value));
+        }
+        assert!(!value);
+    }
+
+    #[test]
+    pub fn sliders() {
+        let mut gui = Gui::new();
+        let mut value = 2;
+        let thumb_center = SLIDER_THUMB_WIDTH as i32 / 2;
+        let travel = (DEFAULT_ITEM_WIDTH - SLIDER_THUMB_WIDTH) as i32;
+
+        let [press, _] = click_at(thumb_center + travel / 2, 5);
+        assert!(run(&mut gui, press, |ui| ui.slider("Volume", &mut value, 0, 10)));
+        assert_eq!(5, value);
+
+        // dragging past the end of the slider
+        let input = GuiInput {
+            mouse_x: 300,
+            mouse_y: 50,
+            mouse_down: true,
+            ..Default::default()
+        };
+        assert!(run(&mut gui, input.clone(), |ui| ui
+            .slider("Volume", &mut value, 0, 10)));
+        assert_eq!(10, value);
+        assert!(!run(&mut gui, input, |ui| ui.slider("Volume", &mut value, 0, 10)));
+
+        let mut gui = Gui::new();
+        let mut value = 0.0;
+        let [press, _] = click_at(thumb_center + travel, 5);
+        assert!(run(&mut gui, press, |ui| ui
+            .slider_f32("Speed", &mut value, 0.5, 1.5)));
+        assert_eq!(1.5, value);
+    }
+
+    #[test]
+    pub fn text_fields() {
+        let mut gui = Gui::new();
+        let mut text = String::from("ab");
+
+        let typing = GuiInput {
+            typed: String::from("cd"),
+            ..Default::default()
+        };
+        assert!(!run(&mut gui, typing.clone(), |ui| ui
+            .text_field("Name", &mut text)));
+        assert_eq!("ab", text);
+
+        let [press, _] = click_at(5, 5);
+        run(&mut gui, press, |ui| ui.text_field("Name", &mut text));
+        assert!(gui.wants_text_input());
+        assert!(run(&mut gui, typing, |ui| ui.text_field("Name", &mut text)));
+        assert_eq!("abcd", text);
+        let backspace = GuiInput {
+            backspace: true,
+            ..Default::default()
+        };
+        assert!(run(&mut gui, backspace, |ui| ui.text_field("Name", &mut text)));
+        assert_eq!("abc", text);
+        let enter = GuiInput {
+            enter: true,
+            ..Default::default()
+        };
+        run(&mut gui, enter, |ui| ui.text_field("Name", &mut text));
+        assert!(!gui.wants_text_input());
+
+        // clicking elsewhere
+        for input in click_at(5, 5) {
+            run(&mut gui, input, |ui| ui.text_field("Name", &mut text));
+        }
+        assert!(gui.wants_text_input());
+        let [press, _] = click_at(300, 150);
+        run(&mut gui, press, |ui| ui.text_field("Name", &mut text));
+        assert!(!gui.wants_text_input());
+
+        // no longer being shown
+        for input in click_at(5, 5) {
+            run(&mut gui, input, |ui| ui.text_field("Name", &mut text));
+        }
+        assert!(gui.wants_text_input());
+        run(&mut gui, GuiInput::default(), |ui| ui.label("Hello"));
+        assert!(!gui.wants_text_input());
+    }
+
+    #[test]
+    pub fn list_boxes() {
+        let mut gui = Gui::new();
+        let items: Vec<String> = (0..10).map(|i| format!("Item {}", i)).collect();
+        let mut selected = None;
+        let line_height = vga_font().line_height() as i32;
+
+        let [press, _] = click_at(5, PADDING + line_height + 1);
+        assert!(run(&mut gui, press, |ui| ui.list_box(
+            "Items",
+            &items,
+            &mut selected,
+            3
+        )));
+        assert_eq!(Some(1), selected);
+
+        let down = GuiInput {
+            mouse_x: 5,
+            mouse_y: 5,
+            down: true,
+            ..Default::default()
+        };
+        assert!(run(&mut gui, down.clone(), |ui| ui.list_box(
+            "Items",
+            &items,
+            &mut selected,
+            3
+        )));
+        assert_eq!(Some(2), selected);
+        assert!(run(&mut gui, down, |ui| ui.list_box(
+            "Items",
+            &items,
+            &mut selected,
+            3
+        )));
+        assert_eq!(Some(3), selected);
+        assert_eq!(Some(&1), gui.scroll.values().next());
+
+        // paging down with the scrollbar
+        let bar_x = (DEFAULT_ITEM_WIDTH - 1) as i32;
+        let [press, _] = click_at(bar_x, PADDING + line_height * 3);
+        assert!(!run(&mut gui, press, |ui| ui.list_box(
+            "Items",
+            &items,
+            &mut selected,
+            3
+        )));
+        assert_eq!(Some(&4), gui.scroll.values().next());
+        assert_eq!(Some(3), selected);
+    }
+}
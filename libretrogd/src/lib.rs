@@ -1,11 +1,17 @@
 extern crate core;
 extern crate sdl2;
 
+pub mod assets;
 pub mod audio;
+pub mod collision;
+pub mod console;
+pub mod effects;
 pub mod entities;
 pub mod events;
 pub mod graphics;
+pub mod gui;
 pub mod math;
+pub mod pathfinding;
 pub mod states;
 pub mod system;
 pub mod utils;
@@ -0,0 +1,256 @@
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use crate::math::*;
+
+/// Wraps an angle (in radians) so that it lies within 0 -> 2π radians (0 -> 360 degrees). Unlike
+/// [WrappingRadians], this works for angles that are any number of turns out of range.
+#[inline]
+pub fn normalize_radians(radians: f32) -> f32 {
+    let result = radians.rem_euclid(RADIANS_360);
+    // rem_euclid can round up to exactly 2π for tiny negative values
+    if result >= RADIANS_360 {
+        0.0
+    } else {
+        result
+    }
+}
+
+/// Wraps an angle (in radians) so that it lies within -π -> π radians (-180 -> 180 degrees).
+#[inline]
+pub fn normalize_radians_signed(radians: f32) -> f32 {
+    let result = normalize_radians(radians);
+    if result >= RADIANS_180 {
+        result - RADIANS_360
+    } else {
+        result
+    }
+}
+
+/// Returns the shortest signed difference (in radians) needed to turn from angle `from` to angle
+/// `to`. The result lies within -π -> π radians, where positive values mean turning clockwise (in
+/// screen coordinates, where y increases downwards).
+#[inline]
+pub fn angle_difference(from: f32, to: f32) -> f32 {
+    normalize_radians_signed(to - from)
+}
+
+/// Quantizes an angle (in radians) to one of a number of evenly spaced facing directions, as
+/// used by sprite sheets that have a frame for each direction a sprite can face. Direction 0 is
+/// [RIGHT] and following directions go clockwise (in screen coordinates, where y increases
+/// downwards), so with 8 directions, direction 2 is [DOWN], 4 is [LEFT] and 6 is [UP]. Angles
+/// are rounded to the closest direction.
+///
+/// # Arguments
+///
+/// * `radians`: the angle to quantize
+/// * `num_directions`: the number of facing directions. must be greater than zero
+pub fn facing_direction(radians: f32, num_directions: u32) -> u32 {
+    let step = RADIANS_360 / num_directions as f32;
+    (normalize_radians(radians) / step).round() as u32 % num_directions
+}
+
+/// Quantizes an angle (in radians) to one of 8 facing directions. See [facing_direction].
+#[inline]
+pub fn facing_direction_8(radians: f32) -> u32 {
+    facing_direction(radians, 8)
+}
+
+/// Quantizes an angle (in radians) to one of 16 facing directions. See [facing_direction].
+#[inline]
+pub fn facing_direction_16(radians: f32) -> u32 {
+    facing_direction(radians, 16)
+}
+
+/// A "binary angle", where a full circle is divided into 256 steps so that an angle fits into a
+/// single byte. Addition and subtraction wrap around naturally. Sine and cosine are looked up from
+/// the same tables used by [Fx32], so results are the same on all platforms.
+///
+/// As with the rest of this module, angle 0 points [RIGHT] and angles increase clockwise (in
+/// screen coordinates, where y increases downwards). So 64 is [DOWN], 128 is [LEFT] and 192 is
+/// [UP].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct BinaryAngle(pub u8);
+
+impl BinaryAngle {
+    pub const STEPS: u32 = 256;
+
+    pub const RIGHT: BinaryAngle = BinaryAngle(0);
+    pub const DOWN: BinaryAngle = BinaryAngle(64);
+    pub const LEFT: BinaryAngle = BinaryAngle(128);
+    pub const UP: BinaryAngle = BinaryAngle(192);
+
+    /// Converts an angle in radians to the nearest binary angle.
+    #[inline]
+    pub fn from_radians(radians: f32) -> BinaryAngle {
+        let steps = (normalize_radians(radians) * (Self::STEPS as f32 / RADIANS_360)).round();
+        BinaryAngle((steps as u32 % Self::STEPS) as u8)
+    }
+
+    /// Returns the binary angle between two points, pointing from the first towards the second.
+    #[inline]
+    pub fn between(x1: f32, y1: f32, x2: f32, y2: f32) -> BinaryAngle {
+        BinaryAngle::from_radians(angle_between(x1, y1, x2, y2))
+    }
+
+    /// Converts this angle to radians, within 0 -> 2π radians.
+    #[inline]
+    pub fn to_radians(self) -> f32 {
+        self.0 as f32 * (RADIANS_360 / Self::STEPS as f32)
+    }
+
+    /// Returns the sine of this angle as a fixed-point value.
+    #[inline]
+    pub fn sin_fx(self) -> Fx32 {
+        fx32_sin_steps(self.fx32_steps())
+    }
+
+    /// Returns the cosine of this angle as a fixed-point value.
+    #[inline]
+    pub fn cos_fx(self) -> Fx32 {
+        fx32_cos_steps(self.fx32_steps())
+    }
+
+    /// Returns the sine of this angle.
+    #[inline]
+    pub fn sin(self) -> f32 {
+        self.sin_fx().to_f32()
+    }
+
+    /// Returns the cosine of this angle.
+    #[inline]
+    pub fn cos(self) -> f32 {
+        self.cos_fx().to_f32()
+    }
+
+    /// Returns a normalized vector pointing in the direction of this angle.
+    #[inline]
+    pub fn to_direction(self) -> Vector2 {
+        Vector2::new(self.cos(), self.sin())
+    }
+
+    /// Quantizes this angle to one of a number of evenly spaced facing directions. See
+    /// [facing_direction].
+    #[inline]
+    pub fn facing_direction(self, num_directions: u32) -> u32 {
+        (self.0 as u32 * num_directions + Self::STEPS / 2) / Self::STEPS % num_directions
+    }
+
+    #[inline]
+    fn fx32_steps(self) -> u32 {
+        self.0 as u32 * (FX32_ANGLE_STEPS / Self::STEPS)
+    }
+}
+
+impl Add for BinaryAngle {
+    type Output = BinaryAngle;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        BinaryAngle(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl AddAssign for BinaryAngle {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for BinaryAngle {
+    type Output = BinaryAngle;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        BinaryAngle(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl SubAssign for BinaryAngle {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for BinaryAngle {
+    type Output = BinaryAngle;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        BinaryAngle(self.0.wrapping_neg())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_normalize() {
+        assert!(normalize_radians(RADIANS_90).nearly_equal(RADIANS_90, 0.0001));
+        assert!(normalize_radians(-RADIANS_90).nearly_equal(RADIANS_270, 0.0001));
+        assert!(normalize_radians(RADIANS_360 * 3.0 + RADIANS_90).nearly_equal(RADIANS_90, 0.0001));
+        assert!(normalize_radians(RADIANS_360).nearly_equal(RADIANS_0, 0.0001));
+        assert!(normalize_radians(-0.0000001) < RADIANS_360);
+
+        assert!(normalize_radians_signed(RADIANS_270).nearly_equal(-RADIANS_90, 0.0001));
+        let angle = RADIANS_45 - RADIANS_360 * 2.0;
+        assert!(normalize_radians_signed(angle).nearly_equal(RADIANS_45, 0.0001));
+
+        assert!(angle_difference(RADIANS_315, RADIANS_45).nearly_equal(RADIANS_90, 0.0001));
+        assert!(angle_difference(RADIANS_45, RADIANS_315).nearly_equal(-RADIANS_90, 0.0001));
+    }
+
+    #[test]
+    pub fn test_facing_direction() {
+        assert_eq!(0, facing_direction_8(RIGHT));
+        assert_eq!(2, facing_direction_8(DOWN));
+        assert_eq!(4, facing_direction_8(LEFT));
+        assert_eq!(6, facing_direction_8(UP));
+        assert_eq!(0, facing_direction_8(-0.3));
+        assert_eq!(1, facing_direction_8(RADIANS_45 - 0.3));
+        assert_eq!(0, facing_direction_8(RADIANS_360 - 0.1));
+        assert_eq!(15, facing_direction_16(-RADIANS_45 / 2.0));
+        assert_eq!(0, facing_direction(RADIANS_180, 1));
+
+        assert_eq!(2, BinaryAngle::DOWN.facing_direction(8));
+        assert_eq!(0, BinaryAngle(250).facing_direction(8));
+        assert_eq!(1, BinaryAngle(20).facing_direction(8));
+    }
+
+    #[test]
+    pub fn test_binary_angle() {
+        assert_eq!(BinaryAngle::DOWN, BinaryAngle::from_radians(DOWN));
+        assert_eq!(BinaryAngle::UP, BinaryAngle::from_radians(UP));
+        assert_eq!(
+            BinaryAngle::RIGHT,
+            BinaryAngle::from_radians(RADIANS_360 - 0.001)
+        );
+        assert_eq!(
+            BinaryAngle::LEFT,
+            BinaryAngle::between(10.0, 10.0, 0.0, 10.0)
+        );
+        assert!(nearly_equal(
+            RADIANS_180,
+            BinaryAngle::LEFT.to_radians(),
+            0.0001
+        ));
+
+        assert_eq!(BinaryAngle(10), BinaryAngle(250) + BinaryAngle(16));
+        assert_eq!(BinaryAngle(250), BinaryAngle(10) - BinaryAngle(16));
+        assert_eq!(BinaryAngle::UP, -BinaryAngle::DOWN);
+
+        assert_eq!(Fx32::ONE, BinaryAngle::DOWN.sin_fx());
+        assert_eq!(-Fx32::ONE, BinaryAngle::LEFT.cos_fx());
+        assert!(BinaryAngle::UP
+            .to_direction()
+            .nearly_equal(Vector2::new(0.0, -1.0), 0.0001));
+        for i in 0..=255 {
+            let angle = BinaryAngle(i);
+            let radians = angle.to_radians();
+            assert!(nearly_equal(radians.sin(), angle.sin(), 0.0001));
+            assert!(nearly_equal(radians.cos(), angle.cos(), 0.0001));
+        }
+    }
+}
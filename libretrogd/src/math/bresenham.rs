@@ -0,0 +1,111 @@
+/// An iterator over the points of a line between two points, using Bresenham's line algorithm.
+/// This walks lines exactly the same way that [Bitmap::line] draws them, so gameplay code (such
+/// as line-of-sight checks or projectile stepping) can be sure to agree with what is rendered.
+/// Both the start and end points are included.
+///
+/// [Bitmap::line]: crate::graphics::Bitmap::line
+#[derive(Debug, Clone)]
+pub struct BresenhamIter {
+    x: i32,
+    y: i32,
+    delta_x_abs: i32,
+    delta_y_abs: i32,
+    delta_x_sign: i32,
+    delta_y_sign: i32,
+    accumulator: i32,
+    remaining: i32,
+}
+
+impl BresenhamIter {
+    /// Creates a new iterator over the points of the line from x1,y1 to x2,y2.
+    pub fn new(x1: i32, y1: i32, x2: i32, y2: i32) -> BresenhamIter {
+        let delta_x = x2 - x1;
+        let delta_y = y2 - y1;
+        let delta_x_abs = delta_x.abs();
+        let delta_y_abs = delta_y.abs();
+        // the accumulator for the minor axis starts off at half of that same axis' delta
+        let accumulator = if delta_x_abs >= delta_y_abs {
+            delta_y_abs / 2
+        } else {
+            delta_x_abs / 2
+        };
+        BresenhamIter {
+            x: x1,
+            y: y1,
+            delta_x_abs,
+            delta_y_abs,
+            delta_x_sign: delta_x.signum(),
+            delta_y_sign: delta_y.signum(),
+            accumulator,
+            remaining: delta_x_abs.max(delta_y_abs) + 1,
+        }
+    }
+}
+
+impl Iterator for BresenhamIter {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining <= 0 {
+            return None;
+        }
+
+        let point = (self.x, self.y);
+        self.remaining -= 1;
+
+        // step towards the next point, if there is one
+        if self.remaining > 0 {
+            if self.delta_x_abs >= self.delta_y_abs {
+                self.accumulator += self.delta_y_abs;
+                if self.accumulator >= self.delta_x_abs {
+                    self.accumulator -= self.delta_x_abs;
+                    self.y += self.delta_y_sign;
+                }
+                self.x += self.delta_x_sign;
+            } else {
+                self.accumulator += self.delta_x_abs;
+                if self.accumulator >= self.delta_y_abs {
+                    self.accumulator -= self.delta_y_abs;
+                    self.x += self.delta_x_sign;
+                }
+                self.y += self.delta_y_sign;
+            }
+        }
+
+        Some(point)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining.max(0) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for BresenhamIter {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn walks_lines() {
+        let points: Vec<(i32, i32)> = BresenhamIter::new(3, 3, 3, 3).collect();
+        assert_eq!(vec![(3, 3)], points);
+
+        let points: Vec<(i32, i32)> = BresenhamIter::new(0, 0, 3, 0).collect();
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0), (3, 0)], points);
+
+        let points: Vec<(i32, i32)> = BresenhamIter::new(0, 2, 0, -1).collect();
+        assert_eq!(vec![(0, 2), (0, 1), (0, 0), (0, -1)], points);
+
+        let points: Vec<(i32, i32)> = BresenhamIter::new(0, 0, 4, 2).collect();
+        assert_eq!(vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 2)], points);
+
+        let points: Vec<(i32, i32)> = BresenhamIter::new(2, 0, 0, -4).collect();
+        assert_eq!(vec![(2, 0), (2, -1), (1, -2), (1, -3), (0, -4)], points);
+
+        let iter = BresenhamIter::new(-5, 10, 20, -3);
+        assert_eq!(26, iter.len());
+        assert_eq!(Some((20, -3)), iter.last());
+    }
+}
@@ -0,0 +1,396 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// The number of steps in a full circle used by the fixed-point trigonometry lookup tables.
+pub const FX32_ANGLE_STEPS: u32 = 1024;
+
+/// A signed 16.16 fixed-point number. That is, a number with 16 bits for the whole number part
+/// (including the sign) and 16 bits for the fractional part, allowing values from -32768 up to
+/// just under 32768 with a precision of 1/65536.
+///
+/// All arithmetic on these values is done using integer math, so the results are exactly the
+/// same on all platforms, which is useful for deterministic simulations (such as for replays or
+/// networking). Trigonometry functions use lookup tables for the same reason.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Fx32(i32);
+
+impl Fx32 {
+    pub const FRACTION_BITS: u32 = 16;
+    pub const ZERO: Fx32 = Fx32(0);
+    pub const ONE: Fx32 = Fx32(1 << Self::FRACTION_BITS);
+    pub const HALF: Fx32 = Fx32(1 << (Self::FRACTION_BITS - 1));
+    pub const MIN: Fx32 = Fx32(i32::MIN);
+    pub const MAX: Fx32 = Fx32(i32::MAX);
+    pub const PI: Fx32 = Fx32(205887);
+    pub const HALF_PI: Fx32 = Fx32(102944);
+    pub const TWO_PI: Fx32 = Fx32(411775);
+
+    /// Creates a value from its raw 16.16 fixed-point bits.
+    #[inline]
+    pub const fn from_raw(raw: i32) -> Fx32 {
+        Fx32(raw)
+    }
+
+    /// Returns the raw 16.16 fixed-point bits of this value.
+    #[inline]
+    pub const fn raw(self) -> i32 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn from_int(value: i32) -> Fx32 {
+        Fx32(value << Self::FRACTION_BITS)
+    }
+
+    #[inline]
+    pub fn from_f32(value: f32) -> Fx32 {
+        Fx32((value * Self::ONE.0 as f32).round() as i32)
+    }
+
+    #[inline]
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / Self::ONE.0 as f32
+    }
+
+    /// Returns the whole number part of this value, rounded down (towards negative infinity).
+    #[inline]
+    pub const fn to_int(self) -> i32 {
+        self.0 >> Self::FRACTION_BITS
+    }
+
+    /// Returns this value rounded to the nearest whole number.
+    #[inline]
+    pub const fn round_to_int(self) -> i32 {
+        (self.0 + Self::HALF.0) >> Self::FRACTION_BITS
+    }
+
+    #[inline]
+    pub const fn floor(self) -> Fx32 {
+        Fx32(self.0 & !(Self::ONE.0 - 1))
+    }
+
+    #[inline]
+    pub const fn ceil(self) -> Fx32 {
+        Fx32((self.0 + (Self::ONE.0 - 1)) & !(Self::ONE.0 - 1))
+    }
+
+    /// Returns the fractional part of this value, which is always positive.
+    #[inline]
+    pub const fn fract(self) -> Fx32 {
+        Fx32(self.0 & (Self::ONE.0 - 1))
+    }
+
+    #[inline]
+    pub const fn abs(self) -> Fx32 {
+        Fx32(self.0.abs())
+    }
+
+    /// Returns the square root of this value, or zero if this value is negative.
+    pub fn sqrt(self) -> Fx32 {
+        if self.0 <= 0 {
+            return Fx32::ZERO;
+        }
+        // sqrt(raw / 2^16) * 2^16 == sqrt(raw * 2^16)
+        let value = (self.0 as u64) << Self::FRACTION_BITS;
+        let mut result = 0u64;
+        let mut bit = 1u64 << 62;
+        while bit > value {
+            bit >>= 2;
+        }
+        let mut remainder = value;
+        while bit != 0 {
+            if remainder >= result + bit {
+                remainder -= result + bit;
+                result = (result >> 1) + bit;
+            } else {
+                result >>= 1;
+            }
+            bit >>= 2;
+        }
+        Fx32(result as i32)
+    }
+
+    /// Converts this value, an angle in radians, to the nearest of the [FX32_ANGLE_STEPS] steps in
+    /// a full circle used by the trigonometry lookup tables.
+    #[inline]
+    pub fn to_angle_steps(self) -> u32 {
+        let steps = (self.0 as i64 * FX32_ANGLE_STEPS as i64 + (Self::TWO_PI.0 as i64 / 2))
+            .div_euclid(Self::TWO_PI.0 as i64);
+        steps.rem_euclid(FX32_ANGLE_STEPS as i64) as u32
+    }
+
+    /// Returns the sine of this value, an angle in radians. Uses a lookup table, so the angle is
+    /// rounded to the nearest of [FX32_ANGLE_STEPS] steps in a full circle.
+    #[inline]
+    pub fn sin(self) -> Fx32 {
+        fx32_sin_steps(self.to_angle_steps())
+    }
+
+    /// Returns the cosine of this value, an angle in radians. Uses a lookup table, so the angle is
+    /// rounded to the nearest of [FX32_ANGLE_STEPS] steps in a full circle.
+    #[inline]
+    pub fn cos(self) -> Fx32 {
+        fx32_cos_steps(self.to_angle_steps())
+    }
+}
+
+/// Returns the sine of an angle given as a number of steps out of [FX32_ANGLE_STEPS] in a full
+/// circle. Angles beyond a full circle wrap around.
+pub fn fx32_sin_steps(steps: u32) -> Fx32 {
+    let quarter = FX32_ANGLE_STEPS / 4;
+    let steps = steps % FX32_ANGLE_STEPS;
+    let index = (steps % quarter) as usize;
+    match steps / quarter {
+        0 => Fx32(QUARTER_SINE_TABLE[index]),
+        1 => Fx32(QUARTER_SINE_TABLE[quarter as usize - index]),
+        2 => Fx32(-QUARTER_SINE_TABLE[index]),
+        _ => Fx32(-QUARTER_SINE_TABLE[quarter as usize - index]),
+    }
+}
+
+/// Returns the cosine of an angle given as a number of steps out of [FX32_ANGLE_STEPS] in a full
+/// circle. Angles beyond a full circle wrap around.
+#[inline]
+pub fn fx32_cos_steps(steps: u32) -> Fx32 {
+    fx32_sin_steps(steps.wrapping_add(FX32_ANGLE_STEPS / 4))
+}
+
+impl Display for Fx32 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_f32())
+    }
+}
+
+impl From<i32> for Fx32 {
+    #[inline]
+    fn from(value: i32) -> Self {
+        Fx32::from_int(value)
+    }
+}
+
+impl From<f32> for Fx32 {
+    #[inline]
+    fn from(value: f32) -> Self {
+        Fx32::from_f32(value)
+    }
+}
+
+impl From<Fx32> for f32 {
+    #[inline]
+    fn from(value: Fx32) -> Self {
+        value.to_f32()
+    }
+}
+
+impl Neg for Fx32 {
+    type Output = Fx32;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Fx32(self.0.wrapping_neg())
+    }
+}
+
+impl Add for Fx32 {
+    type Output = Fx32;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Fx32(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl AddAssign for Fx32 {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Fx32 {
+    type Output = Fx32;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Fx32(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl SubAssign for Fx32 {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul for Fx32 {
+    type Output = Fx32;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Fx32(((self.0 as i64 * rhs.0 as i64) >> Fx32::FRACTION_BITS) as i32)
+    }
+}
+
+impl MulAssign for Fx32 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<i32> for Fx32 {
+    type Output = Fx32;
+
+    #[inline]
+    fn mul(self, rhs: i32) -> Self::Output {
+        Fx32(self.0.wrapping_mul(rhs))
+    }
+}
+
+impl Div for Fx32 {
+    type Output = Fx32;
+
+    /// Divides two fixed-point values. Panics if `rhs` is zero.
+    #[inline]
+    fn div(self, rhs: Self) -> Self::Output {
+        Fx32((((self.0 as i64) << Fx32::FRACTION_BITS) / rhs.0 as i64) as i32)
+    }
+}
+
+impl DivAssign for Fx32 {
+    #[inline]
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl Div<i32> for Fx32 {
+    type Output = Fx32;
+
+    /// Divides a fixed-point value by an integer. Panics if `rhs` is zero.
+    #[inline]
+    fn div(self, rhs: i32) -> Self::Output {
+        Fx32(self.0 / rhs)
+    }
+}
+
+/// Sine values (in 16.16 fixed-point) for the first quarter of a circle divided into
+/// [FX32_ANGLE_STEPS] steps, including the 90 degree step at the end. Hard-coded instead of being
+/// calculated at runtime so that the values are the same on all platforms.
+#[rustfmt::skip]
+static QUARTER_SINE_TABLE: [i32; (FX32_ANGLE_STEPS / 4) as usize + 1] = [
+    0, 402, 804, 1206, 1608, 2010, 2412, 2814,
+    3216, 3617, 4019, 4420, 4821, 5222, 5623, 6023,
+    6424, 6824, 7224, 7623, 8022, 8421, 8820, 9218,
+    9616, 10014, 10411, 10808, 11204, 11600, 11996, 12391,
+    12785, 13180, 13573, 13966, 14359, 14751, 15143, 15534,
+    15924, 16314, 16703, 17091, 17479, 17867, 18253, 18639,
+    19024, 19409, 19792, 20175, 20557, 20939, 21320, 21699,
+    22078, 22457, 22834, 23210, 23586, 23961, 24335, 24708,
+    25080, 25451, 25821, 26190, 26558, 26925, 27291, 27656,
+    28020, 28383, 28745, 29106, 29466, 29824, 30182, 30538,
+    30893, 31248, 31600, 31952, 32303, 32652, 33000, 33347,
+    33692, 34037, 34380, 34721, 35062, 35401, 35738, 36075,
+    36410, 36744, 37076, 37407, 37736, 38064, 38391, 38716,
+    39040, 39362, 39683, 40002, 40320, 40636, 40951, 41264,
+    41576, 41886, 42194, 42501, 42806, 43110, 43412, 43713,
+    44011, 44308, 44604, 44898, 45190, 45480, 45769, 46056,
+    46341, 46624, 46906, 47186, 47464, 47741, 48015, 48288,
+    48559, 48828, 49095, 49361, 49624, 49886, 50146, 50404,
+    50660, 50914, 51166, 51417, 51665, 51911, 52156, 52398,
+    52639, 52878, 53114, 53349, 53581, 53812, 54040, 54267,
+    54491, 54714, 54934, 55152, 55368, 55582, 55794, 56004,
+    56212, 56418, 56621, 56823, 57022, 57219, 57414, 57607,
+    57798, 57986, 58172, 58356, 58538, 58718, 58896, 59071,
+    59244, 59415, 59583, 59750, 59914, 60075, 60235, 60392,
+    60547, 60700, 60851, 60999, 61145, 61288, 61429, 61568,
+    61705, 61839, 61971, 62101, 62228, 62353, 62476, 62596,
+    62714, 62830, 62943, 63054, 63162, 63268, 63372, 63473,
+    63572, 63668, 63763, 63854, 63944, 64031, 64115, 64197,
+    64277, 64354, 64429, 64501, 64571, 64639, 64704, 64766,
+    64827, 64884, 64940, 64993, 65043, 65091, 65137, 65180,
+    65220, 65259, 65294, 65328, 65358, 65387, 65413, 65436,
+    65457, 65476, 65492, 65505, 65516, 65525, 65531, 65535,
+    65536,
+];
+
+#[cfg(test)]
+pub mod tests {
+    use crate::math::*;
+
+    #[test]
+    pub fn conversions() {
+        assert_eq!(Fx32::ONE, Fx32::from_int(1));
+        assert_eq!(Fx32::from_raw(0x28000), Fx32::from_f32(2.5));
+        assert_eq!(Fx32::from(-3), Fx32::from_f32(-3.0));
+        assert_eq!(2.5, f32::from(Fx32::from_raw(0x28000)));
+        assert_eq!(2, Fx32::from_f32(2.5).to_int());
+        assert_eq!(3, Fx32::from_f32(2.5).round_to_int());
+        assert_eq!(-3, Fx32::from_f32(-2.5).to_int());
+        assert_eq!(Fx32::from_int(2), Fx32::from_f32(2.75).floor());
+        assert_eq!(Fx32::from_int(3), Fx32::from_f32(2.25).ceil());
+        assert_eq!(Fx32::from_f32(0.75), Fx32::from_f32(2.75).fract());
+        assert_eq!(Fx32::from_f32(1.5), Fx32::from_f32(-1.5).abs());
+        assert_eq!("1.25", Fx32::from_f32(1.25).to_string());
+    }
+
+    #[test]
+    pub fn arithmetic() {
+        let a = Fx32::from_f32(2.5);
+        let b = Fx32::from_f32(-1.25);
+        assert_eq!(Fx32::from_f32(1.25), a + b);
+        assert_eq!(Fx32::from_f32(3.75), a - b);
+        assert_eq!(Fx32::from_f32(-3.125), a * b);
+        assert_eq!(Fx32::from_f32(-2.0), a / b);
+        assert_eq!(Fx32::from_f32(7.5), a * 3);
+        assert_eq!(Fx32::from_f32(1.25), a / 2);
+        assert_eq!(Fx32::from_f32(-2.5), -a);
+
+        let mut c = a;
+        c += b;
+        c -= Fx32::ONE;
+        c *= Fx32::from_int(4);
+        c /= Fx32::from_int(2);
+        assert_eq!(Fx32::from_f32(0.5), c);
+
+        assert_eq!(Fx32::from_int(3), Fx32::from_int(9).sqrt());
+        assert_eq!(Fx32::from_f32(1.5), Fx32::from_f32(2.25).sqrt());
+        assert!(nearly_equal(
+            std::f32::consts::SQRT_2,
+            Fx32::from_int(2).sqrt().to_f32(),
+            0.0001
+        ));
+        assert_eq!(Fx32::ZERO, Fx32::from_int(-4).sqrt());
+    }
+
+    #[test]
+    pub fn trig() {
+        assert_eq!(Fx32::ZERO, Fx32::ZERO.sin());
+        assert_eq!(Fx32::ONE, Fx32::ZERO.cos());
+        assert_eq!(Fx32::ONE, Fx32::HALF_PI.sin());
+        assert_eq!(-Fx32::ONE, Fx32::PI.cos());
+        assert_eq!(-Fx32::ONE, (-Fx32::HALF_PI).sin());
+        assert_eq!(Fx32::ONE, (Fx32::TWO_PI * 3 + Fx32::HALF_PI).sin());
+
+        for degrees in (-720..=720).step_by(15) {
+            let radians = degrees as f32 * PI_OVER_180;
+            let angle = Fx32::from_f32(radians);
+            assert!(
+                nearly_equal(radians.sin(), angle.sin().to_f32(), 0.005),
+                "sin({})",
+                degrees
+            );
+            assert!(
+                nearly_equal(radians.cos(), angle.cos().to_f32(), 0.005),
+                "cos({})",
+                degrees
+            );
+        }
+
+        assert_eq!(0, Fx32::TWO_PI.to_angle_steps());
+        assert_eq!(256, Fx32::HALF_PI.to_angle_steps());
+        assert_eq!(768, (-Fx32::HALF_PI).to_angle_steps());
+        assert_eq!(fx32_sin_steps(100), fx32_cos_steps(1180));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::math::*;
+
+/// Calculates the point where two line segments intersect.
+///
+/// # Arguments
+///
+/// * `a1`: the start point of the first segment
+/// * `a2`: the end point of the first segment
+/// * `b1`: the start point of the second segment
+/// * `b2`: the end point of the second segment
+///
+/// returns: the point of intersection, or `None` if the segments do not intersect. Parallel
+/// segments (including overlapping collinear segments) are treated as not intersecting.
+pub fn segment_intersection(a1: Vector2, a2: Vector2, b1: Vector2, b2: Vector2) -> Option<Vector2> {
+    let a = a2 - a1;
+    let b = b2 - b1;
+    let denominator = a.x * b.y - a.y * b.x;
+    if nearly_equal(denominator, 0.0, 0.000001) {
+        return None;
+    }
+
+    let offset = b1 - a1;
+    let t = (offset.x * b.y - offset.y * b.x) / denominator;
+    let u = (offset.x * a.y - offset.y * a.x) / denominator;
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(a1 + a * t)
+    } else {
+        None
+    }
+}
+
+/// Returns true if the circle at least partially overlaps the rectangle. As with
+/// [Circle::contains_point], points exactly `radius` away from the circle's center count as
+/// overlapping.
+pub fn circle_overlaps_rect(circle: &Circle, rect: &Rect) -> bool {
+    // find the point within the rect that is closest to the circle's center
+    let closest_x = circle.x.clamp(rect.x, rect.right()) as i64;
+    let closest_y = circle.y.clamp(rect.y, rect.bottom()) as i64;
+    let dx = circle.x as i64 - closest_x;
+    let dy = circle.y as i64 - closest_y;
+    let radius = circle.radius as i64;
+    (dx * dx + dy * dy) <= radius * radius
+}
+
+/// Returns true if the point is inside the polygon. The polygon's vertices can be given in either
+/// winding order and the last vertex is implicitly connected back to the first. Self-intersecting
+/// polygons use the even-odd rule.
+///
+/// # Arguments
+///
+/// * `point`: the point to test
+/// * `polygon`: the vertices of the polygon
+pub fn point_in_polygon(point: Vector2, polygon: &[Vector2]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+
+    // count how many polygon edges a horizontal ray cast to the right of the point crosses
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for &current in polygon.iter() {
+        if (current.y > point.y) != (previous.y > point.y) {
+            let crossing_x = current.x
+                + (point.y - current.y) * (previous.x - current.x) / (previous.y - current.y);
+            if point.x < crossing_x {
+                inside = !inside;
+            }
+        }
+        previous = current;
+    }
+    inside
+}
+
+/// Casts a ray against a rectangle using the "slab" method. The rectangle is treated as covering
+/// the area from its top-left corner to the far edges of its right-most and bottom-most pixels
+/// (that is, from `x` to `x + width`, and `y` to `y + height`).
+///
+/// # Arguments
+///
+/// * `origin`: the point the ray is cast from
+/// * `direction`: the direction the ray is cast in. does not need to be normalized, but the
+///   returned distance is in multiples of this vector's length
+///
+/// returns: the distance along the ray where it first enters the rectangle, or `None` if the ray
+/// misses the rectangle. If the ray's origin is inside the rectangle, the distance is zero.
+pub fn ray_rect_intersection(origin: Vector2, direction: Vector2, rect: &Rect) -> Option<f32> {
+    let min = Vector2::new(rect.x as f32, rect.y as f32);
+    let max = Vector2::new(
+        rect.x as f32 + rect.width as f32,
+        rect.y as f32 + rect.height as f32,
+    );
+
+    let mut t_near = 0.0f32;
+    let mut t_far = f32::INFINITY;
+    for (origin, direction, min, max) in [
+        (origin.x, direction.x, min.x, max.x),
+        (origin.y, direction.y, min.y, max.y),
+    ] {
+        if direction == 0.0 {
+            // parallel to this slab, so the ray can only hit if it already lies within it
+            if origin < min || origin > max {
+                return None;
+            }
+        } else {
+            let t1 = (min - origin) / direction;
+            let t2 = (max - origin) / direction;
+            t_near = t_near.max(t1.min(t2));
+            t_far = t_far.min(t1.max(t2));
+            if t_near > t_far {
+                return None;
+            }
+        }
+    }
+    Some(t_near)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_segment_intersection() {
+        let p = segment_intersection(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(10.0, 10.0),
+            Vector2::new(0.0, 10.0),
+            Vector2::new(10.0, 0.0),
+        );
+        assert!(p.unwrap().nearly_equal(Vector2::new(5.0, 5.0), 0.0001));
+
+        // touching at the end points
+        let p = segment_intersection(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(4.0, 0.0),
+            Vector2::new(4.0, 0.0),
+            Vector2::new(4.0, 4.0),
+        );
+        assert!(p.unwrap().nearly_equal(Vector2::new(4.0, 0.0), 0.0001));
+
+        // the lines would cross, but the segments stop short
+        let p = segment_intersection(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(4.0, 0.0),
+            Vector2::new(6.0, -2.0),
+            Vector2::new(6.0, 2.0),
+        );
+        assert_eq!(None, p);
+
+        // parallel
+        let p = segment_intersection(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(4.0, 0.0),
+            Vector2::new(0.0, 1.0),
+            Vector2::new(4.0, 1.0),
+        );
+        assert_eq!(None, p);
+    }
+
+    #[test]
+    pub fn test_circle_overlaps_rect() {
+        let rect = Rect::new(10, 10, 10, 10);
+        assert!(circle_overlaps_rect(&Circle::new(15, 15, 1), &rect));
+        assert!(circle_overlaps_rect(&Circle::new(5, 15, 5), &rect));
+        assert!(!circle_overlaps_rect(&Circle::new(5, 15, 4), &rect));
+        assert!(circle_overlaps_rect(&Circle::new(21, 21, 3), &rect));
+        assert!(!circle_overlaps_rect(&Circle::new(21, 21, 2), &rect));
+        assert!(circle_overlaps_rect(&Circle::new(15, 15, 100), &rect));
+    }
+
+    #[test]
+    pub fn test_point_in_polygon() {
+        let square = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(10.0, 0.0),
+            Vector2::new(10.0, 10.0),
+            Vector2::new(0.0, 10.0),
+        ];
+        assert!(point_in_polygon(Vector2::new(5.0, 5.0), &square));
+        assert!(!point_in_polygon(Vector2::new(15.0, 5.0), &square));
+        assert!(!point_in_polygon(Vector2::new(-1.0, 5.0), &square));
+
+        // concave "L" shape, with the opposite winding order
+        let l_shape = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(0.0, 10.0),
+            Vector2::new(10.0, 10.0),
+            Vector2::new(10.0, 7.0),
+            Vector2::new(3.0, 7.0),
+            Vector2::new(3.0, 0.0),
+        ];
+        assert!(point_in_polygon(Vector2::new(1.0, 1.0), &l_shape));
+        assert!(point_in_polygon(Vector2::new(8.0, 8.0), &l_shape));
+        assert!(!point_in_polygon(Vector2::new(8.0, 2.0), &l_shape));
+
+        assert!(!point_in_polygon(Vector2::new(0.0, 0.0), &square[0..2]));
+    }
+
+    #[test]
+    pub fn test_ray_rect_intersection() {
+        let rect = Rect::new(10, 10, 10, 10);
+
+        let t = ray_rect_intersection(Vector2::new(0.0, 15.0), Vector2::new(1.0, 0.0), &rect);
+        assert!(nearly_equal(10.0, t.unwrap(), 0.0001));
+
+        let t = ray_rect_intersection(Vector2::new(0.0, 0.0), Vector2::new(2.0, 2.0), &rect);
+        assert!(nearly_equal(5.0, t.unwrap(), 0.0001));
+
+        // pointing away from the rect
+        let t = ray_rect_intersection(Vector2::new(0.0, 15.0), Vector2::new(-1.0, 0.0), &rect);
+        assert_eq!(None, t);
+
+        // parallel to, and outside of, one of the slabs
+        let t = ray_rect_intersection(Vector2::new(0.0, 25.0), Vector2::new(1.0, 0.0), &rect);
+        assert_eq!(None, t);
+
+        // starting inside the rect
+        let t = ray_rect_intersection(Vector2::new(15.0, 15.0), Vector2::new(0.0, -1.0), &rect);
+        assert_eq!(Some(0.0), t);
+    }
+}
@@ -0,0 +1,348 @@
+use std::ops::{Mul, MulAssign};
+
+use crate::math::*;
+
+/// Represents a 2D affine transformation as a 2x3 matrix (a 3x3 matrix with the last row
+/// implied to always be `0, 0, 1`). Points are treated as column vectors, so a point is
+/// transformed as:
+///
+/// ```text
+/// x' = m11 * x + m12 * y + m13
+/// y' = m21 * x + m22 * y + m23
+/// ```
+///
+/// As with [Matrix3x3], matrices are concatenated "right-to-left". That is, `a * b` results in a
+/// matrix which applies the transformation `b` first, followed by `a`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix3x2 {
+    pub m: [f32; 6],
+}
+
+impl Matrix3x2 {
+    pub const M11: usize = 0;
+    pub const M12: usize = 1;
+    pub const M13: usize = 2;
+    pub const M21: usize = 3;
+    pub const M22: usize = 4;
+    pub const M23: usize = 5;
+
+    pub const IDENTITY: Matrix3x2 = Matrix3x2 {
+        m: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+    };
+
+    /// Returns a new identity matrix.
+    #[inline]
+    pub fn identity() -> Matrix3x2 {
+        Matrix3x2::IDENTITY
+    }
+
+    /// Creates a new matrix with the specified elements.
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new(
+        m11: f32, m12: f32, m13: f32,
+        m21: f32, m22: f32, m23: f32,
+    ) -> Matrix3x2 {
+        Matrix3x2 {
+            m: [
+                m11, m12, m13,
+                m21, m22, m23,
+            ],
+        }
+    }
+
+    /// Creates a translation matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: the amount to translate on the x axis
+    /// * `y`: the amount to translate on the y axis
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_translation(x: f32, y: f32) -> Matrix3x2 {
+        Matrix3x2::new(
+            1.0, 0.0, x,
+            0.0, 1.0, y
+        )
+    }
+
+    /// Creates a scaling matrix from scaling factors for each axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: the scale factor for the x axis
+    /// * `y`: the scale factor for the y axis
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_scaling(x: f32, y: f32) -> Matrix3x2 {
+        Matrix3x2::new(
+            x, 0.0, 0.0,
+            0.0, y, 0.0
+        )
+    }
+
+    /// Creates a rotation matrix. With screen coordinates (where y increases downwards), positive
+    /// angles rotate clockwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `radians`: angle to rotate by (in radians)
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_rotation(radians: f32) -> Matrix3x2 {
+        let (s, c) = radians.sin_cos();
+        Matrix3x2::new(
+            c, -s, 0.0,
+            s, c, 0.0
+        )
+    }
+
+    /// Creates a matrix that positions an image the same way that the [BlitMethod::RotoZoom]
+    /// family of blits do. That is, the image is scaled and then rotated about its center, and the
+    /// result is centered within the scaled (but unrotated) image's bounds placed at the given
+    /// destination coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the source image
+    /// * `height`: the height of the source image
+    /// * `dest_x`: the x (left) coordinate of the scaled image's bounds on the destination
+    /// * `dest_y`: the y (top) coordinate of the scaled image's bounds on the destination
+    /// * `angle`: angle to rotate by (in radians)
+    /// * `scale_x`: the scale factor for the x axis
+    /// * `scale_y`: the scale factor for the y axis
+    ///
+    /// [BlitMethod::RotoZoom]: crate::graphics::BlitMethod::RotoZoom
+    pub fn new_rotozoom(
+        width: u32,
+        height: u32,
+        dest_x: i32,
+        dest_y: i32,
+        angle: f32,
+        scale_x: f32,
+        scale_y: f32,
+    ) -> Matrix3x2 {
+        let half_width = width as f32 * 0.5;
+        let half_height = height as f32 * 0.5;
+        Matrix3x2::new_translation(
+            dest_x as f32 + half_width * scale_x,
+            dest_y as f32 + half_height * scale_y,
+        ) * Matrix3x2::new_rotation(angle)
+            * Matrix3x2::new_scaling(scale_x, scale_y)
+            * Matrix3x2::new_translation(-half_width, -half_height)
+    }
+
+    /// Returns a new matrix which applies a translation after this matrix's transformation.
+    #[inline]
+    pub fn translate(&self, x: f32, y: f32) -> Matrix3x2 {
+        Matrix3x2::new_translation(x, y) * *self
+    }
+
+    /// Returns a new matrix which applies a scale after this matrix's transformation.
+    #[inline]
+    pub fn scale(&self, x: f32, y: f32) -> Matrix3x2 {
+        Matrix3x2::new_scaling(x, y) * *self
+    }
+
+    /// Returns a new matrix which applies a rotation (in radians) after this matrix's
+    /// transformation.
+    #[inline]
+    pub fn rotate(&self, radians: f32) -> Matrix3x2 {
+        Matrix3x2::new_rotation(radians) * *self
+    }
+
+    /// Returns a new matrix which applies this matrix's transformation followed by the other
+    /// matrix's transformation. Equivalent to `*other * *self`.
+    #[inline]
+    pub fn then(&self, other: &Matrix3x2) -> Matrix3x2 {
+        *other * *self
+    }
+
+    /// Calculates the determinant of this matrix.
+    #[inline]
+    pub fn determinant(&self) -> f32 {
+        self.m[Matrix3x2::M11] * self.m[Matrix3x2::M22]
+            - self.m[Matrix3x2::M12] * self.m[Matrix3x2::M21]
+    }
+
+    /// Calculates the inverse of this matrix.
+    ///
+    /// returns: the inverted matrix, or `None` if this matrix cannot be inverted (e.g. it scales
+    /// an axis down to zero)
+    #[rustfmt::skip]
+    pub fn invert(&self) -> Option<Matrix3x2> {
+        let d = self.determinant();
+        if nearly_equal(d, 0.0, 0.000001) {
+            None
+        } else {
+            let d = 1.0 / d;
+            let m11 = self.m[Matrix3x2::M22] * d;
+            let m12 = -self.m[Matrix3x2::M12] * d;
+            let m21 = -self.m[Matrix3x2::M21] * d;
+            let m22 = self.m[Matrix3x2::M11] * d;
+            Some(Matrix3x2::new(
+                m11, m12, -(m11 * self.m[Matrix3x2::M13] + m12 * self.m[Matrix3x2::M23]),
+                m21, m22, -(m21 * self.m[Matrix3x2::M13] + m22 * self.m[Matrix3x2::M23]),
+            ))
+        }
+    }
+
+    /// Transforms a point by this matrix, applying the full transformation including
+    /// translation.
+    #[inline]
+    pub fn transform_point(&self, point: Vector2) -> Vector2 {
+        Vector2 {
+            x: point.x * self.m[Matrix3x2::M11]
+                + point.y * self.m[Matrix3x2::M12]
+                + self.m[Matrix3x2::M13],
+            y: point.x * self.m[Matrix3x2::M21]
+                + point.y * self.m[Matrix3x2::M22]
+                + self.m[Matrix3x2::M23],
+        }
+    }
+
+    /// Transforms a direction vector by this matrix. Unlike [Matrix3x2::transform_point], the
+    /// translation is not applied.
+    #[inline]
+    pub fn transform_vector(&self, vector: Vector2) -> Vector2 {
+        Vector2 {
+            x: vector.x * self.m[Matrix3x2::M11] + vector.y * self.m[Matrix3x2::M12],
+            y: vector.x * self.m[Matrix3x2::M21] + vector.y * self.m[Matrix3x2::M22],
+        }
+    }
+}
+
+impl Default for Matrix3x2 {
+    #[inline]
+    fn default() -> Self {
+        Matrix3x2::IDENTITY
+    }
+}
+
+impl Mul for Matrix3x2 {
+    type Output = Self;
+
+    #[rustfmt::skip]
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Matrix3x2::new(
+            self.m[Matrix3x2::M11] * rhs.m[Matrix3x2::M11] + self.m[Matrix3x2::M12] * rhs.m[Matrix3x2::M21],
+            self.m[Matrix3x2::M11] * rhs.m[Matrix3x2::M12] + self.m[Matrix3x2::M12] * rhs.m[Matrix3x2::M22],
+            self.m[Matrix3x2::M11] * rhs.m[Matrix3x2::M13] + self.m[Matrix3x2::M12] * rhs.m[Matrix3x2::M23] + self.m[Matrix3x2::M13],
+            self.m[Matrix3x2::M21] * rhs.m[Matrix3x2::M11] + self.m[Matrix3x2::M22] * rhs.m[Matrix3x2::M21],
+            self.m[Matrix3x2::M21] * rhs.m[Matrix3x2::M12] + self.m[Matrix3x2::M22] * rhs.m[Matrix3x2::M22],
+            self.m[Matrix3x2::M21] * rhs.m[Matrix3x2::M13] + self.m[Matrix3x2::M22] * rhs.m[Matrix3x2::M23] + self.m[Matrix3x2::M23],
+        )
+    }
+}
+
+impl MulAssign for Matrix3x2 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<Vector2> for Matrix3x2 {
+    type Output = Vector2;
+
+    #[inline]
+    fn mul(self, rhs: Vector2) -> Self::Output {
+        self.transform_point(rhs)
+    }
+}
+
+impl NearlyEqual for Matrix3x2 {
+    type Output = Self;
+
+    #[inline]
+    fn nearly_equal(self, other: Self::Output, epsilon: f32) -> bool {
+        self.m
+            .iter()
+            .zip(other.m.iter())
+            .all(|(&a, &b)| nearly_equal(a, b, epsilon))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_new() {
+        let m = Matrix3x2::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+        assert_eq!(1.0, m.m[Matrix3x2::M11]);
+        assert_eq!(2.0, m.m[Matrix3x2::M12]);
+        assert_eq!(3.0, m.m[Matrix3x2::M13]);
+        assert_eq!(4.0, m.m[Matrix3x2::M21]);
+        assert_eq!(5.0, m.m[Matrix3x2::M22]);
+        assert_eq!(6.0, m.m[Matrix3x2::M23]);
+        assert_eq!(Matrix3x2::IDENTITY, Matrix3x2::default());
+    }
+
+    #[test]
+    pub fn test_transforms() {
+        let v = Vector2::new(10.2, 5.7);
+
+        let t = Matrix3x2::new_translation(2.0, 3.0) * v;
+        assert!(t.nearly_equal(Vector2::new(12.2, 8.7), 0.001));
+
+        let t = Matrix3x2::new_scaling(3.0, 4.0) * v;
+        assert!(t.nearly_equal(Vector2::new(30.6, 22.8), 0.001));
+
+        let t = Matrix3x2::new_rotation(RADIANS_90) * Vector2::new(1.0, 0.0);
+        assert!(t.nearly_equal(Vector2::new(0.0, 1.0), 0.0001));
+
+        let m = Matrix3x2::new_translation(2.0, 3.0);
+        assert!(m.transform_vector(v).nearly_equal(v, 0.0001));
+    }
+
+    #[test]
+    pub fn test_compose() {
+        let v = Vector2::new(1.0, 2.0);
+
+        // scale, then rotate, then translate
+        let m = Matrix3x2::identity()
+            .scale(2.0, 2.0)
+            .rotate(RADIANS_90)
+            .translate(10.0, 20.0);
+        assert!((m * v).nearly_equal(Vector2::new(6.0, 22.0), 0.0001));
+
+        let same = Matrix3x2::new_translation(10.0, 20.0)
+            * Matrix3x2::new_rotation(RADIANS_90)
+            * Matrix3x2::new_scaling(2.0, 2.0);
+        assert!(m.nearly_equal(same, 0.0001));
+
+        let then = Matrix3x2::new_scaling(2.0, 2.0).then(&Matrix3x2::new_translation(10.0, 20.0));
+        assert!((then * v).nearly_equal(Vector2::new(12.0, 24.0), 0.0001));
+
+        let mut m = Matrix3x2::new_translation(10.0, 20.0);
+        m *= Matrix3x2::new_scaling(2.0, 2.0);
+        assert!(m.nearly_equal(then, 0.0001));
+    }
+
+    #[test]
+    pub fn test_invert() {
+        let m = Matrix3x2::new_translation(10.0, -4.0)
+            * Matrix3x2::new_rotation(0.7)
+            * Matrix3x2::new_scaling(2.0, 0.5);
+        let inverse = m.invert().unwrap();
+        let v = Vector2::new(3.5, -8.25);
+        assert!((inverse * (m * v)).nearly_equal(v, 0.001));
+        assert!((m * inverse).nearly_equal(Matrix3x2::IDENTITY, 0.0001));
+
+        assert_eq!(None, Matrix3x2::new_scaling(0.0, 1.0).invert());
+    }
+
+    #[test]
+    pub fn test_rotozoom() {
+        // no rotation or scaling just places the image at the destination coordinates
+        let m = Matrix3x2::new_rotozoom(16, 8, 10, 20, 0.0, 1.0, 1.0);
+        assert!((m * Vector2::new(0.0, 0.0)).nearly_equal(Vector2::new(10.0, 20.0), 0.0001));
+        assert!((m * Vector2::new(16.0, 8.0)).nearly_equal(Vector2::new(26.0, 28.0), 0.0001));
+
+        // the center of the image always maps to the center of the scaled bounds
+        let m = Matrix3x2::new_rotozoom(16, 8, 10, 20, 1.3, 2.0, 3.0);
+        assert!((m * Vector2::new(8.0, 4.0)).nearly_equal(Vector2::new(26.0, 32.0), 0.0001));
+    }
+}
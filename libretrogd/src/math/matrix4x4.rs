@@ -0,0 +1,352 @@
+use std::ops::{Mul, MulAssign};
+
+use crate::math::*;
+
+/// Represents a 4x4 column-major matrix and provides common methods for matrix math, mainly for
+/// transforming 3D points. Points are treated as column vectors, so the translation is stored in
+/// the fourth column, and in `a * b` the transform `b` is applied first.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix4x4 {
+    pub m: [f32; 16],
+}
+
+impl Matrix4x4 {
+    pub const M11: usize = 0;
+    pub const M12: usize = 4;
+    pub const M13: usize = 8;
+    pub const M14: usize = 12;
+    pub const M21: usize = 1;
+    pub const M22: usize = 5;
+    pub const M23: usize = 9;
+    pub const M24: usize = 13;
+    pub const M31: usize = 2;
+    pub const M32: usize = 6;
+    pub const M33: usize = 10;
+    pub const M34: usize = 14;
+    pub const M41: usize = 3;
+    pub const M42: usize = 7;
+    pub const M43: usize = 11;
+    pub const M44: usize = 15;
+
+    #[rustfmt::skip]
+    pub const IDENTITY: Matrix4x4 = Matrix4x4 {
+        m: [
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        ],
+    };
+
+    /// Returns a new identity matrix.
+    #[inline]
+    pub fn identity() -> Matrix4x4 {
+        Matrix4x4::IDENTITY
+    }
+
+    /// Creates a new matrix with the specified elements.
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new(
+        m11: f32, m12: f32, m13: f32, m14: f32,
+        m21: f32, m22: f32, m23: f32, m24: f32,
+        m31: f32, m32: f32, m33: f32, m34: f32,
+        m41: f32, m42: f32, m43: f32, m44: f32,
+    ) -> Matrix4x4 {
+        Matrix4x4 {
+            m: [
+                m11, m21, m31, m41,
+                m12, m22, m32, m42,
+                m13, m23, m33, m43,
+                m14, m24, m34, m44
+            ],
+        }
+    }
+
+    /// Creates a new rotation matrix from a set of euler angles.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: the x angle (in radians)
+    /// * `y`: the y angle (in radians)
+    /// * `z`: the z angle (in radians)
+    pub fn from_euler_angles(x: f32, y: f32, z: f32) -> Matrix4x4 {
+        let rotate_z = Matrix4x4::new_rotation_z(z);
+        let rotate_y = Matrix4x4::new_rotation_y(y);
+        let rotate_x = Matrix4x4::new_rotation_x(x);
+
+        // "right-to-left" column-major matrix concatenation
+        rotate_z * rotate_y * rotate_x
+    }
+
+    /// Creates a new rotation matrix for rotation around the x axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `radians`: angle to rotate the x axis around (in radians)
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_rotation_x(radians: f32) -> Matrix4x4 {
+        let (s, c) = radians.sin_cos();
+        Matrix4x4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, c, -s, 0.0,
+            0.0, s, c, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        )
+    }
+
+    /// Creates a new rotation matrix for rotation around the y axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `radians`: angle to rotate the y axis around (in radians)
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_rotation_y(radians: f32) -> Matrix4x4 {
+        let (s, c) = radians.sin_cos();
+        Matrix4x4::new(
+            c, 0.0, s, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            -s, 0.0, c, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        )
+    }
+
+    /// Creates a new rotation matrix for rotation around the z axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `radians`: angle to rotate the z axis around (in radians)
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_rotation_z(radians: f32) -> Matrix4x4 {
+        let (s, c) = radians.sin_cos();
+        Matrix4x4::new(
+            c, -s, 0.0, 0.0,
+            s, c, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        )
+    }
+
+    /// Creates a translation matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: the amount to translate on the x axis
+    /// * `y`: the amount to translate on the y axis
+    /// * `z`: the amount to translate on the z axis
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_translation(x: f32, y: f32, z: f32) -> Matrix4x4 {
+        Matrix4x4::new(
+            1.0, 0.0, 0.0, x,
+            0.0, 1.0, 0.0, y,
+            0.0, 0.0, 1.0, z,
+            0.0, 0.0, 0.0, 1.0
+        )
+    }
+
+    /// Creates a scaling matrix from scaling factors for each axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: the scale factor for the x axis
+    /// * `y`: the scale factor for the y axis
+    /// * `z`: the scale factor for the z axis
+    #[rustfmt::skip]
+    #[inline]
+    pub fn new_scaling(x: f32, y: f32, z: f32) -> Matrix4x4 {
+        Matrix4x4::new(
+            x, 0.0, 0.0, 0.0,
+            0.0, y, 0.0, 0.0,
+            0.0, 0.0, z, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        )
+    }
+
+    /// Creates a view matrix for a camera at `eye` looking towards `target`. The returned matrix
+    /// transforms points into a space where the camera is at the origin, looking along positive
+    /// z, with positive x to its right and positive y above it.
+    ///
+    /// # Arguments
+    ///
+    /// * `eye`: the position of the camera
+    /// * `target`: the point the camera is looking at
+    /// * `up`: which direction is up for the camera, which must not be parallel to the direction
+    ///   from `eye` to `target`
+    #[rustfmt::skip]
+    pub fn new_look_at(eye: Vector3, target: Vector3, up: Vector3) -> Matrix4x4 {
+        let z_axis = (target - eye).normalize();
+        let x_axis = up.cross(&z_axis).normalize();
+        let y_axis = z_axis.cross(&x_axis);
+        Matrix4x4::new(
+            x_axis.x, x_axis.y, x_axis.z, -x_axis.dot(&eye),
+            y_axis.x, y_axis.y, y_axis.z, -y_axis.dot(&eye),
+            z_axis.x, z_axis.y, z_axis.z, -z_axis.dot(&eye),
+            0.0, 0.0, 0.0, 1.0
+        )
+    }
+
+    /// Calculates the transpose of this matrix.
+    #[rustfmt::skip]
+    #[inline]
+    pub fn transpose(&self) -> Matrix4x4 {
+        Matrix4x4::new(
+            self.m[Matrix4x4::M11], self.m[Matrix4x4::M21], self.m[Matrix4x4::M31], self.m[Matrix4x4::M41],
+            self.m[Matrix4x4::M12], self.m[Matrix4x4::M22], self.m[Matrix4x4::M32], self.m[Matrix4x4::M42],
+            self.m[Matrix4x4::M13], self.m[Matrix4x4::M23], self.m[Matrix4x4::M33], self.m[Matrix4x4::M43],
+            self.m[Matrix4x4::M14], self.m[Matrix4x4::M24], self.m[Matrix4x4::M34], self.m[Matrix4x4::M44],
+        )
+    }
+
+    /// Transforms a direction vector by this matrix. Unlike multiplying a [Vector3] by the matrix,
+    /// this ignores the matrix's translation, which is what is wanted for things like normals.
+    #[rustfmt::skip]
+    #[inline]
+    pub fn transform_direction(&self, v: Vector3) -> Vector3 {
+        Vector3 {
+            x: v.x * self.m[Matrix4x4::M11] + v.y * self.m[Matrix4x4::M12] + v.z * self.m[Matrix4x4::M13],
+            y: v.x * self.m[Matrix4x4::M21] + v.y * self.m[Matrix4x4::M22] + v.z * self.m[Matrix4x4::M23],
+            z: v.x * self.m[Matrix4x4::M31] + v.y * self.m[Matrix4x4::M32] + v.z * self.m[Matrix4x4::M33],
+        }
+    }
+}
+
+impl Mul for Matrix4x4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [0.0; 16];
+        for column in 0..4 {
+            for row in 0..4 {
+                m[column * 4 + row] = (0..4)
+                    .map(|i| self.m[i * 4 + row] * rhs.m[column * 4 + i])
+                    .sum();
+            }
+        }
+        Matrix4x4 { m }
+    }
+}
+
+impl MulAssign for Matrix4x4 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<Vector3> for Matrix4x4 {
+    type Output = Vector3;
+
+    /// Transforms a point by this matrix, including its translation. The matrix is assumed to be
+    /// an affine transform, so the bottom row is ignored.
+    #[rustfmt::skip]
+    #[inline]
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        Vector3 {
+            x: rhs.x * self.m[Matrix4x4::M11] + rhs.y * self.m[Matrix4x4::M12] + rhs.z * self.m[Matrix4x4::M13] + self.m[Matrix4x4::M14],
+            y: rhs.x * self.m[Matrix4x4::M21] + rhs.y * self.m[Matrix4x4::M22] + rhs.z * self.m[Matrix4x4::M23] + self.m[Matrix4x4::M24],
+            z: rhs.x * self.m[Matrix4x4::M31] + rhs.y * self.m[Matrix4x4::M32] + rhs.z * self.m[Matrix4x4::M33] + self.m[Matrix4x4::M34],
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    #[test]
+    pub fn test_new_and_transpose() {
+        let m = Matrix4x4::new(
+            1.0, 2.0, 3.0, 4.0,
+            5.0, 6.0, 7.0, 8.0,
+            9.0, 10.0, 11.0, 12.0,
+            13.0, 14.0, 15.0, 16.0
+        );
+        assert_eq!(2.0, m.m[Matrix4x4::M12]);
+        assert_eq!(4.0, m.m[Matrix4x4::M14]);
+        assert_eq!(5.0, m.m[Matrix4x4::M21]);
+        assert_eq!(12.0, m.m[Matrix4x4::M34]);
+        assert_eq!(13.0, m.m[Matrix4x4::M41]);
+
+        let t = m.transpose();
+        assert_eq!(5.0, t.m[Matrix4x4::M12]);
+        assert_eq!(13.0, t.m[Matrix4x4::M14]);
+        assert_eq!(2.0, t.m[Matrix4x4::M21]);
+        assert_eq!(15.0, t.m[Matrix4x4::M34]);
+        assert_eq!(4.0, t.m[Matrix4x4::M41]);
+    }
+
+    #[rustfmt::skip]
+    #[test]
+    pub fn test_mul() {
+        let a = Matrix4x4::new(
+            1.0, 2.0, 3.0, 4.0,
+            5.0, 6.0, 7.0, 8.0,
+            9.0, 10.0, 11.0, 12.0,
+            13.0, 14.0, 15.0, 16.0
+        );
+        let b = Matrix4x4::new(
+            2.0, 0.0, 0.0, 1.0,
+            0.0, 3.0, 0.0, 0.0,
+            1.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        );
+        let c = a * b;
+        assert!(nearly_equal(5.0, c.m[Matrix4x4::M11], 0.001));
+        assert!(nearly_equal(6.0, c.m[Matrix4x4::M12], 0.001));
+        assert!(nearly_equal(3.0, c.m[Matrix4x4::M13], 0.001));
+        assert!(nearly_equal(5.0, c.m[Matrix4x4::M14], 0.001));
+        assert!(nearly_equal(17.0, c.m[Matrix4x4::M21], 0.001));
+        assert!(nearly_equal(13.0, c.m[Matrix4x4::M24], 0.001));
+        assert!(nearly_equal(41.0, c.m[Matrix4x4::M41], 0.001));
+        assert!(nearly_equal(29.0, c.m[Matrix4x4::M44], 0.001));
+
+        let mut d = a;
+        d *= b;
+        assert_eq!(c, d);
+        assert_eq!(a, a * Matrix4x4::IDENTITY);
+    }
+
+    #[test]
+    pub fn test_transforms() {
+        let v = Vector3::new(1.0, 2.0, 3.0);
+
+        let t = Matrix4x4::new_translation(2.0, -3.0, 4.0) * v;
+        assert!(t.nearly_equal(Vector3::new(3.0, -1.0, 7.0), 0.001));
+
+        let t = Matrix4x4::new_scaling(2.0, 3.0, 4.0) * v;
+        assert!(t.nearly_equal(Vector3::new(2.0, 6.0, 12.0), 0.001));
+
+        let t = Matrix4x4::new_rotation_z(RADIANS_90) * v;
+        assert!(t.nearly_equal(Vector3::new(-2.0, 1.0, 3.0), 0.001));
+        let t = Matrix4x4::new_rotation_x(RADIANS_90) * v;
+        assert!(t.nearly_equal(Vector3::new(1.0, -3.0, 2.0), 0.001));
+        let t = Matrix4x4::new_rotation_y(RADIANS_90) * v;
+        assert!(t.nearly_equal(Vector3::new(3.0, 2.0, -1.0), 0.001));
+
+        // translation is applied last, so the rotation happens around the origin
+        let m = Matrix4x4::new_translation(10.0, 0.0, 0.0) * Matrix4x4::new_rotation_z(RADIANS_90);
+        assert!((m * v).nearly_equal(Vector3::new(8.0, 1.0, 3.0), 0.001));
+        assert!(m
+            .transform_direction(v)
+            .nearly_equal(Vector3::new(-2.0, 1.0, 3.0), 0.001));
+    }
+
+    #[test]
+    pub fn test_look_at() {
+        let m =
+            Matrix4x4::new_look_at(Vector3::new(0.0, 0.0, -5.0), Vector3::ZERO, Vector3::Y_AXIS);
+        assert!((m * Vector3::ZERO).nearly_equal(Vector3::new(0.0, 0.0, 5.0), 0.001));
+        assert!((m * Vector3::new(1.0, 2.0, 0.0)).nearly_equal(Vector3::new(1.0, 2.0, 5.0), 0.001));
+
+        // looking down the x axis from the origin puts positive x straight ahead and positive z
+        // to the left
+        let m = Matrix4x4::new_look_at(Vector3::ZERO, Vector3::X_AXIS, Vector3::Y_AXIS);
+        assert!((m * Vector3::new(3.0, 0.0, 0.0)).nearly_equal(Vector3::new(0.0, 0.0, 3.0), 0.001));
+        assert!((m * Vector3::new(0.0, 0.0, 1.0)).nearly_equal(Vector3::new(-1.0, 0.0, 0.0), 0.001));
+    }
+}
@@ -1,14 +1,30 @@
 use std::ops::{Add, Div, Mul, Sub};
 
+pub use self::angle::*;
+pub use self::bresenham::*;
 pub use self::circle::*;
+pub use self::fixed::*;
+pub use self::geometry::*;
+pub use self::matrix3x2::*;
 pub use self::matrix3x3::*;
+pub use self::matrix4x4::*;
 pub use self::rect::*;
 pub use self::vector2::*;
+pub use self::vector2i::*;
+pub use self::vector3::*;
 
+pub mod angle;
+pub mod bresenham;
 pub mod circle;
+pub mod fixed;
+pub mod geometry;
+pub mod matrix3x2;
 pub mod matrix3x3;
+pub mod matrix4x4;
 pub mod rect;
 pub mod vector2;
+pub mod vector2i;
+pub mod vector3;
 
 pub const PI: f32 = std::f32::consts::PI; // 180 degrees
 pub const HALF_PI: f32 = PI / 2.0; // 90 degrees
@@ -1,5 +1,8 @@
+use crate::math::*;
+
 /// Represents a 2D rectangle, using integer coordinates and dimensions.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
@@ -76,6 +79,24 @@ impl Rect {
         }
     }
 
+    /// Creates a new rect from a top-left position and a size.
+    #[inline]
+    pub fn from_position_and_size(position: Vector2i, size: Vector2i) -> Rect {
+        Rect::new(position.x, position.y, size.x as u32, size.y as u32)
+    }
+
+    /// Returns the top-left coordinates of this rect.
+    #[inline]
+    pub fn position(&self) -> Vector2i {
+        Vector2i::new(self.x, self.y)
+    }
+
+    /// Returns the width and height of this rect.
+    #[inline]
+    pub fn size(&self) -> Vector2i {
+        Vector2i::new(self.width as i32, self.height as i32)
+    }
+
     /// Calculates the right-most x coordinate contained by this rect.
     #[inline]
     pub fn right(&self) -> i32 {
@@ -101,6 +122,12 @@ impl Rect {
         (self.x <= x) && (self.right() >= x) && (self.y <= y) && (self.bottom() >= y)
     }
 
+    /// Returns true if the given point is contained within the bounds of this rect.
+    #[inline]
+    pub fn contains(&self, point: Vector2i) -> bool {
+        self.contains_point(point.x, point.y)
+    }
+
     /// Returns true if the given rect is contained completely within the bounds of this rect.
     pub fn contains_rect(&self, other: &Rect) -> bool {
         (other.x >= self.x && other.x < self.right())
@@ -117,6 +144,59 @@ impl Rect {
             && (self.bottom() >= other.y)
     }
 
+    /// Returns the region where this rect and the given rect overlap, or None if they do not
+    /// overlap at all.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width as i32).min(other.x + other.width as i32);
+        let bottom = (self.y + self.height as i32).min(other.y + other.height as i32);
+        if left < right && top < bottom {
+            Some(Rect::new(
+                left,
+                top,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the smallest rect that completely contains both this rect and the given rect.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x + self.width as i32).max(other.x + other.width as i32);
+        let bottom = (self.y + self.height as i32).max(other.y + other.height as i32);
+        Rect::new(left, top, (right - left) as u32, (bottom - top) as u32)
+    }
+
+    /// Returns a copy of this rect grown outwards in all directions by the given amounts. That is,
+    /// the left and right edges each move by `dx`, and the top and bottom edges each move by `dy`.
+    /// Negative amounts shrink the rect instead, down to a minimum width and height of zero.
+    pub fn inflate(&self, dx: i32, dy: i32) -> Rect {
+        let width = (self.width as i32 + dx * 2).max(0);
+        let height = (self.height as i32 + dy * 2).max(0);
+        // when shrinking down to nothing, keep the rect centered on where it was
+        let x = self.x + (self.width as i32 - width) / 2;
+        let y = self.y + (self.height as i32 - height) / 2;
+        Rect::new(x, y, width as u32, height as u32)
+    }
+
+    /// Returns a copy of this rect shrunk inwards in all directions by the given amounts. The same
+    /// as [Rect::inflate] with the amounts negated.
+    #[inline]
+    pub fn deflate(&self, dx: i32, dy: i32) -> Rect {
+        self.inflate(-dx, -dy)
+    }
+
+    /// Returns a copy of this rect moved by the given amounts.
+    #[inline]
+    pub fn translate(&self, dx: i32, dy: i32) -> Rect {
+        Rect::new(self.x + dx, self.y + dy, self.width, self.height)
+    }
+
     pub fn clamp_to(&mut self, other: &Rect) -> bool {
         if !self.overlaps(other) {
             // not possible to clamp this rect to the other rect as they do not overlap at all
@@ -281,4 +361,64 @@ pub mod tests {
         assert!(!r.overlaps(&Rect::from_coords(5, 12, 9, 18)));
         assert!(!r.overlaps(&Rect::from_coords(21, 13, 25, 16)));
     }
+
+    #[test]
+    pub fn test_intersection() {
+        let r = Rect::from_coords(10, 10, 20, 20);
+
+        assert_eq!(
+            Some(Rect::from_coords(12, 12, 15, 15)),
+            r.intersection(&Rect::from_coords(12, 12, 15, 15))
+        );
+        assert_eq!(
+            Some(Rect::from_coords(10, 10, 15, 15)),
+            r.intersection(&Rect::from_coords(5, 5, 15, 15))
+        );
+        assert_eq!(
+            Some(Rect::from_coords(15, 15, 20, 20)),
+            r.intersection(&Rect::from_coords(15, 15, 25, 25))
+        );
+        assert_eq!(
+            Some(Rect::from_coords(20, 13, 20, 16)),
+            r.intersection(&Rect::from_coords(20, 13, 25, 16))
+        );
+        assert_eq!(Some(r), r.intersection(&Rect::from_coords(0, 0, 30, 30)));
+
+        assert_eq!(None, r.intersection(&Rect::from_coords(21, 13, 25, 16)));
+        assert_eq!(None, r.intersection(&Rect::from_coords(2, 2, 8, 4)));
+        assert_eq!(None, r.intersection(&Rect::new(12, 12, 0, 5)));
+    }
+
+    #[test]
+    pub fn test_union() {
+        let r = Rect::from_coords(10, 10, 20, 20);
+
+        assert_eq!(r, r.union(&Rect::from_coords(12, 12, 15, 15)));
+        assert_eq!(
+            Rect::from_coords(5, 5, 20, 20),
+            r.union(&Rect::from_coords(5, 5, 15, 15))
+        );
+        assert_eq!(
+            Rect::from_coords(2, 2, 20, 20),
+            r.union(&Rect::from_coords(2, 2, 8, 4))
+        );
+        assert_eq!(
+            Rect::from_coords(10, 10, 25, 30),
+            r.union(&Rect::from_coords(22, 25, 25, 30))
+        );
+    }
+
+    #[test]
+    pub fn test_inflate_deflate_translate() {
+        let r = Rect::new(10, 10, 8, 6);
+
+        assert_eq!(Rect::new(8, 9, 12, 8), r.inflate(2, 1));
+        assert_eq!(Rect::new(12, 11, 4, 4), r.deflate(2, 1));
+        assert_eq!(r.deflate(2, 1), r.inflate(-2, -1));
+        assert_eq!(Rect::new(14, 13, 0, 0), r.deflate(10, 10));
+        assert_eq!(r, r.inflate(0, 0));
+
+        assert_eq!(Rect::new(5, 13, 8, 6), r.translate(-5, 3));
+        assert_eq!(r, r.translate(0, 0));
+    }
 }
@@ -95,6 +95,42 @@ impl Vector2 {
     pub fn almost_zero(&self, epsilon: f32) -> bool {
         self.nearly_equal(Vector2::ZERO, epsilon)
     }
+
+    /// Calculates the 2D cross product (a.k.a. perp dot product) of this and another vector. This
+    /// is positive if `other` is clockwise from this vector (in screen coordinates, where y
+    /// increases downwards), negative if counter-clockwise, and zero if they are parallel.
+    #[inline]
+    pub fn cross(&self, other: &Vector2) -> f32 {
+        (self.x * other.y) - (self.y * other.x)
+    }
+
+    /// Returns a vector perpendicular to this one, rotated 90 degrees clockwise (in screen
+    /// coordinates, where y increases downwards).
+    #[inline]
+    pub fn perp(&self) -> Vector2 {
+        Vector2 {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
+    /// Returns an integer vector with this vector's components rounded down.
+    #[inline]
+    pub fn floor_to_int(&self) -> Vector2i {
+        Vector2i {
+            x: self.x.floor() as i32,
+            y: self.y.floor() as i32,
+        }
+    }
+
+    /// Returns an integer vector with this vector's components rounded to the nearest integer.
+    #[inline]
+    pub fn round_to_int(&self) -> Vector2i {
+        Vector2i {
+            x: self.x.round() as i32,
+            y: self.y.round() as i32,
+        }
+    }
 }
 
 impl Neg for Vector2 {
@@ -471,6 +507,16 @@ pub mod tests {
         assert!(nearly_equal(0.0, angle, 0.0001));
     }
 
+    #[test]
+    pub fn test_cross_and_perp() {
+        let a = Vector2::new(3.0, 4.0);
+        let b = Vector2::new(-2.0, 5.0);
+        assert!(nearly_equal(23.0, a.cross(&b), 0.0001));
+        assert!(nearly_equal(-23.0, b.cross(&a), 0.0001));
+        assert!(a.perp().nearly_equal(Vector2::new(-4.0, 3.0), 0.0001));
+        assert!(nearly_equal(0.0, a.dot(&a.perp()), 0.0001));
+    }
+
     #[test]
     pub fn test_lerp() {
         let a = Vector2 { x: 5.0, y: 1.0 };
@@ -0,0 +1,332 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::math::*;
+
+/// Represents a 2D vector with integer components. Useful for things like pixel and tile
+/// coordinates where [Vector2] would otherwise require casting back and forth.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Vector2i {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Vector2i {
+    pub const ZERO: Vector2i = Vector2i { x: 0, y: 0 };
+
+    pub const UP: Vector2i = Vector2i { x: 0, y: -1 };
+    pub const DOWN: Vector2i = Vector2i { x: 0, y: 1 };
+    pub const LEFT: Vector2i = Vector2i { x: -1, y: 0 };
+    pub const RIGHT: Vector2i = Vector2i { x: 1, y: 0 };
+
+    /// Creates a vector with the specified X and Y components.
+    #[inline]
+    pub fn new(x: i32, y: i32) -> Vector2i {
+        Vector2i { x, y }
+    }
+
+    /// Calculates the dot product of this and another vector.
+    #[inline]
+    pub fn dot(&self, other: &Vector2i) -> i32 {
+        (self.x * other.x) + (self.y * other.y)
+    }
+
+    /// Calculates the 2D cross product (a.k.a. perp dot product) of this and another vector. This
+    /// is positive if `other` is clockwise from this vector (in screen coordinates, where y
+    /// increases downwards), negative if counter-clockwise, and zero if they are parallel.
+    #[inline]
+    pub fn cross(&self, other: &Vector2i) -> i32 {
+        (self.x * other.y) - (self.y * other.x)
+    }
+
+    /// Returns a vector perpendicular to this one, rotated 90 degrees clockwise (in screen
+    /// coordinates, where y increases downwards).
+    #[inline]
+    pub fn perp(&self) -> Vector2i {
+        Vector2i {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
+    /// Calculates the squared length of this vector.
+    #[inline]
+    pub fn length_squared(&self) -> i32 {
+        (self.x * self.x) + (self.y * self.y)
+    }
+
+    /// Calculates the length (a.k.a. magnitude) of this vector.
+    #[inline]
+    pub fn length(&self) -> f32 {
+        (self.length_squared() as f32).sqrt()
+    }
+
+    /// Calculates the squared distance between this and another vector.
+    #[inline]
+    pub fn distance_squared(&self, other: &Vector2i) -> i32 {
+        (*other - *self).length_squared()
+    }
+
+    /// Calculates the "manhattan" (a.k.a. taxicab) distance between this and another vector. That
+    /// is, the number of horizontal and vertical steps needed to get from one to the other.
+    #[inline]
+    pub fn manhattan_distance(&self, other: &Vector2i) -> i32 {
+        (other.x - self.x).abs() + (other.y - self.y).abs()
+    }
+
+    /// Returns a vector with the absolute values of this vector's components.
+    #[inline]
+    pub fn abs(&self) -> Vector2i {
+        Vector2i {
+            x: self.x.abs(),
+            y: self.y.abs(),
+        }
+    }
+
+    /// Returns a vector with each component being the sign (-1, 0 or 1) of this vector's
+    /// components.
+    #[inline]
+    pub fn signum(&self) -> Vector2i {
+        Vector2i {
+            x: self.x.signum(),
+            y: self.y.signum(),
+        }
+    }
+
+    /// Returns a normalized floating point vector from this vector.
+    #[inline]
+    pub fn normalize(&self) -> Vector2 {
+        Vector2::from(*self).normalize()
+    }
+}
+
+impl From<Vector2i> for Vector2 {
+    #[inline]
+    fn from(value: Vector2i) -> Self {
+        Vector2 {
+            x: value.x as f32,
+            y: value.y as f32,
+        }
+    }
+}
+
+impl From<(i32, i32)> for Vector2i {
+    #[inline]
+    fn from(value: (i32, i32)) -> Self {
+        Vector2i {
+            x: value.0,
+            y: value.1,
+        }
+    }
+}
+
+impl From<Vector2i> for (i32, i32) {
+    #[inline]
+    fn from(value: Vector2i) -> Self {
+        (value.x, value.y)
+    }
+}
+
+impl Neg for Vector2i {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Vector2i {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl Add for Vector2i {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Vector2i {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl AddAssign for Vector2i {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl Sub for Vector2i {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vector2i {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl SubAssign for Vector2i {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl Mul for Vector2i {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Vector2i {
+            x: self.x * rhs.x,
+            y: self.y * rhs.y,
+        }
+    }
+}
+
+impl MulAssign for Vector2i {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        self.x *= rhs.x;
+        self.y *= rhs.y;
+    }
+}
+
+impl Div for Vector2i {
+    type Output = Self;
+
+    /// Divides each component, rounding towards negative infinity (so that, for example, pixel
+    /// coordinates left of zero still map to the correct tile).
+    #[inline]
+    fn div(self, rhs: Self) -> Self::Output {
+        Vector2i {
+            x: self.x.div_euclid(rhs.x),
+            y: self.y.div_euclid(rhs.y),
+        }
+    }
+}
+
+impl DivAssign for Vector2i {
+    #[inline]
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl Mul<i32> for Vector2i {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: i32) -> Self::Output {
+        Vector2i {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
+
+impl MulAssign<i32> for Vector2i {
+    #[inline]
+    fn mul_assign(&mut self, rhs: i32) {
+        self.x *= rhs;
+        self.y *= rhs;
+    }
+}
+
+impl Div<i32> for Vector2i {
+    type Output = Self;
+
+    /// Divides each component, rounding towards negative infinity.
+    #[inline]
+    fn div(self, rhs: i32) -> Self::Output {
+        Vector2i {
+            x: self.x.div_euclid(rhs),
+            y: self.y.div_euclid(rhs),
+        }
+    }
+}
+
+impl DivAssign<i32> for Vector2i {
+    #[inline]
+    fn div_assign(&mut self, rhs: i32) {
+        *self = *self / rhs;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_new() {
+        let v = Vector2i::new(3, 7);
+        assert_eq!(3, v.x);
+        assert_eq!(7, v.y);
+        assert_eq!(Vector2i::ZERO, Vector2i::default());
+        assert_eq!(v, Vector2i::from((3, 7)));
+        assert_eq!((3, 7), v.into());
+    }
+
+    #[test]
+    pub fn test_ops() {
+        let a = Vector2i::new(3, -7);
+        let b = Vector2i::new(2, 4);
+        assert_eq!(Vector2i::new(-3, 7), -a);
+        assert_eq!(Vector2i::new(5, -3), a + b);
+        assert_eq!(Vector2i::new(1, -11), a - b);
+        assert_eq!(Vector2i::new(6, -28), a * b);
+        assert_eq!(Vector2i::new(6, -14), a * 2);
+        // division rounds towards negative infinity
+        assert_eq!(Vector2i::new(1, -2), a / b);
+        assert_eq!(Vector2i::new(1, -4), a / 2);
+
+        let mut c = a;
+        c += b;
+        c -= Vector2i::new(1, 1);
+        c *= 3;
+        c /= 2;
+        assert_eq!(Vector2i::new(6, -6), c);
+        c *= Vector2i::new(2, -1);
+        c /= Vector2i::new(4, 4);
+        assert_eq!(Vector2i::new(3, 1), c);
+    }
+
+    #[test]
+    pub fn test_products_and_lengths() {
+        let a = Vector2i::new(3, 4);
+        let b = Vector2i::new(-2, 5);
+        assert_eq!(14, a.dot(&b));
+        assert_eq!(23, a.cross(&b));
+        assert_eq!(0, a.cross(&(a * 2)));
+        assert_eq!(Vector2i::new(-4, 3), a.perp());
+        assert_eq!(Vector2i::DOWN, Vector2i::RIGHT.perp());
+        assert_eq!(25, a.length_squared());
+        assert!(nearly_equal(5.0, a.length(), 0.0001));
+        assert_eq!(26, a.distance_squared(&b));
+        assert_eq!(6, a.manhattan_distance(&b));
+        assert_eq!(Vector2i::new(2, 5), b.abs());
+        assert_eq!(Vector2i::new(-1, 1), b.signum());
+        assert!(a.normalize().nearly_equal(Vector2::new(0.6, 0.8), 0.0001));
+    }
+
+    #[test]
+    pub fn test_conversions() {
+        let v = Vector2::from(Vector2i::new(3, -7));
+        assert!(v.nearly_equal(Vector2::new(3.0, -7.0), 0.0001));
+        assert_eq!(Vector2i::new(2, -3), Vector2::new(2.7, -2.2).floor_to_int());
+        assert_eq!(Vector2i::new(3, -2), Vector2::new(2.7, -2.2).round_to_int());
+
+        let r = Rect::from_position_and_size(Vector2i::new(3, 4), Vector2i::new(10, 20));
+        assert_eq!(Rect::new(3, 4, 10, 20), r);
+        assert_eq!(Vector2i::new(3, 4), r.position());
+        assert_eq!(Vector2i::new(10, 20), r.size());
+        assert!(r.contains(Vector2i::new(12, 23)));
+        assert!(!r.contains(Vector2i::new(13, 23)));
+    }
+}
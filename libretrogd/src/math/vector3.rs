@@ -0,0 +1,278 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::math::*;
+
+/// Represents a 3D vector and provides common methods for vector math.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Vector3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vector3 {
+    pub const ZERO: Vector3 = Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    pub const X_AXIS: Vector3 = Vector3 {
+        x: 1.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    pub const Y_AXIS: Vector3 = Vector3 {
+        x: 0.0,
+        y: 1.0,
+        z: 0.0,
+    };
+    pub const Z_AXIS: Vector3 = Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+
+    /// Creates a vector with the specified X, Y and Z components.
+    #[inline]
+    pub fn new(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    /// Calculates the distance between this and another vector.
+    #[inline]
+    pub fn distance(&self, other: &Vector3) -> f32 {
+        self.distance_squared(other).sqrt()
+    }
+
+    /// Calculates the squared distance between this and another vector.
+    #[inline]
+    pub fn distance_squared(&self, other: &Vector3) -> f32 {
+        (*other - *self).length_squared()
+    }
+
+    /// Calculates the dot product of this and another vector.
+    #[inline]
+    pub fn dot(&self, other: &Vector3) -> f32 {
+        (self.x * other.x) + (self.y * other.y) + (self.z * other.z)
+    }
+
+    /// Calculates the cross product of this and another vector, which is perpendicular to both.
+    #[inline]
+    pub fn cross(&self, other: &Vector3) -> Vector3 {
+        Vector3 {
+            x: (self.y * other.z) - (self.z * other.y),
+            y: (self.z * other.x) - (self.x * other.z),
+            z: (self.x * other.y) - (self.y * other.x),
+        }
+    }
+
+    /// Calculates the length (a.k.a. magnitude) of this vector.
+    #[inline]
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    /// Calculates the squared length of this vector.
+    #[inline]
+    pub fn length_squared(&self) -> f32 {
+        (self.x * self.x) + (self.y * self.y) + (self.z * self.z)
+    }
+
+    /// Returns a normalized vector from this vector.
+    pub fn normalize(&self) -> Vector3 {
+        let inverse_length = 1.0 / self.length();
+        Vector3 {
+            x: self.x * inverse_length,
+            y: self.y * inverse_length,
+            z: self.z * inverse_length,
+        }
+    }
+
+    /// Returns the vector part way between this and another vector, where `t` of 0.0 returns this
+    /// vector and 1.0 returns `other`.
+    #[inline]
+    pub fn lerp(&self, other: &Vector3, t: f32) -> Vector3 {
+        *self + (*other - *self) * t
+    }
+
+    /// Returns true if this vector is nearly equal to the zero vector (0.0, 0.0, 0.0).
+    #[inline]
+    pub fn almost_zero(&self, epsilon: f32) -> bool {
+        self.nearly_equal(Vector3::ZERO, epsilon)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Vector3 {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Vector3 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl AddAssign for Vector3 {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vector3 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl SubAssign for Vector3 {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
+    }
+}
+
+impl Mul<f32> for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vector3 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl MulAssign<f32> for Vector3 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: f32) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
+    }
+}
+
+impl Div<f32> for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: f32) -> Self::Output {
+        Vector3 {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+        }
+    }
+}
+
+impl DivAssign<f32> for Vector3 {
+    #[inline]
+    fn div_assign(&mut self, rhs: f32) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+    }
+}
+
+impl NearlyEqual for Vector3 {
+    type Output = Self;
+
+    #[inline(always)]
+    fn nearly_equal(self, other: Self::Output, epsilon: f32) -> bool {
+        nearly_equal(self.x, other.x, epsilon)
+            && nearly_equal(self.y, other.y, epsilon)
+            && nearly_equal(self.z, other.z, epsilon)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_new() {
+        let v = Vector3::new(3.0, 7.0, -2.0);
+        assert!(nearly_equal(v.x, 3.0, 0.0001));
+        assert!(nearly_equal(v.y, 7.0, 0.0001));
+        assert!(nearly_equal(v.z, -2.0, 0.0001));
+    }
+
+    #[test]
+    pub fn test_ops() {
+        let a = Vector3::new(3.0, 4.0, 5.0);
+        let b = Vector3::new(1.0, 2.0, 3.0);
+        assert!((a + b).nearly_equal(Vector3::new(4.0, 6.0, 8.0), 0.0001));
+        assert!((a - b).nearly_equal(Vector3::new(2.0, 2.0, 2.0), 0.0001));
+        assert!((a * 2.0).nearly_equal(Vector3::new(6.0, 8.0, 10.0), 0.0001));
+        assert!((a / 2.0).nearly_equal(Vector3::new(1.5, 2.0, 2.5), 0.0001));
+        assert!((-a).nearly_equal(Vector3::new(-3.0, -4.0, -5.0), 0.0001));
+
+        let mut c = a;
+        c += b;
+        c -= Vector3::new(0.0, 1.0, 0.0);
+        c *= 2.0;
+        c /= 4.0;
+        assert!(c.nearly_equal(Vector3::new(2.0, 2.5, 4.0), 0.0001));
+    }
+
+    #[test]
+    pub fn test_length_and_distance() {
+        let v = Vector3::new(2.0, 3.0, 6.0);
+        assert!(nearly_equal(v.length_squared(), 49.0, 0.0001));
+        assert!(nearly_equal(v.length(), 7.0, 0.0001));
+        assert!(v
+            .normalize()
+            .nearly_equal(Vector3::new(2.0 / 7.0, 3.0 / 7.0, 6.0 / 7.0), 0.0001));
+
+        let a = Vector3::new(1.0, 1.0, 1.0);
+        let b = Vector3::new(3.0, 4.0, 7.0);
+        assert!(nearly_equal(a.distance(&b), 7.0, 0.0001));
+        assert!(a
+            .lerp(&b, 0.5)
+            .nearly_equal(Vector3::new(2.0, 2.5, 4.0), 0.0001));
+        assert!(Vector3::new(0.00001, 0.0, 0.0).almost_zero(0.0001));
+    }
+
+    #[test]
+    pub fn test_dot_and_cross() {
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        let b = Vector3::new(4.0, -5.0, 6.0);
+        assert!(nearly_equal(a.dot(&b), 12.0, 0.0001));
+        assert!(a
+            .cross(&b)
+            .nearly_equal(Vector3::new(27.0, 6.0, -13.0), 0.0001));
+        assert!(Vector3::X_AXIS
+            .cross(&Vector3::Y_AXIS)
+            .nearly_equal(Vector3::Z_AXIS, 0.0001));
+    }
+}
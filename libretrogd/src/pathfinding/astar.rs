@@ -0,0 +1,347 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+/// A graph of nodes connected by weighted, one-way edges which paths can be searched for in with
+/// a [PathFinder].
+pub trait PathGraph {
+    type Node: Copy + Eq + Hash;
+
+    /// Adds every node which can be moved to directly from `node` to `neighbours`, along with the
+    /// cost of moving there. Costs must not be negative.
+    fn neighbours(&self, node: Self::Node, neighbours: &mut Vec<(Self::Node, f32)>);
+
+    /// Returns an estimate of the cost of the cheapest path between two nodes. To be sure of
+    /// finding the cheapest path, this must never be more than the actual cost. Returning 0.0
+    /// always is allowed, but searches more nodes than necessary.
+    fn heuristic(&self, from: Self::Node, to: Self::Node) -> f32;
+}
+
+/// Which search algorithm a [PathFinder] uses.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum PathAlgorithm {
+    /// Searches towards the goal first, using the graph's [PathGraph::heuristic].
+    #[default]
+    AStar,
+    /// Searches outwards evenly in all directions, ignoring the graph's heuristic. This is slower
+    /// than A* but still finds the cheapest path when the heuristic over-estimates costs.
+    Dijkstra,
+}
+
+/// A path found by a [PathFinder].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<N> {
+    /// The nodes making up the path, starting with the start node and ending with the goal.
+    pub nodes: Vec<N>,
+    /// The total cost of moving along the path.
+    pub cost: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct OpenNode<N> {
+    node: N,
+    cost: f32,
+    estimate: f32,
+}
+
+impl<N> PartialEq for OpenNode<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N> Eq for OpenNode<N> {}
+
+impl<N> PartialOrd for OpenNode<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N> Ord for OpenNode<N> {
+    // reversed so that BinaryHeap, which is a max-heap, gives the lowest estimate first. ties are
+    // broken in favour of the node furthest along its path, which is usually closest to the goal
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| self.cost.total_cmp(&other.cost))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct VisitedNode<N> {
+    parent: Option<N>,
+    cost: f32,
+    closed: bool,
+}
+
+/// Finds the cheapest paths between nodes of a [PathGraph]. The memory used during a search is
+/// kept and reused by the following searches, so one path finder should be kept around and used
+/// for all searches on graphs with the same type of node, rather than creating one each time.
+///
+/// ```
+/// use libretrogd::collision::*;
+/// use libretrogd::math::*;
+/// use libretrogd::pathfinding::*;
+///
+/// let mut map = CollisionMap::new(10, 10, 16, 16);
+/// map.set(5, 5, TileCollision::Solid);
+///
+/// let grid = map.path_grid(GridOptions::default());
+/// let mut path_finder = PathFinder::new();
+/// let path = path_finder.find_path(&grid, Vector2i::new(0, 0), Vector2i::new(9, 9)).unwrap();
+/// assert_eq!(18.0, path.cost);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathFinder<N> {
+    /// The algorithm used for searches.
+    pub algorithm: PathAlgorithm,
+    /// The most nodes a search will look at before giving up, or `None` for no limit. Limiting
+    /// this keeps searches for unreachable goals on large graphs from taking too long.
+    pub max_visited: Option<usize>,
+    open: BinaryHeap<OpenNode<N>>,
+    visited: HashMap<N, VisitedNode<N>>,
+    neighbours: Vec<(N, f32)>,
+    num_visited: usize,
+}
+
+impl<N: Copy + Eq + Hash> Default for PathFinder<N> {
+    fn default() -> Self {
+        PathFinder::new()
+    }
+}
+
+impl<N: Copy + Eq + Hash> PathFinder<N> {
+    /// Creates a new path finder which uses A*, with no limit on how many nodes are visited.
+    pub fn new() -> Self {
+        PathFinder {
+            algorithm: PathAlgorithm::AStar,
+            max_visited: None,
+            open: BinaryHeap::new(),
+            visited: HashMap::new(),
+            neighbours: Vec::new(),
+            num_visited: 0,
+        }
+    }
+
+    /// Returns how many nodes the last search looked at.
+    #[inline]
+    pub fn num_visited(&self) -> usize {
+        self.num_visited
+    }
+
+    /// Returns the cost of the cheapest path found from the start node of the last search to the
+    /// given node, or `None` if the last search did not reach it.
+    pub fn cost_to(&self, node: N) -> Option<f32> {
+        self.visited
+            .get(&node)
+            .filter(|visited| visited.closed)
+            .map(|visited| visited.cost)
+    }
+
+    /// Finds the cheapest path from `start` to `goal`, returning `None` if there is no path
+    /// between them, or if `max_visited` was reached before finding one.
+    pub fn find_path<G: PathGraph<Node = N>>(
+        &mut self,
+        graph: &G,
+        start: N,
+        goal: N,
+    ) -> Option<Path<N>> {
+        if self.search(graph, start, Some(goal), f32::INFINITY) {
+            Some(self.build_path(goal))
+        } else {
+            None
+        }
+    }
+
+    /// Finds the cheapest paths from `start` to every node which can be reached from it for no
+    /// more than `max_cost`, always using Dijkstra's algorithm. Afterwards, the cost of reaching
+    /// each node can be checked with [PathFinder::cost_to] and paths to them built with
+    /// [PathFinder::path_to]. Useful for things like showing how far a unit can move, or for AI
+    /// which wants to know about many possible destinations at once.
+    pub fn find_all<G: PathGraph<Node = N>>(&mut self, graph: &G, start: N, max_cost: f32) {
+        self.search(graph, start, None, max_cost);
+    }
+
+    /// Returns the path from the start node of the last search to the given node, or `None` if
+    /// the last search did not reach it.
+    pub fn path_to(&self, node: N) -> Option<Path<N>> {
+        self.cost_to(node)?;
+        Some(self.build_path(node))
+    }
+
+    fn build_path(&self, goal: N) -> Path<N> {
+        let mut nodes = vec![goal];
+        let mut current = self.visited[&goal];
+        while let Some(parent) = current.parent {
+            nodes.push(parent);
+            current = self.visited[&parent];
+        }
+        nodes.reverse();
+        Path {
+            nodes,
+            cost: self.visited[&goal].cost,
+        }
+    }
+
+    // runs a search from `start`, returning true once `goal` is reached. with no goal, every node
+    // costing no more than `max_cost` to reach is visited instead, using Dijkstra's algorithm
+    fn search<G: PathGraph<Node = N>>(
+        &mut self,
+        graph: &G,
+        start: N,
+        goal: Option<N>,
+        max_cost: f32,
+    ) -> bool {
+        self.open.clear();
+        self.visited.clear();
+        self.num_visited = 0;
+
+        let algorithm = self.algorithm;
+        let heuristic = |node: N| match (goal, algorithm) {
+            (Some(goal), PathAlgorithm::AStar) => graph.heuristic(node, goal),
+            _ => 0.0,
+        };
+
+        self.visited.insert(
+            start,
+            VisitedNode {
+                parent: None,
+                cost: 0.0,
+                closed: false,
+            },
+        );
+        self.open.push(OpenNode {
+            node: start,
+            cost: 0.0,
+            estimate: heuristic(start),
+        });
+
+        while let Some(current) = self.open.pop() {
+            let visited = self.visited.get_mut(&current.node).unwrap();
+            // a cheaper way to this node was found after this entry was added
+            if visited.closed || current.cost > visited.cost {
+                continue;
+            }
+            visited.closed = true;
+            self.num_visited += 1;
+
+            if Some(current.node) == goal {
+                return true;
+            }
+            if let Some(max_visited) = self.max_visited {
+                if self.num_visited >= max_visited {
+                    return false;
+                }
+            }
+
+            self.neighbours.clear();
+            graph.neighbours(current.node, &mut self.neighbours);
+            for &(neighbour, step_cost) in self.neighbours.iter() {
+                let cost = current.cost + step_cost;
+                if cost > max_cost {
+                    continue;
+                }
+                match self.visited.get_mut(&neighbour) {
+                    Some(visited) if visited.closed || visited.cost <= cost => continue,
+                    Some(visited) => {
+                        visited.parent = Some(current.node);
+                        visited.cost = cost;
+                    }
+                    None => {
+                        self.visited.insert(
+                            neighbour,
+                            VisitedNode {
+                                parent: Some(current.node),
+                                cost,
+                                closed: false,
+                            },
+                        );
+                    }
+                }
+                self.open.push(OpenNode {
+                    node: neighbour,
+                    cost,
+                    estimate: cost + heuristic(neighbour),
+                });
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // a graph of numbered nodes where each node leads to the next two numbers, for a cost of 1.0
+    // and 3.0, except for the jumps listed
+    struct NumberLine {
+        jumps: Vec<(u32, u32, f32)>,
+    }
+
+    impl PathGraph for NumberLine {
+        type Node = u32;
+
+        fn neighbours(&self, node: u32, neighbours: &mut Vec<(u32, f32)>) {
+            neighbours.push((node + 1, 1.0));
+            neighbours.push((node + 2, 3.0));
+            for &(from, to, cost) in self.jumps.iter() {
+                if from == node {
+                    neighbours.push((to, cost));
+                }
+            }
+        }
+
+        fn heuristic(&self, from: u32, to: u32) -> f32 {
+            to.saturating_sub(from) as f32 * 0.1
+        }
+    }
+
+    #[test]
+    pub fn finding_cheapest_path() {
+        let graph = NumberLine {
+            jumps: vec![(2, 8, 2.5), (3, 10, 10.0)],
+        };
+        let mut path_finder = PathFinder::new();
+
+        let path = path_finder.find_path(&graph, 0, 10).unwrap();
+        assert_eq!(vec![0, 1, 2, 8, 9, 10], path.nodes);
+        assert_eq!(6.5, path.cost);
+        assert_eq!(Some(2.0), path_finder.cost_to(2));
+
+        path_finder.algorithm = PathAlgorithm::Dijkstra;
+        let path = path_finder.find_path(&graph, 0, 10).unwrap();
+        assert_eq!(vec![0, 1, 2, 8, 9, 10], path.nodes);
+
+        let path = path_finder.find_path(&graph, 4, 4).unwrap();
+        assert_eq!(vec![4], path.nodes);
+        assert_eq!(0.0, path.cost);
+
+        // there is no way back
+        path_finder.max_visited = Some(100);
+        assert_eq!(None, path_finder.find_path(&graph, 4, 2));
+        assert_eq!(100, path_finder.num_visited());
+    }
+
+    #[test]
+    pub fn finding_all_paths() {
+        let graph = NumberLine {
+            jumps: vec![(0, 20, 2.5)],
+        };
+        let mut path_finder = PathFinder::new();
+
+        path_finder.find_all(&graph, 0, 3.0);
+        assert_eq!(Some(0.0), path_finder.cost_to(0));
+        assert_eq!(Some(3.0), path_finder.cost_to(3));
+        assert_eq!(Some(2.5), path_finder.cost_to(20));
+        assert_eq!(None, path_finder.cost_to(21));
+        assert_eq!(None, path_finder.cost_to(4));
+
+        let path = path_finder.path_to(20).unwrap();
+        assert_eq!(vec![0, 20], path.nodes);
+        assert_eq!(None, path_finder.path_to(4));
+    }
+}
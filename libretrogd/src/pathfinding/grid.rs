@@ -0,0 +1,339 @@
+use std::f32::consts::SQRT_2;
+
+use crate::collision::*;
+use crate::math::*;
+use crate::pathfinding::*;
+
+/// Whether paths on a [Grid] can move diagonally between tiles.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum DiagonalMovement {
+    /// Only horizontal and vertical moves are allowed.
+    #[default]
+    Never,
+    /// Diagonal moves are allowed as long as the destination tile is walkable, even squeezing
+    /// between two blocked tiles.
+    Always,
+    /// Diagonal moves are only allowed when both of the tiles beside the move are walkable, so
+    /// that paths never cut across the corner of a blocked tile.
+    NoCornerCutting,
+}
+
+/// Options controlling how paths move around a [Grid].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GridOptions {
+    /// Whether paths can move diagonally.
+    pub diagonals: DiagonalMovement,
+    /// The cost of moving horizontally or vertically by one tile.
+    pub straight_cost: f32,
+    /// The cost of moving diagonally by one tile. Defaults to the square root of 2, the actual
+    /// distance moved.
+    pub diagonal_cost: f32,
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        GridOptions {
+            diagonals: DiagonalMovement::Never,
+            straight_cost: 1.0,
+            diagonal_cost: SQRT_2,
+        }
+    }
+}
+
+const STRAIGHT_MOVES: [Vector2i; 4] = [
+    Vector2i::UP,
+    Vector2i::DOWN,
+    Vector2i::LEFT,
+    Vector2i::RIGHT,
+];
+
+const DIAGONAL_MOVES: [Vector2i; 4] = [
+    Vector2i { x: -1, y: -1 },
+    Vector2i { x: 1, y: -1 },
+    Vector2i { x: -1, y: 1 },
+    Vector2i { x: 1, y: 1 },
+];
+
+/// A [PathGraph] over a grid of tiles, such as a tile map, where each node is a tile's
+/// coordinates. Which tiles are walkable, and how costly they are to walk onto, is decided by a
+/// function given the tile's coordinates. It returns `None` for tiles which are blocked, or a
+/// multiplier for the cost of moving onto the tile. Multipliers below 1.0 mean that paths may not
+/// always be the cheapest possible.
+///
+/// [CollisionMap::path_grid] creates a grid from a collision map.
+#[derive(Debug, Clone)]
+pub struct Grid<F> {
+    /// Options controlling how paths move around the grid.
+    pub options: GridOptions,
+    width: u32,
+    height: u32,
+    cost: F,
+}
+
+impl<F: Fn(i32, i32) -> Option<f32>> Grid<F> {
+    /// Creates a new grid.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the grid, in tiles. Paths never leave the grid.
+    /// * `height`: the height of the grid, in tiles
+    /// * `options`: options controlling how paths move around the grid
+    /// * `cost`: returns the cost multiplier for moving onto the tile at the given coordinates, or
+    ///   `None` if the tile is blocked
+    pub fn new(width: u32, height: u32, options: GridOptions, cost: F) -> Grid<F> {
+        Grid {
+            options,
+            width,
+            height,
+            cost,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the cost multiplier for moving onto the given tile, or `None` if the tile is
+    /// blocked or outside of the grid.
+    #[inline]
+    pub fn cost(&self, tile: Vector2i) -> Option<f32> {
+        if tile.x >= 0 && tile.y >= 0 && tile.x < self.width as i32 && tile.y < self.height as i32 {
+            (self.cost)(tile.x, tile.y)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the given tile can be walked onto.
+    #[inline]
+    pub fn is_walkable(&self, tile: Vector2i) -> bool {
+        self.cost(tile).is_some()
+    }
+}
+
+impl<F: Fn(i32, i32) -> Option<f32>> PathGraph for Grid<F> {
+    type Node = Vector2i;
+
+    fn neighbours(&self, node: Vector2i, neighbours: &mut Vec<(Vector2i, f32)>) {
+        for &step in STRAIGHT_MOVES.iter() {
+            if let Some(cost) = self.cost(node + step) {
+                neighbours.push((node + step, cost * self.options.straight_cost));
+            }
+        }
+
+        for &step in DIAGONAL_MOVES.iter() {
+            let allowed = match self.options.diagonals {
+                DiagonalMovement::Never => false,
+                DiagonalMovement::Always => true,
+                DiagonalMovement::NoCornerCutting => {
+                    self.is_walkable(Vector2i::new(node.x + step.x, node.y))
+                        && self.is_walkable(Vector2i::new(node.x, node.y + step.y))
+                }
+            };
+            if allowed {
+                if let Some(cost) = self.cost(node + step) {
+                    neighbours.push((node + step, cost * self.options.diagonal_cost));
+                }
+            }
+        }
+    }
+
+    fn heuristic(&self, from: Vector2i, to: Vector2i) -> f32 {
+        let dx = (to.x - from.x).abs() as f32;
+        let dy = (to.y - from.y).abs() as f32;
+        let straight_cost = self.options.straight_cost;
+        if self.options.diagonals == DiagonalMovement::Never {
+            (dx + dy) * straight_cost
+        } else {
+            // octile distance, for as many diagonal moves as possible and then straight moves. a
+            // diagonal move is never worth more than two straight moves
+            let diagonal_cost = self.options.diagonal_cost.min(straight_cost * 2.0);
+            dx.min(dy) * diagonal_cost + (dx - dy).abs() * straight_cost
+        }
+    }
+}
+
+impl CollisionMap {
+    /// Returns a [Grid] for finding paths around this map, where solid tiles are blocked and all
+    /// other tiles (including one-way platforms) cost the same to walk onto.
+    pub fn path_grid(&self, options: GridOptions) -> Grid<impl Fn(i32, i32) -> Option<f32> + '_> {
+        self.path_grid_with(options, |collision| match collision {
+            TileCollision::Solid => None,
+            _ => Some(1.0),
+        })
+    }
+
+    /// Returns a [Grid] for finding paths around this map, using the given function to decide the
+    /// cost multiplier for walking onto each tile, or `None` for tiles which are blocked.
+    pub fn path_grid_with<'a>(
+        &'a self,
+        options: GridOptions,
+        cost: impl Fn(TileCollision) -> Option<f32> + 'a,
+    ) -> Grid<impl Fn(i32, i32) -> Option<f32> + 'a> {
+        Grid::new(self.width(), self.height(), options, move |x, y| {
+            cost(self.get(x, y))
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // 0123456
+    // .......  0
+    // .####..  1
+    // ....#..  2
+    // .#..#..  3
+    // ....#..  4
+    fn test_map() -> CollisionMap {
+        let mut map = CollisionMap::new(7, 5, 16, 16);
+        for x in 1..=4 {
+            map.set(x, 1, TileCollision::Solid);
+        }
+        for y in 2..=4 {
+            map.set(4, y, TileCollision::Solid);
+        }
+        map.set(1, 3, TileCollision::Solid);
+        map
+    }
+
+    fn assert_connected(path: &Path<Vector2i>, diagonals: bool) {
+        for pair in path.nodes.windows(2) {
+            let dx = (pair[1].x - pair[0].x).abs();
+            let dy = (pair[1].y - pair[0].y).abs();
+            if diagonals {
+                assert!(dx <= 1 && dy <= 1 && dx + dy > 0);
+            } else {
+                assert_eq!(1, dx + dy);
+            }
+        }
+    }
+
+    #[test]
+    pub fn finding_paths_on_grids() {
+        let map = test_map();
+        let mut path_finder = PathFinder::new();
+
+        // straight moves only, all the way around the wall
+        let grid = map.path_grid(GridOptions::default());
+        let path = path_finder
+            .find_path(&grid, Vector2i::new(2, 2), Vector2i::new(5, 2))
+            .unwrap();
+        assert_eq!(Vector2i::new(2, 2), path.nodes[0]);
+        assert_eq!(Vector2i::new(5, 2), *path.nodes.last().unwrap());
+        assert_eq!(11.0, path.cost);
+        assert_eq!(12, path.nodes.len());
+        assert_connected(&path, false);
+        assert!(path.nodes.iter().all(|&tile| grid.is_walkable(tile)));
+
+        // blocked tiles and tiles outside of the grid can't be reached
+        assert_eq!(
+            None,
+            path_finder.find_path(&grid, Vector2i::new(0, 0), Vector2i::new(4, 2))
+        );
+        assert_eq!(
+            None,
+            path_finder.find_path(&grid, Vector2i::new(0, 0), Vector2i::new(-1, 0))
+        );
+
+        // cutting diagonally across the wall's corners
+        let grid = map.path_grid(GridOptions {
+            diagonals: DiagonalMovement::Always,
+            ..Default::default()
+        });
+        let path = path_finder
+            .find_path(&grid, Vector2i::new(2, 2), Vector2i::new(5, 2))
+            .unwrap();
+        assert_connected(&path, true);
+        assert!(nearly_equal(5.0 + 3.0 * SQRT_2, path.cost, 0.0001));
+    }
+
+    #[test]
+    pub fn diagonal_corner_cutting() {
+        // .#
+        // #.
+        let mut map = CollisionMap::new(2, 2, 16, 16);
+        map.set(1, 0, TileCollision::Solid);
+        map.set(0, 1, TileCollision::Solid);
+        let mut path_finder = PathFinder::new();
+
+        let options = GridOptions {
+            diagonals: DiagonalMovement::Always,
+            ..Default::default()
+        };
+        let path = path_finder
+            .find_path(
+                &map.path_grid(options),
+                Vector2i::new(0, 0),
+                Vector2i::new(1, 1),
+            )
+            .unwrap();
+        assert_eq!(vec![Vector2i::new(0, 0), Vector2i::new(1, 1)], path.nodes);
+
+        let options = GridOptions {
+            diagonals: DiagonalMovement::NoCornerCutting,
+            ..Default::default()
+        };
+        assert_eq!(
+            None,
+            path_finder.find_path(
+                &map.path_grid(options),
+                Vector2i::new(0, 0),
+                Vector2i::new(1, 1)
+            )
+        );
+
+        // one open tile beside the move is still not enough
+        map.set(1, 0, TileCollision::Empty);
+        let path = path_finder
+            .find_path(
+                &map.path_grid(options),
+                Vector2i::new(0, 0),
+                Vector2i::new(1, 1),
+            )
+            .unwrap();
+        assert_eq!(3, path.nodes.len());
+        assert_eq!(2.0, path.cost);
+    }
+
+    #[test]
+    pub fn tile_costs() {
+        // a strip of mud in the middle of an open field
+        let grid = Grid::new(5, 5, GridOptions::default(), |x, _y| {
+            if x == 2 {
+                Some(5.0)
+            } else {
+                Some(1.0)
+            }
+        });
+        let mut path_finder = PathFinder::new();
+        let path = path_finder
+            .find_path(&grid, Vector2i::new(0, 2), Vector2i::new(4, 2))
+            .unwrap();
+        assert_eq!(8.0, path.cost);
+
+        // water is walkable, but costly, unless it can't be avoided
+        let mut map = CollisionMap::new(5, 1, 16, 16);
+        map.set(2, 0, TileCollision::OneWay);
+        let grid = map.path_grid_with(GridOptions::default(), |collision| match collision {
+            TileCollision::Solid => None,
+            TileCollision::OneWay => Some(10.0),
+            TileCollision::Empty => Some(1.0),
+        });
+        let path = path_finder
+            .find_path(&grid, Vector2i::new(0, 0), Vector2i::new(4, 0))
+            .unwrap();
+        assert_eq!(13.0, path.cost);
+
+        path_finder.find_all(&grid, Vector2i::new(0, 0), 5.0);
+        assert_eq!(Some(1.0), path_finder.cost_to(Vector2i::new(1, 0)));
+        assert_eq!(None, path_finder.cost_to(Vector2i::new(2, 0)));
+    }
+}
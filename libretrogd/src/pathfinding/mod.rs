@@ -0,0 +1,7 @@
+pub use self::astar::*;
+pub use self::grid::*;
+pub use self::nodegraph::*;
+
+pub mod astar;
+pub mod grid;
+pub mod nodegraph;
@@ -0,0 +1,191 @@
+use crate::math::*;
+use crate::pathfinding::*;
+
+/// A [PathGraph] made up of nodes placed anywhere in the world and connected by edges, such as
+/// waypoints placed around a level, or the rooms and doors of a dungeon. Each node is identified
+/// by the index it was added at.
+///
+/// The straight-line distance between nodes is used as the [PathGraph::heuristic], so edge costs
+/// should be no less than the distance between the nodes they connect for the cheapest paths to
+/// always be found.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeGraph {
+    positions: Vec<Vector2>,
+    edges: Vec<Vec<(usize, f32)>>,
+}
+
+impl NodeGraph {
+    /// Creates a new graph with no nodes.
+    pub fn new() -> NodeGraph {
+        NodeGraph {
+            positions: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Returns the number of nodes in the graph.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns true if the graph has no nodes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Adds a node at the given position, returning its index.
+    pub fn add_node(&mut self, position: Vector2) -> usize {
+        self.positions.push(position);
+        self.edges.push(Vec::new());
+        self.positions.len() - 1
+    }
+
+    /// Returns the position of the given node, or `None` if there is no such node.
+    #[inline]
+    pub fn position(&self, node: usize) -> Option<Vector2> {
+        self.positions.get(node).copied()
+    }
+
+    /// Returns the index of the node closest to the given position, or `None` if the graph has no
+    /// nodes.
+    pub fn closest_node(&self, position: Vector2) -> Option<usize> {
+        self.positions
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(&position)
+                    .total_cmp(&b.distance_squared(&position))
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Adds a one-way edge from one node to another with the given cost, replacing any existing
+    /// edge between them. Returns false if either node does not exist.
+    pub fn add_edge(&mut self, from: usize, to: usize, cost: f32) -> bool {
+        if from >= self.len() || to >= self.len() {
+            return false;
+        }
+        let edges = &mut self.edges[from];
+        match edges.iter_mut().find(|(node, _)| *node == to) {
+            Some(edge) => edge.1 = cost,
+            None => edges.push((to, cost)),
+        }
+        true
+    }
+
+    /// Adds edges in both directions between two nodes, costing the distance between them.
+    /// Returns false if either node does not exist.
+    pub fn connect(&mut self, a: usize, b: usize) -> bool {
+        match (self.position(a), self.position(b)) {
+            (Some(position_a), Some(position_b)) => {
+                let distance = position_a.distance(&position_b);
+                self.add_edge(a, b, distance) && self.add_edge(b, a, distance)
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes the edge from one node to another, returning false if there was no such edge.
+    pub fn remove_edge(&mut self, from: usize, to: usize) -> bool {
+        if let Some(edges) = self.edges.get_mut(from) {
+            let len = edges.len();
+            edges.retain(|(node, _)| *node != to);
+            edges.len() != len
+        } else {
+            false
+        }
+    }
+
+    /// Returns the edges leading away from the given node, as the node each leads to and its
+    /// cost.
+    #[inline]
+    pub fn edges(&self, node: usize) -> &[(usize, f32)] {
+        self.edges
+            .get(node)
+            .map(|edges| edges.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+impl PathGraph for NodeGraph {
+    type Node = usize;
+
+    fn neighbours(&self, node: usize, neighbours: &mut Vec<(usize, f32)>) {
+        neighbours.extend_from_slice(self.edges(node));
+    }
+
+    fn heuristic(&self, from: usize, to: usize) -> f32 {
+        match (self.position(from), self.position(to)) {
+            (Some(from), Some(to)) => from.distance(&to),
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn building_graphs() {
+        let mut graph = NodeGraph::new();
+        assert!(graph.is_empty());
+        assert_eq!(None, graph.closest_node(Vector2::ZERO));
+
+        let a = graph.add_node(Vector2::new(0.0, 0.0));
+        let b = graph.add_node(Vector2::new(30.0, 40.0));
+        assert_eq!(2, graph.len());
+        assert_eq!(Some(Vector2::new(30.0, 40.0)), graph.position(b));
+        assert_eq!(None, graph.position(2));
+        assert_eq!(Some(b), graph.closest_node(Vector2::new(20.0, 30.0)));
+
+        assert!(graph.connect(a, b));
+        assert_eq!(&[(b, 50.0)], graph.edges(a));
+        assert_eq!(&[(a, 50.0)], graph.edges(b));
+
+        assert!(graph.add_edge(a, b, 80.0));
+        assert_eq!(&[(b, 80.0)], graph.edges(a));
+        assert!(!graph.add_edge(a, 2, 1.0));
+
+        assert!(graph.remove_edge(b, a));
+        assert!(!graph.remove_edge(b, a));
+        assert!(graph.edges(b).is_empty());
+        assert!(graph.edges(5).is_empty());
+    }
+
+    #[test]
+    pub fn finding_paths_between_nodes() {
+        //  a ---- b
+        //  |      |
+        //  c -d-- e
+        let mut graph = NodeGraph::new();
+        let a = graph.add_node(Vector2::new(0.0, 0.0));
+        let b = graph.add_node(Vector2::new(100.0, 0.0));
+        let c = graph.add_node(Vector2::new(0.0, 50.0));
+        let d = graph.add_node(Vector2::new(20.0, 50.0));
+        let e = graph.add_node(Vector2::new(100.0, 50.0));
+        graph.connect(a, b);
+        graph.connect(b, e);
+        graph.connect(a, c);
+        graph.connect(c, d);
+        graph.connect(d, e);
+
+        let mut path_finder = PathFinder::new();
+        let path = path_finder.find_path(&graph, c, e).unwrap();
+        assert_eq!(vec![c, d, e], path.nodes);
+        assert_eq!(100.0, path.cost);
+
+        // a one-way door
+        graph.remove_edge(d, e);
+        let path = path_finder.find_path(&graph, c, e).unwrap();
+        assert_eq!(vec![c, a, b, e], path.nodes);
+        assert_eq!(200.0, path.cost);
+        let path = path_finder.find_path(&graph, e, d).unwrap();
+        assert_eq!(vec![e, d], path.nodes);
+
+        graph.remove_edge(a, b);
+        assert_eq!(None, path_finder.find_path(&graph, c, e));
+    }
+}
@@ -4,6 +4,14 @@ use std::ops::DerefMut;
 
 use thiserror::Error;
 
+use crate::graphics::*;
+
+pub use self::sequence::*;
+pub use self::transition::*;
+
+pub mod sequence;
+pub mod transition;
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum TransitionTo {
     Paused,
@@ -51,6 +59,33 @@ pub trait GameState<ContextType> {
     fn render(&mut self, state: State, context: &mut ContextType);
     fn transition(&mut self, state: State, context: &mut ContextType) -> bool;
     fn state_change(&mut self, new_state: State, old_state: State, context: &mut ContextType);
+
+    /// Called when this state is first added to the stack, right before it starts transitioning
+    /// in. Called after [`GameState::state_change`] for the change to [`State::Pending`].
+    fn enter(&mut self, _context: &mut ContextType) {}
+
+    /// Called when this state has been removed from the stack (popped or swapped out), after it
+    /// has finished transitioning out. Called after [`GameState::state_change`] for the change to
+    /// [`State::Dead`].
+    fn leave(&mut self, _context: &mut ContextType) {}
+
+    /// Called when this state has finished transitioning out because another state has been
+    /// pushed on top of it. Called after [`GameState::state_change`] for the change to
+    /// [`State::Paused`].
+    fn pause(&mut self, _context: &mut ContextType) {}
+
+    /// Called when this state is back on top of the stack after the states above it have been
+    /// popped, right before it starts transitioning back in. Called after
+    /// [`GameState::state_change`] for the change to [`State::Resume`].
+    fn resume(&mut self, _context: &mut ContextType) {}
+
+    /// Returns the built-in transition effect that [`States`] should render while this state is
+    /// transitioning in or out, or `None` to use the default set via
+    /// [`States::set_default_transition`]. Transitions given via [`States::push_with`],
+    /// [`States::swap_with`] or [`States::pop_with`] take precedence over this.
+    fn transition_effect(&self, _direction: TransitionDirection) -> Option<Transition> {
+        None
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
@@ -107,6 +142,13 @@ impl<ContextType> StateContainer<ContextType> {
         let old_state = self.current_state;
         self.current_state = new_state;
         self.state.state_change(self.current_state, old_state, context);
+        match new_state {
+            State::Pending => self.state.enter(context),
+            State::Dead if old_state != State::Dead => self.state.leave(context),
+            State::Paused => self.state.pause(context),
+            State::Resume => self.state.resume(context),
+            _ => {}
+        }
     }
 
     #[inline]
@@ -182,6 +224,10 @@ pub struct States<ContextType> {
     command: Option<StateChange<ContextType>>,
     pending_state: Option<Box<dyn GameState<ContextType>>>,
     pop_count: Option<u32>,
+    default_transition: Transition,
+    command_transition: Option<Transition>,
+    active_transition: Option<ActiveTransition>,
+    restore_palettes: Option<(Palette, Palette)>,
 }
 
 impl<ContextType> std::fmt::Debug for States<ContextType> {
@@ -194,6 +240,9 @@ impl<ContextType> std::fmt::Debug for States<ContextType> {
                 None => &"None",
             })
             .field("pop_count", &self.pop_count)
+            .field("default_transition", &self.default_transition)
+            .field("command_transition", &self.command_transition)
+            .field("active_transition", &self.active_transition)
             .finish_non_exhaustive()
     }
 }
@@ -205,9 +254,26 @@ impl<ContextType> States<ContextType> {
             command: None,
             pending_state: None,
             pop_count: None,
+            default_transition: Transition::none(),
+            command_transition: None,
+            active_transition: None,
+            restore_palettes: None,
         }
     }
 
+    /// Returns the transition effect used when neither the state change nor the state itself
+    /// specifies one.
+    #[inline]
+    pub fn default_transition(&self) -> Transition {
+        self.default_transition
+    }
+
+    /// Sets the transition effect used when neither the state change nor the state itself
+    /// specifies one. This is [`Transition::none`] unless changed.
+    pub fn set_default_transition(&mut self, transition: Transition) {
+        self.default_transition = transition;
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.states.is_empty() && self.pending_state.is_none() && self.command.is_none()
@@ -263,6 +329,68 @@ impl<ContextType> States<ContextType> {
         }
     }
 
+    /// Pushes a new state like [`States::push`], using the given transition effect for both the
+    /// current state transitioning out and the new state transitioning in.
+    pub fn push_with(&mut self, state: impl GameState<ContextType> + 'static, transition: Transition) -> Result<(), StateError> {
+        self.push(state)?;
+        self.command_transition = Some(transition);
+        Ok(())
+    }
+
+    /// Replaces the current state like [`States::swap`], using the given transition effect for
+    /// both the current state transitioning out and the new state transitioning in.
+    pub fn swap_with(&mut self, state: impl GameState<ContextType> + 'static, transition: Transition) -> Result<(), StateError> {
+        self.swap(state)?;
+        self.command_transition = Some(transition);
+        Ok(())
+    }
+
+    /// Pops states like [`States::pop`], using the given transition effect for both the current
+    /// state transitioning out and the state underneath transitioning back in.
+    pub fn pop_with(&mut self, count: u32, transition: Transition) -> Result<(), StateError> {
+        self.pop(count)?;
+        self.command_transition = Some(transition);
+        Ok(())
+    }
+
+    /// Returns true if the state on top of the stack is currently transitioning in or out.
+    pub fn is_transitioning(&self) -> bool {
+        matches!(self.state_of_front_state(), Some(State::TransitionIn) | Some(State::TransitionOut(_)))
+    }
+
+    /// Makes sure the transition effect being run matches the direction the top state is
+    /// currently transitioning in (if any), starting or finishing effects as needed, and then
+    /// advances it. Returns true if the effect has completed (or if there is none).
+    fn step_transition_effect(&mut self) -> bool {
+        let direction = match self.state_of_front_state() {
+            Some(State::TransitionIn) => Some(TransitionDirection::In),
+            Some(State::TransitionOut(_)) => Some(TransitionDirection::Out),
+            _ => None,
+        };
+        if self.active_transition.as_ref().map(|transition| transition.direction()) != direction {
+            if let Some(mut finished) = self.active_transition.take() {
+                if finished.direction() == TransitionDirection::Out {
+                    // the screen is now fully covered, so the palette can be put back the way it
+                    // was before the fade without it being seen
+                    self.restore_palettes = finished.take_palettes();
+                }
+            }
+            if let Some(direction) = direction {
+                let transition = match self.command_transition {
+                    Some(transition) => transition,
+                    None => self.states.front()
+                        .and_then(|state| state.state.transition_effect(direction))
+                        .unwrap_or(self.default_transition),
+                };
+                self.active_transition = Some(ActiveTransition::new(transition, direction));
+            }
+        }
+        match &mut self.active_transition {
+            Some(transition) => transition.step(),
+            None => true,
+        }
+    }
+
     fn state_of_front_state(&self) -> Option<State> {
         if let Some(state) = self.states.front() {
             Some(state.current_state())
@@ -389,15 +517,17 @@ impl<ContextType> States<ContextType> {
                 return Err(StateError::GameStateInvalidState(State::Dead));
             },
             Some(State::TransitionIn) => {
+                let effect_done = self.step_transition_effect();
                 let state = self.states.front_mut().unwrap();
-                if state.state().transition(State::TransitionIn, context) {
+                if state.state().transition(State::TransitionIn, context) && effect_done {
                     // state has indicated it is done transitioning, so we can switch it to active
                     state.pending_activate();
                 }
             },
             Some(State::TransitionOut(to)) => {
+                let effect_done = self.step_transition_effect();
                 let state = self.states.front_mut().unwrap();
-                if state.state().transition(State::TransitionOut(to), context) {
+                if state.state().transition(State::TransitionOut(to), context) && effect_done {
                     // state has indicated it is done transitioning, so we can switch it to whatever
                     // it was transitioning to
                     match to {
@@ -406,7 +536,14 @@ impl<ContextType> States<ContextType> {
                     }
                 }
             },
-            _ => {}
+            Some(State::Active) => {
+                // the transitions involved in the last state change are over
+                self.step_transition_effect();
+                self.command_transition = None;
+            },
+            _ => {
+                self.step_transition_effect();
+            }
         }
 
         Ok(())
@@ -443,11 +580,32 @@ impl<ContextType> States<ContextType> {
             }
         }
     }
+
+    /// Renders the built-in transition effect (if any) for the state on top of the stack that is
+    /// currently transitioning in or out. This should be called each frame right after
+    /// [`States::render`], with the same screen and palette that the states render to.
+    ///
+    /// # Arguments
+    ///
+    /// * `dest`: the (already rendered) screen to render the effect over
+    /// * `palette`: the palette being used to display the screen
+    pub fn render_transition(&mut self, dest: &mut Bitmap, palette: &mut Palette) {
+        if let Some((original, faded)) = self.restore_palettes.take() {
+            // unless the state now on top has already set up a palette of its own, put back the
+            // palette as it was before the last fade out
+            if *palette == faded {
+                *palette = original;
+            }
+        }
+        if let Some(transition) = &mut self.active_transition {
+            transition.render(dest, palette);
+        }
+    }
 }
 
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use claim::*;
 
     use super::*;
@@ -458,6 +616,7 @@ mod tests {
         Render(u32, State),
         Transition(u32, State),
         StateChange(u32, State, State),
+        Callback(u32, &'static str),
     }
 
     struct TestContext {
@@ -1547,4 +1706,87 @@ mod tests {
 
         Ok(())
     }
+
+    struct CallbackState {
+        id: u32,
+    }
+
+    impl GameState<TestContext> for CallbackState {
+        fn update(&mut self, _state: State, _context: &mut TestContext) -> Option<StateChange<TestContext>> {
+            None
+        }
+
+        fn render(&mut self, _state: State, _context: &mut TestContext) {}
+
+        fn transition(&mut self, _state: State, _context: &mut TestContext) -> bool {
+            true
+        }
+
+        fn state_change(&mut self, _new_state: State, _old_state: State, _context: &mut TestContext) {}
+
+        fn enter(&mut self, context: &mut TestContext) {
+            context.log(LogEntry::Callback(self.id, "enter"));
+        }
+
+        fn leave(&mut self, context: &mut TestContext) {
+            context.log(LogEntry::Callback(self.id, "leave"));
+        }
+
+        fn pause(&mut self, context: &mut TestContext) {
+            context.log(LogEntry::Callback(self.id, "pause"));
+        }
+
+        fn resume(&mut self, context: &mut TestContext) {
+            context.log(LogEntry::Callback(self.id, "resume"));
+        }
+    }
+
+    #[test]
+    fn state_callbacks_and_transition_effects() -> Result<(), StateError> {
+        const FIRST: u32 = 1;
+        const SECOND: u32 = 2;
+
+        let mut states = States::<TestContext>::new();
+        let mut context = TestContext::new();
+        let mut screen = Bitmap::new(16, 10).unwrap();
+        let mut palette = Palette::new_with_default(255, 255, 255);
+        let mut tick_and_render = |states: &mut States<TestContext>, context: &mut TestContext| {
+            screen.clear(0);
+            tick(states, context)?;
+            states.render_transition(&mut screen, &mut palette);
+            Ok::<usize, StateError>(screen.pixels().iter().filter(|&&pixel| pixel == 7).count())
+        };
+
+        // the default transition effect holds the state in its transition until it completes
+        states.set_default_transition(Transition::fizzle(7, 2));
+        states.push(CallbackState { id: FIRST })?;
+        let covered = tick_and_render(&mut states, &mut context)?;
+        assert!(covered > 0 && covered < 160);
+        assert_eq!(context.take_log(), vec![LogEntry::Callback(FIRST, "enter")]);
+        assert!(states.is_transitioning());
+        assert_eq!(0, tick_and_render(&mut states, &mut context)?);
+        assert!(states.is_transitioning());
+        tick_and_render(&mut states, &mut context)?;
+        assert!(!states.is_transitioning());
+
+        // transitions given for a state change take precedence over the default
+        states.push_with(CallbackState { id: SECOND }, Transition::none())?;
+        for _ in 0..3 {
+            assert_eq!(0, tick_and_render(&mut states, &mut context)?);
+        }
+        assert!(!states.is_transitioning());
+        assert_eq!(context.take_log(), vec![LogEntry::Callback(FIRST, "pause"), LogEntry::Callback(SECOND, "enter")]);
+
+        states.pop(1)?;
+        let mut covered = Vec::new();
+        for _ in 0..5 {
+            covered.push(tick_and_render(&mut states, &mut context)?);
+        }
+        assert_eq!(160, covered[1]);
+        assert_eq!(0, covered[4]);
+        assert!(!states.is_transitioning());
+        assert_eq!(context.take_log(), vec![LogEntry::Callback(SECOND, "leave"), LogEntry::Callback(FIRST, "resume")]);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,322 @@
+use std::fmt::Formatter;
+
+use crate::states::*;
+
+type ConditionFn<ContextType> = Box<dyn FnMut(&mut ContextType) -> bool>;
+type ActionFn<ContextType> = Box<dyn FnMut(&mut ContextType)>;
+type StateChangeFn<ContextType> = Box<dyn FnMut(&mut ContextType) -> StateChange<ContextType>>;
+
+/// A single step of a [`Sequence`].
+pub enum SequenceStep<ContextType> {
+    /// Waits for the given number of ticks before moving on to the next step.
+    Wait(u32),
+    /// Waits until the given function returns true. The function is called once per tick,
+    /// starting with the tick this step is reached on.
+    WaitUntil(ConditionFn<ContextType>),
+    /// Calls the given function and then moves straight on to the next step in the same tick.
+    Run(ActionFn<ContextType>),
+    /// Calls the given function and returns the [`StateChange`] it returns from
+    /// [`Sequence::update`], moving on to the next step on the following tick.
+    ChangeState(StateChangeFn<ContextType>),
+}
+
+impl<ContextType> std::fmt::Debug for SequenceStep<ContextType> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use SequenceStep::*;
+        match *self {
+            Wait(ticks) => write!(f, "Wait({})", ticks),
+            WaitUntil(..) => write!(f, "WaitUntil"),
+            Run(..) => write!(f, "Run"),
+            ChangeState(..) => write!(f, "ChangeState"),
+        }
+    }
+}
+
+/// A scripted sequence of steps, run a little at a time once per tick (typically from a
+/// [`GameState::update`]), that can wait for a number of ticks or for some condition in between
+/// running other code. Useful for things like cutscenes, scripted intros and boss behaviours,
+/// which would otherwise end up written as state machines matching on counters.
+///
+/// ```
+/// use libretrogd::states::*;
+///
+/// struct Game {
+///     message: &'static str,
+///     boss_health: u32,
+/// }
+///
+/// struct IntroState {
+///     script: Sequence<Game>,
+/// }
+///
+/// impl IntroState {
+///     pub fn new() -> Self {
+///         IntroState {
+///             script: Sequence::new()
+///                 .run(|game: &mut Game| game.message = "A wild boss appears!")
+///                 .wait(120)
+///                 .run(|game: &mut Game| game.message = "")
+///                 .wait_until(|game: &mut Game| game.boss_health == 0)
+///                 .change_state(|_game: &mut Game| StateChange::Pop(1)),
+///         }
+///     }
+/// }
+///
+/// impl GameState<Game> for IntroState {
+///     fn update(&mut self, state: State, context: &mut Game) -> Option<StateChange<Game>> {
+///         if state == State::Active {
+///             self.script.update(context)
+///         } else {
+///             None
+///         }
+///     }
+///
+///     fn render(&mut self, _state: State, _context: &mut Game) {}
+///
+///     fn transition(&mut self, _state: State, _context: &mut Game) -> bool {
+///         true
+///     }
+///
+///     fn state_change(&mut self, new_state: State, _old_state: State, _context: &mut Game) {
+///         if new_state == State::Pending {
+///             self.script.reset();
+///         }
+///     }
+/// }
+/// ```
+pub struct Sequence<ContextType> {
+    steps: Vec<SequenceStep<ContextType>>,
+    current_step: usize,
+    ticks_waited: u32,
+    looping: bool,
+}
+
+impl<ContextType> std::fmt::Debug for Sequence<ContextType> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sequence")
+            .field("steps", &self.steps)
+            .field("current_step", &self.current_step)
+            .field("ticks_waited", &self.ticks_waited)
+            .field("looping", &self.looping)
+            .finish()
+    }
+}
+
+impl<ContextType> Default for Sequence<ContextType> {
+    fn default() -> Self {
+        Sequence::new()
+    }
+}
+
+impl<ContextType> Sequence<ContextType> {
+    /// Creates and returns a new sequence with no steps.
+    pub fn new() -> Self {
+        Sequence {
+            steps: Vec::new(),
+            current_step: 0,
+            ticks_waited: 0,
+            looping: false,
+        }
+    }
+
+    /// Returns this sequence with the given step added to the end of it.
+    pub fn then(mut self, step: SequenceStep<ContextType>) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Returns this sequence with a step added to the end of it that waits for the given number
+    /// of ticks.
+    #[inline]
+    pub fn wait(self, ticks: u32) -> Self {
+        self.then(SequenceStep::Wait(ticks))
+    }
+
+    /// Returns this sequence with a step added to the end of it that waits until the given
+    /// function returns true.
+    #[inline]
+    pub fn wait_until(self, f: impl FnMut(&mut ContextType) -> bool + 'static) -> Self {
+        self.then(SequenceStep::WaitUntil(Box::new(f)))
+    }
+
+    /// Returns this sequence with a step added to the end of it that calls the given function.
+    #[inline]
+    pub fn run(self, f: impl FnMut(&mut ContextType) + 'static) -> Self {
+        self.then(SequenceStep::Run(Box::new(f)))
+    }
+
+    /// Returns this sequence with a step added to the end of it that calls the given function
+    /// and has [`Sequence::update`] return the state change that it returns.
+    #[inline]
+    pub fn change_state(
+        self,
+        f: impl FnMut(&mut ContextType) -> StateChange<ContextType> + 'static,
+    ) -> Self {
+        self.then(SequenceStep::ChangeState(Box::new(f)))
+    }
+
+    /// Returns this sequence set to start over from the first step each time it finishes, instead
+    /// of stopping.
+    pub fn looped(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    /// Returns the number of steps in this sequence.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns true if this sequence has no steps.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns the index of the step that will be run on the next tick.
+    #[inline]
+    pub fn current_step(&self) -> usize {
+        self.current_step
+    }
+
+    /// Returns true if all of the steps have been run. Looping sequences never finish.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.current_step >= self.steps.len()
+    }
+
+    /// Starts this sequence over again from the first step.
+    pub fn reset(&mut self) {
+        self.current_step = 0;
+        self.ticks_waited = 0;
+    }
+
+    #[inline]
+    fn next_step(&mut self) {
+        self.current_step += 1;
+        self.ticks_waited = 0;
+    }
+
+    /// Runs this sequence for one tick, running each step in turn until one of them waits or
+    /// changes state, or until there are no steps left. This would normally be called once from
+    /// each call to [`GameState::update`].
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: the context passed to the functions in each step
+    ///
+    /// returns: the state change returned by a [`SequenceStep::ChangeState`] step, if one was run
+    pub fn update(&mut self, context: &mut ContextType) -> Option<StateChange<ContextType>> {
+        let mut steps_run = 0;
+        loop {
+            if self.current_step >= self.steps.len() {
+                // never run more steps than there are in one tick, in case none of them ever wait
+                if !self.looping || steps_run >= self.steps.len() {
+                    return None;
+                }
+                self.reset();
+            }
+            steps_run += 1;
+
+            match &mut self.steps[self.current_step] {
+                SequenceStep::Wait(ticks) => {
+                    if self.ticks_waited < *ticks {
+                        self.ticks_waited += 1;
+                        return None;
+                    }
+                    self.next_step();
+                }
+                SequenceStep::WaitUntil(f) => {
+                    if !f(context) {
+                        return None;
+                    }
+                    self.next_step();
+                }
+                SequenceStep::Run(f) => {
+                    f(context);
+                    self.next_step();
+                }
+                SequenceStep::ChangeState(f) => {
+                    let state_change = f(context);
+                    self.next_step();
+                    return Some(state_change);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Context {
+        pub log: Vec<&'static str>,
+        pub ready: bool,
+    }
+
+    #[test]
+    pub fn running_sequences() {
+        let mut context = Context::default();
+        let mut sequence = Sequence::new()
+            .run(|context: &mut Context| context.log.push("start"))
+            .wait(2)
+            .run(|context: &mut Context| context.log.push("waited"))
+            .wait_until(|context: &mut Context| context.ready)
+            .run(|context: &mut Context| context.log.push("ready"))
+            .change_state(|_context: &mut Context| StateChange::Pop(1))
+            .run(|context: &mut Context| context.log.push("end"));
+        assert_eq!(7, sequence.len());
+
+        assert_none!(sequence.update(&mut context));
+        assert_eq!(vec!["start"], context.log);
+        assert_none!(sequence.update(&mut context));
+        assert_eq!(vec!["start"], context.log);
+        assert_none!(sequence.update(&mut context));
+        assert_eq!(vec!["start", "waited"], context.log);
+        assert_none!(sequence.update(&mut context));
+        assert_eq!(3, sequence.current_step());
+
+        context.ready = true;
+        assert_matches!(sequence.update(&mut context), Some(StateChange::Pop(1)));
+        assert_eq!(vec!["start", "waited", "ready"], context.log);
+        assert!(!sequence.is_finished());
+        assert_none!(sequence.update(&mut context));
+        assert_eq!(vec!["start", "waited", "ready", "end"], context.log);
+        assert!(sequence.is_finished());
+        assert_none!(sequence.update(&mut context));
+
+        context.log.clear();
+        sequence.reset();
+        assert_none!(sequence.update(&mut context));
+        assert_eq!(vec!["start"], context.log);
+    }
+
+    #[test]
+    pub fn looping_sequences() {
+        let mut context = Context::default();
+        let mut sequence = Sequence::new()
+            .run(|context: &mut Context| context.log.push("a"))
+            .wait(2)
+            .looped();
+        for _ in 0..4 {
+            sequence.update(&mut context);
+        }
+        assert_eq!(vec!["a", "a"], context.log);
+        assert!(!sequence.is_finished());
+
+        // sequences that never wait only run once per tick
+        let mut sequence = Sequence::new()
+            .run(|context: &mut Context| context.log.push("b"))
+            .looped();
+        context.log.clear();
+        sequence.update(&mut context);
+        assert_eq!(vec!["b"], context.log);
+        sequence.update(&mut context);
+        assert_eq!(vec!["b", "b"], context.log);
+    }
+}
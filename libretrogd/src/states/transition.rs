@@ -0,0 +1,262 @@
+use crate::graphics::*;
+use crate::states::TransitionDirection;
+
+/// The edge of the screen that a [`TransitionEffect::Slide`] slides in from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SlideFrom {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// A built-in visual effect that [`crate::states::States`] can render over the screen while a
+/// [`crate::states::GameState`] is transitioning in or out. When transitioning out, the effect
+/// progressively covers up the screen, and when transitioning in it progressively uncovers it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TransitionEffect {
+    /// No effect, the screen is left as the state rendered it.
+    None,
+    /// Fades all of the palette's colors towards the given RGB color.
+    PaletteFade { r: u8, g: u8, b: u8 },
+    /// Fills in the screen one pixel at a time in a random-looking order with the given color.
+    Fizzle { color: u8 },
+    /// Slides a solid panel of the given color across the screen from the given edge.
+    Slide { from: SlideFrom, color: u8 },
+}
+
+/// A [`TransitionEffect`] along with how many frames (calls to
+/// [`crate::states::States::update`]) it takes to fully cover or uncover the screen.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    pub frames: u32,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Transition::none()
+    }
+}
+
+impl Transition {
+    /// Returns a transition with no effect that completes immediately. States still transition
+    /// in and out for however long their own [`crate::states::GameState::transition`] takes.
+    pub fn none() -> Self {
+        Transition {
+            effect: TransitionEffect::None,
+            frames: 0,
+        }
+    }
+
+    /// Returns a transition that fades the palette to (or from) the given RGB color.
+    pub fn palette_fade(r: u8, g: u8, b: u8, frames: u32) -> Self {
+        Transition {
+            effect: TransitionEffect::PaletteFade { r, g, b },
+            frames,
+        }
+    }
+
+    /// Returns a transition that fades the palette to (or from) black.
+    pub fn fade_to_black(frames: u32) -> Self {
+        Self::palette_fade(0, 0, 0, frames)
+    }
+
+    /// Returns a transition that fizzles the screen to (or from) the given color.
+    pub fn fizzle(color: u8, frames: u32) -> Self {
+        Transition {
+            effect: TransitionEffect::Fizzle { color },
+            frames,
+        }
+    }
+
+    /// Returns a transition that slides a panel of the given color over (or off of) the screen.
+    pub fn slide(from: SlideFrom, color: u8, frames: u32) -> Self {
+        Transition {
+            effect: TransitionEffect::Slide { from, color },
+            frames,
+        }
+    }
+}
+
+/// Scrambles a pixel index into a pseudo-random value. This is a bijection, so the order that
+/// pixels are fizzled in is spread evenly over the whole screen.
+#[inline]
+fn fizzle_order(index: u32) -> u32 {
+    let mut x = index;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x
+}
+
+/// A [`Transition`] that is currently being run by [`crate::states::States`].
+#[derive(Debug, Clone)]
+pub(crate) struct ActiveTransition {
+    transition: Transition,
+    direction: TransitionDirection,
+    current_frame: u32,
+    original_palette: Option<Palette>,
+    faded_palette: Option<Palette>,
+}
+
+impl ActiveTransition {
+    pub fn new(transition: Transition, direction: TransitionDirection) -> Self {
+        ActiveTransition {
+            transition,
+            direction,
+            current_frame: 0,
+            original_palette: None,
+            faded_palette: None,
+        }
+    }
+
+    #[inline]
+    pub fn direction(&self) -> TransitionDirection {
+        self.direction
+    }
+
+    /// Advances the effect by one frame, returning true if it has completed.
+    pub fn step(&mut self) -> bool {
+        if self.current_frame < self.transition.frames {
+            self.current_frame += 1;
+        }
+        self.is_complete()
+    }
+
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.current_frame >= self.transition.frames
+    }
+
+    /// Returns how much of the screen is currently covered by the effect, from 0.0 (not at all)
+    /// to 1.0 (completely).
+    pub fn coverage(&self) -> f32 {
+        let progress = if self.transition.frames == 0 {
+            1.0
+        } else {
+            self.current_frame as f32 / self.transition.frames as f32
+        };
+        match self.direction {
+            TransitionDirection::In => 1.0 - progress,
+            TransitionDirection::Out => progress,
+        }
+    }
+
+    /// Returns the palette as it was before a palette fade started modifying it, along with the
+    /// palette as it was last left by the fade, if this is a palette fade that has started.
+    pub fn take_palettes(&mut self) -> Option<(Palette, Palette)> {
+        self.original_palette.take().zip(self.faded_palette.take())
+    }
+
+    /// Renders the effect over the given (already rendered) screen and palette.
+    pub fn render(&mut self, dest: &mut Bitmap, palette: &mut Palette) {
+        let coverage = self.coverage();
+        match self.transition.effect {
+            TransitionEffect::None => {}
+            TransitionEffect::PaletteFade { r, g, b } => {
+                let original = self.original_palette.get_or_insert_with(|| palette.clone());
+                palette.lerp(
+                    0..=255,
+                    original,
+                    &Palette::new_with_default(r, g, b),
+                    coverage,
+                );
+                self.faded_palette = Some(palette.clone());
+            }
+            TransitionEffect::Fizzle { color } => {
+                let threshold = (coverage as f64 * (u32::MAX as f64 + 1.0)) as u64;
+                for (index, pixel) in dest.pixels_mut().iter_mut().enumerate() {
+                    if (fizzle_order(index as u32) as u64) < threshold {
+                        *pixel = color;
+                    }
+                }
+            }
+            TransitionEffect::Slide { from, color } => {
+                let width = dest.width() as i32;
+                let height = dest.height() as i32;
+                let covered_x = (coverage * width as f32).round() as i32;
+                let covered_y = (coverage * height as f32).round() as i32;
+                match from {
+                    SlideFrom::Left if covered_x > 0 => {
+                        dest.filled_rect(0, 0, covered_x - 1, height - 1, color)
+                    }
+                    SlideFrom::Right if covered_x > 0 => {
+                        dest.filled_rect(width - covered_x, 0, width - 1, height - 1, color)
+                    }
+                    SlideFrom::Top if covered_y > 0 => {
+                        dest.filled_rect(0, 0, width - 1, covered_y - 1, color)
+                    }
+                    SlideFrom::Bottom if covered_y > 0 => {
+                        dest.filled_rect(0, height - covered_y, width - 1, height - 1, color)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn count_pixels(bitmap: &Bitmap, color: u8) -> usize {
+        bitmap
+            .pixels()
+            .iter()
+            .filter(|&&pixel| pixel == color)
+            .count()
+    }
+
+    #[test]
+    pub fn transition_effects_cover_and_uncover_screen() {
+        let mut bitmap = Bitmap::new(16, 10).unwrap();
+        let mut palette = Palette::new_with_default(255, 255, 255);
+
+        let mut slide = ActiveTransition::new(
+            Transition::slide(SlideFrom::Left, 1, 4),
+            TransitionDirection::Out,
+        );
+        slide.step();
+        slide.render(&mut bitmap, &mut palette);
+        assert_eq!(40, count_pixels(&bitmap, 1));
+        assert_eq!(1, bitmap.pixels()[3]);
+        assert_eq!(0, bitmap.pixels()[4]);
+        while !slide.step() {}
+        slide.render(&mut bitmap, &mut palette);
+        assert_eq!(160, count_pixels(&bitmap, 1));
+
+        bitmap.clear(0);
+        let mut fizzle = ActiveTransition::new(Transition::fizzle(2, 2), TransitionDirection::In);
+        fizzle.render(&mut bitmap, &mut palette);
+        assert_eq!(160, count_pixels(&bitmap, 2));
+        bitmap.clear(0);
+        fizzle.step();
+        fizzle.render(&mut bitmap, &mut palette);
+        let covered = count_pixels(&bitmap, 2);
+        assert!(covered > 40 && covered < 120);
+        bitmap.clear(0);
+        assert!(fizzle.step());
+        fizzle.render(&mut bitmap, &mut palette);
+        assert_eq!(0, count_pixels(&bitmap, 2));
+
+        let mut fade =
+            ActiveTransition::new(Transition::fade_to_black(2), TransitionDirection::Out);
+        fade.step();
+        fade.render(&mut bitmap, &mut palette);
+        assert_eq!(0xff7f7f7f, palette[0] | 0xff000000);
+        fade.step();
+        fade.render(&mut bitmap, &mut palette);
+        assert_eq!(0xff000000, palette[255] | 0xff000000);
+        assert_eq!(
+            Some((
+                Palette::new_with_default(255, 255, 255),
+                Palette::new_with_default(0, 0, 0)
+            )),
+            fade.take_palettes()
+        );
+    }
+}
@@ -0,0 +1,702 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::system::*;
+use crate::DEFAULT_SCALE_FACTOR;
+
+const VIDEO_SECTION: &str = "video";
+const AUDIO_SECTION: &str = "audio";
+const INPUT_SECTION: &str = "input";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Bad or unsupported config data: {0}")]
+    BadData(String),
+
+    #[error("Invalid config section or key name: \"{0}\"")]
+    InvalidName(String),
+
+    #[error("Invalid value for \"{section}.{key}\": {value}")]
+    InvalidValue {
+        section: String,
+        key: String,
+        value: String,
+    },
+
+    #[error("Failed to apply config to the system")]
+    SystemError(#[from] SystemError),
+
+    #[error("Config I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+fn validate_name(name: &str) -> Result<(), ConfigError> {
+    if name.is_empty()
+        || name != name.trim()
+        || name.starts_with([';', '#'])
+        || name.contains(['[', ']', '=', '\n', '\r'])
+    {
+        Err(ConfigError::InvalidName(String::from(name)))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_value(section: &str, key: &str, value: &str) -> Result<(), ConfigError> {
+    if value != value.trim() || value.contains(['\n', '\r']) {
+        Err(ConfigError::InvalidValue {
+            section: String::from(section),
+            key: String::from(key),
+            value: String::from(value),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+type Sections = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Identifies a config value that was changed. See [`Config::take_changes`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ConfigChange {
+    pub section: String,
+    pub key: String,
+}
+
+/// Engine and game settings, held as string values organized into named sections, which can be
+/// loaded from and saved to INI-style config files. Each value can have a default, which is
+/// returned until a value is explicitly set (or loaded). Every change to a value is recorded so
+/// that a game can react to settings changed from anywhere (an options menu, a dev console, a
+/// reloaded config file, etc.) by checking [`Config::take_changes`] once per frame.
+///
+/// Some sections are used by the engine itself, and have accessors for them:
+///
+/// * `[video]`: `scale` (the window scale factor) and `fullscreen`.
+/// * `[audio]`: `volume` and `music_volume`, both 1.0 by default.
+/// * `[input]`: one key per [`InputMap`] action, with a comma separated list of that action's
+///   bindings in the text form used by [`InputBinding`]'s `Display` implementation.
+///
+/// Games are free to add any other sections of their own.
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use libretrogd::system::*;
+/// use sdl2::keyboard::Scancode;
+///
+/// let mut config = Config::new();
+/// config.set_default("game", "difficulty", "normal").unwrap();
+/// config.merge_from_file(Path::new("game.ini")).unwrap();
+///
+/// let mut builder = SystemBuilder::new();
+/// config.apply_to_builder(&mut builder);
+/// let mut system = builder.window_title("Game").build().unwrap();
+/// config.apply_to_system(&mut system).unwrap();
+/// config.take_changes();
+///
+/// while !system.keyboard.is_key_pressed(Scancode::Escape) {
+///     system.do_events();
+///     for change in config.take_changes() {
+///         // react to settings changed since the last frame ...
+///     }
+///     let difficulty = config.get("game", "difficulty");
+///     // ...
+/// }
+///
+/// config.read_from_system(&mut system).unwrap();
+/// config.to_file(Path::new("game.ini")).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    values: Sections,
+    defaults: Sections,
+    changes: Vec<ConfigChange>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::new()
+    }
+}
+
+impl Config {
+    /// Creates and returns a new config with no values set, and with defaults for the engine's
+    /// own video and audio settings.
+    pub fn new() -> Self {
+        let mut config = Config {
+            values: BTreeMap::new(),
+            defaults: BTreeMap::new(),
+            changes: Vec::new(),
+        };
+        config.insert_default(VIDEO_SECTION, "scale", DEFAULT_SCALE_FACTOR.to_string());
+        config.insert_default(VIDEO_SECTION, "fullscreen", String::from("false"));
+        config.insert_default(AUDIO_SECTION, "volume", String::from("1"));
+        config.insert_default(AUDIO_SECTION, "music_volume", String::from("1"));
+        config
+    }
+
+    fn insert_default(&mut self, section: &str, key: &str, value: String) {
+        self.defaults
+            .entry(String::from(section))
+            .or_default()
+            .insert(String::from(key), value);
+    }
+
+    fn insert_value(&mut self, section: &str, key: &str, value: String) {
+        let changed = self.get(section, key) != Some(value.as_str());
+        self.values
+            .entry(String::from(section))
+            .or_default()
+            .insert(String::from(key), value);
+        if changed {
+            self.record_change(section, key);
+        }
+    }
+
+    fn record_change(&mut self, section: &str, key: &str) {
+        if !self
+            .changes
+            .iter()
+            .any(|change| change.section == section && change.key == key)
+        {
+            self.changes.push(ConfigChange {
+                section: String::from(section),
+                key: String::from(key),
+            });
+        }
+    }
+
+    /// Sets the default for a value, which is returned until a value is explicitly set. Changing
+    /// a default is not recorded as a change, as defaults are meant to be set up front. Section
+    /// and key names cannot be empty, have leading or trailing whitespace, or contain any of
+    /// "[", "]" or "=". Values cannot have leading or trailing whitespace. Neither can contain
+    /// line breaks.
+    ///
+    /// # Arguments
+    ///
+    /// * `section`: the name of the section the value is in
+    /// * `key`: the name of the value
+    /// * `value`: the default value
+    pub fn set_default(
+        &mut self,
+        section: &str,
+        key: &str,
+        value: impl ToString,
+    ) -> Result<(), ConfigError> {
+        let value = value.to_string();
+        validate_name(section)?;
+        validate_name(key)?;
+        validate_value(section, key, &value)?;
+        self.insert_default(section, key, value);
+        Ok(())
+    }
+
+    /// Sets a value, recording a change if this is different from its current value. See
+    /// [`Config::set_default`] for the restrictions on names and values.
+    ///
+    /// # Arguments
+    ///
+    /// * `section`: the name of the section the value is in
+    /// * `key`: the name of the value
+    /// * `value`: the new value
+    pub fn set(
+        &mut self,
+        section: &str,
+        key: &str,
+        value: impl ToString,
+    ) -> Result<(), ConfigError> {
+        let value = value.to_string();
+        validate_name(section)?;
+        validate_name(key)?;
+        validate_value(section, key, &value)?;
+        self.insert_value(section, key, value);
+        Ok(())
+    }
+
+    /// Returns the value with the given section and key, or its default if it has not been set.
+    /// Returns `None` if neither is present.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.values
+            .get(section)
+            .and_then(|values| values.get(key))
+            .or_else(|| {
+                self.defaults
+                    .get(section)
+                    .and_then(|values| values.get(key))
+            })
+            .map(|value| value.as_str())
+    }
+
+    /// Returns the value with the given section and key (or its default) parsed as the given
+    /// type, or `None` if there is no such value or it could not be parsed.
+    pub fn get_as<T: FromStr>(&self, section: &str, key: &str) -> Option<T> {
+        self.get(section, key).and_then(|value| value.parse().ok())
+    }
+
+    /// Returns the value with the given section and key (or its default) parsed as a boolean,
+    /// accepting "true", "yes", "on" and "1", or "false", "no", "off" and "0", ignoring case.
+    /// Returns `None` if there is no such value or it is not one of those.
+    pub fn get_bool(&self, section: &str, key: &str) -> Option<bool> {
+        self.get(section, key).and_then(parse_bool)
+    }
+
+    /// Returns true if a value with the given section and key has been explicitly set (or
+    /// loaded), as opposed to only having a default.
+    pub fn is_set(&self, section: &str, key: &str) -> bool {
+        self.values
+            .get(section)
+            .is_some_and(|values| values.contains_key(key))
+    }
+
+    /// Removes the value with the given section and key, reverting it to its default (if any).
+    /// Returns true if the value had been set.
+    pub fn remove(&mut self, section: &str, key: &str) -> bool {
+        let before = self.get(section, key).map(String::from);
+        let removed = match self.values.get_mut(section) {
+            Some(values) => {
+                let removed = values.remove(key).is_some();
+                if values.is_empty() {
+                    self.values.remove(section);
+                }
+                removed
+            }
+            None => false,
+        };
+        if before.as_deref() != self.get(section, key) {
+            self.record_change(section, key);
+        }
+        removed
+    }
+
+    /// Returns an iterator over the names of all sections that have values or defaults, in
+    /// sorted order.
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        let mut sections: Vec<&str> = self
+            .values
+            .keys()
+            .chain(self.defaults.keys())
+            .map(|section| section.as_str())
+            .collect();
+        sections.sort_unstable();
+        sections.dedup();
+        sections.into_iter()
+    }
+
+    /// Returns an iterator over the keys of all values or defaults in the given section, in
+    /// sorted order.
+    pub fn keys(&self, section: &str) -> impl Iterator<Item = &str> {
+        let mut keys: Vec<&str> = self
+            .values
+            .get(section)
+            .into_iter()
+            .chain(self.defaults.get(section))
+            .flat_map(|values| values.keys())
+            .map(|key| key.as_str())
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys.into_iter()
+    }
+
+    /// Returns true if any values have changed since the last call to [`Config::take_changes`].
+    #[inline]
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Returns all of the values that have changed since the last time this was called, in the
+    /// order they were first changed, clearing the list of changes. Each value is only listed
+    /// once no matter how many times it changed.
+    pub fn take_changes(&mut self) -> Vec<ConfigChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Returns the window scale factor from the `[video]` section.
+    pub fn scale_factor(&self) -> u32 {
+        self.get_as(VIDEO_SECTION, "scale")
+            .unwrap_or(DEFAULT_SCALE_FACTOR)
+            .max(1)
+    }
+
+    /// Sets the window scale factor in the `[video]` section.
+    pub fn set_scale_factor(&mut self, scale_factor: u32) {
+        self.insert_value(VIDEO_SECTION, "scale", scale_factor.to_string());
+    }
+
+    /// Returns whether the window should be fullscreen from the `[video]` section.
+    pub fn is_fullscreen(&self) -> bool {
+        self.get_bool(VIDEO_SECTION, "fullscreen").unwrap_or(false)
+    }
+
+    /// Sets whether the window should be fullscreen in the `[video]` section.
+    pub fn set_fullscreen(&mut self, enable: bool) {
+        self.insert_value(VIDEO_SECTION, "fullscreen", enable.to_string());
+    }
+
+    /// Returns the overall audio volume from the `[audio]` section.
+    pub fn volume(&self) -> f32 {
+        self.get_as(AUDIO_SECTION, "volume").unwrap_or(1.0)
+    }
+
+    /// Sets the overall audio volume in the `[audio]` section.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.insert_value(AUDIO_SECTION, "volume", volume.to_string());
+    }
+
+    /// Returns the music volume (relative to the overall volume) from the `[audio]` section.
+    pub fn music_volume(&self) -> f32 {
+        self.get_as(AUDIO_SECTION, "music_volume").unwrap_or(1.0)
+    }
+
+    /// Sets the music volume (relative to the overall volume) in the `[audio]` section.
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.insert_value(AUDIO_SECTION, "music_volume", volume.to_string());
+    }
+
+    /// Returns a new input map with all of the actions and bindings from the `[input]` section.
+    pub fn input_map(&self) -> Result<InputMap, ConfigError> {
+        let mut input_map = InputMap::new();
+        for action in self.keys(INPUT_SECTION) {
+            let bindings = self.get(INPUT_SECTION, action).unwrap_or("");
+            for binding in bindings.split(',').filter(|s| !s.trim().is_empty()) {
+                match binding.parse() {
+                    Ok(binding) => input_map.bind(action, binding),
+                    Err(_) => {
+                        return Err(ConfigError::InvalidValue {
+                            section: String::from(INPUT_SECTION),
+                            key: String::from(action),
+                            value: String::from(bindings),
+                        })
+                    }
+                }
+            }
+        }
+        Ok(input_map)
+    }
+
+    /// Replaces the `[input]` section with all of the actions and bindings in the given input
+    /// map. Action names must follow the same restrictions as keys (see
+    /// [`Config::set_default`]).
+    pub fn set_input_map(&mut self, input_map: &InputMap) -> Result<(), ConfigError> {
+        let mut bindings = Vec::new();
+        for action in input_map.actions() {
+            validate_name(action)?;
+            let action_bindings = input_map.bindings(action).unwrap_or(&[]);
+            let action_bindings: Vec<String> =
+                action_bindings.iter().map(|b| b.to_string()).collect();
+            bindings.push((action, action_bindings.join(", ")));
+        }
+        let removed: Vec<String> = self
+            .keys(INPUT_SECTION)
+            .filter(|action| !input_map.has_action(action))
+            .map(String::from)
+            .collect();
+        for action in removed.iter() {
+            self.remove(INPUT_SECTION, action);
+        }
+        for (action, action_bindings) in bindings {
+            self.insert_value(INPUT_SECTION, action, action_bindings);
+        }
+        Ok(())
+    }
+
+    /// Applies the video settings to a [`SystemBuilder`], before the [`System`] is built.
+    pub fn apply_to_builder(&self, builder: &mut SystemBuilder) {
+        builder
+            .scale_factor(self.scale_factor())
+            .fullscreen(self.is_fullscreen());
+    }
+
+    /// Applies the video, audio and input settings to a [`System`], replacing its input map if
+    /// the config has an `[input]` section.
+    pub fn apply_to_system(&self, system: &mut System) -> Result<(), ConfigError> {
+        if self.keys(INPUT_SECTION).next().is_some() {
+            system.input_map = self.input_map()?;
+        }
+        if self.is_fullscreen() != system.is_fullscreen() {
+            system.set_fullscreen(self.is_fullscreen())?;
+        }
+        if !self.is_fullscreen() {
+            system.set_scale_factor(self.scale_factor())?;
+        }
+        let mut audio_device = system.audio.lock();
+        audio_device.volume = self.volume();
+        audio_device.music_volume = self.music_volume();
+        Ok(())
+    }
+
+    /// Updates the fullscreen, audio and input settings from the current state of a [`System`],
+    /// for example to keep fullscreen toggles and rebound controls before saving the config.
+    pub fn read_from_system(&mut self, system: &mut System) -> Result<(), ConfigError> {
+        self.set_fullscreen(system.is_fullscreen());
+        let (volume, music_volume) = {
+            let audio_device = system.audio.lock();
+            (audio_device.volume, audio_device.music_volume)
+        };
+        self.set_volume(volume);
+        self.set_music_volume(music_volume);
+        self.set_input_map(&system.input_map)
+    }
+
+    /// Loads values from an INI-style config file on disk, replacing any values already set
+    /// with the same section and key, and recording changes for those that are different. Values
+    /// not in the file are left as-is. If the file cannot be loaded, no values are changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to load values from
+    pub fn merge_from_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        self.merge_from_bytes(&mut reader)
+    }
+
+    /// Loads values from a reader. The data being loaded is expected to be the same as if the
+    /// values were being loaded from a file on disk. See [`Config::merge_from_file`].
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load values from
+    pub fn merge_from_bytes<T: Read>(&mut self, reader: &mut T) -> Result<(), ConfigError> {
+        let mut text = String::new();
+        if reader.read_to_string(&mut text).is_err() {
+            return Err(ConfigError::BadData(String::from("Not valid UTF-8 text")));
+        }
+
+        let mut values = Vec::new();
+        let mut section: Option<&str> = None;
+        for (index, line) in text.lines().enumerate() {
+            let bad_line =
+                |message: &str| ConfigError::BadData(format!("Line {}: {}", index + 1, message));
+            let line = line.trim();
+            if line.is_empty() || line.starts_with([';', '#']) {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                match name.strip_suffix(']').map(str::trim) {
+                    Some(name) if validate_name(name).is_ok() => section = Some(name),
+                    _ => return Err(bad_line("Invalid section header")),
+                }
+            } else if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim();
+                if validate_name(key).is_err() {
+                    return Err(bad_line("Invalid key"));
+                }
+                match section {
+                    Some(section) => values.push((section, key, value.trim())),
+                    None => return Err(bad_line("Value is not in any section")),
+                }
+            } else {
+                return Err(bad_line(
+                    "Expected a section header or a \"key = value\" line",
+                ));
+            }
+        }
+
+        for (section, key, value) in values {
+            self.insert_value(section, key, String::from(value));
+        }
+        Ok(())
+    }
+
+    /// Writes all values (including defaults for those that have not been set) to an INI-style
+    /// config file on disk. If the file already exists, it will be overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to write the config to
+    pub fn to_file(&self, path: &Path) -> Result<(), ConfigError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_bytes(&mut writer)
+    }
+
+    /// Writes all values to a writer, in the same format as if it was writing to a file on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the config to
+    pub fn to_bytes<T: Write>(&self, writer: &mut T) -> Result<(), ConfigError> {
+        for (index, section) in self.sections().enumerate() {
+            if index > 0 {
+                writeln!(writer)?;
+            }
+            writeln!(writer, "[{}]", section)?;
+            for key in self.keys(section) {
+                writeln!(writer, "{} = {}", key, self.get(section, key).unwrap_or(""))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+
+    use claim::*;
+    use sdl2::controller::Button;
+    use sdl2::keyboard::Scancode;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn change(section: &str, key: &str) -> ConfigChange {
+        ConfigChange {
+            section: String::from(section),
+            key: String::from(key),
+        }
+    }
+
+    #[test]
+    pub fn values_and_defaults() -> Result<(), ConfigError> {
+        let mut config = Config::new();
+        assert_eq!(DEFAULT_SCALE_FACTOR, config.scale_factor());
+        assert!(!config.is_fullscreen());
+        assert_eq!(1.0, config.volume());
+        assert!(!config.has_changes());
+
+        config.set_default("game", "difficulty", "normal")?;
+        config.set_default("game", "lives", 3)?;
+        assert_eq!(Some("normal"), config.get("game", "difficulty"));
+        assert_eq!(Some(3), config.get_as::<u32>("game", "lives"));
+        assert!(!config.is_set("game", "lives"));
+        assert_none!(config.get("game", "missing"));
+        assert_none!(config.get_as::<u32>("game", "difficulty"));
+
+        config.set("game", "lives", 5)?;
+        config.set("game", "lives", 4)?;
+        config.set("game", "difficulty", "normal")?;
+        config.set_fullscreen(true);
+        config.set("game", "cheats", "Yes")?;
+        assert_eq!(Some(4), config.get_as::<u32>("game", "lives"));
+        assert!(config.is_set("game", "lives"));
+        assert!(config.is_fullscreen());
+        assert_eq!(Some(true), config.get_bool("game", "cheats"));
+        assert_eq!(
+            vec![
+                change("game", "lives"),
+                change("video", "fullscreen"),
+                change("game", "cheats")
+            ],
+            config.take_changes()
+        );
+        assert!(!config.has_changes());
+
+        assert!(config.remove("game", "lives"));
+        assert!(!config.remove("game", "lives"));
+        assert_eq!(Some(3), config.get_as::<u32>("game", "lives"));
+        assert_eq!(vec![change("game", "lives")], config.take_changes());
+
+        assert_eq!(
+            vec!["audio", "game", "video"],
+            config.sections().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["cheats", "difficulty", "lives"],
+            config.keys("game").collect::<Vec<_>>()
+        );
+
+        assert_matches!(config.set("", "a", 1), Err(ConfigError::InvalidName(..)));
+        assert_matches!(config.set("a", "b=c", 1), Err(ConfigError::InvalidName(..)));
+        assert_matches!(
+            config.set("a", "b", "two\nlines"),
+            Err(ConfigError::InvalidValue { .. })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn input_bindings() -> Result<(), ConfigError> {
+        let mut input_map = InputMap::new();
+        input_map.bind("jump", InputBinding::Key(Scancode::Space));
+        input_map.bind("jump", InputBinding::GamepadButton(Button::A));
+        input_map.bind("fire", InputBinding::MouseButton(1));
+
+        let mut config = Config::new();
+        config.set("input", "old", "key:4")?;
+        config.set_input_map(&input_map)?;
+        assert_eq!(Some("key:44, button:a"), config.get("input", "jump"));
+        assert!(!config.is_set("input", "old"));
+
+        let loaded = config.input_map()?;
+        assert_eq!(vec!["fire", "jump"], {
+            let mut actions: Vec<&str> = loaded.actions().collect();
+            actions.sort();
+            actions
+        });
+        assert_eq!(input_map.bindings("jump"), loaded.bindings("jump"));
+
+        config.set("input", "fire", "mouse:1, laser")?;
+        assert_matches!(
+            config.input_map(),
+            Err(ConfigError::InvalidValue { key, .. }) if key == "fire"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn loading_and_saving() -> Result<(), ConfigError> {
+        let text = "; comment\n[video]\nscale = 5\nfullscreen=on\n\n# another comment\n[ game ]\nname = Some Game = Fun\n";
+        let mut config = Config::new();
+        config.set_default("game", "lives", 3)?;
+        config.merge_from_bytes(&mut Cursor::new(text))?;
+        assert_eq!(5, config.scale_factor());
+        assert!(config.is_fullscreen());
+        assert_eq!(Some("Some Game = Fun"), config.get("game", "name"));
+        assert_eq!(
+            vec![
+                change("video", "scale"),
+                change("video", "fullscreen"),
+                change("game", "name")
+            ],
+            config.take_changes()
+        );
+
+        let tmp_dir = TempDir::new()?;
+        let path = tmp_dir.path().join("config.ini");
+        config.to_file(&path)?;
+        let mut loaded = Config::new();
+        loaded.merge_from_file(&path)?;
+        for section in config.sections() {
+            for key in config.keys(section) {
+                assert_eq!(config.get(section, key), loaded.get(section, key));
+            }
+        }
+        assert_eq!(Some(3), loaded.get_as::<u32>("game", "lives"));
+
+        for bad in [
+            "scale = 2\n",
+            "[video\n",
+            "[video]\nscale\n",
+            "[video]\n = 2\n",
+        ] {
+            let mut config = Config::new();
+            assert_matches!(
+                config.merge_from_bytes(&mut Cursor::new(bad)),
+                Err(ConfigError::BadData(..))
+            );
+        }
+        let mut config = Config::new();
+        assert_err!(config.merge_from_bytes(&mut Cursor::new("[video]\nscale = 4\n[")));
+        assert_eq!(DEFAULT_SCALE_FACTOR, config.scale_factor());
+        assert!(!config.has_changes());
+
+        Ok(())
+    }
+}
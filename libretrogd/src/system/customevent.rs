@@ -0,0 +1,109 @@
+use std::any::Any;
+use std::fmt::Formatter;
+
+use sdl2::event::Event;
+
+/// An application-defined event, queued via [`System::push_event`] and delivered alongside SDL2
+/// events by [`System::do_all_events_with`]. Any type can be used as a custom event, and
+/// applications can use as many different types as they like (such as one "events" enum per
+/// module). [`CustomEvent::downcast_ref`] is used to get at the original value.
+///
+/// [`System::push_event`]: crate::system::System::push_event
+/// [`System::do_all_events_with`]: crate::system::System::do_all_events_with
+pub struct CustomEvent(Box<dyn Any>);
+
+impl std::fmt::Debug for CustomEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CustomEvent").finish_non_exhaustive()
+    }
+}
+
+impl CustomEvent {
+    pub fn new<T: Any>(event: T) -> CustomEvent {
+        CustomEvent(Box::new(event))
+    }
+
+    /// Returns true if this event holds a value of the given type.
+    #[inline]
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.is::<T>()
+    }
+
+    /// Returns a reference to this event's value if it is of the given type, or `None` if it is
+    /// of some other type.
+    #[inline]
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref::<T>()
+    }
+
+    /// Returns this event's value if it is of the given type, or the event itself if it is of some
+    /// other type.
+    pub fn downcast<T: Any>(self) -> Result<T, CustomEvent> {
+        match self.0.downcast::<T>() {
+            Ok(event) => Ok(*event),
+            Err(event) => Err(CustomEvent(event)),
+        }
+    }
+}
+
+/// An event delivered by [`System::do_all_events_with`], being either an SDL2 event or an
+/// application-defined [`CustomEvent`].
+///
+/// [`System::do_all_events_with`]: crate::system::System::do_all_events_with
+#[derive(Debug)]
+pub enum SystemEvent<'a> {
+    Sdl(&'a Event),
+    Custom(&'a CustomEvent),
+}
+
+impl<'a> SystemEvent<'a> {
+    /// Returns the value of this event if it is a [`CustomEvent`] holding a value of the given
+    /// type, or `None` otherwise.
+    #[inline]
+    pub fn custom<T: Any>(&self) -> Option<&'a T> {
+        match *self {
+            SystemEvent::Custom(event) => event.downcast_ref::<T>(),
+            SystemEvent::Sdl(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq)]
+    enum TestEvent {
+        Spawned(u32),
+        Died,
+    }
+
+    #[test]
+    pub fn downcasting() {
+        let event = CustomEvent::new(TestEvent::Spawned(42));
+        assert!(event.is::<TestEvent>());
+        assert!(!event.is::<u32>());
+        assert_eq!(
+            Some(&TestEvent::Spawned(42)),
+            event.downcast_ref::<TestEvent>()
+        );
+        assert_none!(event.downcast_ref::<String>());
+
+        let event = assert_err!(event.downcast::<String>());
+        assert_eq!(
+            TestEvent::Spawned(42),
+            assert_ok!(event.downcast::<TestEvent>())
+        );
+
+        let custom = CustomEvent::new(TestEvent::Died);
+        let event = SystemEvent::Custom(&custom);
+        assert_eq!(Some(&TestEvent::Died), event.custom::<TestEvent>());
+        assert_none!(event.custom::<u32>());
+
+        let sdl_event = Event::Quit { timestamp: 0 };
+        let event = SystemEvent::Sdl(&sdl_event);
+        assert_none!(event.custom::<TestEvent>());
+    }
+}
@@ -0,0 +1,118 @@
+use sdl2::event::Event;
+use sdl2::keyboard::{Mod, Scancode};
+use sdl2::video::DisplayMode;
+use sdl2::VideoSubsystem;
+
+use crate::math::*;
+
+/// Information about a display (monitor) connected to the system, as returned by
+/// [crate::system::System::displays].
+#[derive(Debug, Clone)]
+pub struct DisplayInfo {
+    /// The index of this display, as used by [crate::system::System::set_display].
+    pub index: i32,
+
+    /// The name of this display, as reported by the operating system.
+    pub name: String,
+
+    /// The area this display covers, in desktop coordinates.
+    pub bounds: Rect,
+
+    /// The display mode used by the desktop on this display.
+    pub desktop_mode: DisplayMode,
+
+    /// All of the display modes supported by this display, which can be used with
+    /// [crate::system::System::set_exclusive_fullscreen].
+    pub modes: Vec<DisplayMode>,
+}
+
+impl DisplayInfo {
+    pub(crate) fn query(video: &VideoSubsystem, index: i32) -> Result<DisplayInfo, String> {
+        let name = video.display_name(index)?;
+        let bounds = display_bounds(video, index)?;
+        let desktop_mode = video.desktop_display_mode(index)?;
+        let num_modes = video.num_display_modes(index)?;
+        let mut modes = Vec::with_capacity(num_modes as usize);
+        for mode_index in 0..num_modes {
+            modes.push(video.display_mode(index, mode_index)?);
+        }
+        Ok(DisplayInfo {
+            index,
+            name,
+            bounds,
+            desktop_mode,
+            modes,
+        })
+    }
+}
+
+/// Returns the area the given display covers, in desktop coordinates.
+pub(crate) fn display_bounds(video: &VideoSubsystem, index: i32) -> Result<Rect, String> {
+    let bounds = video.display_bounds(index)?;
+    Ok(Rect::new(
+        bounds.x(),
+        bounds.y(),
+        bounds.width(),
+        bounds.height(),
+    ))
+}
+
+/// Returns the position a window of the given size should be placed at to be centered within the
+/// given display bounds.
+pub(crate) fn centered_window_position(bounds: &Rect, width: u32, height: u32) -> (i32, i32) {
+    (
+        bounds.x + (bounds.width as i32 - width as i32) / 2,
+        bounds.y + (bounds.height as i32 - height as i32) / 2,
+    )
+}
+
+/// Returns true if the event is the Alt+Enter key combination, which conventionally toggles
+/// between windowed and fullscreen display. Key repeats are ignored.
+pub(crate) fn is_fullscreen_toggle_event(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::KeyDown {
+            scancode: Some(Scancode::Return),
+            keymod,
+            repeat: false,
+            ..
+        } if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD)
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn centers_windows() {
+        let bounds = Rect::new(1920, 0, 1280, 1024);
+        assert_eq!(
+            (1920 + 320, 112),
+            centered_window_position(&bounds, 640, 800)
+        );
+        assert_eq!(
+            (1920 - 10, -8),
+            centered_window_position(&bounds, 1300, 1040)
+        );
+    }
+
+    #[test]
+    pub fn detects_fullscreen_toggle() {
+        let toggles = |scancode, keymod, repeat| {
+            is_fullscreen_toggle_event(&Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: None,
+                scancode: Some(scancode),
+                keymod,
+                repeat,
+            })
+        };
+        assert!(toggles(Scancode::Return, Mod::LALTMOD, false));
+        assert!(toggles(Scancode::Return, Mod::RALTMOD | Mod::NUMMOD, false));
+        assert!(!toggles(Scancode::Return, Mod::LALTMOD, true));
+        assert!(!toggles(Scancode::Return, Mod::NOMOD, false));
+        assert!(!toggles(Scancode::Space, Mod::LALTMOD, false));
+    }
+}
@@ -0,0 +1,276 @@
+/// The default number of recent frames that [FrameStats] keeps timings for.
+pub const DEFAULT_FRAME_STATS_SAMPLES: usize = 120;
+
+/// How long (in seconds) before a frame deadline that [FrameLimiter] users should stop sleeping
+/// and start spinning instead, since sleeping is usually not precise enough to wake up exactly on
+/// time.
+pub const FRAME_LIMITER_SPIN_TIME: f32 = 0.002;
+
+/// Timings (in seconds) for a single frame.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct FrameTiming {
+    /// The total time taken by the frame, including any time spent waiting for the frame limiter
+    /// or V-sync.
+    pub frame: f32,
+
+    /// The time spent updating, that is from the start of the frame until rendering began.
+    pub update: f32,
+
+    /// The time spent rendering, up until the frame was presented.
+    pub render: f32,
+}
+
+/// Keeps timings for a number of recent frames, to calculate performance statistics from, such
+/// as for an on-screen debug overlay.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    samples: Vec<FrameTiming>,
+    capacity: usize,
+    next: usize,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        FrameStats::new(DEFAULT_FRAME_STATS_SAMPLES)
+    }
+}
+
+impl FrameStats {
+    /// Creates a new, empty, set of frame statistics.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: the number of recent frames to keep timings for. must be at least 1
+    pub fn new(capacity: usize) -> FrameStats {
+        let capacity = capacity.max(1);
+        FrameStats {
+            samples: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+        }
+    }
+
+    /// Adds the timings of a frame, replacing the oldest recorded frame if already at capacity.
+    pub fn record(&mut self, timing: FrameTiming) {
+        if self.samples.len() < self.capacity {
+            self.samples.push(timing);
+        } else {
+            self.samples[self.next] = timing;
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    /// Returns the number of frames that timings are currently recorded for.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Discards all recorded frame timings.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.next = 0;
+    }
+
+    /// Returns the timings of the most recently recorded frame, if any.
+    pub fn latest(&self) -> Option<FrameTiming> {
+        if self.samples.is_empty() {
+            None
+        } else {
+            let index = (self.next + self.capacity - 1) % self.capacity;
+            Some(self.samples[index])
+        }
+    }
+
+    /// Returns the average timings over all recorded frames.
+    pub fn average(&self) -> FrameTiming {
+        if self.samples.is_empty() {
+            return FrameTiming::default();
+        }
+        let mut total = FrameTiming::default();
+        for sample in self.samples.iter() {
+            total.frame += sample.frame;
+            total.update += sample.update;
+            total.render += sample.render;
+        }
+        let count = self.samples.len() as f32;
+        FrameTiming {
+            frame: total.frame / count,
+            update: total.update / count,
+            render: total.render / count,
+        }
+    }
+
+    /// Returns the average framerate over all recorded frames.
+    pub fn average_fps(&self) -> f32 {
+        let frame_time = self.average().frame;
+        if frame_time > 0.0 {
+            1.0 / frame_time
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the frame time (in seconds) that the given percentage of recorded frames took less
+    /// than or equal to. For example, the 99th percentile shows how long the slowest 1% of frames
+    /// took, which is useful for spotting stutters that an average would hide.
+    ///
+    /// # Arguments
+    ///
+    /// * `percentile`: the percentile, from 0.0 to 100.0
+    pub fn percentile_frame_time(&self, percentile: f32) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut frame_times: Vec<f32> = self.samples.iter().map(|sample| sample.frame).collect();
+        frame_times.sort_by(|a, b| a.total_cmp(b));
+        let index = ((percentile.clamp(0.0, 100.0) / 100.0) * (frame_times.len() - 1) as f32)
+            .round() as usize;
+        frame_times[index]
+    }
+
+    /// Returns the longest frame time (in seconds) out of all recorded frames.
+    #[inline]
+    pub fn max_frame_time(&self) -> f32 {
+        self.percentile_frame_time(100.0)
+    }
+}
+
+/// Keeps track of frame deadlines to limit how fast frames are presented to a target framerate.
+/// Frames that finish late are not "caught up" on by running the following frames faster.
+#[derive(Debug, Clone)]
+pub struct FrameLimiter {
+    target_framerate: Option<u32>,
+    next_deadline: Option<u64>,
+}
+
+impl FrameLimiter {
+    /// Creates a new frame limiter.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_framerate`: the framerate to limit to, or `None` to not limit the framerate
+    pub fn new(target_framerate: Option<u32>) -> FrameLimiter {
+        FrameLimiter {
+            target_framerate: target_framerate.filter(|&framerate| framerate > 0),
+            next_deadline: None,
+        }
+    }
+
+    #[inline]
+    pub fn target_framerate(&self) -> Option<u32> {
+        self.target_framerate
+    }
+
+    /// Changes the framerate to limit to, or `None` to not limit the framerate.
+    pub fn set_target_framerate(&mut self, target_framerate: Option<u32>) {
+        self.target_framerate = target_framerate.filter(|&framerate| framerate > 0);
+        self.next_deadline = None;
+    }
+
+    /// Called at the end of each frame with the current timer value, returning the timer value
+    /// that should be waited for before starting the next frame, or `None` if there is no need to
+    /// wait (because there is no target framerate, this is the first frame, or the frame took too
+    /// long).
+    ///
+    /// # Arguments
+    ///
+    /// * `ticks`: the current timer value
+    /// * `tick_frequency`: the number of timer ticks per second
+    pub fn next_deadline(&mut self, ticks: u64, tick_frequency: u64) -> Option<u64> {
+        let target_framerate = self.target_framerate?;
+        let frame_ticks = tick_frequency / target_framerate as u64;
+        match self.next_deadline {
+            Some(deadline) if deadline > ticks => {
+                self.next_deadline = Some(deadline + frame_ticks);
+                Some(deadline)
+            }
+            _ => {
+                self.next_deadline = Some(ticks + frame_ticks);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::math::*;
+
+    use super::*;
+
+    fn timing(frame: f32, update: f32, render: f32) -> FrameTiming {
+        FrameTiming {
+            frame,
+            update,
+            render,
+        }
+    }
+
+    #[test]
+    pub fn records_frame_stats() {
+        let mut stats = FrameStats::new(4);
+        assert!(stats.is_empty());
+        assert_eq!(None, stats.latest());
+        assert_eq!(FrameTiming::default(), stats.average());
+        assert_eq!(0.0, stats.average_fps());
+        assert_eq!(0.0, stats.percentile_frame_time(50.0));
+
+        stats.record(timing(0.01, 0.004, 0.002));
+        stats.record(timing(0.02, 0.008, 0.004));
+        assert_eq!(2, stats.len());
+        assert_eq!(Some(timing(0.02, 0.008, 0.004)), stats.latest());
+        let average = stats.average();
+        assert!(nearly_equal(0.015, average.frame, 0.0001));
+        assert!(nearly_equal(0.006, average.update, 0.0001));
+        assert!(nearly_equal(0.003, average.render, 0.0001));
+        assert!(nearly_equal(66.666, stats.average_fps(), 0.01));
+
+        // the oldest frames are replaced once at capacity
+        for _ in 0..3 {
+            stats.record(timing(0.04, 0.0, 0.0));
+        }
+        assert_eq!(4, stats.len());
+        assert!(nearly_equal(0.035, stats.average().frame, 0.0001));
+        assert_eq!(Some(timing(0.04, 0.0, 0.0)), stats.latest());
+
+        stats.clear();
+        assert!(stats.is_empty());
+    }
+
+    #[test]
+    pub fn frame_time_percentiles() {
+        let mut stats = FrameStats::new(100);
+        for i in (1..=100).rev() {
+            stats.record(timing(i as f32, 0.0, 0.0));
+        }
+        assert_eq!(1.0, stats.percentile_frame_time(0.0));
+        assert_eq!(51.0, stats.percentile_frame_time(50.0));
+        assert_eq!(99.0, stats.percentile_frame_time(99.0));
+        assert_eq!(100.0, stats.max_frame_time());
+    }
+
+    #[test]
+    pub fn limits_frames() {
+        let mut limiter = FrameLimiter::new(None);
+        assert_eq!(None, limiter.next_deadline(0, 1000));
+
+        limiter.set_target_framerate(Some(50));
+        assert_eq!(Some(50), limiter.target_framerate());
+        // no waiting on the first frame
+        assert_eq!(None, limiter.next_deadline(1000, 1000));
+        assert_eq!(Some(1020), limiter.next_deadline(1005, 1000));
+        assert_eq!(Some(1040), limiter.next_deadline(1030, 1000));
+        // a late frame is not caught up on
+        assert_eq!(None, limiter.next_deadline(1100, 1000));
+        assert_eq!(Some(1120), limiter.next_deadline(1101, 1000));
+
+        limiter.set_target_framerate(Some(0));
+        assert_eq!(None, limiter.target_framerate());
+    }
+}
@@ -0,0 +1,484 @@
+use std::any::Any;
+use std::collections::VecDeque;
+
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
+use sdl2::keyboard::{Mod, Scancode};
+use sdl2::mouse::{MouseButton, MouseState};
+
+use crate::system::*;
+
+/// The number of ticks per second reported by [`HeadlessSystem::tick_frequency`].
+pub const HEADLESS_TICK_FREQUENCY: u64 = 1_000_000;
+
+/// The framerate that [`HeadlessSystem::display`] advances the clock by when no target framerate
+/// was configured.
+pub const DEFAULT_HEADLESS_FRAMERATE: u32 = 60;
+
+/// A stand-in for [`System`] that does not use SDL2 at all, and so needs no window, display or
+/// audio device. It provides the same `video` backbuffer, palette, font and input devices, but
+/// its clock only advances when told to and its input comes only from events injected by the
+/// application. This makes it possible to run game logic and rendering deterministically in
+/// automated tests or on servers. Created via [`SystemBuilder::build_headless`].
+///
+/// ```
+/// use sdl2::keyboard::Scancode;
+///
+/// use libretrogd::system::*;
+///
+/// let mut system = SystemBuilder::new().build_headless().unwrap();
+/// system.press_key(Scancode::Space);
+/// system.do_events();
+/// assert!(system.keyboard.is_key_pressed(Scancode::Space));
+/// system.display().unwrap();
+/// assert_eq!(1, system.frame_count());
+/// ```
+#[derive(Debug)]
+pub struct HeadlessSystem {
+    ticks: u64,
+    frame_count: u64,
+    target_framerate: u32,
+    injected_events: VecDeque<Event>,
+    custom_events: VecDeque<CustomEvent>,
+    mouse_buttons: u32,
+    rendered_pixels: Box<[u32]>,
+
+    /// The primary backbuffer [`Bitmap`]. Unlike with [`System`], nothing is ever shown from it,
+    /// but it can be inspected after rendering to check the output.
+    pub video: Bitmap,
+
+    /// The [`Palette`] to be used in conjunction with the `video` backbuffer.
+    pub palette: Palette,
+
+    /// A pre-loaded [`Font`] that can be used for text rendering.
+    pub font: BitmaskFont,
+
+    /// The current keyboard state, updated from injected events by [`HeadlessSystem::do_events`].
+    pub keyboard: Keyboard,
+
+    /// The current mouse state, updated from injected events by [`HeadlessSystem::do_events`].
+    pub mouse: Mouse,
+
+    /// The current state of all gamepads "connected" via [`HeadlessSystem::connect_gamepad`].
+    pub gamepads: Gamepads,
+
+    /// Text entered via [`HeadlessSystem::type_text`] while text input mode is active.
+    pub text_input: TextInput,
+
+    /// Named actions mapped to keyboard, mouse and gamepad inputs, updated by
+    /// [`HeadlessSystem::do_events`].
+    pub input_map: InputMap,
+}
+
+impl HeadlessSystem {
+    pub(crate) fn new(
+        width: u32,
+        height: u32,
+        target_framerate: Option<u32>,
+    ) -> Result<HeadlessSystem, SystemError> {
+        let video = match Bitmap::new(width, height) {
+            Ok(bmp) => bmp,
+            Err(error) => return Err(SystemError::InitError(error.to_string())),
+        };
+        let palette = match Palette::new_vga_palette() {
+            Ok(palette) => palette,
+            Err(error) => return Err(SystemError::InitError(error.to_string())),
+        };
+        let font = match BitmaskFont::new_vga_font() {
+            Ok(font) => font,
+            Err(error) => return Err(SystemError::InitError(error.to_string())),
+        };
+
+        Ok(HeadlessSystem {
+            ticks: 0,
+            frame_count: 0,
+            target_framerate: target_framerate
+                .filter(|&framerate| framerate > 0)
+                .unwrap_or(DEFAULT_HEADLESS_FRAMERATE),
+            injected_events: VecDeque::new(),
+            custom_events: VecDeque::new(),
+            mouse_buttons: 0,
+            rendered_pixels: vec![0u32; (width * height) as usize].into_boxed_slice(),
+            video,
+            palette,
+            font,
+            keyboard: Keyboard::new(),
+            mouse: Mouse::new(),
+            gamepads: Gamepads::new(),
+            text_input: TextInput::new(),
+            input_map: InputMap::new(),
+        })
+    }
+
+    /// Finishes the current frame. Nothing is actually displayed, but the `video` backbuffer is
+    /// converted using the `palette` (with the mouse cursor bitmap drawn, if enabled) to the same
+    /// final output as with [`System::display`], and the clock is advanced by the duration of one
+    /// frame at the target framerate.
+    pub fn display(&mut self) -> Result<(), SystemError> {
+        self.mouse.render_cursor(&mut self.video);
+        self.video
+            .copy_as_argb_to(&mut self.rendered_pixels, &self.palette);
+        self.mouse.hide_cursor(&mut self.video);
+        self.frame_count += 1;
+        self.advance_ticks(HEADLESS_TICK_FREQUENCY / self.target_framerate as u64);
+        Ok(())
+    }
+
+    /// Returns the final output of the most recently displayed frame, the same as
+    /// [`System::rendered_frame_pixels`].
+    #[inline]
+    pub fn rendered_frame_pixels(&self) -> &[u32] {
+        &self.rendered_pixels
+    }
+
+    /// Copies the final output of the most recently displayed frame to the given buffer as RGBA
+    /// bytes, the same as [`System::copy_rendered_frame_rgba_to`].
+    pub fn copy_rendered_frame_rgba_to(&self, dest: &mut [u8]) {
+        copy_argb_as_rgba_to(&self.rendered_pixels, dest);
+    }
+
+    /// Returns the number of times [`HeadlessSystem::display`] has been called.
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Returns the framerate that [`HeadlessSystem::display`] advances the clock by.
+    #[inline]
+    pub fn target_framerate(&self) -> u32 {
+        self.target_framerate
+    }
+
+    /// Processes all events injected since the last call, passing each of them to the input
+    /// devices, the same as [`System::do_events`].
+    pub fn do_events(&mut self) {
+        self.do_events_with(|_event| {});
+    }
+
+    /// Same as [`System::do_events_with`], but for injected events.
+    pub fn do_events_with<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Event),
+    {
+        self.do_all_events_with(|event| {
+            if let SystemEvent::Sdl(event) = event {
+                f(event);
+            }
+        });
+    }
+
+    /// Same as [`System::do_all_events_with`], but for injected events.
+    pub fn do_all_events_with<F>(&mut self, mut f: F)
+    where
+        F: FnMut(SystemEvent),
+    {
+        self.keyboard.update();
+        self.mouse.update();
+        self.gamepads.update();
+        self.text_input.update();
+        for event in std::mem::take(&mut self.injected_events) {
+            self.keyboard.handle_event(&event);
+            self.mouse.handle_event(&event);
+            self.gamepads.handle_event(&event);
+            self.text_input.handle_event(&event);
+            f(SystemEvent::Sdl(&event));
+        }
+        self.input_map
+            .update(&self.keyboard, &self.mouse, &self.gamepads);
+        for event in std::mem::take(&mut self.custom_events) {
+            f(SystemEvent::Custom(&event));
+        }
+    }
+
+    /// Queues up an application-defined event, the same as [`System::push_event`].
+    pub fn push_event<T: Any>(&mut self, event: T) {
+        self.custom_events.push_back(CustomEvent::new(event));
+    }
+
+    /// Queues up an SDL2 event to be processed by the next call to
+    /// [`HeadlessSystem::do_events`], as if it had been received from the operating system.
+    pub fn inject_event(&mut self, event: Event) {
+        self.injected_events.push_back(event);
+    }
+
+    /// Injects a key press event for the given key.
+    pub fn press_key(&mut self, scancode: Scancode) {
+        self.inject_event(Event::KeyDown {
+            timestamp: self.millis(),
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: Mod::NOMOD,
+            repeat: false,
+        });
+    }
+
+    /// Injects a key release event for the given key.
+    pub fn release_key(&mut self, scancode: Scancode) {
+        self.inject_event(Event::KeyUp {
+            timestamp: self.millis(),
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: Mod::NOMOD,
+            repeat: false,
+        });
+    }
+
+    /// Injects text as if it had been typed while text input mode is active.
+    pub fn type_text(&mut self, text: &str) {
+        self.inject_event(Event::TextInput {
+            timestamp: self.millis(),
+            window_id: 0,
+            text: String::from(text),
+        });
+    }
+
+    /// Injects a mouse motion event, moving the mouse cursor to the given `video` backbuffer
+    /// coordinates.
+    pub fn move_mouse(&mut self, x: i32, y: i32) {
+        // base the relative motion on the most recently injected position, if any
+        let (last_x, last_y) = self
+            .injected_events
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                Event::MouseMotion { x, y, .. } => Some((*x, *y)),
+                _ => None,
+            })
+            .unwrap_or((self.mouse.x(), self.mouse.y()));
+        self.inject_event(Event::MouseMotion {
+            timestamp: self.millis(),
+            window_id: 0,
+            which: 0,
+            mousestate: MouseState::from_sdl_state(self.mouse_buttons),
+            x,
+            y,
+            xrel: x - last_x,
+            yrel: y - last_y,
+        });
+    }
+
+    /// Injects a mouse button press event at the mouse cursor's current position.
+    pub fn press_mouse_button(&mut self, button: MouseButton) {
+        self.mouse_buttons |= mouse_button_mask(button);
+        self.inject_event(Event::MouseButtonDown {
+            timestamp: self.millis(),
+            window_id: 0,
+            which: 0,
+            mouse_btn: button,
+            clicks: 1,
+            x: self.mouse.x(),
+            y: self.mouse.y(),
+        });
+    }
+
+    /// Injects a mouse button release event at the mouse cursor's current position.
+    pub fn release_mouse_button(&mut self, button: MouseButton) {
+        self.mouse_buttons &= !mouse_button_mask(button);
+        self.inject_event(Event::MouseButtonUp {
+            timestamp: self.millis(),
+            window_id: 0,
+            which: 0,
+            mouse_btn: button,
+            clicks: 1,
+            x: self.mouse.x(),
+            y: self.mouse.y(),
+        });
+    }
+
+    /// Injects an event connecting a gamepad with the given ID, which is then used to refer to it
+    /// when injecting its input.
+    pub fn connect_gamepad(&mut self, id: u32) {
+        self.inject_event(Event::ControllerDeviceAdded {
+            timestamp: self.millis(),
+            which: id,
+        });
+    }
+
+    /// Injects an event disconnecting the gamepad with the given ID.
+    pub fn disconnect_gamepad(&mut self, id: u32) {
+        self.inject_event(Event::ControllerDeviceRemoved {
+            timestamp: self.millis(),
+            which: id,
+        });
+    }
+
+    /// Injects a button press event for the gamepad with the given ID.
+    pub fn press_gamepad_button(&mut self, id: u32, button: Button) {
+        self.inject_event(Event::ControllerButtonDown {
+            timestamp: self.millis(),
+            which: id,
+            button,
+        });
+    }
+
+    /// Injects a button release event for the gamepad with the given ID.
+    pub fn release_gamepad_button(&mut self, id: u32, button: Button) {
+        self.inject_event(Event::ControllerButtonUp {
+            timestamp: self.millis(),
+            which: id,
+            button,
+        });
+    }
+
+    /// Injects an axis motion event for the gamepad with the given ID.
+    pub fn move_gamepad_axis(&mut self, id: u32, axis: Axis, value: i16) {
+        self.inject_event(Event::ControllerAxisMotion {
+            timestamp: self.millis(),
+            which: id,
+            axis,
+            value,
+        });
+    }
+
+    /// Starts text input mode, the same as [`System::start_text_input`].
+    pub fn start_text_input(&mut self) {
+        self.text_input.set_active(true);
+    }
+
+    /// Stops text input mode, the same as [`System::stop_text_input`].
+    pub fn stop_text_input(&mut self) {
+        self.text_input.set_active(false);
+    }
+
+    /// Advances the clock by the given number of ticks.
+    pub fn advance_ticks(&mut self, ticks: u64) {
+        self.ticks += ticks;
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn tick_frequency(&self) -> u64 {
+        HEADLESS_TICK_FREQUENCY
+    }
+
+    /// Returns the number of milliseconds that the clock has been advanced by.
+    pub fn millis(&self) -> u32 {
+        (self.ticks * 1000 / HEADLESS_TICK_FREQUENCY) as u32
+    }
+
+    /// Advances the clock by the number of milliseconds specified, without actually waiting.
+    pub fn delay(&mut self, millis: u32) {
+        self.advance_ticks(millis as u64 * HEADLESS_TICK_FREQUENCY / 1000);
+    }
+}
+
+#[inline]
+fn mouse_button_mask(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Unknown => 0,
+        button => 1 << (button as u32 - 1),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn headless_system() -> HeadlessSystem {
+        SystemBuilder::new()
+            .resolution(160, 100)
+            .target_framerate(50)
+            .build_headless()
+            .unwrap()
+    }
+
+    #[test]
+    pub fn clock_and_frames() {
+        let mut system = headless_system();
+        assert_eq!(160, system.video.width());
+        assert_eq!(100, system.video.height());
+        assert_eq!(0, system.ticks());
+
+        system.display().unwrap();
+        system.display().unwrap();
+        assert_eq!(2, system.frame_count());
+        assert_eq!(40, system.millis());
+
+        system.delay(10);
+        assert_eq!(50, system.millis());
+        assert_eq!(50_000, system.ticks());
+    }
+
+    #[test]
+    pub fn rendered_frame() {
+        let mut system = headless_system();
+        system.palette[1] = to_rgb32(10, 20, 30);
+        system.video.set_pixel(1, 0, 1);
+        system.display().unwrap();
+
+        let pixels = system.rendered_frame_pixels();
+        assert_eq!(160 * 100, pixels.len());
+        assert_eq!(system.palette[0], pixels[0]);
+        assert_eq!(to_rgb32(10, 20, 30), pixels[1]);
+
+        let mut rgba = [0u8; 8];
+        system.copy_rendered_frame_rgba_to(&mut rgba);
+        assert_eq!([0, 0, 0, 255, 10, 20, 30, 255], rgba);
+    }
+
+    #[test]
+    pub fn injected_input() {
+        let mut system = headless_system();
+        system.input_map.bind(
+            "fire",
+            InputBinding::MouseButton(MouseButton::Left as usize),
+        );
+
+        system.press_key(Scancode::Z);
+        system.move_mouse(10, 20);
+        system.move_mouse(15, 18);
+        system.press_mouse_button(MouseButton::Left);
+        system.connect_gamepad(2);
+        system.press_gamepad_button(2, Button::Start);
+        let mut event_count = 0;
+        system.do_events_with(|_event| event_count += 1);
+        assert_eq!(6, event_count);
+
+        assert!(system.keyboard.is_key_pressed(Scancode::Z));
+        assert_eq!(15, system.mouse.x());
+        assert_eq!(18, system.mouse.y());
+        assert_eq!(15, system.mouse.x_delta());
+        assert_eq!(18, system.mouse.raw_y_delta());
+        assert!(system.mouse.is_button_pressed(MouseButton::Left as usize));
+        assert!(system.input_map.is_action_pressed("fire"));
+        assert!(system
+            .gamepads
+            .by_id(2)
+            .unwrap()
+            .is_button_pressed(Button::Start));
+
+        // a held button stays held while the mouse moves
+        system.move_mouse(16, 18);
+        system.do_events();
+        assert!(system.keyboard.is_key_down(Scancode::Z));
+        assert!(system.mouse.is_button_down(MouseButton::Left as usize));
+        assert!(system.input_map.is_action_down("fire"));
+
+        system.release_mouse_button(MouseButton::Left);
+        system.do_events();
+        assert!(system.mouse.is_button_released(MouseButton::Left as usize));
+        assert!(system.input_map.is_action_released("fire"));
+    }
+
+    #[test]
+    pub fn custom_events_and_text() {
+        let mut system = headless_system();
+        system.type_text("ignored");
+        system.do_events();
+        system.start_text_input();
+        system.type_text("abc");
+        system.push_event(42u32);
+        let mut custom = Vec::new();
+        system.do_all_events_with(|event| {
+            if let Some(value) = event.custom::<u32>() {
+                custom.push(*value);
+            }
+        });
+        assert_eq!(vec![42], custom);
+        assert_eq!("abc", system.text_input.text());
+    }
+}
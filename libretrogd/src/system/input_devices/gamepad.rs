@@ -0,0 +1,514 @@
+use std::fmt::Formatter;
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+
+use crate::math::*;
+
+use super::*;
+
+const MAX_GAMEPAD_BUTTONS: usize = 32;
+const MAX_GAMEPAD_AXES: usize = 6;
+
+/// The default deadzone applied to gamepad analog sticks and triggers. See
+/// [`Gamepad::set_deadzone`].
+pub const DEFAULT_GAMEPAD_DEADZONE: f32 = 0.2;
+
+/// Holds the current state of a single gamepad (game controller). Buttons, sticks, triggers and
+/// the d-pad are all mapped to the layout of a typical console controller.
+pub struct Gamepad {
+    id: u32,
+    name: String,
+    // SDL closes the controller when this is dropped, so it must be kept for as long as the
+    // gamepad is connected
+    _controller: Option<GameController>,
+    buttons: [ButtonState; MAX_GAMEPAD_BUTTONS],
+    axes: [i16; MAX_GAMEPAD_AXES],
+    deadzone: f32,
+}
+
+impl std::fmt::Debug for Gamepad {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Gamepad")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("buttons", &self.buttons)
+            .field("axes", &self.axes)
+            .field("deadzone", &self.deadzone)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Gamepad {
+    fn new(id: u32, name: String, controller: Option<GameController>, deadzone: f32) -> Gamepad {
+        Gamepad {
+            id,
+            name,
+            _controller: controller,
+            buttons: [ButtonState::Idle; MAX_GAMEPAD_BUTTONS],
+            axes: [0; MAX_GAMEPAD_AXES],
+            deadzone,
+        }
+    }
+
+    /// Returns the ID of this gamepad, which stays the same for as long as it is connected.
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the name of this gamepad, as reported by the operating system.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the deadzone currently applied to this gamepad's analog sticks and triggers.
+    #[inline]
+    pub fn deadzone(&self) -> f32 {
+        self.deadzone
+    }
+
+    /// Sets the deadzone (from 0.0 to 1.0) applied to this gamepad's analog sticks and triggers.
+    /// Analog values smaller than this are treated as zero, to ignore slight drift of sticks that
+    /// are not being touched. Values outside of the deadzone are rescaled so that they still cover
+    /// the full range.
+    pub fn set_deadzone(&mut self, deadzone: f32) {
+        self.deadzone = deadzone.clamp(0.0, 0.99);
+    }
+
+    /// Returns true if the given button was just pressed or is being held down.
+    #[inline]
+    pub fn is_button_down(&self, button: Button) -> bool {
+        matches!(
+            self.buttons[button as usize],
+            ButtonState::Pressed | ButtonState::Held
+        )
+    }
+
+    /// Returns true if the given button was not just pressed and is not being held down.
+    #[inline]
+    pub fn is_button_up(&self, button: Button) -> bool {
+        matches!(
+            self.buttons[button as usize],
+            ButtonState::Released | ButtonState::Idle
+        )
+    }
+
+    /// Returns true if the given button was just pressed (not being held down, yet).
+    #[inline]
+    pub fn is_button_pressed(&self, button: Button) -> bool {
+        self.buttons[button as usize] == ButtonState::Pressed
+    }
+
+    /// Returns true if the given button was just released.
+    #[inline]
+    pub fn is_button_released(&self, button: Button) -> bool {
+        self.buttons[button as usize] == ButtonState::Released
+    }
+
+    /// Returns the raw value of the given axis, without any deadzone applied.
+    #[inline]
+    pub fn axis_raw(&self, axis: Axis) -> i16 {
+        self.axes[axis as usize]
+    }
+
+    /// Returns the value of the given axis with the deadzone applied. Sticks range from -1.0 to
+    /// 1.0 (where negative values are left/up), while triggers range from 0.0 to 1.0.
+    pub fn axis(&self, axis: Axis) -> f32 {
+        apply_deadzone(normalize_axis(self.axis_raw(axis)), self.deadzone)
+    }
+
+    /// Returns the position of the left analog stick with the deadzone applied, where each
+    /// component ranges from -1.0 to 1.0.
+    #[inline]
+    pub fn left_stick(&self) -> Vector2 {
+        self.stick(Axis::LeftX, Axis::LeftY)
+    }
+
+    /// Returns the position of the right analog stick with the deadzone applied, where each
+    /// component ranges from -1.0 to 1.0.
+    #[inline]
+    pub fn right_stick(&self) -> Vector2 {
+        self.stick(Axis::RightX, Axis::RightY)
+    }
+
+    /// Returns the direction the d-pad is being pressed in, where each component is -1, 0 or 1.
+    pub fn dpad(&self) -> Vector2i {
+        let mut direction = Vector2i::ZERO;
+        if self.is_button_down(Button::DPadLeft) {
+            direction.x -= 1;
+        }
+        if self.is_button_down(Button::DPadRight) {
+            direction.x += 1;
+        }
+        if self.is_button_down(Button::DPadUp) {
+            direction.y -= 1;
+        }
+        if self.is_button_down(Button::DPadDown) {
+            direction.y += 1;
+        }
+        direction
+    }
+
+    fn stick(&self, x_axis: Axis, y_axis: Axis) -> Vector2 {
+        // the deadzone is applied to the stick's distance from the center, rather than each axis
+        // separately, so that diagonal movement is not snapped towards the axes
+        let position = Vector2::new(
+            normalize_axis(self.axis_raw(x_axis)),
+            normalize_axis(self.axis_raw(y_axis)),
+        );
+        let length = position.length();
+        if length <= self.deadzone {
+            Vector2::ZERO
+        } else {
+            let scaled_length = apply_deadzone(length.min(1.0), self.deadzone);
+            position * (scaled_length / length)
+        }
+    }
+
+    fn update(&mut self) {
+        for state in self.buttons.iter_mut() {
+            *state = match *state {
+                ButtonState::Pressed => ButtonState::Held,
+                ButtonState::Released => ButtonState::Idle,
+                otherwise => otherwise,
+            };
+        }
+    }
+
+    fn set_button(&mut self, button: Button, is_pressed: bool) {
+        let state = &mut self.buttons[button as usize];
+        *state = if is_pressed {
+            match *state {
+                ButtonState::Pressed => ButtonState::Held,
+                ButtonState::Held => ButtonState::Held,
+                _ => ButtonState::Pressed,
+            }
+        } else {
+            ButtonState::Released
+        };
+    }
+}
+
+#[inline]
+fn normalize_axis(value: i16) -> f32 {
+    (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0)
+}
+
+#[inline]
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * ((value.abs() - deadzone) / (1.0 - deadzone))
+    }
+}
+
+/// Holds the current state of all connected gamepads (game controllers). Gamepads can be
+/// connected and disconnected at any time while the application is running.
+///
+/// Must be explicitly updated each frame by calling `handle_event` each frame for all SDL2 events
+/// received, as well as calling `do_events` once each frame. Usually, you would accomplish all
+/// this house-keeping by simply calling [`System`]'s `do_events` method once per frame.
+///
+/// [`System`]: crate::System
+pub struct Gamepads {
+    subsystem: Option<GameControllerSubsystem>,
+    gamepads: Vec<Gamepad>,
+    deadzone: f32,
+    connected: Vec<u32>,
+    disconnected: Vec<u32>,
+}
+
+impl std::fmt::Debug for Gamepads {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Gamepads")
+            .field("gamepads", &self.gamepads)
+            .field("deadzone", &self.deadzone)
+            .field("connected", &self.connected)
+            .field("disconnected", &self.disconnected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Gamepads::new()
+    }
+}
+
+impl Gamepads {
+    /// Creates a new gamepad state holder which is not attached to SDL's game controller
+    /// subsystem. Gamepads connected while using this are tracked from their events alone, which
+    /// is mainly useful for feeding in simulated input.
+    pub fn new() -> Gamepads {
+        Gamepads {
+            subsystem: None,
+            gamepads: Vec::new(),
+            deadzone: DEFAULT_GAMEPAD_DEADZONE,
+            connected: Vec::new(),
+            disconnected: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_subsystem(subsystem: GameControllerSubsystem) -> Gamepads {
+        Gamepads {
+            subsystem: Some(subsystem),
+            ..Gamepads::new()
+        }
+    }
+
+    /// Returns the number of currently connected gamepads.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.gamepads.is_empty()
+    }
+
+    /// Returns the connected gamepad at the given index, in the order they were connected. Since
+    /// indices shift as gamepads are disconnected, use [`Gamepads::by_id`] to refer to a
+    /// specific gamepad over time.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&Gamepad> {
+        self.gamepads.get(index)
+    }
+
+    /// Returns the connected gamepad with the given ID, if it is still connected.
+    pub fn by_id(&self, id: u32) -> Option<&Gamepad> {
+        self.gamepads.iter().find(|gamepad| gamepad.id == id)
+    }
+
+    /// Returns the connected gamepad with the given ID, if it is still connected.
+    pub fn by_id_mut(&mut self, id: u32) -> Option<&mut Gamepad> {
+        self.gamepads.iter_mut().find(|gamepad| gamepad.id == id)
+    }
+
+    /// Returns an iterator over all connected gamepads.
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, Gamepad> {
+        self.gamepads.iter()
+    }
+
+    /// Returns the IDs of gamepads that were connected since the last update.
+    #[inline]
+    pub fn just_connected(&self) -> &[u32] {
+        &self.connected
+    }
+
+    /// Returns the IDs of gamepads that were disconnected since the last update.
+    #[inline]
+    pub fn just_disconnected(&self) -> &[u32] {
+        &self.disconnected
+    }
+
+    #[inline]
+    pub fn deadzone(&self) -> f32 {
+        self.deadzone
+    }
+
+    /// Sets the deadzone used for all currently connected gamepads as well as any connected
+    /// later. See [`Gamepad::set_deadzone`].
+    pub fn set_deadzone(&mut self, deadzone: f32) {
+        self.deadzone = deadzone.clamp(0.0, 0.99);
+        for gamepad in self.gamepads.iter_mut() {
+            gamepad.set_deadzone(self.deadzone);
+        }
+    }
+
+    fn add(&mut self, joystick_index: u32) {
+        let gamepad = match &self.subsystem {
+            Some(subsystem) => {
+                if !subsystem.is_game_controller(joystick_index) {
+                    return;
+                }
+                match subsystem.open(joystick_index) {
+                    Ok(controller) => Gamepad::new(
+                        controller.instance_id(),
+                        controller.name(),
+                        Some(controller),
+                        self.deadzone,
+                    ),
+                    Err(_) => return,
+                }
+            }
+            None => Gamepad::new(joystick_index, String::new(), None, self.deadzone),
+        };
+        // already connected gamepads can be reported again after a device reset
+        if self.by_id(gamepad.id).is_none() {
+            self.connected.push(gamepad.id);
+            self.gamepads.push(gamepad);
+        }
+    }
+
+    fn remove(&mut self, id: u32) {
+        if let Some(index) = self.gamepads.iter().position(|gamepad| gamepad.id == id) {
+            self.gamepads.remove(index);
+            self.disconnected.push(id);
+        }
+    }
+}
+
+impl InputDevice for Gamepads {
+    fn update(&mut self) {
+        self.connected.clear();
+        self.disconnected.clear();
+        for gamepad in self.gamepads.iter_mut() {
+            gamepad.update();
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => self.add(*which),
+            Event::ControllerDeviceRemoved { which, .. } => self.remove(*which),
+            Event::ControllerButtonDown { which, button, .. } => {
+                if let Some(gamepad) = self.by_id_mut(*which) {
+                    gamepad.set_button(*button, true);
+                }
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                if let Some(gamepad) = self.by_id_mut(*which) {
+                    gamepad.set_button(*button, false);
+                }
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                if let Some(gamepad) = self.by_id_mut(*which) {
+                    gamepad.axes[*axis as usize] = *value;
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn button_event(which: u32, button: Button, is_pressed: bool) -> Event {
+        if is_pressed {
+            Event::ControllerButtonDown {
+                timestamp: 0,
+                which,
+                button,
+            }
+        } else {
+            Event::ControllerButtonUp {
+                timestamp: 0,
+                which,
+                button,
+            }
+        }
+    }
+
+    fn axis_event(which: u32, axis: Axis, value: i16) -> Event {
+        Event::ControllerAxisMotion {
+            timestamp: 0,
+            which,
+            axis,
+            value,
+        }
+    }
+
+    #[test]
+    pub fn connects_and_disconnects() {
+        let mut gamepads = Gamepads::new();
+        assert!(gamepads.is_empty());
+
+        gamepads.handle_event(&Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which: 0,
+        });
+        gamepads.handle_event(&Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which: 1,
+        });
+        gamepads.handle_event(&Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which: 1,
+        });
+        assert_eq!(2, gamepads.len());
+        assert_eq!(&[0, 1], gamepads.just_connected());
+        assert_eq!(1, gamepads.get(1).unwrap().id());
+
+        gamepads.update();
+        assert!(gamepads.just_connected().is_empty());
+
+        gamepads.handle_event(&Event::ControllerDeviceRemoved {
+            timestamp: 0,
+            which: 0,
+        });
+        assert_eq!(1, gamepads.len());
+        assert_eq!(&[0], gamepads.just_disconnected());
+        assert!(gamepads.by_id(0).is_none());
+        assert_eq!(1, gamepads.get(0).unwrap().id());
+    }
+
+    #[test]
+    pub fn buttons() {
+        let mut gamepads = Gamepads::new();
+        gamepads.handle_event(&Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which: 3,
+        });
+
+        gamepads.handle_event(&button_event(3, Button::A, true));
+        gamepads.handle_event(&button_event(3, Button::DPadLeft, true));
+        gamepads.handle_event(&button_event(3, Button::DPadDown, true));
+        // events for other gamepads are ignored
+        gamepads.handle_event(&button_event(4, Button::B, true));
+        let gamepad = gamepads.by_id(3).unwrap();
+        assert!(gamepad.is_button_pressed(Button::A));
+        assert!(gamepad.is_button_down(Button::A));
+        assert!(gamepad.is_button_up(Button::B));
+        assert_eq!(Vector2i::new(-1, 1), gamepad.dpad());
+
+        gamepads.update();
+        let gamepad = gamepads.by_id(3).unwrap();
+        assert!(!gamepad.is_button_pressed(Button::A));
+        assert!(gamepad.is_button_down(Button::A));
+
+        gamepads.handle_event(&button_event(3, Button::A, false));
+        assert!(gamepads.by_id(3).unwrap().is_button_released(Button::A));
+        gamepads.update();
+        let gamepad = gamepads.by_id(3).unwrap();
+        assert!(!gamepad.is_button_released(Button::A));
+        assert!(gamepad.is_button_up(Button::A));
+    }
+
+    #[test]
+    pub fn axes_and_deadzone() {
+        let mut gamepads = Gamepads::new();
+        gamepads.set_deadzone(0.25);
+        gamepads.handle_event(&Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which: 0,
+        });
+
+        gamepads.handle_event(&axis_event(0, Axis::LeftX, 8000));
+        gamepads.handle_event(&axis_event(0, Axis::TriggerRight, i16::MAX));
+        let gamepad = gamepads.get(0).unwrap();
+        assert_eq!(0.25, gamepad.deadzone());
+        assert_eq!(8000, gamepad.axis_raw(Axis::LeftX));
+        assert_eq!(0.0, gamepad.axis(Axis::LeftX));
+        assert_eq!(Vector2::ZERO, gamepad.left_stick());
+        assert_eq!(1.0, gamepad.axis(Axis::TriggerRight));
+
+        gamepads.handle_event(&axis_event(0, Axis::LeftX, -i16::MAX));
+        gamepads.handle_event(&axis_event(0, Axis::RightY, 16384));
+        let gamepad = gamepads.get(0).unwrap();
+        assert!(nearly_equal(-1.0, gamepad.axis(Axis::LeftX), 0.0001));
+        let stick = gamepad.left_stick();
+        assert!(stick.nearly_equal(Vector2::new(-1.0, 0.0), 0.0001));
+        let stick = gamepad.right_stick();
+        assert!(stick.nearly_equal(Vector2::new(0.0, 0.3334), 0.001));
+    }
+}
@@ -1,7 +1,9 @@
 use sdl2::event::Event;
 
+pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod textinput;
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum ButtonState {
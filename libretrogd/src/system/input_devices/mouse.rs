@@ -45,6 +45,8 @@ pub struct Mouse {
     y: i32,
     x_delta: i32,
     y_delta: i32,
+    raw_x_delta: i32,
+    raw_y_delta: i32,
     buttons: [ButtonState; MAX_BUTTONS],
     cursor: Bitmap,
     cursor_background: Bitmap,
@@ -63,6 +65,8 @@ impl Mouse {
             y: 0,
             x_delta: 0,
             y_delta: 0,
+            raw_x_delta: 0,
+            raw_y_delta: 0,
             buttons: [ButtonState::Idle; MAX_BUTTONS],
             cursor,
             cursor_background,
@@ -98,6 +102,26 @@ impl Mouse {
         self.y_delta
     }
 
+    /// Returns the amount the mouse moved along the x-axis since the last time that the mouse
+    /// state was updated, as reported by the operating system. Unlike [`Mouse::x_delta`], this is
+    /// not limited by the cursor reaching the edge of the window, making it better suited to
+    /// things like FPS-style mouse look, especially when used together with relative mouse mode.
+    /// Whether this is scaled to backbuffer pixels depends on the
+    /// [`SystemBuilder::relative_mouse_scaling`] option.
+    ///
+    /// [`SystemBuilder::relative_mouse_scaling`]: crate::system::SystemBuilder::relative_mouse_scaling
+    #[inline]
+    pub fn raw_x_delta(&self) -> i32 {
+        self.raw_x_delta
+    }
+
+    /// Returns the amount the mouse moved along the y-axis since the last time that the mouse
+    /// state was updated, as reported by the operating system. See [`Mouse::raw_x_delta`].
+    #[inline]
+    pub fn raw_y_delta(&self) -> i32 {
+        self.raw_y_delta
+    }
+
     /// Returns true if the given button was just pressed or is being held down.
     #[inline]
     pub fn is_button_down(&self, button: usize) -> bool {
@@ -281,6 +305,8 @@ impl InputDevice for Mouse {
     fn update(&mut self) {
         self.x_delta = 0;
         self.y_delta = 0;
+        self.raw_x_delta = 0;
+        self.raw_y_delta = 0;
         for state in self.buttons.iter_mut() {
             *state = match *state {
                 ButtonState::Pressed => ButtonState::Held,
@@ -300,10 +326,13 @@ impl InputDevice for Mouse {
                 yrel,
                 ..
             } => {
+                // several motion events may be received in a single frame
+                self.x_delta += *x - self.x;
+                self.y_delta += *y - self.y;
                 self.x = *x;
                 self.y = *y;
-                self.x_delta = *xrel;
-                self.y_delta = *yrel;
+                self.raw_x_delta += *xrel;
+                self.raw_y_delta += *yrel;
                 for (button, is_pressed) in mousestate.mouse_buttons() {
                     self.update_button_state(button as u32, is_pressed);
                 }
@@ -0,0 +1,249 @@
+use sdl2::event::Event;
+use sdl2::keyboard::Scancode;
+
+use super::*;
+
+/// Collects text typed by the user while text input mode is active, for things like high score
+/// name entry or a developer console. Unlike reading [`Keyboard`] scancodes, this uses the text
+/// the operating system produces for key presses, so shift, caps lock, dead keys, non-US
+/// keyboard layouts and IME (input method editor) composition all work as the user expects.
+///
+/// Text input mode is started and stopped via [`System`]'s `start_text_input` and
+/// `stop_text_input` methods. While active, typed characters are appended to the text, backspace
+/// removes the last character and enter submits the text (see [`TextInput::is_submitted`]). Key
+/// presses are still reported by [`Keyboard`] as normal.
+///
+/// Must be explicitly updated each frame by calling `handle_event` each frame for all SDL2 events
+/// received, as well as calling `do_events` once each frame. Usually, you would accomplish all
+/// this house-keeping by simply calling [`System`]'s `do_events` method once per frame.
+///
+/// [`Keyboard`]: crate::system::Keyboard
+/// [`System`]: crate::System
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    active: bool,
+    text: String,
+    typed: String,
+    composition: String,
+    composition_cursor: usize,
+    max_length: Option<usize>,
+    submitted: bool,
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput::default()
+    }
+
+    /// Returns true if text input mode is currently active.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub(crate) fn set_active(&mut self, active: bool) {
+        self.active = active;
+        self.composition.clear();
+        self.composition_cursor = 0;
+    }
+
+    /// Returns the text entered so far.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text entered so far, such as to pre-fill it with a default value. The text is
+    /// truncated to the maximum length, if one is set.
+    pub fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        self.append(text);
+    }
+
+    /// Clears the text entered so far.
+    pub fn clear(&mut self) {
+        self.text.clear();
+    }
+
+    /// Returns the text that was typed during this frame only.
+    #[inline]
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    /// Returns the text currently being composed with an IME, which has not yet been added to the
+    /// entered text. Applications should usually display this after the entered text. Empty if
+    /// no IME is in use.
+    #[inline]
+    pub fn composition(&self) -> &str {
+        &self.composition
+    }
+
+    /// Returns the position of the cursor within the text currently being composed with an IME,
+    /// in characters.
+    #[inline]
+    pub fn composition_cursor(&self) -> usize {
+        self.composition_cursor
+    }
+
+    /// Returns the maximum number of characters that can be entered, or `None` if unlimited.
+    #[inline]
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Sets the maximum number of characters that can be entered, or `None` for no limit. Any
+    /// text already entered beyond this length is truncated.
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+        if let Some(max_length) = max_length {
+            if let Some((index, _)) = self.text.char_indices().nth(max_length) {
+                self.text.truncate(index);
+            }
+        }
+    }
+
+    /// Returns true if enter was pressed during this frame, to finish entering the text.
+    #[inline]
+    pub fn is_submitted(&self) -> bool {
+        self.submitted
+    }
+
+    fn append(&mut self, text: &str) {
+        let mut length = self.text.chars().count();
+        for c in text.chars() {
+            if c.is_control() || self.max_length.is_some_and(|max| length >= max) {
+                continue;
+            }
+            self.text.push(c);
+            length += 1;
+        }
+    }
+}
+
+impl InputDevice for TextInput {
+    fn update(&mut self) {
+        self.typed.clear();
+        self.submitted = false;
+    }
+
+    fn handle_event(&mut self, event: &Event) {
+        if !self.active {
+            return;
+        }
+        match event {
+            Event::TextInput { text, .. } => {
+                let previous_length = self.text.len();
+                self.append(text);
+                self.typed.push_str(&self.text[previous_length..]);
+                self.composition.clear();
+                self.composition_cursor = 0;
+            }
+            Event::TextEditing { text, start, .. } => {
+                self.composition.clear();
+                self.composition.push_str(text);
+                self.composition_cursor = (*start).max(0) as usize;
+            }
+            // while an IME is composing text, it handles these keys itself
+            Event::KeyDown {
+                scancode: Some(scancode),
+                ..
+            } if self.composition.is_empty() => match scancode {
+                Scancode::Backspace => {
+                    self.text.pop();
+                }
+                Scancode::Return | Scancode::KpEnter => self.submitted = true,
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use sdl2::keyboard::Mod;
+
+    use super::*;
+
+    fn text_event(text: &str) -> Event {
+        Event::TextInput {
+            timestamp: 0,
+            window_id: 0,
+            text: String::from(text),
+        }
+    }
+
+    fn key_event(scancode: Scancode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: Mod::NOMOD,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    pub fn entering_text() {
+        let mut input = TextInput::new();
+        input.handle_event(&text_event("ignored"));
+        assert_eq!("", input.text());
+
+        input.set_active(true);
+        input.handle_event(&text_event("Hé"));
+        input.handle_event(&text_event("y!"));
+        assert_eq!("Héy!", input.text());
+        assert_eq!("Héy!", input.typed());
+
+        input.update();
+        assert_eq!("", input.typed());
+        input.handle_event(&key_event(Scancode::Backspace));
+        input.handle_event(&key_event(Scancode::Backspace));
+        assert_eq!("Hé", input.text());
+        assert!(!input.is_submitted());
+
+        input.handle_event(&key_event(Scancode::Return));
+        assert!(input.is_submitted());
+        input.update();
+        assert!(!input.is_submitted());
+    }
+
+    #[test]
+    pub fn max_length() {
+        let mut input = TextInput::new();
+        input.set_active(true);
+        input.set_text("ABCDEF");
+        input.set_max_length(Some(4));
+        assert_eq!("ABCD", input.text());
+        input.handle_event(&text_event("G"));
+        assert_eq!("ABCD", input.text());
+        input.clear();
+        input.handle_event(&text_event("日本語です"));
+        assert_eq!("日本語で", input.text());
+    }
+
+    #[test]
+    pub fn ime_composition() {
+        let mut input = TextInput::new();
+        input.set_active(true);
+        input.handle_event(&Event::TextEditing {
+            timestamp: 0,
+            window_id: 0,
+            text: String::from("にほ"),
+            start: 2,
+            length: 0,
+        });
+        assert_eq!("にほ", input.composition());
+        assert_eq!(2, input.composition_cursor());
+
+        // keys are left to the IME while composing
+        input.handle_event(&key_event(Scancode::Return));
+        assert!(!input.is_submitted());
+
+        input.handle_event(&text_event("日本"));
+        assert_eq!("日本", input.text());
+        assert_eq!("", input.composition());
+    }
+}
@@ -0,0 +1,718 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::Scancode;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use crate::system::*;
+
+#[derive(Error, Debug)]
+pub enum InputMapError {
+    #[error("Bad or unsupported input map data: {0}")]
+    BadData(String),
+
+    #[error("Input map I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+const GAMEPAD_BUTTON_NAMES: [(Button, &str); 15] = [
+    (Button::A, "a"),
+    (Button::B, "b"),
+    (Button::X, "x"),
+    (Button::Y, "y"),
+    (Button::Back, "back"),
+    (Button::Guide, "guide"),
+    (Button::Start, "start"),
+    (Button::LeftStick, "leftstick"),
+    (Button::RightStick, "rightstick"),
+    (Button::LeftShoulder, "leftshoulder"),
+    (Button::RightShoulder, "rightshoulder"),
+    (Button::DPadUp, "dpup"),
+    (Button::DPadDown, "dpdown"),
+    (Button::DPadLeft, "dpleft"),
+    (Button::DPadRight, "dpright"),
+];
+
+const GAMEPAD_AXIS_NAMES: [(Axis, &str); 6] = [
+    (Axis::LeftX, "leftx"),
+    (Axis::LeftY, "lefty"),
+    (Axis::RightX, "rightx"),
+    (Axis::RightY, "righty"),
+    (Axis::TriggerLeft, "lefttrigger"),
+    (Axis::TriggerRight, "righttrigger"),
+];
+
+/// A single physical input that can trigger an action in an [`InputMap`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InputBinding {
+    /// A keyboard key.
+    Key(Scancode),
+
+    /// A mouse button, numbered the same as for [`Mouse::is_button_down`].
+    MouseButton(usize),
+
+    /// A button on any connected gamepad.
+    GamepadButton(Button),
+
+    /// An axis on any connected gamepad, which counts as being held down while the axis value
+    /// (with the gamepad's deadzone applied) is past the threshold. A positive threshold is
+    /// passed when the axis value is greater than or equal to it, while a negative threshold is
+    /// passed when the axis value is less than or equal to it. For example, `LeftY` with a
+    /// threshold of `-0.5` is triggered by pushing the left stick at least halfway up.
+    GamepadAxis { axis: Axis, threshold: f32 },
+}
+
+impl InputBinding {
+    fn is_down(&self, keyboard: &Keyboard, mouse: &Mouse, gamepads: &Gamepads) -> bool {
+        match *self {
+            InputBinding::Key(scancode) => keyboard.is_key_down(scancode),
+            InputBinding::MouseButton(button) => mouse.is_button_down(button),
+            InputBinding::GamepadButton(button) => gamepads
+                .iter()
+                .any(|gamepad| gamepad.is_button_down(button)),
+            InputBinding::GamepadAxis { axis, threshold } => gamepads.iter().any(|gamepad| {
+                let value = gamepad.axis(axis);
+                if threshold < 0.0 {
+                    value <= threshold
+                } else {
+                    value >= threshold
+                }
+            }),
+        }
+    }
+
+    fn to_json(self) -> Value {
+        match self {
+            InputBinding::Key(scancode) => json!({ "key": scancode as i32 }),
+            InputBinding::MouseButton(button) => json!({ "mouse_button": button }),
+            InputBinding::GamepadButton(button) => {
+                let name = GAMEPAD_BUTTON_NAMES
+                    .iter()
+                    .find(|(b, _)| *b == button)
+                    .map(|(_, name)| *name);
+                json!({ "gamepad_button": name })
+            }
+            InputBinding::GamepadAxis { axis, threshold } => {
+                let name = GAMEPAD_AXIS_NAMES
+                    .iter()
+                    .find(|(a, _)| *a == axis)
+                    .map(|(_, name)| *name);
+                json!({ "gamepad_axis": name, "threshold": threshold })
+            }
+        }
+    }
+
+    fn from_json(value: &Value) -> Result<InputBinding, InputMapError> {
+        if let Some(key) = value.get("key") {
+            match key.as_i64().and_then(|n| Scancode::from_i32(n as i32)) {
+                Some(scancode) => Ok(InputBinding::Key(scancode)),
+                None => Err(InputMapError::BadData(format!("Invalid key: {}", key))),
+            }
+        } else if let Some(button) = value.get("mouse_button") {
+            match button.as_u64() {
+                Some(button) => Ok(InputBinding::MouseButton(button as usize)),
+                None => Err(InputMapError::BadData(format!(
+                    "Invalid mouse button: {}",
+                    button
+                ))),
+            }
+        } else if let Some(button) = value.get("gamepad_button") {
+            match GAMEPAD_BUTTON_NAMES
+                .iter()
+                .find(|(_, name)| Some(*name) == button.as_str())
+            {
+                Some((button, _)) => Ok(InputBinding::GamepadButton(*button)),
+                None => Err(InputMapError::BadData(format!(
+                    "Invalid gamepad button: {}",
+                    button
+                ))),
+            }
+        } else if let Some(axis) = value.get("gamepad_axis") {
+            let axis = match GAMEPAD_AXIS_NAMES
+                .iter()
+                .find(|(_, name)| Some(*name) == axis.as_str())
+            {
+                Some((axis, _)) => *axis,
+                None => {
+                    return Err(InputMapError::BadData(format!(
+                        "Invalid gamepad axis: {}",
+                        axis
+                    )))
+                }
+            };
+            match value.get("threshold").and_then(Value::as_f64) {
+                Some(threshold) => Ok(InputBinding::GamepadAxis {
+                    axis,
+                    threshold: threshold as f32,
+                }),
+                None => Err(InputMapError::BadData(String::from(
+                    "Gamepad axis binding is missing \"threshold\"",
+                ))),
+            }
+        } else {
+            Err(InputMapError::BadData(format!(
+                "Unrecognized input binding: {}",
+                value
+            )))
+        }
+    }
+}
+
+/// Input bindings are displayed in a short text form, such as "key:44" (keys are given by their
+/// scancode number), "mouse:1", "button:a" or "axis:lefty:-0.5", which can be parsed back into
+/// an input binding with [`str::parse`].
+impl std::fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            InputBinding::Key(scancode) => write!(f, "key:{}", scancode as i32),
+            InputBinding::MouseButton(button) => write!(f, "mouse:{}", button),
+            InputBinding::GamepadButton(button) => {
+                match GAMEPAD_BUTTON_NAMES.iter().find(|(b, _)| *b == button) {
+                    Some((_, name)) => write!(f, "button:{}", name),
+                    None => write!(f, "button:?"),
+                }
+            }
+            InputBinding::GamepadAxis { axis, threshold } => {
+                match GAMEPAD_AXIS_NAMES.iter().find(|(a, _)| *a == axis) {
+                    Some((_, name)) => write!(f, "axis:{}:{}", name, threshold),
+                    None => write!(f, "axis:?:{}", threshold),
+                }
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for InputBinding {
+    type Err = InputMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InputMapError::BadData(format!("Invalid input binding: {}", s));
+        let mut parts = s.trim().split(':');
+        let binding = match (parts.next(), parts.next()) {
+            (Some("key"), Some(key)) => key
+                .parse::<i32>()
+                .ok()
+                .and_then(Scancode::from_i32)
+                .map(InputBinding::Key),
+            (Some("mouse"), Some(button)) => button.parse().ok().map(InputBinding::MouseButton),
+            (Some("button"), Some(button)) => GAMEPAD_BUTTON_NAMES
+                .iter()
+                .find(|(_, name)| *name == button)
+                .map(|(button, _)| InputBinding::GamepadButton(*button)),
+            (Some("axis"), Some(axis)) => {
+                let axis = GAMEPAD_AXIS_NAMES.iter().find(|(_, name)| *name == axis);
+                let threshold = parts.next().and_then(|threshold| threshold.parse().ok());
+                match (axis, threshold) {
+                    (Some((axis, _)), Some(threshold)) => Some(InputBinding::GamepadAxis {
+                        axis: *axis,
+                        threshold,
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match (binding, parts.next()) {
+            (Some(binding), None) => Ok(binding),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Action {
+    bindings: Vec<InputBinding>,
+    state: ButtonState,
+}
+
+/// Maps named actions (such as "jump" or "fire") to any number of keys, mouse buttons and
+/// gamepad buttons or axes, so that game logic can check for actions rather than specific inputs.
+/// An action is considered held down while any of its bindings are. Input maps can be saved and
+/// loaded as JSON, making it simple to offer rebindable controls.
+///
+/// Must be explicitly updated once each frame, after all input devices have processed that
+/// frame's events, by calling [`InputMap::update`]. The [`System`]'s own `input_map` is updated
+/// automatically by its `do_events` method.
+///
+/// [`System`]: crate::System
+#[derive(Debug, Clone, Default)]
+pub struct InputMap {
+    actions: HashMap<String, Action>,
+}
+
+impl InputMap {
+    /// Creates a new input map with no actions.
+    pub fn new() -> InputMap {
+        InputMap {
+            actions: HashMap::new(),
+        }
+    }
+
+    /// Adds a binding to an action, creating the action if it does not yet exist. Does nothing if
+    /// the action already has this exact binding.
+    ///
+    /// # Arguments
+    ///
+    /// * `action`: the name of the action
+    /// * `binding`: the input to bind to the action
+    pub fn bind(&mut self, action: &str, binding: InputBinding) {
+        let action = self
+            .actions
+            .entry(String::from(action))
+            .or_insert_with(|| Action {
+                bindings: Vec::new(),
+                state: ButtonState::Idle,
+            });
+        if !action.bindings.contains(&binding) {
+            action.bindings.push(binding);
+        }
+    }
+
+    /// Removes a binding from an action. Returns true if the action had the binding.
+    ///
+    /// # Arguments
+    ///
+    /// * `action`: the name of the action
+    /// * `binding`: the input to unbind from the action
+    pub fn unbind(&mut self, action: &str, binding: InputBinding) -> bool {
+        match self.actions.get_mut(action) {
+            Some(action) => {
+                let count = action.bindings.len();
+                action.bindings.retain(|b| *b != binding);
+                action.bindings.len() != count
+            }
+            None => false,
+        }
+    }
+
+    /// Removes all bindings from an action, but keeps the action itself so it can be rebound.
+    pub fn clear_bindings(&mut self, action: &str) {
+        if let Some(action) = self.actions.get_mut(action) {
+            action.bindings.clear();
+        }
+    }
+
+    /// Removes an action and all of its bindings. Returns true if the action existed.
+    pub fn remove_action(&mut self, action: &str) -> bool {
+        self.actions.remove(action).is_some()
+    }
+
+    /// Removes all actions.
+    pub fn clear(&mut self) {
+        self.actions.clear();
+    }
+
+    /// Returns true if an action with the given name exists.
+    #[inline]
+    pub fn has_action(&self, action: &str) -> bool {
+        self.actions.contains_key(action)
+    }
+
+    /// Returns an iterator over the names of all actions, in no particular order.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    /// Returns the bindings for the given action, or `None` if there is no such action.
+    pub fn bindings(&self, action: &str) -> Option<&[InputBinding]> {
+        self.actions
+            .get(action)
+            .map(|action| action.bindings.as_slice())
+    }
+
+    /// Updates the state of all actions from the current state of the given input devices. This
+    /// should be called once each frame, after the input devices have processed all of that
+    /// frame's events.
+    pub fn update(&mut self, keyboard: &Keyboard, mouse: &Mouse, gamepads: &Gamepads) {
+        for action in self.actions.values_mut() {
+            let is_down = action
+                .bindings
+                .iter()
+                .any(|binding| binding.is_down(keyboard, mouse, gamepads));
+            action.state = match (action.state, is_down) {
+                (ButtonState::Pressed | ButtonState::Held, true) => ButtonState::Held,
+                (_, true) => ButtonState::Pressed,
+                (ButtonState::Pressed | ButtonState::Held, false) => ButtonState::Released,
+                (_, false) => ButtonState::Idle,
+            };
+        }
+    }
+
+    #[inline]
+    fn action_state(&self, action: &str) -> ButtonState {
+        self.actions
+            .get(action)
+            .map_or(ButtonState::Idle, |action| action.state)
+    }
+
+    /// Returns true if the given action was just pressed or is being held down. Unknown actions
+    /// are never down.
+    #[inline]
+    pub fn is_action_down(&self, action: &str) -> bool {
+        matches!(
+            self.action_state(action),
+            ButtonState::Pressed | ButtonState::Held
+        )
+    }
+
+    /// Returns true if the given action was not just pressed and is not being held down.
+    #[inline]
+    pub fn is_action_up(&self, action: &str) -> bool {
+        matches!(
+            self.action_state(action),
+            ButtonState::Released | ButtonState::Idle
+        )
+    }
+
+    /// Returns true if the given action was just pressed (not being held down, yet).
+    #[inline]
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        self.action_state(action) == ButtonState::Pressed
+    }
+
+    /// Returns true if the given action was just released.
+    #[inline]
+    pub fn is_action_released(&self, action: &str) -> bool {
+        self.action_state(action) == ButtonState::Released
+    }
+
+    /// Loads and returns an input map from a JSON file on disk, in the format written by
+    /// [`InputMap::to_file`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to load the input map from
+    pub fn load_from_file(path: &Path) -> Result<InputMap, InputMapError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_from_bytes(&mut reader)
+    }
+
+    /// Loads and returns an input map from a reader. The data being loaded is expected to be the
+    /// same as if the input map was being loaded from a file on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the input map from
+    pub fn load_from_bytes<T: Read>(reader: &mut T) -> Result<InputMap, InputMapError> {
+        let json: Value = match serde_json::from_reader(reader) {
+            Ok(json) => json,
+            Err(error) => return Err(InputMapError::BadData(error.to_string())),
+        };
+        Self::from_json(&json)
+    }
+
+    /// Writes the input map to a JSON file on disk. If the file already exists, it will be
+    /// overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to save the input map to
+    pub fn to_file(&self, path: &Path) -> Result<(), InputMapError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_bytes(&mut writer)
+    }
+
+    /// Writes the input map to a writer, in the same format as if it was writing to a file on
+    /// disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the input map to
+    pub fn to_bytes<T: Write>(&self, writer: &mut T) -> Result<(), InputMapError> {
+        if let Err(error) = serde_json::to_writer_pretty(writer, &self.to_json()) {
+            return Err(InputMapError::IOError(error.into()));
+        }
+        Ok(())
+    }
+
+    fn to_json(&self) -> Value {
+        let mut actions = Map::new();
+        for (name, action) in self.actions.iter() {
+            let bindings = action.bindings.iter().map(|binding| binding.to_json());
+            actions.insert(name.clone(), Value::Array(bindings.collect()));
+        }
+        Value::Object(actions)
+    }
+
+    fn from_json(json: &Value) -> Result<InputMap, InputMapError> {
+        let actions = match json.as_object() {
+            Some(actions) => actions,
+            None => {
+                return Err(InputMapError::BadData(String::from(
+                    "Expected an object of actions",
+                )))
+            }
+        };
+
+        let mut input_map = InputMap::new();
+        for (name, bindings) in actions.iter() {
+            let bindings = match bindings.as_array() {
+                Some(bindings) => bindings,
+                None => {
+                    return Err(InputMapError::BadData(format!(
+                        "Expected an array of bindings for action \"{}\"",
+                        name
+                    )))
+                }
+            };
+            input_map.actions.insert(
+                name.clone(),
+                Action {
+                    bindings: Vec::with_capacity(bindings.len()),
+                    state: ButtonState::Idle,
+                },
+            );
+            for binding in bindings.iter() {
+                input_map.bind(name, InputBinding::from_json(binding)?);
+            }
+        }
+        Ok(input_map)
+    }
+}
+
+/// Input bindings are serialized in the same form as they are written in an input map's JSON
+/// file. Only available with the "serde" feature enabled.
+#[cfg(feature = "serde")]
+impl serde::Serialize for InputBinding {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InputBinding {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        InputBinding::from_json(&Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Input maps are serialized in the same form as [`InputMap::to_bytes`] writes them, with only
+/// the bindings being saved and not the current state of each action. Only available with the
+/// "serde" feature enabled.
+#[cfg(feature = "serde")]
+impl serde::Serialize for InputMap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InputMap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        InputMap::from_json(&Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+
+    use claim::*;
+    use sdl2::keyboard::Mod;
+
+    use super::*;
+
+    fn key_event(scancode: Scancode, is_pressed: bool) -> Event {
+        if is_pressed {
+            Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: None,
+                scancode: Some(scancode),
+                keymod: Mod::NOMOD,
+                repeat: false,
+            }
+        } else {
+            Event::KeyUp {
+                timestamp: 0,
+                window_id: 0,
+                keycode: None,
+                scancode: Some(scancode),
+                keymod: Mod::NOMOD,
+                repeat: false,
+            }
+        }
+    }
+
+    #[test]
+    pub fn action_states() {
+        let mut keyboard = Keyboard::new();
+        let mouse = Mouse::new();
+        let mut gamepads = Gamepads::new();
+        gamepads.handle_event(&Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which: 0,
+        });
+
+        let mut input_map = InputMap::new();
+        input_map.bind("jump", InputBinding::Key(Scancode::Space));
+        input_map.bind("jump", InputBinding::GamepadButton(Button::A));
+        input_map.bind(
+            "up",
+            InputBinding::GamepadAxis {
+                axis: Axis::LeftY,
+                threshold: -0.5,
+            },
+        );
+        assert!(input_map.is_action_up("jump"));
+        assert!(input_map.is_action_up("unknown"));
+
+        keyboard.handle_event(&key_event(Scancode::Space, true));
+        input_map.update(&keyboard, &mouse, &gamepads);
+        assert!(input_map.is_action_pressed("jump"));
+        assert!(input_map.is_action_down("jump"));
+        assert!(input_map.is_action_up("up"));
+
+        // still held by the gamepad button after the key is released
+        keyboard.update();
+        keyboard.handle_event(&key_event(Scancode::Space, false));
+        gamepads.handle_event(&Event::ControllerButtonDown {
+            timestamp: 0,
+            which: 0,
+            button: Button::A,
+        });
+        gamepads.handle_event(&Event::ControllerAxisMotion {
+            timestamp: 0,
+            which: 0,
+            axis: Axis::LeftY,
+            value: -30000,
+        });
+        input_map.update(&keyboard, &mouse, &gamepads);
+        assert!(!input_map.is_action_pressed("jump"));
+        assert!(input_map.is_action_down("jump"));
+        assert!(input_map.is_action_pressed("up"));
+
+        gamepads.handle_event(&Event::ControllerButtonUp {
+            timestamp: 0,
+            which: 0,
+            button: Button::A,
+        });
+        input_map.update(&keyboard, &mouse, &gamepads);
+        assert!(input_map.is_action_released("jump"));
+        input_map.update(&keyboard, &mouse, &gamepads);
+        assert!(input_map.is_action_up("jump"));
+        assert!(!input_map.is_action_released("jump"));
+    }
+
+    #[test]
+    pub fn binding_and_unbinding() {
+        let mut input_map = InputMap::new();
+        input_map.bind("fire", InputBinding::Key(Scancode::LCtrl));
+        input_map.bind("fire", InputBinding::Key(Scancode::LCtrl));
+        input_map.bind("fire", InputBinding::MouseButton(1));
+        assert_eq!(2, input_map.bindings("fire").unwrap().len());
+
+        assert!(input_map.unbind("fire", InputBinding::Key(Scancode::LCtrl)));
+        assert!(!input_map.unbind("fire", InputBinding::Key(Scancode::LCtrl)));
+        assert_eq!(
+            &[InputBinding::MouseButton(1)],
+            input_map.bindings("fire").unwrap()
+        );
+
+        input_map.clear_bindings("fire");
+        assert!(input_map.has_action("fire"));
+        assert!(input_map.bindings("fire").unwrap().is_empty());
+        assert!(input_map.remove_action("fire"));
+        assert!(!input_map.has_action("fire"));
+        assert_none!(input_map.bindings("fire"));
+    }
+
+    #[test]
+    pub fn save_and_load() -> Result<(), InputMapError> {
+        let mut input_map = InputMap::new();
+        input_map.bind("jump", InputBinding::Key(Scancode::Space));
+        input_map.bind("jump", InputBinding::GamepadButton(Button::A));
+        input_map.bind("fire", InputBinding::MouseButton(1));
+        input_map.bind(
+            "fire",
+            InputBinding::GamepadAxis {
+                axis: Axis::TriggerRight,
+                threshold: 0.5,
+            },
+        );
+        input_map.bind("pause", InputBinding::Key(Scancode::Escape));
+        input_map.clear_bindings("pause");
+
+        let mut data = Vec::new();
+        input_map.to_bytes(&mut data)?;
+        let loaded = InputMap::load_from_bytes(&mut Cursor::new(data))?;
+
+        let mut actions: Vec<&str> = loaded.actions().collect();
+        actions.sort();
+        assert_eq!(vec!["fire", "jump", "pause"], actions);
+        for action in actions {
+            assert_eq!(input_map.bindings(action), loaded.bindings(action));
+        }
+
+        let bad = r#"{ "jump": [ { "gamepad_button": "z" } ] }"#;
+        assert_matches!(
+            InputMap::load_from_bytes(&mut bad.as_bytes()),
+            Err(InputMapError::BadData(..))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn binding_text_form() {
+        let bindings = [
+            InputBinding::Key(Scancode::Space),
+            InputBinding::MouseButton(2),
+            InputBinding::GamepadButton(Button::DPadUp),
+            InputBinding::GamepadAxis {
+                axis: Axis::TriggerRight,
+                threshold: 0.25,
+            },
+        ];
+        for binding in bindings.iter() {
+            assert_eq!(*binding, assert_ok!(binding.to_string().parse()));
+        }
+        assert_eq!("key:44", InputBinding::Key(Scancode::Space).to_string());
+        assert_eq!(
+            InputBinding::GamepadAxis {
+                axis: Axis::LeftY,
+                threshold: -0.5
+            },
+            assert_ok!(" axis:lefty:-0.5 ".parse())
+        );
+        assert_err!("mouse:left".parse::<InputBinding>());
+        assert_err!("button:z".parse::<InputBinding>());
+        assert_err!("axis:lefty".parse::<InputBinding>());
+        assert_err!("mouse:1:2".parse::<InputBinding>());
+        assert_err!("joystick:1".parse::<InputBinding>());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn serde_round_trip() {
+        let mut input_map = InputMap::new();
+        input_map.bind("jump", InputBinding::Key(Scancode::Space));
+        input_map.bind(
+            "jump",
+            InputBinding::GamepadAxis {
+                axis: Axis::LeftY,
+                threshold: -0.5,
+            },
+        );
+
+        let json = serde_json::to_string(&input_map).unwrap();
+        let loaded: InputMap = assert_ok!(serde_json::from_str(&json));
+        assert_eq!(input_map.bindings("jump"), loaded.bindings("jump"));
+
+        let binding = InputBinding::GamepadButton(Button::Start);
+        let json = serde_json::to_string(&binding).unwrap();
+        assert_eq!(binding, assert_ok!(serde_json::from_str(&json)));
+        let bad = r#"{ "mouse_button": "left" }"#;
+        assert_err!(serde_json::from_str::<InputBinding>(bad));
+    }
+}
@@ -1,22 +1,45 @@
+use std::any::Any;
+use std::collections::VecDeque;
 use std::fmt::Formatter;
 
 use byte_slice_cast::AsByteSlice;
 use sdl2::{AudioSubsystem, EventPump, Sdl, TimerSubsystem, VideoSubsystem};
 use sdl2::audio::AudioSpecDesired;
 use sdl2::event::Event;
-use sdl2::pixels::PixelFormatEnum;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::render::{Texture, WindowCanvas};
+use sdl2::video::{DisplayMode, FullscreenType, WindowPos};
 use thiserror::Error;
 
 use crate::{DEFAULT_SCALE_FACTOR, SCREEN_HEIGHT, SCREEN_WIDTH};
 use crate::audio::*;
 use crate::graphics::*;
 
+pub use self::config::*;
+pub use self::customevent::*;
+pub use self::display::*;
+pub use self::framepacing::*;
+pub use self::headless::*;
 pub use self::input_devices::*;
+pub use self::input_devices::gamepad::*;
 pub use self::input_devices::keyboard::*;
 pub use self::input_devices::mouse::*;
-
+pub use self::input_devices::textinput::*;
+pub use self::inputmap::*;
+pub use self::presentation::*;
+pub use self::secondarywindow::*;
+pub use self::timestep::*;
+
+pub mod config;
+pub mod customevent;
+pub mod display;
+pub mod framepacing;
+pub mod headless;
 pub mod input_devices;
+pub mod inputmap;
+pub mod presentation;
+pub mod secondarywindow;
+pub mod timestep;
 
 #[derive(Error, Debug)]
 pub enum SystemError {
@@ -36,11 +59,20 @@ pub struct SystemBuilder {
     window_title: String,
     vsync: bool,
     target_framerate: Option<u32>,
+    screen_width: u32,
+    screen_height: u32,
     initial_scale_factor: u32,
     resizable: bool,
+    fullscreen: bool,
+    display_index: Option<i32>,
+    alt_enter_fullscreen: bool,
     show_mouse: bool,
     relative_mouse_scaling: bool,
-    integer_scaling: bool,
+    presentation: Presentation,
+    audio_channels: usize,
+    audio_frequency: u32,
+    audio_buffer_size: Option<u16>,
+    audio_device: Option<String>,
 }
 
 impl SystemBuilder {
@@ -50,11 +82,20 @@ impl SystemBuilder {
             window_title: String::new(),
             vsync: false,
             target_framerate: None,
+            screen_width: SCREEN_WIDTH,
+            screen_height: SCREEN_HEIGHT,
             initial_scale_factor: DEFAULT_SCALE_FACTOR,
             resizable: true,
+            fullscreen: false,
+            display_index: None,
+            alt_enter_fullscreen: true,
             show_mouse: false,
             relative_mouse_scaling: true,
-            integer_scaling: false,
+            presentation: Presentation::default(),
+            audio_channels: NUM_CHANNELS,
+            audio_frequency: TARGET_AUDIO_FREQUENCY,
+            audio_buffer_size: None,
+            audio_device: None,
         }
     }
 
@@ -81,6 +122,15 @@ impl SystemBuilder {
         self
     }
 
+    /// Sets the resolution of the virtual framebuffer for the [`System`] being built. By default
+    /// this is [`SCREEN_WIDTH`]x[`SCREEN_HEIGHT`]. This can also be changed later on via
+    /// [`System::resize_backbuffer`].
+    pub fn resolution(&mut self, width: u32, height: u32) -> &mut SystemBuilder {
+        self.screen_width = width;
+        self.screen_height = height;
+        self
+    }
+
     /// Sets an integer scaling factor for the [`System`] being built to up-scale the virtual
     /// framebuffer to when displaying it on screen.
     pub fn scale_factor(&mut self, scale_factor: u32) -> &mut SystemBuilder {
@@ -94,6 +144,26 @@ impl SystemBuilder {
         self
     }
 
+    /// Sets whether the [`System`] being built will start out in (borderless) fullscreen mode.
+    pub fn fullscreen(&mut self, enable: bool) -> &mut SystemBuilder {
+        self.fullscreen = enable;
+        self
+    }
+
+    /// Sets the index of the display (monitor) that the window for the [`System`] being built
+    /// will be shown on. See [`System::displays`].
+    pub fn display(&mut self, index: i32) -> &mut SystemBuilder {
+        self.display_index = Some(index);
+        self
+    }
+
+    /// Enables or disables toggling fullscreen mode by pressing Alt+Enter for the [`System`] being
+    /// built. Enabled by default.
+    pub fn alt_enter_fullscreen(&mut self, enable: bool) -> &mut SystemBuilder {
+        self.alt_enter_fullscreen = enable;
+        self
+    }
+
     /// Enables or disables mouse cursor display by the operating system when the cursor is over
     /// the window for the [`System`] being built. Disable this if you intend to render your own
     /// custom mouse cursor.
@@ -103,7 +173,7 @@ impl SystemBuilder {
     }
 
     /// Enables or disables automatic DPI scaling of mouse relative movement values (delta values)
-    /// available via the [`Mouse`] input device.
+    /// available via [`Mouse::raw_x_delta`] and [`Mouse::raw_y_delta`].
     pub fn relative_mouse_scaling(&mut self, enable: bool) -> &mut SystemBuilder {
         self.relative_mouse_scaling = enable;
         self
@@ -112,16 +182,72 @@ impl SystemBuilder {
     /// Enables or disables restricting the final rendered output to always be integer scaled,
     /// even if that result will not fully fill the area of the window.
     pub fn integer_scaling(&mut self, enable: bool) -> &mut SystemBuilder {
-        self.integer_scaling = enable;
+        self.presentation.integer_scaling = enable;
+        self
+    }
+
+    /// Enables or disables displaying the virtual framebuffer at a 4:3 aspect ratio, as it would
+    /// have been on a CRT monitor, instead of with square pixels.
+    pub fn aspect_correction(&mut self, enable: bool) -> &mut SystemBuilder {
+        self.presentation.aspect_correction = enable;
+        self
+    }
+
+    /// Sets the 32-bit RGB color used to fill areas of the window not covered by the virtual
+    /// framebuffer (e.g. the borders around it when the window's aspect ratio does not match).
+    pub fn letterbox_color(&mut self, color: u32) -> &mut SystemBuilder {
+        self.presentation.letterbox_color = color;
+        self
+    }
+
+    /// Sets the number of audio channels available for simultaneous playback for the [`System`]
+    /// being built. By default this is [`NUM_CHANNELS`].
+    pub fn audio_channels(&mut self, num_channels: usize) -> &mut SystemBuilder {
+        self.audio_channels = num_channels;
+        self
+    }
+
+    /// Sets the audio playback frequency to request for the [`System`] being built. By default
+    /// this is [`TARGET_AUDIO_FREQUENCY`]. The audio device may not support the requested
+    /// frequency, in which case the frequency actually obtained can be checked via
+    /// [`Audio::spec`].
+    pub fn audio_frequency(&mut self, frequency: u32) -> &mut SystemBuilder {
+        self.audio_frequency = frequency;
+        self
+    }
+
+    /// Sets the audio device buffer size (in samples, normally a power of two) to request for the
+    /// [`System`] being built. Smaller buffers lower the latency between playing a sound and
+    /// hearing it, but risk audible glitches if the audio cannot be mixed quickly enough. By
+    /// default SDL picks a buffer size itself. The buffer size actually obtained can be checked
+    /// via [`Audio::buffer_size`].
+    pub fn audio_buffer_size(&mut self, samples: u16) -> &mut SystemBuilder {
+        self.audio_buffer_size = Some(samples);
+        self
+    }
+
+    /// Sets the name of the audio output device that the [`System`] being built should play
+    /// audio on, instead of the system's default device. Available device names can be listed
+    /// with [`audio_output_devices`].
+    pub fn audio_device(&mut self, device_name: &str) -> &mut SystemBuilder {
+        self.audio_device = Some(device_name.to_string());
         self
     }
 
+    /// Builds and returns a [`HeadlessSystem`] from the current configuration. Only the resolution
+    /// and target framerate are used, as there is no window, display or audio device.
+    pub fn build_headless(&self) -> Result<HeadlessSystem, SystemError> {
+        HeadlessSystem::new(
+            self.screen_width,
+            self.screen_height,
+            self.target_framerate,
+        )
+    }
+
     /// Builds and returns a [`System`] from the current configuration.
     pub fn build(&self) -> Result<System, SystemError> {
-        // todo: maybe let this be customized in the future, or at least halved so a 160x120 mode can be available ... ?
-        let screen_width = SCREEN_WIDTH;
-        let screen_height = SCREEN_HEIGHT;
-        let texture_pixel_size = 4; // 32-bit ARGB format
+        let screen_width = self.screen_width;
+        let screen_height = self.screen_height;
 
         sdl2::hint::set(
             "SDL_MOUSE_RELATIVE_SCALING",
@@ -149,6 +275,11 @@ impl SystemBuilder {
             Err(message) => return Err(SystemError::InitError(message)),
         };
 
+        let sdl_game_controller_subsystem = match sdl_context.game_controller() {
+            Ok(game_controller_subsystem) => game_controller_subsystem,
+            Err(message) => return Err(SystemError::InitError(message)),
+        };
+
         let sdl_event_pump = match sdl_context.event_pump() {
             Ok(event_pump) => event_pump,
             Err(message) => return Err(SystemError::InitError(message)),
@@ -161,8 +292,10 @@ impl SystemBuilder {
 
         // create the window
 
-        let window_width = screen_width * self.initial_scale_factor;
-        let window_height = screen_height * self.initial_scale_factor;
+        let (display_width, display_height) =
+            self.presentation.display_size(screen_width, screen_height);
+        let window_width = display_width * self.initial_scale_factor;
+        let window_height = display_height * self.initial_scale_factor;
         let mut window_builder = &mut (sdl_video_subsystem.window(
             self.window_title.as_str(),
             window_width,
@@ -171,6 +304,17 @@ impl SystemBuilder {
         if self.resizable {
             window_builder = window_builder.resizable();
         }
+        if let Some(display_index) = self.display_index {
+            let bounds = match display_bounds(&sdl_video_subsystem, display_index) {
+                Ok(bounds) => bounds,
+                Err(message) => return Err(SystemError::InitError(message)),
+            };
+            let (x, y) = centered_window_position(&bounds, window_width, window_height);
+            window_builder = window_builder.position(x, y);
+        }
+        if self.fullscreen {
+            window_builder = window_builder.fullscreen_desktop();
+        }
         let sdl_window = match window_builder.build() {
             Ok(window) => window,
             Err(error) => return Err(SystemError::InitError(error.to_string())),
@@ -180,58 +324,33 @@ impl SystemBuilder {
 
         // turn the window into a canvas (under the hood, an SDL Renderer that owns the window)
 
-        let mut canvas_builder = sdl_window.into_canvas();
-        if self.vsync {
-            canvas_builder = canvas_builder.present_vsync();
-        }
-        let mut sdl_canvas = match canvas_builder.build() {
+        let mut sdl_canvas = match create_canvas(sdl_window, self.vsync) {
             Ok(canvas) => canvas,
-            Err(error) => return Err(SystemError::InitError(error.to_string())),
+            Err(error) => return Err(SystemError::InitError(error)),
         };
-        if let Err(error) = sdl_canvas.set_logical_size(screen_width, screen_height) {
-            return Err(SystemError::InitError(error.to_string()));
-        };
-
-        // TODO: newer versions of rust-sdl2 support this directly off the WindowCanvas struct
-        unsafe {
-            sdl2::sys::SDL_RenderSetIntegerScale(
-                sdl_canvas.raw(),
-                if self.integer_scaling {
-                    sdl2::sys::SDL_bool::SDL_TRUE
-                } else {
-                    sdl2::sys::SDL_bool::SDL_FALSE
-                },
-            );
-        }
-
-        // create an SDL texture which we will be uploading to every frame to display the
-        // application's framebuffer
-
-        let sdl_texture = match sdl_canvas.create_texture_streaming(
-            Some(PixelFormatEnum::ARGB8888),
+        if let Err(error) = apply_presentation(
+            &mut sdl_canvas,
+            &self.presentation,
             screen_width,
             screen_height,
         ) {
-            Ok(texture) => texture,
-            Err(error) => return Err(SystemError::InitError(error.to_string())),
+            return Err(SystemError::InitError(error));
         };
-        let sdl_texture_pitch = (sdl_texture.query().width * texture_pixel_size) as usize;
-
-        // create a raw 32-bit RGBA buffer that will be used as the temporary source for
-        // SDL texture uploads each frame. necessary as applications are dealing with 8-bit indexed
-        // bitmaps, not 32-bit RGBA pixels, so this temporary buffer is where we convert the final
-        // application framebuffer to 32-bit RGBA pixels before it is uploaded to the SDL texture
-        let texture_pixels_size = (screen_width * screen_height * texture_pixel_size) as usize;
-        let texture_pixels = vec![0u32; texture_pixels_size].into_boxed_slice();
 
         // create the Bitmap object that will be exposed to the application acting as the system
         // backbuffer
 
-        let framebuffer = match Bitmap::new(SCREEN_WIDTH, SCREEN_HEIGHT) {
+        let framebuffer = match Bitmap::new(screen_width, screen_height) {
             Ok(bmp) => bmp,
             Err(error) => return Err(SystemError::InitError(error.to_string())),
         };
 
+        let (sdl_texture, sdl_texture_pitch, texture_pixels) =
+            match create_display_texture(&sdl_canvas, screen_width, screen_height) {
+                Ok(result) => result,
+                Err(error) => return Err(SystemError::InitError(error)),
+            };
+
         // create the default palette, initialized to the VGA default palette. also exposed to the
         // application for manipulation
 
@@ -248,11 +367,16 @@ impl SystemBuilder {
         };
 
         let audio_spec = AudioSpecDesired {
-            freq: Some(TARGET_AUDIO_FREQUENCY as i32),
-            channels: Some(TARGET_AUDIO_CHANNELS),
-            samples: None,
+            freq: Some(self.audio_frequency as i32),
+            channels: Some(TARGET_AUDIO_OUTPUT_CHANNELS),
+            samples: self.audio_buffer_size,
         };
-        let mut audio = Audio::new(audio_spec, &sdl_audio_subsystem)?;
+        let mut audio = Audio::open(
+            self.audio_device.as_deref(),
+            audio_spec,
+            self.audio_channels,
+            &sdl_audio_subsystem,
+        )?;
         audio.resume();
         let audio_queue = AudioQueue::new(&audio);
 
@@ -260,6 +384,14 @@ impl SystemBuilder {
 
         let keyboard = Keyboard::new();
         let mouse = Mouse::new();
+        let gamepads = Gamepads::with_subsystem(sdl_game_controller_subsystem);
+
+        // SDL starts out with text input enabled, which can also show an on-screen keyboard or IME
+        // window on some platforms, so it is only enabled when applications ask for it
+        sdl_video_subsystem.text_input().stop();
+        let text_input = TextInput::new();
+
+        let frame_start_ticks = sdl_timer_subsystem.performance_counter();
 
         Ok(System {
             sdl_context,
@@ -270,21 +402,122 @@ impl SystemBuilder {
             sdl_texture,
             sdl_texture_pitch,
             sdl_event_pump,
+            display_lost: false,
             texture_pixels,
             audio,
             audio_queue,
             video: framebuffer,
             palette,
+            raster_effects: RasterEffects::new(),
+            #[cfg(feature = "crt-filters")]
+            crt_filters: CrtFilters::default(),
+            #[cfg(feature = "crt-filters")]
+            sdl_crt_texture: None,
+            #[cfg(feature = "crt-filters")]
+            crt_texture_pitch: 0,
+            #[cfg(feature = "crt-filters")]
+            crt_texture_pixels: Box::new([]),
             font,
             keyboard,
             mouse,
-            target_framerate: self.target_framerate,
-            target_framerate_delta: None,
-            next_tick: 0,
+            gamepads,
+            text_input,
+            input_map: InputMap::new(),
+            vsync: self.vsync,
+            frame_limiter: FrameLimiter::new(self.target_framerate),
+            frame_stats: FrameStats::default(),
+            frame_start_ticks,
+            render_start_ticks: None,
+            presentation: self.presentation,
+            alt_enter_fullscreen: self.alt_enter_fullscreen,
+            custom_events: VecDeque::new(),
         })
     }
 }
 
+/// Turns the window into a canvas (under the hood, an SDL Renderer that owns the window).
+fn create_canvas(window: sdl2::video::Window, vsync: bool) -> Result<WindowCanvas, String> {
+    let mut canvas_builder = window.into_canvas();
+    if vsync {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    match canvas_builder.build() {
+        Ok(canvas) => Ok(canvas),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Converts 32-bit ARGB colors to RGBA bytes, for as many pixels as fit in the destination.
+pub(crate) fn copy_argb_as_rgba_to(src: &[u32], dest: &mut [u8]) {
+    for (argb, rgba) in src.iter().zip(dest.chunks_exact_mut(4)) {
+        let (a, r, g, b) = from_argb32(*argb);
+        rgba.copy_from_slice(&[r, g, b, a]);
+    }
+}
+
+/// Creates the SDL texture and pixel buffer used to display a backbuffer of the given dimensions.
+/// Returns the texture, its pitch, and the pixel buffer.
+fn create_display_texture(
+    canvas: &WindowCanvas,
+    width: u32,
+    height: u32,
+) -> Result<(Texture, usize, Box<[u32]>), String> {
+    let texture_pixel_size = 4; // 32-bit ARGB format
+
+    // create an SDL texture which we will be uploading to every frame to display the
+    // application's framebuffer
+
+    let texture =
+        match canvas.create_texture_streaming(Some(PixelFormatEnum::ARGB8888), width, height) {
+            Ok(texture) => texture,
+            Err(error) => return Err(error.to_string()),
+        };
+    let texture_pitch = (texture.query().width * texture_pixel_size) as usize;
+
+    // create a raw 32-bit RGBA buffer that will be used as the temporary source for
+    // SDL texture uploads each frame. necessary as applications are dealing with 8-bit indexed
+    // bitmaps, not 32-bit RGBA pixels, so this temporary buffer is where we convert the final
+    // application framebuffer to 32-bit RGBA pixels before it is uploaded to the SDL texture
+    let texture_pixels_size = (width * height) as usize;
+    let texture_pixels = vec![0u32; texture_pixels_size].into_boxed_slice();
+
+    Ok((texture, texture_pitch, texture_pixels))
+}
+
+/// Sets up the canvas' logical size and scaling to match the given presentation options, for a
+/// backbuffer of the given dimensions.
+fn apply_presentation(
+    canvas: &mut WindowCanvas,
+    presentation: &Presentation,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let (display_width, display_height) = presentation.display_size(width, height);
+    if let Err(error) = canvas.set_logical_size(display_width, display_height) {
+        return Err(error.to_string());
+    }
+
+    // TODO: newer versions of rust-sdl2 support this directly off the WindowCanvas struct
+    let result = unsafe {
+        sdl2::sys::SDL_RenderSetIntegerScale(
+            canvas.raw(),
+            if presentation.integer_scaling {
+                sdl2::sys::SDL_bool::SDL_TRUE
+            } else {
+                sdl2::sys::SDL_bool::SDL_FALSE
+            },
+        )
+    };
+    if result != 0 {
+        return Err(sdl2::get_error());
+    }
+
+    let (r, g, b) = from_rgb32(presentation.letterbox_color);
+    canvas.set_draw_color(Color::RGB(r, g, b));
+
+    Ok(())
+}
+
 /// Holds all primary structures necessary for interacting with the operating system and for
 /// applications to render to the display, react to input device events, etc. through the
 /// "virtual machine" exposed by this library.
@@ -298,12 +531,20 @@ pub struct System {
     sdl_texture: Texture,
     sdl_texture_pitch: usize,
     sdl_event_pump: EventPump,
+    display_lost: bool,
 
     texture_pixels: Box<[u32]>,
 
-    target_framerate: Option<u32>,
-    target_framerate_delta: Option<i64>,
-    next_tick: i64,
+    vsync: bool,
+    frame_limiter: FrameLimiter,
+    frame_stats: FrameStats,
+    frame_start_ticks: u64,
+    render_start_ticks: Option<u64>,
+
+    presentation: Presentation,
+    alt_enter_fullscreen: bool,
+
+    custom_events: VecDeque<CustomEvent>,
 
     /// An [`Audio`] instance that allows interacting with the system's audio output device.
     pub audio: Audio,
@@ -316,13 +557,32 @@ pub struct System {
 
     /// The primary backbuffer [`Bitmap`] that will be rendered to the screen whenever
     /// [`System::display`] is called. Regardless of the actual window size, this bitmap is always
-    /// [`SCREEN_WIDTH`]x[`SCREEN_HEIGHT`] pixels in size.
+    /// the resolution the [`System`] was built with ([`SCREEN_WIDTH`]x[`SCREEN_HEIGHT`] by
+    /// default), unless changed via [`System::resize_backbuffer`].
     pub video: Bitmap,
 
     /// The [`Palette`] that will be used in conjunction with the `video` backbuffer to
     /// render the final output to the screen whenever [`System::display`] is called.
     pub palette: Palette,
 
+    /// Palette changes to be made part way through rendering the `video` backbuffer to the screen
+    /// whenever [`System::display`] is called, allowing for raster effects like copper bars or
+    /// horizon splits. These are not cleared automatically after each frame.
+    pub raster_effects: RasterEffects,
+
+    /// CRT-style post-processing filters applied to the final output whenever
+    /// [`System::display`] is called. No filters are applied by default. Since the filtered output
+    /// is up-scaled by [`CrtFilters::scale`] before being scaled again to fit the window, these
+    /// look best when that scale roughly matches the window's scale factor.
+    #[cfg(feature = "crt-filters")]
+    pub crt_filters: CrtFilters,
+    #[cfg(feature = "crt-filters")]
+    sdl_crt_texture: Option<Texture>,
+    #[cfg(feature = "crt-filters")]
+    crt_texture_pitch: usize,
+    #[cfg(feature = "crt-filters")]
+    crt_texture_pixels: Box<[u32]>,
+
     /// A pre-loaded [`Font`] that can be used for text rendering.
     pub font: BitmaskFont,
 
@@ -333,6 +593,21 @@ pub struct System {
     /// The current mouse state. To ensure it is updated each frame, you should call
     /// [`System::do_events`] or [`System::do_events_with`] each frame.
     pub mouse: Mouse,
+
+    /// The current state of all connected gamepads. Gamepads can be connected and disconnected
+    /// at any time. To ensure it is updated each frame, you should call [`System::do_events`] or
+    /// [`System::do_events_with`] each frame.
+    pub gamepads: Gamepads,
+
+    /// Text entered while text input mode is active. See [`System::start_text_input`]. To ensure
+    /// it is updated each frame, you should call [`System::do_events`] or
+    /// [`System::do_events_with`] each frame.
+    pub text_input: TextInput,
+
+    /// Named actions mapped to keyboard, mouse and gamepad inputs. No actions are defined by
+    /// default. This is updated from the other input devices each frame by
+    /// [`System::do_events`] and [`System::do_events_with`].
+    pub input_map: InputMap,
 }
 
 impl std::fmt::Debug for System {
@@ -342,12 +617,17 @@ impl std::fmt::Debug for System {
             .field("audio_queue", &self.audio_queue)
             .field("video", &self.video)
             .field("palette", &self.palette)
+            .field("raster_effects", &self.raster_effects)
             .field("font", &self.font)
             .field("keyboard", &self.keyboard)
             .field("mouse", &self.mouse)
-            .field("target_framerate", &self.target_framerate)
-            .field("target_framerate_delta", &self.target_framerate_delta)
-            .field("next_tick", &self.next_tick)
+            .field("gamepads", &self.gamepads)
+            .field("text_input", &self.text_input)
+            .field("input_map", &self.input_map)
+            .field("vsync", &self.vsync)
+            .field("frame_limiter", &self.frame_limiter)
+            .field("frame_stats", &self.frame_stats)
+            .field("presentation", &self.presentation)
             .finish_non_exhaustive()
     }
 }
@@ -355,63 +635,267 @@ impl std::fmt::Debug for System {
 impl System {
     /// Takes the `video` backbuffer bitmap and `palette` and renders it to the window, up-scaled
     /// to fill the window (preserving aspect ratio of course). If V-sync is enabled, this method
-    /// will block to wait for V-sync. Otherwise, if a target framerate was configured a delay
-    /// might be used to try to meet that framerate.
+    /// will block to wait for V-sync. If a target framerate was configured, this method will also
+    /// block for as long as needed to meet that framerate. Timings for the frame are then added to
+    /// the [`System::frame_stats`].
     pub fn display(&mut self) -> Result<(), SystemError> {
+        self.check_display()?;
+
         self.mouse.render_cursor(&mut self.video);
 
         // convert application framebuffer to 32-bit RGBA pixels, and then upload it to the SDL
         // texture so it will be displayed on screen
 
-        self.video
-            .copy_as_argb_to(&mut self.texture_pixels, &self.palette);
+        self.video.copy_as_argb_to_with_raster_effects(
+            &mut self.texture_pixels,
+            &self.palette,
+            &self.raster_effects,
+        );
 
-        let texture_pixels = self.texture_pixels.as_byte_slice();
-        if let Err(error) = self
-            .sdl_texture
-            .update(None, texture_pixels, self.sdl_texture_pitch)
-        {
-            return Err(SystemError::DisplayError(error.to_string()));
-        }
         self.sdl_canvas.clear();
-        if let Err(error) = self.sdl_canvas.copy(&self.sdl_texture, None, None) {
-            return Err(SystemError::DisplayError(error));
+        #[cfg(feature = "crt-filters")]
+        let copied = self.copy_crt_filtered()?;
+        #[cfg(not(feature = "crt-filters"))]
+        let copied = false;
+        if !copied {
+            let texture_pixels = self.texture_pixels.as_byte_slice();
+            if let Err(error) =
+                self.sdl_texture
+                    .update(None, texture_pixels, self.sdl_texture_pitch)
+            {
+                return Err(SystemError::DisplayError(error.to_string()));
+            }
+            if let Err(error) = self.sdl_canvas.copy(&self.sdl_texture, None, None) {
+                return Err(SystemError::DisplayError(error));
+            }
         }
+        let render_end_ticks = self.ticks();
         self.sdl_canvas.present();
 
         self.mouse.hide_cursor(&mut self.video);
 
-        // if a specific target framerate is desired, apply some loop timing/delay to achieve it
-        // TODO: do this better. delaying when running faster like this is a poor way to do this..
-
-        if let Some(target_framerate) = self.target_framerate {
-            if self.target_framerate_delta.is_some() {
-                // normal path for every other loop iteration except the first
-                let delay = self.next_tick - self.ticks() as i64;
-                if delay < 0 {
-                    // this loop iteration took too long, no need to delay
-                    self.next_tick -= delay;
-                } else {
-                    // this loop iteration completed before next_tick time, delay by the remainder
-                    // time period so we're running at about the desired framerate
-                    self.delay(((delay * 1000) / self.tick_frequency() as i64) as u32);
+        // if a specific target framerate is desired, wait until it is time for the next frame
+
+        let tick_frequency = self.tick_frequency();
+        if let Some(deadline) = self
+            .frame_limiter
+            .next_deadline(self.ticks(), tick_frequency)
+        {
+            self.wait_until(deadline);
+        }
+
+        let frame_end_ticks = self.ticks();
+        let render_start_ticks = self
+            .render_start_ticks
+            .take()
+            .unwrap_or(self.frame_start_ticks);
+        let seconds = |ticks: u64| (ticks as f64 / tick_frequency as f64) as f32;
+        self.frame_stats.record(FrameTiming {
+            frame: seconds(frame_end_ticks.saturating_sub(self.frame_start_ticks)),
+            update: seconds(render_start_ticks.saturating_sub(self.frame_start_ticks)),
+            render: seconds(render_end_ticks.saturating_sub(render_start_ticks)),
+        });
+        self.frame_start_ticks = frame_end_ticks;
+
+        Ok(())
+    }
+
+    /// Blocks until the timer reaches the given value. Sleeps for most of the time, but spins
+    /// for the last little bit as sleeping is not very precise.
+    fn wait_until(&mut self, deadline_ticks: u64) {
+        let tick_frequency = self.tick_frequency();
+        let spin_ticks = (tick_frequency as f64 * FRAME_LIMITER_SPIN_TIME as f64) as u64;
+        let ticks = self.ticks();
+        if deadline_ticks > ticks + spin_ticks {
+            let millis = ((deadline_ticks - spin_ticks - ticks) * 1000) / tick_frequency;
+            self.delay(millis as u32);
+        }
+        while self.ticks() < deadline_ticks {
+            std::hint::spin_loop();
+        }
+    }
+
+    /// Returns the final output of the most recently displayed frame, as converted from the
+    /// `video` backbuffer using the `palette` and any raster effects by [`System::display`]. There
+    /// is one 32-bit ARGB color (see [`to_argb32`]) per backbuffer pixel, row by row, including the
+    /// mouse cursor if it was drawn. Any CRT filters and window up-scaling are not applied. Useful
+    /// for piping frames into video encoders, streaming tools or custom presentation layers.
+    #[inline]
+    pub fn rendered_frame_pixels(&self) -> &[u32] {
+        &self.texture_pixels
+    }
+
+    /// Copies the final output of the most recently displayed frame (see
+    /// [`System::rendered_frame_pixels`]) to the given buffer as RGBA bytes, 4 bytes per pixel.
+    /// Only as many pixels as fit in the destination buffer are copied.
+    pub fn copy_rendered_frame_rgba_to(&self, dest: &mut [u8]) {
+        copy_argb_as_rgba_to(&self.texture_pixels, dest);
+    }
+
+    /// Marks the point in the current frame where updating has finished and rendering is
+    /// starting, so that [`System::frame_stats`] can break frame timings down into update and
+    /// render times. If this is not called during a frame, all of the time spent before
+    /// [`System::display`] is counted as rendering.
+    pub fn begin_render(&mut self) {
+        self.render_start_ticks = Some(self.ticks());
+    }
+
+    /// Returns timing statistics for recently displayed frames.
+    #[inline]
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    /// Returns the framerate that [`System::display`] is currently being limited to, if any.
+    #[inline]
+    pub fn target_framerate(&self) -> Option<u32> {
+        self.frame_limiter.target_framerate()
+    }
+
+    /// Changes the framerate that [`System::display`] is limited to, or `None` to not limit the
+    /// framerate. This is independent of V-sync, though normally only one or the other would
+    /// be used.
+    pub fn set_target_framerate(&mut self, target_framerate: Option<u32>) {
+        self.frame_limiter.set_target_framerate(target_framerate);
+    }
+
+    #[inline]
+    pub fn is_vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Enables or disables V-sync. The `video` backbuffer, `palette` and all other state is
+    /// preserved. If this fails, the previous V-sync setting is restored and an error is returned.
+    /// If even that is not possible, the display is lost and this, along with all further calls to
+    /// [`System::display`], will return an error.
+    pub fn set_vsync(&mut self, enable: bool) -> Result<(), SystemError> {
+        self.check_display()?;
+        if enable == self.vsync {
+            return Ok(());
+        }
+
+        // the version of SDL in use can only set V-sync when a renderer is created, so a new one
+        // is needed. but only one renderer can exist per window, so the current renderer is
+        // swapped for a renderer belonging to a hidden placeholder window so that it can be
+        // destroyed (along with all of its textures) before creating the new one
+        let placeholder_window = match self.sdl_video_subsystem.window("", 1, 1).hidden().build() {
+            Ok(window) => window,
+            Err(error) => return Err(SystemError::DisplayError(error.to_string())),
+        };
+        let placeholder_canvas = match create_canvas(placeholder_window, false) {
+            Ok(canvas) => canvas,
+            Err(error) => return Err(SystemError::DisplayError(error)),
+        };
+        let window = std::mem::replace(&mut self.sdl_canvas, placeholder_canvas).into_window();
+        // keeps the window alive if creating its new renderer fails, so that it can be given
+        // another renderer using the previous setting
+        let window_context = window.context();
+
+        match self.recreate_display(window, enable) {
+            Ok(()) => {
+                self.vsync = enable;
+                Ok(())
+            }
+            Err(error) => {
+                // the old renderer and its textures are already gone either way, so try to get back
+                // to a usable display with the previous setting. safe, as the window is still
+                // alive and no renderer belongs to it any longer
+                let window = unsafe { sdl2::video::Window::from_ref(window_context) };
+                if self.recreate_display(window, self.vsync).is_err() {
+                    self.display_lost = true;
                 }
-            } else {
-                // this branch will occur on the first main loop iteration. we use the fact that
-                // target_framerate_delta was not yet set to avoid doing any delay on the first
-                // loop, just in case there was some other processing between the System struct
-                // being created and the actual beginning of the first loop ...
-                self.target_framerate_delta =
-                    Some((self.tick_frequency() / target_framerate as u64) as i64);
+                Err(SystemError::DisplayError(error))
             }
-
-            // expected time for the next display() call to happen by
-            self.next_tick = (self.ticks() as i64) + self.target_framerate_delta.unwrap();
         }
+    }
 
+    /// Gives the window a new renderer with the given V-sync setting, along with new textures for
+    /// displaying the `video` backbuffer. Nothing is changed unless all of them are created.
+    fn recreate_display(&mut self, window: sdl2::video::Window, vsync: bool) -> Result<(), String> {
+        let mut canvas = create_canvas(window, vsync)?;
+        let width = self.video.width();
+        let height = self.video.height();
+        apply_presentation(&mut canvas, &self.presentation, width, height)?;
+        let (texture, texture_pitch, texture_pixels) =
+            create_display_texture(&canvas, width, height)?;
+
+        // the old textures were destroyed along with the old renderer, so must not be used again
+        self.sdl_canvas = canvas;
+        self.sdl_texture = texture;
+        self.sdl_texture_pitch = texture_pitch;
+        self.texture_pixels = texture_pixels;
+        #[cfg(feature = "crt-filters")]
+        {
+            self.sdl_crt_texture = None;
+        }
         Ok(())
     }
 
+    /// Returns an error if the display was lost by a failed [`System::set_vsync`], in which case
+    /// the textures held for displaying the `video` backbuffer are no longer valid.
+    fn check_display(&self) -> Result<(), SystemError> {
+        if self.display_lost {
+            Err(SystemError::DisplayError(String::from(
+                "The display was lost after failing to change V-sync",
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies `crt_filters` to the converted backbuffer pixels and copies the result to the
+    /// canvas. Returns false if there were no filters to apply, in which case nothing is copied.
+    #[cfg(feature = "crt-filters")]
+    fn copy_crt_filtered(&mut self) -> Result<bool, SystemError> {
+        if self.crt_filters.is_empty() {
+            return Ok(false);
+        }
+
+        let width = self.video.width();
+        let height = self.video.height();
+        let (output_width, output_height) = self.crt_filters.output_size(width, height);
+
+        // the filtered output size changes along with the backbuffer size or filter scale
+        let recreate_texture = match &self.sdl_crt_texture {
+            Some(texture) => {
+                let query = texture.query();
+                query.width != output_width || query.height != output_height
+            }
+            None => true,
+        };
+        if recreate_texture {
+            if let Some(texture) = self.sdl_crt_texture.take() {
+                // safe, as the canvas that created this texture is still alive
+                unsafe { texture.destroy() };
+            }
+            let (texture, texture_pitch, texture_pixels) =
+                match create_display_texture(&self.sdl_canvas, output_width, output_height) {
+                    Ok(result) => result,
+                    Err(error) => return Err(SystemError::DisplayError(error)),
+                };
+            self.sdl_crt_texture = Some(texture);
+            self.crt_texture_pitch = texture_pitch;
+            self.crt_texture_pixels = texture_pixels;
+        }
+
+        self.crt_filters.apply(
+            &self.texture_pixels,
+            width,
+            height,
+            &mut self.crt_texture_pixels,
+        );
+
+        let texture = self.sdl_crt_texture.as_mut().unwrap();
+        let texture_pixels = self.crt_texture_pixels.as_byte_slice();
+        if let Err(error) = texture.update(None, texture_pixels, self.crt_texture_pitch) {
+            return Err(SystemError::DisplayError(error.to_string()));
+        }
+        if let Err(error) = self.sdl_canvas.copy(texture, None, None) {
+            return Err(SystemError::DisplayError(error));
+        }
+        Ok(true)
+    }
+
     /// Checks for and responds to all SDL2 events waiting in the queue. Each event is passed to
     /// all [`InputDevice`]'s automatically to ensure input device state is up to date.
     pub fn do_events(&mut self) {
@@ -420,21 +904,353 @@ impl System {
 
     /// Same as [`System::do_events`] but also takes a function which will be called for each
     /// SDL2 event being processed (after everything else has already processed it), allowing
-    /// your application to also react to any events received.
+    /// your application to also react to any events received. Any queued [`CustomEvent`]s are
+    /// discarded, use [`System::do_all_events_with`] to receive those as well.
     pub fn do_events_with<F>(&mut self, mut f: F)
     where
         F: FnMut(&Event),
+    {
+        self.do_all_events_with(|event| {
+            if let SystemEvent::Sdl(event) = event {
+                f(event);
+            }
+        });
+    }
+
+    /// Queues up an application-defined event, to be delivered to the function passed to the
+    /// next call to [`System::do_all_events_with`], after all of that call's SDL2 events. Events
+    /// can be of any type, see [`CustomEvent`].
+    pub fn push_event<T: Any>(&mut self, event: T) {
+        self.custom_events.push_back(CustomEvent::new(event));
+    }
+
+    /// Returns the number of application-defined events queued via [`System::push_event`] that
+    /// have not been delivered yet.
+    #[inline]
+    pub fn pending_custom_events(&self) -> usize {
+        self.custom_events.len()
+    }
+
+    /// Same as [`System::do_events_with`] but also delivers any application-defined events that
+    /// were queued via [`System::push_event`] to the given function, after all SDL2 events. This
+    /// lets applications handle their own events in the same place as system events, without a
+    /// separate event loop.
+    ///
+    /// ```no_run
+    /// use libretrogd::system::*;
+    ///
+    /// enum GameEvent {
+    ///     LevelComplete,
+    /// }
+    ///
+    /// let mut system = SystemBuilder::new().build().unwrap();
+    /// system.push_event(GameEvent::LevelComplete);
+    /// system.do_all_events_with(|event| match event {
+    ///     SystemEvent::Sdl(sdl2::event::Event::Quit { .. }) => { /* ... */ }
+    ///     event => {
+    ///         if let Some(GameEvent::LevelComplete) = event.custom::<GameEvent>() {
+    ///             // ...
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn do_all_events_with<F>(&mut self, mut f: F)
+    where
+        F: FnMut(SystemEvent),
     {
         self.keyboard.update();
         self.mouse.update();
+        self.gamepads.update();
+        self.text_input.update();
         self.sdl_event_pump.pump_events();
+        let main_window_id = self.sdl_canvas.window().id();
+        let mut toggle_fullscreen = false;
         for event in self.sdl_event_pump.poll_iter() {
+            // events belonging to any secondary windows are only passed along to `f`
+            let is_main_window_event = match event.get_window_id() {
+                Some(window_id) => window_id == main_window_id,
+                None => true,
+            };
+            if !is_main_window_event {
+                f(SystemEvent::Sdl(&event));
+                continue;
+            }
+
+            let event =
+                self.presentation
+                    .map_mouse_event(event, self.video.width(), self.video.height());
+            if self.alt_enter_fullscreen && is_fullscreen_toggle_event(&event) {
+                toggle_fullscreen = true;
+            }
             self.keyboard.handle_event(&event);
             self.mouse.handle_event(&event);
-            f(&event);
+            self.gamepads.handle_event(&event);
+            self.text_input.handle_event(&event);
+            // if the audio device could not be reopened, the old one is kept and nothing else can
+            // really be done about it here
+            let _ = self.audio.handle_event(&event);
+            f(SystemEvent::Sdl(&event));
+        }
+        self.input_map
+            .update(&self.keyboard, &self.mouse, &self.gamepads);
+        for event in std::mem::take(&mut self.custom_events) {
+            f(SystemEvent::Custom(&event));
+        }
+        if toggle_fullscreen {
+            // not much we can do about a failure here, and the window will simply stay as it was
+            let _ = self.toggle_fullscreen();
+        }
+    }
+
+    /// Switches between drawing the [`Mouse`] cursor bitmap (see [`Mouse::set_mouse_cursor`]) and
+    /// showing the operating system's own mouse cursor. The cursor bitmap is drawn into the `video`
+    /// backbuffer by [`System::display`], and so is up-scaled along with everything else in the
+    /// same way, while the operating system's cursor is hidden whenever it is over the window.
+    pub fn enable_bitmap_cursor(&mut self, enable: bool) {
+        self.mouse.enable_cursor(enable);
+        self.sdl_context.mouse().show_cursor(!enable);
+    }
+
+    /// Returns true if relative mouse mode is currently enabled.
+    pub fn is_relative_mouse_mode(&self) -> bool {
+        self.sdl_context.mouse().relative_mouse_mode()
+    }
+
+    /// Enables or disables relative mouse mode, where the mouse is captured by the window and the
+    /// operating system's mouse cursor is hidden. The [`Mouse`] cursor position still stops at the
+    /// edges of the window, but [`Mouse::raw_x_delta`] and [`Mouse::raw_y_delta`] continue to
+    /// report all movement, as is needed for FPS-style mouse controls.
+    pub fn set_relative_mouse_mode(&mut self, enable: bool) {
+        self.sdl_context.mouse().set_relative_mouse_mode(enable);
+    }
+
+    /// Starts text input mode, where text typed by the user is collected in `text_input`. On some
+    /// platforms this may also show an on-screen keyboard or IME window. The text entered by any
+    /// previous use of text input mode is kept, and can be cleared with [`TextInput::clear`].
+    pub fn start_text_input(&mut self) {
+        self.sdl_video_subsystem.text_input().start();
+        self.text_input.set_active(true);
+    }
+
+    /// Stops text input mode. The entered text remains available in `text_input`.
+    pub fn stop_text_input(&mut self) {
+        self.sdl_video_subsystem.text_input().stop();
+        self.text_input.set_active(false);
+    }
+
+    /// Returns the options currently being used to present the `video` backbuffer in the window.
+    #[inline]
+    pub fn presentation(&self) -> &Presentation {
+        &self.presentation
+    }
+
+    /// Changes how the `video` backbuffer is presented in the window. Takes effect immediately,
+    /// and can be called at any time.
+    pub fn set_presentation(&mut self, presentation: Presentation) -> Result<(), SystemError> {
+        if let Err(error) = apply_presentation(
+            &mut self.sdl_canvas,
+            &presentation,
+            self.video.width(),
+            self.video.height(),
+        ) {
+            return Err(SystemError::DisplayError(error));
+        }
+        self.presentation = presentation;
+        Ok(())
+    }
+
+    /// Enables or disables restricting the final rendered output to always be integer scaled.
+    /// See [`Presentation::integer_scaling`].
+    pub fn set_integer_scaling(&mut self, enable: bool) -> Result<(), SystemError> {
+        self.set_presentation(Presentation {
+            integer_scaling: enable,
+            ..self.presentation
+        })
+    }
+
+    /// Enables or disables 4:3 aspect ratio correction. See [`Presentation::aspect_correction`].
+    pub fn set_aspect_correction(&mut self, enable: bool) -> Result<(), SystemError> {
+        self.set_presentation(Presentation {
+            aspect_correction: enable,
+            ..self.presentation
+        })
+    }
+
+    /// Sets the 32-bit RGB color used to fill areas of the window not covered by the `video`
+    /// backbuffer. See [`Presentation::letterbox_color`].
+    pub fn set_letterbox_color(&mut self, color: u32) -> Result<(), SystemError> {
+        self.set_presentation(Presentation {
+            letterbox_color: color,
+            ..self.presentation
+        })
+    }
+
+    /// Resizes the window so that the `video` backbuffer is up-scaled by the given integer scale
+    /// factor, taking the current presentation options into account.
+    pub fn set_scale_factor(&mut self, scale_factor: u32) -> Result<(), SystemError> {
+        let (display_width, display_height) = self
+            .presentation
+            .display_size(self.video.width(), self.video.height());
+        let scale_factor = scale_factor.max(1);
+        match self
+            .sdl_canvas
+            .window_mut()
+            .set_size(display_width * scale_factor, display_height * scale_factor)
+        {
+            Ok(_) => Ok(()),
+            Err(error) => Err(SystemError::DisplayError(error.to_string())),
+        }
+    }
+
+    /// Changes the resolution of the `video` backbuffer, replacing it with a new (cleared) bitmap
+    /// of the given size. The window is left as-is, so the new backbuffer will be up-scaled to
+    /// fit it according to the current presentation options. Use [`System::set_scale_factor`]
+    /// afterwards if the window should be resized to match.
+    pub fn resize_backbuffer(&mut self, width: u32, height: u32) -> Result<(), SystemError> {
+        self.check_display()?;
+        let video = match Bitmap::new(width, height) {
+            Ok(bmp) => bmp,
+            Err(error) => return Err(SystemError::DisplayError(error.to_string())),
+        };
+        let (texture, texture_pitch, texture_pixels) =
+            match create_display_texture(&self.sdl_canvas, width, height) {
+                Ok(result) => result,
+                Err(error) => return Err(SystemError::DisplayError(error)),
+            };
+        if let Err(error) =
+            apply_presentation(&mut self.sdl_canvas, &self.presentation, width, height)
+        {
+            // safe, as the canvas that created this texture is still alive
+            unsafe { texture.destroy() };
+            return Err(SystemError::DisplayError(error));
+        }
+
+        let old_texture = std::mem::replace(&mut self.sdl_texture, texture);
+        // safe, as the canvas that created this texture is still alive
+        unsafe { old_texture.destroy() };
+        self.sdl_texture_pitch = texture_pitch;
+        self.texture_pixels = texture_pixels;
+        self.video = video;
+        Ok(())
+    }
+
+    /// Creates a new [`SecondaryWindow`], shown alongside the main window, with its own `video`
+    /// backbuffer of the given size.
+    ///
+    /// # Arguments
+    ///
+    /// * `title`: the title of the new window
+    /// * `width`: the width of the new window's backbuffer
+    /// * `height`: the height of the new window's backbuffer
+    /// * `scale_factor`: the integer scale factor the window is initially sized to
+    pub fn create_window(
+        &self,
+        title: &str,
+        width: u32,
+        height: u32,
+        scale_factor: u32,
+    ) -> Result<SecondaryWindow, SystemError> {
+        SecondaryWindow::new(
+            &self.sdl_video_subsystem,
+            title,
+            width,
+            height,
+            scale_factor,
+        )
+    }
+
+    /// Returns true if the window is currently fullscreen (either borderless or exclusive).
+    pub fn is_fullscreen(&self) -> bool {
+        self.sdl_canvas.window().fullscreen_state() != FullscreenType::Off
+    }
+
+    /// Switches the window between borderless fullscreen (covering the entire display it is on,
+    /// using the desktop's display mode) and windowed mode. The `video` backbuffer, `palette` and
+    /// presentation options are all preserved across the switch.
+    pub fn set_fullscreen(&mut self, enable: bool) -> Result<(), SystemError> {
+        let fullscreen_type = if enable {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Off
+        };
+        if let Err(error) = self.sdl_canvas.window_mut().set_fullscreen(fullscreen_type) {
+            return Err(SystemError::DisplayError(error));
+        }
+        Ok(())
+    }
+
+    /// Toggles between borderless fullscreen and windowed mode. See [`System::set_fullscreen`].
+    pub fn toggle_fullscreen(&mut self) -> Result<(), SystemError> {
+        self.set_fullscreen(!self.is_fullscreen())
+    }
+
+    /// Switches the window to exclusive fullscreen, changing the display to the given display
+    /// mode, which should be one of those listed in [`DisplayInfo::modes`] for the current
+    /// display. Use [`System::set_fullscreen`] to return to windowed mode.
+    pub fn set_exclusive_fullscreen(&mut self, mode: DisplayMode) -> Result<(), SystemError> {
+        let window = self.sdl_canvas.window_mut();
+        if let Err(error) = window.set_display_mode(mode) {
+            return Err(SystemError::DisplayError(error));
+        }
+        if let Err(error) = window.set_fullscreen(FullscreenType::True) {
+            return Err(SystemError::DisplayError(error));
+        }
+        Ok(())
+    }
+
+    /// Returns information about all of the displays (monitors) connected to the system.
+    pub fn displays(&self) -> Result<Vec<DisplayInfo>, SystemError> {
+        let num_displays = match self.sdl_video_subsystem.num_video_displays() {
+            Ok(num_displays) => num_displays,
+            Err(error) => return Err(SystemError::DisplayError(error)),
+        };
+        let mut displays = Vec::with_capacity(num_displays as usize);
+        for index in 0..num_displays {
+            match DisplayInfo::query(&self.sdl_video_subsystem, index) {
+                Ok(display) => displays.push(display),
+                Err(error) => return Err(SystemError::DisplayError(error)),
+            }
+        }
+        Ok(displays)
+    }
+
+    /// Returns the index of the display that the window is currently shown on.
+    pub fn current_display(&self) -> Result<i32, SystemError> {
+        match self.sdl_canvas.window().display_index() {
+            Ok(index) => Ok(index),
+            Err(error) => Err(SystemError::DisplayError(error)),
         }
     }
 
+    /// Moves the window so that it is centered on the display with the given index. If the window
+    /// is currently fullscreen, it will remain fullscreen on the new display.
+    pub fn set_display(&mut self, index: i32) -> Result<(), SystemError> {
+        let bounds = match display_bounds(&self.sdl_video_subsystem, index) {
+            Ok(bounds) => bounds,
+            Err(error) => return Err(SystemError::DisplayError(error)),
+        };
+
+        let window = self.sdl_canvas.window_mut();
+        let fullscreen_type = window.fullscreen_state();
+        if fullscreen_type != FullscreenType::Off {
+            // fullscreen windows cannot be moved, so temporarily switch back to windowed mode
+            if let Err(error) = window.set_fullscreen(FullscreenType::Off) {
+                return Err(SystemError::DisplayError(error));
+            }
+        }
+
+        let (width, height) = window.size();
+        let (x, y) = centered_window_position(&bounds, width, height);
+        window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+
+        if fullscreen_type != FullscreenType::Off {
+            if let Err(error) = window.set_fullscreen(fullscreen_type) {
+                return Err(SystemError::DisplayError(error));
+            }
+        }
+        Ok(())
+    }
+
     pub fn ticks(&self) -> u64 {
         self.sdl_timer_subsystem.performance_counter()
     }
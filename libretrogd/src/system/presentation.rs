@@ -0,0 +1,153 @@
+use sdl2::event::Event;
+
+/// Options controlling how the `video` backbuffer is up-scaled and presented in the window by
+/// [crate::system::System::display]. These can be changed at any time while the application is
+/// running via [crate::system::System::set_presentation] and friends.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Presentation {
+    /// Restricts up-scaling to whole multiples of the display size, even if that means the
+    /// window will not be fully filled.
+    pub integer_scaling: bool,
+
+    /// Displays the backbuffer at a 4:3 aspect ratio regardless of its pixel dimensions, by
+    /// stretching it vertically. For example, a 320x200 backbuffer will be shown with the same
+    /// non-square pixels a CRT monitor would have displayed it with.
+    pub aspect_correction: bool,
+
+    /// The 32-bit RGB color (see [crate::graphics::to_rgb32]) to fill areas of the window not
+    /// covered by the backbuffer with.
+    pub letterbox_color: u32,
+}
+
+impl Presentation {
+    /// Returns the size that a backbuffer of the given dimensions is displayed at (before any
+    /// up-scaling to fit the window) with these presentation options.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: the width of the backbuffer
+    /// * `height`: the height of the backbuffer
+    pub fn display_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.aspect_correction {
+            (width, (width * 3).div_ceil(4))
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Maps a mouse event with coordinates relative to the display size (see
+    /// [Presentation::display_size]) back to backbuffer coordinates. Events other than mouse
+    /// motion and button events are returned as-is.
+    pub(crate) fn map_mouse_event(&self, event: Event, width: u32, height: u32) -> Event {
+        let (_, display_height) = self.display_size(width, height);
+        if display_height == height {
+            return event;
+        }
+        let map_y = |y: i32| (y as i64 * height as i64 / display_height as i64) as i32;
+        match event {
+            Event::MouseMotion {
+                timestamp,
+                window_id,
+                which,
+                mousestate,
+                x,
+                y,
+                xrel,
+                yrel,
+            } => Event::MouseMotion {
+                timestamp,
+                window_id,
+                which,
+                mousestate,
+                x,
+                y: map_y(y),
+                xrel,
+                yrel: map_y(yrel),
+            },
+            Event::MouseButtonDown {
+                timestamp,
+                window_id,
+                which,
+                mouse_btn,
+                clicks,
+                x,
+                y,
+            } => Event::MouseButtonDown {
+                timestamp,
+                window_id,
+                which,
+                mouse_btn,
+                clicks,
+                x,
+                y: map_y(y),
+            },
+            Event::MouseButtonUp {
+                timestamp,
+                window_id,
+                which,
+                mouse_btn,
+                clicks,
+                x,
+                y,
+            } => Event::MouseButtonUp {
+                timestamp,
+                window_id,
+                which,
+                mouse_btn,
+                clicks,
+                x,
+                y: map_y(y),
+            },
+            _ => event,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use claim::*;
+    use sdl2::mouse::MouseButton;
+
+    use super::*;
+
+    #[test]
+    pub fn display_size() {
+        let mut presentation = Presentation::default();
+        assert_eq!((320, 200), presentation.display_size(320, 200));
+        presentation.aspect_correction = true;
+        assert_eq!((320, 240), presentation.display_size(320, 200));
+        assert_eq!((320, 240), presentation.display_size(320, 240));
+        assert_eq!((640, 480), presentation.display_size(640, 400));
+        assert_eq!((10, 8), presentation.display_size(10, 4));
+    }
+
+    #[test]
+    pub fn maps_mouse_events() {
+        let event = Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x: 100,
+            y: 239,
+        };
+
+        let presentation = Presentation::default();
+        let mapped = presentation.map_mouse_event(event.clone(), 320, 200);
+        assert_matches!(mapped, Event::MouseButtonDown { x: 100, y: 239, .. });
+
+        let presentation = Presentation {
+            aspect_correction: true,
+            ..Presentation::default()
+        };
+        let mapped = presentation.map_mouse_event(event.clone(), 320, 200);
+        assert_matches!(mapped, Event::MouseButtonDown { x: 100, y: 199, .. });
+        let mapped = presentation.map_mouse_event(event, 320, 240);
+        assert_matches!(mapped, Event::MouseButtonDown { x: 100, y: 239, .. });
+
+        let event = Event::Quit { timestamp: 0 };
+        let mapped = presentation.map_mouse_event(event, 320, 200);
+        assert_matches!(mapped, Event::Quit { .. });
+    }
+}
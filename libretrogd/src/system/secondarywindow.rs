@@ -0,0 +1,193 @@
+use std::fmt::Formatter;
+
+use byte_slice_cast::AsByteSlice;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::render::{Texture, WindowCanvas};
+use sdl2::VideoSubsystem;
+
+use crate::system::*;
+
+/// An additional window, separate from the main [`System`] window, with its own backbuffer,
+/// palette and input devices. Useful for things like live tile, palette or entity inspectors
+/// shown alongside the game while it runs. Created via [`System::create_window`].
+///
+/// Events belonging to a secondary window are not passed to the main [`System`] input devices.
+/// Instead, pass events to [`SecondaryWindow::handle_event`] from [`System::do_events_with`]:
+///
+/// ```no_run
+/// use sdl2::keyboard::Scancode;
+///
+/// use libretrogd::system::*;
+///
+/// let mut system = SystemBuilder::new().build().unwrap();
+/// let mut inspector = system.create_window("Inspector", 256, 256, 2).unwrap();
+/// while !system.keyboard.is_key_pressed(Scancode::Escape) {
+///     inspector.update();
+///     system.do_events_with(|event| {
+///         inspector.handle_event(event);
+///     });
+///     // ... render to system.video and inspector.video ...
+///     system.display().unwrap();
+///     inspector.display().unwrap();
+/// }
+/// ```
+pub struct SecondaryWindow {
+    sdl_canvas: WindowCanvas,
+    sdl_texture: Texture,
+    sdl_texture_pitch: usize,
+    texture_pixels: Box<[u32]>,
+    window_id: u32,
+    close_requested: bool,
+
+    /// The backbuffer [`Bitmap`] that will be rendered to this window whenever
+    /// [`SecondaryWindow::display`] is called.
+    pub video: Bitmap,
+
+    /// The [`Palette`] that will be used in conjunction with the `video` backbuffer to render the
+    /// final output to this window.
+    pub palette: Palette,
+
+    /// The keyboard state, only reflecting key presses made while this window has focus.
+    pub keyboard: Keyboard,
+
+    /// The mouse state, only reflecting mouse movement and clicks within this window.
+    pub mouse: Mouse,
+}
+
+impl std::fmt::Debug for SecondaryWindow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecondaryWindow")
+            .field("window_id", &self.window_id)
+            .field("close_requested", &self.close_requested)
+            .field("video", &self.video)
+            .field("palette", &self.palette)
+            .field("keyboard", &self.keyboard)
+            .field("mouse", &self.mouse)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecondaryWindow {
+    pub(crate) fn new(
+        video_subsystem: &VideoSubsystem,
+        title: &str,
+        width: u32,
+        height: u32,
+        scale_factor: u32,
+    ) -> Result<SecondaryWindow, SystemError> {
+        let scale_factor = scale_factor.max(1);
+        let sdl_window = match video_subsystem
+            .window(title, width * scale_factor, height * scale_factor)
+            .resizable()
+            .build()
+        {
+            Ok(window) => window,
+            Err(error) => return Err(SystemError::InitError(error.to_string())),
+        };
+        let window_id = sdl_window.id();
+
+        let mut sdl_canvas = match sdl_window.into_canvas().build() {
+            Ok(canvas) => canvas,
+            Err(error) => return Err(SystemError::InitError(error.to_string())),
+        };
+        if let Err(error) =
+            apply_presentation(&mut sdl_canvas, &Presentation::default(), width, height)
+        {
+            return Err(SystemError::InitError(error));
+        }
+
+        let video = match Bitmap::new(width, height) {
+            Ok(bmp) => bmp,
+            Err(error) => return Err(SystemError::InitError(error.to_string())),
+        };
+        let (sdl_texture, sdl_texture_pitch, texture_pixels) =
+            match create_display_texture(&sdl_canvas, width, height) {
+                Ok(result) => result,
+                Err(error) => return Err(SystemError::InitError(error)),
+            };
+
+        let palette = match Palette::new_vga_palette() {
+            Ok(palette) => palette,
+            Err(error) => return Err(SystemError::InitError(error.to_string())),
+        };
+
+        Ok(SecondaryWindow {
+            sdl_canvas,
+            sdl_texture,
+            sdl_texture_pitch,
+            texture_pixels,
+            window_id,
+            close_requested: false,
+            video,
+            palette,
+            keyboard: Keyboard::new(),
+            mouse: Mouse::new(),
+        })
+    }
+
+    /// Returns the SDL window ID of this window, as found in the events belonging to it.
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.window_id
+    }
+
+    /// Returns true if the user has tried to close this window since the last call to
+    /// [`SecondaryWindow::update`]. Secondary windows are not closed automatically, the window is
+    /// closed when this [`SecondaryWindow`] is dropped.
+    #[inline]
+    pub fn is_close_requested(&self) -> bool {
+        self.close_requested
+    }
+
+    /// Performs per-frame house-keeping for this window's input devices. This should be called
+    /// once each frame, before events are processed.
+    pub fn update(&mut self) {
+        self.close_requested = false;
+        self.keyboard.update();
+        self.mouse.update();
+    }
+
+    /// Processes the given event if it belongs to this window, updating this window's input
+    /// devices. Returns true if the event belonged to this window.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if event.get_window_id() != Some(self.window_id) {
+            return false;
+        }
+        if let Event::Window {
+            win_event: WindowEvent::Close,
+            ..
+        } = event
+        {
+            self.close_requested = true;
+        }
+        self.keyboard.handle_event(event);
+        self.mouse.handle_event(event);
+        true
+    }
+
+    /// Takes the `video` backbuffer bitmap and `palette` and renders it to this window, up-scaled
+    /// to fill the window.
+    pub fn display(&mut self) -> Result<(), SystemError> {
+        self.mouse.render_cursor(&mut self.video);
+
+        self.video
+            .copy_as_argb_to(&mut self.texture_pixels, &self.palette);
+
+        let texture_pixels = self.texture_pixels.as_byte_slice();
+        if let Err(error) = self
+            .sdl_texture
+            .update(None, texture_pixels, self.sdl_texture_pitch)
+        {
+            return Err(SystemError::DisplayError(error.to_string()));
+        }
+        self.sdl_canvas.clear();
+        if let Err(error) = self.sdl_canvas.copy(&self.sdl_texture, None, None) {
+            return Err(SystemError::DisplayError(error));
+        }
+        self.sdl_canvas.present();
+
+        self.mouse.hide_cursor(&mut self.video);
+
+        Ok(())
+    }
+}
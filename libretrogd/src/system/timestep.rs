@@ -0,0 +1,204 @@
+/// The default maximum number of updates run in a single frame by [FixedTimestep].
+pub const DEFAULT_MAX_UPDATES_PER_FRAME: u32 = 8;
+
+/// Helper for main loops that run game logic at a fixed rate, independent of how fast frames are
+/// being rendered. Updating with a fixed delta keeps simulations deterministic (e.g. physics
+/// behaves the same regardless of framerate), while rendering with an interpolation factor (the
+/// "alpha") between the previous and current update keeps motion smooth.
+///
+/// If frames take so long that more than a maximum number of updates would be needed to catch
+/// up, the extra time is dropped so the game slows down instead of falling further and further
+/// behind (the "spiral of death").
+///
+/// ```no_run
+/// use sdl2::keyboard::Scancode;
+///
+/// use libretrogd::system::*;
+///
+/// let mut system = SystemBuilder::new().build().unwrap();
+/// let mut timestep = FixedTimestep::new(60);
+/// while !system.keyboard.is_key_pressed(Scancode::Escape) {
+///     system.do_events();
+///     let elapsed = timestep.elapsed_ticks(system.ticks(), system.tick_frequency());
+///     timestep.frame(
+///         elapsed,
+///         |delta| { /* update game state by `delta` seconds */ },
+///         |alpha| { /* render, interpolating between the last two states by `alpha` */ },
+///     );
+///     system.display().unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    update_delta: f32,
+    accumulator: f32,
+    max_updates_per_frame: u32,
+    last_ticks: Option<u64>,
+}
+
+impl FixedTimestep {
+    /// Creates a new fixed timestep helper.
+    ///
+    /// # Arguments
+    ///
+    /// * `updates_per_second`: how many times per second game logic should be updated. must be
+    ///   greater than zero
+    pub fn new(updates_per_second: u32) -> FixedTimestep {
+        FixedTimestep {
+            update_delta: 1.0 / updates_per_second.max(1) as f32,
+            accumulator: 0.0,
+            max_updates_per_frame: DEFAULT_MAX_UPDATES_PER_FRAME,
+            last_ticks: None,
+        }
+    }
+
+    /// Returns the fixed amount of time (in seconds) that each update represents.
+    #[inline]
+    pub fn update_delta(&self) -> f32 {
+        self.update_delta
+    }
+
+    #[inline]
+    pub fn max_updates_per_frame(&self) -> u32 {
+        self.max_updates_per_frame
+    }
+
+    /// Sets the maximum number of updates that will be run in a single frame, after which any
+    /// remaining elapsed time is dropped. Must be at least 1.
+    pub fn set_max_updates_per_frame(&mut self, max_updates: u32) {
+        self.max_updates_per_frame = max_updates.max(1);
+    }
+
+    /// Returns how far (from 0.0 to 1.0) the current time is between the most recent update and
+    /// the next one. Rendering should interpolate between the previous and current game state by
+    /// this amount.
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.update_delta).clamp(0.0, 1.0)
+    }
+
+    /// Calculates the time elapsed (in seconds) since the last call to this method, from a
+    /// high-resolution timer such as [crate::system::System::ticks]. The first call always
+    /// returns zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `ticks`: the current timer value
+    /// * `tick_frequency`: the number of timer ticks per second
+    pub fn elapsed_ticks(&mut self, ticks: u64, tick_frequency: u64) -> f32 {
+        let elapsed = match self.last_ticks {
+            Some(last_ticks) => ticks.saturating_sub(last_ticks) as f64 / tick_frequency as f64,
+            None => 0.0,
+        };
+        self.last_ticks = Some(ticks);
+        elapsed as f32
+    }
+
+    /// Adds the elapsed time to this helper, returning how many updates should be run now. Each
+    /// update should advance the game state by [FixedTimestep::update_delta].
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed`: the time elapsed since the last frame, in seconds
+    pub fn advance(&mut self, elapsed: f32) -> u32 {
+        self.accumulator += elapsed.max(0.0);
+        let mut updates = (self.accumulator / self.update_delta) as u32;
+        if updates > self.max_updates_per_frame {
+            // too far behind to ever catch up. run as many updates as allowed and drop the rest
+            updates = self.max_updates_per_frame;
+            self.accumulator %= self.update_delta;
+        } else {
+            self.accumulator -= updates as f32 * self.update_delta;
+        }
+        updates
+    }
+
+    /// Runs a single frame: calls `update` as many times as needed for the elapsed time, and then
+    /// calls `render` once.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed`: the time elapsed since the last frame, in seconds
+    /// * `update`: function called for each update, given [FixedTimestep::update_delta]
+    /// * `render`: function called once after all updates, given [FixedTimestep::alpha]
+    ///
+    /// returns: the number of updates that were run
+    pub fn frame(
+        &mut self,
+        elapsed: f32,
+        mut update: impl FnMut(f32),
+        render: impl FnOnce(f32),
+    ) -> u32 {
+        let updates = self.advance(elapsed);
+        for _ in 0..updates {
+            update(self.update_delta);
+        }
+        render(self.alpha());
+        updates
+    }
+
+    /// Discards any accumulated time, for example after loading or un-pausing, so that the next
+    /// frame does not try to catch up on time that passed in the meantime.
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.last_ticks = None;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::math::*;
+
+    use super::*;
+
+    #[test]
+    pub fn runs_fixed_updates() {
+        let mut timestep = FixedTimestep::new(10);
+        assert!(nearly_equal(0.1, timestep.update_delta(), 0.0001));
+
+        assert_eq!(0, timestep.advance(0.05));
+        assert!(nearly_equal(0.5, timestep.alpha(), 0.0001));
+        assert_eq!(1, timestep.advance(0.075));
+        assert!(nearly_equal(0.25, timestep.alpha(), 0.0001));
+        assert_eq!(3, timestep.advance(0.3));
+        assert!(nearly_equal(0.25, timestep.alpha(), 0.0001));
+
+        let mut deltas = Vec::new();
+        let mut rendered_alpha = None;
+        let updates = timestep.frame(
+            0.2,
+            |delta| deltas.push(delta),
+            |alpha| rendered_alpha = Some(alpha),
+        );
+        assert_eq!(2, updates);
+        assert_eq!(vec![0.1, 0.1], deltas);
+        assert!(nearly_equal(0.25, rendered_alpha.unwrap(), 0.0001));
+    }
+
+    #[test]
+    pub fn limits_updates_per_frame() {
+        let mut timestep = FixedTimestep::new(10);
+        timestep.set_max_updates_per_frame(4);
+        assert_eq!(4, timestep.advance(10.05));
+        assert!(nearly_equal(0.5, timestep.alpha(), 0.001));
+        // the dropped time is not caught up on later
+        assert_eq!(0, timestep.advance(0.0));
+
+        timestep.set_max_updates_per_frame(0);
+        assert_eq!(1, timestep.max_updates_per_frame());
+
+        timestep.reset();
+        assert_eq!(0.0, timestep.alpha());
+    }
+
+    #[test]
+    pub fn elapsed_from_ticks() {
+        let mut timestep = FixedTimestep::new(60);
+        assert_eq!(0.0, timestep.elapsed_ticks(1000, 1000));
+        let elapsed = timestep.elapsed_ticks(1250, 1000);
+        assert!(nearly_equal(0.25, elapsed, 0.0001));
+        assert_eq!(0.0, timestep.elapsed_ticks(1250, 1000));
+        timestep.reset();
+        assert_eq!(0.0, timestep.elapsed_ticks(5000, 1000));
+    }
+}
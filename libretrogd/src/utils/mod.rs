@@ -7,6 +7,9 @@ use rand::Rng;
 pub mod bytes;
 pub mod io;
 pub mod packbits;
+pub mod prng;
+#[cfg(feature = "serde")]
+pub mod savefile;
 
 pub fn rnd_value<N: SampleUniform + PartialOrd>(low: N, high: N) -> N {
     rand::thread_rng().gen_range(low..=high)
@@ -0,0 +1,252 @@
+use std::ops::RangeInclusive;
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+/// A small, fast, seedable pseudo-random number generator (PCG32, "XSH RR" variant). The same
+/// seed always produces the same sequence of values on every platform, which makes it suitable
+/// for deterministic replays and procedural generation. It is NOT suitable for cryptographic use.
+///
+/// For one-off random values where determinism doesn't matter, [crate::utils::rnd_value] is
+/// simpler to use.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Prng {
+    state: u64,
+    increment: u64,
+}
+
+impl Prng {
+    /// Creates a new generator with the given seed.
+    pub fn new(seed: u64) -> Prng {
+        Prng::new_with_stream(seed, DEFAULT_STREAM)
+    }
+
+    /// Creates a new generator with the given seed and stream. Generators with the same seed but
+    /// different streams produce different, independent sequences of values.
+    pub fn new_with_stream(seed: u64, stream: u64) -> Prng {
+        let mut prng = Prng {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        prng.next_u32();
+        prng.state = prng.state.wrapping_add(seed);
+        prng.next_u32();
+        prng
+    }
+
+    /// Creates a new generator with a random seed. Useful when determinism isn't needed, but the
+    /// generator can still be cloned to replay the values it produces from this point on.
+    pub fn from_entropy() -> Prng {
+        Prng::new(rand::random())
+    }
+
+    /// Resets this generator as if it were newly created with the given seed.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Prng::new(seed);
+    }
+
+    /// Returns the next random 32-bit value.
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// Returns the next random 64-bit value.
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// Returns a random value in the range 0.0 (inclusive) to 1.0 (exclusive).
+    #[inline]
+    pub fn next_f32(&mut self) -> f32 {
+        // use only as many bits as fit in an f32's mantissa so that all values are equally likely
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Returns a random value less than `bound`, without any bias towards lower values.
+    fn next_bounded_u64(&mut self, bound: u64) -> u64 {
+        // values below this threshold would make some results slightly more likely than others
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let value = self.next_u64();
+            if value >= threshold {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Returns a random value within the given range (inclusive of both ends).
+    ///
+    /// # Arguments
+    ///
+    /// * `range`: the range of values to pick from, e.g. `1..=6`
+    #[inline]
+    pub fn rand_range<N: PrngRange>(&mut self, range: RangeInclusive<N>) -> N {
+        N::rand_range(self, *range.start(), *range.end())
+    }
+
+    /// Returns true with the given probability.
+    ///
+    /// # Arguments
+    ///
+    /// * `probability`: the chance of returning true, from 0.0 (never) to 1.0 (always)
+    #[inline]
+    pub fn rand_chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// Randomly re-orders the items in the given slice.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.next_bounded_u64(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Returns a randomly chosen item from the given slice, or `None` if the slice is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.next_bounded_u64(items.len() as u64) as usize)
+        }
+    }
+}
+
+/// Types that [Prng::rand_range] can return.
+pub trait PrngRange: Copy {
+    /// Returns a random value between `low` and `high` (inclusive of both). If `low` is greater
+    /// than `high`, the two are swapped.
+    fn rand_range(prng: &mut Prng, low: Self, high: Self) -> Self;
+}
+
+macro_rules! impl_prng_range_for_integer {
+    ($($t:ty),*) => {
+        $(
+            impl PrngRange for $t {
+                fn rand_range(prng: &mut Prng, low: Self, high: Self) -> Self {
+                    let (low, high) = if low <= high { (low, high) } else { (high, low) };
+                    // the span is calculated with wrapping math so that it also works for signed
+                    // types, where a range like -100..=100 spans more values than fit in i8
+                    let span = (high as u64).wrapping_sub(low as u64).wrapping_add(1);
+                    let span = span & (u64::MAX >> (64 - <$t>::BITS));
+                    if span == 0 {
+                        // the range covers every possible value of the type
+                        prng.next_u64() as $t
+                    } else {
+                        (low as u64).wrapping_add(prng.next_bounded_u64(span)) as $t
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_prng_range_for_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl PrngRange for f32 {
+    fn rand_range(prng: &mut Prng, low: Self, high: Self) -> Self {
+        let (low, high) = if low <= high {
+            (low, high)
+        } else {
+            (high, low)
+        };
+        (low + (high - low) * prng.next_f32()).min(high)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn deterministic_sequence() {
+        // reference values from the PCG32 demo program (seed 42, stream 54)
+        let mut prng = Prng::new_with_stream(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+        for value in expected {
+            assert_eq!(value, prng.next_u32());
+        }
+
+        let mut a = Prng::new(1234);
+        let mut b = Prng::new(1234);
+        let mut c = Prng::new(4321);
+        let a_values: Vec<u64> = (0..16).map(|_| a.next_u64()).collect();
+        let b_values: Vec<u64> = (0..16).map(|_| b.next_u64()).collect();
+        let c_values: Vec<u64> = (0..16).map(|_| c.next_u64()).collect();
+        assert_eq!(a_values, b_values);
+        assert_ne!(a_values, c_values);
+
+        a.reseed(1234);
+        assert_eq!(a_values[0], a.next_u64());
+    }
+
+    #[test]
+    pub fn ranges() {
+        let mut prng = Prng::new(42);
+        let mut seen = [false; 6];
+        for _ in 0..1000 {
+            let value = prng.rand_range(1..=6);
+            assert!((1..=6).contains(&value));
+            seen[value as usize - 1] = true;
+
+            let value = prng.rand_range(-100i8..=100);
+            assert!((-100..=100).contains(&value));
+
+            let value = prng.rand_range(2.5f32..=3.0);
+            assert!((2.5..=3.0).contains(&value));
+
+            let value = prng.next_f32();
+            assert!((0.0..1.0).contains(&value));
+        }
+        assert!(seen.iter().all(|&seen| seen));
+
+        assert_eq!(7, prng.rand_range(7u32..=7));
+        let value = i32::rand_range(&mut prng, 10, 5);
+        assert!((5..=10).contains(&value));
+        // full range of the type
+        prng.rand_range(u8::MIN..=u8::MAX);
+        prng.rand_range(i64::MIN..=i64::MAX);
+    }
+
+    #[test]
+    pub fn chance() {
+        let mut prng = Prng::new(42);
+        assert!((0..100).all(|_| !prng.rand_chance(0.0)));
+        assert!((0..100).all(|_| prng.rand_chance(1.0)));
+        let hits = (0..10000).filter(|_| prng.rand_chance(0.25)).count();
+        assert!((2000..3000).contains(&hits));
+    }
+
+    #[test]
+    pub fn shuffle_and_choose() {
+        let mut prng = Prng::new(42);
+        let mut items: Vec<u32> = (0..32).collect();
+        prng.shuffle(&mut items);
+        assert_ne!((0..32).collect::<Vec<u32>>(), items);
+        items.sort();
+        assert_eq!((0..32).collect::<Vec<u32>>(), items);
+
+        // shuffling is deterministic too
+        let mut a: Vec<u32> = (0..32).collect();
+        let mut b = a.clone();
+        Prng::new(7).shuffle(&mut a);
+        Prng::new(7).shuffle(&mut b);
+        assert_eq!(a, b);
+
+        let empty: [u32; 0] = [];
+        assert_eq!(None, prng.choose(&empty));
+        assert_eq!(Some(&5), prng.choose(&[5]));
+        let choice = prng.choose(&items).unwrap();
+        assert!(items.contains(choice));
+    }
+}
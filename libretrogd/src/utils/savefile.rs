@@ -0,0 +1,310 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+const SAVE_FILE_MAGIC: [u8; 4] = *b"RGSV";
+const SAVE_FILE_FORMAT_VERSION: u8 = 1;
+
+#[derive(Error, Debug)]
+pub enum SaveFileError {
+    #[error("Bad or unsupported save file data: {0}")]
+    BadData(String),
+
+    #[error("Unsupported save file format version: {0}")]
+    UnsupportedFormatVersion(u8),
+
+    #[error("Save file checksum mismatch, the save data is corrupt")]
+    ChecksumMismatch,
+
+    #[error("Save data could not be serialized or deserialized: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Save file I/O error")]
+    IOError(#[from] std::io::Error),
+}
+
+/// A versioned container for a game's save data (or replays, settings, etc), which can be any
+/// type serializable with serde. Along with the data itself, a game-defined version number is
+/// stored so that games can tell which version of their save data format a save file was
+/// written with (via [`SaveFile::version`]) before deciding how to load it. Older save data can
+/// also be loaded as a [`serde_json::Value`] first, if it needs to be migrated by hand.
+///
+/// Save files are written with a small header and a checksum, with the save data itself stored
+/// compressed.
+///
+/// ```
+/// use libretrogd::utils::savefile::*;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct SaveData {
+///     level: u32,
+///     score: u64,
+/// }
+///
+/// let save = SaveFile::new(1, &SaveData { level: 3, score: 12000 }).unwrap();
+/// let mut bytes = Vec::new();
+/// save.to_bytes(&mut bytes).unwrap();
+///
+/// let loaded = SaveFile::load_from_bytes(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(1, loaded.version());
+/// assert_eq!(SaveData { level: 3, score: 12000 }, loaded.data().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveFile {
+    version: u32,
+    payload: Vec<u8>,
+}
+
+impl SaveFile {
+    /// Creates and returns a new save file containing the given data.
+    ///
+    /// # Arguments
+    ///
+    /// * `version`: the game-defined version of the save data format being used for `data`
+    /// * `data`: the save data
+    pub fn new<T: Serialize>(version: u32, data: &T) -> Result<SaveFile, SaveFileError> {
+        Ok(SaveFile {
+            version,
+            payload: serde_json::to_vec(data)?,
+        })
+    }
+
+    /// Returns the game-defined version of the save data format the save data was written with.
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Deserializes and returns the save data.
+    pub fn data<T: DeserializeOwned>(&self) -> Result<T, SaveFileError> {
+        Ok(serde_json::from_slice(&self.payload)?)
+    }
+
+    /// Loads and returns a save file from a file on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the save file to be loaded
+    pub fn load_from_file(path: &Path) -> Result<SaveFile, SaveFileError> {
+        let f = File::open(path)?;
+        let mut reader = BufReader::new(f);
+        Self::load_from_bytes(&mut reader)
+    }
+
+    /// Loads and returns a save file from a reader. The data being loaded is expected to be the
+    /// same as if the save file was being loaded from a file on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: the reader to load the save file from
+    pub fn load_from_bytes<T: Read>(reader: &mut T) -> Result<SaveFile, SaveFileError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != SAVE_FILE_MAGIC {
+            return Err(SaveFileError::BadData(String::from(
+                "Unexpected magic bytes, not a save file",
+            )));
+        }
+        let format_version = reader.read_u8()?;
+        if format_version != SAVE_FILE_FORMAT_VERSION {
+            return Err(SaveFileError::UnsupportedFormatVersion(format_version));
+        }
+        let version = reader.read_u32::<LittleEndian>()?;
+        let length = reader.read_u32::<LittleEndian>()? as usize;
+        let checksum = reader.read_u32::<LittleEndian>()?;
+
+        let mut payload = Vec::with_capacity(length);
+        ZlibDecoder::new(reader)
+            .take(length as u64)
+            .read_to_end(&mut payload)?;
+        if payload.len() != length {
+            return Err(SaveFileError::BadData(String::from(
+                "Save data is truncated",
+            )));
+        }
+        if crc32fast::hash(&payload) != checksum {
+            return Err(SaveFileError::ChecksumMismatch);
+        }
+
+        Ok(SaveFile { version, payload })
+    }
+
+    /// Writes the save file to a file on disk. If the file already exists, it will be
+    /// overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: the path of the file to write the save file to
+    pub fn to_file(&self, path: &Path) -> Result<(), SaveFileError> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_bytes(&mut writer)
+    }
+
+    /// Writes the save file to a writer, in the same format as if it was writing to a file on
+    /// disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: the writer to write the save file to
+    pub fn to_bytes<T: Write>(&self, writer: &mut T) -> Result<(), SaveFileError> {
+        writer.write_all(&SAVE_FILE_MAGIC)?;
+        writer.write_u8(SAVE_FILE_FORMAT_VERSION)?;
+        writer.write_u32::<LittleEndian>(self.version)?;
+        writer.write_u32::<LittleEndian>(self.payload.len() as u32)?;
+        writer.write_u32::<LittleEndian>(crc32fast::hash(&self.payload))?;
+
+        let mut encoder = ZlibEncoder::new(writer, Compression::default());
+        encoder.write_all(&self.payload)?;
+        encoder.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use claim::*;
+    use serde::Deserialize;
+    use tempfile::TempDir;
+
+    use crate::entities::*;
+    use crate::graphics::*;
+    use crate::math::*;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position(i32, i32);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct SaveData {
+        name: String,
+        scores: HashMap<String, u32>,
+        area: Rect,
+        palette: Palette,
+        screenshot: Bitmap,
+        blend_map: BlendMap,
+        entities: SavedEntities,
+    }
+
+    #[test]
+    pub fn save_file_round_trip() -> Result<(), SaveFileError> {
+        let tmp_dir = TempDir::new()?;
+
+        let mut palette = Palette::new_vga_palette().unwrap();
+        palette[1] = 0xff123456;
+        let mut screenshot = Bitmap::new(8, 4).unwrap();
+        screenshot.set_pixel(1, 2, 7);
+        screenshot.set_palette(Some(palette.clone()));
+        let mut blend_map = BlendMap::new_colorized_map(16, 31, &palette);
+        blend_map.set_fallback(BlendMapFallback::BlendMap(Arc::new(BlendMap::new(0, 0))));
+
+        let mut types = SaveComponentTypes::new();
+        types.register::<Position>("Position");
+        let mut entities = Entities::new();
+        let entity = entities.new_entity();
+        entities.add_component(entity, Position(3, -4));
+
+        let data = SaveData {
+            name: String::from("test"),
+            scores: HashMap::from([(String::from("level1"), 500)]),
+            area: Rect::new(1, 2, 30, 40),
+            palette,
+            screenshot,
+            blend_map,
+            entities: types.save(&entities).unwrap(),
+        };
+
+        let path = tmp_dir.path().join("test_save.sav");
+        SaveFile::new(3, &data)?.to_file(&path)?;
+        let loaded = SaveFile::load_from_file(&path)?;
+        assert_eq!(3, loaded.version());
+        let loaded_data: SaveData = loaded.data()?;
+        assert_eq!(data, loaded_data);
+        assert_eq!(7, loaded_data.screenshot.get_pixel(1, 2).unwrap());
+
+        let entities = types.load(&loaded_data.entities).unwrap();
+        assert_eq!(
+            Some(&Position(3, -4)),
+            entities.components::<Position>().unwrap().get(&entity)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn bad_save_files() {
+        let mut bytes = Vec::new();
+        assert_ok!(assert_ok!(SaveFile::new(1, &vec![1, 2, 3])).to_bytes(&mut bytes));
+
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert_matches!(
+            SaveFile::load_from_bytes(&mut corrupt.as_slice()),
+            Err(SaveFileError::BadData(..))
+        );
+
+        let mut corrupt = bytes.clone();
+        corrupt[4] = 99;
+        assert_matches!(
+            SaveFile::load_from_bytes(&mut corrupt.as_slice()),
+            Err(SaveFileError::UnsupportedFormatVersion(99))
+        );
+
+        let mut corrupt = bytes.clone();
+        corrupt[13] ^= 0xff;
+        assert_matches!(
+            SaveFile::load_from_bytes(&mut corrupt.as_slice()),
+            Err(SaveFileError::ChecksumMismatch)
+        );
+
+        let truncated = &bytes[0..20];
+        assert_err!(SaveFile::load_from_bytes(&mut &truncated[..]));
+
+        let save = assert_ok!(SaveFile::load_from_bytes(&mut bytes.as_slice()));
+        assert_matches!(
+            save.data::<String>(),
+            Err(SaveFileError::SerializationError(..))
+        );
+    }
+
+    #[test]
+    pub fn bitmap_rows() -> Result<(), serde_json::Error> {
+        // only the pixels within each row are saved, never the padding between rows
+        let mut pixels = [9u8; 12];
+        let mut bitmap = Bitmap::from_slice_mut_with_pitch(3, 2, 4, &mut pixels).unwrap();
+        bitmap.set_pixel(2, 1, 5);
+        let json = serde_json::to_string(&bitmap)?;
+        assert_eq!(
+            r#"{"width":3,"height":2,"pixels":[[9,9,9],[9,9,5]],"palette":null}"#,
+            json
+        );
+        let loaded: Bitmap = serde_json::from_str(&json)?;
+        assert_eq!(Some(5), loaded.get_pixel(2, 1));
+
+        // the pixels are checked against the dimensions before creating the bitmap
+        assert_err!(serde_json::from_str::<Bitmap>(
+            r#"{"width":3,"height":2,"pixels":[[9,9,9]],"palette":null}"#
+        ));
+        assert_err!(serde_json::from_str::<Bitmap>(
+            r#"{"width":3,"height":2,"pixels":[[9,9,9],[9,9]],"palette":null}"#
+        ));
+        assert_err!(serde_json::from_str::<Bitmap>(
+            r#"{"width":4294967295,"height":4294967295,"pixels":[[1]],"palette":null}"#
+        ));
+
+        Ok(())
+    }
+}